@@ -3,7 +3,7 @@ use tokio::runtime::Runtime;
 use std::time::Duration;
 use zk_sac_engine::{
     consensus::{ConsensusEngine, BeamChainConfig},
-    types::{Transaction, Address, Block},
+    types::{Transaction, Address, Block, DEFAULT_CHAIN_ID},
     async_utils::{AsyncTaskPool, BatchProcessor, ConsensusCoordinator},
 };
 
@@ -308,6 +308,8 @@ fn setup_large_block(data_size: usize) -> (ConsensusEngine, Block) {
             value: 1000,
             data: vec![0u8; data_size],
             nonce: 0,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             gas_limit: 1000000,
             gas_price: 20,
             signature: vec![0; 64],
@@ -326,6 +328,8 @@ fn generate_test_transactions(count: usize) -> Vec<Transaction> {
             value: 1000 + i as u64,
             data: vec![],
             nonce: i as u64,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             gas_limit: 21000,
             gas_price: 20,
             signature: vec![0; 64],
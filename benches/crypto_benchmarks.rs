@@ -5,7 +5,7 @@ use zk_sac_engine::{
         hash::MultiHasher,
         signatures::QuantumResistantSigner,
     },
-    types::{Transaction, Address, BlockHash},
+    types::{Transaction, Address, BlockHash, DEFAULT_CHAIN_ID},
 };
 use ed25519_dalek::{SigningKey, VerifyingKey, Signature};
 use rand::rngs::OsRng;
@@ -174,6 +174,8 @@ fn bench_evm_compatibility(c: &mut Criterion) {
             value: 1000,
             data: vec![0u8; *tx_data_size],
             nonce: 0,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             gas_limit: 21000,
             gas_price: 20,
             signature: vec![0; 65],
@@ -3,7 +3,7 @@ use tokio::runtime::Runtime;
 use std::time::Duration;
 use zk_sac_engine::{
     zkvm::{Risc0Executor, ZKVMConfig},
-    types::{Transaction, Address, Block},
+    types::{Transaction, Address, Block, ProverMode, DEFAULT_CHAIN_ID},
     crypto::hash::MultiHasher,
 };
 use sp1_sdk::{ProverClient, SP1Stdin, SP1PublicValues};
@@ -386,7 +386,7 @@ fn setup_parallel_proof_inputs(parallel_count: usize) -> (Risc0Executor, Vec<(Ve
 fn setup_memory_optimized_zkvm(mode: &str) -> (Risc0Executor, Vec<u8>) {
     let config = ZKVMConfig {
         memory_optimization: mode.to_string(),
-        prover_mode: "cpu".to_string(),
+        prover_mode: ProverMode::Cpu,
         parallel_execution: true,
     };
     let executor = Risc0Executor::with_config(config).expect("Failed to create Risc0 executor");
@@ -431,6 +431,8 @@ fn generate_test_transactions(count: usize) -> Vec<Transaction> {
             value: 1000 + i as u64,
             data: vec![],
             nonce: i as u64,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             gas_limit: 21000,
             gas_price: 20,
             signature: vec![0; 64],
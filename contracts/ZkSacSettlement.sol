@@ -0,0 +1,88 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+/// Verifier interface of the RISC Zero Groth16 verifier contract
+interface IRiscZeroVerifier {
+    function verify(bytes calldata seal, bytes32 imageId, bytes32 journalDigest) external view;
+}
+
+/// Settlement contract for ZK-SAC checkpoints, deposits and withdrawals.
+/// Encodings must match `src/bridge` in the engine.
+contract ZkSacSettlement {
+    struct Checkpoint {
+        bytes32 blockHash;
+        bytes32 stateRoot;
+        bytes32 withdrawalsRoot;
+    }
+
+    IRiscZeroVerifier public immutable verifier;
+    bytes32 public immutable imageId;
+    address public immutable submitter;
+
+    uint64 public latestBlock;
+    uint64 public nextDepositNonce;
+    mapping(uint64 => Checkpoint) public checkpoints;
+    mapping(uint64 => bool) public withdrawalClaimed;
+
+    event CheckpointSubmitted(uint64 indexed blockNumber, bytes32 blockHash, bytes32 stateRoot, bytes32 withdrawalsRoot);
+    event Deposited(uint64 indexed nonce, address indexed sender, bytes20 recipient, uint256 amount);
+    event WithdrawalClaimed(uint64 indexed nonce, address indexed recipient, uint256 amount);
+
+    constructor(IRiscZeroVerifier _verifier, bytes32 _imageId, address _submitter) {
+        verifier = _verifier;
+        imageId = _imageId;
+        submitter = _submitter;
+    }
+
+    function submitCheckpoint(
+        uint64 blockNumber,
+        bytes32 blockHash,
+        bytes32 stateRoot,
+        bytes32 withdrawalsRoot,
+        bytes calldata seal
+    ) external {
+        require(msg.sender == submitter, "not submitter");
+        require(blockNumber > latestBlock, "stale checkpoint");
+
+        bytes memory journal = abi.encodePacked(blockNumber, blockHash, stateRoot, withdrawalsRoot);
+        verifier.verify(seal, imageId, sha256(journal));
+
+        checkpoints[blockNumber] = Checkpoint(blockHash, stateRoot, withdrawalsRoot);
+        latestBlock = blockNumber;
+        emit CheckpointSubmitted(blockNumber, blockHash, stateRoot, withdrawalsRoot);
+    }
+
+    /// Lock ETH to be credited to `recipient` on ZK-SAC
+    function deposit(bytes20 recipient) external payable {
+        require(msg.value > 0 && msg.value <= type(uint64).max, "invalid amount");
+        emit Deposited(nextDepositNonce++, msg.sender, recipient, msg.value);
+    }
+
+    /// Release a withdrawal included in a checkpoint's withdrawals root
+    function claimWithdrawal(
+        uint64 checkpointBlock,
+        uint64 nonce,
+        bytes20 sender,
+        address payable recipient,
+        uint64 amount,
+        bytes32[] calldata proof
+    ) external {
+        require(!withdrawalClaimed[nonce], "already claimed");
+        bytes32 root = checkpoints[checkpointBlock].withdrawalsRoot;
+        require(root != bytes32(0), "unknown checkpoint");
+
+        bytes32 node = keccak256(abi.encodePacked(nonce, sender, recipient, amount));
+        for (uint256 i = 0; i < proof.length; i++) {
+            bytes32 sibling = proof[i];
+            node = node <= sibling
+                ? keccak256(abi.encodePacked(node, sibling))
+                : keccak256(abi.encodePacked(sibling, node));
+        }
+        require(node == root, "invalid proof");
+
+        withdrawalClaimed[nonce] = true;
+        emit WithdrawalClaimed(nonce, recipient, amount);
+        (bool sent, ) = recipient.call{value: amount}("");
+        require(sent, "transfer failed");
+    }
+}
@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zk_sac_engine::serialization::{
+    decode_batch, decode_blockchain_data, decode_hybrid, decode_state_data, decode_zkvm_output,
+};
+use zk_sac_engine::types::{Block, Transaction, ValidatorSet, WorldState, ZkProof};
+
+fuzz_target!(|data: &[u8]| {
+    let _ = decode_blockchain_data::<Block>(data);
+    let _ = decode_state_data::<WorldState>(data);
+    let _ = decode_zkvm_output::<ZkProof>(data);
+    let _ = decode_hybrid::<Transaction>(data);
+    let _ = decode_hybrid::<ValidatorSet>(data);
+
+    let items: Vec<Vec<u8>> = data.split(|&byte| byte == 0xff).map(<[u8]>::to_vec).collect();
+    let _ = decode_batch::<Transaction>(&items);
+});
@@ -0,0 +1,16 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zk_sac_engine::serialization::{canonical_bytes, decode_block_ref, decode_transaction_ref, DecodeLimits};
+
+fuzz_target!(|data: &[u8]| {
+    let limits = DecodeLimits::default();
+
+    // Anything that decodes must be the one canonical encoding of its value
+    if let Ok(tx) = decode_transaction_ref(data, &limits) {
+        assert_eq!(canonical_bytes(&tx.to_transaction()), data);
+    }
+    if let Ok(block) = decode_block_ref(data, &limits) {
+        assert_eq!(canonical_bytes(&block.to_block()), data);
+    }
+});
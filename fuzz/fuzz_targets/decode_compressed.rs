@@ -0,0 +1,18 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zk_sac_engine::serialization::{
+    decode_network_message, decompress, CompressionPolicies, DecodeLimits, PayloadKind,
+};
+use zk_sac_engine::types::Block;
+
+fuzz_target!(|data: &[u8]| {
+    let limits = DecodeLimits::default();
+    let policies = CompressionPolicies::default();
+    for kind in [PayloadKind::Block, PayloadKind::Transaction, PayloadKind::Proof, PayloadKind::Message] {
+        if let Ok(payload) = decompress(kind, data, &policies, &limits) {
+            assert!(payload.len() <= limits.limit_for(kind));
+        }
+    }
+    let _ = decode_network_message::<Block>(data);
+});
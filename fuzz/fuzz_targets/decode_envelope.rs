@@ -0,0 +1,17 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zk_sac_engine::serialization::{open, peek, DecodeLimits};
+use zk_sac_engine::types::{Block, Transaction, ValidatorSet, WorldState, ZkProof};
+
+fuzz_target!(|data: &[u8]| {
+    if peek(data).is_err() {
+        return;
+    }
+    let limits = DecodeLimits::default();
+    let _ = open::<Block>(data, &limits);
+    let _ = open::<Transaction>(data, &limits);
+    let _ = open::<ZkProof>(data, &limits);
+    let _ = open::<WorldState>(data, &limits);
+    let _ = open::<ValidatorSet>(data, &limits);
+});
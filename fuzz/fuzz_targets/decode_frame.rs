@@ -0,0 +1,14 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use zk_sac_engine::serialization::{decode_frame, DecodeLimits};
+
+fuzz_target!(|data: &[u8]| {
+    let limits = DecodeLimits::default();
+    let mut rest = data;
+    while let Ok(Some((frame, used))) = decode_frame(rest, &limits) {
+        assert!(used <= rest.len());
+        assert!(frame.payload.len() <= limits.limit_for(frame.kind));
+        rest = &rest[used..];
+    }
+});
@@ -0,0 +1,20 @@
+#![no_main]
+
+use futures::executor::block_on;
+use libfuzzer_sys::fuzz_target;
+use zk_sac_engine::serialization::{read_block, DecodeLimits, FrameReader, ProofArchiveReader, SnapshotReader};
+
+fuzz_target!(|data: &[u8]| {
+    block_on(async {
+        let limits = DecodeLimits::default();
+
+        let _ = read_block(&mut FrameReader::new(data, limits)).await;
+
+        if let Ok(snapshot) = SnapshotReader::open(data, limits).await {
+            let _ = snapshot.into_state().await;
+        }
+
+        let mut archive = ProofArchiveReader::new(data, limits);
+        while let Ok(Some(_)) = archive.next_proof().await {}
+    });
+});
@@ -0,0 +1,360 @@
+//! Circuit breaker for calls to flaky dependencies
+//!
+//! Wrap calls to a remote prover, signer, or peer in a [`CircuitBreaker`].
+//! While the recent failure rate stays under the threshold the circuit is
+//! closed and calls go through. Once it is exceeded the circuit opens and
+//! calls fail immediately, without taking a concurrency slot, until the
+//! cooldown elapses. The circuit then half-opens and lets a few trial calls
+//! through: enough successes close it again, any failure re-opens it.
+
+use anyhow::Result;
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::Arc;
+use tokio::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CircuitBreakerConfig {
+    /// Open once this fraction of the last `window_size` calls failed
+    pub failure_rate_threshold: f64,
+    /// Calls needed in the window before the failure rate is acted on
+    pub minimum_calls: usize,
+    pub window_size: usize,
+    /// How long the circuit stays open before trial calls are allowed
+    pub cooldown: Duration,
+    /// Trial calls allowed concurrently while half-open
+    pub half_open_max_calls: usize,
+    /// Successful trial calls needed to close the circuit again
+    pub half_open_successes: usize,
+}
+
+impl Default for CircuitBreakerConfig {
+    fn default() -> Self {
+        Self {
+            failure_rate_threshold: 0.5,
+            minimum_calls: 10,
+            window_size: 20,
+            cooldown: Duration::from_secs(30),
+            half_open_max_calls: 1,
+            half_open_successes: 3,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CircuitState {
+    Closed,
+    Open,
+    HalfOpen,
+}
+
+/// Returned instead of calling the dependency while the circuit is open
+#[derive(Debug, Clone, thiserror::Error)]
+#[error("circuit '{name}' is open, retry in {retry_in:?}")]
+pub struct CircuitOpenError {
+    pub name: String,
+    pub retry_in: Duration,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CircuitStats {
+    pub successes: u64,
+    pub failures: u64,
+    /// Calls refused because the circuit was open
+    pub rejected: u64,
+    pub times_opened: u64,
+}
+
+#[derive(Debug)]
+struct BreakerState {
+    state: CircuitState,
+    /// Recent outcomes, `true` for failure
+    outcomes: VecDeque<bool>,
+    opened_at: Option<Instant>,
+    half_open_in_flight: usize,
+    half_open_successes: usize,
+    stats: CircuitStats,
+}
+
+/// Cloneable handle; clones share state
+#[derive(Debug, Clone)]
+pub struct CircuitBreaker {
+    name: Arc<str>,
+    config: Arc<CircuitBreakerConfig>,
+    inner: Arc<Mutex<BreakerState>>,
+}
+
+impl CircuitBreaker {
+    pub fn new(name: impl Into<String>, config: CircuitBreakerConfig) -> Self {
+        Self {
+            name: Arc::from(name.into()),
+            inner: Arc::new(Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                outcomes: VecDeque::with_capacity(config.window_size),
+                opened_at: None,
+                half_open_in_flight: 0,
+                half_open_successes: 0,
+                stats: CircuitStats::default(),
+            })),
+            config: Arc::new(config),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let mut state = self.inner.lock();
+        self.refresh(&mut state);
+        state.state
+    }
+
+    pub fn stats(&self) -> CircuitStats {
+        self.inner.lock().stats.clone()
+    }
+
+    /// Run `call` through the breaker, failing fast with [`CircuitOpenError`] while open
+    pub async fn call<F, T>(&self, call: F) -> Result<T>
+    where
+        F: Future<Output = Result<T>>,
+    {
+        let permit = self.acquire()?;
+        let result = call.await;
+        permit.record(result.is_ok());
+        result
+    }
+
+    /// Reserve a call slot; report the outcome through the returned permit.
+    /// A permit dropped without a recorded outcome releases its slot and
+    /// counts as nothing.
+    pub fn acquire(&self) -> Result<CallPermit, CircuitOpenError> {
+        let mut state = self.inner.lock();
+        self.refresh(&mut state);
+
+        match state.state {
+            CircuitState::Closed => {}
+            CircuitState::HalfOpen if state.half_open_in_flight < self.config.half_open_max_calls => {
+                state.half_open_in_flight += 1;
+            }
+            _ => {
+                state.stats.rejected += 1;
+                let retry_in = state.opened_at
+                    .map(|opened| self.config.cooldown.saturating_sub(opened.elapsed()))
+                    .unwrap_or_default();
+                return Err(CircuitOpenError { name: self.name.to_string(), retry_in });
+            }
+        }
+
+        Ok(CallPermit {
+            breaker: self.clone(),
+            half_open: state.state == CircuitState::HalfOpen,
+            recorded: false,
+        })
+    }
+
+    /// Force the circuit closed and forget recent outcomes
+    pub fn reset(&self) {
+        let mut state = self.inner.lock();
+        self.transition(&mut state, CircuitState::Closed);
+    }
+
+    /// Move an open circuit to half-open once the cooldown has passed
+    fn refresh(&self, state: &mut BreakerState) {
+        if state.state == CircuitState::Open
+            && state.opened_at.is_some_and(|opened| opened.elapsed() >= self.config.cooldown)
+        {
+            self.transition(state, CircuitState::HalfOpen);
+        }
+    }
+
+    fn record(&self, half_open: bool, success: bool) {
+        let mut state = self.inner.lock();
+        if success {
+            state.stats.successes += 1;
+        } else {
+            state.stats.failures += 1;
+        }
+
+        if half_open {
+            state.half_open_in_flight = state.half_open_in_flight.saturating_sub(1);
+            // The circuit may have moved on while this trial call was running
+            if state.state != CircuitState::HalfOpen {
+                return;
+            }
+            if !success {
+                self.transition(&mut state, CircuitState::Open);
+            } else {
+                state.half_open_successes += 1;
+                if state.half_open_successes >= self.config.half_open_successes {
+                    self.transition(&mut state, CircuitState::Closed);
+                }
+            }
+            return;
+        }
+
+        if state.state != CircuitState::Closed {
+            return;
+        }
+        state.outcomes.push_back(!success);
+        while state.outcomes.len() > self.config.window_size.max(1) {
+            state.outcomes.pop_front();
+        }
+
+        let calls = state.outcomes.len();
+        if calls >= self.config.minimum_calls {
+            let failures = state.outcomes.iter().filter(|failed| **failed).count();
+            let rate = failures as f64 / calls as f64;
+            if rate >= self.config.failure_rate_threshold {
+                warn!("🔌 Circuit '{}' opened: {}/{} recent calls failed", self.name, failures, calls);
+                self.transition(&mut state, CircuitState::Open);
+            }
+        }
+    }
+
+    fn release(&self, half_open: bool) {
+        if half_open {
+            let mut state = self.inner.lock();
+            state.half_open_in_flight = state.half_open_in_flight.saturating_sub(1);
+        }
+    }
+
+    fn transition(&self, state: &mut BreakerState, to: CircuitState) {
+        match to {
+            CircuitState::Open => {
+                state.opened_at = Some(Instant::now());
+                state.stats.times_opened += 1;
+            }
+            CircuitState::HalfOpen => {
+                info!("🔌 Circuit '{}' half-open, allowing trial calls", self.name);
+            }
+            CircuitState::Closed => {
+                if state.state != CircuitState::Closed {
+                    info!("🔌 Circuit '{}' closed", self.name);
+                }
+                state.opened_at = None;
+            }
+        }
+        state.state = to;
+        state.outcomes.clear();
+        state.half_open_in_flight = 0;
+        state.half_open_successes = 0;
+    }
+}
+
+/// A reserved call slot on a [`CircuitBreaker`]
+#[must_use = "record the call outcome with `record`"]
+pub struct CallPermit {
+    breaker: CircuitBreaker,
+    half_open: bool,
+    recorded: bool,
+}
+
+impl CallPermit {
+    pub fn record(mut self, success: bool) {
+        self.recorded = true;
+        self.breaker.record(self.half_open, success);
+    }
+}
+
+impl Drop for CallPermit {
+    fn drop(&mut self) {
+        if !self.recorded {
+            self.breaker.release(self.half_open);
+        }
+    }
+}
+
+/// One breaker per dependency instance, e.g. per peer or per remote prover
+#[derive(Debug)]
+pub struct CircuitBreakers<K> {
+    config: CircuitBreakerConfig,
+    breakers: Mutex<HashMap<K, CircuitBreaker>>,
+}
+
+impl<K: Hash + Eq + Clone + fmt::Display> CircuitBreakers<K> {
+    pub fn new(config: CircuitBreakerConfig) -> Self {
+        Self {
+            config,
+            breakers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> CircuitBreaker {
+        self.breakers.lock()
+            .entry(key.clone())
+            .or_insert_with(|| CircuitBreaker::new(key.to_string(), self.config.clone()))
+            .clone()
+    }
+
+    /// Keys whose circuit is currently not closed
+    pub fn unhealthy(&self) -> Vec<K> {
+        self.breakers.lock()
+            .iter()
+            .filter(|(_, breaker)| breaker.state() != CircuitState::Closed)
+            .map(|(key, _)| key.clone())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    fn config() -> CircuitBreakerConfig {
+        CircuitBreakerConfig {
+            failure_rate_threshold: 0.5,
+            minimum_calls: 4,
+            window_size: 4,
+            cooldown: Duration::from_millis(50),
+            half_open_max_calls: 1,
+            half_open_successes: 2,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_opens_on_failure_rate_and_recovers() {
+        let breaker = CircuitBreaker::new("prover", config());
+
+        for i in 0..4 {
+            let _ = breaker.call(async move {
+                if i % 2 == 0 { Err(anyhow!("down")) } else { Ok(()) }
+            }).await;
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        let refused = breaker.call(async { Ok(()) }).await.unwrap_err();
+        assert!(refused.downcast_ref::<CircuitOpenError>().is_some());
+        assert_eq!(breaker.stats().rejected, 1);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        // Only one trial call at a time
+        let trial = breaker.acquire().unwrap();
+        assert!(breaker.acquire().is_err());
+        trial.record(true);
+
+        breaker.call(async { Ok(()) }).await.unwrap();
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn test_failed_trial_reopens() {
+        let breaker = CircuitBreaker::new("signer", config());
+        for _ in 0..4 {
+            let _ = breaker.call(async { Err::<(), _>(anyhow!("down")) }).await;
+        }
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        let _ = breaker.call(async { Err::<(), _>(anyhow!("still down")) }).await;
+        assert_eq!(breaker.state(), CircuitState::Open);
+        assert_eq!(breaker.stats().times_opened, 2);
+    }
+}
@@ -0,0 +1,103 @@
+//! Slot deadlines for multi-stage work
+//!
+//! Block production runs several stages (collection, execution, proving,
+//! signature collection) inside one slot. A [`Deadline`] is passed down to
+//! each stage so it can see how much of the slot is left and shrink its work
+//! or skip optional parts instead of running past the end of the slot.
+
+use anyhow::{Result, anyhow};
+use std::future::Future;
+use tokio::time::{timeout_at, Duration, Instant};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Deadline {
+    started: Instant,
+    at: Instant,
+}
+
+impl Deadline {
+    /// A deadline `budget` from now
+    pub fn after(budget: Duration) -> Self {
+        let started = Instant::now();
+        Self { started, at: started + budget }
+    }
+
+    pub fn at(&self) -> Instant {
+        self.at
+    }
+
+    /// Total time the deadline was created with
+    pub fn budget(&self) -> Duration {
+        self.at.saturating_duration_since(self.started)
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.at.saturating_duration_since(Instant::now())
+    }
+
+    pub fn is_expired(&self) -> bool {
+        Instant::now() >= self.at
+    }
+
+    /// Fraction of the budget still available, between 0 and 1
+    pub fn fraction_remaining(&self) -> f64 {
+        let budget = self.budget().as_secs_f64();
+        if budget == 0.0 {
+            return 0.0;
+        }
+        (self.remaining().as_secs_f64() / budget).clamp(0.0, 1.0)
+    }
+
+    /// Whether at least `needed` is left
+    pub fn has(&self, needed: Duration) -> bool {
+        self.remaining() >= needed
+    }
+
+    /// Deadline for one stage that may use at most `fraction` of what is left
+    pub fn stage(&self, fraction: f64) -> Deadline {
+        let now = Instant::now();
+        let share = self.remaining().mul_f64(fraction.clamp(0.0, 1.0));
+        Deadline { started: now, at: (now + share).min(self.at) }
+    }
+
+    /// Deadline that ends `reserve` earlier, leaving that time for later stages
+    pub fn reserving(&self, reserve: Duration) -> Deadline {
+        let at = self.at.checked_sub(reserve).unwrap_or(self.started).max(self.started);
+        Deadline { started: self.started, at }
+    }
+
+    /// Run `future` until the deadline, failing if it does not finish in time
+    pub async fn run<F: Future>(&self, future: F) -> Result<F::Output> {
+        timeout_at(self.at, future).await
+            .map_err(|_| anyhow!("Deadline exceeded after {:?}", self.elapsed()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_stage_and_reserve_stay_within_parent() {
+        let slot = Deadline::after(Duration::from_secs(4));
+        assert!(slot.stage(0.5).remaining() <= Duration::from_secs(2));
+        assert!(slot.stage(2.0).at() <= slot.at());
+
+        let execution = slot.reserving(Duration::from_secs(3));
+        assert!(execution.remaining() <= Duration::from_secs(1));
+        assert_eq!(slot.reserving(Duration::from_secs(10)).remaining(), Duration::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_run_times_out() {
+        let deadline = Deadline::after(Duration::from_millis(10));
+        let result = deadline.run(tokio::time::sleep(Duration::from_secs(1))).await;
+        assert!(result.is_err());
+        assert!(deadline.is_expired());
+        assert_eq!(deadline.fraction_remaining(), 0.0);
+    }
+}
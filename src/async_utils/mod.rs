@@ -0,0 +1,1331 @@
+//! Advanced Async Utilities using Tokio 1.46.1
+//! 
+//! This module provides optimized async patterns and utilities for the ZK-SAC consensus engine,
+//! leveraging Tokio 1.46.1's latest performance improvements and features.
+
+use tokio::{
+    task::{JoinHandle, JoinSet, spawn, spawn_blocking},
+    time::{timeout, Duration, Instant, sleep},
+    sync::{mpsc, OwnedSemaphorePermit, RwLock, Semaphore},
+    select, try_join,
+};
+use futures::{
+    future::{Future, FutureExt, join_all, try_join_all, select_all},
+    stream::{Stream, StreamExt, FuturesUnordered},
+    channel::{mpsc as futures_mpsc},
+    pin_mut, SinkExt,
+};
+use serde::{Serialize, Deserialize};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::task::{Context, Poll};
+use anyhow::{Result, anyhow};
+use tracing::{info, debug, warn, error};
+
+pub mod circuit_breaker;
+pub mod deadline;
+pub mod rate_limiter;
+pub mod shutdown;
+pub mod single_flight;
+pub mod supervisor;
+
+pub use circuit_breaker::{CircuitBreaker, CircuitBreakerConfig, CircuitBreakers, CircuitOpenError, CircuitState};
+pub use deadline::Deadline;
+pub use rate_limiter::{KeyedRateLimiter, RateLimiter};
+pub use shutdown::TaskScope;
+pub use single_flight::SingleFlight;
+pub use supervisor::{RestartPolicy, Supervisor, SupervisorConfig, TaskExit};
+pub use tokio_util::sync::CancellationToken;
+
+/// Snapshot of an [`AsyncTaskPool`]'s load
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PoolStats {
+    pub max_concurrent: usize,
+    /// Tasks waiting for a slot
+    pub queued: usize,
+    pub running: usize,
+    /// Tasks that have released their slot, successfully or not
+    pub completed: u64,
+    /// Tasks refused because the pool was shutting down
+    pub rejected: u64,
+    /// Mean time tasks spent waiting for a slot
+    pub average_wait_ms: f64,
+}
+
+#[derive(Debug, Default)]
+struct PoolCounters {
+    queued: AtomicUsize,
+    running: AtomicUsize,
+    completed: AtomicU64,
+    rejected: AtomicU64,
+    total_wait_micros: AtomicU64,
+    admitted: AtomicU64,
+}
+
+/// Counts a task as queued for as long as it is waiting for a slot
+struct QueuedGuard<'a>(&'a PoolCounters);
+
+impl Drop for QueuedGuard<'_> {
+    fn drop(&mut self) {
+        self.0.queued.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+/// A pool slot held by a running task
+struct SlotGuard {
+    _permit: OwnedSemaphorePermit,
+    counters: Arc<PoolCounters>,
+}
+
+impl Drop for SlotGuard {
+    fn drop(&mut self) {
+        self.counters.running.fetch_sub(1, Ordering::Relaxed);
+        self.counters.completed.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+/// Wait for a pool slot, recording queueing time
+async fn acquire_slot(
+    semaphore: Arc<Semaphore>,
+    counters: Arc<PoolCounters>,
+    token: CancellationToken,
+) -> Result<SlotGuard> {
+    let permit = {
+        counters.queued.fetch_add(1, Ordering::Relaxed);
+        let _queued = QueuedGuard(&counters);
+        let started = Instant::now();
+        let permit = select! {
+            permit = semaphore.acquire_owned() => permit
+                .map_err(|e| anyhow!("Failed to acquire semaphore: {}", e)),
+            _ = token.cancelled() => Err(anyhow!("Task pool is shutting down")),
+        };
+        if permit.is_ok() {
+            counters.total_wait_micros.fetch_add(started.elapsed().as_micros() as u64, Ordering::Relaxed);
+            counters.admitted.fetch_add(1, Ordering::Relaxed);
+        }
+        permit
+    };
+
+    match permit {
+        Ok(permit) => {
+            counters.running.fetch_add(1, Ordering::Relaxed);
+            Ok(SlotGuard { _permit: permit, counters })
+        }
+        Err(e) => {
+            counters.rejected.fetch_add(1, Ordering::Relaxed);
+            Err(e)
+        }
+    }
+}
+
+/// Advanced async task pool for consensus operations
+pub struct AsyncTaskPool {
+    semaphore: Arc<Semaphore>,
+    max_concurrent: AtomicUsize,
+    counters: Arc<PoolCounters>,
+    scope: TaskScope,
+}
+
+impl AsyncTaskPool {
+    /// Create new task pool with maximum concurrent tasks
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            semaphore: Arc::new(Semaphore::new(max_concurrent)),
+            max_concurrent: AtomicUsize::new(max_concurrent),
+            counters: Arc::new(PoolCounters::default()),
+            scope: TaskScope::new(),
+        }
+    }
+
+    /// Start shutting down when `parent` is cancelled
+    pub fn with_cancellation(mut self, parent: &CancellationToken) -> Self {
+        self.scope = TaskScope::child_of(parent);
+        self
+    }
+
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.scope.token()
+    }
+
+    fn slot(&self) -> impl Future<Output = Result<SlotGuard>> {
+        acquire_slot(self.semaphore.clone(), self.counters.clone(), self.scope.token())
+    }
+
+    fn reject(&self, error: anyhow::Error) -> anyhow::Error {
+        self.counters.rejected.fetch_add(1, Ordering::Relaxed);
+        error
+    }
+
+    /// Execute task with concurrency control
+    pub async fn execute<F, Fut, T>(&self, task: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<T>> + Send,
+        T: Send + 'static,
+    {
+        let slot = self.slot().await?;
+        
+        let handle = self.scope.spawn(async move {
+            let _slot = slot;
+            task().await
+        }).map_err(|e| self.reject(e))?;
+        
+        handle.await
+            .map_err(|e| anyhow!("Task execution failed: {}", e))??
+    }
+
+    /// Spawn `future` to run once a pool slot is free. The returned handle
+    /// resolves to the future's output, or an error if the task was rejected,
+    /// aborted, or panicked.
+    pub fn spawn<Fut, T>(&self, future: Fut) -> PoolHandle<T>
+    where
+        Fut: std::future::Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let slot = self.slot();
+        let spawned = self.scope.spawn(async move {
+            let _slot = slot.await?;
+            Ok(future.await)
+        });
+
+        match spawned {
+            Ok(handle) => PoolHandle { state: PoolHandleState::Running(handle) },
+            Err(e) => PoolHandle { state: PoolHandleState::Rejected(Some(self.reject(e))) },
+        }
+    }
+
+    /// Run CPU-bound closures on the blocking thread pool, at most
+    /// `max_concurrent` at a time. Results are returned in input order, one
+    /// per task, so a failing task does not hide the others' results.
+    pub async fn execute_all<F, T>(&self, tasks: Vec<F>) -> Vec<Result<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        let count = tasks.len();
+        if self.scope.is_shutting_down() {
+            return (0..count)
+                .map(|_| Err(self.reject(anyhow!("Task pool is shutting down"))))
+                .collect();
+        }
+
+        let mut join_set = JoinSet::new();
+        for (index, task) in tasks.into_iter().enumerate() {
+            let slot = self.slot();
+            join_set.spawn(async move {
+                let result = match slot.await {
+                    Ok(slot) => spawn_blocking(move || {
+                        let _slot = slot;
+                        task()
+                    }).await.map_err(|e| anyhow!("Task execution failed: {}", e)),
+                    Err(e) => Err(e),
+                };
+                (index, result)
+            });
+        }
+
+        let mut results: Vec<Option<Result<T>>> = (0..count).map(|_| None).collect();
+        while let Some(joined) = join_set.join_next().await {
+            match joined {
+                Ok((index, result)) => results[index] = Some(result),
+                Err(e) => error!("❌ Bulk task wrapper failed: {}", e),
+            }
+        }
+
+        results.into_iter()
+            .map(|result| result.unwrap_or_else(|| Err(anyhow!("Task did not complete"))))
+            .collect()
+    }
+
+    /// Execute a call to an external dependency through `breaker`. While the
+    /// circuit is open the call is refused before it takes a pool slot.
+    pub async fn execute_guarded<F, Fut, T>(&self, breaker: &CircuitBreaker, task: F) -> Result<T>
+    where
+        F: FnOnce() -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<T>> + Send,
+        T: Send + 'static,
+    {
+        let permit = breaker.acquire()?;
+        let result = self.execute(task).await;
+        permit.record(result.is_ok());
+        result
+    }
+
+    /// Change the number of concurrent slots. Growing takes effect
+    /// immediately; shrinking takes effect as running tasks release slots.
+    pub fn resize(&self, max_concurrent: usize) {
+        let previous = self.max_concurrent.swap(max_concurrent, Ordering::SeqCst);
+        if max_concurrent > previous {
+            self.semaphore.add_permits(max_concurrent - previous);
+        } else if max_concurrent < previous {
+            let excess = previous - max_concurrent;
+            let outstanding = excess - self.semaphore.forget_permits(excess);
+            if outstanding > 0 {
+                // Retire the remaining slots as busy tasks hand them back
+                let semaphore = self.semaphore.clone();
+                match tokio::runtime::Handle::try_current() {
+                    Ok(handle) => {
+                        handle.spawn(async move {
+                            if let Ok(permits) = semaphore.acquire_many_owned(outstanding as u32).await {
+                                permits.forget();
+                            }
+                        });
+                    }
+                    Err(_) => warn!("⚠️  No runtime to retire {} busy pool slots", outstanding),
+                }
+            }
+        }
+        info!("📐 Task pool resized from {} to {} slots", previous, max_concurrent);
+    }
+
+    pub fn max_concurrent(&self) -> usize {
+        self.max_concurrent.load(Ordering::SeqCst)
+    }
+
+    pub fn stats(&self) -> PoolStats {
+        let counters = &self.counters;
+        let admitted = counters.admitted.load(Ordering::Relaxed);
+        PoolStats {
+            max_concurrent: self.max_concurrent(),
+            queued: counters.queued.load(Ordering::Relaxed),
+            running: counters.running.load(Ordering::Relaxed),
+            completed: counters.completed.load(Ordering::Relaxed),
+            rejected: counters.rejected.load(Ordering::Relaxed),
+            average_wait_ms: if admitted == 0 {
+                0.0
+            } else {
+                counters.total_wait_micros.load(Ordering::Relaxed) as f64 / admitted as f64 / 1000.0
+            },
+        }
+    }
+
+    /// Stop accepting tasks and wait up to `timeout` for running ones, aborting the rest
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        self.scope.shutdown(timeout).await
+    }
+
+    /// Number of tasks currently running
+    pub fn in_flight(&self) -> usize {
+        self.scope.in_flight()
+    }
+
+    /// Get current available capacity
+    pub fn available_permits(&self) -> usize {
+        self.semaphore.available_permits()
+    }
+}
+
+/// Handle to a task spawned with [`AsyncTaskPool::spawn`]
+pub struct PoolHandle<T> {
+    state: PoolHandleState<T>,
+}
+
+enum PoolHandleState<T> {
+    Running(JoinHandle<Result<Result<T>>>),
+    Rejected(Option<anyhow::Error>),
+}
+
+impl<T> PoolHandle<T> {
+    /// Abort the task if it has not finished
+    pub fn abort(&self) {
+        if let PoolHandleState::Running(handle) = &self.state {
+            handle.abort();
+        }
+    }
+
+    pub fn is_finished(&self) -> bool {
+        match &self.state {
+            PoolHandleState::Running(handle) => handle.is_finished(),
+            PoolHandleState::Rejected(_) => true,
+        }
+    }
+}
+
+impl<T> Future for PoolHandle<T> {
+    type Output = Result<T>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        match &mut self.state {
+            PoolHandleState::Running(handle) => Pin::new(handle).poll(cx).map(|joined| {
+                joined.map_err(|e| anyhow!("Task execution failed: {}", e))?
+                    .and_then(|result| result)
+            }),
+            PoolHandleState::Rejected(error) => Poll::Ready(Err(
+                error.take().unwrap_or_else(|| anyhow!("Pool handle polled after completion"))
+            )),
+        }
+    }
+}
+
+/// High-performance async batch processor for transactions
+pub struct BatchProcessor<T> {
+    batch_size: usize,
+    timeout_duration: Duration,
+    sender: mpsc::Sender<T>,
+    receiver: Arc<RwLock<Option<mpsc::Receiver<T>>>>,
+    scope: TaskScope,
+}
+
+impl<T: Send + 'static> BatchProcessor<T> {
+    /// Create new batch processor
+    pub fn new(batch_size: usize, timeout_ms: u64) -> Self {
+        let (sender, receiver) = mpsc::channel(batch_size * 2);
+        
+        Self {
+            batch_size,
+            timeout_duration: Duration::from_millis(timeout_ms),
+            sender,
+            receiver: Arc::new(RwLock::new(Some(receiver))),
+            scope: TaskScope::new(),
+        }
+    }
+
+    /// Start draining when `parent` is cancelled
+    pub fn with_cancellation(mut self, parent: &CancellationToken) -> Self {
+        self.scope = TaskScope::child_of(parent);
+        self
+    }
+
+    /// Stop accepting items, process everything already queued, and wait up
+    /// to `timeout` for the final batch before aborting
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        self.scope.shutdown(timeout).await
+    }
+
+    /// Add item to batch
+    pub async fn add_item(&self, item: T) -> Result<()> {
+        self.sender.send(item).await
+            .map_err(|_| anyhow!("Batch processor channel closed"))
+    }
+
+    /// Start processing batches with provided handler
+    pub async fn start_processing<F, Fut>(&self, mut handler: F) -> Result<()>
+    where
+        F: FnMut(Vec<T>) -> Fut + Send + 'static,
+        Fut: std::future::Future<Output = Result<()>> + Send,
+    {
+        let mut receiver = {
+            let mut lock = self.receiver.write().await;
+            lock.take().ok_or_else(|| anyhow!("Batch processor already started"))?
+        };
+
+        let batch_size = self.batch_size;
+        let timeout_duration = self.timeout_duration;
+        let shutdown = self.scope.token();
+
+        self.scope.spawn(async move {
+            let mut current_batch = Vec::with_capacity(batch_size);
+            let mut last_batch_time = Instant::now();
+            let mut draining = false;
+
+            loop {
+                select! {
+                    // Shutdown requested: refuse new items and drain what is queued
+                    _ = shutdown.cancelled(), if !draining => {
+                        debug!("🛑 Batch processor draining");
+                        draining = true;
+                        receiver.close();
+                    }
+
+                    // Receive new item
+                    item = receiver.recv() => {
+                        match item {
+                            Some(item) => {
+                                current_batch.push(item);
+                                
+                                // Process batch if full
+                                if current_batch.len() >= batch_size {
+                                    run_batch(&mut handler, current_batch.drain(..).collect(), "Batch processing").await;
+                                    last_batch_time = Instant::now();
+                                }
+                            }
+                            None => {
+                                // Channel closed, process remaining items
+                                if !current_batch.is_empty() {
+                                    run_batch(&mut handler, current_batch.drain(..).collect(), "Final batch processing").await;
+                                }
+                                break;
+                            }
+                        }
+                    }
+                    
+                    // Timeout elapsed
+                    _ = sleep(timeout_duration), if !current_batch.is_empty() => {
+                        if last_batch_time.elapsed() >= timeout_duration {
+                            run_batch(&mut handler, current_batch.drain(..).collect(), "Timeout batch processing").await;
+                            last_batch_time = Instant::now();
+                        }
+                    }
+                }
+            }
+        })?;
+
+        Ok(())
+    }
+}
+
+/// Run one batch, containing a panicking handler so the processing loop survives it
+async fn run_batch<T, F, Fut>(handler: &mut F, batch: Vec<T>, context: &str)
+where
+    F: FnMut(Vec<T>) -> Fut,
+    Fut: std::future::Future<Output = Result<()>>,
+{
+    let size = batch.len();
+    match supervisor::catch_panic(handler(batch)).await {
+        TaskExit::Completed => {}
+        TaskExit::Failed(e) => error!("{} failed: {}", context, e),
+        TaskExit::Panicked(message) => error!("💥 {} panicked on {} items: {}", context, size, message),
+    }
+}
+
+/// Async parallel execution helper using Tokio 1.46.1 features
+pub struct ParallelExecutor;
+
+impl ParallelExecutor {
+    /// Execute multiple async tasks in parallel with timeout
+    pub async fn execute_with_timeout<T>(
+        tasks: Vec<JoinHandle<Result<T>>>,
+        timeout_duration: Duration,
+    ) -> Result<Vec<T>>
+    where
+        T: Send + 'static,
+    {
+        let execution = async {
+            let mut results = Vec::with_capacity(tasks.len());
+            
+            for task in tasks {
+                let result = task.await
+                    .map_err(|e| anyhow!("Task join failed: {}", e))??;
+                results.push(result);
+            }
+            
+            Ok(results)
+        };
+
+        timeout(timeout_duration, execution).await
+            .map_err(|_| anyhow!("Parallel execution timed out"))?
+    }
+
+    /// Execute CPU-bound tasks on blocking thread pool
+    pub async fn execute_cpu_bound<F, T>(tasks: Vec<F>) -> Result<Vec<T>>
+    where
+        F: FnOnce() -> Result<T> + Send + 'static,
+        T: Send + 'static,
+    {
+        let handles: Vec<_> = tasks.into_iter()
+            .map(|task| spawn_blocking(task))
+            .collect();
+
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            let result = handle.await
+                .map_err(|e| anyhow!("Blocking task failed: {}", e))??;
+            results.push(result);
+        }
+
+        Ok(results)
+    }
+
+    /// Advanced parallel execution with load balancing
+    pub async fn execute_load_balanced<F, Fut, T>(
+        task_factory: F,
+        task_count: usize,
+        max_concurrent: usize,
+    ) -> Result<Vec<T>>
+    where
+        F: Fn(usize) -> Fut + Send + Sync + Clone + 'static,
+        Fut: std::future::Future<Output = Result<T>> + Send,
+        T: Send + 'static,
+    {
+        let task_pool = AsyncTaskPool::new(max_concurrent);
+        let mut handles = Vec::with_capacity(task_count);
+
+        for i in 0..task_count {
+            let factory = Arc::new(task_factory.clone());
+            
+            let handle = spawn(async move {
+                let fut = factory(i);
+                fut.await
+            });
+            
+            handles.push(handle);
+        }
+
+        Self::execute_with_timeout(handles, Duration::from_secs(30)).await
+    }
+}
+
+/// Advanced async coordinator for consensus operations
+pub struct ConsensusCoordinator {
+    block_production_pool: AsyncTaskPool,
+    validation_pool: AsyncTaskPool,
+    signature_pool: AsyncTaskPool,
+    token: CancellationToken,
+}
+
+impl ConsensusCoordinator {
+    /// Create new consensus coordinator with optimized pools
+    pub fn new() -> Self {
+        Self::with_cancellation(&CancellationToken::new())
+    }
+
+    /// Create a coordinator whose pools start shutting down when `parent` is cancelled
+    pub fn with_cancellation(parent: &CancellationToken) -> Self {
+        let token = parent.child_token();
+        Self {
+            block_production_pool: AsyncTaskPool::new(2).with_cancellation(&token), // Limited for sequential block production
+            validation_pool: AsyncTaskPool::new(8).with_cancellation(&token),       // Parallel validation
+            signature_pool: AsyncTaskPool::new(16).with_cancellation(&token),       // Many parallel signatures
+            token,
+        }
+    }
+
+    pub fn cancellation_token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Shut down all pools, giving in-flight work up to `timeout` to finish
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        info!("🛑 Shutting down consensus coordinator");
+        self.token.cancel();
+        let (production, validation, signatures) = tokio::join!(
+            self.block_production_pool.shutdown(timeout),
+            self.validation_pool.shutdown(timeout),
+            self.signature_pool.shutdown(timeout),
+        );
+        production.and(validation).and(signatures)
+    }
+
+    /// Coordinate block production with parallel validation
+    pub async fn coordinate_block_production<F1, F2, F3, Fut1, Fut2, Fut3, T1, T2, T3>(
+        &self,
+        produce_block: F1,
+        validate_transactions: F2,
+        collect_signatures: F3,
+    ) -> Result<(T1, T2, T3)>
+    where
+        F1: FnOnce() -> Fut1 + Send + 'static,
+        F2: FnOnce() -> Fut2 + Send + 'static,
+        F3: FnOnce() -> Fut3 + Send + 'static,
+        Fut1: std::future::Future<Output = Result<T1>> + Send,
+        Fut2: std::future::Future<Output = Result<T2>> + Send,
+        Fut3: std::future::Future<Output = Result<T3>> + Send,
+        T1: Send + 'static,
+        T2: Send + 'static,
+        T3: Send + 'static,
+    {
+        info!("🔄 Coordinating parallel block production");
+
+        // Execute block production, validation, and signature collection in parallel
+        let (block_result, validation_result, signature_result) = try_join!(
+            self.block_production_pool.execute(produce_block),
+            self.validation_pool.execute(validate_transactions),
+            self.signature_pool.execute(collect_signatures)
+        )?;
+
+        info!("✅ Block production coordination completed");
+        Ok((block_result, validation_result, signature_result))
+    }
+
+    /// Like [`coordinate_block_production`](Self::coordinate_block_production),
+    /// but every stage is handed `deadline` so it can shrink its work, and the
+    /// whole round fails once the deadline passes
+    pub async fn coordinate_block_production_by<F1, F2, F3, Fut1, Fut2, Fut3, T1, T2, T3>(
+        &self,
+        deadline: Deadline,
+        produce_block: F1,
+        validate_transactions: F2,
+        collect_signatures: F3,
+    ) -> Result<(T1, T2, T3)>
+    where
+        F1: FnOnce(Deadline) -> Fut1 + Send + 'static,
+        F2: FnOnce(Deadline) -> Fut2 + Send + 'static,
+        F3: FnOnce(Deadline) -> Fut3 + Send + 'static,
+        Fut1: std::future::Future<Output = Result<T1>> + Send,
+        Fut2: std::future::Future<Output = Result<T2>> + Send,
+        Fut3: std::future::Future<Output = Result<T3>> + Send,
+        T1: Send + 'static,
+        T2: Send + 'static,
+        T3: Send + 'static,
+    {
+        info!("🔄 Coordinating parallel block production ({:?} left in slot)", deadline.remaining());
+
+        let round = self.coordinate_block_production(
+            move || produce_block(deadline),
+            move || validate_transactions(deadline),
+            move || collect_signatures(deadline),
+        );
+        deadline.run(round).await?
+    }
+
+    /// Monitor pool performance
+    pub fn get_pool_stats(&self) -> (usize, usize, usize) {
+        (
+            self.block_production_pool.available_permits(),
+            self.validation_pool.available_permits(),
+            self.signature_pool.available_permits(),
+        )
+    }
+
+    /// Detailed load for each pool, labelled by name
+    pub fn pool_stats(&self) -> [(&'static str, PoolStats); 3] {
+        [
+            ("block_production", self.block_production_pool.stats()),
+            ("validation", self.validation_pool.stats()),
+            ("signature", self.signature_pool.stats()),
+        ]
+    }
+
+    pub fn block_production_pool(&self) -> &AsyncTaskPool {
+        &self.block_production_pool
+    }
+
+    /// Validation pool, e.g. to `resize` it under sustained queueing
+    pub fn validation_pool(&self) -> &AsyncTaskPool {
+        &self.validation_pool
+    }
+
+    pub fn signature_pool(&self) -> &AsyncTaskPool {
+        &self.signature_pool
+    }
+}
+
+/// Async timeout helper with exponential backoff using futures 0.3.31
+pub struct TimeoutManager;
+
+impl TimeoutManager {
+    /// Execute with exponential backoff retry
+    pub async fn execute_with_backoff<F, Fut, T>(
+        task: F,
+        max_retries: usize,
+        base_timeout_ms: u64,
+    ) -> Result<T>
+    where
+        F: Fn() -> Fut,
+        Fut: std::future::Future<Output = Result<T>>,
+    {
+        let mut current_timeout = Duration::from_millis(base_timeout_ms);
+        
+        for attempt in 0..max_retries {
+            debug!("⏱️  Attempt {} with timeout {:?}", attempt + 1, current_timeout);
+            
+            match timeout(current_timeout, task()).await {
+                Ok(Ok(result)) => {
+                    if attempt > 0 {
+                        info!("✅ Task succeeded after {} retries", attempt);
+                    }
+                    return Ok(result);
+                }
+                Ok(Err(e)) => {
+                    warn!("🚫 Task failed: {}", e);
+                    return Err(e);
+                }
+                Err(_) => {
+                    warn!("⏰ Timeout on attempt {}", attempt + 1);
+                    if attempt == max_retries - 1 {
+                        return Err(anyhow!("Task failed after {} timeout attempts", max_retries));
+                    }
+                    // Exponential backoff
+                    current_timeout = Duration::from_millis((current_timeout.as_millis() as u64) * 2);
+                    sleep(Duration::from_millis(100)).await; // Brief pause between retries
+                }
+            }
+        }
+        
+        Err(anyhow!("Task failed after {} attempts", max_retries))
+    }
+}
+
+/// Order in which stream results are returned
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultOrder {
+    /// Results in input order; a slow item holds back later ones
+    Ordered,
+    /// Results as soon as they complete
+    Unordered,
+}
+
+/// What to do when processing an item fails
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// Stop at the first error, dropping work in flight
+    FailFast,
+    /// Keep going and report every error with its input index
+    Collect,
+}
+
+#[derive(Debug, Clone)]
+pub struct StreamOptions {
+    pub max_concurrent: usize,
+    pub order: ResultOrder,
+    pub error_policy: ErrorPolicy,
+    /// Stop pulling items and drop in-flight work once cancelled
+    pub cancellation: Option<CancellationToken>,
+}
+
+impl StreamOptions {
+    pub fn new(max_concurrent: usize) -> Self {
+        Self {
+            max_concurrent,
+            order: ResultOrder::Ordered,
+            error_policy: ErrorPolicy::FailFast,
+            cancellation: None,
+        }
+    }
+
+    pub fn unordered(mut self) -> Self {
+        self.order = ResultOrder::Unordered;
+        self
+    }
+
+    pub fn with_error_policy(mut self, error_policy: ErrorPolicy) -> Self {
+        self.error_policy = error_policy;
+        self
+    }
+
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancellation = Some(token);
+        self
+    }
+}
+
+/// Successful results plus, under [`ErrorPolicy::Collect`], the failures
+#[derive(Debug)]
+pub struct StreamOutcome<U> {
+    pub results: Vec<U>,
+    /// `(input index, error)` pairs
+    pub errors: Vec<(usize, anyhow::Error)>,
+}
+
+/// Advanced streaming utilities using futures 0.3.31
+pub struct StreamingProcessor;
+
+impl StreamingProcessor {
+    /// Process a stream with at most `max_concurrent` items in flight,
+    /// returning results in input order and stopping at the first error
+    pub async fn process_stream<S, F, Fut, T, U>(
+        stream: S,
+        processor: F,
+        max_concurrent: usize,
+    ) -> Result<Vec<U>>
+    where
+        S: Stream<Item = T> + Unpin,
+        F: Fn(T) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<U>> + Send,
+        T: Send + 'static,
+        U: Send + 'static,
+    {
+        let outcome = Self::process_stream_with(stream, processor, StreamOptions::new(max_concurrent)).await?;
+        Ok(outcome.results)
+    }
+
+    /// Like [`process_stream`](Self::process_stream), returning results in completion order
+    pub async fn process_stream_unordered<S, F, Fut, T, U>(
+        stream: S,
+        processor: F,
+        max_concurrent: usize,
+    ) -> Result<Vec<U>>
+    where
+        S: Stream<Item = T> + Unpin,
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<U>>,
+    {
+        let options = StreamOptions::new(max_concurrent).unordered();
+        let outcome = Self::process_stream_with(stream, processor, options).await?;
+        Ok(outcome.results)
+    }
+
+    /// Process a stream with explicit ordering, error policy, and cancellation.
+    /// Items are pulled from `stream` only as concurrency slots free up.
+    pub async fn process_stream_with<S, F, Fut, T, U>(
+        stream: S,
+        processor: F,
+        options: StreamOptions,
+    ) -> Result<StreamOutcome<U>>
+    where
+        S: Stream<Item = T> + Unpin,
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<U>>,
+    {
+        let max_concurrent = options.max_concurrent.max(1);
+        let tasks = stream.enumerate().map(|(index, item)| {
+            let future = processor(item);
+            async move { (index, future.await) }
+        });
+        let mut completed = match options.order {
+            ResultOrder::Ordered => tasks.buffered(max_concurrent).boxed_local(),
+            ResultOrder::Unordered => tasks.buffer_unordered(max_concurrent).boxed_local(),
+        };
+
+        let cancelled = async {
+            match &options.cancellation {
+                Some(token) => token.cancelled().await,
+                None => futures::future::pending().await,
+            }
+        };
+        pin_mut!(cancelled);
+
+        let mut outcome = StreamOutcome { results: Vec::new(), errors: Vec::new() };
+        loop {
+            let next = select! {
+                biased;
+                _ = &mut cancelled => {
+                    return Err(anyhow!("Stream processing cancelled after {} results", outcome.results.len()));
+                }
+                next = completed.next() => next,
+            };
+
+            match next {
+                None => break,
+                Some((_, Ok(result))) => outcome.results.push(result),
+                Some((index, Err(e))) => match options.error_policy {
+                    ErrorPolicy::FailFast => return Err(e.context(format!("Stream item {} failed", index))),
+                    ErrorPolicy::Collect => {
+                        debug!("⚠️  Stream item {} failed: {}", index, e);
+                        outcome.errors.push((index, e));
+                    }
+                },
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    /// Batch process with futures::join_all
+    pub async fn batch_process_all<F, Fut, T, U>(
+        items: Vec<T>,
+        processor: F,
+    ) -> Result<Vec<U>>
+    where
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<U>>,
+    {
+        let futures: Vec<_> = items.into_iter().map(processor).collect();
+        let results = join_all(futures).await;
+        
+        // Convert Vec<Result<U>> to Result<Vec<U>>
+        results.into_iter().collect()
+    }
+
+    /// Try join all with early termination on error
+    pub async fn try_batch_process_all<F, Fut, T, U>(
+        items: Vec<T>,
+        processor: F,
+    ) -> Result<Vec<U>>
+    where
+        F: Fn(T) -> Fut,
+        Fut: Future<Output = Result<U>>,
+    {
+        let futures: Vec<_> = items.into_iter().map(processor).collect();
+        try_join_all(futures).await
+    }
+}
+
+/// Racing and selection utilities using futures 0.3.31
+pub struct FutureRacer;
+
+impl FutureRacer {
+    /// Race multiple futures, return first successful result
+    pub async fn race_first_success<F, T>(futures: Vec<F>) -> Result<T>
+    where
+        F: Future<Output = Result<T>> + Unpin,
+    {
+        if futures.is_empty() {
+            return Err(anyhow!("No futures to race"));
+        }
+
+        let (result, _index, _remaining) = select_all(futures).await;
+        result
+    }
+
+    /// Select between multiple operations with timeout
+    pub async fn select_with_timeout<F1, F2, T1, T2>(
+        fut1: F1,
+        fut2: F2,
+        timeout_duration: Duration,
+    ) -> Result<Either<T1, T2>>
+    where
+        F1: Future<Output = Result<T1>>,
+        F2: Future<Output = Result<T2>>,
+    {
+        pin_mut!(fut1, fut2);
+        
+        let selection = select! {
+            result1 = fut1.fuse() => Either::Left(result1?),
+            result2 = fut2.fuse() => Either::Right(result2?),
+        };
+
+        timeout(timeout_duration, async { Ok(selection) }).await
+            .map_err(|_| anyhow!("Selection timed out"))?
+    }
+}
+
+/// Custom Either type for selection results
+#[derive(Debug)]
+pub enum Either<L, R> {
+    Left(L),
+    Right(R),
+}
+
+/// Advanced channel utilities using futures channels
+pub struct ChannelManager;
+
+impl ChannelManager {
+    /// Create a futures channel for cross-task communication
+    pub fn create_futures_channel<T>(buffer_size: usize) -> (futures_mpsc::Sender<T>, futures_mpsc::Receiver<T>) {
+        futures_mpsc::channel(buffer_size)
+    }
+
+    /// Broadcast to multiple receivers
+    pub async fn broadcast_to_many<T: Clone>(
+        message: T,
+        senders: Vec<futures_mpsc::Sender<T>>,
+    ) -> Result<()> {
+        let futures: Vec<_> = senders
+            .into_iter()
+            .map(|mut sender| {
+                let msg = message.clone();
+                async move {
+                    sender.send(msg).await
+                        .map_err(|e| anyhow!("Broadcast failed: {:?}", e))
+                }
+            })
+            .collect();
+
+        try_join_all(futures).await?;
+        Ok(())
+    }
+
+    /// Collect from multiple streams
+    pub async fn collect_from_streams<T, S>(
+        streams: Vec<S>,
+        max_items: usize,
+    ) -> Result<Vec<T>>
+    where
+        S: Stream<Item = T> + Unpin,
+        T: Send + 'static,
+    {
+        let mut all_items = Vec::new();
+        let mut stream_futures = FuturesUnordered::new();
+
+        // Convert streams to futures
+        for mut stream in streams {
+            stream_futures.push(async move {
+                let mut items = Vec::new();
+                while let Some(item) = stream.next().await {
+                    items.push(item);
+                    if items.len() >= max_items {
+                        break;
+                    }
+                }
+                items
+            });
+        }
+
+        // Collect all results
+        while let Some(items) = stream_futures.next().await {
+            all_items.extend(items);
+        }
+
+        Ok(all_items)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::test;
+
+    #[test]
+    async fn test_task_pool() {
+        let pool = AsyncTaskPool::new(2);
+        
+        let result = pool.execute(|| async {
+            sleep(Duration::from_millis(10)).await;
+            Ok::<i32, anyhow::Error>(42)
+        }).await.unwrap();
+        
+        assert_eq!(result, 42);
+    }
+
+    #[test]
+    async fn test_task_pool_shutdown_aborts_stragglers() {
+        let pool = Arc::new(AsyncTaskPool::new(2));
+
+        let runner = pool.clone();
+        let stuck = spawn(async move {
+            runner.execute(|| async {
+                sleep(Duration::from_secs(60)).await;
+                Ok::<(), anyhow::Error>(())
+            }).await
+        });
+        sleep(Duration::from_millis(20)).await;
+        assert_eq!(pool.in_flight(), 1);
+
+        assert!(pool.shutdown(Duration::from_millis(20)).await.is_err());
+        assert!(stuck.await.unwrap().is_err());
+        assert!(pool.execute(|| async { Ok::<i32, anyhow::Error>(1) }).await.is_err());
+    }
+
+    #[test]
+    async fn test_batch_processor_drains_on_shutdown() {
+        let processor = BatchProcessor::new(100, 10_000);
+        let processed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let counter = processed.clone();
+        processor.start_processing(move |batch: Vec<u32>| {
+            let counter = counter.clone();
+            async move {
+                counter.fetch_add(batch.len(), std::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            }
+        }).await.unwrap();
+
+        for i in 0..5 {
+            processor.add_item(i).await.unwrap();
+        }
+        processor.shutdown(Duration::from_secs(1)).await.unwrap();
+
+        assert_eq!(processed.load(std::sync::atomic::Ordering::SeqCst), 5);
+        assert!(processor.add_item(5).await.is_err());
+    }
+
+    #[test]
+    async fn test_batch_processor_survives_handler_panic() {
+        let processor = BatchProcessor::new(1, 10_000);
+        let processed = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+
+        let counter = processed.clone();
+        processor.start_processing(move |batch: Vec<u32>| {
+            let counter = counter.clone();
+            async move {
+                if batch[0] == 0 {
+                    panic!("poisoned batch");
+                }
+                counter.fetch_add(batch.len(), std::sync::atomic::Ordering::SeqCst);
+                Ok(())
+            }
+        }).await.unwrap();
+
+        for i in 0..3 {
+            processor.add_item(i).await.unwrap();
+        }
+        processor.shutdown(Duration::from_secs(1)).await.unwrap();
+        assert_eq!(processed.load(std::sync::atomic::Ordering::SeqCst), 2);
+    }
+
+    #[test]
+    async fn test_task_pool_spawn_and_execute_all() {
+        let pool = AsyncTaskPool::new(2);
+
+        let handles: Vec<_> = (0..5u64)
+            .map(|i| pool.spawn(async move {
+                sleep(Duration::from_millis(5)).await;
+                i * 10
+            }))
+            .collect();
+        let results = join_all(handles).await;
+        assert_eq!(results.into_iter().map(Result::unwrap).collect::<Vec<_>>(), vec![0, 10, 20, 30, 40]);
+
+        let tasks: Vec<Box<dyn FnOnce() -> u64 + Send>> = vec![
+            Box::new(|| 1),
+            Box::new(|| -> u64 { panic!("bad task") }),
+            Box::new(|| 3),
+        ];
+        let results = pool.execute_all(tasks).await;
+        assert_eq!(*results[0].as_ref().unwrap(), 1);
+        assert!(results[1].is_err());
+        assert_eq!(*results[2].as_ref().unwrap(), 3);
+    }
+
+    #[test]
+    async fn test_pool_stats_and_resize() {
+        let pool = AsyncTaskPool::new(1);
+
+        let handles: Vec<_> = (0..3)
+            .map(|_| pool.spawn(sleep(Duration::from_millis(30))))
+            .collect();
+        sleep(Duration::from_millis(10)).await;
+        let stats = pool.stats();
+        assert_eq!(stats.running, 1);
+        assert_eq!(stats.queued, 2);
+
+        pool.resize(3);
+        sleep(Duration::from_millis(5)).await;
+        assert_eq!(pool.stats().running, 3);
+
+        join_all(handles).await;
+        let stats = pool.stats();
+        assert_eq!(stats.completed, 3);
+        assert_eq!(stats.queued, 0);
+        assert!(stats.average_wait_ms > 0.0);
+
+        pool.resize(1);
+        assert_eq!(pool.available_permits(), 1);
+        assert_eq!(pool.max_concurrent(), 1);
+    }
+
+    #[test]
+    async fn test_parallel_executor() {
+        let tasks: Vec<JoinHandle<Result<i32>>> = (0..5)
+            .map(|i| spawn(async move {
+                sleep(Duration::from_millis(10)).await;
+                Ok(i * 2)
+            }))
+            .collect();
+
+        let results = ParallelExecutor::execute_with_timeout(
+            tasks, 
+            Duration::from_secs(1)
+        ).await.unwrap();
+
+        assert_eq!(results, vec![0, 2, 4, 6, 8]);
+    }
+
+    #[test]
+    async fn test_timeout_manager() {
+        let result = TimeoutManager::execute_with_backoff(
+            || async {
+                sleep(Duration::from_millis(5)).await;
+                Ok::<String, anyhow::Error>("success".to_string())
+            },
+            3,
+            100,
+        ).await.unwrap();
+
+        assert_eq!(result, "success");
+    }
+
+    #[test]
+    async fn test_streaming_processor() {
+        use futures::stream;
+        
+        let items = vec![1, 2, 3, 4, 5];
+        let stream = stream::iter(items);
+        
+        let results = StreamingProcessor::process_stream(
+            stream,
+            |x| async move { 
+                sleep(Duration::from_millis(10)).await;
+                Ok::<i32, anyhow::Error>(x * 2) 
+            },
+            3, // max concurrent
+        ).await.unwrap();
+
+        assert_eq!(results, vec![2, 4, 6, 8, 10]);
+    }
+
+    #[test]
+    async fn test_stream_ordering_variants() {
+        use futures::stream;
+
+        // Earlier items take longer, so completion order is the reverse of input order
+        let slow_first = |x: u64| async move {
+            sleep(Duration::from_millis(40 - x * 10)).await;
+            Ok::<u64, anyhow::Error>(x)
+        };
+
+        let ordered = StreamingProcessor::process_stream(stream::iter(0..4), slow_first, 4).await.unwrap();
+        assert_eq!(ordered, vec![0, 1, 2, 3]);
+
+        let unordered = StreamingProcessor::process_stream_unordered(stream::iter(0..4), slow_first, 4).await.unwrap();
+        assert_eq!(unordered, vec![3, 2, 1, 0]);
+    }
+
+    #[test]
+    async fn test_stream_error_policies() {
+        use futures::stream;
+
+        let odd_fails = |x: i32| async move {
+            if x % 2 == 1 { Err(anyhow!("odd {}", x)) } else { Ok(x) }
+        };
+
+        assert!(StreamingProcessor::process_stream(stream::iter(0..6), odd_fails, 2).await.is_err());
+
+        let options = StreamOptions::new(2).with_error_policy(ErrorPolicy::Collect);
+        let outcome = StreamingProcessor::process_stream_with(stream::iter(0..6), odd_fails, options).await.unwrap();
+        assert_eq!(outcome.results, vec![0, 2, 4]);
+        assert_eq!(outcome.errors.iter().map(|(i, _)| *i).collect::<Vec<_>>(), vec![1, 3, 5]);
+    }
+
+    #[test]
+    async fn test_stream_cancelled_mid_stream() {
+        use futures::stream;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let started = Arc::new(AtomicUsize::new(0));
+        let token = CancellationToken::new();
+
+        let canceller = token.clone();
+        spawn(async move {
+            sleep(Duration::from_millis(50)).await;
+            canceller.cancel();
+        });
+
+        let counter = started.clone();
+        let result = StreamingProcessor::process_stream_with(
+            stream::iter(0..),
+            move |x: u64| {
+                counter.fetch_add(1, Ordering::SeqCst);
+                async move {
+                    sleep(Duration::from_millis(10)).await;
+                    Ok::<u64, anyhow::Error>(x)
+                }
+            },
+            StreamOptions::new(2).with_cancellation(token),
+        ).await;
+
+        assert!(result.is_err());
+        let after_cancel = started.load(Ordering::SeqCst);
+        assert!(after_cancel < 20, "pulled {} items", after_cancel);
+        sleep(Duration::from_millis(30)).await;
+        assert_eq!(started.load(Ordering::SeqCst), after_cancel);
+    }
+
+    #[test]
+    async fn test_batch_processing() {
+        let items = vec![1, 2, 3, 4, 5];
+        
+        let results = StreamingProcessor::batch_process_all(
+            items,
+            |x| async move {
+                sleep(Duration::from_millis(5)).await;
+                Ok::<i32, anyhow::Error>(x * 3)
+            }
+        ).await.unwrap();
+
+        assert_eq!(results, vec![3, 6, 9, 12, 15]);
+    }
+
+    #[test]
+    async fn test_future_racing() {
+        let fut1 = async {
+            sleep(Duration::from_millis(50)).await;
+            Ok::<String, anyhow::Error>("slow".to_string())
+        };
+        
+        let fut2 = async {
+            sleep(Duration::from_millis(10)).await;
+            Ok::<String, anyhow::Error>("fast".to_string())
+        };
+
+        let result = FutureRacer::race_first_success(vec![fut1, fut2]).await.unwrap();
+        assert_eq!(result, "fast");
+    }
+
+    #[test]
+    async fn test_channel_manager() {
+        let (sender1, mut receiver1) = ChannelManager::create_futures_channel(10);
+        let (sender2, mut receiver2) = ChannelManager::create_futures_channel(10);
+        
+        let message = "broadcast test".to_string();
+        ChannelManager::broadcast_to_many(
+            message.clone(), 
+            vec![sender1, sender2]
+        ).await.unwrap();
+
+        assert_eq!(receiver1.next().await, Some(message.clone()));
+        assert_eq!(receiver2.next().await, Some(message));
+    }
+} 
\ No newline at end of file
@@ -0,0 +1,203 @@
+//! Token-bucket rate limiting
+//!
+//! A [`RateLimiter`] refills tokens continuously at `rate` per second up to
+//! `burst`. Callers either check without waiting ([`RateLimiter::try_acquire`],
+//! e.g. to drop a peer's excess gossip) or wait for capacity
+//! ([`RateLimiter::acquire`], e.g. before submitting to a remote prover).
+//! Rates can be changed at runtime; waiters pick up the new rate.
+//! [`KeyedRateLimiter`] keeps one bucket per peer or client.
+
+use anyhow::{Result, anyhow};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::hash::Hash;
+use std::sync::Arc;
+use tokio::time::{sleep, Duration, Instant};
+
+/// Longest single sleep while waiting, so rate changes are noticed promptly
+const MAX_WAIT_SLICE: Duration = Duration::from_millis(250);
+
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    burst: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_refill).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.burst);
+        self.last_refill = now;
+    }
+
+    /// Take `n` tokens, or return how long until they are available
+    fn take(&mut self, n: f64) -> Result<(), Duration> {
+        self.refill();
+        if self.tokens >= n {
+            self.tokens -= n;
+            return Ok(());
+        }
+        if self.rate <= 0.0 {
+            return Err(MAX_WAIT_SLICE);
+        }
+        Err(Duration::from_secs_f64((n - self.tokens) / self.rate))
+    }
+}
+
+/// Cloneable handle; clones share one bucket
+#[derive(Debug, Clone)]
+pub struct RateLimiter {
+    bucket: Arc<Mutex<Bucket>>,
+}
+
+impl RateLimiter {
+    /// Allow `rate` operations per second on average and bursts of up to `burst`
+    pub fn new(rate: f64, burst: u32) -> Self {
+        let burst = f64::from(burst.max(1));
+        Self {
+            bucket: Arc::new(Mutex::new(Bucket {
+                rate: rate.max(0.0),
+                burst,
+                tokens: burst,
+                last_refill: Instant::now(),
+            })),
+        }
+    }
+
+    /// Take `n` tokens if available right now
+    pub fn try_acquire(&self, n: u32) -> bool {
+        self.bucket.lock().take(f64::from(n)).is_ok()
+    }
+
+    /// Wait until `n` tokens are available and take them
+    pub async fn acquire(&self, n: u32) -> Result<()> {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock();
+                if f64::from(n) > bucket.burst {
+                    return Err(anyhow!("Requested {} tokens but burst is {}", n, bucket.burst));
+                }
+                match bucket.take(f64::from(n)) {
+                    Ok(()) => return Ok(()),
+                    Err(wait) => wait,
+                }
+            };
+            sleep(wait.min(MAX_WAIT_SLICE)).await;
+        }
+    }
+
+    /// Change the rate and burst; accumulated tokens are capped at the new burst
+    pub fn set_rate(&self, rate: f64, burst: u32) {
+        let mut bucket = self.bucket.lock();
+        bucket.refill();
+        bucket.rate = rate.max(0.0);
+        bucket.burst = f64::from(burst.max(1));
+        bucket.tokens = bucket.tokens.min(bucket.burst);
+    }
+
+    pub fn rate(&self) -> f64 {
+        self.bucket.lock().rate
+    }
+
+    /// Tokens available right now
+    pub fn available(&self) -> f64 {
+        let mut bucket = self.bucket.lock();
+        bucket.refill();
+        bucket.tokens
+    }
+
+    fn is_full(&self) -> bool {
+        let mut bucket = self.bucket.lock();
+        bucket.refill();
+        bucket.tokens >= bucket.burst
+    }
+}
+
+/// One bucket per key, all sharing the same rate
+#[derive(Debug)]
+pub struct KeyedRateLimiter<K> {
+    rate: Mutex<(f64, u32)>,
+    limiters: Mutex<HashMap<K, RateLimiter>>,
+}
+
+impl<K: Hash + Eq + Clone> KeyedRateLimiter<K> {
+    pub fn new(rate: f64, burst: u32) -> Self {
+        Self {
+            rate: Mutex::new((rate, burst)),
+            limiters: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, key: &K) -> RateLimiter {
+        let (rate, burst) = *self.rate.lock();
+        self.limiters.lock()
+            .entry(key.clone())
+            .or_insert_with(|| RateLimiter::new(rate, burst))
+            .clone()
+    }
+
+    pub fn try_acquire(&self, key: &K, n: u32) -> bool {
+        self.get(key).try_acquire(n)
+    }
+
+    pub async fn acquire(&self, key: &K, n: u32) -> Result<()> {
+        self.get(key).acquire(n).await
+    }
+
+    /// Change the rate for every existing and future key
+    pub fn set_rate(&self, rate: f64, burst: u32) {
+        *self.rate.lock() = (rate, burst);
+        for limiter in self.limiters.lock().values() {
+            limiter.set_rate(rate, burst);
+        }
+    }
+
+    /// Forget keys whose bucket has fully refilled, i.e. that have been idle
+    pub fn prune_idle(&self) -> usize {
+        let mut limiters = self.limiters.lock();
+        let before = limiters.len();
+        limiters.retain(|_, limiter| !limiter.is_full());
+        before - limiters.len()
+    }
+
+    pub fn len(&self) -> usize {
+        self.limiters.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.limiters.lock().is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_burst_then_refill() {
+        let limiter = RateLimiter::new(100.0, 5);
+        assert!((0..5).all(|_| limiter.try_acquire(1)));
+        assert!(!limiter.try_acquire(1));
+
+        let started = Instant::now();
+        limiter.acquire(2).await.unwrap();
+        assert!(started.elapsed() >= Duration::from_millis(15));
+        assert!(limiter.acquire(6).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rate_change_applies_to_keys() {
+        let limiter = KeyedRateLimiter::new(0.0, 1);
+        assert!(limiter.try_acquire(&"peer-a", 1));
+        assert!(!limiter.try_acquire(&"peer-a", 1));
+        assert!(limiter.try_acquire(&"peer-b", 1));
+
+        limiter.set_rate(1_000.0, 1);
+        sleep(Duration::from_millis(5)).await;
+        assert!(limiter.try_acquire(&"peer-a", 1));
+        assert_eq!(limiter.len(), 2);
+    }
+}
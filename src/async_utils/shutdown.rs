@@ -0,0 +1,108 @@
+//! Task ownership for graceful shutdown
+//!
+//! A [`TaskScope`] owns the background tasks spawned by one async utility.
+//! Shutdown is two-phase: the scope's cancellation token is cancelled so
+//! new work is refused and long-running loops can drain, then in-flight
+//! tasks get a grace period before being aborted. Dropping a scope aborts
+//! whatever is still running, so tasks never outlive their owner.
+
+use anyhow::{Result, anyhow};
+use std::future::Future;
+use tokio::select;
+use tokio::task::JoinHandle;
+use tokio::time::{timeout, Duration};
+use tokio_util::sync::CancellationToken;
+use tokio_util::task::TaskTracker;
+use tracing::{debug, warn};
+
+pub struct TaskScope {
+    /// Cancelled when shutdown starts; tasks may watch it to drain
+    shutdown: CancellationToken,
+    /// Cancelled when remaining tasks must stop at their next await point
+    abort: CancellationToken,
+    tracker: TaskTracker,
+}
+
+impl TaskScope {
+    pub fn new() -> Self {
+        Self::with_token(CancellationToken::new())
+    }
+
+    /// A scope that also starts shutting down when `parent` is cancelled
+    pub fn child_of(parent: &CancellationToken) -> Self {
+        Self::with_token(parent.child_token())
+    }
+
+    fn with_token(shutdown: CancellationToken) -> Self {
+        Self {
+            shutdown,
+            abort: CancellationToken::new(),
+            tracker: TaskTracker::new(),
+        }
+    }
+
+    /// Token cancelled when this scope starts shutting down
+    pub fn token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutdown.is_cancelled()
+    }
+
+    /// Number of spawned tasks that have not finished yet
+    pub fn in_flight(&self) -> usize {
+        self.tracker.len()
+    }
+
+    /// Spawn a task owned by this scope; resolves to an error if the task is aborted
+    pub fn spawn<F, T>(&self, future: F) -> Result<JoinHandle<Result<T>>>
+    where
+        F: Future<Output = T> + Send + 'static,
+        T: Send + 'static,
+    {
+        if self.shutdown.is_cancelled() {
+            return Err(anyhow!("Task scope is shutting down"));
+        }
+
+        let abort = self.abort.clone();
+        Ok(self.tracker.spawn(async move {
+            select! {
+                _ = abort.cancelled() => Err(anyhow!("Task aborted during shutdown")),
+                output = future => Ok(output),
+            }
+        }))
+    }
+
+    /// Refuse new work and wait up to `grace` for in-flight tasks, aborting any left over
+    pub async fn shutdown(&self, grace: Duration) -> Result<()> {
+        self.shutdown.cancel();
+        self.tracker.close();
+
+        if timeout(grace, self.tracker.wait()).await.is_ok() {
+            debug!("🛑 Task scope drained");
+            return Ok(());
+        }
+
+        let remaining = self.tracker.len();
+        warn!("🛑 {} tasks still running after {:?}, aborting", remaining, grace);
+        self.abort.cancel();
+        Err(anyhow!("{} tasks did not finish within {:?} and were aborted", remaining, grace))
+    }
+}
+
+impl Default for TaskScope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TaskScope {
+    fn drop(&mut self) {
+        if !self.tracker.is_empty() {
+            debug!("🛑 Aborting {} orphaned tasks", self.tracker.len());
+        }
+        self.shutdown.cancel();
+        self.abort.cancel();
+    }
+}
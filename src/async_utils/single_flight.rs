@@ -0,0 +1,136 @@
+//! Deduplication of identical concurrent operations
+//!
+//! When several tasks ask for the same thing at once (verify the same
+//! proof, fetch the same block, rebuild the same witness), [`SingleFlight`]
+//! runs the operation once and hands every caller a clone of its result.
+//! Results are not cached: once the shared call finishes, the next request
+//! for the key starts a fresh one.
+
+use anyhow::{Result, anyhow};
+use futures::future::{BoxFuture, FutureExt, Shared};
+use parking_lot::Mutex;
+use std::collections::HashMap;
+use std::future::Future;
+use std::hash::Hash;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+type SharedCall<V> = Shared<BoxFuture<'static, Result<V, Arc<anyhow::Error>>>>;
+
+pub struct SingleFlight<K, V> {
+    calls: Mutex<HashMap<K, SharedCall<V>>>,
+    started: AtomicU64,
+    deduplicated: AtomicU64,
+}
+
+impl<K, V> SingleFlight<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    pub fn new() -> Self {
+        Self {
+            calls: Mutex::new(HashMap::new()),
+            started: AtomicU64::new(0),
+            deduplicated: AtomicU64::new(0),
+        }
+    }
+
+    /// Run the operation built by `make` for `key`, or join the call already
+    /// in flight for it. `make` is only invoked when no call is in flight.
+    pub async fn run<F, Fut>(&self, key: K, make: F) -> Result<V>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<V>> + Send + 'static,
+    {
+        let call = {
+            let mut calls = self.calls.lock();
+            match calls.get(&key) {
+                Some(call) => {
+                    self.deduplicated.fetch_add(1, Ordering::Relaxed);
+                    call.clone()
+                }
+                None => {
+                    self.started.fetch_add(1, Ordering::Relaxed);
+                    let call = make().map(|result| result.map_err(Arc::new)).boxed().shared();
+                    calls.insert(key.clone(), call.clone());
+                    call
+                }
+            }
+        };
+
+        let result = call.clone().await;
+
+        // Whoever finishes first clears the entry, unless a newer call replaced it
+        let mut calls = self.calls.lock();
+        if calls.get(&key).is_some_and(|current| current.ptr_eq(&call)) {
+            calls.remove(&key);
+        }
+        drop(calls);
+
+        result.map_err(|e| anyhow!("{:#}", e))
+    }
+
+    pub fn is_in_flight(&self, key: &K) -> bool {
+        self.calls.lock().contains_key(key)
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.calls.lock().len()
+    }
+
+    /// `(calls started, calls that joined one already in flight)`
+    pub fn counts(&self) -> (u64, u64) {
+        (self.started.load(Ordering::Relaxed), self.deduplicated.load(Ordering::Relaxed))
+    }
+}
+
+impl<K, V> Default for SingleFlight<K, V>
+where
+    K: Hash + Eq + Clone,
+    V: Clone + Send + Sync + 'static,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::join_all;
+    use std::sync::atomic::AtomicUsize;
+    use tokio::time::{sleep, Duration};
+
+    #[tokio::test]
+    async fn test_concurrent_calls_share_one_execution() {
+        let flight: SingleFlight<&str, u64> = SingleFlight::new();
+        let executions = Arc::new(AtomicUsize::new(0));
+
+        let calls = (0..10).map(|_| {
+            let executions = executions.clone();
+            flight.run("proof-1", move || async move {
+                executions.fetch_add(1, Ordering::SeqCst);
+                sleep(Duration::from_millis(20)).await;
+                Ok(7)
+            })
+        });
+        let results = join_all(calls).await;
+
+        assert!(results.iter().all(|r| *r.as_ref().unwrap() == 7));
+        assert_eq!(executions.load(Ordering::SeqCst), 1);
+        assert_eq!(flight.counts(), (1, 9));
+        assert_eq!(flight.in_flight(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_errors_are_shared_and_not_cached() {
+        let flight: SingleFlight<u32, u32> = SingleFlight::new();
+
+        let failing = flight.run(1, || async { Err(anyhow!("peer timed out")) }).await;
+        assert!(failing.unwrap_err().to_string().contains("peer timed out"));
+
+        let retried = flight.run(1, || async { Ok(5) }).await.unwrap();
+        assert_eq!(retried, 5);
+    }
+}
@@ -0,0 +1,247 @@
+//! Supervision of critical background tasks
+//!
+//! A [`Supervisor`] runs long-lived tasks, catches their panics instead of
+//! letting them vanish into a `JoinError`, logs them with the task's name,
+//! and restarts the task according to its [`RestartPolicy`] with exponential
+//! backoff. A task that keeps failing more than `max_restarts` times within
+//! `restart_window` escalates: the supervisor cancels the node's shutdown
+//! token so the process stops cleanly instead of limping along.
+
+use super::shutdown::TaskScope;
+use anyhow::Result;
+use futures::FutureExt;
+use serde::{Serialize, Deserialize};
+use std::any::Any;
+use std::collections::VecDeque;
+use std::future::Future;
+use std::panic::AssertUnwindSafe;
+use tokio::select;
+use tokio::task::JoinHandle;
+use tokio::time::{sleep, Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{error, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RestartPolicy {
+    /// Run once; failures are logged but not retried
+    Never,
+    /// Restart after a panic or an error, but not after a clean exit
+    OnFailure,
+    /// Restart whenever the task stops
+    Always,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SupervisorConfig {
+    /// Failures tolerated within `restart_window` before escalating
+    pub max_restarts: usize,
+    pub restart_window: Duration,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+}
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        Self {
+            max_restarts: 5,
+            restart_window: Duration::from_secs(60),
+            initial_backoff: Duration::from_millis(100),
+            max_backoff: Duration::from_secs(10),
+        }
+    }
+}
+
+/// How a supervised run ended
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TaskExit {
+    Completed,
+    Failed(String),
+    Panicked(String),
+}
+
+/// Extract the message from a panic payload
+pub fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+/// Run `future`, turning a panic into [`TaskExit::Panicked`]
+pub async fn catch_panic<F>(future: F) -> TaskExit
+where
+    F: Future<Output = Result<()>>,
+{
+    match AssertUnwindSafe(future).catch_unwind().await {
+        Ok(Ok(())) => TaskExit::Completed,
+        Ok(Err(e)) => TaskExit::Failed(e.to_string()),
+        Err(payload) => TaskExit::Panicked(panic_message(payload.as_ref())),
+    }
+}
+
+pub struct Supervisor {
+    config: SupervisorConfig,
+    /// Cancelled on escalation; also stops every supervised task
+    shutdown: CancellationToken,
+    scope: TaskScope,
+}
+
+impl Supervisor {
+    /// Supervise tasks under `shutdown`, the token the node stops on
+    pub fn new(shutdown: CancellationToken, config: SupervisorConfig) -> Self {
+        Self {
+            config,
+            scope: TaskScope::child_of(&shutdown),
+            shutdown,
+        }
+    }
+
+    pub fn shutdown_token(&self) -> CancellationToken {
+        self.shutdown.clone()
+    }
+
+    /// Run the task built by `factory`, rebuilding and restarting it per `policy`.
+    /// The handle resolves once the task is no longer being restarted.
+    pub fn supervise<F, Fut>(&self, name: impl Into<String>, policy: RestartPolicy, factory: F) -> Result<JoinHandle<Result<()>>>
+    where
+        F: Fn() -> Fut + Send + 'static,
+        Fut: Future<Output = Result<()>> + Send + 'static,
+    {
+        let name = name.into();
+        let config = self.config.clone();
+        let shutdown = self.shutdown.clone();
+        let stop = self.scope.token();
+
+        self.scope.spawn(async move {
+            let mut failures: VecDeque<Instant> = VecDeque::new();
+            let mut backoff = config.initial_backoff;
+
+            loop {
+                info!("👷 Starting supervised task '{}'", name);
+                let exit = select! {
+                    exit = catch_panic(factory()) => exit,
+                    _ = stop.cancelled() => return,
+                };
+
+                let failed = match &exit {
+                    TaskExit::Completed => {
+                        info!("👷 Supervised task '{}' completed", name);
+                        false
+                    }
+                    TaskExit::Failed(message) => {
+                        error!("❌ Supervised task '{}' failed: {}", name, message);
+                        true
+                    }
+                    TaskExit::Panicked(message) => {
+                        error!("💥 Supervised task '{}' panicked: {}", name, message);
+                        true
+                    }
+                };
+
+                let restart = match policy {
+                    RestartPolicy::Never => false,
+                    RestartPolicy::OnFailure => failed,
+                    RestartPolicy::Always => true,
+                };
+                if !restart {
+                    return;
+                }
+
+                if failed {
+                    let now = Instant::now();
+                    failures.push_back(now);
+                    while failures.front().is_some_and(|at| now.duration_since(*at) > config.restart_window) {
+                        failures.pop_front();
+                    }
+                    if failures.len() > config.max_restarts {
+                        error!("🚨 Task '{}' failed {} times within {:?}, escalating to shutdown",
+                               name, failures.len(), config.restart_window);
+                        shutdown.cancel();
+                        return;
+                    }
+                } else {
+                    backoff = config.initial_backoff;
+                }
+
+                warn!("👷 Restarting '{}' in {:?}", name, backoff);
+                select! {
+                    _ = sleep(backoff) => {}
+                    _ = stop.cancelled() => return,
+                }
+                if failed {
+                    backoff = (backoff * 2).min(config.max_backoff);
+                }
+            }
+        })
+    }
+
+    /// Stop all supervised tasks, waiting up to `timeout` before aborting them
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        self.scope.shutdown(timeout).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    fn fast_config(max_restarts: usize) -> SupervisorConfig {
+        SupervisorConfig {
+            max_restarts,
+            restart_window: Duration::from_secs(10),
+            initial_backoff: Duration::from_millis(1),
+            max_backoff: Duration::from_millis(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_panicking_task_is_restarted() {
+        let supervisor = Supervisor::new(CancellationToken::new(), fast_config(5));
+        let runs = Arc::new(AtomicUsize::new(0));
+
+        let counter = runs.clone();
+        let handle = supervisor.supervise("flaky", RestartPolicy::OnFailure, move || {
+            let counter = counter.clone();
+            async move {
+                if counter.fetch_add(1, Ordering::SeqCst) < 2 {
+                    panic!("boom");
+                }
+                Ok(())
+            }
+        }).unwrap();
+
+        handle.await.unwrap().unwrap();
+        assert_eq!(runs.load(Ordering::SeqCst), 3);
+        assert!(!supervisor.shutdown_token().is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_repeated_failures_escalate() {
+        let shutdown = CancellationToken::new();
+        let supervisor = Supervisor::new(shutdown.clone(), fast_config(2));
+
+        let handle = supervisor.supervise("broken", RestartPolicy::OnFailure, || async {
+            Err(anyhow!("cannot start"))
+        }).unwrap();
+
+        handle.await.unwrap().unwrap();
+        assert!(shutdown.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_catch_panic_reports_message() {
+        let exit = catch_panic(async {
+            if true {
+                panic!("bad state {}", 7);
+            }
+            Ok(())
+        }).await;
+        assert_eq!(exit, TaskExit::Panicked("bad state 7".to_string()));
+    }
+}
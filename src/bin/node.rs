@@ -0,0 +1,560 @@
+use anyhow::{Context, Result, bail};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+use zk_sac_engine::conformance::{self, ReferenceTarget, VectorSuite};
+use zk_sac_engine::consensus::engine::{ConsensusEngine, ZkSacConsensusEngine};
+use zk_sac_engine::consensus::events::EventBus;
+use zk_sac_engine::consensus::registration::{KeyRotation, ValidatorRegistration};
+use zk_sac_engine::crypto::bls::BlsKeyPair;
+use zk_sac_engine::crypto::hash::hex_utils;
+use zk_sac_engine::crypto::keystore::{KeyPair, Keystore};
+use zk_sac_engine::da::{AvailabilityGate, CelestiaDa, DaConfig, Namespace};
+use zk_sac_engine::node::config::{DaMode, TelemetrySettings};
+use zk_sac_engine::node::telemetry;
+use zk_sac_engine::node::store::{read_blocks, write_blocks};
+use zk_sac_engine::node::{BlockLog, Devnet, DevnetConfig, GenesisConfig, NodeConfig};
+use zk_sac_engine::rpc::{EthApi, EthRpcServer, SubscriptionServer};
+use zk_sac_engine::serialization::DecodeLimits;
+use zk_sac_engine::types::{Block, SignatureType};
+
+const PASSWORD_ENV: &str = "ZKSAC_KEYSTORE_PASSWORD";
+
+/// ZK-SAC full node
+#[derive(Debug, Parser)]
+#[command(name = "zk-sac-node", version)]
+struct Cli {
+    /// Config file; settings can also be overridden with ZKSAC_<SECTION>_<KEY> variables
+    #[arg(long, short, global = true, env = "ZKSAC_CONFIG")]
+    config: Option<PathBuf>,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+enum Command {
+    /// Write a default config.toml and a development genesis.toml whose
+    /// validator key is stored in the keystore
+    Init {
+        /// Directory for the config, genesis and chain data
+        #[arg(long, default_value = "./data")]
+        data_dir: PathBuf,
+        /// Overwrite existing files
+        #[arg(long)]
+        force: bool,
+        #[command(flatten)]
+        password: PasswordArgs,
+    },
+    /// Replay the stored chain and keep producing blocks until interrupted,
+    /// signing with the validator keys found in the keystore
+    Run {
+        #[command(flatten)]
+        password: PasswordArgs,
+    },
+    /// Validate and append blocks from a file written by `export`
+    Import { file: PathBuf },
+    /// Write stored blocks to a file
+    Export {
+        file: PathBuf,
+        /// First block number to export
+        #[arg(long)]
+        from: Option<u64>,
+        /// Last block number to export
+        #[arg(long)]
+        to: Option<u64>,
+    },
+    /// Run several validators in this process, connected by a simulated network
+    Devnet {
+        #[arg(long, default_value_t = 4)]
+        validators: usize,
+        /// Stop once every node has reached this height
+        #[arg(long)]
+        blocks: Option<u64>,
+    },
+    /// Generate or check conformance test vectors
+    Conformance {
+        #[command(subcommand)]
+        command: ConformanceCommand,
+    },
+    /// Manage validator keys in the encrypted keystore
+    Keys {
+        #[command(flatten)]
+        password: PasswordArgs,
+        #[command(subcommand)]
+        command: KeysCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum ConformanceCommand {
+    /// Write the test vectors this build produces
+    Generate { file: PathBuf },
+    /// Check this build against previously generated test vectors
+    Check { file: PathBuf },
+}
+
+#[derive(Debug, Subcommand)]
+enum KeysCommand {
+    /// Generate a new key and store it
+    Generate {
+        #[arg(long = "type", value_enum, default_value_t = KeyType::Ed25519)]
+        key_type: KeyType,
+    },
+    /// Store an existing 32-byte secret key read as hex from a file
+    Import {
+        #[arg(long = "type", value_enum, default_value_t = KeyType::Ed25519)]
+        key_type: KeyType,
+        #[arg(long)]
+        secret_file: PathBuf,
+    },
+    /// List stored keys with their addresses and public keys
+    List,
+    /// Print a signed validator registration for a stored key as JSON
+    Register {
+        address: String,
+        #[arg(long)]
+        stake: u64,
+    },
+    /// Generate a replacement key and print a rotation signed by both keys as JSON
+    Rotate {
+        address: String,
+        #[arg(long = "type", value_enum, default_value_t = KeyType::Ed25519)]
+        key_type: KeyType,
+        /// First epoch in which the new key signs
+        #[arg(long)]
+        epoch: u64,
+    },
+    /// Generate and store a BLS vote key for a stored validator key; its
+    /// public key goes in the validator's `bls_public_key` in genesis
+    Bls { address: String },
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+enum KeyType {
+    Ed25519,
+    Secp256k1,
+    PostQuantum,
+}
+
+impl From<KeyType> for SignatureType {
+    fn from(key_type: KeyType) -> Self {
+        match key_type {
+            KeyType::Ed25519 => SignatureType::Ed25519,
+            KeyType::Secp256k1 => SignatureType::Secp256k1,
+            KeyType::PostQuantum => SignatureType::PostQuantum,
+        }
+    }
+}
+
+#[derive(Debug, Args)]
+struct PasswordArgs {
+    /// Read the keystore password from a file instead of $ZKSAC_KEYSTORE_PASSWORD or a prompt
+    #[arg(long, global = true)]
+    password_file: Option<PathBuf>,
+}
+
+impl PasswordArgs {
+    fn read(&self) -> Result<String> {
+        if let Some(path) = &self.password_file {
+            let text = std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read password file {}", path.display()))?;
+            return Ok(text.trim_end_matches(['\r', '\n']).to_string());
+        }
+        if let Ok(password) = std::env::var(PASSWORD_ENV) {
+            return Ok(password);
+        }
+        rpassword::prompt_password("Keystore password: ").context("Failed to read password")
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let cli = Cli::parse();
+
+    match &cli.command {
+        Command::Init { data_dir, force, password } => {
+            let _telemetry = telemetry::init(&TelemetrySettings::default())?;
+            return init(data_dir, *force, password);
+        }
+        Command::Conformance { command } => {
+            let _telemetry = telemetry::init(&TelemetrySettings::default())?;
+            return conformance(command);
+        }
+        _ => {}
+    }
+    let config = NodeConfig::load(cli.config.as_deref())?;
+    let _telemetry = telemetry::init(&config.telemetry)?;
+
+    match cli.command {
+        Command::Init { .. } | Command::Conformance { .. } => unreachable!("handled above"),
+        Command::Run { password } => run(config, &password).await,
+        Command::Import { file } => import(config, &file).await,
+        Command::Export { file, from, to } => export(config, &file, from, to).await,
+        Command::Devnet { validators, blocks } => devnet(config, validators, blocks).await,
+        Command::Keys { password, command } => keys(&config, &password, command),
+    }
+}
+
+fn init(data_dir: &Path, force: bool, password: &PasswordArgs) -> Result<()> {
+    std::fs::create_dir_all(data_dir)
+        .with_context(|| format!("Failed to create {}", data_dir.display()))?;
+
+    let mut config = NodeConfig::default();
+    config.storage.data_dir = data_dir.to_path_buf();
+    let config_path = data_dir.join("config.toml");
+    let genesis_path = config.storage.genesis_path();
+
+    for path in [&config_path, &genesis_path] {
+        if path.exists() && !force {
+            bail!("{} already exists (use --force to overwrite)", path.display());
+        }
+    }
+    let keystore = Keystore::open(config.storage.keystore_dir())?;
+    let key = KeyPair::generate(SignatureType::Ed25519);
+    store_key(&keystore, &key, &password.read()?)?;
+    std::fs::write(&config_path, config.to_toml()?)?;
+    GenesisConfig::dev(&key).save(&genesis_path)?;
+
+    println!("✅ Wrote {}", config_path.display());
+    println!("✅ Wrote {} with the stored key as its validator", genesis_path.display());
+    println!("🚀 Start the node with: zk-sac-node --config {} run", config_path.display());
+    Ok(())
+}
+
+fn conformance(command: &ConformanceCommand) -> Result<()> {
+    match command {
+        ConformanceCommand::Generate { file } => {
+            let suite = conformance::generate()?;
+            suite.save(file)?;
+            println!("✅ Wrote {} test vectors to {}", suite.vectors.len(), file.display());
+        }
+        ConformanceCommand::Check { file } => {
+            let suite = VectorSuite::load(file)?;
+            if suite.version != conformance::SUITE_VERSION {
+                warn!("⚠️  Vectors are version {}, this build generates version {}", suite.version, conformance::SUITE_VERSION);
+            }
+            let report = conformance::validate(&suite, &ReferenceTarget);
+            for failure in &report.failures {
+                match &failure.actual {
+                    Ok(actual) => println!("❌ {}: expected {}, got {}", failure.name, hex::encode(&failure.expected), hex::encode(actual)),
+                    Err(e) => println!("❌ {}: {}", failure.name, e),
+                }
+            }
+            if !report.is_conformant() {
+                bail!("{} of {} vectors failed", report.failures.len(), suite.vectors.len());
+            }
+            println!("✅ All {} vectors match", report.passed);
+        }
+    }
+    Ok(())
+}
+
+/// Engine at genesis with every stored block replayed
+async fn open_chain(config: &NodeConfig) -> Result<(ZkSacConsensusEngine, BlockLog)> {
+    let genesis_path = config.storage.genesis_path();
+    let genesis = GenesisConfig::load(&genesis_path)
+        .context("Run `zk-sac-node init` to create a genesis file")?;
+    let protocol = genesis.protocol_config(config.protocol_config());
+    let log = BlockLog::new(config.storage.block_log_path(), DecodeLimits::from_protocol(&protocol));
+
+    let mut engine = ZkSacConsensusEngine::new(genesis.world_state(), genesis.validators(), protocol)?;
+    let blocks = log.read_all().await?;
+    info!("📚 Replaying {} stored blocks", blocks.len());
+    for block in blocks {
+        apply_checked(&mut engine, block).await?;
+    }
+    Ok((engine, log))
+}
+
+async fn apply_checked(engine: &mut ZkSacConsensusEngine, block: Block) -> Result<()> {
+    let number = block.header.block_number;
+    if !engine.validate_block(&block).await? {
+        bail!("Block {} failed validation", number);
+    }
+    Ok(engine.apply_block(block).await?)
+}
+
+/// Sign for every validator whose key is in the keystore; the password is
+/// only asked for when there is such a key
+fn unlock_validator_keys(config: &NodeConfig, engine: &mut ZkSacConsensusEngine, password: &PasswordArgs) -> Result<usize> {
+    let keystore = Keystore::open(config.storage.keystore_dir())?;
+    let entries: Vec<_> = keystore.list()?.into_iter()
+        .filter(|entry| engine.validator_set.validators.iter().any(|v| v.address == entry.address))
+        .collect();
+    if entries.is_empty() {
+        return Ok(0);
+    }
+    let password = password.read()?;
+    for entry in &entries {
+        engine.add_validator_key(keystore.unlock(&entry.address, &password)?)?;
+        if keystore.has_bls_key(&entry.address) {
+            engine.add_validator_bls_key(entry.address, keystore.unlock_bls(&entry.address, &password)?)?;
+        }
+    }
+    Ok(entries.len())
+}
+
+async fn run(config: NodeConfig, password: &PasswordArgs) -> Result<()> {
+    let (mut engine, log) = open_chain(&config).await?;
+    info!("🌐 Network: {} ({} bootnodes)", config.network.listen_addr, config.network.bootnodes.len());
+    if !config.consensus.produce_blocks {
+        warn!("⏸️  Block production is disabled");
+    } else if unlock_validator_keys(&config, &mut engine, password)? == 0 {
+        warn!("⏸️  No validator key in {}, not producing blocks", config.storage.keystore_dir().display());
+    }
+    let da = availability_gate(&config)?;
+    let indexer = start_indexer(&config, &engine)?;
+    let events = engine.events.clone();
+    let engine = Arc::new(Mutex::new(engine));
+    let rpc = start_rpc(&config, &engine, events).await?;
+
+    let mut ticker = tokio::time::interval(config.consensus.block_time);
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = ticker.tick() => {
+                if !config.consensus.produce_blocks {
+                    continue;
+                }
+                let mut engine = engine.lock().await;
+                let producer = engine.select_block_producer(engine.blocks.len() as u64 + 1)?;
+                if !engine.holds_validator_key(&producer) {
+                    continue;
+                }
+                let block = match engine.produce_block(producer).await {
+                    Ok(block) => block,
+                    Err(e) => {
+                        warn!("❌ Failed to produce block, retrying next slot: {:#}", e);
+                        continue;
+                    }
+                };
+                if !engine.validate_block(&block).await? {
+                    warn!("❌ Produced block {} failed validation, skipping", block.header.block_number);
+                    continue;
+                }
+                if let Some(gate) = &da {
+                    if let Err(e) = gate.publish(&block).await {
+                        warn!("❌ Block {} is not available, not finalizing: {:#}", block.header.block_number, e);
+                        continue;
+                    }
+                }
+                // Unpersisted blocks are not applied; the slot is produced again
+                if let Err(e) = log.append(&block).await {
+                    warn!("❌ Failed to persist block {}, retrying next slot: {:#}", block.header.block_number, e);
+                    continue;
+                }
+                engine.apply_block(block).await?;
+            }
+        }
+    }
+
+    // RPC requests in flight need the engine to finish
+    if let Some((stop, tasks)) = rpc {
+        stop.cancel();
+        for task in tasks {
+            task.await??;
+        }
+    }
+    let engine = engine.lock().await;
+    info!("🛑 Shutting down at height {}", engine.blocks.len());
+    if let Some((stop, task)) = indexer {
+        stop.cancel();
+        task.await??;
+    }
+    engine.shutdown(Duration::from_secs(10)).await
+}
+
+type IndexerTask = (CancellationToken, JoinHandle<Result<()>>);
+
+/// JSON-RPC over HTTP and subscriptions over WebSocket, when `[rpc]` is enabled
+async fn start_rpc(
+    config: &NodeConfig,
+    engine: &Arc<Mutex<ZkSacConsensusEngine>>,
+    events: EventBus,
+) -> Result<Option<(CancellationToken, Vec<JoinHandle<Result<()>>>)>> {
+    if !config.rpc.enabled {
+        return Ok(None);
+    }
+    let http = EthRpcServer::bind(config.rpc.listen_addr, EthApi::new(engine.clone()).await).await
+        .with_context(|| format!("Failed to listen on {}", config.rpc.listen_addr))?;
+    let ws = SubscriptionServer::bind(config.rpc.ws_listen_addr, events).await
+        .with_context(|| format!("Failed to listen on {}", config.rpc.ws_listen_addr))?;
+    let stop = CancellationToken::new();
+    let tasks = vec![tokio::spawn(http.run(stop.clone())), tokio::spawn(ws.run(stop.clone()))];
+    Ok(Some((stop, tasks)))
+}
+
+#[cfg(feature = "indexer")]
+fn start_indexer(config: &NodeConfig, engine: &ZkSacConsensusEngine) -> Result<Option<IndexerTask>> {
+    use zk_sac_engine::indexer::{Indexer, IndexerConfig};
+
+    if !config.indexer.enabled {
+        return Ok(None);
+    }
+    let mut settings = IndexerConfig::new(config.storage.index_path());
+    if config.indexer.export_parquet {
+        settings = settings.with_parquet_dir(config.storage.parquet_dir());
+    }
+    let indexer = Indexer::new(settings, &engine.events)?;
+    let stop = CancellationToken::new();
+    let task = tokio::spawn({
+        let stop = stop.clone();
+        async move { indexer.run(stop).await.map(|_| ()) }
+    });
+    Ok(Some((stop, task)))
+}
+
+#[cfg(not(feature = "indexer"))]
+fn start_indexer(config: &NodeConfig, _engine: &ZkSacConsensusEngine) -> Result<Option<IndexerTask>> {
+    if config.indexer.enabled {
+        warn!("⚠️  [indexer] is enabled but this binary was built without the `indexer` feature");
+    }
+    Ok(None)
+}
+
+/// DA gate blocks must pass before they are finalized, in rollup mode
+fn availability_gate(config: &NodeConfig) -> Result<Option<AvailabilityGate>> {
+    let settings = &config.da;
+    match settings.mode {
+        DaMode::Disabled => Ok(None),
+        DaMode::Celestia => {
+            let id = hex::decode(&settings.namespace).context("[da] namespace must be hex")?;
+            let mut celestia = CelestiaDa::new(&settings.endpoint, Namespace::v0(&id)?);
+            if !settings.auth_token.is_empty() {
+                celestia = celestia.with_auth_token(&settings.auth_token);
+            }
+            info!("📦 Rollup mode: publishing blocks to Celestia at {}", settings.endpoint);
+            let config = DaConfig::default().with_availability_timeout(settings.availability_timeout);
+            Ok(Some(AvailabilityGate::new(Arc::new(celestia), config)))
+        }
+    }
+}
+
+async fn import(config: NodeConfig, file: &Path) -> Result<()> {
+    let (mut engine, log) = open_chain(&config).await?;
+    let limits = DecodeLimits::from_protocol(&engine.protocol_config);
+    let height = engine.blocks.len() as u64;
+
+    let mut imported = 0;
+    for block in read_blocks(file, limits).await? {
+        if block.header.block_number <= height {
+            continue;
+        }
+        let number = block.header.block_number;
+        if !engine.validate_block(&block).await? {
+            bail!("Block {} from {} failed validation", number, file.display());
+        }
+        log.append(&block).await?;
+        engine.apply_block(block).await?;
+        imported += 1;
+    }
+
+    println!("✅ Imported {} blocks, height is now {}", imported, engine.blocks.len());
+    Ok(())
+}
+
+async fn export(config: NodeConfig, file: &Path, from: Option<u64>, to: Option<u64>) -> Result<()> {
+    let (engine, _) = open_chain(&config).await?;
+    let range = from.unwrap_or(0)..=to.unwrap_or(u64::MAX);
+    let blocks = engine.blocks.iter().filter(|block| range.contains(&block.header.block_number));
+
+    let written = write_blocks(file, blocks).await?;
+    println!("✅ Exported {} blocks to {}", written, file.display());
+    Ok(())
+}
+
+async fn devnet(config: NodeConfig, validators: usize, blocks: Option<u64>) -> Result<()> {
+    let devnet_config = DevnetConfig::default()
+        .with_validators(validators)
+        .with_block_time(config.consensus.block_time);
+    let devnet = Devnet::start(devnet_config)?;
+
+    let mut ticker = tokio::time::interval(config.consensus.block_time);
+    let shutdown = tokio::signal::ctrl_c();
+    tokio::pin!(shutdown);
+
+    loop {
+        tokio::select! {
+            _ = &mut shutdown => break,
+            _ = ticker.tick() => {
+                let mut heights = Vec::with_capacity(devnet.nodes().len());
+                for node in devnet.nodes() {
+                    heights.push(node.height().await);
+                }
+                println!("📊 Heights: {:?}", heights);
+                if blocks.is_some_and(|target| heights.iter().all(|&height| height >= target)) {
+                    break;
+                }
+            }
+        }
+    }
+    devnet.shutdown().await
+}
+
+fn keys(config: &NodeConfig, password: &PasswordArgs, command: KeysCommand) -> Result<()> {
+    let keystore = Keystore::open(config.storage.keystore_dir())?;
+    match command {
+        KeysCommand::Generate { key_type } => {
+            let key = KeyPair::generate(key_type.into());
+            store_key(&keystore, &key, &password.read()?)
+        }
+        KeysCommand::Import { key_type, secret_file } => {
+            let text = std::fs::read_to_string(&secret_file)
+                .with_context(|| format!("Failed to read {}", secret_file.display()))?;
+            let secret = hex::decode(text.trim().trim_start_matches("0x"))
+                .context("Secret key file must contain hex")?;
+            let key = KeyPair::from_secret(key_type.into(), &secret)?;
+            store_key(&keystore, &key, &password.read()?)
+        }
+        KeysCommand::List => {
+            for entry in keystore.list()? {
+                println!("🔑 {}  {:?}  public key {}",
+                         hex_utils::hash_to_hex_prefixed(&entry.address.0),
+                         entry.sig_type,
+                         hex_utils::hash_to_hex(&entry.public_key));
+            }
+            Ok(())
+        }
+        KeysCommand::Register { address, stake } => {
+            let key = keystore.unlock(&hex_utils::parse_address(&address)?, &password.read()?)?;
+            let registration = ValidatorRegistration::new(&key, stake)?;
+            println!("{}", serde_json::to_string_pretty(&registration)?);
+            Ok(())
+        }
+        KeysCommand::Rotate { address, key_type, epoch } => {
+            let password = password.read()?;
+            let old_key = keystore.unlock(&hex_utils::parse_address(&address)?, &password)?;
+            let new_key = KeyPair::generate(key_type.into());
+            let rotation = KeyRotation::new(&old_key, &new_key, epoch)?;
+            keystore.insert(&new_key, &password)?;
+            println!("{}", serde_json::to_string_pretty(&rotation)?);
+            Ok(())
+        }
+        KeysCommand::Bls { address } => {
+            let password = password.read()?;
+            let validator = hex_utils::parse_address(&address)?;
+            keystore.unlock(&validator, &password)?;
+            let entry = keystore.insert_bls(&validator, &BlsKeyPair::generate(), &password)?;
+            println!("✅ Stored BLS vote key for {}", hex_utils::hash_to_hex_prefixed(&validator.0));
+            println!("   bls public key: {}", hex_utils::hash_to_hex(&entry.public_key));
+            Ok(())
+        }
+    }
+}
+
+fn store_key(keystore: &Keystore, key: &KeyPair, password: &str) -> Result<()> {
+    let entry = keystore.insert(key, password)?;
+    println!("✅ Stored {:?} key", entry.sig_type);
+    println!("   address:    {}", hex_utils::hash_to_hex_prefixed(&entry.address.0));
+    println!("   public key: {}", hex_utils::hash_to_hex(&entry.public_key));
+    Ok(())
+}
@@ -0,0 +1,232 @@
+//! Ethereum JSON-RPC settlement layer
+//!
+//! Talks to `contracts/ZkSacSettlement.sol` through a node that holds the
+//! submitter account (`eth_sendTransaction`), such as a local geth/anvil or
+//! a signing proxy in front of a remote endpoint.
+
+use super::{DepositMessage, L1Address, L1TxHash, L1TxStatus, SettlementLayer, StateCheckpoint};
+use crate::crypto::hash::keccak256_hash;
+use crate::types::Address;
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+const SUBMIT_SIGNATURE: &str = "submitCheckpoint(uint64,bytes32,bytes32,bytes32,bytes)";
+const DEPOSIT_EVENT: &str = "Deposited(uint64,address,bytes20,uint256)";
+
+fn selector(signature: &str) -> [u8; 4] {
+    let hash = keccak256_hash(signature.as_bytes());
+    [hash[0], hash[1], hash[2], hash[3]]
+}
+
+fn word_u64(value: u64) -> [u8; 32] {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    word
+}
+
+/// Calldata for `submitCheckpoint`
+pub fn encode_submit_checkpoint(checkpoint: &StateCheckpoint) -> Vec<u8> {
+    let seal = &checkpoint.proof.seal;
+    let mut data = selector(SUBMIT_SIGNATURE).to_vec();
+    data.extend_from_slice(&word_u64(checkpoint.block_number));
+    data.extend_from_slice(&checkpoint.block_hash.0);
+    data.extend_from_slice(&checkpoint.state_root.0);
+    data.extend_from_slice(&checkpoint.withdrawals_root.0);
+    // Offset of the dynamic `bytes` argument, after the five head words
+    data.extend_from_slice(&word_u64(5 * 32));
+    data.extend_from_slice(&word_u64(seal.len() as u64));
+    data.extend_from_slice(seal);
+    data.resize(data.len() + (32 - seal.len() % 32) % 32, 0);
+    data
+}
+
+fn parse_quantity(value: &Value) -> Result<u64> {
+    let text = value.as_str().ok_or_else(|| anyhow!("Expected a hex quantity, got {}", value))?;
+    u64::from_str_radix(text.trim_start_matches("0x"), 16)
+        .with_context(|| format!("Invalid hex quantity {}", text))
+}
+
+fn parse_bytes(value: &Value) -> Result<Vec<u8>> {
+    let text = value.as_str().ok_or_else(|| anyhow!("Expected hex data, got {}", value))?;
+    hex::decode(text.trim_start_matches("0x")).with_context(|| format!("Invalid hex data {}", text))
+}
+
+/// Low 8 bytes of a big-endian word, rejecting larger values
+fn word_to_u64(word: &[u8]) -> Result<u64> {
+    if word.len() != 32 || word[..24].iter().any(|&byte| byte != 0) {
+        bail!("Value does not fit in 64 bits");
+    }
+    Ok(u64::from_be_bytes(word[24..].try_into().expect("8 bytes")))
+}
+
+/// Decode a `Deposited` log
+fn decode_deposit(log: &Value) -> Result<DepositMessage> {
+    let topics = log["topics"].as_array().ok_or_else(|| anyhow!("Log without topics"))?;
+    if topics.len() != 3 {
+        bail!("Deposited log has {} topics, expected 3", topics.len());
+    }
+    let nonce = word_to_u64(&parse_bytes(&topics[1])?)?;
+    let sender_word = parse_bytes(&topics[2])?;
+    let data = parse_bytes(&log["data"])?;
+    if sender_word.len() != 32 || data.len() != 64 {
+        bail!("Malformed Deposited log");
+    }
+
+    let mut l1_sender: L1Address = [0; 20];
+    l1_sender.copy_from_slice(&sender_word[12..]);
+    // bytes20 is left-aligned in its word
+    let mut recipient = [0u8; 20];
+    recipient.copy_from_slice(&data[..20]);
+    Ok(DepositMessage {
+        nonce,
+        l1_sender,
+        recipient: Address(recipient),
+        amount: word_to_u64(&data[32..])?,
+    })
+}
+
+pub struct EthereumSettlement {
+    rpc_url: String,
+    contract: L1Address,
+    submitter: L1Address,
+    /// L1 block the contract was deployed in; log queries start here
+    deployment_block: u64,
+    client: reqwest::Client,
+    next_id: AtomicU64,
+}
+
+impl EthereumSettlement {
+    pub fn new(rpc_url: impl Into<String>, contract: L1Address, submitter: L1Address) -> Self {
+        Self {
+            rpc_url: rpc_url.into(),
+            contract,
+            submitter,
+            deployment_block: 0,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_default(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    pub fn with_deployment_block(mut self, block: u64) -> Self {
+        self.deployment_block = block;
+        self
+    }
+
+    async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<T> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let response: Value = self.client.post(&self.rpc_url)
+            .json(&request)
+            .send()
+            .await
+            .with_context(|| format!("{} request to {} failed", method, self.rpc_url))?
+            .error_for_status()?
+            .json()
+            .await?;
+
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error["message"].as_str().unwrap_or("unknown error"));
+        }
+        serde_json::from_value(response["result"].clone())
+            .with_context(|| format!("Unexpected {} result", method))
+    }
+
+    async fn block_number(&self) -> Result<u64> {
+        parse_quantity(&self.call::<Value>("eth_blockNumber", json!([])).await?)
+    }
+}
+
+#[async_trait]
+impl SettlementLayer for EthereumSettlement {
+    async fn submit_checkpoint(&self, checkpoint: &StateCheckpoint) -> Result<L1TxHash> {
+        let tx = json!({
+            "from": format!("0x{}", hex::encode(self.submitter)),
+            "to": format!("0x{}", hex::encode(self.contract)),
+            "data": format!("0x{}", hex::encode(encode_submit_checkpoint(checkpoint))),
+        });
+        let hash = parse_bytes(&self.call::<Value>("eth_sendTransaction", json!([tx])).await?)?;
+        hash.try_into().map_err(|_| anyhow!("eth_sendTransaction returned a malformed hash"))
+    }
+
+    async fn transaction_status(&self, tx: &L1TxHash) -> Result<L1TxStatus> {
+        let receipt: Value = self.call("eth_getTransactionReceipt", json!([format!("0x{}", hex::encode(tx))])).await?;
+        if receipt.is_null() || receipt["blockNumber"].is_null() {
+            return Ok(L1TxStatus::Pending);
+        }
+        if parse_quantity(&receipt["status"])? == 0 {
+            return Ok(L1TxStatus::Reverted);
+        }
+        let included_in = parse_quantity(&receipt["blockNumber"])?;
+        let head = self.block_number().await?;
+        Ok(L1TxStatus::Mined { confirmations: head.saturating_sub(included_in) + 1 })
+    }
+
+    async fn deposits_since(&self, from_nonce: u64) -> Result<Vec<DepositMessage>> {
+        let filter = json!({
+            "address": format!("0x{}", hex::encode(self.contract)),
+            "fromBlock": format!("0x{:x}", self.deployment_block),
+            "toBlock": "latest",
+            "topics": [format!("0x{}", hex::encode(keccak256_hash(DEPOSIT_EVENT.as_bytes())))],
+        });
+        // Topic filters only match exact nonces, so older deposits are dropped here
+        let logs: Vec<Value> = self.call("eth_getLogs", json!([filter])).await?;
+        let mut deposits = logs.iter()
+            .map(decode_deposit)
+            .collect::<Result<Vec<_>>>()?;
+        deposits.retain(|deposit| deposit.nonce >= from_nonce);
+        deposits.sort_by_key(|deposit| deposit.nonce);
+        Ok(deposits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::WrappedProof;
+    use crate::types::BlockHash;
+
+    #[test]
+    fn test_submit_calldata_layout() {
+        let checkpoint = StateCheckpoint {
+            block_number: 64,
+            block_hash: BlockHash([1; 32]),
+            state_root: BlockHash([2; 32]),
+            withdrawals_root: BlockHash([3; 32]),
+            proof: WrappedProof { seal: vec![0xab; 33], image_id: [0; 32] },
+        };
+        let data = encode_submit_checkpoint(&checkpoint);
+        assert_eq!(&data[..4], &selector(SUBMIT_SIGNATURE));
+        assert_eq!(data.len(), 4 + 5 * 32 + 32 + 64);
+        assert_eq!(word_to_u64(&data[4..36]).unwrap(), 64);
+        assert_eq!(word_to_u64(&data[4 + 4 * 32..4 + 5 * 32]).unwrap(), 160);
+        assert_eq!(word_to_u64(&data[4 + 5 * 32..4 + 6 * 32]).unwrap(), 33);
+    }
+
+    #[test]
+    fn test_decode_deposit_log() {
+        let mut data = vec![0x11; 20];
+        data.resize(32, 0);
+        data.extend_from_slice(&word_u64(750));
+        let log = json!({
+            "topics": [
+                format!("0x{}", hex::encode(keccak256_hash(DEPOSIT_EVENT.as_bytes()))),
+                format!("0x{}", hex::encode(word_u64(3))),
+                format!("0x{}{}", "00".repeat(12), "22".repeat(20)),
+            ],
+            "data": format!("0x{}", hex::encode(&data)),
+        });
+        let deposit = decode_deposit(&log).unwrap();
+        assert_eq!(deposit, DepositMessage { nonce: 3, l1_sender: [0x22; 20], recipient: Address([0x11; 20]), amount: 750 });
+    }
+}
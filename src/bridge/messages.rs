@@ -0,0 +1,205 @@
+//! Bridge transactions on this chain
+//!
+//! Both directions go through transactions to [`BRIDGE_ADDRESS`] carrying a
+//! [`BridgeAction`]:
+//!
+//! - A validator relays each L1 deposit as [`BridgeAction::Deposit`], which
+//!   mints the amount to the recipient. Deposits are applied strictly in L1
+//!   nonce order, so each is minted exactly once however many validators
+//!   relay it. Any validator may relay; the deposits are not yet checked
+//!   against an L1 commitment.
+//! - Any account exits with [`BridgeAction::Withdraw`], burning the
+//!   transaction value and recording a [`WithdrawalMessage`] under the next
+//!   exit nonce.
+//!
+//! The nonces and the exit records are storage of the bridge account, so
+//! the state root commits to them and [`exit_proof`] proves a withdrawal
+//! against any state root that includes it.
+
+use super::{DepositMessage, L1Address, WithdrawalMessage};
+use crate::crypto::hash::keccak256_hash;
+use crate::crypto::keystore::KeyPair;
+use crate::serialization::{DecodeLimits, PayloadKind, decode_bounded};
+use crate::state::{AccountDiff, StateOverlay, StorageProof, storage_proof};
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Recipient of bridge transactions, whose storage holds the exit records
+pub const BRIDGE_ADDRESS: Address = Address(*b"zk-sac/bridge\0\0\0\0\0\0\0");
+
+const DEPOSIT_NONCE_SLOT: [u8; 32] = [0; 32];
+const WITHDRAWAL_NONCE_SLOT: [u8; 32] = {
+    let mut slot = [0; 32];
+    slot[31] = 1;
+    slot
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BridgeError {
+    #[error("deposit {found} is out of order, the next is {expected}")]
+    DepositOutOfOrder { expected: u64, found: u64 },
+    #[error("deposits are relayed by validators, {0:?} is not one")]
+    UnauthorizedRelayer(Address),
+    #[error("a deposit transaction carries no value")]
+    DepositWithValue,
+    #[error("a withdrawal needs a value")]
+    EmptyWithdrawal,
+    #[error("malformed bridge action: {0}")]
+    Malformed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BridgeAction {
+    /// Mint an L1 deposit on this chain
+    Deposit(DepositMessage),
+    /// Burn the transaction value, to be claimed by `l1_recipient` on L1
+    Withdraw { l1_recipient: L1Address },
+}
+
+impl BridgeAction {
+    /// A transaction from `sender` performing this action, withdrawing `value`
+    pub fn to_transaction(&self, sender: &KeyPair, value: u64, nonce: u64) -> anyhow::Result<Transaction> {
+        let mut transaction = Transaction::new(sender.address(), BRIDGE_ADDRESS, value, nonce);
+        transaction.data = bincode::serialize(self)?;
+        transaction.gas_limit = GasSchedule::default().intrinsic_gas(&transaction);
+        transaction.signed(sender)
+    }
+
+    /// The action `transaction` carries, or `None` if it is not a bridge transaction
+    pub fn from_transaction(transaction: &Transaction) -> Option<Result<Self, BridgeError>> {
+        if transaction.to != BRIDGE_ADDRESS {
+            return None;
+        }
+        Some(decode_bounded(PayloadKind::Transaction, &transaction.data, &DecodeLimits::default())
+            .map_err(|e| BridgeError::Malformed(e.to_string())))
+    }
+
+    /// Mint the deposit or record the exit of `transaction` in `state`; the
+    /// caller moves the value. `state` is unchanged on error.
+    pub fn apply(&self, state: &mut StateOverlay, transaction: &Transaction) -> Result<(), BridgeError> {
+        match self {
+            BridgeAction::Deposit(deposit) => {
+                if transaction.value != 0 {
+                    return Err(BridgeError::DepositWithValue);
+                }
+                let expected = counter(state, &DEPOSIT_NONCE_SLOT);
+                if deposit.nonce != expected {
+                    return Err(BridgeError::DepositOutOfOrder { expected, found: deposit.nonce });
+                }
+                set_counter(state, DEPOSIT_NONCE_SLOT, expected + 1);
+                let recipient = state.account_mut(deposit.recipient);
+                recipient.balance = recipient.balance.saturating_add(deposit.amount);
+            }
+            BridgeAction::Withdraw { l1_recipient } => {
+                if transaction.value == 0 {
+                    return Err(BridgeError::EmptyWithdrawal);
+                }
+                let nonce = counter(state, &WITHDRAWAL_NONCE_SLOT);
+                let withdrawal = WithdrawalMessage { nonce, sender: transaction.from, l1_recipient: *l1_recipient, amount: transaction.value };
+                set_counter(state, WITHDRAWAL_NONCE_SLOT, nonce + 1);
+                bridge_account(state).storage.insert(exit_slot(nonce), withdrawal.leaf());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Storage slot of the exit record with `nonce`
+pub fn exit_slot(nonce: u64) -> [u8; 32] {
+    let mut bytes = b"exit".to_vec();
+    bytes.extend_from_slice(&nonce.to_be_bytes());
+    keccak256_hash(&bytes)
+}
+
+/// Nonce the next relayed deposit must have
+pub fn next_deposit_nonce(state: &WorldState) -> u64 {
+    counter(&StateOverlay::new(state), &DEPOSIT_NONCE_SLOT)
+}
+
+/// Nonce the next withdrawal is recorded under
+pub fn next_withdrawal_nonce(state: &WorldState) -> u64 {
+    counter(&StateOverlay::new(state), &WITHDRAWAL_NONCE_SLOT)
+}
+
+/// Proof that the exit record of `withdrawal` is in `state`; check it with
+/// [`verify_exit`]
+pub fn exit_proof(state: &WorldState, withdrawal: &WithdrawalMessage) -> Option<StorageProof> {
+    storage_proof(state, &BRIDGE_ADDRESS, &exit_slot(withdrawal.nonce))
+        .filter(|proof| proof.value == withdrawal.leaf())
+}
+
+/// Whether `proof` shows `withdrawal` recorded under `state_root`
+pub fn verify_exit(state_root: &BlockHash, withdrawal: &WithdrawalMessage, proof: &StorageProof) -> bool {
+    proof.account.address == BRIDGE_ADDRESS
+        && proof.slot == exit_slot(withdrawal.nonce)
+        && proof.value == withdrawal.leaf()
+        && proof.verify(state_root).is_ok()
+}
+
+fn counter(state: &StateOverlay, slot: &[u8; 32]) -> u64 {
+    u64::from_be_bytes(state.storage(&BRIDGE_ADDRESS, slot)[24..].try_into().expect("8 bytes"))
+}
+
+fn set_counter(state: &mut StateOverlay, slot: [u8; 32], value: u64) {
+    let mut word = [0u8; 32];
+    word[24..].copy_from_slice(&value.to_be_bytes());
+    bridge_account(state).storage.insert(slot, word);
+}
+
+fn bridge_account<'a>(state: &'a mut StateOverlay) -> &'a mut AccountDiff {
+    state.account_mut(BRIDGE_ADDRESS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deposit(nonce: u64) -> Transaction {
+        let action = BridgeAction::Deposit(DepositMessage { nonce, l1_sender: [1; 20], recipient: Address::new(7), amount: 500 });
+        let mut transaction = Transaction::new(Address::new(1), BRIDGE_ADDRESS, 0, nonce);
+        transaction.data = bincode::serialize(&action).unwrap();
+        transaction
+    }
+
+    fn apply(state: &mut WorldState, transaction: &Transaction) -> Result<(), BridgeError> {
+        let mut overlay = StateOverlay::new(state);
+        BridgeAction::from_transaction(transaction).unwrap()?.apply(&mut overlay, transaction)?;
+        let diff = overlay.into_diff();
+        state.apply_diff(&diff);
+        Ok(())
+    }
+
+    #[test]
+    fn test_deposits_mint_once_in_order() {
+        let mut state = WorldState::default();
+        apply(&mut state, &deposit(0)).unwrap();
+        assert_eq!(apply(&mut state, &deposit(0)), Err(BridgeError::DepositOutOfOrder { expected: 1, found: 0 }));
+        assert_eq!(apply(&mut state, &deposit(2)), Err(BridgeError::DepositOutOfOrder { expected: 1, found: 2 }));
+        apply(&mut state, &deposit(1)).unwrap();
+        assert_eq!(state.accounts[&Address::new(7)].balance, 1_000);
+        assert_eq!(next_deposit_nonce(&state), 2);
+    }
+
+    #[test]
+    fn test_exit_records_prove_against_the_state_root() {
+        let mut state = WorldState::default();
+        let mut withdrawals = Vec::new();
+        for (nonce, amount) in [(0, 40), (1, 60)] {
+            let mut transaction = Transaction::new(Address::new(3), BRIDGE_ADDRESS, amount, nonce);
+            transaction.data = bincode::serialize(&BridgeAction::Withdraw { l1_recipient: [9; 20] }).unwrap();
+            apply(&mut state, &transaction).unwrap();
+            withdrawals.push(WithdrawalMessage { nonce, sender: Address::new(3), l1_recipient: [9; 20], amount });
+        }
+        let root = state.compute_state_root();
+
+        for withdrawal in &withdrawals {
+            let proof = exit_proof(&state, withdrawal).unwrap();
+            assert!(verify_exit(&root, withdrawal, &proof));
+        }
+        let mut forged = withdrawals[1].clone();
+        forged.amount = 6_000;
+        assert!(exit_proof(&state, &forged).is_none());
+        assert!(!verify_exit(&root, &forged, &exit_proof(&state, &withdrawals[1]).unwrap()));
+    }
+}
@@ -0,0 +1,96 @@
+//! In-memory settlement layer for tests and the devnet
+
+use super::{DepositMessage, L1Address, L1TxHash, L1TxStatus, SettlementLayer, StateCheckpoint};
+use crate::crypto::hash::keccak256_hash;
+use crate::types::Address;
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+#[derive(Debug, Default)]
+struct MockL1 {
+    head: u64,
+    /// Block each transaction is included in, and whether it reverted
+    transactions: HashMap<L1TxHash, (u64, bool)>,
+    checkpoints: Vec<StateCheckpoint>,
+    deposits: Vec<DepositMessage>,
+    revert_next: bool,
+}
+
+/// Simulated L1: transactions are included in the next block, and blocks
+/// are only mined when [`MockSettlement::mine`] is called
+#[derive(Debug, Default)]
+pub struct MockSettlement {
+    inner: Mutex<MockL1>,
+}
+
+impl MockSettlement {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mine(&self, blocks: u64) {
+        self.inner.lock().head += blocks;
+    }
+
+    pub fn head(&self) -> u64 {
+        self.inner.lock().head
+    }
+
+    /// Make the next checkpoint submission revert
+    pub fn revert_next_submission(&self) {
+        self.inner.lock().revert_next = true;
+    }
+
+    /// Make an L1 deposit; returns its nonce
+    pub fn deposit(&self, l1_sender: L1Address, recipient: Address, amount: u64) -> u64 {
+        let mut l1 = self.inner.lock();
+        let nonce = l1.deposits.len() as u64;
+        l1.deposits.push(DepositMessage { nonce, l1_sender, recipient, amount });
+        nonce
+    }
+
+    /// Checkpoints accepted by the contract, in submission order
+    pub fn checkpoints(&self) -> Vec<StateCheckpoint> {
+        self.inner.lock().checkpoints.clone()
+    }
+}
+
+#[async_trait]
+impl SettlementLayer for MockSettlement {
+    async fn submit_checkpoint(&self, checkpoint: &StateCheckpoint) -> Result<L1TxHash> {
+        let mut l1 = self.inner.lock();
+        let reverted = std::mem::take(&mut l1.revert_next);
+        let mut preimage = checkpoint.journal();
+        preimage.extend_from_slice(&(l1.transactions.len() as u64).to_be_bytes());
+        let tx = keccak256_hash(&preimage);
+
+        let included_in = l1.head + 1;
+        l1.transactions.insert(tx, (included_in, reverted));
+        if !reverted {
+            l1.checkpoints.push(checkpoint.clone());
+        }
+        Ok(tx)
+    }
+
+    async fn transaction_status(&self, tx: &L1TxHash) -> Result<L1TxStatus> {
+        let l1 = self.inner.lock();
+        let &(included_in, reverted) = l1.transactions.get(tx)
+            .ok_or_else(|| anyhow!("Unknown L1 transaction 0x{}", hex::encode(tx)))?;
+        Ok(if l1.head < included_in {
+            L1TxStatus::Pending
+        } else if reverted {
+            L1TxStatus::Reverted
+        } else {
+            L1TxStatus::Mined { confirmations: l1.head - included_in + 1 }
+        })
+    }
+
+    async fn deposits_since(&self, from_nonce: u64) -> Result<Vec<DepositMessage>> {
+        Ok(self.inner.lock().deposits.iter()
+            .filter(|deposit| deposit.nonce >= from_nonce)
+            .cloned()
+            .collect())
+    }
+}
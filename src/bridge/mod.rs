@@ -0,0 +1,495 @@
+//! Settlement bridge to Ethereum
+//!
+//! Every `checkpoint_interval` finalized blocks the bridge builds a
+//! [`StateCheckpoint`] (block hash, state root, root of the withdrawals
+//! queued since the last checkpoint, and the block's proof wrapped for the
+//! on-chain verifier) and submits it through a [`SettlementLayer`]. Submitted
+//! checkpoints are tracked until they have enough L1 confirmations, and are
+//! resubmitted if the L1 transaction reverts. Deposits made on L1 are pulled
+//! into a queue the engine drains, relaying each as a deposit transaction
+//! (see [`messages`]); withdrawals are queued from the withdrawal
+//! transactions of finalized blocks. A [`BridgeStatus`]
+//! summary is republished after every change, for the RPC and anything else
+//! that needs to know how far the chain is settled.
+//!
+//! The matching contract is `contracts/ZkSacSettlement.sol`.
+
+pub mod ethereum;
+pub mod messages;
+pub mod mock;
+
+pub use ethereum::EthereumSettlement;
+pub use messages::{BRIDGE_ADDRESS, BridgeAction, BridgeError};
+pub use mock::MockSettlement;
+
+use crate::crypto::hash::keccak256_hash;
+use crate::types::{Address, Block, BlockHash};
+use anyhow::Result;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, watch};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+pub type L1TxHash = [u8; 32];
+/// 20-byte Ethereum account
+pub type L1Address = [u8; 20];
+
+/// Block proof wrapped for the on-chain verifier
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WrappedProof {
+    #[serde(with = "hex::serde")]
+    pub seal: Vec<u8>,
+    /// Guest program the proof is for
+    #[serde(with = "hex::serde")]
+    pub image_id: [u8; 32],
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateCheckpoint {
+    pub block_number: u64,
+    pub block_hash: BlockHash,
+    pub state_root: BlockHash,
+    pub withdrawals_root: BlockHash,
+    pub proof: WrappedProof,
+}
+
+impl StateCheckpoint {
+    /// `abi.encodePacked(blockNumber, blockHash, stateRoot, withdrawalsRoot)`,
+    /// the journal the contract hashes before calling the verifier
+    pub fn journal(&self) -> Vec<u8> {
+        let mut journal = Vec::with_capacity(8 + 3 * 32);
+        journal.extend_from_slice(&self.block_number.to_be_bytes());
+        journal.extend_from_slice(&self.block_hash.0);
+        journal.extend_from_slice(&self.state_root.0);
+        journal.extend_from_slice(&self.withdrawals_root.0);
+        journal
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DepositMessage {
+    pub nonce: u64,
+    pub l1_sender: L1Address,
+    pub recipient: Address,
+    pub amount: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WithdrawalMessage {
+    pub nonce: u64,
+    pub sender: Address,
+    pub l1_recipient: L1Address,
+    pub amount: u64,
+}
+
+impl WithdrawalMessage {
+    /// `keccak256(abi.encodePacked(nonce, sender, recipient, amount))` with
+    /// 64-bit nonce and amount
+    pub fn leaf(&self) -> [u8; 32] {
+        let mut bytes = Vec::with_capacity(8 + 20 + 20 + 8);
+        bytes.extend_from_slice(&self.nonce.to_be_bytes());
+        bytes.extend_from_slice(&self.sender.0);
+        bytes.extend_from_slice(&self.l1_recipient);
+        bytes.extend_from_slice(&self.amount.to_be_bytes());
+        keccak256_hash(&bytes)
+    }
+}
+
+/// Hash a pair in sorted order, as OpenZeppelin's `MerkleProof` expects
+fn hash_pair(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    let mut bytes = [0u8; 64];
+    bytes[..32].copy_from_slice(left);
+    bytes[32..].copy_from_slice(right);
+    keccak256_hash(&bytes)
+}
+
+fn merkle_levels(leaves: Vec<[u8; 32]>) -> Vec<Vec<[u8; 32]>> {
+    let mut levels = vec![leaves];
+    while levels.last().is_some_and(|level| level.len() > 1) {
+        let next = levels.last().expect("checked").chunks(2)
+            .map(|pair| match pair {
+                [left, right] => hash_pair(left, right),
+                [single] => *single,
+                _ => unreachable!("chunks(2)"),
+            })
+            .collect();
+        levels.push(next);
+    }
+    levels
+}
+
+/// Root of the withdrawal leaves; zero when there are none
+pub fn withdrawals_root(withdrawals: &[WithdrawalMessage]) -> BlockHash {
+    let levels = merkle_levels(withdrawals.iter().map(WithdrawalMessage::leaf).collect());
+    BlockHash(levels.last().and_then(|level| level.first()).copied().unwrap_or([0; 32]))
+}
+
+/// Sibling hashes proving `withdrawals[index]` against [`withdrawals_root`]
+pub fn withdrawal_proof(withdrawals: &[WithdrawalMessage], index: usize) -> Option<Vec<[u8; 32]>> {
+    if index >= withdrawals.len() {
+        return None;
+    }
+    let levels = merkle_levels(withdrawals.iter().map(WithdrawalMessage::leaf).collect());
+    let mut proof = Vec::new();
+    let mut index = index;
+    for level in &levels[..levels.len() - 1] {
+        if let Some(sibling) = level.get(index ^ 1) {
+            proof.push(*sibling);
+        }
+        index /= 2;
+    }
+    Some(proof)
+}
+
+pub fn verify_withdrawal(root: &BlockHash, withdrawal: &WithdrawalMessage, proof: &[[u8; 32]]) -> bool {
+    proof.iter().fold(withdrawal.leaf(), |node, sibling| hash_pair(&node, sibling)) == root.0
+}
+
+/// State of an L1 transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum L1TxStatus {
+    Pending,
+    Mined { confirmations: u64 },
+    Reverted,
+}
+
+/// The settlement contract on L1
+#[async_trait]
+pub trait SettlementLayer: Send + Sync {
+    async fn submit_checkpoint(&self, checkpoint: &StateCheckpoint) -> Result<L1TxHash>;
+    async fn transaction_status(&self, tx: &L1TxHash) -> Result<L1TxStatus>;
+    /// Deposits with nonce `>= from_nonce`, in nonce order
+    async fn deposits_since(&self, from_nonce: u64) -> Result<Vec<DepositMessage>>;
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CheckpointStatus {
+    Pending,
+    Submitted { tx: L1TxHash },
+    Confirmed { tx: L1TxHash, confirmations: u64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackedCheckpoint {
+    pub checkpoint: StateCheckpoint,
+    pub withdrawals: Vec<WithdrawalMessage>,
+    pub status: CheckpointStatus,
+}
+
+/// How far the chain is settled on L1
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BridgeStatus {
+    /// Newest checkpoint created, whatever its status
+    pub latest_checkpoint: Option<u64>,
+    /// Newest checkpoint with the required confirmations
+    pub latest_confirmed: Option<u64>,
+    /// State root the contract holds for `latest_confirmed`
+    pub confirmed_state_root: Option<BlockHash>,
+    /// Checkpoints created but not yet confirmed
+    pub unconfirmed: usize,
+    pub pending_withdrawals: usize,
+    pub queued_deposits: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct BridgeConfig {
+    /// Submit a checkpoint every this many finalized blocks
+    pub checkpoint_interval: u64,
+    pub required_confirmations: u64,
+    pub poll_interval: Duration,
+    /// Image id of the guest program the wrapped proofs are for
+    pub image_id: [u8; 32],
+}
+
+impl Default for BridgeConfig {
+    fn default() -> Self {
+        Self {
+            checkpoint_interval: 32,
+            required_confirmations: 12,
+            poll_interval: Duration::from_secs(12),
+            image_id: [0; 32],
+        }
+    }
+}
+
+impl BridgeConfig {
+    pub fn with_checkpoint_interval(mut self, blocks: u64) -> Self {
+        self.checkpoint_interval = blocks.max(1);
+        self
+    }
+
+    pub fn with_required_confirmations(mut self, confirmations: u64) -> Self {
+        self.required_confirmations = confirmations;
+        self
+    }
+
+    pub fn with_image_id(mut self, image_id: [u8; 32]) -> Self {
+        self.image_id = image_id;
+        self
+    }
+}
+
+pub struct SettlementBridge {
+    layer: Arc<dyn SettlementLayer>,
+    config: BridgeConfig,
+    checkpoints: BTreeMap<u64, TrackedCheckpoint>,
+    deposits: VecDeque<DepositMessage>,
+    next_deposit_nonce: u64,
+    withdrawals: Vec<WithdrawalMessage>,
+    next_withdrawal_nonce: u64,
+    status: watch::Sender<BridgeStatus>,
+}
+
+impl SettlementBridge {
+    pub fn new(layer: Arc<dyn SettlementLayer>, config: BridgeConfig) -> Self {
+        Self {
+            layer,
+            config,
+            checkpoints: BTreeMap::new(),
+            deposits: VecDeque::new(),
+            next_deposit_nonce: 0,
+            withdrawals: Vec::new(),
+            next_withdrawal_nonce: 0,
+            status: watch::channel(BridgeStatus::default()).0,
+        }
+    }
+
+    /// Summary updated after every checkpoint, poll and queue change
+    pub fn status(&self) -> watch::Receiver<BridgeStatus> {
+        self.status.subscribe()
+    }
+
+    fn report(&self) {
+        let confirmed = self.checkpoints.values().rev()
+            .find(|tracked| matches!(tracked.status, CheckpointStatus::Confirmed { .. }));
+        self.status.send_replace(BridgeStatus {
+            latest_checkpoint: self.checkpoints.keys().next_back().copied(),
+            latest_confirmed: confirmed.map(|tracked| tracked.checkpoint.block_number),
+            confirmed_state_root: confirmed.map(|tracked| tracked.checkpoint.state_root),
+            unconfirmed: self.checkpoints.values()
+                .filter(|tracked| !matches!(tracked.status, CheckpointStatus::Confirmed { .. }))
+                .count(),
+            pending_withdrawals: self.withdrawals.len(),
+            queued_deposits: self.deposits.len(),
+        });
+    }
+
+    /// Queue a withdrawal for the next checkpoint; returns its nonce
+    pub fn queue_withdrawal(&mut self, sender: Address, l1_recipient: L1Address, amount: u64) -> u64 {
+        let nonce = self.next_withdrawal_nonce;
+        self.next_withdrawal_nonce += 1;
+        self.withdrawals.push(WithdrawalMessage { nonce, sender, l1_recipient, amount });
+        self.report();
+        nonce
+    }
+
+    /// Record a finalized block, queueing its withdrawals and creating a
+    /// checkpoint on interval boundaries. Blocks must come in order from
+    /// genesis, so withdrawals get the nonces the chain recorded them under.
+    pub fn on_finalized_block(&mut self, block: &Block) -> Option<u64> {
+        for transaction in &block.transactions {
+            if let Some(Ok(BridgeAction::Withdraw { l1_recipient })) = BridgeAction::from_transaction(transaction) {
+                self.queue_withdrawal(transaction.from, l1_recipient, transaction.value);
+            }
+        }
+        let number = block.header.block_number;
+        if number == 0 || !number.is_multiple_of(self.config.checkpoint_interval) {
+            return None;
+        }
+        let withdrawals = std::mem::take(&mut self.withdrawals);
+        let checkpoint = StateCheckpoint {
+            block_number: number,
+            block_hash: block.header.hash(),
+            state_root: block.header.state_root,
+            withdrawals_root: withdrawals_root(&withdrawals),
+            proof: WrappedProof {
+                seal: block.recursive_proof.proof_data.clone(),
+                image_id: self.config.image_id,
+            },
+        };
+        info!("🌉 Checkpoint for block {} with {} withdrawals", number, withdrawals.len());
+        self.checkpoints.insert(number, TrackedCheckpoint { checkpoint, withdrawals, status: CheckpointStatus::Pending });
+        self.report();
+        Some(number)
+    }
+
+    /// Submit pending checkpoints, advance confirmations and pull new deposits
+    pub async fn poll(&mut self) -> Result<()> {
+        let outcome = self.poll_layer().await;
+        self.report();
+        outcome
+    }
+
+    async fn poll_layer(&mut self) -> Result<()> {
+        let required = self.config.required_confirmations;
+        for (number, tracked) in self.checkpoints.iter_mut() {
+            match tracked.status {
+                CheckpointStatus::Pending => {
+                    let tx = self.layer.submit_checkpoint(&tracked.checkpoint).await?;
+                    info!("📤 Submitted checkpoint {} in L1 tx 0x{}", number, hex::encode(tx));
+                    tracked.status = CheckpointStatus::Submitted { tx };
+                }
+                CheckpointStatus::Submitted { tx } | CheckpointStatus::Confirmed { tx, .. } => {
+                    match self.layer.transaction_status(&tx).await? {
+                        L1TxStatus::Pending => {}
+                        L1TxStatus::Mined { confirmations } if confirmations >= required => {
+                            if matches!(tracked.status, CheckpointStatus::Submitted { .. }) {
+                                info!("✅ Checkpoint {} confirmed on L1", number);
+                            }
+                            tracked.status = CheckpointStatus::Confirmed { tx, confirmations };
+                        }
+                        L1TxStatus::Mined { .. } => tracked.status = CheckpointStatus::Submitted { tx },
+                        L1TxStatus::Reverted => {
+                            warn!("❌ Checkpoint {} reverted on L1, resubmitting", number);
+                            tracked.status = CheckpointStatus::Pending;
+                        }
+                    }
+                }
+            }
+        }
+
+        for deposit in self.layer.deposits_since(self.next_deposit_nonce).await? {
+            if deposit.nonce < self.next_deposit_nonce {
+                continue;
+            }
+            self.next_deposit_nonce = deposit.nonce + 1;
+            self.deposits.push_back(deposit);
+        }
+        Ok(())
+    }
+
+    /// Up to `max` deposits for the engine to credit, oldest first
+    pub fn take_deposits(&mut self, max: usize) -> Vec<DepositMessage> {
+        let count = max.min(self.deposits.len());
+        let deposits = self.deposits.drain(..count).collect();
+        self.report();
+        deposits
+    }
+
+    pub fn pending_withdrawals(&self) -> &[WithdrawalMessage] {
+        &self.withdrawals
+    }
+
+    pub fn checkpoint(&self, block_number: u64) -> Option<&TrackedCheckpoint> {
+        self.checkpoints.get(&block_number)
+    }
+
+    /// Proof a user submits on L1 to claim withdrawal `nonce`
+    pub fn withdrawal_claim(&self, nonce: u64) -> Option<(u64, WithdrawalMessage, Vec<[u8; 32]>)> {
+        self.checkpoints.iter().find_map(|(number, tracked)| {
+            let index = tracked.withdrawals.iter().position(|withdrawal| withdrawal.nonce == nonce)?;
+            let proof = withdrawal_proof(&tracked.withdrawals, index)?;
+            Some((*number, tracked.withdrawals[index].clone(), proof))
+        })
+    }
+
+    /// Feed finalized blocks from `finalized` and poll L1 every `poll_interval`
+    pub async fn run(mut self, mut finalized: mpsc::Receiver<Block>, shutdown: CancellationToken) -> Result<()> {
+        let mut ticker = tokio::time::interval(self.config.poll_interval);
+        loop {
+            tokio::select! {
+                _ = shutdown.cancelled() => return Ok(()),
+                block = finalized.recv() => match block {
+                    Some(block) => {
+                        self.on_finalized_block(&block);
+                    }
+                    None => return Ok(()),
+                },
+                _ = ticker.tick() => {
+                    if let Err(e) = self.poll().await {
+                        warn!("⚠️  Settlement poll failed: {:#}", e);
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{BlockHeader, ProofType, RandomnessProof, ZkProof};
+
+    fn block(number: u64) -> Block {
+        Block {
+            header: BlockHeader {
+                previous_hash: BlockHash::zero(),
+                merkle_root: BlockHash::zero(),
+                prev_state_root: BlockHash::zero(),
+                state_root: BlockHash([number as u8; 32]),
+                timestamp: 0,
+                block_number: number,
+                gas_limit: 0,
+                gas_used: 0,
+                producer: Address::new(1),
+                extra_data: Vec::new(),
+                randomness: RandomnessProof::default(),
+            },
+            transactions: Vec::new(),
+            validator_signatures: Vec::new(),
+            recursive_proof: ZkProof { proof_data: vec![9; 32], public_inputs: vec![], verification_key: vec![], proof_type: ProofType::Risc0 },
+            protocol_updates: Vec::new(),
+            proof_signature: Vec::new(),
+            aggregate_signature: None,
+        }
+    }
+
+    #[test]
+    fn test_withdrawal_proofs_verify() {
+        let withdrawals: Vec<WithdrawalMessage> = (0..5)
+            .map(|nonce| WithdrawalMessage { nonce, sender: Address::new(1), l1_recipient: [2; 20], amount: 10 + nonce })
+            .collect();
+        let root = withdrawals_root(&withdrawals);
+        for (index, withdrawal) in withdrawals.iter().enumerate() {
+            let proof = withdrawal_proof(&withdrawals, index).unwrap();
+            assert!(verify_withdrawal(&root, withdrawal, &proof));
+        }
+        let mut forged = withdrawals[0].clone();
+        forged.amount = 1_000;
+        assert!(!verify_withdrawal(&root, &forged, &withdrawal_proof(&withdrawals, 0).unwrap()));
+    }
+
+    #[tokio::test]
+    async fn test_checkpoints_confirm_and_resubmit_after_revert() {
+        let l1 = Arc::new(MockSettlement::new());
+        let config = BridgeConfig::default().with_checkpoint_interval(4).with_required_confirmations(3);
+        let mut bridge = SettlementBridge::new(l1.clone(), config);
+        let status = bridge.status();
+
+        let nonce = bridge.queue_withdrawal(Address::new(5), [7; 20], 250);
+        for number in 1..=4 {
+            bridge.on_finalized_block(&block(number));
+        }
+        l1.deposit([1; 20], Address::new(9), 500);
+
+        l1.revert_next_submission();
+        bridge.poll().await.unwrap();
+        l1.mine(1);
+        bridge.poll().await.unwrap();
+        assert_eq!(bridge.checkpoint(4).unwrap().status, CheckpointStatus::Pending);
+        assert_eq!(status.borrow().latest_checkpoint, Some(4));
+        assert_eq!(status.borrow().latest_confirmed, None);
+
+        bridge.poll().await.unwrap();
+        l1.mine(3);
+        bridge.poll().await.unwrap();
+        assert!(matches!(bridge.checkpoint(4).unwrap().status, CheckpointStatus::Confirmed { confirmations: 3, .. }));
+        assert_eq!(l1.checkpoints().last().unwrap().block_number, 4);
+        assert_eq!(status.borrow().latest_confirmed, Some(4));
+        assert_eq!(status.borrow().confirmed_state_root, Some(block(4).header.state_root));
+        assert_eq!((status.borrow().unconfirmed, status.borrow().queued_deposits), (0, 1));
+
+        let deposits = bridge.take_deposits(10);
+        assert_eq!(deposits.len(), 1);
+        assert_eq!(deposits[0].amount, 500);
+
+        let (checkpoint, withdrawal, proof) = bridge.withdrawal_claim(nonce).unwrap();
+        let root = bridge.checkpoint(checkpoint).unwrap().checkpoint.withdrawals_root;
+        assert!(verify_withdrawal(&root, &withdrawal, &proof));
+    }
+}
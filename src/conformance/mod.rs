@@ -0,0 +1,274 @@
+//! Conformance test vectors
+//!
+//! [`generate`] evaluates fixed inputs (blocks, transactions, world states
+//! and guest inputs) with this crate and records the exact bytes produced.
+//! The resulting [`VectorSuite`] is plain JSON, so another client can load
+//! it and compare its own output, and [`validate`] checks any
+//! [`ConformanceTarget`] against a suite, byte for byte.
+//!
+//! Packed guest outputs are `new_state_root | transaction_count (u64 LE) |
+//! gas_used (u64 LE) | success (u8)`.
+
+use crate::crypto::keystore::KeyPair;
+use crate::serialization::{Codec, canonical_bytes, seal};
+use crate::types::*;
+use crate::zkvm::programs::guest_program::{StateTransitionInput, verify_state_transition};
+use crate::zkvm::state_transition_input;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Bumped whenever vectors are added or their inputs change
+pub const SUITE_VERSION: u32 = 7;
+
+/// An account in a state-root vector; accounts are listed rather than keyed
+/// by address so the file stays plain JSON
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct VectorAccount {
+    pub address: Address,
+    pub balance: u64,
+    pub nonce: u64,
+    #[serde(with = "hex::serde")]
+    pub code: Vec<u8>,
+    pub storage: Vec<(BlockHash, BlockHash)>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum VectorInput {
+    /// Canonical encoding of a block
+    BlockCanonical { block: Block },
+    /// Versioned bincode envelope of a block, as stored and gossiped
+    BlockEnvelope { block: Block },
+    BlockHash { header: BlockHeader },
+    TransactionHash { transaction: Transaction },
+    StateRoot { accounts: Vec<VectorAccount>, global_nonce: u64 },
+    /// Compact encoding of the guest input, the proof's public input
+    GuestInput { input: StateTransitionInput },
+    /// Packed guest output for the input, the proof's public output
+    GuestOutput { input: StateTransitionInput },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TestVector {
+    pub name: String,
+    #[serde(flatten)]
+    pub input: VectorInput,
+    #[serde(with = "hex::serde")]
+    pub expected: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VectorSuite {
+    pub version: u32,
+    pub vectors: Vec<TestVector>,
+}
+
+impl VectorSuite {
+    pub fn load(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read test vectors {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Invalid test vectors {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        std::fs::write(path, serde_json::to_string_pretty(self)? + "\n")
+            .with_context(|| format!("Failed to write test vectors {}", path.display()))
+    }
+}
+
+/// An implementation under test: the bytes it produces for a vector input
+pub trait ConformanceTarget {
+    fn evaluate(&self, input: &VectorInput) -> Result<Vec<u8>>;
+}
+
+/// This crate
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ReferenceTarget;
+
+impl ConformanceTarget for ReferenceTarget {
+    fn evaluate(&self, input: &VectorInput) -> Result<Vec<u8>> {
+        Ok(match input {
+            VectorInput::BlockCanonical { block } => canonical_bytes(block),
+            VectorInput::BlockEnvelope { block } => seal(block, Codec::Bincode)?,
+            VectorInput::BlockHash { header } => header.hash().0.to_vec(),
+            VectorInput::TransactionHash { transaction } => transaction.hash().0.to_vec(),
+            VectorInput::StateRoot { accounts, global_nonce } => {
+                world_state(accounts, *global_nonce).compute_state_root().0.to_vec()
+            }
+            VectorInput::GuestInput { input } => input.encode_compact(),
+            VectorInput::GuestOutput { input } => {
+                let output = verify_state_transition(input.clone());
+                let mut packed = output.new_state_root.to_vec();
+                packed.extend_from_slice(&output.transaction_count.to_le_bytes());
+                packed.extend_from_slice(&output.gas_used.to_le_bytes());
+                packed.push(u8::from(output.success));
+                packed
+            }
+        })
+    }
+}
+
+fn world_state(accounts: &[VectorAccount], global_nonce: u64) -> WorldState {
+    let accounts = accounts.iter()
+        .map(|account| {
+            let storage: HashMap<[u8; 32], [u8; 32]> = account.storage.iter()
+                .map(|(key, value)| (key.0, value.0))
+                .collect();
+            (account.address, Account { balance: account.balance, nonce: account.nonce, code: account.code.clone(), storage })
+        })
+        .collect();
+    WorldState { accounts, global_nonce, state_root: BlockHash::zero(), block_number: 0 }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VectorFailure {
+    pub name: String,
+    /// What the target produced, or why it could not
+    pub actual: Result<Vec<u8>, String>,
+    pub expected: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ConformanceReport {
+    pub passed: usize,
+    pub failures: Vec<VectorFailure>,
+}
+
+impl ConformanceReport {
+    pub fn is_conformant(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Check `target` against every vector in `suite`
+pub fn validate(suite: &VectorSuite, target: &dyn ConformanceTarget) -> ConformanceReport {
+    let mut report = ConformanceReport::default();
+    for vector in &suite.vectors {
+        match target.evaluate(&vector.input) {
+            Ok(actual) if actual == vector.expected => report.passed += 1,
+            actual => report.failures.push(VectorFailure {
+                name: vector.name.clone(),
+                actual: actual.map_err(|e| format!("{:#}", e)),
+                expected: vector.expected.clone(),
+            }),
+        }
+    }
+    report
+}
+
+/// Evaluate the fixed inputs with [`ReferenceTarget`]
+pub fn generate() -> Result<VectorSuite> {
+    let vectors = fixed_inputs()?.into_iter()
+        .map(|(name, input)| {
+            let expected = ReferenceTarget.evaluate(&input)
+                .with_context(|| format!("Failed to evaluate vector {}", name))?;
+            Ok(TestVector { name: name.to_string(), input, expected })
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(VectorSuite { version: SUITE_VERSION, vectors })
+}
+
+/// Signed with a fixed key; both signature schemes are deterministic
+fn signed_transaction(sig_type: SignatureType, secret: u8, nonce: u64) -> Result<Transaction> {
+    let key = KeyPair::from_secret(sig_type, &[secret; 32])?;
+    let mut transaction = Transaction {
+        data: vec![0xca, 0xfe],
+        gas_limit: 30_000,
+        ..Transaction::new(key.address(), Address::new(2), 1_000, nonce)
+    };
+    transaction.sig_type = key.sig_type().clone();
+    transaction.signature = key.sign(&transaction.signing_hash().0)?;
+    Ok(transaction)
+}
+
+fn fixed_header(number: u64, previous_hash: BlockHash) -> BlockHeader {
+    BlockHeader {
+        previous_hash,
+        merkle_root: BlockHash([0x11; 32]),
+        prev_state_root: BlockHash([0x21; 32]),
+        state_root: BlockHash([0x22; 32]),
+        timestamp: 1_700_000_000 + number * 4,
+        block_number: number,
+        gas_limit: 30_000_000,
+        gas_used: 42_000,
+        producer: Address::new(1),
+        extra_data: b"zk-sac".to_vec(),
+        randomness: RandomnessProof::default(),
+    }
+}
+
+/// Guest input for `transactions` on a state funding their senders
+fn guest_input(transactions: &[Transaction]) -> StateTransitionInput {
+    let mut state = WorldState::default();
+    for tx in transactions {
+        state.accounts.insert(tx.from, Account { nonce: tx.nonce, ..Account::new(1_000_000) });
+    }
+    state.state_root = state.compute_state_root();
+    let header = BlockHeader { prev_state_root: state.state_root, ..fixed_header(1, BlockHash::zero()) };
+    state_transition_input(&state, &GasSchedule::default(), &header, transactions)
+}
+
+fn fixed_inputs() -> Result<Vec<(&'static str, VectorInput)>> {
+    let ed25519 = signed_transaction(SignatureType::Ed25519, 7, 0)?;
+    let secp256k1 = signed_transaction(SignatureType::Secp256k1, 9, 3)?;
+    let unsigned = Transaction::with_post_quantum(Address::new(3), Address::new(4), 5, 1);
+
+    let empty = Block {
+        header: fixed_header(1, BlockHash::zero()),
+        transactions: Vec::new(),
+        validator_signatures: Vec::new(),
+        recursive_proof: ZkProof { proof_data: Vec::new(), public_inputs: vec![], verification_key: vec![], proof_type: ProofType::Deferred },
+        protocol_updates: Vec::new(),
+        proof_signature: Vec::new(),
+        aggregate_signature: None,
+    };
+    let full = Block {
+        header: fixed_header(2, empty.header.hash()),
+        transactions: vec![ed25519.clone(), secp256k1.clone()],
+        validator_signatures: vec![ValidatorSignature {
+            validator_address: Address::new(1),
+            stake_weight: 32_000,
+            signature: vec![0x5a; 64],
+            sig_type: SignatureType::Ed25519,
+        }],
+        recursive_proof: ZkProof {
+            proof_data: vec![0xab; 32],
+            public_inputs: vec![0x01, 0x02],
+            verification_key: vec![0x03; 8],
+            proof_type: ProofType::Risc0,
+        },
+        protocol_updates: Vec::new(),
+        proof_signature: vec![0x6b; 64],
+        aggregate_signature: Some(AggregateSignature { signers: vec![0b0110], signature: vec![0x7c; 96] }),
+    };
+
+    let accounts = vec![
+        VectorAccount { address: Address::new(1), balance: 1_000_000, nonce: 4, code: Vec::new(), storage: Vec::new() },
+        VectorAccount {
+            address: Address::new(2),
+            balance: 0,
+            nonce: 0,
+            code: vec![0x60, 0x00, 0x60, 0x00],
+            storage: vec![(BlockHash([1; 32]), BlockHash([2; 32]))],
+        },
+        VectorAccount { address: Address::new(9), balance: 77, nonce: 1, code: Vec::new(), storage: Vec::new() },
+    ];
+
+    Ok(vec![
+        ("block_canonical/empty", VectorInput::BlockCanonical { block: empty.clone() }),
+        ("block_canonical/two_transactions", VectorInput::BlockCanonical { block: full.clone() }),
+        ("block_envelope/two_transactions", VectorInput::BlockEnvelope { block: full.clone() }),
+        ("block_hash/genesis_child", VectorInput::BlockHash { header: empty.header.clone() }),
+        ("block_hash/with_parent", VectorInput::BlockHash { header: full.header.clone() }),
+        ("transaction_hash/ed25519", VectorInput::TransactionHash { transaction: ed25519.clone() }),
+        ("transaction_hash/secp256k1", VectorInput::TransactionHash { transaction: secp256k1.clone() }),
+        ("transaction_hash/unsigned_post_quantum", VectorInput::TransactionHash { transaction: unsigned }),
+        ("state_root/empty", VectorInput::StateRoot { accounts: Vec::new(), global_nonce: 0 }),
+        ("state_root/three_accounts", VectorInput::StateRoot { accounts, global_nonce: 12 }),
+        ("guest_input/two_transactions", VectorInput::GuestInput { input: guest_input(&full.transactions) }),
+        ("guest_output/two_transactions", VectorInput::GuestOutput { input: guest_input(&full.transactions) }),
+        ("guest_output/no_transactions", VectorInput::GuestOutput { input: guest_input(&[]) }),
+    ])
+}
@@ -1,13 +1,38 @@
 use crate::types::*;
-use crate::zkvm::Risc0Executor;
-use crate::crypto::signatures::{SignatureEngine, PostQuantumSigner};
-use crate::crypto::hash::{IncrementalHasher, keccak256_hash, compute_consensus_hash, hex_utils};
+use crate::zkvm::{ProofError, ProverBackend, check_committed_output, prover_backend};
+use crate::zkvm::cache::{ProofCache, ProofCacheKey};
+use crate::zkvm::remote::{ProofJob, ProofOutcome, RemoteProofQueue};
+use crate::crypto::signatures::{SignatureEngine, PostQuantumSigner, secp256k1};
+use crate::crypto::hash::{IncrementalHasher, keccak256_hash, hex_utils};
+use crate::crypto::bls::{self, BlsKeyPair};
+use crate::crypto::randomness::{Vdf, beacon_randomness, beacon_seed};
+use crate::crypto::keystore::{KeyPair, address_of, verify_signature};
+use crate::state::StateDiff;
+use crate::state::snapshot::{self, StateSnapshot};
+use crate::light_client::{BackendProofVerifier, header_signing_bytes};
 use crate::serialization::{encode_blockchain_data, encode_state_data, to_json_pretty, compare_formats, create_block_metadata, to_json_value, extract_block_summary};
-use crate::async_utils::{ConsensusCoordinator, BatchProcessor};
-use anyhow::{Result, anyhow};
-use tracing::{info, warn, debug};
-// Removed async_trait - using sync methods for now
+use crate::async_utils::{ConsensusCoordinator, BatchProcessor, Deadline};
+use crate::mempool::{TransactionPool, TxOrigin, TxValidationError};
+use crate::bridge::{self, BRIDGE_ADDRESS, BridgeAction, BridgeError, DepositMessage};
+use super::error::ConsensusError;
+use super::execution::{self, BlockContext, BlockExecution, ExecutionBackend, ExecutionResult, TransactionHooks};
+use super::events::{ConsensusEvent, EventBus, GovernanceEvent, ValidatorEvent};
+use super::finality::{Attestation, FinalityGadget};
+use super::fork_choice::{BlockImport, BlockTree, ChainSnapshot};
+use super::genesis::GenesisConfig;
+use super::governance::{BackendRuleVerifier, GOVERNANCE_ADDRESS, Governance, GovernanceAction, GovernanceOutcome, RuleChange, rule_commitment};
+use super::slashing::{PROOF_ADDRESS, ProofSubmission, SLASHING_ADDRESS, Slashed, Slasher, SlashingError, SlashingEvidence, proof_signing_bytes};
+use super::staking::{Queued, STAKING_ADDRESS, StakingAction, StakingError};
+use super::registration::{KeyRotation, ValidatorRegistration};
+use crate::performance::alloc::{self, Subsystem};
+use crate::performance::cost_model::ProvingBudget;
+use crate::performance::latency::TxLatencyTracker;
+use anyhow::Result;
+use tracing::{Span, debug, field, info, instrument, warn};
+use async_trait::async_trait;
 use tokio::time::{timeout, Duration};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 
 /// BeamChain-inspired ZK-SAC Consensus Engine
 /// Features:
@@ -15,26 +40,161 @@ use tokio::time::{timeout, Duration};
 /// - Post-quantum signature support
 /// - 4-second block times
 /// - Self-amending protocol rules
-/// - Risc0 zkVM integration
+/// - Risc0 or SP1 zkVM backend
 pub struct ZkSacConsensusEngine {
     pub current_state: WorldState,
+    /// Height and hash of the block `blocks` follows: genesis, or the
+    /// snapshot this node started from
+    base_height: u64,
+    base_hash: BlockHash,
+    /// Beacon randomness of that block, which selects the producer after it
+    base_randomness: [u8; 32],
     pub validator_set: ValidatorSet,
     pub blocks: Vec<Block>,
-    pub pending_transactions: Vec<Transaction>,
+    pub mempool: TransactionPool,
     pub protocol_config: ProtocolConfig,
-    #[cfg(feature = "risc0")]
-    pub zkvm_engine: Box<Risc0Executor>,
+    /// zkVM backend chosen by `zkvm_config.backend`, shared with the
+    /// coordinator's proving and verification tasks
+    pub zkvm_engine: Arc<dyn ProverBackend>,
+    /// Proofs already verified, so revalidating a block does not verify again
+    pub proof_cache: ProofCache,
     pub signature_engine: SignatureEngine,
     pub post_quantum_signer: PostQuantumSigner,
     pub async_coordinator: ConsensusCoordinator,
     pub transaction_processor: BatchProcessor<Transaction>,
+    pub tx_latency: TxLatencyTracker,
+    /// Caps the estimated proving time of produced blocks when set
+    pub proving_budget: Option<ProvingBudget>,
+    pub slot_budget: SlotBudget,
+    /// Blocks produced with a deferred proof, awaiting `prove_deferred`
+    pub deferred_proofs: Vec<u64>,
+    /// Proving service produced blocks are sent to; blocks are then produced
+    /// optimistically and backfilled by `backfill_remote_proofs`
+    pub remote_prover: Option<RemoteProofQueue>,
+    /// Remote proofs that finished before their block was applied
+    remote_outcomes: Vec<ProofOutcome>,
+    pub events: EventBus,
+    /// Keys of the validators this node signs for
+    validator_keys: HashMap<Address, KeyPair>,
+    /// BLS keys of the validators this node signs for, whose votes are aggregated
+    bls_keys: HashMap<Address, BlsKeyPair>,
+    /// Public keys that Ed25519 and secp256k1 transaction signatures are checked against
+    account_keys: HashMap<Address, Vec<u8>>,
+    finality: FinalityGadget,
+    slasher: Slasher,
+    /// Rule proposals being voted on and approved rules awaiting their epoch
+    governance: Governance,
+    /// Loaded from `protocol_config.transaction_hooks`
+    transaction_hooks: Option<Arc<dyn TransactionHooks>>,
+    /// Selected by `protocol_config.execution_backend`
+    execution_backend: Arc<dyn ExecutionBackend>,
+    /// Received blocks that are not on the canonical chain
+    pub block_tree: BlockTree,
+    /// State after each height from the finalized one up, to roll back to
+    snapshots: BTreeMap<u64, ChainSnapshot>,
 }
 
+/// How block production divides the slot between its stages
+#[derive(Debug, Clone)]
+pub struct SlotBudget {
+    /// Time that must be left after execution to prove in-slot; otherwise the proof is deferred
+    pub proving_reserve: Duration,
+    /// Time kept back at the end of the slot for signature collection
+    pub signature_reserve: Duration,
+}
+
+impl Default for SlotBudget {
+    fn default() -> Self {
+        Self {
+            proving_reserve: Duration::from_millis(1500),
+            signature_reserve: Duration::from_millis(500),
+        }
+    }
+}
+
+/// The validator set, slasher and governance as changed by the slashing
+/// evidence, proof submissions, staking and governance actions among a
+/// block's transactions
+#[derive(Debug, Clone)]
+struct ValidatorChanges {
+    validators: ValidatorSet,
+    slasher: Slasher,
+    governance: Governance,
+    slashed: Vec<Slashed>,
+    proven: Vec<ProofSubmission>,
+    queued: Vec<Queued>,
+    governed: Vec<GovernanceOutcome>,
+}
+
+/// Hooks of the WASM module governance installed, if any
+fn load_transaction_hooks(config: &ProtocolConfig) -> Option<Arc<dyn TransactionHooks>> {
+    if config.transaction_hooks.is_empty() {
+        return None;
+    }
+    execution::load_hooks(&config.transaction_hooks)
+        .inspect_err(|e| warn!("❌ Running without the installed transaction hooks: {}", e))
+        .ok()
+}
+
+/// Placeholder proof of a block whose proof comes later
+fn deferred_proof() -> ZkProof {
+    ZkProof {
+        proof_data: Vec::new(),
+        public_inputs: vec![],
+        verification_key: vec![],
+        proof_type: ProofType::Deferred,
+    }
+}
+
+/// Hash of block `block_number` in `blocks`, a chain following block `base_height`
+fn block_hash_at(blocks: &[Block], base_height: u64, block_number: u64) -> Option<BlockHash> {
+    let index = usize::try_from(block_number.checked_sub(base_height)?).ok()?.checked_sub(1)?;
+    blocks.get(index)
+        .filter(|block| block.header.block_number == block_number)
+        .map(|block| block.header.hash())
+}
+
+/// Domain separator for producer selection randomness
+const PRODUCER_SELECTION_DOMAIN: &[u8] = b"zk-sac/producer-selection/v1";
+
+/// Randomness for slot `block_number`, derived from the beacon randomness
+/// of the block before it: anyone holding the chain can recompute it, and
+/// that block's producer can only steer it by evaluating the VDF again for
+/// every block it tries out
+pub fn selection_randomness(beacon: &[u8; 32], block_number: u64) -> [u8; 32] {
+    let mut input = PRODUCER_SELECTION_DOMAIN.to_vec();
+    input.extend_from_slice(beacon);
+    input.extend_from_slice(&block_number.to_le_bytes());
+    keccak256_hash(&input)
+}
+
+/// Stake-weighted pick: each validator is chosen with probability
+/// `stake / total_stake`; `None` if no validator has stake
+pub fn select_weighted<'a>(validators: &'a [Validator], randomness: &[u8; 32]) -> Option<&'a Validator> {
+    let total: u128 = validators.iter().map(|v| u128::from(v.stake)).sum();
+    if total == 0 {
+        return None;
+    }
+    // Map the first 8 bytes uniformly onto [0, total)
+    let sample = u128::from(u64::from_le_bytes(randomness[..8].try_into().expect("8 bytes")));
+    let mut target = (sample * total) >> 64;
+    validators.iter().find(|validator| {
+        let stake = u128::from(validator.stake);
+        if target < stake {
+            true
+        } else {
+            target -= stake;
+            false
+        }
+    })
+}
+
+#[async_trait]
 pub trait ConsensusEngine {
-    fn validate_block(&self, block: &Block) -> Result<bool>;
-    fn produce_block(&mut self, producer: Address) -> Result<Block>;
-    fn apply_block(&mut self, block: Block) -> Result<()>;
-    fn select_block_producer(&self, block_number: u64) -> Result<Address>;
+    async fn validate_block(&self, block: &Block) -> Result<bool, ConsensusError>;
+    async fn produce_block(&mut self, producer: Address) -> Result<Block, ConsensusError>;
+    async fn apply_block(&mut self, block: Block) -> Result<(), ConsensusError>;
+    fn select_block_producer(&self, block_number: u64) -> Result<Address, ConsensusError>;
 }
 
 impl ZkSacConsensusEngine {
@@ -42,17 +202,16 @@ impl ZkSacConsensusEngine {
         genesis_state: WorldState, 
         initial_validators: Vec<Validator>,
         config: ProtocolConfig
-    ) -> Result<Self> {
+    ) -> Result<Self, ConsensusError> {
         info!("🚀 Initializing ZK-SAC Consensus Engine");
         info!("   ⚡ Block time: {:?}", config.block_time);
         info!("   🏗️  Max TX per block: {}", config.max_transactions_per_block);
         
-        let total_stake = initial_validators.iter().map(|v| v.stake).sum();
-        
-        #[cfg(feature = "risc0")]
-        let zkvm_engine = Box::new(Risc0Executor::new()?);
+        let zkvm_engine: Arc<dyn ProverBackend> = Arc::from(prover_backend(&config.zkvm_config)?);
+        let remote_prover = config.zkvm_config.remote.as_ref().map(RemoteProofQueue::from_config);
+        let proof_cache = ProofCache::new(&config.zkvm_config.proof_cache);
         let signature_engine = SignatureEngine::new();
-        let post_quantum_signer = PostQuantumSigner::new()?;
+        let post_quantum_signer = PostQuantumSigner::new().map_err(ConsensusError::signing)?;
         
         let async_coordinator = ConsensusCoordinator::new();
         let transaction_processor = BatchProcessor::new(
@@ -62,154 +221,1432 @@ impl ZkSacConsensusEngine {
         
         info!("🚀 Async coordination pools initialized");
 
+        let mut genesis_state = genesis_state;
+        genesis_state.state_root = genesis_state.compute_state_root();
+
+        let account_keys = initial_validators.iter()
+            .map(|validator| (validator.address, validator.public_key.clone()))
+            .collect();
+        let validator_set = ValidatorSet::new(initial_validators);
+        let transaction_hooks = load_transaction_hooks(&config);
+        let execution_backend = execution::execution_backend(config.execution_backend);
+        let genesis = ChainSnapshot {
+            undo: StateDiff::default(),
+            state_root: genesis_state.state_root,
+            validator_set: validator_set.clone(),
+            slasher: Slasher::new(),
+            governance: Governance::new(),
+            protocol_config: config.clone(),
+        };
+
         Ok(Self {
+            base_height: 0,
+            base_hash: BlockHash::zero(),
+            base_randomness: [0; 32],
             current_state: genesis_state,
-            validator_set: ValidatorSet {
-                validators: initial_validators,
-                total_stake,
-            },
+            validator_set,
             blocks: Vec::new(),
-            pending_transactions: Vec::new(),
+            mempool: TransactionPool::default(),
             protocol_config: config,
-            #[cfg(feature = "risc0")]
             zkvm_engine,
+            proof_cache,
             signature_engine,
             post_quantum_signer,
             async_coordinator,
             transaction_processor,
+            tx_latency: TxLatencyTracker::new(),
+            proving_budget: None,
+            slot_budget: SlotBudget::default(),
+            deferred_proofs: Vec::new(),
+            remote_prover,
+            remote_outcomes: Vec::new(),
+            events: EventBus::new(),
+            validator_keys: HashMap::new(),
+            bls_keys: HashMap::new(),
+            account_keys,
+            finality: FinalityGadget::new(),
+            slasher: Slasher::new(),
+            governance: Governance::new(),
+            transaction_hooks,
+            execution_backend,
+            block_tree: BlockTree::new(),
+            snapshots: BTreeMap::from([(0, genesis)]),
         })
     }
 
-    pub fn execute_transactions_with_zkvm(&self, transactions: &[Transaction]) -> Result<(WorldState, ZkProof)> {
-        let mut new_state = self.current_state.clone();
-        
-        // Simple state update for each transaction
-        for tx in transactions {
-            // Update account balances
-            if let Some(from_account) = new_state.accounts.get_mut(&tx.from) {
-                if from_account.balance >= tx.value {
-                    from_account.balance -= tx.value;
-                    from_account.nonce += 1;
+    /// Start from `genesis`, on its protocol rules or the defaults
+    pub fn from_genesis(genesis: &GenesisConfig) -> Result<Self, ConsensusError> {
+        let config = genesis.protocol_config(ProtocolConfig::default());
+        Self::new(genesis.world_state(), genesis.validators(), config)
+    }
+
+    /// [`Self::from_genesis`] with the genesis read from a JSON or TOML file
+    pub fn from_genesis_file(path: impl AsRef<std::path::Path>) -> Result<Self, ConsensusError> {
+        Self::from_genesis(&GenesisConfig::load(path.as_ref())?)
+    }
+
+    /// Start from a snapshot of the state after `header` instead of from
+    /// genesis. The header must come from a trusted source, such as a light
+    /// client following finality: the snapshot is checked against it, and
+    /// its block is treated as final.
+    pub fn from_snapshot(snapshot: &StateSnapshot, header: &BlockHeader, config: ProtocolConfig) -> Result<Self, ConsensusError> {
+        snapshot.manifest.verify_header(header)?;
+        let state = snapshot.restore()?;
+        let validator_set = snapshot.manifest.validator_set.clone();
+        let mut engine = Self::new(state, validator_set.validators.clone(), config)?;
+        info!("📸 Starting from the snapshot of block {}", header.block_number);
+
+        engine.base_height = header.block_number;
+        engine.base_hash = header.hash();
+        engine.base_randomness = beacon_randomness(&header.randomness);
+        engine.finality = FinalityGadget::from_checkpoint(engine.base_height, engine.base_hash);
+        let base = ChainSnapshot {
+            undo: StateDiff::default(),
+            state_root: engine.current_state.state_root,
+            validator_set: validator_set.clone(),
+            slasher: Slasher::new(),
+            governance: Governance::new(),
+            protocol_config: engine.protocol_config.clone(),
+        };
+        engine.snapshots = BTreeMap::from([(engine.base_height, base)]);
+        engine.validator_set = validator_set;
+        Ok(engine)
+    }
+
+    /// Snapshot of the state after the head block, for new nodes to start from
+    pub fn export_snapshot(&self, chunk_accounts: usize) -> Result<StateSnapshot, ConsensusError> {
+        let head = self.blocks.last().ok_or(ConsensusError::UnknownBlock(self.height()))?;
+        Ok(snapshot::export(&self.current_state, &self.validator_set, &head.header, chunk_accounts))
+    }
+
+    /// Snapshot of the state after block `height`, which must be the
+    /// finalized block or a later one; nodes that agree on the block export
+    /// identical chunks, so a new node can fetch them from several peers
+    pub fn export_snapshot_at(&self, height: u64, chunk_accounts: usize) -> Result<StateSnapshot, ConsensusError> {
+        let saved = self.snapshots.get(&height).ok_or(ConsensusError::UnknownBlock(height))?;
+        let block = usize::try_from(height.saturating_sub(self.base_height)).ok()
+            .and_then(|index| index.checked_sub(1))
+            .and_then(|index| self.blocks.get(index))
+            .filter(|block| block.header.block_number == height)
+            .ok_or(ConsensusError::UnknownBlock(height))?;
+        Ok(snapshot::export(&self.state_at(height)?, &saved.validator_set, &block.header, chunk_accounts))
+    }
+
+    /// The state after block `height`, which must be the finalized block, a
+    /// later one or the parent of a block awaiting its proof, rebuilt by
+    /// undoing the blocks above it
+    fn state_at(&self, height: u64) -> Result<WorldState, ConsensusError> {
+        let saved = self.snapshots.get(&height).ok_or(ConsensusError::UnknownBlock(height))?;
+        let mut state = self.current_state.clone();
+        for later in self.snapshots.range(height + 1..).rev().map(|(_, later)| later) {
+            state.apply_diff(&later.undo);
+        }
+        state.state_root = saved.state_root;
+        Ok(state)
+    }
+
+    /// Height of the head block, 0 at genesis
+    pub fn height(&self) -> u64 {
+        self.base_height + self.blocks.len() as u64
+    }
+
+    /// Sign produced blocks for the validator `key` belongs to
+    pub fn add_validator_key(&mut self, key: KeyPair) -> Result<(), ConsensusError> {
+        let address = key.address();
+        let validator = self.validator_set.validators.iter()
+            .find(|v| v.address == address)
+            .ok_or(ConsensusError::UnknownValidator(address))?;
+        if validator.public_key != key.public_key() {
+            return Err(ConsensusError::KeyMismatch(address));
+        }
+        info!("🔑 Signing blocks as validator {:?}", address);
+        self.validator_keys.insert(address, key);
+        Ok(())
+    }
+
+    /// Aggregate the votes of `validator` with BLS `key`, which must match
+    /// the validator's registered BLS key
+    pub fn add_validator_bls_key(&mut self, validator: Address, key: BlsKeyPair) -> Result<(), ConsensusError> {
+        let registered = self.validator_set.validators.iter()
+            .find(|v| v.address == validator)
+            .ok_or(ConsensusError::UnknownValidator(validator))?;
+        if registered.bls_public_key != key.public_key() {
+            return Err(ConsensusError::KeyMismatch(validator));
+        }
+        info!("🔑 Aggregating votes of validator {:?} with BLS", validator);
+        self.bls_keys.insert(validator, key);
+        Ok(())
+    }
+
+    /// Whether this node can sign for `validator`
+    pub fn holds_validator_key(&self, validator: &Address) -> bool {
+        self.validator_keys.contains_key(validator)
+    }
+
+    /// Accept Ed25519 and secp256k1 transactions from the account `public_key`
+    /// controls; secp256k1 senders without a registered key are recovered
+    /// from their signature
+    pub fn register_account_key(&mut self, sig_type: &SignatureType, public_key: Vec<u8>) -> Address {
+        let address = address_of(sig_type, &public_key);
+        self.account_keys.insert(address, public_key);
+        address
+    }
+
+    /// Add a vote for `block` from every validator this node holds a key for,
+    /// and sign its proof if this node is the producer; returns the stake
+    /// that has signed it. Validators with a BLS key vote through the
+    /// block's aggregate signature, except the producer, whose own vote
+    /// attributes the block to it.
+    pub fn collect_validator_signatures(&self, block: &mut Block) -> Result<u64, ConsensusError> {
+        if let Some(key) = self.validator_keys.get(&block.header.producer) {
+            block.proof_signature = key.sign(&proof_signing_bytes(&block.header.hash(), &block.recursive_proof))
+                .map_err(ConsensusError::signing)?;
+        }
+        let message = header_signing_bytes(block.header.block_number, &block.header.hash());
+        let mut aggregated = Vec::new();
+        for (index, validator) in self.validator_set.validators.iter().enumerate() {
+            let Some(key) = self.validator_keys.get(&validator.address) else { continue };
+            if block.validator_signatures.iter().any(|vote| vote.validator_address == validator.address)
+                || block.aggregate_signature.as_ref().is_some_and(|aggregate| aggregate.has_signer(index))
+            {
+                continue;
+            }
+            if let Some(bls_key) = self.bls_keys.get(&validator.address).filter(|_| validator.address != block.header.producer) {
+                aggregated.push((index, bls_key.sign(&message)));
+                continue;
+            }
+            block.validator_signatures.push(ValidatorSignature {
+                validator_address: validator.address,
+                stake_weight: validator.stake,
+                signature: key.sign(&message).map_err(ConsensusError::signing)?,
+                sig_type: key.sig_type().clone(),
+            });
+        }
+        if !aggregated.is_empty() {
+            let mut aggregate = block.aggregate_signature.take().unwrap_or_default();
+            let mut signatures: Vec<&[u8]> = aggregated.iter().map(|(_, signature)| signature.as_slice()).collect();
+            if !aggregate.signature.is_empty() {
+                signatures.push(&aggregate.signature);
+            }
+            let signature = bls::aggregate(&signatures).map_err(ConsensusError::InvalidAggregate)?;
+            for (index, _) in &aggregated {
+                aggregate.add_signer(*index);
+            }
+            aggregate.signature = signature;
+            block.aggregate_signature = Some(aggregate);
+        }
+
+        let signed = self.signed_stake(block);
+        debug!("✍️  Block {} signed by {} validators and {} aggregated ({} stake)",
+               block.header.block_number, block.validator_signatures.len(),
+               block.aggregate_signature.as_ref().map_or(0, |aggregate| aggregate.signer_indices().count()), signed);
+        Ok(signed)
+    }
+
+    /// Stake behind the votes `block` carries, individual and aggregated
+    pub fn signed_stake(&self, block: &Block) -> u64 {
+        let individual: u64 = block.validator_signatures.iter().map(|vote| vote.stake_weight).sum();
+        let aggregated: u64 = block.aggregate_signature.iter()
+            .flat_map(AggregateSignature::signer_indices)
+            .filter_map(|index| self.validator_set.validators.get(index))
+            .map(|validator| validator.stake)
+            .sum();
+        individual + aggregated
+    }
+
+    /// Reject transactions signed for another chain
+    pub fn verify_chain_id(&self, transaction: &Transaction) -> Result<(), TxValidationError> {
+        if transaction.chain_id != self.protocol_config.chain_id {
+            return Err(TxValidationError::WrongChain { chain_id: transaction.chain_id, expected: self.protocol_config.chain_id });
+        }
+        Ok(())
+    }
+
+    /// Check the chain id and signature of `transaction` against its sender
+    pub fn verify_transaction_signature(&self, transaction: &Transaction) -> Result<(), TxValidationError> {
+        self.verify_chain_id(transaction)?;
+        if transaction.signature.is_empty() {
+            return Err(TxValidationError::Unsigned(transaction.from));
+        }
+        let message = transaction.signing_hash().0;
+        let verified = match transaction.sig_type {
+            // The LMS mock binds signatures to the signer's address
+            SignatureType::PostQuantum => self.post_quantum_signer
+                .verify_lms(&transaction.signature, &transaction.from, &message),
+            // Without a registered key, the sender is whoever the signature recovers to
+            SignatureType::Secp256k1 if !self.account_keys.contains_key(&transaction.from) => {
+                match secp256k1::recover_address(&message, &transaction.signature) {
+                    Ok(signer) if signer != transaction.from => {
+                        return Err(TxValidationError::KeyMismatch { sender: transaction.from, sig_type: SignatureType::Secp256k1 });
+                    }
+                    recovered => recovered.map(|_| ()),
+                }
+            }
+            SignatureType::Ed25519 | SignatureType::Secp256k1 => {
+                let public_key = self.account_keys.get(&transaction.from)
+                    .ok_or(TxValidationError::UnknownSender(transaction.from))?;
+                if address_of(&transaction.sig_type, public_key) != transaction.from {
+                    return Err(TxValidationError::KeyMismatch { sender: transaction.from, sig_type: transaction.sig_type.clone() });
                 }
+                self.verify_with_public_key(&transaction.sig_type, public_key, &message, &transaction.signature)
             }
-            
-            // Update to account
-            new_state.accounts.entry(tx.to).or_insert_with(|| Account {
-                balance: 0,
-                nonce: 0,
-                code: Vec::new(),
-                storage: std::collections::HashMap::new(),
-            }).balance += tx.value;
+        };
+        verified.map_err(|e| TxValidationError::InvalidSignature { sender: transaction.from, reason: format!("{:#}", e) })
+    }
+
+    /// Check every transaction signature and validator vote in `block`; the
+    /// producer's vote and its signature over the proof are required
+    pub fn verify_block_signatures(&self, block: &Block) -> Result<(), ConsensusError> {
+        let message = header_signing_bytes(block.header.block_number, &block.header.hash());
+        let (batched_transactions, batched_votes) = self.verify_ed25519_batch(block, &message)?;
+        for (index, transaction) in block.transactions.iter().enumerate() {
+            let verified = if batched_transactions.contains(&index) {
+                self.verify_chain_id(transaction)
+            } else {
+                self.verify_transaction_signature(transaction)
+            };
+            verified.map_err(|source| ConsensusError::InvalidTransaction { index, source })?;
         }
 
-        // Generate zkVM proof for all executions (mock for now - async makes it complex)
-        let proof = vec![0; 32]; // Mock proof
-        
-        let zk_proof = ZkProof {
-            proof_data: proof,
-            public_inputs: vec![],
-            verification_key: vec![],
-            proof_type: crate::types::ProofType::Risc0,
+        let mut seen = HashSet::new();
+        for (position, vote) in block.validator_signatures.iter().enumerate() {
+            if !seen.insert(vote.validator_address) {
+                return Err(ConsensusError::DuplicateVote(vote.validator_address));
+            }
+            let validator = self.validator_set.validators.iter()
+                .find(|v| v.address == vote.validator_address)
+                .ok_or(ConsensusError::UnknownValidator(vote.validator_address))?;
+            if batched_votes.contains(&position) {
+                continue;
+            }
+            self.verify_with_public_key(&vote.sig_type, &validator.public_key, &message, &vote.signature)
+                .map_err(|e| ConsensusError::InvalidVote { validator: vote.validator_address, reason: format!("{:#}", e) })?;
+        }
+        if let Some(aggregate) = &block.aggregate_signature {
+            // One pairing check covers every aggregated vote
+            let signers = bls::verify_aggregate_vote(&self.validator_set, &message, aggregate)
+                .map_err(ConsensusError::InvalidAggregate)?;
+            if let Some(signer) = signers.iter().find(|signer| !seen.insert(signer.address)) {
+                return Err(ConsensusError::DuplicateVote(signer.address));
+            }
+        }
+        let Some(vote) = block.validator_signatures.iter().find(|vote| vote.validator_address == block.header.producer) else {
+            return Err(ConsensusError::UnsignedByProducer { block_number: block.header.block_number, producer: block.header.producer });
         };
-        Ok((new_state, zk_proof))
+        let producer = self.validator_set.validators.iter()
+            .find(|v| v.address == block.header.producer)
+            .ok_or(ConsensusError::UnknownValidator(block.header.producer))?;
+        let message = proof_signing_bytes(&block.header.hash(), &block.recursive_proof);
+        self.verify_with_public_key(&vote.sig_type, &producer.public_key, &message, &block.proof_signature)
+            .map_err(|e| ConsensusError::InvalidProofSignature { producer: block.header.producer, reason: format!("{:#}", e) })?;
+        Ok(())
     }
 
-    pub fn generate_recursive_proof(&self, protocol_updates: Vec<ProtocolRule>) -> Result<ZkProof> {
-        info!("🔄 Generating recursive zk-proof for {} protocol updates", protocol_updates.len());
-        
-        let mut proof_inputs = Vec::new();
-        for update in &protocol_updates {
-            proof_inputs.extend_from_slice(&update.validity_proof.proof_data);
+    /// Verify the Ed25519 transaction signatures and validator votes of
+    /// `block` in one batch, returning the positions of the transactions and
+    /// votes it covered. Signatures whose key cannot be resolved are left to
+    /// the individual checks, which report why.
+    fn verify_ed25519_batch(&self, block: &Block, vote_message: &[u8]) -> Result<(HashSet<usize>, HashSet<usize>), ConsensusError> {
+        let transactions: Vec<(usize, [u8; 32], &[u8])> = block.transactions.iter().enumerate()
+            .filter(|(_, transaction)| transaction.sig_type == SignatureType::Ed25519 && !transaction.signature.is_empty())
+            .filter_map(|(index, transaction)| {
+                let public_key = self.account_keys.get(&transaction.from)?;
+                (address_of(&SignatureType::Ed25519, public_key) == transaction.from)
+                    .then(|| (index, transaction.signing_hash().0, public_key.as_slice()))
+            })
+            .collect();
+        let votes: Vec<(usize, &[u8])> = block.validator_signatures.iter().enumerate()
+            .filter(|(_, vote)| vote.sig_type == SignatureType::Ed25519)
+            .filter_map(|(position, vote)| {
+                let validator = self.validator_set.validators.iter().find(|v| v.address == vote.validator_address)?;
+                Some((position, validator.public_key.as_slice()))
+            })
+            .collect();
+
+        let items: Vec<(&[u8], &[u8], &[u8])> = transactions.iter()
+            .map(|(index, message, public_key)| (block.transactions[*index].signature.as_slice(), *public_key, message.as_slice()))
+            .chain(votes.iter().map(|(position, public_key)| (block.validator_signatures[*position].signature.as_slice(), *public_key, vote_message)))
+            .collect();
+        if let Err(failure) = self.signature_engine.verify_batch_ed25519(&items) {
+            return Err(match transactions.get(failure.index) {
+                Some(&(index, ..)) => ConsensusError::InvalidTransaction {
+                    index,
+                    source: TxValidationError::InvalidSignature { sender: block.transactions[index].from, reason: failure.reason },
+                },
+                None => ConsensusError::InvalidVote {
+                    validator: block.validator_signatures[votes[failure.index - transactions.len()].0].validator_address,
+                    reason: failure.reason,
+                },
+            });
         }
-        
-        // Generate the recursive proof (mock for sync execution)
-        let proof = vec![0; 32]; // Mock proof
-        
-        let zk_proof = ZkProof {
-            proof_data: proof,
-            public_inputs: vec![],
+        Ok((
+            transactions.iter().map(|(index, ..)| *index).collect(),
+            votes.iter().map(|(position, _)| *position).collect(),
+        ))
+    }
+
+    /// Structural check of the recursive proof of a block: that it is there
+    /// and commits to the block's transition from `prev_state_root`, with
+    /// `transaction_count` transactions if the body is at hand. The binding
+    /// is read from the proof, which the backend then verifies; only proofs
+    /// committing to nothing, as mock proofs do, are held to the public
+    /// inputs attached beside them. Deferred proofs are accepted: the block
+    /// then owes its proof to the slasher, which keeps it from finalizing
+    /// until a [`ProofSubmission`] supplies it and slashes its producer if
+    /// none does within `proof_deadline` blocks.
+    fn verify_proof(
+        &self,
+        prev_state_root: &BlockHash,
+        header: &BlockHeader,
+        transaction_count: Option<usize>,
+        proof: &ZkProof,
+    ) -> Result<(), ProofError> {
+        if matches!(proof.proof_type, ProofType::Deferred) {
+            return Ok(());
+        }
+        if proof.proof_data.is_empty() {
+            return Err(ProofError::Malformed(format!("block {} has an empty proof", header.block_number)));
+        }
+        match self.zkvm_engine.committed_output(&proof.proof_data)? {
+            Some(output) => check_committed_output(&output, prev_state_root, header, transaction_count),
+            None if proof.public_inputs != header.public_inputs().encode() => {
+                Err(ProofError::PublicInputsMismatch(header.block_number))
+            }
+            None => Ok(()),
+        }
+    }
+
+    /// Slashable misbehaviour `block` proves, if any: a different block at a
+    /// height this node already holds from the same producer, or a proof
+    /// that does not verify. Offenses already punished are not reported again.
+    pub fn detect_offense(&self, block: &Block) -> Option<SlashingEvidence> {
+        let evidence = match block_hash_at(&self.blocks, self.base_height, block.header.block_number) {
+            Some(hash) if hash != block.header.hash() => {
+                let ours = &self.blocks[(block.header.block_number - self.base_height) as usize - 1];
+                if ours.header.producer != block.header.producer {
+                    return None;
+                }
+                SlashingEvidence::double_production(ours, block)?
+            }
+            Some(_) => return None,
+            None if self.verify_proof(&block.header.prev_state_root, &block.header, Some(block.transactions.len()), &block.recursive_proof).is_err() => {
+                SlashingEvidence::invalid_proof(block)?
+            }
+            None => return None,
+        };
+        self.slasher.check(&self.validator_set, &evidence, &self.proof_verifier()).ok()?;
+        Some(evidence)
+    }
+
+    /// Verifier of block proofs over this node's prover backend, which
+    /// evidence of an invalid proof is checked with
+    pub fn proof_verifier(&self) -> BackendProofVerifier {
+        BackendProofVerifier::new(self.zkvm_engine.clone())
+    }
+
+    /// Submit `evidence` as a transaction signed by a validator key this node
+    /// holds, returning the transaction so it can be gossiped
+    pub fn report_offense(&mut self, evidence: &SlashingEvidence) -> Result<Transaction, ConsensusError> {
+        self.slasher.check(&self.validator_set, evidence, &self.proof_verifier())?;
+        let reporter = self.validator_set.validators.iter()
+            .find_map(|validator| self.validator_keys.get(&validator.address))
+            .ok_or(ConsensusError::NoValidatorKey)?;
+        let mut nonce = self.current_state.accounts.get(&reporter.address()).map_or(0, |account| account.nonce);
+        while self.mempool.get(&reporter.address(), nonce).is_some() {
+            nonce += 1;
+        }
+        let transaction = evidence.to_transaction(reporter, nonce).map_err(ConsensusError::signing)?;
+        warn!("⚔️  Reporting {:?} by {:?} at height {}", evidence.offense(), evidence.offender(), evidence.height());
+        self.add_local_transaction(transaction.clone())?;
+        Ok(transaction)
+    }
+
+    /// Submit the L1 deposits not yet minted as transactions signed by a
+    /// validator key this node holds, returning how many were submitted
+    pub fn relay_deposits(&mut self, deposits: &[DepositMessage]) -> Result<usize, ConsensusError> {
+        let relayer = self.validator_set.validators.iter()
+            .find_map(|validator| self.validator_keys.get(&validator.address))
+            .ok_or(ConsensusError::NoValidatorKey)?
+            .clone();
+        let mut next = bridge::messages::next_deposit_nonce(&self.current_state);
+        let mut nonce = self.current_state.accounts.get(&relayer.address()).map_or(0, |account| account.nonce);
+        let mut relayed = 0;
+        for deposit in deposits {
+            if deposit.nonce < next {
+                continue;
+            }
+            if deposit.nonce != next {
+                break;
+            }
+            while self.mempool.get(&relayer.address(), nonce).is_some() {
+                nonce += 1;
+            }
+            let transaction = BridgeAction::Deposit(deposit.clone()).to_transaction(&relayer, 0, nonce)
+                .map_err(ConsensusError::signing)?;
+            self.add_local_transaction(transaction)?;
+            next += 1;
+            relayed += 1;
+        }
+        if relayed > 0 {
+            info!("🌉 Relayed {} deposits up to nonce {}", relayed, next - 1);
+        }
+        Ok(relayed)
+    }
+
+    fn unchanged_validators(&self) -> ValidatorChanges {
+        ValidatorChanges {
+            validators: self.validator_set.clone(),
+            slasher: self.slasher.clone(),
+            governance: self.governance.clone(),
+            slashed: Vec::new(),
+            proven: Vec::new(),
+            queued: Vec::new(),
+            governed: Vec::new(),
+        }
+    }
+
+    /// Apply `transaction` to `changes` if it carries slashing evidence, a
+    /// deferred proof, a staking action or a governance action for block
+    /// `block_number`, and check that relayed deposits come from a validator;
+    /// leaves `changes` alone on error
+    fn apply_validator_transaction(&self, changes: &mut ValidatorChanges, block_number: u64, transaction: &Transaction) -> Result<(), TxValidationError> {
+        if let Some(evidence) = SlashingEvidence::from_transaction(transaction) {
+            changes.slashed.push(changes.slasher.slash(&mut changes.validators, &evidence?, &self.proof_verifier(), &self.protocol_config)?);
+        } else if let Some(submission) = ProofSubmission::from_transaction(transaction) {
+            let submission = submission?;
+            let proven = submission.block_number;
+            let block = self.blocks.iter()
+                .find(|block| block.header.block_number == proven)
+                .filter(|_| !changes.proven.iter().any(|earlier| earlier.block_number == proven))
+                .ok_or(SlashingError::NotDeferred(proven))?;
+            // A block synced after its proof was supplied already carries it
+            // and owes nothing, so the submission changes nothing
+            if changes.slasher.owes(proven) {
+                changes.slasher.supply(&block.header, &submission, &self.proof_verifier())?;
+            } else if block.proof_signature != submission.proof_signature || matches!(block.recursive_proof.proof_type, ProofType::Deferred) {
+                return Err(SlashingError::NotDeferred(proven).into());
+            }
+            changes.proven.push(submission);
+        } else if let Some(action) = StakingAction::from_transaction(transaction) {
+            let action = action?;
+            let balance = self.current_state.accounts.get(&transaction.from).map_or(0, |account| account.balance);
+            let fee = GasSchedule::fee(self.protocol_config.gas_schedule.intrinsic_gas(transaction), transaction.gas_price);
+            if matches!(action, StakingAction::Stake { .. }) && balance < transaction.value.saturating_add(fee) {
+                return Err(TxValidationError::InsufficientBalance { sender: transaction.from, stake: transaction.value, balance });
+            }
+            changes.queued.push(changes.validators.apply_staking(transaction, &action, block_number, &self.protocol_config)?);
+        } else if let Some(action) = GovernanceAction::from_transaction(transaction) {
+            let outcome = changes.governance.apply(transaction, &action?, block_number, &changes.validators, &self.protocol_config, &BackendRuleVerifier::new(self.zkvm_engine.clone()))?;
+            changes.governed.push(outcome);
+        } else if let Some(action) = BridgeAction::from_transaction(transaction) {
+            let relayer = transaction.from;
+            if matches!(action?, BridgeAction::Deposit(_)) && !changes.validators.validators.iter().any(|validator| validator.address == relayer) {
+                return Err(BridgeError::UnauthorizedRelayer(relayer).into());
+            }
+        }
+        Ok(())
+    }
+
+    /// Apply the evidence, staking and governance actions in `transactions`,
+    /// in order, to copies of the validator set, slasher and governance; fails on the first that
+    /// is malformed, invalid or already used
+    fn validator_changes(&self, block_number: u64, transactions: &[Transaction]) -> Result<ValidatorChanges, TxValidationError> {
+        let mut changes = self.unchanged_validators();
+        for transaction in transactions {
+            self.apply_validator_transaction(&mut changes, block_number, transaction)?;
+        }
+        Ok(changes)
+    }
+
+    /// Drop evidence, staking and governance actions that an earlier block or
+    /// transaction already used since they were pooled
+    fn retain_usable_validator_transactions(&self, transactions: &mut Vec<Transaction>) {
+        let block_number = self.height() + 1;
+        let mut changes = self.unchanged_validators();
+        transactions.retain(|transaction| self.apply_validator_transaction(&mut changes, block_number, transaction).is_ok());
+    }
+
+    fn verify_with_public_key(&self, sig_type: &SignatureType, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<()> {
+        match sig_type {
+            SignatureType::Ed25519 => self.signature_engine.verify_with_public_key(signature, public_key, message),
+            SignatureType::PostQuantum => self.post_quantum_signer
+                .verify_lms(signature, &address_of(sig_type, public_key), message),
+            SignatureType::Secp256k1 => verify_signature(sig_type, public_key, message, signature),
+        }
+    }
+
+    /// Apply `transactions` to the current state in the block `context` describes
+    pub fn execute_transactions(&self, context: &BlockContext, transactions: &[Transaction]) -> BlockExecution {
+        self.execution_backend.execute(&self.current_state, &self.protocol_config.gas_schedule, self.transaction_hooks(), context, transactions)
+    }
+
+    /// Environment this node executes transactions in
+    pub fn execution_backend(&self) -> &dyn ExecutionBackend {
+        self.execution_backend.as_ref()
+    }
+
+    /// Protocol rules governance installed over transaction execution
+    pub fn transaction_hooks(&self) -> Option<&dyn TransactionHooks> {
+        self.transaction_hooks.as_deref()
+    }
+
+    /// Check that every transaction of the block `header` describes executed,
+    /// and that it stays within the block gas limit and reports the gas used
+    fn check_execution(&self, header: &BlockHeader, transactions: &[Transaction], execution: &BlockExecution) -> Result<(), ConsensusError> {
+        if let Some((index, source)) = execution.rejected().next() {
+            return Err(ConsensusError::RejectedTransaction { block_number: header.block_number, index, source: source.clone() });
+        }
+        let limit = self.protocol_config.gas_schedule.block_gas_limit;
+        let requested = transactions.iter().fold(0u64, |total, tx| total.saturating_add(tx.gas_limit));
+        if header.gas_limit > limit || requested > header.gas_limit {
+            return Err(ConsensusError::GasLimitExceeded {
+                block_number: header.block_number,
+                gas: requested.max(header.gas_limit),
+                limit,
+            });
+        }
+        if header.gas_used != execution.gas_used {
+            return Err(ConsensusError::GasUsedMismatch {
+                block_number: header.block_number,
+                header: header.gas_used,
+                executed: execution.gas_used,
+            });
+        }
+        Ok(())
+    }
+
+    /// Prove with the zkVM backend, on the coordinator's block production
+    /// pool, that `transactions`, charged under `schedule`, take `state`, the
+    /// state the block `header` describes builds on, to that block
+    #[instrument(name = "prove_block", skip_all, fields(block_number = header.block_number, tx_count = transactions.len(), proof_type = ?self.zkvm_engine.proof_type()))]
+    pub async fn prove_block(&self, state: &WorldState, schedule: &GasSchedule, header: &BlockHeader, transactions: &[Transaction]) -> Result<ZkProof, ProofError> {
+        info!("🔧 Proving block {} with {} transactions", header.block_number, transactions.len());
+        let input = self.execution_backend.proof_input(state, schedule, header, transactions);
+        let backend = self.zkvm_engine.clone();
+        let proof_data = self.async_coordinator.block_production_pool()
+            .execute(move || async move {
+                let _alloc = alloc::enter(Subsystem::Zkvm);
+                Ok(backend.generate_state_transition_proof(&input).await)
+            })
+            .await
+            .map_err(|e| ProofError::Task(format!("{:#}", e)))??;
+        // Blocks the guest does not run as executed, such as ones with
+        // contract or system transactions, prove another transition
+        if let Some(output) = self.zkvm_engine.committed_output(&proof_data)? {
+            check_committed_output(&output, &header.prev_state_root, header, Some(transactions.len()))?;
+        }
+        let proof = ZkProof {
+            proof_data,
+            public_inputs: header.public_inputs().encode(),
             verification_key: vec![],
-            proof_type: crate::types::ProofType::Risc0,
+            proof_type: self.zkvm_engine.proof_type(),
         };
-        info!("✅ Recursive zk-proof generated: {} bytes", zk_proof.proof_data.len());
-        Ok(zk_proof)
+        // A proof this node generated needs no verifying
+        let key = ProofCacheKey { prev_state_root: header.prev_state_root, tx_root: header.merkle_root };
+        self.proof_cache.insert(key, proof.clone());
+        Ok(proof)
     }
 
-    fn collect_transactions_for_block(&mut self) -> Vec<Transaction> {
-        let max_tx = self.protocol_config.max_transactions_per_block;
-        let collected: Vec<Transaction> = self.pending_transactions
-            .drain(..std::cmp::min(max_tx, self.pending_transactions.len()))
-            .collect();
-        
-        debug!("📦 Collected {} transactions for block production", collected.len());
-        collected
+    /// Evaluate the randomness beacon VDF for block `block_number` on the
+    /// coordinator's block production pool
+    pub async fn evaluate_beacon(&self, previous_hash: &BlockHash, block_number: u64) -> Result<RandomnessProof, ConsensusError> {
+        let seed = beacon_seed(previous_hash, block_number);
+        let vdf = Vdf::new(self.protocol_config.vdf_iterations);
+        self.async_coordinator.block_production_pool()
+            .execute(move || async move { Ok(vdf.evaluate(&seed)) })
+            .await
+            .map_err(|e| ConsensusError::Beacon(format!("{:#}", e)))
     }
 
-    fn get_last_block_hash(&self) -> BlockHash {
-        if let Some(last_block) = self.blocks.last() {
-            // Serialize header to bytes for hashing
-            let header_bytes = bincode::serialize(&last_block.header).unwrap_or_default();
-            let (hash, _, _) = compute_consensus_hash(&header_bytes);
-            BlockHash(hash)
-        } else {
-            BlockHash::zero() // Genesis
+    /// Check the beacon output in `header` against the seed its parent fixes
+    pub fn verify_beacon(&self, header: &BlockHeader) -> Result<(), ConsensusError> {
+        let seed = beacon_seed(&header.previous_hash, header.block_number);
+        Vdf::new(self.protocol_config.vdf_iterations)
+            .verify(&seed, &header.randomness)
+            .map_err(|source| ConsensusError::InvalidRandomness { block_number: header.block_number, source })
+    }
+
+    /// Check the recursive proof of a block on top of `prev_state_root` and
+    /// verify it with the zkVM backend on the coordinator's validation pool,
+    /// unless it verified before, and that what it commits to, if the
+    /// backend's proofs commit to anything, is this block's transition from
+    /// `prev_state_root`, with `transaction_count` transactions if the body
+    /// is at hand; deferred proofs are accepted, to be supplied on chain
+    /// within `proof_deadline` blocks
+    #[instrument(name = "verify_proof", skip_all, fields(block_number = header.block_number, proof_type = ?proof.proof_type))]
+    pub async fn verify_block_proof(
+        &self,
+        prev_state_root: &BlockHash,
+        header: &BlockHeader,
+        transaction_count: Option<usize>,
+        proof: &ZkProof,
+    ) -> Result<(), ProofError> {
+        if matches!(proof.proof_type, ProofType::Deferred) {
+            return Ok(());
         }
+        self.verify_proof(prev_state_root, header, transaction_count, proof)?;
+        if proof.proof_type != self.zkvm_engine.proof_type() {
+            return Err(ProofError::WrongProofType {
+                block_number: header.block_number,
+                found: proof.proof_type.clone(),
+                expected: self.zkvm_engine.proof_type(),
+            });
+        }
+        let key = ProofCacheKey { prev_state_root: *prev_state_root, tx_root: header.merkle_root };
+        if self.proof_cache.is_verified(&key, proof) {
+            debug!("🗃️ Proof of block {} verified before", header.block_number);
+            return Ok(());
+        }
+
+        let backend = self.zkvm_engine.clone();
+        let proof_data = proof.proof_data.clone();
+        let valid = self.async_coordinator.validation_pool()
+            .execute(move || async move { Ok(backend.verify_proof(&proof_data).await) })
+            .await
+            .map_err(|e| ProofError::Task(format!("{:#}", e)))??;
+        if !valid {
+            return Err(ProofError::Invalid(header.block_number));
+        }
+        self.proof_cache.insert(key, proof.clone());
+        Ok(())
     }
 
-    fn create_block_header(&self, transactions: &[Transaction], producer: Address) -> BlockHeader {
-        BlockHeader {
-            previous_hash: self.get_last_block_hash(),
-            merkle_root: BlockHash::zero(), // Will be computed separately
-            state_root: self.current_state.state_root,
+    /// Check that `header` extends this node's chain and that `proof` proves
+    /// it, without executing its transactions: the header must follow the
+    /// head, start from the current state root, come from the selected
+    /// producer with a valid beacon and stay within the gas limit, and the
+    /// proof must verify for the public inputs the header commits to. A
+    /// deferred proof does not qualify.
+    pub async fn validate_header_stateless(&self, header: &BlockHeader, proof: &ZkProof) -> Result<(), ConsensusError> {
+        let block_number = header.block_number;
+        if header.previous_hash != self.get_last_block_hash() {
+            return Err(ConsensusError::UnknownParent { block_number, parent: header.previous_hash });
+        }
+        if block_number != self.height() + 1 {
+            return Err(ConsensusError::InvalidBlock(block_number));
+        }
+        if header.prev_state_root != self.current_state.state_root {
+            return Err(ConsensusError::PrevStateRootMismatch(block_number));
+        }
+        let selected = self.select_block_producer(block_number)?;
+        if header.producer != selected {
+            return Err(ConsensusError::WrongProducer { block_number, producer: header.producer, selected });
+        }
+        self.verify_beacon(header)?;
+        let limit = self.protocol_config.gas_schedule.block_gas_limit;
+        if header.gas_limit > limit || header.gas_used > header.gas_limit {
+            return Err(ConsensusError::GasLimitExceeded { block_number, gas: header.gas_used.max(header.gas_limit), limit });
+        }
+        if matches!(proof.proof_type, ProofType::Deferred) {
+            return Err(ConsensusError::MissingProof(block_number));
+        }
+        Ok(self.verify_block_proof(&header.prev_state_root, header, None, proof).await?)
+    }
+
+    /// Submit a transaction from this node's own RPC or operator
+    pub fn add_local_transaction(&mut self, transaction: Transaction) -> Result<(), TxValidationError> {
+        self.submit_transaction(transaction, TxOrigin::Local)
+    }
+
+    /// Submit a transaction received from a peer
+    pub fn add_remote_transaction(&mut self, transaction: Transaction) -> Result<(), TxValidationError> {
+        self.submit_transaction(transaction, TxOrigin::Remote)
+    }
+
+    /// Limit block packing to what the fitted cost model predicts can be proven in time
+    pub fn set_proving_budget(&mut self, budget: Option<ProvingBudget>) {
+        if let Some(budget) = &budget {
+            info!("⏱️  Proving budget set to {:?} ({:.3} ms/tx, {:.5} ms/byte)",
+                  budget.max_proof_time, budget.model.per_transaction_ms, budget.model.per_calldata_byte_ms);
+        }
+        self.proving_budget = budget;
+    }
+
+    /// Drain the async coordinator and transaction batcher, aborting work still
+    /// running after `timeout`
+    pub async fn shutdown(&self, timeout: Duration) -> Result<()> {
+        info!("🛑 Shutting down consensus engine");
+        let (coordinator, processor) = tokio::join!(
+            self.async_coordinator.shutdown(timeout),
+            self.transaction_processor.shutdown(timeout),
+        );
+        coordinator.and(processor)
+    }
+
+    /// Produce a block that must be finished by `deadline`. Later stages see
+    /// how much of the slot is left: the block shrinks when production starts
+    /// late, and proving is deferred when too little time remains after execution.
+    #[instrument(name = "produce_block", skip_all, fields(block_number = self.height() + 1, tx_count = field::Empty, proof_type = field::Empty))]
+    pub async fn produce_block_by(&mut self, producer: Address, deadline: Deadline) -> Result<Block, ConsensusError> {
+        let _alloc = alloc::enter(Subsystem::Consensus);
+        if !self.holds_validator_key(&producer) {
+            return Err(ConsensusError::NoSigningKey(producer));
+        }
+        info!("🔨 Producing block {} with producer {:?} ({:?} left in slot)",
+              self.height() + 1, producer, deadline.remaining());
+
+        let start_time = std::time::Instant::now();
+        let build = deadline.reserving(self.slot_budget.signature_reserve);
+
+        // Collect transactions, dropping those execution rejects; dropping
+        // one can invalidate a later staking action, so repeat until all apply
+        let mut transactions = self.collect_transactions_for_block(&build);
+        let context = BlockContext {
+            number: self.height() + 1,
             timestamp: std::time::SystemTime::now()
                 .duration_since(std::time::UNIX_EPOCH)
                 .unwrap()
                 .as_secs(),
-            block_number: self.blocks.len() as u64 + 1,
-            gas_used: transactions.iter().map(|tx| tx.gas_limit).sum(),
-            gas_limit: 30_000_000, // Default gas limit
             producer,
-            extra_data: Vec::new(),
-        }
-    }
-}
-
-impl ConsensusEngine for ZkSacConsensusEngine {
-    fn produce_block(&mut self, producer: Address) -> Result<Block> {
-        info!("🔨 Producing block {} with producer {:?}", 
-              self.blocks.len() + 1, producer);
-        
-        let start_time = std::time::Instant::now();
-        
-        // Collect transactions
-        let transactions = self.collect_transactions_for_block();
+        };
+        let execution = loop {
+            self.retain_usable_validator_transactions(&mut transactions);
+            let execution = self.execute_transactions(&context, &transactions);
+            if execution.rejected().next().is_none() {
+                break execution;
+            }
+            for (index, e) in execution.rejected() {
+                warn!("🚫 Dropping transaction {:?}: {}", transactions[index].hash(), e);
+            }
+            let mut results = execution.results.iter();
+            transactions.retain(|_| results.next().is_some_and(ExecutionResult::is_included));
+        };
         debug!("📦 Collected {} transactions for block", transactions.len());
-        
-        // Execute transactions with zkVM
-        let (new_state, execution_proof) = self.execute_transactions_with_zkvm(&transactions)?;
-        
-        // Create block header
-        let header = self.create_block_header(&transactions, producer);
-        
-        // Generate recursive proof for protocol updates
-        let protocol_updates = Vec::new(); // Empty for now
-        let recursive_proof = self.generate_recursive_proof(protocol_updates.clone())?;
+        Span::current().record("tx_count", transactions.len());
+
+        // Create block header, committing to the state after execution
+        let randomness = self.evaluate_beacon(&self.get_last_block_hash(), context.number).await?;
+        let header = self.create_block_header(&transactions, &context, &execution, randomness);
 
-        let block = Block {
+        // Prove the state transition, unless the slot is nearly spent or a
+        // remote prover backfills it
+        let protocol_updates = self.expected_protocol_updates(header.block_number).to_vec();
+        let recursive_proof = if self.remote_prover.is_some() {
+            deferred_proof()
+        } else if build.has(self.slot_budget.proving_reserve) {
+            self.prove_block(&self.current_state, &self.protocol_config.gas_schedule, &header, &transactions).await?
+        } else {
+            warn!("⏳ Only {:?} left in slot, deferring proof for block {}",
+                  build.remaining(), header.block_number);
+            self.deferred_proofs.push(header.block_number);
+            deferred_proof()
+        };
+        Span::current().record("proof_type", field::debug(&recursive_proof.proof_type));
+
+        let mut block = Block {
             header,
             transactions,
-            validator_signatures: Vec::new(), // Will be added during consensus
+            validator_signatures: Vec::new(),
             recursive_proof,
             protocol_updates,
+            proof_signature: Vec::new(),
+            aggregate_signature: None,
         };
-        
+        self.collect_validator_signatures(&mut block)?;
+
+        if let Some(queue) = &self.remote_prover {
+            let job = ProofJob::for_block(&self.current_state, &self.protocol_config.gas_schedule, &block, self.zkvm_engine.proof_type());
+            match queue.submit(job) {
+                Ok(()) => debug!("🛰️ Block {} produced optimistically, proof requested remotely", block.header.block_number),
+                Err(e) => {
+                    warn!("⚠️ Remote proving unavailable for block {} ({:#}), proving it locally later", block.header.block_number, e);
+                    self.deferred_proofs.push(block.header.block_number);
+                }
+            }
+        }
+
         let elapsed = start_time.elapsed();
         info!("✅ Block {} produced in {:?}", block.header.block_number, elapsed);
+        if deadline.is_expired() {
+            warn!("⏰ Block {} overshot its slot by {:?}",
+                  block.header.block_number, elapsed.saturating_sub(deadline.budget()));
+        }
 
         Ok(block)
     }
 
-    fn validate_block(&self, block: &Block) -> Result<bool> {
+    /// Generate proofs for blocks that were produced with a deferred proof,
+    /// returning the transactions supplying them on chain so they can be
+    /// gossiped; blocks whose proving fails stay queued
+    pub async fn prove_deferred(&mut self) -> Result<Vec<Transaction>, ConsensusError> {
+        let mut pending = std::mem::take(&mut self.deferred_proofs).into_iter();
+        let mut supplied = Vec::new();
+        while let Some(block_number) = pending.next() {
+            let Some(index) = self.blocks.iter().position(|b| b.header.block_number == block_number) else {
+                // Not applied (yet); keep it queued
+                self.deferred_proofs.push(block_number);
+                continue;
+            };
+            let mut block = self.blocks[index].clone();
+            let proof = match self.state_at(block_number - 1) {
+                Ok(state) => {
+                    // The block was charged under the schedule in force after its parent
+                    let schedule = &self.snapshots[&(block_number - 1)].protocol_config.gas_schedule;
+                    self.prove_block(&state, schedule, &block.header, &block.transactions).await.map_err(ConsensusError::from)
+                }
+                Err(e) => Err(e),
+            };
+            block.recursive_proof = match proof {
+                Ok(proof) => proof,
+                Err(e) => {
+                    self.deferred_proofs.push(block_number);
+                    self.deferred_proofs.extend(pending);
+                    return Err(e);
+                }
+            };
+            // Re-signs the proof; the votes are already there
+            self.collect_validator_signatures(&mut block)?;
+            self.blocks[index] = block;
+            supplied.extend(self.supply_proof(index));
+        }
+        if !supplied.is_empty() {
+            info!("🔄 Generated {} deferred proofs", supplied.len());
+        }
+        Ok(supplied)
+    }
+
+    /// Install the proofs the remote prover finished for blocks produced
+    /// optimistically, returning the transactions supplying them on chain.
+    /// Failed jobs are left to `prove_deferred`, and proofs for blocks that
+    /// were replaced at their height are dropped.
+    pub fn backfill_remote_proofs(&mut self) -> Result<Vec<Transaction>, ConsensusError> {
+        let Some(queue) = self.remote_prover.as_mut() else {
+            return Ok(Vec::new());
+        };
+        let mut outcomes = std::mem::take(&mut self.remote_outcomes);
+        outcomes.extend(queue.finished());
+
+        let mut backfilled = Vec::new();
+        for outcome in outcomes {
+            let Some(index) = self.blocks.iter().position(|b| b.header.block_number == outcome.block_number) else {
+                // Not applied (yet); keep it for the next call
+                self.remote_outcomes.push(outcome);
+                continue;
+            };
+            if self.blocks[index].header.hash() != outcome.block_hash {
+                debug!("🗑️ Dropping remote proof for replaced block {}", outcome.block_number);
+                continue;
+            }
+            match outcome.result {
+                Ok(proof_data) => {
+                    let mut block = self.blocks[index].clone();
+                    block.recursive_proof = ZkProof {
+                        proof_data,
+                        public_inputs: block.header.public_inputs().encode(),
+                        verification_key: vec![],
+                        proof_type: self.zkvm_engine.proof_type(),
+                    };
+                    // Re-signs the proof; the votes are already there
+                    self.collect_validator_signatures(&mut block)?;
+                    self.blocks[index] = block;
+                    backfilled.extend(self.supply_proof(index));
+                }
+                Err(e) => {
+                    warn!("❌ Remote proving of block {} failed ({}), proving it locally", outcome.block_number, e);
+                    self.deferred_proofs.push(outcome.block_number);
+                }
+            }
+        }
+        if !backfilled.is_empty() {
+            info!("🛰️ Backfilled {} remote proofs", backfilled.len());
+        }
+        Ok(backfilled)
+    }
+
+    /// Put the proof just installed in the block at `index` on chain, as a
+    /// transaction signed by the block's producer, so the block can finalize
+    fn supply_proof(&mut self, index: usize) -> Option<Transaction> {
+        let block = &self.blocks[index];
+        let block_number = block.header.block_number;
+        let Some(producer) = self.validator_keys.get(&block.header.producer) else {
+            warn!("❌ No key to supply the proof of block {} with", block_number);
+            return None;
+        };
+        let mut nonce = self.current_state.accounts.get(&producer.address()).map_or(0, |account| account.nonce);
+        while self.mempool.get(&producer.address(), nonce).is_some() {
+            nonce += 1;
+        }
+        let supplied = ProofSubmission::for_block(block).to_transaction(producer, nonce)
+            .map_err(ConsensusError::signing)
+            .and_then(|transaction| {
+                self.add_local_transaction(transaction.clone())?;
+                Ok(transaction)
+            });
+        supplied.inspect_err(|e| warn!("❌ Failed to supply the proof of block {}: {:#}", block_number, e)).ok()
+    }
+
+    /// Height of the highest finalized block, 0 before any
+    pub fn finalized_height(&self) -> u64 {
+        self.finality.finalized_height()
+    }
+
+    /// Whether `block_hash` is in this node's chain at or below the finalized height
+    pub fn is_finalized(&self, block_hash: &BlockHash) -> bool {
+        self.blocks.iter()
+            .take_while(|block| block.header.block_number <= self.finalized_height())
+            .any(|block| block.header.hash() == *block_hash)
+    }
+
+    /// Attest the local block at `block_number` with every validator key this node holds
+    pub fn attest(&self, block_number: u64) -> Result<Vec<Attestation>, ConsensusError> {
+        let block_hash = block_hash_at(&self.blocks, self.base_height, block_number)
+            .ok_or(ConsensusError::UnknownBlock(block_number))?;
+        self.validator_set.validators.iter()
+            .filter_map(|validator| Some((validator, self.validator_keys.get(&validator.address)?)))
+            .map(|(validator, key)| Attestation::sign(key, validator.stake, block_number, block_hash).map_err(ConsensusError::signing))
+            .collect()
+    }
+
+    /// Count a validator's attestation, finalizing blocks that reach a quorum;
+    /// returns whether the attestation was new
+    pub fn add_attestation(&mut self, attestation: &Attestation) -> Result<bool, ConsensusError> {
+        let added = self.finality.add_attestation(&self.validator_set, attestation)?;
+        if added {
+            self.update_finality();
+        }
+        Ok(added)
+    }
+
+    fn update_finality(&mut self) {
+        let (blocks, base_height) = (&self.blocks, self.base_height);
+        // A block still owing its proof, and every block after it, is not final
+        let ceiling = self.slasher.first_deferred().map_or(u64::MAX, |owed| owed - 1);
+        let finalized = self.finality.try_finalize_up_to(self.validator_set.total_stake, ceiling, |height| block_hash_at(blocks, base_height, height));
+        if let Some(block_number) = finalized {
+            // Nothing at or below a finalized block is rolled back, and blocks
+            // still waiting for their proof, all above it, are proven against
+            // their parent
+            self.snapshots = self.snapshots.split_off(&block_number);
+            self.block_tree.prune(block_number);
+            self.mark_finalized(block_number);
+        }
+    }
+
+    /// Import a block from the network: blocks on the head are applied, others
+    /// are kept in the block tree, and the chain switches to the branch with
+    /// the most attesting stake. Votes carried by side blocks count toward it.
+    #[instrument(name = "import_block", skip_all, fields(block_number = block.header.block_number, tx_count = block.transactions.len()))]
+    pub async fn on_block_received(&mut self, block: Block) -> Result<BlockImport, ConsensusError> {
+        let hash = block.header.hash();
+        let block_number = block.header.block_number;
+        if block_hash_at(&self.blocks, self.base_height, block_number) == Some(hash) || self.block_tree.contains(&hash) {
+            return Ok(BlockImport::Known);
+        }
+        let parent = block.header.previous_hash;
+        if parent == self.get_last_block_hash() {
+            if !self.validate_block(&block).await? {
+                return Err(ConsensusError::InvalidBlock(block_number));
+            }
+            self.apply_block(block).await?;
+            return Ok(BlockImport::Extended);
+        }
+        if block_number <= self.finalized_height() {
+            return Err(ConsensusError::ConflictsWithFinalized(block_number));
+        }
+        if !self.block_tree.contains(&parent) && self.canonical_hash(block_number - 1) != Some(parent) {
+            return Err(ConsensusError::UnknownParent { block_number, parent });
+        }
+
+        for attestation in Attestation::from_block(&block) {
+            if let Err(e) = self.finality.add_attestation(&self.validator_set, &attestation) {
+                debug!("🗳️ Not counting vote on side block {}: {}", block_number, e);
+            }
+        }
+        if let Some(aggregate) = &block.aggregate_signature {
+            if let Err(e) = self.finality.add_aggregate(&self.validator_set, block_number, hash, aggregate) {
+                debug!("🗳️ Not counting aggregate vote on side block {}: {}", block_number, e);
+            }
+        }
+        info!("🌿 Stored side block {} ({})", block_number, hex_utils::hash_to_hex(&hash.0[..8]));
+        self.block_tree.insert(block);
+        self.update_fork_choice().await
+    }
+
+    /// Switch to the heaviest branch of the block tree if it outweighs the
+    /// canonical blocks it would replace
+    async fn update_fork_choice(&mut self) -> Result<BlockImport, ConsensusError> {
+        let finalized = self.finalized_height();
+        let heaviest = self.block_tree.tips().into_iter()
+            .filter_map(|tip| {
+                let branch = self.block_tree.branch(&tip);
+                let root = branch.first()?;
+                let fork_height = root.header.block_number.checked_sub(1)?;
+                if fork_height < finalized || self.canonical_hash(fork_height) != Some(root.header.previous_hash) {
+                    return None;
+                }
+                let weight = self.attested_weight(branch.iter().copied());
+                let replaced = self.attested_weight(self.blocks.iter().skip((fork_height - self.base_height) as usize));
+                (weight > replaced).then_some((weight, tip, fork_height))
+            })
+            // Equal weights are settled by hash so every node picks the same branch
+            .max_by_key(|(weight, tip, _)| (*weight, tip.0));
+        match heaviest {
+            Some((_, tip, fork_height)) => self.reorg(fork_height, &tip).await,
+            None => Ok(BlockImport::Stored),
+        }
+    }
+
+    fn attested_weight<'a>(&self, blocks: impl Iterator<Item = &'a Block>) -> u64 {
+        blocks.map(|block| self.finality.attested_stake(block.header.block_number, &block.header.hash())).sum()
+    }
+
+    /// Roll back to `fork_height` and apply the branch ending at `tip`. If one
+    /// of its blocks fails, it is dropped with its descendants and the
+    /// previous chain is restored.
+    async fn reorg(&mut self, fork_height: u64, tip: &BlockHash) -> Result<BlockImport, ConsensusError> {
+        let branch: Vec<Block> = self.block_tree.branch(tip).into_iter().cloned().collect();
+        for block in &branch {
+            self.block_tree.remove(&block.header.hash());
+        }
+        let replaced = self.roll_back(fork_height)?;
+        warn!("🔀 Reorganizing at height {}: {} blocks replaced by {}", fork_height, replaced.len(), branch.len());
+        self.events.publish_with(|| ConsensusEvent::Reorged { fork_height, depth: replaced.len() });
+
+        for block in branch {
+            let hash = block.header.hash();
+            let block_number = block.header.block_number;
+            let applied = match self.validate_block(&block).await {
+                Ok(true) => self.apply_block(block).await,
+                Ok(false) => Err(ConsensusError::InvalidBlock(block_number)),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = applied {
+                warn!("❌ Side block {} does not apply ({}), restoring the previous chain", block_number, e);
+                self.block_tree.remove_descendants(&hash);
+                // The blocks before it are valid and stay candidates
+                for block in self.roll_back(fork_height)? {
+                    self.block_tree.insert(block);
+                }
+                self.events.publish_with(|| ConsensusEvent::Reorged { fork_height, depth: 0 });
+                for block in replaced {
+                    self.apply_block(block).await?;
+                }
+                return Err(e);
+            }
+        }
+
+        self.deferred_proofs.retain(|block_number| *block_number <= fork_height);
+        let orphaned: Vec<Transaction> = replaced.iter().flat_map(|block| block.transactions.iter().cloned()).collect();
+        // The replaced blocks stay candidates unless the new branch finalized past them
+        let finalized = self.finalized_height();
+        for block in replaced.into_iter().filter(|block| block.header.block_number > finalized) {
+            self.block_tree.insert(block);
+        }
+        // Return what the new branch did not include to the pool
+        for transaction in orphaned {
+            if let Err(e) = self.submit_transaction(transaction, TxOrigin::Local) {
+                debug!("🗑️ Dropping orphaned transaction: {}", e);
+            }
+        }
+        let head = self.get_last_block_hash();
+        info!("✅ Reorganized to head {} at height {}", hex_utils::hash_to_hex(&head.0[..8]), self.height());
+        Ok(BlockImport::Reorged { fork_height, depth: (self.height() - fork_height) as usize, head })
+    }
+
+    /// Restore the chain as it stood after `height`, returning the blocks above it
+    fn roll_back(&mut self, height: u64) -> Result<Vec<Block>, ConsensusError> {
+        let snapshot = self.snapshots.get(&height).cloned().ok_or(ConsensusError::UnknownBlock(height))?;
+        for undone in self.snapshots.split_off(&(height + 1)).into_values().rev() {
+            self.current_state.apply_diff(&undone.undo);
+        }
+        self.current_state.state_root = snapshot.state_root;
+        self.validator_set = snapshot.validator_set;
+        self.slasher = snapshot.slasher;
+        self.governance = snapshot.governance;
+        self.protocol_config = snapshot.protocol_config;
+        self.transaction_hooks = load_transaction_hooks(&self.protocol_config);
+        Ok(self.blocks.split_off((height - self.base_height) as usize))
+    }
+
+    /// Hash of canonical block `block_number`, the zero hash for genesis
+    fn canonical_hash(&self, block_number: u64) -> Option<BlockHash> {
+        if block_number == self.base_height {
+            return Some(self.base_hash);
+        }
+        block_hash_at(&self.blocks, self.base_height, block_number)
+    }
+
+    fn publish_slashing(&self, block_number: u64, slashed: Slashed) {
+        warn!("⚔️  Slashed {:?} by {} for {:?} at height {}", slashed.offender, slashed.amount, slashed.offense, slashed.height);
+        let event = ValidatorEvent::Slashed { address: slashed.offender, offense: slashed.offense, amount: slashed.amount };
+        self.events.publish_with(|| ConsensusEvent::Validator { block_number, event });
+        if slashed.removed {
+            warn!("🚫 Removed validator {:?}: stake fell below {}", slashed.offender, self.protocol_config.min_stake_threshold);
+            let event = ValidatorEvent::Removed { address: slashed.offender };
+            self.events.publish_with(|| ConsensusEvent::Validator { block_number, event });
+        }
+    }
+
+    fn publish_queued(&self, block_number: u64, queued: Queued) {
+        let event = match queued {
+            Queued::Activation { address, stake, epoch } => {
+                info!("🪙 {:?} staked {}, joining in epoch {}", address, stake, epoch);
+                ValidatorEvent::Staked { address, stake, activation_epoch: epoch }
+            }
+            Queued::Exit { address, epoch } => {
+                info!("👋 {:?} unstaked, leaving in epoch {}", address, epoch);
+                ValidatorEvent::Unstaked { address, exit_epoch: epoch }
+            }
+        };
+        self.events.publish_with(|| ConsensusEvent::Validator { block_number, event });
+    }
+
+    /// Switch to the validator set and protocol rules of the epoch following
+    /// block `block_number`
+    fn enter_next_epoch(&mut self, block_number: u64) {
+        let epoch = self.protocol_config.epoch_schedule.epoch_of(block_number + 1);
+        let rules = self.governance.enter_epoch(epoch, &mut self.protocol_config);
+        if rules.iter().any(|rule| rule.rule_id == RuleChange::TRANSACTION_HOOKS) {
+            self.transaction_hooks = load_transaction_hooks(&self.protocol_config);
+        }
+        for rule in rules {
+            info!("📜 Epoch {}: rule {} is now in effect", epoch, rule.rule_id);
+            let event = GovernanceEvent::Activated { proposal: rule_commitment(&rule), rule_id: rule.rule_id, epoch };
+            self.events.publish_with(|| ConsensusEvent::Governance { block_number, event });
+        }
+        let transition = self.validator_set.enter_epoch(epoch);
+        if transition.activated.is_empty() && transition.exited.is_empty() {
+            return;
+        }
+        info!("🔁 Epoch {}: {} validators joined, {} left, {} active with {} stake",
+              epoch, transition.activated.len(), transition.exited.len(),
+              self.validator_set.validators.len(), self.validator_set.total_stake);
+        for validator in transition.activated {
+            let event = ValidatorEvent::Activated { address: validator.address, stake: validator.stake };
+            self.events.publish_with(|| ConsensusEvent::Validator { block_number, event });
+        }
+        for validator in transition.exited {
+            let event = ValidatorEvent::Exited { address: validator.address };
+            self.events.publish_with(|| ConsensusEvent::Validator { block_number, event });
+        }
+    }
+
+    fn publish_governance(&self, block_number: u64, outcome: GovernanceOutcome) {
+        let event = match outcome {
+            GovernanceOutcome::Proposed { proposal, rule_id, voting_ends } => {
+                info!("📜 Rule {} proposed, voting ends with epoch {}", rule_id, voting_ends);
+                GovernanceEvent::Proposed { proposal, rule_id, voting_ends }
+            }
+            GovernanceOutcome::Voted { proposal, voter, approve } => GovernanceEvent::Voted { proposal, voter, approve },
+            GovernanceOutcome::Approved { proposal, activation_epoch } => {
+                info!("🗳️  Rule proposal {} approved, activating in epoch {}", hex_utils::hash_to_hex(&proposal.0[..8]), activation_epoch);
+                GovernanceEvent::Approved { proposal, activation_epoch }
+            }
+            GovernanceOutcome::Rejected { proposal, approving_stake } => {
+                info!("🗳️  Rule proposal {} rejected with {} stake approving", hex_utils::hash_to_hex(&proposal.0[..8]), approving_stake);
+                GovernanceEvent::Rejected { proposal, approving_stake }
+            }
+        };
+        self.events.publish_with(|| ConsensusEvent::Governance { block_number, event });
+    }
+
+    /// Rules the block `block_number` must list as its protocol updates:
+    /// those that took effect in its epoch if it is the epoch's first block
+    fn expected_protocol_updates(&self, block_number: u64) -> &[ProtocolRule] {
+        let schedule = &self.protocol_config.epoch_schedule;
+        if block_number == 0 || !schedule.ends_epoch(block_number - 1) {
+            return &[];
+        }
+        self.governance.activated_in(schedule.epoch_of(block_number))
+    }
+
+    fn check_protocol_updates(&self, block: &Block) -> Result<(), ConsensusError> {
+        let expected = self.expected_protocol_updates(block.header.block_number);
+        if !block.protocol_updates.iter().map(rule_commitment).eq(expected.iter().map(rule_commitment)) {
+            return Err(ConsensusError::ProtocolUpdatesMismatch(block.header.block_number));
+        }
+        Ok(())
+    }
+
+    /// Record that all blocks up to `block_number` are final
+    fn mark_finalized(&self, block_number: u64) {
+        info!("🔒 Finalized block {}", block_number);
+        self.tx_latency.finalized(block_number);
+        // Finalized blocks are canonical
+        let block_hash = self.canonical_hash(block_number).unwrap_or_else(BlockHash::zero);
+        self.events.publish_with(|| ConsensusEvent::Finalized { block_number, block_hash });
+    }
+
+    /// Add the validator a verified registration proves control of
+    pub fn register_validator(&mut self, registration: &ValidatorRegistration) -> Result<(), ConsensusError> {
+        registration.verify().map_err(|e| ConsensusError::InvalidRegistration(format!("{:#}", e)))?;
+        if registration.stake < self.protocol_config.min_stake_threshold {
+            return Err(StakingError::BelowMinimum { stake: registration.stake, minimum: self.protocol_config.min_stake_threshold }.into());
+        }
+        if self.validator_set.validators.iter().any(|v| v.address == registration.address) {
+            return Err(ConsensusError::AlreadyRegistered(registration.address));
+        }
+
+        self.validator_set.validators.push(Validator {
+            address: registration.address,
+            stake: registration.stake,
+            public_key: registration.public_key.clone(),
+            performance_score: 1.0,
+            bls_public_key: Vec::new(),
+        });
+        self.account_keys.entry(registration.address).or_insert_with(|| registration.public_key.clone());
+        self.validator_set.total_stake += registration.stake;
+        info!("🆕 Registered validator {:?} with stake {}", registration.address, registration.stake);
+
+        let event = ValidatorEvent::Registered { address: registration.address, stake: registration.stake };
+        self.events.publish_with(|| ConsensusEvent::Validator { block_number: self.height(), event });
+        Ok(())
+    }
+
+    /// Switch a validator to the new key of a verified rotation
+    pub fn rotate_validator_key(&mut self, rotation: &KeyRotation) -> Result<(), ConsensusError> {
+        rotation.verify().map_err(|e| ConsensusError::InvalidRegistration(format!("{:#}", e)))?;
+        let validator = self.validator_set.validators.iter_mut()
+            .find(|v| v.address == rotation.validator)
+            .ok_or(ConsensusError::UnknownValidator(rotation.validator))?;
+        if validator.public_key != rotation.old_public_key {
+            return Err(ConsensusError::StaleRotation(rotation.validator));
+        }
+        validator.public_key = rotation.new_public_key.clone();
+        info!("🔄 Rotated key of validator {:?} from epoch {}", rotation.validator, rotation.effective_epoch);
+
+        let event = ValidatorEvent::KeyRotated { address: rotation.validator, effective_epoch: rotation.effective_epoch };
+        self.events.publish_with(|| ConsensusEvent::Validator { block_number: self.height(), event });
+        Ok(())
+    }
+
+    fn submit_transaction(&mut self, transaction: Transaction, origin: TxOrigin) -> Result<(), TxValidationError> {
+        // Blocks carrying badly signed transactions are rejected, so keep them out of the pool
+        self.verify_transaction_signature(&transaction)?;
+        let schedule = &self.protocol_config.gas_schedule;
+        let intrinsic = schedule.intrinsic_gas(&transaction);
+        if transaction.gas_limit < intrinsic {
+            return Err(TxValidationError::IntrinsicGasTooLow { gas_limit: transaction.gas_limit, intrinsic });
+        }
+        if transaction.gas_limit > schedule.block_gas_limit {
+            return Err(TxValidationError::GasLimitTooHigh { gas_limit: transaction.gas_limit, block_limit: schedule.block_gas_limit });
+        }
+        if transaction.data.len() > schedule.max_data_bytes {
+            return Err(TxValidationError::DataTooLarge { size: transaction.data.len(), limit: schedule.max_data_bytes });
+        }
+        let nonce = self.current_state.accounts.get(&transaction.from).map_or(0, |account| account.nonce);
+        if transaction.nonce < nonce {
+            return Err(TxValidationError::StaleNonce { nonce: transaction.nonce, expected: nonce });
+        }
+        if [SLASHING_ADDRESS, PROOF_ADDRESS, STAKING_ADDRESS, GOVERNANCE_ADDRESS, BRIDGE_ADDRESS].contains(&transaction.to) {
+            let block_number = self.height() + 1;
+            self.apply_validator_transaction(&mut self.unchanged_validators(), block_number, &transaction)?;
+        }
+        let hash = transaction.hash();
+        self.tx_latency.received(hash);
+        match self.mempool.add(transaction, origin) {
+            Ok(()) => {
+                self.tx_latency.pooled(hash);
+                self.events.publish_with(|| ConsensusEvent::TransactionPooled { hash });
+                Ok(())
+            }
+            Err(e) => {
+                self.tx_latency.forget(&hash);
+                Err(e)
+            }
+        }
+    }
+
+    /// Collect transactions, shrinking the block when production starts late in
+    /// the slot and keeping estimated proving time within what is left
+    fn collect_transactions_for_block(&mut self, deadline: &Deadline) -> Vec<Transaction> {
+        let full = self.protocol_config.max_transactions_per_block;
+        let max_tx = ((full as f64 * deadline.fraction_remaining()).ceil() as usize).min(full);
+        if max_tx < full {
+            debug!("⏳ Slot {:.0}% used, packing at most {} transactions",
+                   (1.0 - deadline.fraction_remaining()) * 100.0, max_tx);
+        }
+
+        self.mempool.evict_expired();
+        // Owed proofs hold back finality, so they go in however late the slot is
+        let owed: Vec<(Address, u64)> = self.mempool.iter()
+            .map(|pooled| &pooled.transaction)
+            .filter(|tx| tx.to == PROOF_ADDRESS && tx.nonce == self.current_state.accounts.get(&tx.from).map_or(0, |account| account.nonce))
+            .map(|tx| (tx.from, tx.nonce))
+            .collect();
+        let mut proofs: Vec<Transaction> = owed.iter()
+            .filter_map(|(sender, nonce)| self.mempool.take(sender, *nonce))
+            .collect();
+        // Packing stops at the block gas limit, counting each transaction's full gas limit
+        let mut gas_left = proofs.iter()
+            .fold(self.protocol_config.gas_schedule.block_gas_limit, |left, tx| left.saturating_sub(tx.gas_limit));
+        let collected = match &self.proving_budget {
+            Some(budget) => {
+                let window = ProvingBudget {
+                    max_proof_time: budget.max_proof_time.min(deadline.remaining()),
+                    ..*budget
+                };
+                let mut tracker = window.tracker();
+                let collected = self.mempool.take_for_block_within(max_tx, |tx| {
+                    if tx.gas_limit > gas_left || !tracker.try_add(tx) {
+                        return false;
+                    }
+                    gas_left -= tx.gas_limit;
+                    true
+                });
+                debug!("⏱️  Packed block estimated at {:.1} ms of proving", tracker.estimated_ms());
+                collected
+            }
+            None => self.mempool.take_for_block_within(max_tx, |tx| match gas_left.checked_sub(tx.gas_limit) {
+                Some(left) => {
+                    gas_left = left;
+                    true
+                }
+                None => false,
+            }),
+        };
+        
+        proofs.extend(collected);
+        debug!("📦 Collected {} transactions for block production", proofs.len());
+        proofs
+    }
+
+    /// Beacon randomness of the block before `block_number`; slots past the head are seeded from the head
+    fn parent_randomness(&self, block_number: u64) -> [u8; 32] {
+        let parent = match block_number.checked_sub(1) {
+            Some(parent) if parent > self.base_height => parent,
+            _ => return self.base_randomness,
+        };
+        let index = usize::try_from(parent - self.base_height - 1).unwrap_or(usize::MAX);
+        self.blocks.get(index).or(self.blocks.last())
+            .map_or(self.base_randomness, |block| beacon_randomness(&block.header.randomness))
+    }
+
+    fn get_last_block_hash(&self) -> BlockHash {
+        if let Some(last_block) = self.blocks.last() {
+            last_block.header.hash()
+        } else {
+            self.base_hash // Genesis or the snapshot block
+        }
+    }
+
+    fn create_block_header(&self, transactions: &[Transaction], context: &BlockContext, execution: &BlockExecution, randomness: RandomnessProof) -> BlockHeader {
+        BlockHeader {
+            previous_hash: self.get_last_block_hash(),
+            merkle_root: Block::transactions_root(transactions),
+            prev_state_root: self.current_state.state_root,
+            state_root: execution.state_root,
+            timestamp: context.timestamp,
+            block_number: context.number,
+            gas_used: execution.gas_used,
+            gas_limit: self.protocol_config.gas_schedule.block_gas_limit,
+            producer: context.producer,
+            extra_data: Vec::new(),
+            randomness,
+        }
+    }
+}
+
+#[async_trait]
+impl ConsensusEngine for ZkSacConsensusEngine {
+    async fn produce_block(&mut self, producer: Address) -> Result<Block, ConsensusError> {
+        let deadline = Deadline::after(self.protocol_config.block_time);
+        self.produce_block_by(producer, deadline).await
+    }
+
+    #[instrument(name = "validate_block", skip_all, fields(block_number = block.header.block_number, tx_count = block.transactions.len(), proof_type = ?block.recursive_proof.proof_type))]
+    async fn validate_block(&self, block: &Block) -> Result<bool, ConsensusError> {
+        let _alloc = alloc::enter(Subsystem::Consensus);
         debug!("🔍 Validating block {}", block.header.block_number);
         
         // Basic validation
@@ -217,17 +1654,66 @@ impl ConsensusEngine for ZkSacConsensusEngine {
             warn!("❌ Invalid previous hash");
             return Ok(false);
         }
+
+        if block.header.block_number != self.height() + 1 {
+            warn!("❌ Block number {} does not follow height {}", block.header.block_number, self.height());
+            return Ok(false);
+        }
+
+        let selected = self.select_block_producer(block.header.block_number)?;
+        if block.header.producer != selected {
+            warn!("❌ Block produced by {:?}, but {:?} was selected", block.header.producer, selected);
+            return Ok(false);
+        }
+
+        if let Err(e) = self.verify_beacon(&block.header) {
+            warn!("❌ {}", e);
+            return Ok(false);
+        }
         
         if block.transactions.len() > self.protocol_config.max_transactions_per_block {
             warn!("❌ Too many transactions in block");
             return Ok(false);
         }
+
+        if block.header.merkle_root != Block::transactions_root(&block.transactions) {
+            warn!("❌ Transaction Merkle root does not match the block's transactions");
+            return Ok(false);
+        }
+
+        if block.header.prev_state_root != self.current_state.state_root {
+            warn!("❌ Block {} does not start from the current state root", block.header.block_number);
+            return Ok(false);
+        }
+
+        if let Err(e) = self.verify_block_signatures(block) {
+            warn!("❌ {}", e);
+            return Ok(false);
+        }
+
+        let execution = self.execute_transactions(&BlockContext::of(&block.header), &block.transactions);
+        if execution.state_root != block.header.state_root {
+            warn!("❌ State root does not match the state after executing block {}", block.header.block_number);
+            return Ok(false);
+        }
+
+        if let Err(e) = self.check_execution(&block.header, &block.transactions, &execution) {
+            warn!("❌ {}", e);
+            return Ok(false);
+        }
         
-        // Verify zk-proof (mock for sync execution)
-        let verified = true; // Mock verification
-        
-        if !verified {
-            warn!("❌ ZK proof verification failed");
+        if let Err(e) = self.verify_block_proof(&self.current_state.state_root, &block.header, Some(block.transactions.len()), &block.recursive_proof).await {
+            warn!("❌ ZK proof verification failed: {}", e);
+            return Ok(false);
+        }
+
+        if let Err(e) = self.validator_changes(block.header.block_number, &block.transactions) {
+            warn!("❌ Block {} carries unusable slashing evidence, staking or governance: {}", block.header.block_number, e);
+            return Ok(false);
+        }
+
+        if let Err(e) = self.check_protocol_updates(block) {
+            warn!("❌ {}", e);
             return Ok(false);
         }
         
@@ -235,30 +1721,111 @@ impl ConsensusEngine for ZkSacConsensusEngine {
         Ok(true)
     }
 
-    fn apply_block(&mut self, block: Block) -> Result<()> {
+    #[instrument(name = "apply_block", skip_all, fields(block_number = block.header.block_number, tx_count = block.transactions.len()))]
+    async fn apply_block(&mut self, block: Block) -> Result<(), ConsensusError> {
+        let _alloc = alloc::enter(Subsystem::Consensus);
         info!("📝 Applying block {} to chain", block.header.block_number);
+        let rejected = |source: ConsensusError| ConsensusError::Rejected { block_number: block.header.block_number, source: Box::new(source) };
+        self.verify_block_signatures(&block).map_err(rejected)?;
+        self.verify_beacon(&block.header).map_err(rejected)?;
         
         // Update current state by re-executing transactions
-        let (new_state, _) = self.execute_transactions_with_zkvm(&block.transactions)?;
-        self.current_state = new_state;
+        let execution = self.execute_transactions(&BlockContext::of(&block.header), &block.transactions);
+        if execution.state_root != block.header.state_root {
+            return Err(rejected(ConsensusError::StateRootMismatch(block.header.block_number)));
+        }
+        self.check_execution(&block.header, &block.transactions, &execution).map_err(rejected)?;
+        self.check_protocol_updates(&block).map_err(rejected)?;
+        let block_number = block.header.block_number;
+        let mut changes = self.validator_changes(block_number, &block.transactions)
+            .map_err(|e| rejected(e.into()))?;
+        // Producers owing a proof past the deadline are slashed, and a block
+        // with a deferred proof owes one from here on
+        let overdue = changes.slasher.punish_overdue(&mut changes.validators, block_number, &self.protocol_config);
+        changes.slashed.extend(overdue);
+        if matches!(block.recursive_proof.proof_type, ProofType::Deferred) {
+            if let Some(producer) = self.validator_set.validators.iter().find(|v| v.address == block.header.producer) {
+                changes.slasher.defer(&block, producer);
+            }
+        }
+        let undo = execution.diff.undo(&self.current_state);
+        execution.apply_to(&mut self.current_state);
+        self.validator_set = changes.validators;
+        self.slasher = changes.slasher;
+        self.governance = changes.governance;
+        for slashed in changes.slashed {
+            self.publish_slashing(block_number, slashed);
+        }
+        for submission in changes.proven {
+            if let Some(proven) = self.blocks.iter_mut().find(|b| b.header.block_number == submission.block_number) {
+                proven.recursive_proof = submission.proof;
+                proven.proof_signature = submission.proof_signature;
+            }
+        }
+        for queued in changes.queued {
+            self.publish_queued(block_number, queued);
+        }
+        for outcome in changes.governed {
+            self.publish_governance(block_number, outcome);
+        }
         
+        // Drop included transactions from the pool
+        self.mempool.mark_included(&block.transactions, block.header.block_number);
+
+        // Applied blocks carry a validated proof, so inclusion and proving coincide here
+        let hashes: Vec<BlockHash> = block.transactions.iter().map(Transaction::hash).collect();
+        self.tx_latency.included(&hashes, block.header.block_number);
+        self.tx_latency.proven(block.header.block_number);
+        self.events.publish_with(|| ConsensusEvent::block_applied(&block, &execution.results, &self.current_state, &undo));
+
+        // The block's votes are attestations for it
+        let attestations = Attestation::from_block(&block);
+        let aggregate = block.aggregate_signature.clone().map(|aggregate| (block.header.hash(), aggregate));
+
         // Add block to chain
         self.blocks.push(block);
         
-        info!("✅ Block applied successfully. Chain length: {}", self.blocks.len());
+        info!("✅ Block applied successfully. Chain length: {}", self.height());
+        for attestation in &attestations {
+            if let Err(e) = self.finality.add_attestation(&self.validator_set, attestation) {
+                warn!("❌ Ignoring block vote: {}", e);
+            }
+        }
+        if let Some((hash, aggregate)) = &aggregate {
+            if let Err(e) = self.finality.add_aggregate(&self.validator_set, block_number, *hash, aggregate) {
+                warn!("❌ Ignoring aggregate block vote: {}", e);
+            }
+        }
+        self.update_finality();
+        if self.protocol_config.epoch_schedule.ends_epoch(block_number) {
+            let epoch = self.protocol_config.epoch_schedule.epoch_of(block_number);
+            for outcome in self.governance.close_voting(epoch, &self.validator_set) {
+                self.publish_governance(block_number, outcome);
+            }
+            self.enter_next_epoch(block_number);
+        }
+        self.snapshots.insert(block_number, ChainSnapshot {
+            undo,
+            state_root: self.current_state.state_root,
+            validator_set: self.validator_set.clone(),
+            slasher: self.slasher.clone(),
+            governance: self.governance.clone(),
+            protocol_config: self.protocol_config.clone(),
+        });
         Ok(())
     }
 
-    fn select_block_producer(&self, block_number: u64) -> Result<Address> {
+    fn select_block_producer(&self, block_number: u64) -> Result<Address, ConsensusError> {
         if self.validator_set.validators.is_empty() {
-            return Err(anyhow!("No validators available"));
+            return Err(ConsensusError::NoValidators);
         }
         
-        // Simple round-robin selection based on block number
-        let index = (block_number as usize) % self.validator_set.validators.len();
-        let selected = &self.validator_set.validators[index];
+        // Stake-weighted draw from the previous block's beacon randomness
+        let randomness = selection_randomness(&self.parent_randomness(block_number), block_number);
+        let selected = select_weighted(&self.validator_set.validators, &randomness)
+            .ok_or(ConsensusError::NoStake)?;
         
-        info!("🎯 Selected validator {:?} for block {}", selected.address, block_number);
+        debug!("🎯 Selected validator {:?} for block {}", selected.address, block_number);
         Ok(selected.address)
     }
 }
\ No newline at end of file
@@ -0,0 +1,104 @@
+//! Errors from the consensus engine
+
+use super::execution::ExecutionError;
+use super::finality::FinalityError;
+use super::genesis::GenesisError;
+use super::governance::GovernanceError;
+use super::slashing::SlashingError;
+use super::staking::StakingError;
+use crate::crypto::bls::BlsError;
+use crate::crypto::randomness::RandomnessError;
+use crate::mempool::TxValidationError;
+use crate::state::SnapshotError;
+use crate::types::{Address, BlockHash};
+use crate::zkvm::ProofError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ConsensusError {
+    #[error("{0:?} is not a validator")]
+    UnknownValidator(Address),
+    #[error("key does not match the registered public key of {0:?}")]
+    KeyMismatch(Address),
+    #[error("no signing key for {0:?}")]
+    NoSigningKey(Address),
+    #[error("no validator key to sign with")]
+    NoValidatorKey,
+    #[error("no validators available")]
+    NoValidators,
+    #[error("no validator has stake")]
+    NoStake,
+    #[error("no block {0}")]
+    UnknownBlock(u64),
+    #[error("block {0} is invalid")]
+    InvalidBlock(u64),
+    #[error("parent {parent:?} of block {block_number} is unknown")]
+    UnknownParent { block_number: u64, parent: BlockHash },
+    #[error("block {0} conflicts with the finalized chain")]
+    ConflictsWithFinalized(u64),
+    #[error("signing failed: {0}")]
+    Signing(String),
+    #[error("transaction {index} is invalid: {source}")]
+    InvalidTransaction { index: usize, source: TxValidationError },
+    #[error("validator {0:?} signed more than once")]
+    DuplicateVote(Address),
+    #[error("invalid signature from validator {validator:?}: {reason}")]
+    InvalidVote { validator: Address, reason: String },
+    #[error("invalid aggregate vote: {0}")]
+    InvalidAggregate(BlsError),
+    #[error("block {block_number} is not signed by its producer {producer:?}")]
+    UnsignedByProducer { block_number: u64, producer: Address },
+    #[error("invalid proof signature from producer {producer:?}: {reason}")]
+    InvalidProofSignature { producer: Address, reason: String },
+    #[error("randomness beacon of block {block_number} is invalid: {source}")]
+    InvalidRandomness { block_number: u64, source: RandomnessError },
+    #[error("randomness beacon evaluation failed: {0}")]
+    Beacon(String),
+    #[error("state root of block {0} does not match its transactions")]
+    StateRootMismatch(u64),
+    #[error("block {0} does not start from the current state root")]
+    PrevStateRootMismatch(u64),
+    #[error("block {block_number} produced by {producer:?}, but {selected:?} was selected")]
+    WrongProducer { block_number: u64, producer: Address, selected: Address },
+    #[error("block {0} has no proof yet")]
+    MissingProof(u64),
+    #[error("protocol updates of block {0} are not the rules that took effect in its epoch")]
+    ProtocolUpdatesMismatch(u64),
+    #[error("transaction {index} of block {block_number} does not execute: {source}")]
+    RejectedTransaction { block_number: u64, index: usize, source: ExecutionError },
+    #[error("block {block_number} asks for {gas} gas, over the limit of {limit}")]
+    GasLimitExceeded { block_number: u64, gas: u64, limit: u64 },
+    #[error("block {block_number} reports {header} gas used, but its transactions use {executed}")]
+    GasUsedMismatch { block_number: u64, header: u64, executed: u64 },
+    #[error("refusing to apply block {block_number}: {source}")]
+    Rejected { block_number: u64, source: Box<ConsensusError> },
+    #[error("invalid registration: {0}")]
+    InvalidRegistration(String),
+    #[error("validator {0:?} is already registered")]
+    AlreadyRegistered(Address),
+    #[error("rotation is not signed by the current key of {0:?}")]
+    StaleRotation(Address),
+    #[error(transparent)]
+    Proof(#[from] ProofError),
+    #[error(transparent)]
+    Transaction(#[from] TxValidationError),
+    #[error(transparent)]
+    Governance(#[from] GovernanceError),
+    #[error(transparent)]
+    Slashing(#[from] SlashingError),
+    #[error(transparent)]
+    Staking(#[from] StakingError),
+    #[error(transparent)]
+    Finality(#[from] FinalityError),
+    #[error(transparent)]
+    Snapshot(#[from] SnapshotError),
+    #[error(transparent)]
+    Genesis(#[from] GenesisError),
+}
+
+impl ConsensusError {
+    /// Signing failures come from the crypto layer, which reports them with `anyhow`
+    pub(crate) fn signing(error: anyhow::Error) -> Self {
+        Self::Signing(format!("{:#}", error))
+    }
+}
@@ -0,0 +1,168 @@
+//! Consensus event bus
+//!
+//! The engine publishes what happens to the chain (applied blocks with their
+//! receipts and balance changes, finality, validator set changes, governance,
+//! transactions entering the pool) on a
+//! broadcast channel, and the performance monitor can forward its samples
+//! onto the same bus through the [`MetricsSink`] impl. Consumers such as the indexer subscribe instead
+//! of polling the engine. Events are only built while someone is subscribed.
+
+use super::execution::ExecutionResult;
+use super::slashing::Offense;
+use crate::performance::event_log::TelemetryEvent;
+use crate::performance::sink::MetricsSink;
+use crate::performance::SystemBenchmark;
+use crate::state::StateDiff;
+use crate::types::{Address, Block, BlockHash, WorldState};
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+/// Capacity of the event channel; slow subscribers lag past this many events
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Outcome of one transaction in an applied block
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionReceipt {
+    pub transaction_hash: BlockHash,
+    pub block_hash: BlockHash,
+    pub block_number: u64,
+    pub index: u32,
+    pub gas_used: u64,
+    /// False for a contract transaction that reverted; it still paid for its gas
+    #[serde(default = "included")]
+    pub success: bool,
+}
+
+fn included() -> bool {
+    true
+}
+
+/// Receipts for every transaction in `block`, whose execution had `results`
+pub fn receipts(block: &Block, results: &[ExecutionResult]) -> Vec<TransactionReceipt> {
+    let block_hash = block.header.hash();
+    block.transactions.iter().enumerate()
+        .zip(results)
+        .map(|((index, tx), result)| TransactionReceipt {
+            transaction_hash: tx.hash(),
+            block_hash,
+            block_number: block.header.block_number,
+            index: index as u32,
+            gas_used: result.gas_used(),
+            success: matches!(result, ExecutionResult::Applied { .. }),
+        })
+        .collect()
+}
+
+/// New balance of an account a block changed; removed accounts have 0
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BalanceChange {
+    pub address: Address,
+    pub balance: u64,
+}
+
+/// Accounts whose balance differs between `after` and the state `undo`
+/// restores, by address
+pub fn balance_changes(after: &WorldState, undo: &StateDiff) -> Vec<BalanceChange> {
+    undo.accounts.keys()
+        .map(|address| BalanceChange { address: *address, balance: after.accounts.get(address).map_or(0, |account| account.balance) })
+        .filter(|change| undo.balance(after, &change.address) != change.balance)
+        .collect()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ValidatorEvent {
+    Registered { address: Address, stake: u64 },
+    KeyRotated { address: Address, effective_epoch: u64 },
+    Slashed { address: Address, offense: Offense, amount: u64 },
+    /// Left the set after slashing took its stake below the minimum
+    Removed { address: Address },
+    /// Queued to join the set in `activation_epoch`
+    Staked { address: Address, stake: u64, activation_epoch: u64 },
+    /// Queued to leave the set in `exit_epoch`
+    Unstaked { address: Address, exit_epoch: u64 },
+    /// Joined the set at an epoch boundary
+    Activated { address: Address, stake: u64 },
+    /// Left the set at an epoch boundary
+    Exited { address: Address },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GovernanceEvent {
+    /// Rule `rule_id` was proposed; votes count until `voting_ends` is over
+    Proposed { proposal: BlockHash, rule_id: u32, voting_ends: u64 },
+    Voted { proposal: BlockHash, voter: Address, approve: bool },
+    /// Two thirds of the stake approved; the rule takes effect in `activation_epoch`
+    Approved { proposal: BlockHash, activation_epoch: u64 },
+    Rejected { proposal: BlockHash, approving_stake: u64 },
+    /// Rule `rule_id` took effect at the start of `epoch`
+    Activated { proposal: BlockHash, rule_id: u32, epoch: u64 },
+}
+
+#[derive(Debug, Clone)]
+pub enum ConsensusEvent {
+    BlockApplied { block: Arc<Block>, receipts: Vec<TransactionReceipt>, balances: Vec<BalanceChange> },
+    /// Every block up to `block_number`, whose hash is `block_hash`, is final
+    Finalized { block_number: u64, block_hash: BlockHash },
+    /// The `depth` blocks above `fork_height` were rolled back for a heavier
+    /// branch, whose blocks follow as `BlockApplied`
+    Reorged { fork_height: u64, depth: usize },
+    Validator { block_number: u64, event: ValidatorEvent },
+    Governance { block_number: u64, event: GovernanceEvent },
+    /// A transaction entered the pool
+    TransactionPooled { hash: BlockHash },
+    Performance(SystemBenchmark),
+}
+
+impl ConsensusEvent {
+    /// `block` took the state to `after`, and `undo` takes it back
+    pub fn block_applied(block: &Block, results: &[ExecutionResult], after: &WorldState, undo: &StateDiff) -> Self {
+        ConsensusEvent::BlockApplied {
+            block: Arc::new(block.clone()),
+            receipts: receipts(block, results),
+            balances: balance_changes(after, undo),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct EventBus {
+    sender: broadcast::Sender<ConsensusEvent>,
+}
+
+impl Default for EventBus {
+    fn default() -> Self {
+        let (sender, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self { sender }
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Subscribe to events from this point on
+    pub fn subscribe(&self) -> broadcast::Receiver<ConsensusEvent> {
+        self.sender.subscribe()
+    }
+
+    /// Publish the event `build` creates, if anyone is listening
+    pub fn publish_with(&self, build: impl FnOnce() -> ConsensusEvent) {
+        if self.sender.receiver_count() > 0 {
+            // Subscribers may drop between the check and the send; that is fine
+            let _ = self.sender.send(build());
+        }
+    }
+}
+
+/// Forwards the monitor's benchmarks onto the bus
+impl MetricsSink for EventBus {
+    fn record(&mut self, event: &TelemetryEvent) -> Result<()> {
+        if let TelemetryEvent::Benchmark(benchmark) = event {
+            self.publish_with(|| ConsensusEvent::Performance(benchmark.clone()));
+        }
+        Ok(())
+    }
+}
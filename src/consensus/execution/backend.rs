@@ -0,0 +1,214 @@
+//! Execution backends
+//!
+//! [`ExecutionBackend`] is the boundary between consensus and the environment
+//! transactions run in: it applies the transactions of a block, runs a
+//! transaction without committing it, and builds the input the state
+//! transition guest proves a block's execution with. [`execution_backend`]
+//! picks one from [`ProtocolConfig::execution_backend`]:
+//!
+//! - [`NativeBackend`] runs transfers and bridge transactions and rejects
+//!   contract transactions
+//! - [`EvmBackend`] also runs contract transactions on revm with the `evm`
+//!   feature; without it, it rejects them like the native backend
+//! - [`GuestBackend`] only includes the transactions the state transition
+//!   guest proves, plain transfers, so every block it produces can be
+//!   proven as long as no protocol hooks change the fees
+
+use super::{BlockContext, BlockExecution, CallOutput, ExecutionError, TransactionHooks, is_contract};
+use crate::bridge::BRIDGE_ADDRESS;
+use crate::state::StateOverlay;
+use crate::types::*;
+use crate::zkvm::programs::guest_program::{StateTransitionInput, TransactionData, accepts_transaction};
+use crate::zkvm::state_transition_input;
+use std::sync::Arc;
+
+pub trait ExecutionBackend: Send + Sync {
+    fn kind(&self) -> ExecutionBackendKind;
+    /// Apply `transactions` to `state` in the block `context` describes,
+    /// charging fees under `schedule`, or `hooks` if installed
+    fn execute(
+        &self,
+        state: &WorldState,
+        schedule: &GasSchedule,
+        hooks: Option<&dyn TransactionHooks>,
+        context: &BlockContext,
+        transactions: &[Transaction],
+    ) -> BlockExecution;
+    /// Run `transaction` on `state` and return its output, leaving `state` as it is
+    fn call(
+        &self,
+        state: &WorldState,
+        schedule: &GasSchedule,
+        hooks: Option<&dyn TransactionHooks>,
+        context: &BlockContext,
+        transaction: &Transaction,
+    ) -> Result<CallOutput, ExecutionError>;
+    /// Guest input proving that `transactions`, charged under `schedule`,
+    /// take `state`, the state at `header.prev_state_root`, to the block
+    /// `header` describes
+    fn proof_input(&self, state: &WorldState, schedule: &GasSchedule, header: &BlockHeader, transactions: &[Transaction]) -> StateTransitionInput {
+        state_transition_input(state, schedule, header, transactions)
+    }
+}
+
+/// Transfers and bridge transactions, without contracts
+#[derive(Debug, Clone, Default)]
+pub struct NativeBackend;
+
+impl NativeBackend {
+    fn admit(state: &StateOverlay, transaction: &Transaction) -> Result<(), ExecutionError> {
+        if is_contract(state, transaction) {
+            return Err(ExecutionError::ContractsUnsupported);
+        }
+        Ok(())
+    }
+}
+
+impl ExecutionBackend for NativeBackend {
+    fn kind(&self) -> ExecutionBackendKind {
+        ExecutionBackendKind::Native
+    }
+
+    fn execute(
+        &self,
+        state: &WorldState,
+        schedule: &GasSchedule,
+        hooks: Option<&dyn TransactionHooks>,
+        context: &BlockContext,
+        transactions: &[Transaction],
+    ) -> BlockExecution {
+        super::execute_admitted(state, schedule, hooks, context, transactions, Self::admit)
+    }
+
+    fn call(
+        &self,
+        state: &WorldState,
+        schedule: &GasSchedule,
+        hooks: Option<&dyn TransactionHooks>,
+        context: &BlockContext,
+        transaction: &Transaction,
+    ) -> Result<CallOutput, ExecutionError> {
+        Self::admit(&StateOverlay::new(state), transaction)?;
+        super::call(state, schedule, hooks, context, transaction)
+    }
+}
+
+/// Native execution with contract transactions on revm
+#[derive(Debug, Clone, Default)]
+pub struct EvmBackend;
+
+impl ExecutionBackend for EvmBackend {
+    fn kind(&self) -> ExecutionBackendKind {
+        ExecutionBackendKind::Evm
+    }
+
+    fn execute(
+        &self,
+        state: &WorldState,
+        schedule: &GasSchedule,
+        hooks: Option<&dyn TransactionHooks>,
+        context: &BlockContext,
+        transactions: &[Transaction],
+    ) -> BlockExecution {
+        super::execute(state, schedule, hooks, context, transactions)
+    }
+
+    fn call(
+        &self,
+        state: &WorldState,
+        schedule: &GasSchedule,
+        hooks: Option<&dyn TransactionHooks>,
+        context: &BlockContext,
+        transaction: &Transaction,
+    ) -> Result<CallOutput, ExecutionError> {
+        super::call(state, schedule, hooks, context, transaction)
+    }
+}
+
+/// Native execution of the transactions the state transition guest accepts
+#[derive(Debug, Clone, Default)]
+pub struct GuestBackend;
+
+impl GuestBackend {
+    fn admit(state: &StateOverlay, transaction: &Transaction) -> Result<(), ExecutionError> {
+        NativeBackend::admit(state, transaction)?;
+        // The guest runs plain transfers; bridge transactions mint and burn
+        if transaction.to == BRIDGE_ADDRESS || !accepts_transaction(&TransactionData::from(transaction)) {
+            return Err(ExecutionError::Unprovable);
+        }
+        Ok(())
+    }
+}
+
+impl ExecutionBackend for GuestBackend {
+    fn kind(&self) -> ExecutionBackendKind {
+        ExecutionBackendKind::Guest
+    }
+
+    fn execute(
+        &self,
+        state: &WorldState,
+        schedule: &GasSchedule,
+        hooks: Option<&dyn TransactionHooks>,
+        context: &BlockContext,
+        transactions: &[Transaction],
+    ) -> BlockExecution {
+        super::execute_admitted(state, schedule, hooks, context, transactions, Self::admit)
+    }
+
+    fn call(
+        &self,
+        state: &WorldState,
+        schedule: &GasSchedule,
+        hooks: Option<&dyn TransactionHooks>,
+        context: &BlockContext,
+        transaction: &Transaction,
+    ) -> Result<CallOutput, ExecutionError> {
+        Self::admit(&StateOverlay::new(state), transaction)?;
+        super::call(state, schedule, hooks, context, transaction)
+    }
+}
+
+/// The backend `kind` selects
+pub fn execution_backend(kind: ExecutionBackendKind) -> Arc<dyn ExecutionBackend> {
+    match kind {
+        ExecutionBackendKind::Native => Arc::new(NativeBackend),
+        ExecutionBackendKind::Evm => Arc::new(EvmBackend),
+        ExecutionBackendKind::Guest => Arc::new(GuestBackend),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::execution::ExecutionResult;
+
+    #[test]
+    fn test_backends_differ_in_what_they_include() {
+        let mut state = WorldState::default();
+        for id in [0, 1] {
+            state.accounts.insert(Address::new(id), Account::new(1_000_000));
+        }
+        let context = BlockContext { number: 1, timestamp: 0, producer: Address::new(9) };
+        let transfer = Transaction::new(Address::new(1), Address::new(2), 100, 0);
+        // The guest refuses the zero address as a sender
+        let from_zero = Transaction::new(Address::new(0), Address::new(2), 100, 0);
+        let mut deploy = Transaction::new(Address::new(1), CREATE_ADDRESS, 0, 1);
+        deploy.data = vec![0x00];
+        deploy.gas_limit = 100_000;
+        let transactions = [transfer, from_zero, deploy];
+
+        let included = |kind| {
+            let execution = execution_backend(kind).execute(&state, &GasSchedule::default(), None, &context, &transactions);
+            execution.results.iter().map(ExecutionResult::is_included).collect::<Vec<_>>()
+        };
+        assert_eq!(included(ExecutionBackendKind::Native), vec![true, true, false]);
+        assert_eq!(included(ExecutionBackendKind::Guest), vec![true, false, false]);
+        assert_eq!(included(ExecutionBackendKind::Evm), vec![true, true, cfg!(feature = "evm")]);
+
+        let mut json = serde_json::to_value(ProtocolConfig::default()).unwrap();
+        json["execution_backend"] = "guest".into();
+        let config: ProtocolConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(execution_backend(config.execution_backend).kind(), ExecutionBackendKind::Guest);
+    }
+}
@@ -0,0 +1,257 @@
+//! Contract execution on revm
+//!
+//! The EVM reads accounts, code and storage through the [`StateOverlay`] a
+//! block executes on and its state changes are folded back into it, so contract storage is
+//! committed to by the state trie like balances are. Contracts run under the
+//! Cancun rules with a zero base fee: the sender pays `gas_used * gas_price`
+//! and revm credits it to the block producer. `BLOCKHASH` returns zero.
+//! A transaction with an access list fails when the EVM loads an account or
+//! slot it did not declare, before any of its changes are kept.
+
+use super::{BlockContext, CallOutput, ExecutionError, ExecutionResult};
+use crate::state::StateOverlay;
+use crate::types::*;
+use revm::primitives::{
+    self as evm, AccountInfo, Bytecode, Bytes, EVMError, EvmState, InvalidTransaction, KECCAK_EMPTY,
+    ResultAndState, SpecId, TxKind, B256, U256, keccak256,
+};
+use revm::{DatabaseRef, Evm};
+
+/// Address of the contract `sender` deploys with `nonce`
+pub fn contract_address(sender: &Address, nonce: u64) -> Address {
+    from_evm(to_evm(sender).create(nonce))
+}
+
+/// Run `transaction` on the EVM and fold its state changes into `state`
+pub(super) fn transact(
+    state: &mut StateOverlay,
+    schedule: &GasSchedule,
+    context: &BlockContext,
+    transaction: &Transaction,
+) -> Result<ExecutionResult, ExecutionError> {
+    let ResultAndState { result, state: changes } = run(state, schedule, context, transaction)?;
+    fold(state, changes);
+
+    let gas_used = result.gas_used();
+    let fee = GasSchedule::fee(gas_used, transaction.gas_price);
+    Ok(if result.is_success() {
+        ExecutionResult::Applied { gas_used, fee }
+    } else {
+        ExecutionResult::Reverted { gas_used, fee }
+    })
+}
+
+/// Run `transaction` on the EVM without keeping its state changes
+pub(super) fn call(
+    state: &StateOverlay,
+    schedule: &GasSchedule,
+    context: &BlockContext,
+    transaction: &Transaction,
+) -> Result<CallOutput, ExecutionError> {
+    let ResultAndState { result, .. } = run(state, schedule, context, transaction)?;
+    Ok(CallOutput {
+        success: result.is_success(),
+        gas_used: result.gas_used(),
+        output: result.output().map(|output| output.to_vec()).unwrap_or_default(),
+    })
+}
+
+fn run(
+    state: &StateOverlay,
+    schedule: &GasSchedule,
+    context: &BlockContext,
+    transaction: &Transaction,
+) -> Result<ResultAndState, ExecutionError> {
+    let outcome = Evm::builder()
+        .with_ref_db(StateDb { state, transaction, producer: context.producer })
+        .with_spec_id(SpecId::CANCUN)
+        .modify_cfg_env(|cfg| cfg.chain_id = transaction.chain_id)
+        .modify_block_env(|block| {
+            block.number = U256::from(context.number);
+            block.timestamp = U256::from(context.timestamp);
+            block.coinbase = to_evm(&context.producer);
+            block.gas_limit = U256::from(schedule.block_gas_limit);
+        })
+        .modify_tx_env(|tx| {
+            tx.caller = to_evm(&transaction.from);
+            tx.transact_to = if transaction.to == CREATE_ADDRESS {
+                TxKind::Create
+            } else {
+                TxKind::Call(to_evm(&transaction.to))
+            };
+            tx.value = U256::from(transaction.value);
+            tx.data = Bytes::from(transaction.data.clone());
+            tx.gas_limit = transaction.gas_limit;
+            tx.gas_price = U256::from(transaction.gas_price);
+            tx.nonce = Some(transaction.nonce);
+        })
+        .build()
+        .transact();
+    outcome.map_err(|e| match e {
+        EVMError::Transaction(InvalidTransaction::LackOfFundForMaxFee { fee, balance }) => {
+            ExecutionError::InsufficientFunds { balance: balance.saturating_to(), required: fee.saturating_to() }
+        }
+        EVMError::Database(e) => e,
+        e => ExecutionError::Evm(e.to_string()),
+    })
+}
+
+/// Write the accounts the EVM touched back to `state`; destroyed and
+/// emptied accounts are removed
+fn fold(state: &mut StateOverlay, changes: EvmState) {
+    for (address, account) in changes {
+        if !account.is_touched() {
+            continue;
+        }
+        let address = from_evm(address);
+        if account.is_selfdestructed() {
+            state.remove(address);
+            continue;
+        }
+
+        let entry = state.account_mut(address);
+        entry.balance = account.info.balance.saturating_to();
+        entry.nonce = account.info.nonce;
+        if account.is_created() {
+            entry.code = Some(account.info.code.as_ref().map(|code| code.original_bytes().to_vec()).unwrap_or_default());
+            entry.storage_cleared = true;
+            entry.storage.clear();
+        }
+        // A zero value clears the slot
+        for (slot, value) in account.storage.iter().filter(|(_, value)| value.is_changed()) {
+            entry.storage.insert(slot.to_be_bytes::<32>(), value.present_value.to_be_bytes::<32>());
+        }
+        if state.is_empty(&address) {
+            state.remove(address);
+        }
+    }
+}
+
+/// The overlay as revm's database, limited to what `transaction` declares;
+/// revm loads the producer to credit its fee
+struct StateDb<'a, 'b> {
+    state: &'a StateOverlay<'b>,
+    transaction: &'a Transaction,
+    producer: Address,
+}
+
+impl DatabaseRef for StateDb<'_, '_> {
+    type Error = ExecutionError;
+
+    fn basic_ref(&self, address: evm::Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let address = from_evm(address);
+        if address != self.producer && !self.transaction.declares(&address) {
+            return Err(ExecutionError::UndeclaredAccount(address));
+        }
+        Ok(self.state.get(&address).map(|account| {
+            let code_hash = if account.code.is_empty() { KECCAK_EMPTY } else { keccak256(account.code) };
+            AccountInfo::new(
+                U256::from(account.balance),
+                account.nonce,
+                code_hash,
+                Bytecode::new_raw(Bytes::from(account.code.to_vec())),
+            )
+        }))
+    }
+
+    fn code_by_hash_ref(&self, _code_hash: B256) -> Result<Bytecode, Self::Error> {
+        // Code comes with the account from `basic_ref`
+        Ok(Bytecode::default())
+    }
+
+    fn storage_ref(&self, address: evm::Address, index: U256) -> Result<U256, Self::Error> {
+        let (address, slot) = (from_evm(address), index.to_be_bytes::<32>());
+        if !self.transaction.declares_slot(&address, &slot) {
+            return Err(ExecutionError::UndeclaredSlot { address, slot });
+        }
+        Ok(U256::from_be_bytes(self.state.storage(&address, &slot)))
+    }
+
+    fn block_hash_ref(&self, _number: U256) -> Result<B256, Self::Error> {
+        Ok(B256::ZERO)
+    }
+}
+
+fn to_evm(address: &Address) -> evm::Address {
+    evm::Address::from(address.0)
+}
+
+fn from_evm(address: evm::Address) -> Address {
+    Address(address.into_array())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{execute, BlockExecution};
+    use super::*;
+
+    /// Stores its first calldata word in slot 0
+    const STORE_RUNTIME: [u8; 7] = [0x60, 0x00, 0x35, 0x60, 0x00, 0x55, 0x00];
+    /// Always reverts
+    const REVERT_RUNTIME: [u8; 5] = [0x60, 0x00, 0x60, 0x00, 0xfd];
+
+    /// Init code returning `runtime`, which must be shorter than 256 bytes
+    fn deploy_code(runtime: &[u8]) -> Vec<u8> {
+        let len = runtime.len() as u8;
+        let mut code = vec![0x60, len, 0x60, 0x0c, 0x60, 0x00, 0x39, 0x60, len, 0x60, 0x00, 0xf3];
+        code.extend_from_slice(runtime);
+        code
+    }
+
+    fn contract_tx(to: Address, data: Vec<u8>, nonce: u64) -> Transaction {
+        let mut tx = Transaction::new(Address::new(1), to, 0, nonce);
+        tx.data = data;
+        tx.gas_limit = 200_000;
+        tx
+    }
+
+    fn run(state: &WorldState, transactions: &[Transaction]) -> BlockExecution {
+        let context = BlockContext { number: 1, timestamp: 0, producer: Address::new(9) };
+        execute(state, &GasSchedule::default(), None, &context, transactions)
+    }
+
+    #[test]
+    fn test_deploys_and_calls_contracts() {
+        let mut state = WorldState::default();
+        state.accounts.insert(Address::new(1), Account::new(1_000_000));
+        let store = contract_address(&Address::new(1), 0);
+        let reverter = contract_address(&Address::new(1), 1);
+        let mut value = [0u8; 32];
+        value[31] = 42;
+
+        let execution = run(&state, &[
+            contract_tx(CREATE_ADDRESS, deploy_code(&STORE_RUNTIME), 0),
+            contract_tx(CREATE_ADDRESS, deploy_code(&REVERT_RUNTIME), 1),
+            contract_tx(store, value.to_vec(), 2),
+            contract_tx(reverter, value.to_vec(), 3),
+        ]);
+        assert!(execution.rejected().next().is_none());
+        assert!(matches!(execution.results[2], ExecutionResult::Applied { .. }));
+        assert!(matches!(execution.results[3], ExecutionResult::Reverted { .. }));
+
+        let mut after = state.clone();
+        execution.apply_to(&mut after);
+        let accounts = &after.accounts;
+        assert_eq!(accounts[&store].code, STORE_RUNTIME);
+        assert_eq!(accounts[&store].storage[&[0; 32]], value);
+        assert!(accounts[&reverter].storage.is_empty());
+        // Every transaction, the reverted one too, pays its gas to the producer
+        assert_eq!(accounts[&Address::new(1)].nonce, 4);
+        assert_eq!(accounts[&Address::new(1)].balance, 1_000_000 - execution.fees);
+        assert_eq!(accounts[&Address::new(9)].balance, execution.fees);
+        assert_eq!(execution.fees, execution.gas_used);
+        assert_eq!(after.compute_state_root(), execution.state_root);
+        assert_ne!(execution.state_root, run(&state, &[]).state_root);
+    }
+
+    #[test]
+    fn test_contract_calls_pay_for_gas_up_front() {
+        let mut state = WorldState::default();
+        state.accounts.insert(Address::new(1), Account::new(100_000));
+        let execution = run(&state, &[contract_tx(CREATE_ADDRESS, deploy_code(&STORE_RUNTIME), 0)]);
+        assert_eq!(execution.results, vec![ExecutionResult::Rejected(
+            ExecutionError::InsufficientFunds { balance: 100_000, required: 200_000 },
+        )]);
+        assert!(execution.diff.is_empty());
+    }
+}
@@ -0,0 +1,430 @@
+//! Transaction execution
+//!
+//! Transactions are applied one by one to a [`StateOverlay`] of the world
+//! state, and a block's execution is the [`StateDiff`] they leave. Each is
+//! checked against the state left by those before it: its nonce must be the
+//! sender's next, its data within [`GasSchedule::max_data_bytes`], its gas
+//! limit at least its intrinsic gas, and the sender must cover value plus fee.
+//! A transaction failing a check is rejected and leaves the state untouched;
+//! blocks containing one are invalid, so producers drop them before sealing.
+//!
+//! Transactions to [`CREATE_ADDRESS`], or to an account with code, run on the
+//! EVM (the `evm` feature, see [`evm`]): the first deploy their data as init
+//! code and the second call the contract with it. Their gas is metered by the
+//! EVM, and a call that reverts is still included, paying for its gas.
+//!
+//! Governance can install [`TransactionHooks`], a WASM module (the
+//! `wasm-rules` feature, see [`wasm`]) that every transaction must pass and
+//! that sets the fee of plain transfers in place of `gas * gas_price`.
+//!
+//! Transactions to [`BRIDGE_ADDRESS`] mint relayed deposits or record exits
+//! (see [`crate::bridge::messages`]); the value of a withdrawal is burned.
+//!
+//! A transaction with an access list is rejected if it touches an account,
+//! or a contract storage slot, it did not declare; the fee credited to the
+//! producer does not count. Transactions that declare disjoint accounts run
+//! in parallel, see [`schedule`].
+//!
+//! The engine executes through the [`ExecutionBackend`] its protocol config
+//! selects (see [`backend`]), which decides which of these transactions run.
+
+pub mod backend;
+#[cfg(feature = "evm")]
+pub mod evm;
+pub mod schedule;
+#[cfg(feature = "wasm-rules")]
+pub mod wasm;
+
+use crate::bridge::{BRIDGE_ADDRESS, BridgeAction, BridgeError};
+use crate::state::{StateDiff, StateOverlay};
+use crate::types::*;
+use std::sync::Arc;
+use thiserror::Error;
+
+pub use backend::{EvmBackend, ExecutionBackend, GuestBackend, NativeBackend, execution_backend};
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum ExecutionError {
+    #[error("nonce {found} does not follow the sender's nonce {expected}")]
+    NonceMismatch { expected: u64, found: u64 },
+    #[error("balance {balance} does not cover {required}")]
+    InsufficientFunds { balance: u64, required: u64 },
+    #[error("{size} bytes of data exceed the limit of {limit}")]
+    DataTooLarge { size: usize, limit: usize },
+    #[error("gas limit {gas_limit} below intrinsic gas {intrinsic}")]
+    IntrinsicGasTooLow { gas_limit: u64, intrinsic: u64 },
+    #[error("contract transactions need the evm backend and feature")]
+    ContractsUnsupported,
+    #[error("the state transition guest cannot prove this transaction")]
+    Unprovable,
+    #[error("evm rejected the transaction: {0}")]
+    Evm(String),
+    #[error("WASM protocol rules need the wasm-rules feature")]
+    HooksUnsupported,
+    #[error("invalid transaction hooks: {0}")]
+    InvalidHooks(String),
+    #[error("protocol rules rejected the transaction: {0}")]
+    RuleRejected(String),
+    #[error("account {0:?} is not in the access list")]
+    UndeclaredAccount(Address),
+    #[error("slot 0x{} of {address:?} is not in the access list", hex::encode(.slot))]
+    UndeclaredSlot { address: Address, slot: [u8; 32] },
+    #[error(transparent)]
+    Bridge(#[from] BridgeError),
+}
+
+/// Protocol rules run on every transaction
+pub trait TransactionHooks: Send + Sync {
+    /// Whether the rules allow `transaction`
+    fn check(&self, transaction: &Transaction) -> Result<(), String>;
+    /// Fee of a plain transfer using `gas`
+    fn fee(&self, transaction: &Transaction, gas: u64) -> Result<u64, String>;
+}
+
+/// Load the hooks a WASM module implements
+#[cfg_attr(not(feature = "wasm-rules"), allow(unused_variables))]
+pub fn load_hooks(module: &[u8]) -> Result<Arc<dyn TransactionHooks>, ExecutionError> {
+    #[cfg(feature = "wasm-rules")]
+    return Ok(Arc::new(wasm::RuleModule::new(module)?));
+    #[cfg(not(feature = "wasm-rules"))]
+    return Err(ExecutionError::HooksUnsupported);
+}
+
+/// Outcome of one transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExecutionResult {
+    Applied { gas_used: u64, fee: u64 },
+    /// A contract transaction that reverted or halted; only its fee and nonce apply
+    Reverted { gas_used: u64, fee: u64 },
+    Rejected(ExecutionError),
+}
+
+impl ExecutionResult {
+    /// Whether the transaction belongs in the block
+    pub fn is_included(&self) -> bool {
+        !matches!(self, ExecutionResult::Rejected(_))
+    }
+
+    /// Gas charged, 0 if rejected
+    pub fn gas_used(&self) -> u64 {
+        match self {
+            ExecutionResult::Applied { gas_used, .. } | ExecutionResult::Reverted { gas_used, .. } => *gas_used,
+            ExecutionResult::Rejected(_) => 0,
+        }
+    }
+
+    /// Fee paid to the producer, 0 if rejected
+    pub fn fee(&self) -> u64 {
+        match self {
+            ExecutionResult::Applied { fee, .. } | ExecutionResult::Reverted { fee, .. } => *fee,
+            ExecutionResult::Rejected(_) => 0,
+        }
+    }
+}
+
+/// Outcome of a transaction run without committing it, as by `eth_call`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallOutput {
+    /// False if a contract reverted or halted
+    pub success: bool,
+    pub gas_used: u64,
+    /// Return or revert data; empty for plain transfers
+    pub output: Vec<u8>,
+}
+
+/// The block a transaction executes in, as seen by contracts
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BlockContext {
+    pub number: u64,
+    pub timestamp: u64,
+    /// Receives the fees
+    pub producer: Address,
+}
+
+impl BlockContext {
+    pub fn of(header: &BlockHeader) -> Self {
+        Self { number: header.block_number, timestamp: header.timestamp, producer: header.producer }
+    }
+}
+
+/// Outcome of executing a block's transactions
+#[derive(Debug, Clone)]
+pub struct BlockExecution {
+    /// What the transactions changed in the state they ran on
+    pub diff: StateDiff,
+    /// Root of the state after the transactions
+    pub state_root: BlockHash,
+    /// One result per transaction, in order
+    pub results: Vec<ExecutionResult>,
+    pub gas_used: u64,
+    /// Fees credited to the producer
+    pub fees: u64,
+}
+
+impl BlockExecution {
+    /// Rejected transactions with their index
+    pub fn rejected(&self) -> impl Iterator<Item = (usize, &ExecutionError)> {
+        self.results.iter().enumerate().filter_map(|(index, result)| match result {
+            ExecutionResult::Rejected(e) => Some((index, e)),
+            _ => None,
+        })
+    }
+
+    /// Gas each transaction used
+    pub fn transaction_gas(&self) -> Vec<u64> {
+        self.results.iter().map(ExecutionResult::gas_used).collect()
+    }
+
+    /// Commit the transactions to `state`, the state they ran on
+    pub fn apply_to(&self, state: &mut WorldState) {
+        state.apply_diff(&self.diff);
+        state.state_root = self.state_root;
+    }
+}
+
+/// Apply `transactions` to `state` in the block `context` describes,
+/// charging fees under `schedule`, or `hooks` if installed, and crediting
+/// them to the producer
+pub fn execute(
+    state: &WorldState,
+    schedule: &GasSchedule,
+    hooks: Option<&dyn TransactionHooks>,
+    context: &BlockContext,
+    transactions: &[Transaction],
+) -> BlockExecution {
+    execute_admitted(state, schedule, hooks, context, transactions, |_, _| Ok(()))
+}
+
+/// [`execute`], rejecting each transaction `admit` refuses in the state the
+/// transactions before it left
+pub fn execute_admitted(
+    state: &WorldState,
+    schedule: &GasSchedule,
+    hooks: Option<&dyn TransactionHooks>,
+    context: &BlockContext,
+    transactions: &[Transaction],
+    admit: impl Fn(&StateOverlay, &Transaction) -> Result<(), ExecutionError> + Sync,
+) -> BlockExecution {
+    let run = |overlay: &mut StateOverlay<'_>, transaction: &Transaction| admit(overlay, transaction)
+        .and_then(|()| apply(overlay, schedule, hooks, context, transaction))
+        .unwrap_or_else(ExecutionResult::Rejected);
+
+    let mut overlay = StateOverlay::new(state);
+    let mut results = vec![None; transactions.len()];
+    for batch in schedule::schedule(transactions, &context.producer) {
+        if let [index] = batch[..] {
+            results[index] = Some(run(&mut overlay, &transactions[index]));
+            continue;
+        }
+        let workers = std::thread::available_parallelism().map_or(1, usize::from);
+        let forked = &overlay;
+        let run = &run;
+        let outcomes: Vec<(ExecutionResult, StateDiff)> = std::thread::scope(|scope| {
+            let handles: Vec<_> = batch.chunks(batch.len().div_ceil(workers))
+                .map(|indices| scope.spawn(move || indices.iter()
+                    .map(|&index| {
+                        let transaction = &transactions[index];
+                        let mut fork = forked.fork(transaction.accessed_accounts().into_iter().flatten());
+                        let result = run(&mut fork, transaction);
+                        (result, fork.into_diff())
+                    })
+                    .collect::<Vec<_>>()))
+                .collect();
+            handles.into_iter()
+                .flat_map(|handle| handle.join().expect("transaction execution panicked"))
+                .collect()
+        });
+        // Fees go to the producer here, in place of each fork's credit
+        for (&index, (result, mut diff)) in batch.iter().zip(outcomes) {
+            diff.accounts.remove(&context.producer);
+            overlay.absorb(diff);
+            if result.fee() > 0 {
+                let producer = overlay.account_mut(context.producer);
+                producer.balance = producer.balance.saturating_add(result.fee());
+            }
+            results[index] = Some(result);
+        }
+    }
+    let results: Vec<ExecutionResult> = results.into_iter().map(|result| result.expect("every transaction is scheduled")).collect();
+
+    let diff = overlay.into_diff();
+    BlockExecution {
+        state_root: diff.state_root(state),
+        diff,
+        gas_used: results.iter().map(ExecutionResult::gas_used).sum(),
+        fees: results.iter().map(ExecutionResult::fee).sum(),
+        results,
+    }
+}
+
+/// Run `transaction` on `state` and return its output, leaving `state` as
+/// it is. Plain transfers only go through the same checks as in a block.
+pub fn call(
+    state: &WorldState,
+    schedule: &GasSchedule,
+    hooks: Option<&dyn TransactionHooks>,
+    context: &BlockContext,
+    transaction: &Transaction,
+) -> Result<CallOutput, ExecutionError> {
+    let mut overlay = StateOverlay::new(state);
+    if is_contract(&overlay, transaction) {
+        if let Some(hooks) = hooks {
+            hooks.check(transaction).map_err(ExecutionError::RuleRejected)?;
+        }
+        #[cfg(feature = "evm")]
+        return evm::call(&overlay, schedule, context, transaction);
+        #[cfg(not(feature = "evm"))]
+        return Err(ExecutionError::ContractsUnsupported);
+    }
+    let result = apply(&mut overlay, schedule, hooks, context, transaction)?;
+    Ok(CallOutput { success: true, gas_used: result.gas_used(), output: Vec::new() })
+}
+
+/// Whether `transaction` deploys or calls a contract
+pub fn is_contract_transaction(state: &WorldState, transaction: &Transaction) -> bool {
+    is_contract(&StateOverlay::new(state), transaction)
+}
+
+fn is_contract(state: &StateOverlay, transaction: &Transaction) -> bool {
+    transaction.to == CREATE_ADDRESS
+        || state.get(&transaction.to).is_some_and(|account| !account.code.is_empty())
+}
+
+/// Check `transaction` against `state` and apply it; `state` is unchanged on error
+fn apply(
+    state: &mut StateOverlay,
+    schedule: &GasSchedule,
+    hooks: Option<&dyn TransactionHooks>,
+    context: &BlockContext,
+    transaction: &Transaction,
+) -> Result<ExecutionResult, ExecutionError> {
+    let (balance, nonce) = state.get(&transaction.from)
+        .map_or((0, 0), |account| (account.balance, account.nonce));
+    if transaction.nonce != nonce {
+        return Err(ExecutionError::NonceMismatch { expected: nonce, found: transaction.nonce });
+    }
+    if transaction.data.len() > schedule.max_data_bytes {
+        return Err(ExecutionError::DataTooLarge { size: transaction.data.len(), limit: schedule.max_data_bytes });
+    }
+    let gas = schedule.intrinsic_gas(transaction);
+    if transaction.gas_limit < gas {
+        return Err(ExecutionError::IntrinsicGasTooLow { gas_limit: transaction.gas_limit, intrinsic: gas });
+    }
+    if let Some(hooks) = hooks {
+        hooks.check(transaction).map_err(ExecutionError::RuleRejected)?;
+    }
+    if is_contract(state, transaction) {
+        #[cfg(feature = "evm")]
+        return evm::transact(state, schedule, context, transaction);
+        #[cfg(not(feature = "evm"))]
+        return Err(ExecutionError::ContractsUnsupported);
+    }
+
+    let fee = match hooks {
+        Some(hooks) => hooks.fee(transaction, gas).map_err(ExecutionError::RuleRejected)?,
+        None => GasSchedule::fee(gas, transaction.gas_price),
+    };
+    let required = transaction.value.saturating_add(fee);
+    if balance < required {
+        return Err(ExecutionError::InsufficientFunds { balance, required });
+    }
+    if let Some(action) = BridgeAction::from_transaction(transaction) {
+        let action = action?;
+        if let BridgeAction::Deposit(deposit) = &action {
+            if !transaction.declares(&deposit.recipient) {
+                return Err(ExecutionError::UndeclaredAccount(deposit.recipient));
+            }
+        }
+        action.apply(state, transaction)?;
+    }
+
+    let sender = state.account_mut(transaction.from);
+    sender.balance -= required;
+    sender.nonce += 1;
+    // Withdrawn value leaves this chain
+    if transaction.to != BRIDGE_ADDRESS {
+        let recipient = state.account_mut(transaction.to);
+        recipient.balance = recipient.balance.saturating_add(transaction.value);
+    }
+    if fee > 0 {
+        let producer = state.account_mut(context.producer);
+        producer.balance = producer.balance.saturating_add(fee);
+    }
+    Ok(ExecutionResult::Applied { gas_used: gas, fee })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bridge::DepositMessage;
+    use std::collections::HashMap;
+
+    fn state(balance: u64) -> WorldState {
+        let mut accounts = HashMap::new();
+        accounts.insert(Address::new(1), Account { balance, nonce: 0, code: Vec::new(), storage: HashMap::new() });
+        WorldState { accounts, global_nonce: 0, state_root: BlockHash::zero(), block_number: 0 }
+    }
+
+    #[test]
+    fn test_rejected_transactions_leave_state_untouched() {
+        let schedule = GasSchedule::default();
+        let producer = Address::new(9);
+        let context = BlockContext { number: 1, timestamp: 0, producer };
+        let mut oversized = Transaction::new(Address::new(1), Address::new(2), 1, 1);
+        oversized.data = vec![0; schedule.max_data_bytes + 1];
+        oversized.gas_limit = schedule.block_gas_limit;
+        let transactions = vec![
+            Transaction::new(Address::new(1), Address::new(2), 100, 0),
+            // Replays nonce 0
+            Transaction::new(Address::new(1), Address::new(2), 100, 0),
+            // Skips nonce 1
+            Transaction::new(Address::new(1), Address::new(2), 100, 2),
+            oversized,
+            Transaction::new(Address::new(1), Address::new(2), 1_000_000, 1),
+            Transaction::new(Address::new(1), Address::new(2), 100, 1),
+        ];
+
+        let execution = execute(&state(100_000), &schedule, None, &context, &transactions);
+        assert_eq!(execution.results, vec![
+            ExecutionResult::Applied { gas_used: 21_000, fee: 21_000 },
+            ExecutionResult::Rejected(ExecutionError::NonceMismatch { expected: 1, found: 0 }),
+            ExecutionResult::Rejected(ExecutionError::NonceMismatch { expected: 1, found: 2 }),
+            ExecutionResult::Rejected(ExecutionError::DataTooLarge { size: schedule.max_data_bytes + 1, limit: schedule.max_data_bytes }),
+            ExecutionResult::Rejected(ExecutionError::InsufficientFunds { balance: 78_900, required: 1_021_000 }),
+            ExecutionResult::Applied { gas_used: 21_000, fee: 21_000 },
+        ]);
+        assert_eq!(execution.rejected().map(|(index, _)| index).collect::<Vec<_>>(), vec![1, 2, 3, 4]);
+        assert_eq!(execution.gas_used, 42_000);
+
+        let mut after = state(100_000);
+        execution.apply_to(&mut after);
+        assert_eq!(after.state_root, after.compute_state_root());
+        let accounts = &after.accounts;
+        assert_eq!(accounts[&Address::new(1)].balance, 100_000 - 2 * 21_100);
+        assert_eq!(accounts[&Address::new(1)].nonce, 2);
+        assert_eq!(accounts[&Address::new(2)].balance, 200);
+        assert_eq!(accounts[&producer].balance, 42_000);
+    }
+
+    #[test]
+    fn test_declared_transactions_stay_within_their_access_list() {
+        let context = BlockContext { number: 1, timestamp: 0, producer: Address::new(9) };
+        let deposit = |recipient| {
+            let message = DepositMessage { nonce: 0, l1_sender: [0; 20], recipient, amount: 500 };
+            let mut transaction = Transaction::new(Address::new(1), BRIDGE_ADDRESS, 0, 0);
+            transaction.data = bincode::serialize(&BridgeAction::Deposit(message)).unwrap();
+            transaction.gas_limit = GasSchedule::default().intrinsic_gas(&transaction);
+            transaction.access_list = Some(vec![AccessListItem { address: Address::new(3), storage_keys: Vec::new() }]);
+            transaction
+        };
+
+        let run = |transaction| execute(&state(1_000_000), &GasSchedule::default(), None, &context, &[transaction]);
+        let undeclared = run(deposit(Address::new(4)));
+        assert_eq!(undeclared.results, vec![ExecutionResult::Rejected(ExecutionError::UndeclaredAccount(Address::new(4)))]);
+        assert!(undeclared.diff.is_empty());
+
+        let declared = run(deposit(Address::new(3)));
+        assert!(declared.results[0].is_included());
+        assert_eq!(declared.diff.balance(&state(0), &Address::new(3)), 500);
+    }
+}
@@ -0,0 +1,92 @@
+//! Parallel scheduling of transactions with access lists
+//!
+//! A transaction that declares an access list can only touch its sender, its
+//! recipient and the accounts it lists, so two such transactions with no
+//! account in common can run at the same time. [`schedule`] groups a block's
+//! transactions into batches that run one after another; the transactions of
+//! a batch share no account, and each runs after every earlier transaction it
+//! shares one with, so running the batches gives the state running the
+//! transactions in order does.
+//!
+//! Fees are credited to the producer after each batch, so the producer does
+//! not count as shared. A transaction without an access list, or one that
+//! touches the producer, could touch anything and runs in a batch of its own.
+
+use crate::types::{Address, Transaction};
+use std::collections::HashMap;
+
+/// Indices of `transactions` in batches to run in order, each in
+/// ascending order, for a block produced by `producer`
+pub fn schedule(transactions: &[Transaction], producer: &Address) -> Vec<Vec<usize>> {
+    let mut batches: Vec<Vec<usize>> = Vec::new();
+    // Batch after the last transaction that ran alone
+    let mut floor = 0;
+    // Batch of the last transaction touching each account
+    let mut last_batch: HashMap<Address, usize> = HashMap::new();
+
+    for (index, transaction) in transactions.iter().enumerate() {
+        let accounts: Option<Vec<Address>> = transaction.accessed_accounts()
+            .map(Iterator::collect)
+            .filter(|accounts: &Vec<Address>| !accounts.contains(producer));
+        let batch = match &accounts {
+            Some(accounts) => accounts.iter()
+                .filter_map(|account| last_batch.get(account).map(|batch| batch + 1))
+                .fold(floor, usize::max),
+            None => batches.len(),
+        };
+        if batch == batches.len() {
+            batches.push(Vec::new());
+        }
+        batches[batch].push(index);
+        match accounts {
+            Some(accounts) => last_batch.extend(accounts.into_iter().map(|account| (account, batch))),
+            None => floor = batch + 1,
+        }
+    }
+    batches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::execution::{BlockContext, execute};
+    use crate::types::*;
+
+    fn declared(from: u8, to: u8, nonce: u64) -> Transaction {
+        let mut transaction = Transaction::new(Address::new(from), Address::new(to), 10, nonce);
+        transaction.access_list = Some(Vec::new());
+        transaction
+    }
+
+    #[test]
+    fn test_batches_keep_shared_accounts_in_order() {
+        let producer = Address::new(9);
+        let transactions = vec![
+            declared(1, 2, 0),
+            declared(3, 4, 0),
+            // Shares account 2 with the first
+            declared(2, 5, 0),
+            declared(6, 7, 0),
+            // Runs alone and after everything before it
+            Transaction::new(Address::new(8), Address::new(10), 10, 0),
+            declared(11, 12, 0),
+            // Touches the producer
+            declared(13, 9, 0),
+        ];
+        assert_eq!(schedule(&transactions, &producer), vec![vec![0, 1, 3], vec![2], vec![4], vec![5], vec![6]]);
+
+        let mut state = WorldState::default();
+        for id in [1, 2, 3, 6, 8, 11, 13] {
+            state.accounts.insert(Address::new(id), Account::new(100_000));
+        }
+        let context = BlockContext { number: 1, timestamp: 0, producer };
+        let execution = execute(&state, &GasSchedule::default(), None, &context, &transactions);
+        assert!(execution.rejected().next().is_none());
+
+        // The same transfers without access lists run one at a time
+        let serial: Vec<Transaction> = transactions.iter()
+            .map(|transaction| Transaction { access_list: None, ..transaction.clone() })
+            .collect();
+        assert_eq!(execute(&state, &GasSchedule::default(), None, &context, &serial).state_root, execution.state_root);
+    }
+}
@@ -0,0 +1,141 @@
+//! Transaction hooks as WASM modules on wasmtime
+//!
+//! A hooks module imports nothing, so it cannot reach the host, and exports:
+//!
+//! - `memory` and `alloc(len: i32) -> i32`, returning where the host may
+//!   write `len` bytes
+//! - `validate_transaction(ptr: i32, len: i32) -> i32`, given the canonical
+//!   encoding of the transaction; 0 accepts it
+//! - `transaction_fee(gas: i64, gas_price: i64, data_len: i64) -> i64`, the
+//!   fee of a plain transfer, which must not be negative
+//!
+//! Every call runs in a fresh instance with [`HOOK_FUEL`] fuel and at most
+//! [`MAX_MEMORY_BYTES`] of memory, so no state carries between
+//! transactions and a hook that runs out fails the transaction. Threads and
+//! SIMD are disabled and NaNs canonicalized, so every node computes the same
+//! result.
+
+use super::{ExecutionError, TransactionHooks};
+use crate::serialization::canonical_bytes;
+use crate::types::Transaction;
+use wasmtime::{Config, Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder, Trap};
+
+/// Fuel each hook call gets, instantiation included
+pub const HOOK_FUEL: u64 = 10_000_000;
+/// Most linear memory a hooks module may grow to
+pub const MAX_MEMORY_BYTES: usize = 16 * 64 * 1024;
+
+const EXPORTS: [&str; 4] = ["memory", "alloc", "validate_transaction", "transaction_fee"];
+
+/// A compiled hooks module
+pub struct RuleModule {
+    engine: Engine,
+    module: Module,
+}
+
+impl RuleModule {
+    /// Compile `bytes`, checking that the module imports nothing and has every hook
+    pub fn new(bytes: &[u8]) -> Result<Self, ExecutionError> {
+        let mut config = Config::new();
+        config
+            .consume_fuel(true)
+            .cranelift_nan_canonicalization(true)
+            .wasm_threads(false)
+            .wasm_relaxed_simd(false)
+            .wasm_simd(false)
+            .wasm_memory64(false);
+        let invalid = |e: wasmtime::Error| ExecutionError::InvalidHooks(format!("{:#}", e));
+        let engine = Engine::new(&config).map_err(invalid)?;
+        let module = Module::new(&engine, bytes).map_err(invalid)?;
+        if let Some(import) = module.imports().next() {
+            return Err(ExecutionError::InvalidHooks(format!("module imports {}::{}", import.module(), import.name())));
+        }
+        if let Some(missing) = EXPORTS.iter().find(|name| module.get_export(name).is_none()) {
+            return Err(ExecutionError::InvalidHooks(format!("module does not export {}", missing)));
+        }
+        Ok(Self { engine, module })
+    }
+
+    fn instantiate(&self) -> Result<(Store<StoreLimits>, Instance), String> {
+        let limits = StoreLimitsBuilder::new().memory_size(MAX_MEMORY_BYTES).instances(1).build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(HOOK_FUEL).map_err(describe)?;
+        let instance = Instance::new(&mut store, &self.module, &[]).map_err(describe)?;
+        Ok((store, instance))
+    }
+}
+
+fn describe(e: wasmtime::Error) -> String {
+    match e.downcast_ref::<Trap>() {
+        Some(Trap::OutOfFuel) => format!("hook ran out of its {} fuel", HOOK_FUEL),
+        _ => format!("{:#}", e),
+    }
+}
+
+impl TransactionHooks for RuleModule {
+    fn check(&self, transaction: &Transaction) -> Result<(), String> {
+        let (mut store, instance) = self.instantiate()?;
+        let bytes = canonical_bytes(transaction);
+        let len = i32::try_from(bytes.len()).map_err(|_| "transaction too large for the hooks".to_string())?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut store, "alloc").map_err(describe)?;
+        let ptr = alloc.call(&mut store, len).map_err(describe)?;
+        let memory = instance.get_memory(&mut store, "memory").ok_or_else(|| "memory is not a memory".to_string())?;
+        memory.write(&mut store, ptr as u32 as usize, &bytes).map_err(|e| e.to_string())?;
+        let validate = instance.get_typed_func::<(i32, i32), i32>(&mut store, "validate_transaction").map_err(describe)?;
+        match validate.call(&mut store, (ptr, len)).map_err(describe)? {
+            0 => Ok(()),
+            code => Err(format!("validity predicate returned {}", code)),
+        }
+    }
+
+    fn fee(&self, transaction: &Transaction, gas: u64) -> Result<u64, String> {
+        let (mut store, instance) = self.instantiate()?;
+        let fee = instance.get_typed_func::<(i64, i64, i64), i64>(&mut store, "transaction_fee").map_err(describe)?;
+        let args = (gas as i64, transaction.gas_price as i64, transaction.data.len() as i64);
+        let fee = fee.call(&mut store, args).map_err(describe)?;
+        u64::try_from(fee).map_err(|_| format!("fee formula returned {}", fee))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Address;
+
+    /// Rejects transactions over 256 encoded bytes and charges 10 per data byte on top of gas
+    const HOOKS: &str = r#"
+        (module
+          (memory (export "memory") 1)
+          (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+          (func (export "validate_transaction") (param i32 i32) (result i32)
+            (i32.gt_u (local.get 1) (i32.const 256)))
+          (func (export "transaction_fee") (param i64 i64 i64) (result i64)
+            (i64.add (i64.mul (local.get 0) (local.get 1)) (i64.mul (local.get 2) (i64.const 10)))))
+    "#;
+
+    fn transaction(data_len: usize) -> Transaction {
+        let mut transaction = Transaction::new(Address::new(1), Address::new(2), 5, 0);
+        transaction.data = vec![7; data_len];
+        transaction.gas_price = 2;
+        transaction
+    }
+
+    #[test]
+    fn test_hooks_check_transactions_and_set_fees() {
+        let hooks = RuleModule::new(HOOKS.as_bytes()).unwrap();
+        assert!(hooks.check(&transaction(0)).is_ok());
+        assert!(hooks.check(&transaction(512)).is_err());
+        assert_eq!(hooks.fee(&transaction(3), 21_000).unwrap(), 42_030);
+    }
+
+    #[test]
+    fn test_rejects_imports_and_stops_runaway_hooks() {
+        let importing = HOOKS.replacen("(module", r#"(module (import "env" "now" (func (result i64)))"#, 1);
+        assert!(matches!(RuleModule::new(importing.as_bytes()), Err(ExecutionError::InvalidHooks(_))));
+
+        let looping = HOOKS.replace("(i32.gt_u (local.get 1) (i32.const 256))", "(loop $spin (br $spin)) (i32.const 0)");
+        let hooks = RuleModule::new(looping.as_bytes()).unwrap();
+        assert!(hooks.check(&transaction(0)).unwrap_err().contains("fuel"));
+    }
+}
@@ -0,0 +1,285 @@
+//! BFT finality gadget
+//!
+//! Validators sign an [`Attestation`] for every block they apply. The
+//! [`FinalityGadget`] counts attested stake per block; a block is final once
+//! validators holding at least two thirds of the total stake have attested
+//! it, and finalizing a block finalizes all of its ancestors. Attestations
+//! sign the same bytes as the votes carried in a block, so those votes count
+//! as attestations too, as do the BLS votes aggregated into the block.
+
+use crate::crypto::bls::{self, BlsError};
+use crate::crypto::keystore::{KeyPair, verify_signature};
+use crate::light_client::header_signing_bytes;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FinalityError {
+    #[error("attestation from {0:?}, which is not in the validator set")]
+    UnknownValidator(Address),
+    #[error("invalid attestation signature from {0:?}")]
+    InvalidSignature(Address),
+    #[error("invalid aggregate attestation: {0}")]
+    InvalidAggregate(BlsError),
+    #[error("{validator:?} attested both {first:?} and {second:?} at height {height}")]
+    Equivocation { validator: Address, height: u64, first: BlockHash, second: BlockHash },
+}
+
+/// A validator's vote that `block_hash` is the block at `block_number`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Attestation {
+    pub block_number: u64,
+    pub block_hash: BlockHash,
+    pub vote: ValidatorSignature,
+}
+
+impl Attestation {
+    pub fn sign(key: &KeyPair, stake_weight: u64, block_number: u64, block_hash: BlockHash) -> anyhow::Result<Self> {
+        Ok(Self {
+            block_number,
+            block_hash,
+            vote: ValidatorSignature {
+                validator_address: key.address(),
+                stake_weight,
+                signature: key.sign(&header_signing_bytes(block_number, &block_hash))?,
+                sig_type: key.sig_type().clone(),
+            },
+        })
+    }
+
+    /// The votes a block carries, as attestations for that block
+    pub fn from_block(block: &Block) -> Vec<Self> {
+        let block_hash = block.header.hash();
+        block.validator_signatures.iter()
+            .map(|vote| Self { block_number: block.header.block_number, block_hash, vote: vote.clone() })
+            .collect()
+    }
+}
+
+/// Whether `signed` is at least two thirds of `total`
+pub fn has_quorum(signed: u64, total: u64) -> bool {
+    total > 0 && u128::from(signed) * 3 >= u128::from(total) * 2
+}
+
+/// Attestations per height and block, until the height is finalized
+#[derive(Debug, Clone, Default)]
+pub struct FinalityGadget {
+    /// Attesting validator and its stake, per block, per height
+    votes: BTreeMap<u64, HashMap<BlockHash, HashMap<Address, u64>>>,
+    finalized: Option<(u64, BlockHash)>,
+}
+
+impl FinalityGadget {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start from a block already known to be final, such as a trusted snapshot
+    pub fn from_checkpoint(height: u64, hash: BlockHash) -> Self {
+        Self { votes: BTreeMap::new(), finalized: Some((height, hash)) }
+    }
+
+    /// Height of the highest finalized block, 0 before any
+    pub fn finalized_height(&self) -> u64 {
+        self.finalized.map_or(0, |(height, _)| height)
+    }
+
+    pub fn finalized_hash(&self) -> Option<BlockHash> {
+        self.finalized.map(|(_, hash)| hash)
+    }
+
+    /// Verify and count `attestation`; returns whether it was new. Stake is
+    /// taken from `validators`, not from the weight the vote claims.
+    pub fn add_attestation(&mut self, validators: &ValidatorSet, attestation: &Attestation) -> Result<bool, FinalityError> {
+        let vote = &attestation.vote;
+        let validator = validators.validators.iter()
+            .find(|validator| validator.address == vote.validator_address)
+            .ok_or(FinalityError::UnknownValidator(vote.validator_address))?;
+        verify_signature(&vote.sig_type, &validator.public_key, &header_signing_bytes(attestation.block_number, &attestation.block_hash), &vote.signature)
+            .map_err(|_| FinalityError::InvalidSignature(validator.address))?;
+        if !self.is_new_vote(attestation.block_number, &attestation.block_hash, &validator.address)? {
+            return Ok(false);
+        }
+        self.count_vote(attestation.block_number, attestation.block_hash, validator);
+        Ok(true)
+    }
+
+    /// Verify and count the BLS votes for `block_hash` aggregated in its
+    /// block; returns how many were new. Nothing is counted if one of the
+    /// signers attested another block at the height.
+    pub fn add_aggregate(
+        &mut self,
+        validators: &ValidatorSet,
+        block_number: u64,
+        block_hash: BlockHash,
+        aggregate: &AggregateSignature,
+    ) -> Result<usize, FinalityError> {
+        let signers = bls::verify_aggregate_vote(validators, &header_signing_bytes(block_number, &block_hash), aggregate)
+            .map_err(FinalityError::InvalidAggregate)?;
+        let mut new = Vec::with_capacity(signers.len());
+        for validator in signers {
+            if self.is_new_vote(block_number, &block_hash, &validator.address)? {
+                new.push(validator);
+            }
+        }
+        for validator in &new {
+            self.count_vote(block_number, block_hash, validator);
+        }
+        Ok(new.len())
+    }
+
+    /// Whether a vote from `validator` for `block_hash` still counts; an
+    /// error if the validator attested another block at the height
+    fn is_new_vote(&self, block_number: u64, block_hash: &BlockHash, validator: &Address) -> Result<bool, FinalityError> {
+        if block_number <= self.finalized_height() {
+            return Ok(false);
+        }
+        let Some(height) = self.votes.get(&block_number) else { return Ok(true) };
+        if let Some((first, _)) = height.iter()
+            .find(|(hash, voters)| *hash != block_hash && voters.contains_key(validator))
+        {
+            return Err(FinalityError::Equivocation {
+                validator: *validator,
+                height: block_number,
+                first: *first,
+                second: *block_hash,
+            });
+        }
+        Ok(!height.get(block_hash).is_some_and(|voters| voters.contains_key(validator)))
+    }
+
+    fn count_vote(&mut self, block_number: u64, block_hash: BlockHash, validator: &Validator) {
+        self.votes.entry(block_number).or_default()
+            .entry(block_hash).or_default()
+            .insert(validator.address, validator.stake);
+    }
+
+    /// Stake that has attested `block_hash` at `block_number`
+    pub fn attested_stake(&self, block_number: u64, block_hash: &BlockHash) -> u64 {
+        self.votes.get(&block_number)
+            .and_then(|height| height.get(block_hash))
+            .map_or(0, |voters| voters.values().sum())
+    }
+
+    /// Finalize the highest block of the local chain that has a quorum;
+    /// `chain` gives the local block hash at a height. Returns the newly
+    /// finalized height, if finality advanced.
+    pub fn try_finalize(&mut self, total_stake: u64, chain: impl Fn(u64) -> Option<BlockHash>) -> Option<u64> {
+        self.try_finalize_up_to(total_stake, u64::MAX, chain)
+    }
+
+    /// Like [`Self::try_finalize`], but a quorum above `ceiling` finalizes
+    /// only its ancestor at `ceiling`; attesting a block attests its
+    /// ancestors. Votes above `ceiling` are kept for when it rises.
+    pub fn try_finalize_up_to(&mut self, total_stake: u64, ceiling: u64, chain: impl Fn(u64) -> Option<BlockHash>) -> Option<u64> {
+        let (height, _) = self.votes.iter().rev()
+            .filter_map(|(height, _)| Some((*height, chain(*height)?)))
+            .find(|(height, hash)| has_quorum(self.attested_stake(*height, hash), total_stake))?;
+        let height = height.min(ceiling);
+        if height <= self.finalized_height() {
+            return None;
+        }
+        let hash = chain(height)?;
+
+        self.finalized = Some((height, hash));
+        // Votes at or below a final height can no longer change anything
+        self.votes = self.votes.split_off(&(height + 1));
+        Some(height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators(keys: &[KeyPair], stakes: &[u64]) -> ValidatorSet {
+        ValidatorSet::new(keys.iter().zip(stakes)
+            .map(|(key, stake)| Validator { address: key.address(), stake: *stake, public_key: key.public_key(), performance_score: 1.0, bls_public_key: Vec::new() })
+            .collect())
+    }
+
+    #[test]
+    fn test_two_thirds_of_stake_finalizes() {
+        let keys: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+        let set = validators(&keys, &[40, 20, 40]);
+        let hashes = [BlockHash([1; 32]), BlockHash([2; 32])];
+        let chain = |height: u64| hashes.get(height as usize - 1).copied();
+        let mut gadget = FinalityGadget::new();
+
+        // 60 of 100 is not enough
+        for key in &keys[..2] {
+            let attestation = Attestation::sign(key, 0, 2, hashes[1]).unwrap();
+            assert!(gadget.add_attestation(&set, &attestation).unwrap());
+            assert!(!gadget.add_attestation(&set, &attestation).unwrap(), "counted twice");
+        }
+        assert_eq!(gadget.try_finalize(set.total_stake, chain), None);
+
+        // 80 of 100 finalizes block 2, and with it block 1
+        gadget.add_attestation(&set, &Attestation::sign(&keys[2], 0, 2, hashes[1]).unwrap()).unwrap();
+        assert_eq!(gadget.try_finalize(set.total_stake, chain), Some(2));
+        assert_eq!(gadget.finalized_height(), 2);
+        assert_eq!(gadget.finalized_hash(), Some(hashes[1]));
+
+        assert!(has_quorum(2, 3));
+        assert!(!has_quorum(1, 3));
+        assert!(!has_quorum(0, 0));
+    }
+
+    #[test]
+    fn test_quorum_above_ceiling_finalizes_its_ancestor() {
+        let keys: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+        let set = validators(&keys, &[40, 20, 40]);
+        let hashes = [BlockHash([1; 32]), BlockHash([2; 32]), BlockHash([3; 32])];
+        let chain = |height: u64| hashes.get(height as usize - 1).copied();
+        let mut gadget = FinalityGadget::new();
+        for key in &keys {
+            gadget.add_attestation(&set, &Attestation::sign(key, 0, 3, hashes[2]).unwrap()).unwrap();
+        }
+
+        assert_eq!(gadget.try_finalize_up_to(set.total_stake, 0, chain), None);
+        assert_eq!(gadget.try_finalize_up_to(set.total_stake, 2, chain), Some(2));
+        assert_eq!(gadget.finalized_hash(), Some(hashes[1]));
+        assert_eq!(gadget.try_finalize_up_to(set.total_stake, 2, chain), None);
+        // The votes for block 3 finalize it once the ceiling lifts
+        assert_eq!(gadget.try_finalize(set.total_stake, chain), Some(3));
+    }
+
+    #[test]
+    fn test_invalid_and_conflicting_attestations_are_rejected() {
+        let keys: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+        let set = validators(&keys, &[50, 50]);
+        let mut gadget = FinalityGadget::new();
+
+        let outsider = KeyPair::generate(SignatureType::Ed25519);
+        let error = gadget.add_attestation(&set, &Attestation::sign(&outsider, 0, 1, BlockHash([1; 32])).unwrap());
+        assert_eq!(error, Err(FinalityError::UnknownValidator(outsider.address())));
+
+        let mut forged = Attestation::sign(&keys[0], 0, 1, BlockHash([1; 32])).unwrap();
+        forged.block_hash = BlockHash([2; 32]);
+        assert_eq!(gadget.add_attestation(&set, &forged), Err(FinalityError::InvalidSignature(keys[0].address())));
+
+        gadget.add_attestation(&set, &Attestation::sign(&keys[0], 0, 1, BlockHash([1; 32])).unwrap()).unwrap();
+        let conflicting = Attestation::sign(&keys[0], 0, 1, BlockHash([2; 32])).unwrap();
+        assert!(matches!(gadget.add_attestation(&set, &conflicting), Err(FinalityError::Equivocation { height: 1, .. })));
+        assert_eq!(gadget.attested_stake(1, &BlockHash([2; 32])), 0);
+    }
+
+    #[test]
+    fn test_relabeled_attestations_do_not_count_as_equivocations() {
+        let keys: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+        let set = validators(&keys, &[50, 50]);
+        let mut gadget = FinalityGadget::new();
+
+        // A relayer moves the vote for block 5 to height 6
+        let mut relabeled = Attestation::sign(&keys[0], 0, 5, BlockHash([5; 32])).unwrap();
+        relabeled.block_number = 6;
+        assert_eq!(gadget.add_attestation(&set, &relabeled), Err(FinalityError::InvalidSignature(keys[0].address())));
+        assert_eq!(gadget.attested_stake(6, &BlockHash([5; 32])), 0);
+
+        // The validator's real vote at height 6 still counts
+        assert!(gadget.add_attestation(&set, &Attestation::sign(&keys[0], 0, 6, BlockHash([6; 32])).unwrap()).unwrap());
+        assert_eq!(gadget.attested_stake(6, &BlockHash([6; 32])), 50);
+    }
+}
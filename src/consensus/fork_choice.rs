@@ -0,0 +1,172 @@
+//! Block tree and fork choice
+//!
+//! Blocks that arrive for a parent other than the head are kept in a
+//! [`BlockTree`] instead of being dropped. The canonical chain follows the
+//! heaviest branch: a side branch replaces the canonical blocks above its fork
+//! point once the stake attesting its blocks exceeds the stake attesting the
+//! blocks it would replace. Ties keep the current chain, and finalized blocks
+//! are never replaced. The engine keeps a [`ChainSnapshot`] per unfinalized
+//! height so it can roll back to the fork point before applying the branch;
+//! for the state, it keeps the diff undoing each block rather than a copy.
+
+use super::governance::Governance;
+use super::slashing::Slasher;
+use crate::state::StateDiff;
+use crate::types::*;
+use std::collections::{HashMap, HashSet};
+
+/// Outcome of receiving a block
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BlockImport {
+    /// Already in the chain or the tree
+    Known,
+    /// Applied on top of the head
+    Extended,
+    /// Kept on a side branch that is not heavier than the canonical chain
+    Stored,
+    /// The canonical chain switched to a heavier branch, replacing `depth`
+    /// blocks above `fork_height`
+    Reorged { fork_height: u64, depth: usize, head: BlockHash },
+}
+
+/// What applying blocks changes, as it stood after a height
+#[derive(Debug, Clone)]
+pub struct ChainSnapshot {
+    /// Reverts the state after this height to the one before; empty for the
+    /// height the chain starts from
+    pub undo: StateDiff,
+    pub state_root: BlockHash,
+    pub validator_set: ValidatorSet,
+    pub slasher: Slasher,
+    pub governance: Governance,
+    /// Protocol rules in effect, which governance changes at epoch boundaries
+    pub protocol_config: ProtocolConfig,
+}
+
+/// Blocks off the canonical chain, by hash
+#[derive(Debug, Clone, Default)]
+pub struct BlockTree {
+    blocks: HashMap<BlockHash, Block>,
+}
+
+impl BlockTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.blocks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.blocks.is_empty()
+    }
+
+    pub fn contains(&self, hash: &BlockHash) -> bool {
+        self.blocks.contains_key(hash)
+    }
+
+    pub fn get(&self, hash: &BlockHash) -> Option<&Block> {
+        self.blocks.get(hash)
+    }
+
+    /// Add `block`; returns whether it was new
+    pub fn insert(&mut self, block: Block) -> bool {
+        self.blocks.insert(block.header.hash(), block).is_none()
+    }
+
+    pub fn remove(&mut self, hash: &BlockHash) -> Option<Block> {
+        self.blocks.remove(hash)
+    }
+
+    /// Blocks no other block in the tree builds on
+    pub fn tips(&self) -> Vec<BlockHash> {
+        let parents: HashSet<_> = self.blocks.values()
+            .map(|block| block.header.previous_hash)
+            .collect();
+        self.blocks.keys().filter(|hash| !parents.contains(hash)).copied().collect()
+    }
+
+    /// The branch ending at `tip`, oldest first, back to the first block
+    /// whose parent is not in the tree
+    pub fn branch(&self, tip: &BlockHash) -> Vec<&Block> {
+        let mut branch: Vec<&Block> = std::iter::successors(self.blocks.get(tip), |block| self.blocks.get(&block.header.previous_hash))
+            .collect();
+        branch.reverse();
+        branch
+    }
+
+    /// Remove `hash` and every block building on it
+    pub fn remove_descendants(&mut self, hash: &BlockHash) {
+        let mut doomed = vec![*hash];
+        while let Some(hash) = doomed.pop() {
+            self.blocks.remove(&hash);
+            doomed.extend(self.blocks.iter()
+                .filter(|(_, block)| block.header.previous_hash == hash)
+                .map(|(hash, _)| *hash));
+        }
+    }
+
+    /// Drop blocks at or below `height`, which can no longer become canonical
+    pub fn prune(&mut self, height: u64) {
+        self.blocks.retain(|_, block| block.header.block_number > height);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(number: u64, parent: BlockHash, extra: u8) -> Block {
+        Block {
+            header: BlockHeader {
+                previous_hash: parent,
+                merkle_root: BlockHash::zero(),
+                prev_state_root: BlockHash::zero(),
+                state_root: BlockHash::zero(),
+                timestamp: 0,
+                block_number: number,
+                gas_used: 0,
+                gas_limit: 0,
+                producer: Address::new(1),
+                extra_data: vec![extra],
+                randomness: RandomnessProof::default(),
+            },
+            transactions: Vec::new(),
+            validator_signatures: Vec::new(),
+            recursive_proof: ZkProof { proof_data: Vec::new(), public_inputs: vec![], verification_key: vec![], proof_type: ProofType::Deferred },
+            protocol_updates: Vec::new(),
+            proof_signature: Vec::new(),
+            aggregate_signature: None,
+        }
+    }
+
+    #[test]
+    fn test_branches_and_pruning() {
+        let root = BlockHash::new([7; 32]);
+        let a2 = block(2, root, 0);
+        let a3 = block(3, a2.header.hash(), 0);
+        let b3 = block(3, a2.header.hash(), 1);
+        let mut tree = BlockTree::new();
+        for block in [a2.clone(), a3.clone(), b3.clone()] {
+            assert!(tree.insert(block));
+        }
+        assert!(!tree.insert(a3.clone()));
+
+        let mut tips = tree.tips();
+        tips.sort_by_key(|hash| hash.0);
+        let mut expected = vec![a3.header.hash(), b3.header.hash()];
+        expected.sort_by_key(|hash| hash.0);
+        assert_eq!(tips, expected);
+        let branch: Vec<u64> = tree.branch(&b3.header.hash()).iter().map(|b| b.header.block_number).collect();
+        assert_eq!(branch, vec![2, 3]);
+
+        tree.prune(2);
+        assert_eq!(tree.branch(&a3.header.hash()).len(), 1);
+        tree.remove_descendants(&a3.header.hash());
+        assert_eq!(tree.len(), 1);
+        tree.insert(a2.clone());
+        tree.remove_descendants(&a2.header.hash());
+        assert!(tree.is_empty());
+    }
+}
@@ -0,0 +1,219 @@
+//! Genesis file: the accounts, validators and protocol rules a chain starts with
+//!
+//! Files ending in `.json` are read as JSON, anything else as TOML. Only the
+//! validators are required; a file without `protocol` leaves the rules to
+//! whoever starts the engine (the node uses its `[consensus]` settings), and
+//! `chain_id` overrides the chain id of either.
+
+use crate::crypto::hash::hex_utils;
+use crate::crypto::keystore::KeyPair;
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum GenesisError {
+    #[error("failed to read genesis file {path}: {source}")]
+    Io { path: String, source: std::io::Error },
+    #[error("invalid TOML genesis: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("invalid JSON genesis: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("genesis has no validators")]
+    NoValidators,
+    #[error("account {0:?} is listed twice in genesis")]
+    DuplicateAccount(Address),
+    #[error("validator {0:?} is listed twice in genesis")]
+    DuplicateValidator(Address),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisConfig {
+    /// Chain transactions must be signed for; overrides `protocol.chain_id`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chain_id: Option<u64>,
+    /// Protocol rules the chain starts with
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<ProtocolConfig>,
+    #[serde(default)]
+    pub accounts: Vec<GenesisAccount>,
+    pub validators: Vec<GenesisValidator>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisAccount {
+    #[serde(with = "hex_utils::address_serde")]
+    pub address: Address,
+    pub balance: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct GenesisValidator {
+    #[serde(with = "hex_utils::address_serde")]
+    pub address: Address,
+    pub stake: u64,
+    #[serde(with = "hex::serde")]
+    pub public_key: Vec<u8>,
+    /// BLS12-381 key for aggregated votes, if the validator has one
+    #[serde(default, with = "hex::serde", skip_serializing_if = "Vec::is_empty")]
+    pub bls_public_key: Vec<u8>,
+}
+
+impl GenesisConfig {
+    /// Single-validator genesis for local development, with `validator` as the validator
+    pub fn dev(validator: &KeyPair) -> Self {
+        Self {
+            chain_id: None,
+            protocol: None,
+            accounts: vec![
+                GenesisAccount { address: validator.address(), balance: 1_000_000 },
+                GenesisAccount { address: Address::new(2), balance: 1_000_000 },
+            ],
+            validators: vec![GenesisValidator {
+                address: validator.address(),
+                stake: 32_000_000_000,
+                public_key: validator.public_key(),
+                bls_public_key: Vec::new(),
+            }],
+        }
+    }
+
+    /// Read `path` as JSON if it ends in `.json`, as TOML otherwise
+    pub fn load(path: &Path) -> Result<Self, GenesisError> {
+        let text = std::fs::read_to_string(path)
+            .map_err(|source| GenesisError::Io { path: path.display().to_string(), source })?;
+        if is_json(path) {
+            Self::from_json(&text)
+        } else {
+            Self::from_toml(&text)
+        }
+    }
+
+    /// Write to `path` in the format [`Self::load`] reads it in
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let text = if is_json(path) { self.to_json()? } else { self.to_toml()? };
+        std::fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn from_toml(text: &str) -> Result<Self, GenesisError> {
+        let genesis: Self = toml::from_str(text)?;
+        genesis.validate()?;
+        Ok(genesis)
+    }
+
+    pub fn from_json(text: &str) -> Result<Self, GenesisError> {
+        let genesis: Self = serde_json::from_str(text)?;
+        genesis.validate()?;
+        Ok(genesis)
+    }
+
+    pub fn to_toml(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn to_json(&self) -> anyhow::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Every account and validator appears once, and there is a validator
+    pub fn validate(&self) -> Result<(), GenesisError> {
+        if self.validators.is_empty() {
+            return Err(GenesisError::NoValidators);
+        }
+        let mut accounts = HashSet::new();
+        if let Some(account) = self.accounts.iter().find(|account| !accounts.insert(account.address)) {
+            return Err(GenesisError::DuplicateAccount(account.address));
+        }
+        let mut validators = HashSet::new();
+        if let Some(validator) = self.validators.iter().find(|validator| !validators.insert(validator.address)) {
+            return Err(GenesisError::DuplicateValidator(validator.address));
+        }
+        Ok(())
+    }
+
+    /// The genesis protocol rules, or `fallback` when the file has none,
+    /// under the genesis chain id if it sets one
+    pub fn protocol_config(&self, fallback: ProtocolConfig) -> ProtocolConfig {
+        let mut protocol = self.protocol.clone().unwrap_or(fallback);
+        if let Some(chain_id) = self.chain_id {
+            protocol.chain_id = chain_id;
+        }
+        protocol
+    }
+
+    pub fn world_state(&self) -> WorldState {
+        let accounts: HashMap<Address, Account> = self.accounts.iter()
+            .map(|account| (account.address, Account::new(account.balance)))
+            .collect();
+        let mut state = WorldState {
+            accounts,
+            global_nonce: 0,
+            state_root: BlockHash::zero(),
+            block_number: 0,
+        };
+        state.state_root = state.compute_state_root();
+        state
+    }
+
+    pub fn validators(&self) -> Vec<Validator> {
+        self.validators.iter()
+            .map(|validator| Validator {
+                address: validator.address,
+                stake: validator.stake,
+                public_key: validator.public_key.clone(),
+                performance_score: 1.0,
+                bls_public_key: validator.bls_public_key.clone(),
+            })
+            .collect()
+    }
+}
+
+fn is_json(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("json"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_toml_and_json_genesis_agree() {
+        let key = KeyPair::generate(SignatureType::Ed25519);
+        let genesis = GenesisConfig {
+            chain_id: Some(7),
+            protocol: Some(ProtocolConfig { max_transactions_per_block: 500, ..ProtocolConfig::default() }),
+            ..GenesisConfig::dev(&key)
+        };
+
+        let from_toml = GenesisConfig::from_toml(&genesis.to_toml().unwrap()).unwrap();
+        let from_json = GenesisConfig::from_json(&genesis.to_json().unwrap()).unwrap();
+        for loaded in [from_toml, from_json] {
+            assert_eq!(loaded.validators[0].public_key, key.public_key());
+            assert_eq!(loaded.world_state().state_root, genesis.world_state().state_root);
+            let protocol = loaded.protocol_config(ProtocolConfig::default());
+            assert_eq!(protocol.chain_id, 7);
+            assert_eq!(protocol.max_transactions_per_block, 500);
+        }
+
+        // Without rules of its own, genesis keeps the fallback's
+        let dev = GenesisConfig::dev(&key);
+        assert_eq!(dev.protocol_config(ProtocolConfig::default()).chain_id, DEFAULT_CHAIN_ID);
+    }
+
+    #[test]
+    fn test_rejects_duplicate_or_missing_validators() {
+        let key = KeyPair::generate(SignatureType::Ed25519);
+        let mut genesis = GenesisConfig::dev(&key);
+        genesis.validators.push(genesis.validators[0].clone());
+        assert!(matches!(GenesisConfig::from_json(&genesis.to_json().unwrap()), Err(GenesisError::DuplicateValidator(_))));
+
+        genesis.validators.clear();
+        assert!(matches!(GenesisConfig::from_toml(&genesis.to_toml().unwrap()), Err(GenesisError::NoValidators)));
+    }
+}
@@ -0,0 +1,473 @@
+//! Self-amendment of protocol parameters
+//!
+//! Any account proposes a [`ProtocolRule`] with a transaction to
+//! [`GOVERNANCE_ADDRESS`] carrying [`GovernanceAction::Propose`]. The rule's
+//! `rule_id` names the parameter it changes and `rule_data` the new value,
+//! see [`RuleChange`]; a rule can also install a WASM module as the
+//! transaction hooks of [`execution`]. Its `validity_proof` proves the rule
+//! well-formed, committing to it through [`rule_commitment`], and is verified
+//! by the node's prover backend through [`BackendRuleVerifier`]. Validators vote with
+//! [`GovernanceAction::Vote`] until the end of the epoch after the one the
+//! proposal was included in. When that epoch ends, a proposal approved by
+//! validators holding two thirds of the stake is scheduled for its
+//! `activation_epoch`, and any other is dropped. When the activation epoch
+//! starts the engine applies the rule to its [`ProtocolConfig`], and the
+//! first block of the epoch lists the rule in `protocol_updates`. State
+//! snapshots do not carry open proposals yet, so a node started from one
+//! only sees proposals made after its snapshot.
+
+use super::execution;
+use super::finality::has_quorum;
+use crate::crypto::keystore::KeyPair;
+use crate::serialization::canonical::CanonicalEncode;
+use crate::serialization::{DecodeLimits, PayloadKind, canonical_hash, decode_bounded};
+use crate::types::*;
+use crate::zkvm::ProverBackend;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use thiserror::Error;
+use tokio::time::Duration;
+
+/// Recipient of governance transactions
+pub const GOVERNANCE_ADDRESS: Address = Address(*b"zk-sac/governance\0\0\0");
+
+const RULE_COMMITMENT_DOMAIN: &[u8] = b"zk-sac/protocol-rule/v1";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum GovernanceError {
+    #[error("unknown rule id {0}")]
+    UnknownRule(u32),
+    #[error("rule {rule_id} is malformed: {reason}")]
+    Malformed { rule_id: u32, reason: String },
+    #[error("validity proof of rule {rule_id} rejected: {reason}")]
+    InvalidProof { rule_id: u32, reason: String },
+    #[error("rule activates in epoch {activation_epoch}, before voting ends after epoch {voting_ends}")]
+    ActivationTooEarly { activation_epoch: u64, voting_ends: u64 },
+    #[error("rule {0:?} is already proposed")]
+    AlreadyProposed(BlockHash),
+    #[error("no open proposal {0:?}")]
+    UnknownProposal(BlockHash),
+    #[error("{0:?} is not a validator")]
+    NotValidator(Address),
+    #[error("{voter:?} already voted on {proposal:?}")]
+    AlreadyVoted { voter: Address, proposal: BlockHash },
+    #[error("malformed governance action: {0}")]
+    MalformedAction(String),
+}
+
+/// A protocol parameter a rule can change, with its new value
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleChange {
+    BlockGasLimit(u64),
+    BlockTime(Duration),
+    MaxTransactionsPerBlock(u64),
+    MinStakeThreshold(u64),
+    /// WASM module to install as the transaction hooks, or empty to remove them
+    TransactionHooks(Vec<u8>),
+}
+
+impl RuleChange {
+    pub const BLOCK_GAS_LIMIT: u32 = 1;
+    pub const BLOCK_TIME_MS: u32 = 2;
+    pub const MAX_TRANSACTIONS_PER_BLOCK: u32 = 3;
+    pub const MIN_STAKE_THRESHOLD: u32 = 4;
+    pub const TRANSACTION_HOOKS: u32 = 5;
+
+    pub fn rule_id(&self) -> u32 {
+        match self {
+            RuleChange::BlockGasLimit(_) => Self::BLOCK_GAS_LIMIT,
+            RuleChange::BlockTime(_) => Self::BLOCK_TIME_MS,
+            RuleChange::MaxTransactionsPerBlock(_) => Self::MAX_TRANSACTIONS_PER_BLOCK,
+            RuleChange::MinStakeThreshold(_) => Self::MIN_STAKE_THRESHOLD,
+            RuleChange::TransactionHooks(_) => Self::TRANSACTION_HOOKS,
+        }
+    }
+
+    /// The value as `rule_data`: eight bytes, big-endian, or the module itself
+    pub fn rule_data(&self) -> Vec<u8> {
+        let value = match self {
+            RuleChange::BlockGasLimit(value)
+            | RuleChange::MaxTransactionsPerBlock(value)
+            | RuleChange::MinStakeThreshold(value) => *value,
+            RuleChange::BlockTime(block_time) => block_time.as_millis() as u64,
+            RuleChange::TransactionHooks(module) => return module.clone(),
+        };
+        value.to_be_bytes().to_vec()
+    }
+
+    /// Decode and bounds-check the change `rule` makes
+    pub fn from_rule(rule: &ProtocolRule) -> Result<Self, GovernanceError> {
+        let malformed = |reason: &str| GovernanceError::Malformed { rule_id: rule.rule_id, reason: reason.to_string() };
+        if rule.rule_id == Self::TRANSACTION_HOOKS {
+            if !rule.rule_data.is_empty() {
+                execution::load_hooks(&rule.rule_data).map_err(|e| malformed(&e.to_string()))?;
+            }
+            return Ok(RuleChange::TransactionHooks(rule.rule_data.clone()));
+        }
+        let bytes: [u8; 8] = rule.rule_data.as_slice().try_into()
+            .map_err(|_| malformed("rule data must be 8 bytes"))?;
+        let value = u64::from_be_bytes(bytes);
+        let change = match rule.rule_id {
+            Self::BLOCK_GAS_LIMIT => RuleChange::BlockGasLimit(value),
+            Self::BLOCK_TIME_MS => RuleChange::BlockTime(Duration::from_millis(value)),
+            Self::MAX_TRANSACTIONS_PER_BLOCK => RuleChange::MaxTransactionsPerBlock(value),
+            Self::MIN_STAKE_THRESHOLD => RuleChange::MinStakeThreshold(value),
+            rule_id => return Err(GovernanceError::UnknownRule(rule_id)),
+        };
+        match change {
+            RuleChange::BlockGasLimit(limit) if limit < GasSchedule::default().transaction_base => {
+                Err(malformed("block gas limit is below the gas of one transaction"))
+            }
+            RuleChange::BlockTime(block_time) if block_time < Duration::from_millis(100) || block_time > Duration::from_secs(60) => {
+                Err(malformed("block time must be between 100ms and 60s"))
+            }
+            RuleChange::MaxTransactionsPerBlock(0) => Err(malformed("blocks must fit at least one transaction")),
+            RuleChange::MinStakeThreshold(0) => Err(malformed("minimum stake must be positive")),
+            change => Ok(change),
+        }
+    }
+
+    /// Switch `config` to the new value
+    pub fn apply(&self, config: &mut ProtocolConfig) {
+        match self {
+            RuleChange::BlockGasLimit(limit) => config.gas_schedule.block_gas_limit = *limit,
+            RuleChange::BlockTime(block_time) => config.block_time = *block_time,
+            RuleChange::MaxTransactionsPerBlock(max) => config.max_transactions_per_block = *max as usize,
+            RuleChange::MinStakeThreshold(minimum) => config.min_stake_threshold = *minimum,
+            RuleChange::TransactionHooks(module) => config.transaction_hooks = module.clone(),
+        }
+    }
+}
+
+/// Hash identifying a rule and what its validity proof commits to
+pub fn rule_commitment(rule: &ProtocolRule) -> BlockHash {
+    let mut bytes = RULE_COMMITMENT_DOMAIN.to_vec();
+    rule.rule_id.encode_canonical(&mut bytes);
+    rule.rule_data.encode_canonical(&mut bytes);
+    rule.activation_epoch.encode_canonical(&mut bytes);
+    canonical_hash(bytes.as_slice())
+}
+
+/// Checks the proof that a rule is well-formed
+pub trait RuleProofVerifier: Send + Sync {
+    fn verify(&self, rule: &ProtocolRule) -> Result<(), String>;
+}
+
+/// Checks rule proofs with a prover backend: a proof of the backend's type,
+/// committing to the rule through its public inputs, that the backend verifies
+pub struct BackendRuleVerifier {
+    backend: Arc<dyn ProverBackend>,
+}
+
+impl BackendRuleVerifier {
+    pub fn new(backend: Arc<dyn ProverBackend>) -> Self {
+        Self { backend }
+    }
+}
+
+impl RuleProofVerifier for BackendRuleVerifier {
+    fn verify(&self, rule: &ProtocolRule) -> Result<(), String> {
+        let proof = &rule.validity_proof;
+        if matches!(proof.proof_type, ProofType::Deferred) || proof.proof_data.is_empty() {
+            return Err("proof is missing".to_string());
+        }
+        let expected = self.backend.proof_type();
+        if proof.proof_type != expected {
+            return Err(format!("{:?} proof, this node verifies {:?} proofs", proof.proof_type, expected));
+        }
+        if proof.public_inputs != rule_commitment(rule).0 {
+            return Err("proof is for another rule".to_string());
+        }
+        // Backends verify on the calling thread, so nothing needs a runtime
+        match futures::executor::block_on(self.backend.verify_proof(&proof.proof_data)) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err("proof does not verify".to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Accepts the proofs [`prove_rule`] produces: present, not deferred, and
+/// committing to the rule through their public inputs
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct MockRuleVerifier;
+
+#[cfg(test)]
+impl RuleProofVerifier for MockRuleVerifier {
+    fn verify(&self, rule: &ProtocolRule) -> Result<(), String> {
+        let proof = &rule.validity_proof;
+        if matches!(proof.proof_type, ProofType::Deferred) || proof.proof_data.is_empty() {
+            return Err("proof is missing".to_string());
+        }
+        if proof.public_inputs != rule_commitment(rule).0 {
+            return Err("proof is for another rule".to_string());
+        }
+        Ok(())
+    }
+}
+
+/// A rule making `change` from `activation_epoch` on, with the mock
+/// prover's validity proof
+pub fn prove_rule(change: RuleChange, activation_epoch: u64) -> Result<ProtocolRule, GovernanceError> {
+    let mut rule = ProtocolRule {
+        rule_id: change.rule_id(),
+        rule_data: change.rule_data(),
+        validity_proof: ZkProof { proof_data: Vec::new(), public_inputs: Vec::new(), verification_key: Vec::new(), proof_type: ProofType::Risc0 },
+        activation_epoch,
+    };
+    RuleChange::from_rule(&rule)?;
+    let commitment = rule_commitment(&rule);
+    rule.validity_proof.proof_data = commitment.0.to_vec();
+    rule.validity_proof.public_inputs = commitment.0.to_vec();
+    Ok(rule)
+}
+
+/// Check `rule`'s proof and decode its change
+pub fn verify_rule(rule: &ProtocolRule, verifier: &dyn RuleProofVerifier) -> Result<RuleChange, GovernanceError> {
+    verifier.verify(rule).map_err(|reason| GovernanceError::InvalidProof { rule_id: rule.rule_id, reason })?;
+    RuleChange::from_rule(rule)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GovernanceAction {
+    Propose { rule: ProtocolRule },
+    /// Approve or reject the proposal whose rule has this commitment
+    Vote { proposal: BlockHash, approve: bool },
+}
+
+impl GovernanceAction {
+    /// A transaction from `sender` performing this action
+    pub fn to_transaction(&self, sender: &KeyPair, nonce: u64) -> anyhow::Result<Transaction> {
+        let mut transaction = Transaction::new(sender.address(), GOVERNANCE_ADDRESS, 0, nonce);
+        transaction.data = bincode::serialize(self)?;
+        transaction.gas_limit = GasSchedule::default().intrinsic_gas(&transaction);
+        transaction.signed(sender)
+    }
+
+    /// The action `transaction` carries, or `None` if it is not a governance transaction
+    pub fn from_transaction(transaction: &Transaction) -> Option<Result<Self, GovernanceError>> {
+        if transaction.to != GOVERNANCE_ADDRESS {
+            return None;
+        }
+        Some(decode_bounded(PayloadKind::Transaction, &transaction.data, &DecodeLimits::default())
+            .map_err(|e| GovernanceError::MalformedAction(e.to_string())))
+    }
+}
+
+/// A rule being voted on
+#[derive(Debug, Clone)]
+pub struct Proposal {
+    pub rule: ProtocolRule,
+    pub proposer: Address,
+    /// Last epoch votes are accepted in
+    pub voting_ends: u64,
+    /// Each voter's choice
+    pub votes: BTreeMap<Address, bool>,
+}
+
+/// What a governance action or the end of a vote did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum GovernanceOutcome {
+    Proposed { proposal: BlockHash, rule_id: u32, voting_ends: u64 },
+    Voted { proposal: BlockHash, voter: Address, approve: bool },
+    Approved { proposal: BlockHash, activation_epoch: u64 },
+    Rejected { proposal: BlockHash, approving_stake: u64 },
+}
+
+/// Open proposals and the approved rules waiting for their epoch
+#[derive(Debug, Clone, Default)]
+pub struct Governance {
+    proposals: BTreeMap<BlockHash, Proposal>,
+    scheduled: BTreeMap<u64, Vec<ProtocolRule>>,
+    /// Rules applied when each epoch started
+    activated: BTreeMap<u64, Vec<ProtocolRule>>,
+}
+
+impl Governance {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn proposal(&self, commitment: &BlockHash) -> Option<&Proposal> {
+        self.proposals.get(commitment)
+    }
+
+    /// Apply `action` from `transaction`, included in block `block_number`
+    pub fn apply(
+        &mut self,
+        transaction: &Transaction,
+        action: &GovernanceAction,
+        block_number: u64,
+        validators: &ValidatorSet,
+        config: &ProtocolConfig,
+        verifier: &dyn RuleProofVerifier,
+    ) -> Result<GovernanceOutcome, GovernanceError> {
+        let epoch = config.epoch_schedule.epoch_of(block_number);
+        match action {
+            GovernanceAction::Propose { rule } => {
+                verify_rule(rule, verifier)?;
+                let voting_ends = epoch + 1;
+                if rule.activation_epoch <= voting_ends {
+                    return Err(GovernanceError::ActivationTooEarly { activation_epoch: rule.activation_epoch, voting_ends });
+                }
+                let proposal = rule_commitment(rule);
+                if self.proposals.contains_key(&proposal) {
+                    return Err(GovernanceError::AlreadyProposed(proposal));
+                }
+                self.proposals.insert(proposal, Proposal { rule: rule.clone(), proposer: transaction.from, voting_ends, votes: BTreeMap::new() });
+                Ok(GovernanceOutcome::Proposed { proposal, rule_id: rule.rule_id, voting_ends })
+            }
+            GovernanceAction::Vote { proposal, approve } => {
+                let open = self.proposals.get_mut(proposal)
+                    .filter(|open| open.voting_ends >= epoch)
+                    .ok_or(GovernanceError::UnknownProposal(*proposal))?;
+                let voter = transaction.from;
+                if !validators.validators.iter().any(|validator| validator.address == voter) {
+                    return Err(GovernanceError::NotValidator(voter));
+                }
+                if open.votes.contains_key(&voter) {
+                    return Err(GovernanceError::AlreadyVoted { voter, proposal: *proposal });
+                }
+                open.votes.insert(voter, *approve);
+                Ok(GovernanceOutcome::Voted { proposal: *proposal, voter, approve: *approve })
+            }
+        }
+    }
+
+    /// Count the votes of the proposals whose voting ends with `epoch`,
+    /// with the stake `validators` hold now, and schedule the approved ones
+    pub fn close_voting(&mut self, epoch: u64, validators: &ValidatorSet) -> Vec<GovernanceOutcome> {
+        let closing: Vec<BlockHash> = self.proposals.iter()
+            .filter(|(_, proposal)| proposal.voting_ends <= epoch)
+            .map(|(commitment, _)| *commitment)
+            .collect();
+        let mut outcomes = Vec::with_capacity(closing.len());
+        for commitment in closing {
+            let proposal = self.proposals.remove(&commitment).expect("closing proposal is open");
+            let approving_stake: u64 = validators.validators.iter()
+                .filter(|validator| proposal.votes.get(&validator.address) == Some(&true))
+                .map(|validator| validator.stake)
+                .sum();
+            if has_quorum(approving_stake, validators.total_stake) {
+                let activation_epoch = proposal.rule.activation_epoch;
+                self.scheduled.entry(activation_epoch).or_default().push(proposal.rule);
+                outcomes.push(GovernanceOutcome::Approved { proposal: commitment, activation_epoch });
+            } else {
+                outcomes.push(GovernanceOutcome::Rejected { proposal: commitment, approving_stake });
+            }
+        }
+        outcomes
+    }
+
+    /// Apply the rules scheduled for `epoch` to `config`, returning them
+    pub fn enter_epoch(&mut self, epoch: u64, config: &mut ProtocolConfig) -> Vec<ProtocolRule> {
+        let Some(rules) = self.scheduled.remove(&epoch) else { return Vec::new() };
+        for rule in &rules {
+            // Rules were checked when proposed
+            if let Ok(change) = RuleChange::from_rule(rule) {
+                change.apply(config);
+            }
+        }
+        self.activated.insert(epoch, rules.clone());
+        rules
+    }
+
+    /// Rules the first block of `epoch` must list in `protocol_updates`
+    pub fn activated_in(&self, epoch: u64) -> &[ProtocolRule] {
+        self.activated.get(&epoch).map(Vec::as_slice).unwrap_or(&[])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators(keys: &[KeyPair]) -> ValidatorSet {
+        ValidatorSet::new(keys.iter()
+            .map(|key| Validator { address: key.address(), stake: 100, public_key: key.public_key(), performance_score: 1.0, bls_public_key: Vec::new() })
+            .collect())
+    }
+
+    fn short_epochs() -> ProtocolConfig {
+        ProtocolConfig {
+            epoch_schedule: EpochSchedule { epoch_length: 10, ..EpochSchedule::default() },
+            ..ProtocolConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_approved_rule_changes_config_at_activation_epoch() {
+        let keys: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+        let set = validators(&keys);
+        let mut config = short_epochs();
+        let mut governance = Governance::new();
+        let apply = |governance: &mut Governance, key: &KeyPair, action: GovernanceAction, block_number: u64| {
+            let transaction = action.to_transaction(key, 0).unwrap();
+            let action = GovernanceAction::from_transaction(&transaction).unwrap().unwrap();
+            governance.apply(&transaction, &action, block_number, &set, &short_epochs(), &MockRuleVerifier)
+        };
+
+        // Voting ends with epoch 1, so the rule cannot activate before epoch 2
+        let early = prove_rule(RuleChange::BlockGasLimit(5_000_000), 1).unwrap();
+        assert!(matches!(apply(&mut governance, &keys[0], GovernanceAction::Propose { rule: early }, 5),
+                         Err(GovernanceError::ActivationTooEarly { .. })));
+        let mut forged = prove_rule(RuleChange::BlockGasLimit(5_000_000), 3).unwrap();
+        forged.rule_data = 1u64.to_be_bytes().to_vec();
+        assert!(matches!(apply(&mut governance, &keys[0], GovernanceAction::Propose { rule: forged }, 5),
+                         Err(GovernanceError::InvalidProof { .. })));
+
+        let rule = prove_rule(RuleChange::BlockGasLimit(5_000_000), 3).unwrap();
+        let proposal = rule_commitment(&rule);
+        apply(&mut governance, &keys[0], GovernanceAction::Propose { rule }, 5).unwrap();
+        let outsider = KeyPair::generate(SignatureType::Ed25519);
+        assert!(matches!(apply(&mut governance, &outsider, GovernanceAction::Vote { proposal, approve: true }, 6),
+                         Err(GovernanceError::NotValidator(_))));
+        for key in &keys[..2] {
+            apply(&mut governance, key, GovernanceAction::Vote { proposal, approve: true }, 12).unwrap();
+        }
+        assert!(matches!(apply(&mut governance, &keys[1], GovernanceAction::Vote { proposal, approve: false }, 13),
+                         Err(GovernanceError::AlreadyVoted { .. })));
+
+        assert!(governance.close_voting(0, &set).is_empty());
+        assert_eq!(governance.close_voting(1, &set), vec![GovernanceOutcome::Approved { proposal, activation_epoch: 3 }]);
+        assert!(governance.enter_epoch(2, &mut config).is_empty());
+        assert_eq!(governance.enter_epoch(3, &mut config).len(), 1);
+        assert_eq!(config.gas_schedule.block_gas_limit, 5_000_000);
+        assert_eq!(governance.activated_in(3).len(), 1);
+    }
+
+    #[test]
+    fn test_proposal_without_two_thirds_is_dropped() {
+        let keys: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+        let set = validators(&keys);
+        let mut governance = Governance::new();
+        let rule = prove_rule(RuleChange::BlockTime(Duration::from_secs(2)), 5).unwrap();
+        let proposal = rule_commitment(&rule);
+        let transaction = GovernanceAction::Propose { rule: rule.clone() }.to_transaction(&keys[0], 0).unwrap();
+        governance.apply(&transaction, &GovernanceAction::Propose { rule }, 0, &set, &short_epochs(), &MockRuleVerifier).unwrap();
+        let vote = GovernanceAction::Vote { proposal, approve: true };
+        governance.apply(&vote.to_transaction(&keys[0], 1).unwrap(), &vote, 1, &set, &short_epochs(), &MockRuleVerifier).unwrap();
+
+        assert_eq!(governance.close_voting(1, &set), vec![GovernanceOutcome::Rejected { proposal, approving_stake: 100 }]);
+        let mut config = short_epochs();
+        assert!(governance.enter_epoch(5, &mut config).is_empty());
+        assert_eq!(config.block_time, ProtocolConfig::default().block_time);
+        assert!(governance.proposal(&proposal).is_none());
+    }
+
+    #[test]
+    fn test_rule_proofs_are_checked_by_the_prover_backend() {
+        let config = ZkVMConfig { backend: ProverBackendKind::Sp1, ..ZkVMConfig::default() };
+        let verifier = BackendRuleVerifier::new(crate::zkvm::prover_backend(&config).unwrap().into());
+        let mut rule = prove_rule(RuleChange::BlockGasLimit(5_000_000), 3).unwrap();
+        assert!(matches!(verify_rule(&rule, &verifier), Err(GovernanceError::InvalidProof { .. })));
+
+        rule.validity_proof.proof_type = ProofType::SP1;
+        assert_eq!(verify_rule(&rule, &verifier).unwrap(), RuleChange::BlockGasLimit(5_000_000));
+        let other = prove_rule(RuleChange::BlockGasLimit(6_000_000), 3).unwrap();
+        rule.validity_proof.public_inputs = other.validity_proof.public_inputs;
+        assert!(matches!(verify_rule(&rule, &verifier), Err(GovernanceError::InvalidProof { .. })));
+    }
+}
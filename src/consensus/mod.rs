@@ -1,3 +1,25 @@
 pub mod engine;
+pub mod error;
+pub mod events;
+pub mod execution;
+pub mod finality;
+pub mod fork_choice;
+pub mod genesis;
+pub mod governance;
+pub mod registration;
+pub mod runner;
+pub mod slashing;
+pub mod staking;
 
-pub use engine::*; 
\ No newline at end of file
+pub use engine::*;
+pub use error::ConsensusError;
+pub use events::{BalanceChange, ConsensusEvent, EventBus, GovernanceEvent, TransactionReceipt, ValidatorEvent};
+pub use execution::{BlockContext, BlockExecution, ExecutionError, ExecutionResult};
+pub use finality::{Attestation, FinalityError, FinalityGadget};
+pub use fork_choice::{BlockImport, BlockTree, ChainSnapshot};
+pub use genesis::{GenesisConfig, GenesisError};
+pub use governance::{GovernanceAction, GovernanceError, RuleChange};
+pub use registration::{KeyRotation, ValidatorRegistration};
+pub use runner::{ConsensusRunner, RunnerState, RunnerStats};
+pub use slashing::{Offense, ProofSubmission, Slasher, SlashingEvidence};
+pub use staking::{StakingAction, StakingError};
@@ -0,0 +1,158 @@
+//! Signed validator registration and key rotation messages
+//!
+//! A registration proves control of the key being registered. A rotation is
+//! signed by both the outgoing and the incoming key, so neither can be swapped
+//! in without the other.
+
+use crate::crypto::hash::hex_utils;
+use crate::crypto::keystore::{KeyPair, address_of, verify_signature};
+use crate::types::{Address, SignatureType};
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+
+const REGISTRATION_DOMAIN: &[u8] = b"zk-sac/validator-registration/v1";
+const ROTATION_DOMAIN: &[u8] = b"zk-sac/key-rotation/v1";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ValidatorRegistration {
+    #[serde(with = "hex_utils::address_serde")]
+    pub address: Address,
+    pub sig_type: SignatureType,
+    #[serde(with = "hex::serde")]
+    pub public_key: Vec<u8>,
+    pub stake: u64,
+    #[serde(with = "hex::serde")]
+    pub signature: Vec<u8>,
+}
+
+impl ValidatorRegistration {
+    pub fn new(key: &KeyPair, stake: u64) -> Result<Self> {
+        let mut registration = Self {
+            address: key.address(),
+            sig_type: key.sig_type().clone(),
+            public_key: key.public_key(),
+            stake,
+            signature: Vec::new(),
+        };
+        registration.signature = key.sign(&registration.signing_bytes())?;
+        Ok(registration)
+    }
+
+    /// Domain-separated bytes covered by the signature
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = REGISTRATION_DOMAIN.to_vec();
+        bytes.extend_from_slice(&self.address.0);
+        bytes.push(sig_type_tag(&self.sig_type));
+        push_bytes(&mut bytes, &self.public_key);
+        bytes.extend_from_slice(&self.stake.to_le_bytes());
+        bytes
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        if address_of(&self.sig_type, &self.public_key) != self.address {
+            bail!("Registration address does not match its public key");
+        }
+        verify_signature(&self.sig_type, &self.public_key, &self.signing_bytes(), &self.signature)
+            .context("Invalid registration signature")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRotation {
+    #[serde(with = "hex_utils::address_serde")]
+    pub validator: Address,
+    pub old_sig_type: SignatureType,
+    #[serde(with = "hex::serde")]
+    pub old_public_key: Vec<u8>,
+    pub new_sig_type: SignatureType,
+    #[serde(with = "hex::serde")]
+    pub new_public_key: Vec<u8>,
+    /// First epoch in which the new key signs
+    pub effective_epoch: u64,
+    #[serde(with = "hex::serde")]
+    pub old_signature: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    pub new_signature: Vec<u8>,
+}
+
+impl KeyRotation {
+    /// Rotate the validator identified by `old_key` to `new_key`
+    pub fn new(old_key: &KeyPair, new_key: &KeyPair, effective_epoch: u64) -> Result<Self> {
+        let mut rotation = Self {
+            validator: old_key.address(),
+            old_sig_type: old_key.sig_type().clone(),
+            old_public_key: old_key.public_key(),
+            new_sig_type: new_key.sig_type().clone(),
+            new_public_key: new_key.public_key(),
+            effective_epoch,
+            old_signature: Vec::new(),
+            new_signature: Vec::new(),
+        };
+        let message = rotation.signing_bytes();
+        rotation.old_signature = old_key.sign(&message)?;
+        rotation.new_signature = new_key.sign(&message)?;
+        Ok(rotation)
+    }
+
+    /// Domain-separated bytes covered by both signatures
+    pub fn signing_bytes(&self) -> Vec<u8> {
+        let mut bytes = ROTATION_DOMAIN.to_vec();
+        bytes.extend_from_slice(&self.validator.0);
+        bytes.push(sig_type_tag(&self.old_sig_type));
+        push_bytes(&mut bytes, &self.old_public_key);
+        bytes.push(sig_type_tag(&self.new_sig_type));
+        push_bytes(&mut bytes, &self.new_public_key);
+        bytes.extend_from_slice(&self.effective_epoch.to_le_bytes());
+        bytes
+    }
+
+    pub fn verify(&self) -> Result<()> {
+        if address_of(&self.old_sig_type, &self.old_public_key) != self.validator {
+            bail!("Rotation validator does not match the old public key");
+        }
+        let message = self.signing_bytes();
+        verify_signature(&self.old_sig_type, &self.old_public_key, &message, &self.old_signature)
+            .context("Invalid signature from the old key")?;
+        verify_signature(&self.new_sig_type, &self.new_public_key, &message, &self.new_signature)
+            .context("Invalid signature from the new key")
+    }
+}
+
+fn sig_type_tag(sig_type: &SignatureType) -> u8 {
+    match sig_type {
+        SignatureType::Ed25519 => 0,
+        SignatureType::Secp256k1 => 1,
+        SignatureType::PostQuantum => 2,
+    }
+}
+
+fn push_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    buffer.extend_from_slice(&(bytes.len() as u32).to_le_bytes());
+    buffer.extend_from_slice(bytes);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registration_and_rotation_verify() {
+        let old_key = KeyPair::generate(SignatureType::Ed25519);
+        let registration = ValidatorRegistration::new(&old_key, 32_000).unwrap();
+        registration.verify().unwrap();
+
+        let mut inflated = registration.clone();
+        inflated.stake = 64_000;
+        assert!(inflated.verify().is_err());
+
+        let new_key = KeyPair::generate(SignatureType::Secp256k1);
+        let rotation = KeyRotation::new(&old_key, &new_key, 7).unwrap();
+        rotation.verify().unwrap();
+
+        let json = serde_json::to_string(&rotation).unwrap();
+        let mut decoded: KeyRotation = serde_json::from_str(&json).unwrap();
+        decoded.verify().unwrap();
+        decoded.new_public_key = KeyPair::generate(SignatureType::Secp256k1).public_key();
+        assert!(decoded.verify().is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! Block production loop driven by `ProtocolConfig::block_time`
+//!
+//! The runner divides time into slots of one block time, counted from when
+//! it starts. At the start of each slot it selects the producer of the next
+//! block and, when the engine signs for that validator, produces, validates
+//! and applies the block, which must be done by the end of the slot. A slot
+//! that passes while an earlier one is still busy is missed and skipped, not
+//! produced late.
+
+use super::engine::{ConsensusEngine, ZkSacConsensusEngine};
+use crate::async_utils::Deadline;
+use anyhow::{Result, anyhow, bail};
+use parking_lot::Mutex as StatsLock;
+use std::sync::Arc;
+use tokio::sync::{Mutex, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RunnerState {
+    Stopped,
+    Running,
+    /// Slots pass without blocks until resumed
+    Paused,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RunnerStats {
+    /// Slots the runner was running (not paused) for
+    pub slots: u64,
+    pub produced: u64,
+    /// Slots whose producer is a validator this engine does not sign for
+    pub other_producer: u64,
+    /// Slots that passed while an earlier slot was still producing
+    pub missed: u64,
+    /// Slots whose block failed to produce, validate or apply
+    pub failed: u64,
+}
+
+pub struct ConsensusRunner {
+    engine: Arc<Mutex<ZkSacConsensusEngine>>,
+    paused: watch::Sender<bool>,
+    stats: Arc<StatsLock<RunnerStats>>,
+    task: Option<(CancellationToken, JoinHandle<()>)>,
+}
+
+impl ConsensusRunner {
+    pub fn new(engine: Arc<Mutex<ZkSacConsensusEngine>>) -> Self {
+        Self {
+            engine,
+            paused: watch::channel(false).0,
+            stats: Arc::new(StatsLock::new(RunnerStats::default())),
+            task: None,
+        }
+    }
+
+    /// The engine, shared with the production task
+    pub fn engine(&self) -> &Arc<Mutex<ZkSacConsensusEngine>> {
+        &self.engine
+    }
+
+    /// Start producing, with the first slot starting now
+    pub fn start(&mut self) -> Result<()> {
+        if self.task.is_some() {
+            bail!("Consensus runner is already running");
+        }
+        let stop = CancellationToken::new();
+        let task = tokio::spawn(run_slots(self.engine.clone(), self.paused.subscribe(), self.stats.clone(), stop.clone()));
+        self.task = Some((stop, task));
+        Ok(())
+    }
+
+    /// Let slots pass without producing; a block in progress is finished
+    pub fn pause(&self) {
+        self.paused.send_replace(true);
+    }
+
+    pub fn resume(&self) {
+        self.paused.send_replace(false);
+    }
+
+    pub fn state(&self) -> RunnerState {
+        match (&self.task, *self.paused.borrow()) {
+            (None, _) => RunnerState::Stopped,
+            (Some(_), true) => RunnerState::Paused,
+            (Some(_), false) => RunnerState::Running,
+        }
+    }
+
+    pub fn stats(&self) -> RunnerStats {
+        self.stats.lock().clone()
+    }
+
+    /// Stop after the slot in progress, returning the stats so far
+    pub async fn stop(&mut self) -> Result<RunnerStats> {
+        if let Some((stop, task)) = self.task.take() {
+            stop.cancel();
+            task.await.map_err(|e| anyhow!("Consensus runner panicked: {}", e))?;
+        }
+        Ok(self.stats())
+    }
+}
+
+async fn run_slots(
+    engine: Arc<Mutex<ZkSacConsensusEngine>>,
+    paused: watch::Receiver<bool>,
+    stats: Arc<StatsLock<RunnerStats>>,
+    stop: CancellationToken,
+) {
+    let mut block_time = engine.lock().await.protocol_config.block_time;
+    info!("⏱️  Consensus runner started with {:?} slots", block_time);
+    let mut start = Instant::now();
+    let mut ticker = tokio::time::interval_at(start, block_time);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+    let mut last_slot: Option<u64> = None;
+
+    loop {
+        tokio::select! {
+            _ = stop.cancelled() => break,
+            _ = ticker.tick() => {}
+        }
+        let (elapsed, slot_nanos) = (start.elapsed().as_nanos(), block_time.as_nanos().max(1));
+        let slot = (elapsed / slot_nanos) as u64;
+        let missed = last_slot.map_or(0, |last| slot.saturating_sub(last + 1));
+        last_slot = Some(slot);
+        if *paused.borrow() {
+            continue;
+        }
+        {
+            let mut counts = stats.lock();
+            counts.slots += 1;
+            counts.missed += missed;
+        }
+        if missed > 0 {
+            warn!("⏭️  Missed {} slots before slot {}", missed, slot);
+        }
+
+        let deadline = Deadline::after(Duration::from_nanos((slot_nanos - elapsed % slot_nanos) as u64));
+        let (outcome, next_block_time) = {
+            let mut engine = engine.lock().await;
+            let outcome = produce_in_slot(&mut engine, deadline).await;
+            (outcome, engine.protocol_config.block_time)
+        };
+        {
+            let mut counts = stats.lock();
+            match outcome {
+                Ok(true) => counts.produced += 1,
+                Ok(false) => counts.other_producer += 1,
+                Err(e) => {
+                    warn!("❌ Slot {} failed, producing again next slot: {:#}", slot, e);
+                    counts.failed += 1;
+                }
+            }
+        }
+
+        // Governance can change the block time at an epoch boundary; slots
+        // are counted afresh from the change
+        if next_block_time != block_time {
+            info!("⏱️  Block time changed from {:?} to {:?}", block_time, next_block_time);
+            block_time = next_block_time;
+            start = Instant::now();
+            ticker = tokio::time::interval_at(start + block_time, block_time);
+            ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+            last_slot = None;
+        }
+    }
+    info!("🛑 Consensus runner stopped");
+}
+
+/// Produce and apply the next block if this engine signs for its producer;
+/// `false` if it does not
+async fn produce_in_slot(engine: &mut ZkSacConsensusEngine, deadline: Deadline) -> Result<bool> {
+    let next = engine.height() + 1;
+    let producer = engine.select_block_producer(next)?;
+    if !engine.holds_validator_key(&producer) {
+        debug!("👀 Block {} is for {:?}, waiting", next, producer);
+        return Ok(false);
+    }
+    let block = engine.produce_block_by(producer, deadline).await?;
+    if !engine.validate_block(&block).await? {
+        bail!("Produced block {} failed validation", next);
+    }
+    engine.apply_block(block).await?;
+    // Slots too short to prove in are proven after the fact
+    if let Err(e) = engine.prove_deferred().await {
+        warn!("❌ Failed to prove deferred blocks: {:#}", e);
+    }
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::genesis::GenesisConfig;
+    use crate::crypto::keystore::KeyPair;
+    use crate::types::{ProtocolConfig, SignatureType};
+
+    async fn wait_for_height(runner: &ConsensusRunner, height: u64) {
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while runner.engine().lock().await.height() < height {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("runner did not reach the height in time");
+    }
+
+    #[tokio::test]
+    async fn test_runner_produces_every_slot_until_paused() {
+        let key = KeyPair::generate(SignatureType::Ed25519);
+        let genesis = GenesisConfig {
+            protocol: Some(ProtocolConfig { block_time: Duration::from_millis(50), ..ProtocolConfig::default() }),
+            ..GenesisConfig::dev(&key)
+        };
+        let mut engine = ZkSacConsensusEngine::from_genesis(&genesis).unwrap();
+        engine.add_validator_key(key).unwrap();
+        let mut runner = ConsensusRunner::new(Arc::new(Mutex::new(engine)));
+        assert_eq!(runner.state(), RunnerState::Stopped);
+
+        runner.start().unwrap();
+        assert!(runner.start().is_err());
+        wait_for_height(&runner, 2).await;
+
+        runner.pause();
+        assert_eq!(runner.state(), RunnerState::Paused);
+        // Let a block in progress finish
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let paused_at = runner.engine().lock().await.height();
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        assert_eq!(runner.engine().lock().await.height(), paused_at);
+
+        runner.resume();
+        wait_for_height(&runner, paused_at + 1).await;
+        let stats = runner.stop().await.unwrap();
+        assert_eq!(runner.state(), RunnerState::Stopped);
+        assert!(stats.produced > paused_at);
+        assert_eq!(stats.produced, runner.engine().lock().await.height());
+        assert_eq!(stats.other_producer, 0);
+    }
+}
@@ -0,0 +1,494 @@
+//! Slashing for provable misbehaviour
+//!
+//! Three offenses are slashable: producing two different blocks at one
+//! height, producing a block whose recursive proof does not verify, and
+//! leaving a deferred proof unsupplied for more than `proof_deadline` blocks.
+//! The first two are proven by [`SlashingEvidence`] made of data the offender
+//! signed, so every node can check it. Evidence reaches the chain as a
+//! transaction to [`SLASHING_ADDRESS`] carrying the encoded evidence, which
+//! commits it through the block's transaction root. The third needs no
+//! evidence: the [`Slasher`] tracks blocks applied with a deferred proof until
+//! a [`ProofSubmission`] to [`PROOF_ADDRESS`] supplies it, and punishes the
+//! producer of any still missing at the deadline. Either way, applying the
+//! block takes `slashing_rate` of the offender's stake and removes validators
+//! left below `min_stake_threshold`.
+
+use crate::crypto::keystore::{KeyPair, verify_signature};
+use crate::light_client::{ProofVerifier, header_signing_bytes};
+use crate::serialization::{DecodeLimits, PayloadKind, canonical_hash, decode_bounded};
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use thiserror::Error;
+
+/// Recipient of evidence transactions
+pub const SLASHING_ADDRESS: Address = Address(*b"zk-sac/slashing\0\0\0\0\0");
+
+/// Recipient of transactions supplying deferred block proofs
+pub const PROOF_ADDRESS: Address = Address(*b"zk-sac/proofs\0\0\0\0\0\0\0");
+
+/// Blocks a producer has to supply a deferred proof in, by default
+pub const DEFAULT_PROOF_DEADLINE: u64 = 32;
+
+const PROOF_SIGNING_DOMAIN: &[u8] = b"zk-sac/block-proof/v1";
+
+/// Bytes a producer signs to vouch for the recursive proof it attached to a block
+pub fn proof_signing_bytes(header_hash: &BlockHash, proof: &ZkProof) -> Vec<u8> {
+    let mut bytes = PROOF_SIGNING_DOMAIN.to_vec();
+    bytes.extend_from_slice(&header_hash.0);
+    bytes.extend_from_slice(&canonical_hash(proof).0);
+    bytes
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SlashingError {
+    #[error("{0:?} is not a validator")]
+    UnknownValidator(Address),
+    #[error("invalid signature from {0:?}")]
+    InvalidSignature(Address),
+    #[error("headers do not conflict: {0}")]
+    NotConflicting(&'static str),
+    #[error("the proof of block {0} verifies")]
+    ProofVerifies(u64),
+    #[error("{offender:?} was already slashed for {offense:?} at height {height}")]
+    AlreadySlashed { offender: Address, offense: Offense, height: u64 },
+    #[error("malformed evidence: {0}")]
+    Malformed(String),
+    #[error("block {0} awaits no proof")]
+    NotDeferred(u64),
+    #[error("the proof supplied for block {0} does not verify: {1}")]
+    UnverifiedProof(u64, String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Offense {
+    DoubleProduction,
+    InvalidProof,
+    MissingProof,
+}
+
+/// A block header with its producer's vote
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SignedHeader {
+    pub header: BlockHeader,
+    pub vote: ValidatorSignature,
+}
+
+impl SignedHeader {
+    /// The header of `block` with its producer's vote, if the block carries one
+    pub fn from_block(block: &Block) -> Option<Self> {
+        let vote = block.validator_signatures.iter()
+            .find(|vote| vote.validator_address == block.header.producer)?;
+        Some(Self { header: block.header.clone(), vote: vote.clone() })
+    }
+
+    fn verify(&self, producer: &Validator) -> Result<(), SlashingError> {
+        if self.vote.validator_address != self.header.producer {
+            return Err(SlashingError::Malformed("vote is not from the block producer".to_string()));
+        }
+        verify_signature(&self.vote.sig_type, &producer.public_key, &header_signing_bytes(self.header.block_number, &self.header.hash()), &self.vote.signature)
+            .map_err(|_| SlashingError::InvalidSignature(producer.address))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SlashingEvidence {
+    /// Two different blocks at one height from the same producer
+    DoubleProduction { first: Box<SignedHeader>, second: Box<SignedHeader> },
+    /// A block whose producer signed a proof that does not verify
+    InvalidProof { header: Box<SignedHeader>, proof: ZkProof, proof_signature: Vec<u8> },
+}
+
+impl SlashingEvidence {
+    /// Evidence that `block` and `other` were both produced for one height
+    pub fn double_production(block: &Block, other: &Block) -> Option<Self> {
+        Some(Self::DoubleProduction {
+            first: Box::new(SignedHeader::from_block(block)?),
+            second: Box::new(SignedHeader::from_block(other)?),
+        })
+    }
+
+    /// Evidence that the proof attached to `block` is invalid
+    pub fn invalid_proof(block: &Block) -> Option<Self> {
+        Some(Self::InvalidProof {
+            header: Box::new(SignedHeader::from_block(block)?),
+            proof: block.recursive_proof.clone(),
+            proof_signature: block.proof_signature.clone(),
+        })
+    }
+
+    pub fn offense(&self) -> Offense {
+        match self {
+            Self::DoubleProduction { .. } => Offense::DoubleProduction,
+            Self::InvalidProof { .. } => Offense::InvalidProof,
+        }
+    }
+
+    pub fn offender(&self) -> Address {
+        match self {
+            Self::DoubleProduction { first, .. } => first.header.producer,
+            Self::InvalidProof { header, .. } => header.header.producer,
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        match self {
+            Self::DoubleProduction { first, .. } => first.header.block_number,
+            Self::InvalidProof { header, .. } => header.header.block_number,
+        }
+    }
+
+    /// Check the evidence against `validators`, returning the offender
+    pub fn verify<'a>(&self, validators: &'a ValidatorSet, verifier: &dyn ProofVerifier) -> Result<&'a Validator, SlashingError> {
+        let offender = validators.validators.iter()
+            .find(|validator| validator.address == self.offender())
+            .ok_or(SlashingError::UnknownValidator(self.offender()))?;
+
+        match self {
+            Self::DoubleProduction { first, second } => {
+                if first.header.block_number != second.header.block_number {
+                    return Err(SlashingError::NotConflicting("different heights"));
+                }
+                if first.header.producer != second.header.producer {
+                    return Err(SlashingError::NotConflicting("different producers"));
+                }
+                if first.header.hash() == second.header.hash() {
+                    return Err(SlashingError::NotConflicting("same block"));
+                }
+                first.verify(offender)?;
+                second.verify(offender)?;
+            }
+            Self::InvalidProof { header, proof, proof_signature } => {
+                header.verify(offender)?;
+                verify_signature(&header.vote.sig_type, &offender.public_key, &proof_signing_bytes(&header.header.hash(), proof), proof_signature)
+                    .map_err(|_| SlashingError::InvalidSignature(offender.address))?;
+                // A deferred proof is missing, not wrong
+                if matches!(proof.proof_type, ProofType::Deferred) || verifier.verify(&header.header, proof).is_ok() {
+                    return Err(SlashingError::ProofVerifies(header.header.block_number));
+                }
+            }
+        }
+        Ok(offender)
+    }
+
+    /// A transaction from `reporter` that puts this evidence on chain
+    pub fn to_transaction(&self, reporter: &KeyPair, nonce: u64) -> anyhow::Result<Transaction> {
+        let mut transaction = Transaction::new(reporter.address(), SLASHING_ADDRESS, 0, nonce);
+        transaction.data = bincode::serialize(self)?;
+        transaction.gas_limit = GasSchedule::default().intrinsic_gas(&transaction);
+        transaction.signed(reporter)
+    }
+
+    /// The evidence `transaction` carries, or `None` if it is not an evidence transaction
+    pub fn from_transaction(transaction: &Transaction) -> Option<Result<Self, SlashingError>> {
+        if transaction.to != SLASHING_ADDRESS {
+            return None;
+        }
+        Some(decode_bounded(PayloadKind::Transaction, &transaction.data, &DecodeLimits::default())
+            .map_err(|e| SlashingError::Malformed(e.to_string())))
+    }
+}
+
+/// The proof of a block produced with a deferred proof, signed by its producer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofSubmission {
+    pub block_number: u64,
+    pub block_hash: BlockHash,
+    pub proof: ZkProof,
+    pub proof_signature: Vec<u8>,
+}
+
+impl ProofSubmission {
+    /// The proof `block` now carries
+    pub fn for_block(block: &Block) -> Self {
+        Self {
+            block_number: block.header.block_number,
+            block_hash: block.header.hash(),
+            proof: block.recursive_proof.clone(),
+            proof_signature: block.proof_signature.clone(),
+        }
+    }
+
+    /// A transaction from `sender` that puts this proof on chain
+    pub fn to_transaction(&self, sender: &KeyPair, nonce: u64) -> anyhow::Result<Transaction> {
+        let mut transaction = Transaction::new(sender.address(), PROOF_ADDRESS, 0, nonce);
+        transaction.data = bincode::serialize(self)?;
+        transaction.gas_limit = GasSchedule::default().intrinsic_gas(&transaction);
+        transaction.signed(sender)
+    }
+
+    /// The proof `transaction` supplies, or `None` if it is not a proof transaction
+    pub fn from_transaction(transaction: &Transaction) -> Option<Result<Self, SlashingError>> {
+        if transaction.to != PROOF_ADDRESS {
+            return None;
+        }
+        Some(decode_bounded(PayloadKind::Transaction, &transaction.data, &DecodeLimits::default())
+            .map_err(|e| SlashingError::Malformed(e.to_string())))
+    }
+}
+
+/// A block applied with a deferred proof, and the key its proof must be signed with
+#[derive(Debug, Clone)]
+struct DeferredProof {
+    block_hash: BlockHash,
+    producer: Address,
+    public_key: Vec<u8>,
+    sig_type: SignatureType,
+}
+
+/// Stake taken from a validator and whether it dropped out of the set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Slashed {
+    pub offender: Address,
+    pub offense: Offense,
+    pub height: u64,
+    pub amount: u64,
+    pub removed: bool,
+}
+
+/// Applies verified evidence to a validator set, at most once per offense,
+/// and tracks the deferred proofs still owed
+#[derive(Debug, Clone, Default)]
+pub struct Slasher {
+    punished: HashSet<(Address, Offense, u64)>,
+    deferred: BTreeMap<u64, DeferredProof>,
+}
+
+impl Slasher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether `evidence` would be accepted against `validators`
+    pub fn check(&self, validators: &ValidatorSet, evidence: &SlashingEvidence, verifier: &dyn ProofVerifier) -> Result<(), SlashingError> {
+        let key = (evidence.offender(), evidence.offense(), evidence.height());
+        if self.punished.contains(&key) {
+            return Err(SlashingError::AlreadySlashed { offender: key.0, offense: key.1, height: key.2 });
+        }
+        evidence.verify(validators, verifier).map(|_| ())
+    }
+
+    /// Take `slashing_rate` of the offender's stake, removing it from the set
+    /// if what remains is below `min_stake_threshold`
+    pub fn slash(
+        &mut self,
+        validators: &mut ValidatorSet,
+        evidence: &SlashingEvidence,
+        verifier: &dyn ProofVerifier,
+        config: &ProtocolConfig,
+    ) -> Result<Slashed, SlashingError> {
+        self.check(validators, evidence, verifier)?;
+        self.punish(validators, evidence.offender(), evidence.offense(), evidence.height(), config)
+    }
+
+    /// Owe the proof of `block`, produced by `producer` with a deferred proof
+    pub fn defer(&mut self, block: &Block, producer: &Validator) {
+        let Some(signed) = SignedHeader::from_block(block) else { return };
+        self.deferred.insert(block.header.block_number, DeferredProof {
+            block_hash: block.header.hash(),
+            producer: producer.address,
+            public_key: producer.public_key.clone(),
+            sig_type: signed.vote.sig_type,
+        });
+    }
+
+    /// Whether `submission` supplies a proof still owed, signed by the block's
+    /// producer and verifying against `header`, the block it is for
+    pub fn check_proof(&self, header: &BlockHeader, submission: &ProofSubmission, verifier: &dyn ProofVerifier) -> Result<(), SlashingError> {
+        let block_number = submission.block_number;
+        let deferred = self.deferred.get(&block_number)
+            .filter(|deferred| deferred.block_hash == submission.block_hash && header.hash() == submission.block_hash)
+            .ok_or(SlashingError::NotDeferred(block_number))?;
+        verify_signature(&deferred.sig_type, &deferred.public_key, &proof_signing_bytes(&submission.block_hash, &submission.proof), &submission.proof_signature)
+            .map_err(|_| SlashingError::InvalidSignature(deferred.producer))?;
+        verifier.verify(header, &submission.proof)
+            .map_err(|e| SlashingError::UnverifiedProof(block_number, e))
+    }
+
+    /// Settle the proof `submission` supplies for the block `header` describes
+    pub fn supply(&mut self, header: &BlockHeader, submission: &ProofSubmission, verifier: &dyn ProofVerifier) -> Result<(), SlashingError> {
+        self.check_proof(header, submission, verifier)?;
+        self.deferred.remove(&submission.block_number);
+        Ok(())
+    }
+
+    /// Whether the proof of block `block_number` is still owed
+    pub fn owes(&self, block_number: u64) -> bool {
+        self.deferred.contains_key(&block_number)
+    }
+
+    /// Lowest block whose deferred proof is still owed
+    pub fn first_deferred(&self) -> Option<u64> {
+        self.deferred.keys().next().copied()
+    }
+
+    /// Punish, once each, the producers still owing a proof for a block more
+    /// than `proof_deadline` blocks before `block_number`. The proofs stay
+    /// owed, so those blocks stay unfinalized until they arrive.
+    pub fn punish_overdue(&mut self, validators: &mut ValidatorSet, block_number: u64, config: &ProtocolConfig) -> Vec<Slashed> {
+        let overdue: Vec<(u64, Address)> = self.deferred.iter()
+            .take_while(|(height, _)| height.saturating_add(config.proof_deadline) < block_number)
+            .map(|(height, deferred)| (*height, deferred.producer))
+            .filter(|(height, producer)| !self.punished.contains(&(*producer, Offense::MissingProof, *height)))
+            .collect();
+        overdue.into_iter()
+            .filter_map(|(height, producer)| self.punish(validators, producer, Offense::MissingProof, height, config).ok())
+            .collect()
+    }
+
+    /// Take `slashing_rate` of `offender`'s stake for `offense` at `height`,
+    /// removing it from the set if what remains is below `min_stake_threshold`
+    fn punish(&mut self, validators: &mut ValidatorSet, offender: Address, offense: Offense, height: u64, config: &ProtocolConfig) -> Result<Slashed, SlashingError> {
+        self.punished.insert((offender, offense, height));
+
+        let index = validators.validators.iter()
+            .position(|validator| validator.address == offender)
+            .ok_or(SlashingError::UnknownValidator(offender))?;
+        let validator = &mut validators.validators[index];
+        let amount = slash_amount(validator.stake, config.slashing_rate);
+        validator.stake -= amount;
+        validators.total_stake -= amount;
+
+        let removed = validator.stake < config.min_stake_threshold;
+        if removed {
+            let validator = validators.validators.remove(index);
+            validators.total_stake -= validator.stake;
+        }
+        Ok(Slashed { offender, offense, height, amount, removed })
+    }
+}
+
+/// `rate` of `stake`, rounded down
+pub fn slash_amount(stake: u64, rate: f64) -> u64 {
+    ((stake as f64 * rate.clamp(0.0, 1.0)) as u64).min(stake)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::light_client::MockProofVerifier;
+
+    fn header(number: u64, producer: &KeyPair, extra_data: &[u8]) -> SignedHeader {
+        let header = BlockHeader {
+            previous_hash: BlockHash::zero(),
+            merkle_root: BlockHash::zero(),
+            prev_state_root: BlockHash::zero(),
+            state_root: BlockHash::zero(),
+            timestamp: 1_700_000_000,
+            block_number: number,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            producer: producer.address(),
+            extra_data: extra_data.to_vec(),
+            randomness: RandomnessProof::default(),
+        };
+        let vote = crate::light_client::sign_header(producer, 0, &header).unwrap();
+        SignedHeader { header, vote }
+    }
+
+    fn validators(keys: &[&KeyPair], stake: u64) -> ValidatorSet {
+        ValidatorSet::new(keys.iter()
+            .map(|key| Validator { address: key.address(), stake, public_key: key.public_key(), performance_score: 1.0, bls_public_key: Vec::new() })
+            .collect())
+    }
+
+    #[test]
+    fn test_double_production_is_slashed_once() {
+        let producer = KeyPair::generate(SignatureType::Ed25519);
+        let other = KeyPair::generate(SignatureType::Ed25519);
+        let mut set = validators(&[&producer, &other], 1_000);
+        let config = ProtocolConfig { min_stake_threshold: 950, slashing_rate: 0.05, ..ProtocolConfig::default() };
+        let mut slasher = Slasher::new();
+
+        let same = SlashingEvidence::DoubleProduction { first: Box::new(header(3, &producer, b"a")), second: Box::new(header(3, &producer, b"a")) };
+        assert_eq!(slasher.check(&set, &same, &MockProofVerifier), Err(SlashingError::NotConflicting("same block")));
+        let mut forged = header(3, &producer, b"b");
+        forged.header.extra_data = b"c".to_vec();
+        let forged = SlashingEvidence::DoubleProduction { first: Box::new(header(3, &producer, b"a")), second: Box::new(forged) };
+        assert_eq!(slasher.check(&set, &forged, &MockProofVerifier), Err(SlashingError::InvalidSignature(producer.address())));
+
+        let evidence = SlashingEvidence::DoubleProduction { first: Box::new(header(3, &producer, b"a")), second: Box::new(header(3, &producer, b"b")) };
+        let slashed = slasher.slash(&mut set, &evidence, &MockProofVerifier, &config).unwrap();
+        assert_eq!(slashed, Slashed { offender: producer.address(), offense: Offense::DoubleProduction, height: 3, amount: 50, removed: false });
+        assert_eq!(set.validators[0].stake, 950);
+        assert_eq!(set.total_stake, 1_950);
+        assert!(matches!(slasher.slash(&mut set, &evidence, &MockProofVerifier, &config), Err(SlashingError::AlreadySlashed { .. })));
+
+        // A second offense takes the stake below the threshold
+        let again = SlashingEvidence::DoubleProduction { first: Box::new(header(4, &producer, b"a")), second: Box::new(header(4, &producer, b"b")) };
+        assert!(slasher.slash(&mut set, &again, &MockProofVerifier, &config).unwrap().removed);
+        assert_eq!(set.validators.len(), 1);
+        assert_eq!(set.total_stake, 1_000);
+    }
+
+    #[test]
+    fn test_only_signed_invalid_proofs_are_slashable() {
+        let producer = KeyPair::generate(SignatureType::Ed25519);
+        let set = validators(&[&producer], 1_000);
+        let signed = header(5, &producer, b"");
+        let evidence = |proof: ZkProof, signer: &KeyPair| SlashingEvidence::InvalidProof {
+            proof_signature: signer.sign(&proof_signing_bytes(&signed.header.hash(), &proof)).unwrap(),
+            header: Box::new(signed.clone()),
+            proof,
+        };
+        let empty = ZkProof { proof_data: vec![], public_inputs: vec![], verification_key: vec![], proof_type: ProofType::Risc0 };
+        let valid = ZkProof { proof_data: vec![0; 32], ..empty.clone() };
+        let deferred = ZkProof { proof_type: ProofType::Deferred, ..empty.clone() };
+
+        let slasher = Slasher::new();
+        assert_eq!(slasher.check(&set, &evidence(empty.clone(), &producer), &MockProofVerifier), Ok(()));
+        assert_eq!(slasher.check(&set, &evidence(valid, &producer), &MockProofVerifier), Err(SlashingError::ProofVerifies(5)));
+        assert_eq!(slasher.check(&set, &evidence(deferred, &producer), &MockProofVerifier), Err(SlashingError::ProofVerifies(5)));
+        // Someone else attaching a bad proof cannot frame the producer
+        let relay = KeyPair::generate(SignatureType::Ed25519);
+        assert_eq!(slasher.check(&set, &evidence(empty.clone(), &relay), &MockProofVerifier),
+                   Err(SlashingError::InvalidSignature(producer.address())));
+
+        let transaction = evidence(empty, &producer).to_transaction(&relay, 0).unwrap();
+        let decoded = SlashingEvidence::from_transaction(&transaction).unwrap().unwrap();
+        assert_eq!(decoded.offender(), producer.address());
+        assert_eq!(decoded.offense(), Offense::InvalidProof);
+        assert!(SlashingEvidence::from_transaction(&Transaction::new(relay.address(), producer.address(), 1, 0)).is_none());
+    }
+
+    #[test]
+    fn test_deferred_proofs_are_owed_until_supplied() {
+        let producer = KeyPair::generate(SignatureType::Ed25519);
+        let mut set = validators(&[&producer], 1_000);
+        let config = ProtocolConfig { min_stake_threshold: 0, slashing_rate: 0.05, proof_deadline: 2, ..ProtocolConfig::default() };
+        let signed = header(5, &producer, b"");
+        let block = Block {
+            header: signed.header.clone(),
+            transactions: Vec::new(),
+            validator_signatures: vec![signed.vote.clone()],
+            recursive_proof: ZkProof { proof_data: vec![], public_inputs: vec![], verification_key: vec![], proof_type: ProofType::Deferred },
+            protocol_updates: Vec::new(),
+            proof_signature: Vec::new(),
+            aggregate_signature: None,
+        };
+        let mut slasher = Slasher::new();
+        slasher.defer(&block, &set.validators[0]);
+        assert_eq!(slasher.first_deferred(), Some(5));
+
+        // Overdue once more than `proof_deadline` blocks have passed, and punished once
+        assert!(slasher.punish_overdue(&mut set, 7, &config).is_empty());
+        let slashed = slasher.punish_overdue(&mut set, 8, &config);
+        assert_eq!(slashed, vec![Slashed { offender: producer.address(), offense: Offense::MissingProof, height: 5, amount: 50, removed: false }]);
+        assert!(slasher.punish_overdue(&mut set, 9, &config).is_empty());
+
+        let proof = ZkProof { proof_data: vec![0; 32], proof_type: ProofType::Risc0, ..block.recursive_proof.clone() };
+        let submission = |signer: &KeyPair| ProofSubmission {
+            block_number: 5,
+            block_hash: block.header.hash(),
+            proof_signature: signer.sign(&proof_signing_bytes(&block.header.hash(), &proof)).unwrap(),
+            proof: proof.clone(),
+        };
+        let relay = KeyPair::generate(SignatureType::Ed25519);
+        assert_eq!(slasher.supply(&block.header, &submission(&relay), &MockProofVerifier), Err(SlashingError::InvalidSignature(producer.address())));
+        let transaction = submission(&producer).to_transaction(&relay, 0).unwrap();
+        let decoded = ProofSubmission::from_transaction(&transaction).unwrap().unwrap();
+        assert_eq!(slasher.supply(&block.header, &decoded, &MockProofVerifier), Ok(()));
+        assert_eq!(slasher.first_deferred(), None);
+        assert_eq!(slasher.supply(&block.header, &decoded, &MockProofVerifier), Err(SlashingError::NotDeferred(5)));
+    }
+}
@@ -0,0 +1,258 @@
+//! Staking transactions and epoch-based validator set rotation
+//!
+//! Accounts join or leave the validator set with a transaction to
+//! [`STAKING_ADDRESS`] carrying a [`StakingAction`]. A stake transaction's
+//! `value` is the stake, which execution moves to the staking address. Neither
+//! action changes the active set right away: the validator is put on the
+//! activation or exit queue for an epoch at least `activation_delay` epochs
+//! ahead, with at most `churn_limit` changes of each kind per epoch, and the
+//! engine moves to [`ValidatorSet::for_epoch`] when an epoch ends. Withdrawing
+//! the stake of an exited validator is not supported yet.
+
+use crate::crypto::keystore::{KeyPair, address_of};
+use crate::serialization::{DecodeLimits, PayloadKind, decode_bounded};
+use crate::types::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Recipient of staking transactions, and holder of the staked balances
+pub const STAKING_ADDRESS: Address = Address(*b"zk-sac/staking\0\0\0\0\0\0");
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum StakingError {
+    #[error("stake {stake} is below the minimum of {minimum}")]
+    BelowMinimum { stake: u64, minimum: u64 },
+    #[error("{0:?} is already a validator or queued to become one")]
+    AlreadyStaked(Address),
+    #[error("{0:?} is not an active validator")]
+    NotValidator(Address),
+    #[error("{0:?} is already queued to exit")]
+    AlreadyExiting(Address),
+    #[error("{0:?} is the last validator not exiting")]
+    LastValidator(Address),
+    #[error("staked public key does not belong to {0:?}")]
+    KeyMismatch(Address),
+    #[error("malformed staking action: {0}")]
+    Malformed(String),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum StakingAction {
+    /// Become a validator signing with `public_key`, staking the transaction value
+    Stake { public_key: Vec<u8> },
+    /// Leave the validator set
+    Unstake,
+}
+
+impl StakingAction {
+    /// A transaction from `staker` performing this action, staking `value`
+    pub fn to_transaction(&self, staker: &KeyPair, value: u64, nonce: u64) -> anyhow::Result<Transaction> {
+        let mut transaction = Transaction::new(staker.address(), STAKING_ADDRESS, value, nonce);
+        transaction.data = bincode::serialize(self)?;
+        transaction.gas_limit = GasSchedule::default().intrinsic_gas(&transaction);
+        transaction.signed(staker)
+    }
+
+    /// The action `transaction` carries, or `None` if it is not a staking transaction
+    pub fn from_transaction(transaction: &Transaction) -> Option<Result<Self, StakingError>> {
+        if transaction.to != STAKING_ADDRESS {
+            return None;
+        }
+        Some(decode_bounded(PayloadKind::Transaction, &transaction.data, &DecodeLimits::default())
+            .map_err(|e| StakingError::Malformed(e.to_string())))
+    }
+}
+
+/// A queued change to the validator set
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Queued {
+    Activation { address: Address, stake: u64, epoch: u64 },
+    Exit { address: Address, epoch: u64 },
+}
+
+/// Validators that joined and left when a set moved to a new epoch
+#[derive(Debug, Clone, Default)]
+pub struct EpochTransition {
+    pub activated: Vec<Validator>,
+    pub exited: Vec<Validator>,
+}
+
+/// First epoch from `earliest` with room for another change, given the
+/// epochs already taken by `queued`
+fn next_free_epoch(queued: impl Iterator<Item = u64>, earliest: u64, churn_limit: usize) -> u64 {
+    let churn_limit = churn_limit.max(1);
+    let mut counts = std::collections::BTreeMap::new();
+    for epoch in queued.filter(|epoch| *epoch >= earliest) {
+        *counts.entry(epoch).or_insert(0usize) += 1;
+    }
+    let mut epoch = earliest;
+    while counts.get(&epoch).is_some_and(|count| *count >= churn_limit) {
+        epoch += 1;
+    }
+    epoch
+}
+
+impl ValidatorSet {
+    fn is_active(&self, address: &Address) -> bool {
+        self.validators.iter().any(|validator| validator.address == *address)
+    }
+
+    fn is_exiting(&self, address: &Address) -> bool {
+        self.exit_queue.iter().any(|queued| queued.address == *address)
+    }
+
+    /// Apply `action` from `transaction`, included in block `block_number`,
+    /// to the queues
+    pub fn apply_staking(
+        &mut self,
+        transaction: &Transaction,
+        action: &StakingAction,
+        block_number: u64,
+        config: &ProtocolConfig,
+    ) -> Result<Queued, StakingError> {
+        let schedule = &config.epoch_schedule;
+        let earliest = schedule.epoch_of(block_number) + schedule.activation_delay.max(1);
+        let address = transaction.from;
+
+        match action {
+            StakingAction::Stake { public_key } => {
+                if address_of(&transaction.sig_type, public_key) != address {
+                    return Err(StakingError::KeyMismatch(address));
+                }
+                if transaction.value < config.min_stake_threshold {
+                    return Err(StakingError::BelowMinimum { stake: transaction.value, minimum: config.min_stake_threshold });
+                }
+                if self.is_active(&address) || self.activation_queue.iter().any(|queued| queued.validator.address == address) {
+                    return Err(StakingError::AlreadyStaked(address));
+                }
+
+                let epoch = next_free_epoch(self.activation_queue.iter().map(|queued| queued.epoch), earliest, schedule.churn_limit);
+                let validator = Validator { address, stake: transaction.value, public_key: public_key.clone(), performance_score: 1.0, bls_public_key: Vec::new() };
+                let index = self.activation_queue.partition_point(|queued| queued.epoch <= epoch);
+                self.activation_queue.insert(index, QueuedActivation { validator, epoch });
+                Ok(Queued::Activation { address, stake: transaction.value, epoch })
+            }
+            StakingAction::Unstake => {
+                if !self.is_active(&address) {
+                    return Err(StakingError::NotValidator(address));
+                }
+                if self.is_exiting(&address) {
+                    return Err(StakingError::AlreadyExiting(address));
+                }
+                if self.validators.len() <= self.exit_queue.len() + 1 {
+                    return Err(StakingError::LastValidator(address));
+                }
+
+                let epoch = next_free_epoch(self.exit_queue.iter().map(|queued| queued.epoch), earliest, schedule.churn_limit);
+                let index = self.exit_queue.partition_point(|queued| queued.epoch <= epoch);
+                self.exit_queue.insert(index, QueuedExit { address, epoch });
+                Ok(Queued::Exit { address, epoch })
+            }
+        }
+    }
+
+    /// Apply the queued changes due by `epoch`
+    pub fn enter_epoch(&mut self, epoch: u64) -> EpochTransition {
+        let mut transition = EpochTransition::default();
+
+        let due = self.exit_queue.partition_point(|queued| queued.epoch <= epoch);
+        for queued in self.exit_queue.drain(..due) {
+            // Slashing may have removed the validator already
+            if let Some(index) = self.validators.iter().position(|validator| validator.address == queued.address) {
+                let validator = self.validators.remove(index);
+                self.total_stake -= validator.stake;
+                transition.exited.push(validator);
+            }
+        }
+
+        let due = self.activation_queue.partition_point(|queued| queued.epoch <= epoch);
+        for queued in self.activation_queue.drain(..due) {
+            self.total_stake += queued.validator.stake;
+            self.validators.push(queued.validator.clone());
+            transition.activated.push(queued.validator);
+        }
+        transition
+    }
+
+    /// The set in effect from `epoch` on, with the queued changes due by then applied
+    pub fn for_epoch(&self, epoch: u64) -> ValidatorSet {
+        let mut set = self.clone();
+        set.enter_epoch(epoch);
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> ProtocolConfig {
+        ProtocolConfig {
+            min_stake_threshold: 100,
+            epoch_schedule: EpochSchedule { epoch_length: 10, activation_delay: 1, churn_limit: 2 },
+            ..ProtocolConfig::default()
+        }
+    }
+
+    fn stake(key: &KeyPair, value: u64) -> (Transaction, StakingAction) {
+        let transaction = StakingAction::Stake { public_key: key.public_key() }.to_transaction(key, value, 0).unwrap();
+        let action = StakingAction::from_transaction(&transaction).unwrap().unwrap();
+        (transaction, action)
+    }
+
+    fn unstake(key: &KeyPair) -> (Transaction, StakingAction) {
+        (StakingAction::Unstake.to_transaction(key, 0, 1).unwrap(), StakingAction::Unstake)
+    }
+
+    #[test]
+    fn test_stakes_activate_at_epoch_boundaries_within_the_churn_limit() {
+        let genesis = KeyPair::generate(SignatureType::Ed25519);
+        let mut set = ValidatorSet::new(vec![Validator { address: genesis.address(), stake: 100, public_key: genesis.public_key(), performance_score: 1.0, bls_public_key: Vec::new() }]);
+        let keys: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+
+        // Block 5 is in epoch 0, so the first two join in epoch 1 and the third waits for epoch 2
+        let epochs: Vec<Queued> = keys.iter()
+            .map(|key| { let (tx, action) = stake(key, 200); set.apply_staking(&tx, &action, 5, &config()).unwrap() })
+            .collect();
+        assert_eq!(epochs[1], Queued::Activation { address: keys[1].address(), stake: 200, epoch: 1 });
+        assert_eq!(epochs[2], Queued::Activation { address: keys[2].address(), stake: 200, epoch: 2 });
+
+        let (tx, action) = stake(&keys[0], 200);
+        assert_eq!(set.apply_staking(&tx, &action, 5, &config()), Err(StakingError::AlreadyStaked(keys[0].address())));
+        let (tx, action) = stake(&KeyPair::generate(SignatureType::Ed25519), 99);
+        assert!(matches!(set.apply_staking(&tx, &action, 5, &config()), Err(StakingError::BelowMinimum { stake: 99, .. })));
+
+        assert_eq!(set.for_epoch(0).validators.len(), 1);
+        let epoch_1 = set.for_epoch(1);
+        assert_eq!(epoch_1.validators.len(), 3);
+        assert_eq!(epoch_1.total_stake, 500);
+        assert_eq!(set.for_epoch(2).validators.len(), 4);
+        assert_eq!(set.validators.len(), 1, "for_epoch leaves the set alone");
+
+        let transition = set.enter_epoch(1);
+        assert_eq!(transition.activated.len(), 2);
+        assert_eq!(set.activation_queue.len(), 1);
+    }
+
+    #[test]
+    fn test_unstaked_validators_exit_and_the_last_cannot() {
+        let keys: Vec<KeyPair> = (0..2).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+        let mut set = ValidatorSet::new(keys.iter()
+            .map(|key| Validator { address: key.address(), stake: 100, public_key: key.public_key(), performance_score: 1.0, bls_public_key: Vec::new() })
+            .collect());
+
+        let (tx, action) = unstake(&keys[0]);
+        assert_eq!(set.apply_staking(&tx, &action, 12, &config()), Ok(Queued::Exit { address: keys[0].address(), epoch: 2 }));
+        assert_eq!(set.apply_staking(&tx, &action, 12, &config()), Err(StakingError::AlreadyExiting(keys[0].address())));
+        let (tx, action) = unstake(&keys[1]);
+        assert_eq!(set.apply_staking(&tx, &action, 12, &config()), Err(StakingError::LastValidator(keys[1].address())));
+        let (tx, action) = unstake(&KeyPair::generate(SignatureType::Ed25519));
+        assert!(matches!(set.apply_staking(&tx, &action, 12, &config()), Err(StakingError::NotValidator(_))));
+
+        let transition = set.enter_epoch(2);
+        assert_eq!(transition.exited[0].address, keys[0].address());
+        assert_eq!(set.validators.len(), 1);
+        assert_eq!(set.total_stake, 100);
+        assert!(set.exit_queue.is_empty());
+    }
+}
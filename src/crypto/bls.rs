@@ -0,0 +1,168 @@
+//! BLS12-381 signatures for validator votes
+//!
+//! Public keys are 48-byte compressed G1 points and signatures 96-byte
+//! compressed G2 points, as in Ethereum's consensus layer. Votes for one
+//! block all sign the same message, so they add up to a single signature
+//! that verifies against the sum of the signers' public keys. That is only
+//! sound for keys whose owners know the secret, so BLS keys come from the
+//! genesis validator set rather than from unchecked registrations.
+
+use crate::types::{AggregateSignature, Validator, ValidatorSet};
+use blst::BLST_ERROR;
+use blst::min_pk::{AggregateSignature as BlstAggregate, PublicKey, SecretKey, Signature};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use thiserror::Error;
+
+/// Domain separation tag of the proof-of-possession ciphersuite
+pub const BLS_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+pub const PUBLIC_KEY_LEN: usize = 48;
+pub const SIGNATURE_LEN: usize = 96;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum BlsError {
+    #[error("invalid BLS secret key")]
+    InvalidSecretKey,
+    #[error("invalid BLS public key")]
+    InvalidPublicKey,
+    #[error("malformed BLS signature")]
+    MalformedSignature,
+    #[error("BLS signature does not verify")]
+    InvalidSignature,
+    #[error("nothing to aggregate")]
+    NoSignatures,
+    #[error("signer bit {0} is outside the validator set")]
+    UnknownSigner(usize),
+    #[error("signer {0} has no BLS key")]
+    NoBlsKey(usize),
+}
+
+/// A validator's BLS signing key
+#[derive(Clone)]
+pub struct BlsKeyPair {
+    secret: SecretKey,
+}
+
+impl BlsKeyPair {
+    pub fn generate() -> Self {
+        let mut ikm = [0u8; 32];
+        OsRng.fill_bytes(&mut ikm);
+        Self::from_seed(&ikm).expect("32 bytes of key material are enough")
+    }
+
+    /// Derive a key from at least 32 bytes of key material
+    pub fn from_seed(ikm: &[u8]) -> Result<Self, BlsError> {
+        let secret = SecretKey::key_gen(ikm, &[]).map_err(|_| BlsError::InvalidSecretKey)?;
+        Ok(Self { secret })
+    }
+
+    pub fn from_secret(secret: &[u8]) -> Result<Self, BlsError> {
+        let secret = SecretKey::from_bytes(secret).map_err(|_| BlsError::InvalidSecretKey)?;
+        Ok(Self { secret })
+    }
+
+    pub fn secret(&self) -> [u8; 32] {
+        self.secret.to_bytes()
+    }
+
+    pub fn public_key(&self) -> Vec<u8> {
+        self.secret.sk_to_pk().to_bytes().to_vec()
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Vec<u8> {
+        self.secret.sign(message, BLS_DST, &[]).to_bytes().to_vec()
+    }
+}
+
+impl std::fmt::Debug for BlsKeyPair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("BlsKeyPair").field("public_key", &hex::encode(self.public_key())).finish_non_exhaustive()
+    }
+}
+
+fn public_key(bytes: &[u8]) -> Result<PublicKey, BlsError> {
+    PublicKey::key_validate(bytes).map_err(|_| BlsError::InvalidPublicKey)
+}
+
+fn signature(bytes: &[u8]) -> Result<Signature, BlsError> {
+    Signature::sig_validate(bytes, true).map_err(|_| BlsError::MalformedSignature)
+}
+
+fn check(result: BLST_ERROR) -> Result<(), BlsError> {
+    match result {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        _ => Err(BlsError::InvalidSignature),
+    }
+}
+
+/// Check one signature of `message` by `public_key`
+pub fn verify(public_key_bytes: &[u8], message: &[u8], signature_bytes: &[u8]) -> Result<(), BlsError> {
+    let key = public_key(public_key_bytes)?;
+    check(signature(signature_bytes)?.verify(false, message, BLS_DST, &[], &key, false))
+}
+
+/// Combine signatures of the same message into one
+pub fn aggregate(signatures: &[&[u8]]) -> Result<Vec<u8>, BlsError> {
+    let signatures = signatures.iter().map(|bytes| signature(bytes)).collect::<Result<Vec<_>, _>>()?;
+    let refs: Vec<&Signature> = signatures.iter().collect();
+    let aggregate = BlstAggregate::aggregate(&refs, false).map_err(|_| BlsError::NoSignatures)?;
+    Ok(aggregate.to_signature().to_bytes().to_vec())
+}
+
+/// Check that every key in `public_keys` signed `message`, given the
+/// aggregate of their signatures
+pub fn verify_aggregate(public_keys: &[&[u8]], message: &[u8], signature_bytes: &[u8]) -> Result<(), BlsError> {
+    if public_keys.is_empty() {
+        return Err(BlsError::NoSignatures);
+    }
+    let keys = public_keys.iter().map(|bytes| public_key(bytes)).collect::<Result<Vec<_>, _>>()?;
+    let refs: Vec<&PublicKey> = keys.iter().collect();
+    check(signature(signature_bytes)?.fast_aggregate_verify(false, message, BLS_DST, &refs))
+}
+
+/// Check the aggregate vote of the validators its bitfield names for
+/// `message`, returning those validators
+pub fn verify_aggregate_vote<'a>(
+    validators: &'a ValidatorSet,
+    message: &[u8],
+    aggregate: &AggregateSignature,
+) -> Result<Vec<&'a Validator>, BlsError> {
+    let signers = aggregate.signer_indices()
+        .map(|index| {
+            let validator = validators.validators.get(index).ok_or(BlsError::UnknownSigner(index))?;
+            if validator.bls_public_key.is_empty() {
+                return Err(BlsError::NoBlsKey(index));
+            }
+            Ok(validator)
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let keys: Vec<&[u8]> = signers.iter().map(|validator| validator.bls_public_key.as_slice()).collect();
+    verify_aggregate(&keys, message, &aggregate.signature)?;
+    Ok(signers)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregate_verifies_for_exactly_its_signers() {
+        let keys: Vec<BlsKeyPair> = (0..3).map(|_| BlsKeyPair::generate()).collect();
+        let message = b"block";
+        let signatures: Vec<Vec<u8>> = keys.iter().map(|key| key.sign(message)).collect();
+        verify(&keys[0].public_key(), message, &signatures[0]).unwrap();
+        assert_eq!(verify(&keys[1].public_key(), message, &signatures[0]), Err(BlsError::InvalidSignature));
+
+        let aggregate = aggregate(&signatures.iter().map(Vec::as_slice).collect::<Vec<_>>()).unwrap();
+        assert_eq!(aggregate.len(), SIGNATURE_LEN);
+        let public_keys: Vec<Vec<u8>> = keys.iter().map(BlsKeyPair::public_key).collect();
+        let all: Vec<&[u8]> = public_keys.iter().map(Vec::as_slice).collect();
+        verify_aggregate(&all, message, &aggregate).unwrap();
+        assert_eq!(verify_aggregate(&all[..2], message, &aggregate), Err(BlsError::InvalidSignature));
+        assert_eq!(verify_aggregate(&all, b"other", &aggregate), Err(BlsError::InvalidSignature));
+
+        let restored = BlsKeyPair::from_secret(&keys[0].secret()).unwrap();
+        assert_eq!(restored.public_key(), public_keys[0]);
+        assert_eq!(verify(&[0; PUBLIC_KEY_LEN], message, &signatures[0]), Err(BlsError::InvalidPublicKey));
+    }
+}
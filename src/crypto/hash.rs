@@ -1,6 +1,7 @@
 use blake3::{self, Hasher as Blake3Hasher};
 use sha3::{Digest, Sha3_256, Sha3_512, Keccak256, Shake128, Shake256, digest::{ExtendableOutput, XofReader, Update}};
 use hex;
+use serde::{Deserialize, Serialize};
 
 /// Enhanced Blake3 hash using version 1.8.2 features
 pub fn blake3_hash(data: &[u8]) -> [u8; 32] {
@@ -115,36 +116,98 @@ pub fn shake256_hash(data: &[u8], output_len: usize) -> Vec<u8> {
 
 /// Enhanced Merkle tree using Blake3 1.8.2 incremental hashing
 pub fn merkle_root(leaves: &[Vec<u8>]) -> [u8; 32] {
+    merkle_root_with(leaves, blake3_hash)
+}
+
+/// Merkle root with `hash` for leaves and for each concatenated pair of
+/// children; an unpaired node is carried up a level unchanged
+fn merkle_root_with(leaves: &[Vec<u8>], hash: impl Fn(&[u8]) -> [u8; 32]) -> [u8; 32] {
     if leaves.is_empty() {
         return [0; 32];
     }
-    
-    if leaves.len() == 1 {
-        return blake3_hash(&leaves[0]);
-    }
-    
-    // Optimized merkle tree using incremental hashing
-    let mut level = leaves.iter().map(|leaf| blake3_hash(leaf)).collect::<Vec<_>>();
-    
+
+    let mut level = leaves.iter().map(|leaf| hash(leaf)).collect::<Vec<_>>();
     while level.len() > 1 {
-        let mut next_level = Vec::new();
-        
-        for chunk in level.chunks(2) {
-            if chunk.len() == 2 {
-                // Use incremental hasher for better performance
-                let mut hasher = IncrementalHasher::new();
-                hasher.update(&chunk[0]);
-                hasher.update(&chunk[1]);
-                next_level.push(hasher.finalize());
+        level = level.chunks(2)
+            .map(|chunk| match chunk {
+                [left, right] => hash(&[left.as_slice(), right.as_slice()].concat()),
+                [single] => *single,
+                _ => unreachable!("chunks(2)"),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+/// One level of a [`MerkleProof`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleStep {
+    pub sibling: [u8; 32],
+    /// The sibling is the left child
+    pub sibling_on_left: bool,
+}
+
+/// Proof that a leaf is part of a [`merkle_root`]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MerkleProof {
+    /// Position of the leaf among the leaves
+    pub index: u64,
+    /// Siblings from the leaf upwards; levels where the node is carried up unpaired have no step
+    pub path: Vec<MerkleStep>,
+}
+
+impl MerkleProof {
+    /// Root implied by this proof for `leaf`
+    pub fn compute_root(&self, leaf: &[u8]) -> [u8; 32] {
+        self.path.iter().fold(blake3_hash(leaf), |node, step| {
+            let mut hasher = IncrementalHasher::new();
+            if step.sibling_on_left {
+                hasher.update(&step.sibling);
+                hasher.update(&node);
             } else {
-                next_level.push(chunk[0]);
+                hasher.update(&node);
+                hasher.update(&step.sibling);
             }
+            hasher.finalize()
+        })
+    }
+
+    pub fn verify(&self, leaf: &[u8], root: &[u8; 32]) -> bool {
+        hash_compare::constant_time_compare(&self.compute_root(leaf), root)
+    }
+}
+
+/// Inclusion proof for `leaves[index]` against [`merkle_root`]`(leaves)`
+pub fn merkle_proof(leaves: &[Vec<u8>], index: usize) -> Option<MerkleProof> {
+    if index >= leaves.len() {
+        return None;
+    }
+
+    let mut level = leaves.iter().map(|leaf| blake3_hash(leaf)).collect::<Vec<_>>();
+    let mut position = index;
+    let mut path = Vec::new();
+
+    while level.len() > 1 {
+        let sibling = position ^ 1;
+        if sibling < level.len() {
+            path.push(MerkleStep { sibling: level[sibling], sibling_on_left: sibling < position });
         }
-        
-        level = next_level;
+        level = level.chunks(2)
+            .map(|chunk| match chunk {
+                [left, right] => {
+                    let mut hasher = IncrementalHasher::new();
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize()
+                }
+                [single] => *single,
+                _ => unreachable!("chunks(2)"),
+            })
+            .collect();
+        position /= 2;
     }
-    
-    level[0]
+
+    Some(MerkleProof { index: index as u64, path })
 }
 
 /// Enhanced state root computation using Keccak256 for EVM compatibility
@@ -221,6 +284,177 @@ pub fn compute_consensus_hash(data: &[u8]) -> ([u8; 32], [u8; 32], [u8; 32]) {
     )
 }
 
+/// Algorithms a [`MultiHasher`] can default to
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HashAlgorithm {
+    #[default]
+    Blake3,
+    Keccak256,
+    Sha3_256,
+}
+
+impl HashAlgorithm {
+    pub fn hash(&self, data: &[u8]) -> [u8; 32] {
+        match self {
+            HashAlgorithm::Blake3 => blake3_hash(data),
+            HashAlgorithm::Keccak256 => keccak256_hash(data),
+            HashAlgorithm::Sha3_256 => sha3_256_hash(data),
+        }
+    }
+}
+
+/// Every hash function behind one handle, with a default algorithm and an
+/// optional domain separation tag
+///
+/// With a tag, every input is prefixed by the tag's length as a little-endian
+/// `u64` and the tag itself, so hashers for different purposes never agree
+/// on a digest. Without one, each method matches the free function of the
+/// same name.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MultiHasher {
+    algorithm: HashAlgorithm,
+    domain: Option<Vec<u8>>,
+}
+
+impl MultiHasher {
+    /// Blake3 by default, without a domain tag
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.algorithm = algorithm;
+        self
+    }
+
+    /// Prefix every input with `tag`
+    pub fn with_domain(mut self, tag: impl Into<Vec<u8>>) -> Self {
+        self.domain = Some(tag.into());
+        self
+    }
+
+    pub fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub fn domain(&self) -> Option<&[u8]> {
+        self.domain.as_deref()
+    }
+
+    /// Hash with the default algorithm
+    pub fn hash(&self, data: &[u8]) -> [u8; 32] {
+        self.hash_with(self.algorithm, data)
+    }
+
+    pub fn hash_with(&self, algorithm: HashAlgorithm, data: &[u8]) -> [u8; 32] {
+        algorithm.hash(&self.tagged(data))
+    }
+
+    pub fn blake3_hash(&self, data: &[u8]) -> [u8; 32] {
+        self.hash_with(HashAlgorithm::Blake3, data)
+    }
+
+    pub fn keccak256_hash(&self, data: &[u8]) -> [u8; 32] {
+        self.hash_with(HashAlgorithm::Keccak256, data)
+    }
+
+    pub fn sha3_256_hash(&self, data: &[u8]) -> [u8; 32] {
+        self.hash_with(HashAlgorithm::Sha3_256, data)
+    }
+
+    pub fn sha3_512_hash(&self, data: &[u8]) -> [u8; 64] {
+        sha3_512_hash(&self.tagged(data))
+    }
+
+    pub fn shake128_hash(&self, data: &[u8], output_len: usize) -> Vec<u8> {
+        shake128_hash(&self.tagged(data), output_len)
+    }
+
+    pub fn shake256_hash(&self, data: &[u8], output_len: usize) -> Vec<u8> {
+        shake256_hash(&self.tagged(data), output_len)
+    }
+
+    /// Streaming Blake3 hasher that has already absorbed the domain tag
+    pub fn new_incremental_blake3(&self) -> IncrementalHasher {
+        let mut hasher = IncrementalHasher::new();
+        if let Some(prefix) = self.domain_prefix() {
+            hasher.update(&prefix);
+        }
+        hasher
+    }
+
+    /// Merkle root with the default algorithm
+    pub fn compute_merkle_root(&self, leaves: &[Vec<u8>]) -> [u8; 32] {
+        self.compute_merkle_root_with(self.algorithm, leaves)
+    }
+
+    /// Merkle root in the shape of [`merkle_root`], hashing with `algorithm`
+    pub fn compute_merkle_root_with(&self, algorithm: HashAlgorithm, leaves: &[Vec<u8>]) -> [u8; 32] {
+        merkle_root_with(leaves, |data| self.hash_with(algorithm, data))
+    }
+
+    pub fn compute_merkle_root_blake3(&self, leaves: &[Vec<u8>]) -> [u8; 32] {
+        self.compute_merkle_root_with(HashAlgorithm::Blake3, leaves)
+    }
+
+    pub fn compute_merkle_root_keccak256(&self, leaves: &[Vec<u8>]) -> [u8; 32] {
+        self.compute_merkle_root_with(HashAlgorithm::Keccak256, leaves)
+    }
+
+    pub fn compute_merkle_root_sha3_256(&self, leaves: &[Vec<u8>]) -> [u8; 32] {
+        self.compute_merkle_root_with(HashAlgorithm::Sha3_256, leaves)
+    }
+
+    /// Constant-time equality of two digests
+    pub fn compare_hashes(&self, a: &[u8; 32], b: &[u8; 32]) -> bool {
+        hash_compare::constant_time_compare(a, b)
+    }
+
+    /// Ethereum address of a secp256k1 public key, compressed or not; other
+    /// 65-byte `0x04`-prefixed input is hashed as raw coordinates. Addresses
+    /// are never domain separated.
+    pub fn generate_evm_address(&self, public_key: &[u8]) -> crate::types::Address {
+        crate::types::Address::from_secp256k1_pubkey(public_key).unwrap_or_else(|_| {
+            let coordinates = public_key.strip_prefix(&[0x04]).unwrap_or(public_key);
+            crate::types::Address::from_key_hash(&keccak256_hash(coordinates))
+        })
+    }
+
+    /// [`compute_transaction_hash_evm`] of `transaction`
+    pub fn hash_evm_transaction(&self, transaction: &crate::types::Transaction) -> [u8; 32] {
+        compute_transaction_hash_evm(
+            transaction.nonce,
+            transaction.gas_price,
+            transaction.gas_limit,
+            &transaction.to.0,
+            transaction.value,
+            &transaction.data,
+        )
+    }
+
+    /// [`super::randomness::vdf_nonce`] with `difficulty` squarings
+    pub fn generate_vdf_nonce(&self, difficulty: u64) -> u64 {
+        super::randomness::vdf_nonce(difficulty)
+    }
+
+    /// [`super::randomness::random_beacon_nonce`]
+    pub fn generate_random_beacon_nonce(&self) -> u64 {
+        super::randomness::random_beacon_nonce()
+    }
+
+    fn domain_prefix(&self) -> Option<Vec<u8>> {
+        self.domain.as_ref().map(|tag| [&(tag.len() as u64).to_le_bytes()[..], tag].concat())
+    }
+
+    fn tagged<'a>(&self, data: &'a [u8]) -> std::borrow::Cow<'a, [u8]> {
+        match self.domain_prefix() {
+            Some(prefix) => std::borrow::Cow::Owned([prefix.as_slice(), data].concat()),
+            None => std::borrow::Cow::Borrowed(data),
+        }
+    }
+}
+
 /// Enhanced hex utilities using hex 0.4.3 with serde support
 pub mod hex_utils {
     use super::*;
@@ -308,6 +542,27 @@ pub mod hex_utils {
 
         Ok(hash)
     }
+
+    /// Parse a 20-byte address, with or without 0x prefix; mixed case must
+    /// be a valid EIP-55 checksum
+    pub fn parse_address(hex_str: &str) -> Result<crate::types::Address> {
+        Ok(crate::types::Address::from_hex(hex_str.trim())?)
+    }
+
+    /// `#[serde(with = "crate::crypto::hash::hex_utils::address_serde")]` for 0x-prefixed addresses
+    pub mod address_serde {
+        use crate::types::Address;
+        use serde::{Deserialize, Deserializer, Serializer};
+
+        pub fn serialize<S: Serializer>(address: &Address, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_str(&super::hash_to_hex_prefixed(&address.0))
+        }
+
+        pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Address, D::Error> {
+            let text = String::deserialize(deserializer)?;
+            super::parse_address(&text).map_err(serde::de::Error::custom)
+        }
+    }
 }
 
 /// Hash comparison utilities
@@ -378,4 +633,51 @@ pub mod hash_compare {
 
         true
     }
-} 
\ No newline at end of file
+} 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merkle_proofs_match_root() {
+        for count in 1..=9u8 {
+            let leaves: Vec<Vec<u8>> = (0..count).map(|i| vec![i; 3]).collect();
+            let root = merkle_root(&leaves);
+            for (index, leaf) in leaves.iter().enumerate() {
+                let proof = merkle_proof(&leaves, index).unwrap();
+                assert!(proof.verify(leaf, &root), "leaf {} of {}", index, count);
+                assert!(!proof.verify(b"forged", &root));
+            }
+            assert!(merkle_proof(&leaves, count as usize).is_none());
+        }
+    }
+
+    #[test]
+    fn test_multi_hasher_defaults_and_domain_tags() {
+        let data = b"block";
+        let leaves: Vec<Vec<u8>> = (0..5u8).map(|i| vec![i; 4]).collect();
+
+        let plain = MultiHasher::new();
+        assert_eq!(plain.hash(data), blake3_hash(data));
+        assert_eq!(plain.keccak256_hash(data), keccak256_hash(data));
+        assert_eq!(plain.compute_merkle_root_blake3(&leaves), merkle_root(&leaves));
+        let mut incremental = plain.new_incremental_blake3();
+        incremental.update(b"blo");
+        incremental.update(b"ck");
+        assert_eq!(incremental.finalize(), blake3_hash(data));
+
+        let keccak = MultiHasher::new().with_algorithm(HashAlgorithm::Keccak256);
+        assert_eq!(keccak.hash(data), keccak256_hash(data));
+        assert_eq!(keccak.compute_merkle_root(&leaves), plain.compute_merkle_root_keccak256(&leaves));
+        assert_ne!(keccak.compute_merkle_root(&leaves), merkle_root(&leaves));
+
+        let votes = MultiHasher::new().with_domain("votes");
+        let headers = MultiHasher::new().with_domain("headers");
+        assert_ne!(votes.hash(data), plain.hash(data));
+        assert_ne!(votes.hash(data), headers.hash(data));
+        assert_ne!(votes.compute_merkle_root(&leaves), merkle_root(&leaves));
+        let mut incremental = votes.new_incremental_blake3();
+        incremental.update(data);
+        assert_eq!(incremental.finalize(), votes.blake3_hash(data));
+    }
+}
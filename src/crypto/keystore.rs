@@ -0,0 +1,503 @@
+//! Password-encrypted validator keys on disk
+//!
+//! Each key is a JSON file named after its address, laid out like EIP-2335
+//! keystores. The 32-byte secret is encrypted with AES-256-GCM under a key
+//! derived from the password with scrypt or Argon2id; the key type, address
+//! and public key are stored in the clear so keys can be listed without the
+//! password. A validator's BLS vote key is kept the same way under `bls/`,
+//! so a restarted node unlocks every key it signed with before.
+
+use crate::crypto::bls::BlsKeyPair;
+use crate::crypto::hash::{hex_utils, keccak256_hash};
+use crate::crypto::signatures::{PostQuantumSigner, SignatureEngine, secp256k1};
+use crate::types::{Address, SignatureType};
+use aes_gcm::aead::Aead;
+use aes_gcm::{Aes256Gcm, KeyInit, Nonce};
+use anyhow::{Context, Result, anyhow, bail};
+use rand::RngCore;
+use rand::rngs::OsRng;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tracing::info;
+
+const KEYSTORE_VERSION: u32 = 1;
+/// Default scrypt cost, 2^15 iterations
+pub const DEFAULT_SCRYPT_LOG_N: u8 = 15;
+const SCRYPT_R: u32 = 8;
+const SCRYPT_P: u32 = 1;
+/// Default Argon2id cost, 64 MiB over three passes
+pub const DEFAULT_ARGON2_MEMORY_KIB: u32 = 64 * 1024;
+pub const DEFAULT_ARGON2_ITERATIONS: u32 = 3;
+const ARGON2_PARALLELISM: u32 = 1;
+
+/// Password hashing for newly stored keys
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kdf {
+    /// scrypt with 2^`log_n` iterations
+    Scrypt { log_n: u8 },
+    Argon2id { memory_kib: u32, iterations: u32 },
+}
+
+impl Kdf {
+    pub fn argon2id() -> Self {
+        Kdf::Argon2id { memory_kib: DEFAULT_ARGON2_MEMORY_KIB, iterations: DEFAULT_ARGON2_ITERATIONS }
+    }
+}
+
+impl Default for Kdf {
+    fn default() -> Self {
+        Kdf::Scrypt { log_n: DEFAULT_SCRYPT_LOG_N }
+    }
+}
+
+/// A signing key of any supported type
+#[derive(Clone)]
+pub struct KeyPair {
+    sig_type: SignatureType,
+    secret: [u8; 32],
+}
+
+impl KeyPair {
+    pub fn generate(sig_type: SignatureType) -> Self {
+        let secret = match sig_type {
+            // Not every 32-byte string is a valid secp256k1 scalar
+            SignatureType::Secp256k1 => k256::ecdsa::SigningKey::random(&mut OsRng).to_bytes().into(),
+            SignatureType::Ed25519 | SignatureType::PostQuantum => {
+                let mut secret = [0u8; 32];
+                OsRng.fill_bytes(&mut secret);
+                secret
+            }
+        };
+        Self { sig_type, secret }
+    }
+
+    pub fn from_secret(sig_type: SignatureType, secret: &[u8]) -> Result<Self> {
+        let secret: [u8; 32] = secret.try_into()
+            .map_err(|_| anyhow!("Secret key must be 32 bytes, got {}", secret.len()))?;
+        if sig_type == SignatureType::Secp256k1 {
+            k256::ecdsa::SigningKey::from_slice(&secret)
+                .map_err(|_| anyhow!("Invalid secp256k1 secret key"))?;
+        }
+        Ok(Self { sig_type, secret })
+    }
+
+    pub fn sig_type(&self) -> &SignatureType {
+        &self.sig_type
+    }
+
+    pub fn secret(&self) -> &[u8; 32] {
+        &self.secret
+    }
+
+    /// Ed25519: 32 bytes; secp256k1: 33-byte compressed point; post-quantum: 32-byte key id
+    pub fn public_key(&self) -> Vec<u8> {
+        match self.sig_type {
+            SignatureType::Ed25519 => ed25519_dalek::SigningKey::from_bytes(&self.secret)
+                .verifying_key().to_bytes().to_vec(),
+            SignatureType::Secp256k1 => self.secp256k1_key().verifying_key()
+                .to_encoded_point(true).as_bytes().to_vec(),
+            // The LMS signer is still a mock, so the public key is a commitment to the seed
+            SignatureType::PostQuantum => blake3::hash(&self.secret).as_bytes().to_vec(),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        address_of(&self.sig_type, &self.public_key())
+    }
+
+    pub fn sign(&self, message: &[u8]) -> Result<Vec<u8>> {
+        match self.sig_type {
+            SignatureType::Ed25519 => {
+                use ed25519_dalek::Signer;
+                Ok(ed25519_dalek::SigningKey::from_bytes(&self.secret).sign(message).to_bytes().to_vec())
+            }
+            SignatureType::Secp256k1 => secp256k1::sign(&self.secp256k1_key(), message),
+            SignatureType::PostQuantum => PostQuantumSigner::new()?.sign_lms(&self.address(), message),
+        }
+    }
+
+    fn secp256k1_key(&self) -> k256::ecdsa::SigningKey {
+        k256::ecdsa::SigningKey::from_slice(&self.secret).expect("secret validated on construction")
+    }
+}
+
+/// Last 20 bytes of the Keccak-256 hash of the public key (uncompressed for secp256k1)
+pub fn address_of(sig_type: &SignatureType, public_key: &[u8]) -> Address {
+    match sig_type {
+        SignatureType::Secp256k1 => Address::from_secp256k1_pubkey(public_key)
+            .unwrap_or_else(|_| Address::from_key_hash(&keccak256_hash(public_key))),
+        SignatureType::Ed25519 | SignatureType::PostQuantum => Address::from_key_hash(&keccak256_hash(public_key)),
+    }
+}
+
+/// Check `signature` over `message` against a public key of type `sig_type`
+pub fn verify_signature(sig_type: &SignatureType, public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<()> {
+    match sig_type {
+        SignatureType::Ed25519 => SignatureEngine::new().verify_with_public_key(signature, public_key, message),
+        SignatureType::Secp256k1 => secp256k1::verify(public_key, message, signature),
+        SignatureType::PostQuantum => PostQuantumSigner::new()?
+            .verify_lms(signature, &address_of(sig_type, public_key), message),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ScryptParams {
+    log_n: u8,
+    r: u32,
+    p: u32,
+    #[serde(with = "hex::serde")]
+    salt: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Argon2Params {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    #[serde(with = "hex::serde")]
+    salt: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kdf", content = "kdf_params", rename_all = "lowercase")]
+enum KdfParams {
+    Scrypt(ScryptParams),
+    Argon2id(Argon2Params),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct EncryptedSecret {
+    #[serde(flatten)]
+    kdf: KdfParams,
+    cipher: String,
+    #[serde(with = "hex::serde")]
+    nonce: Vec<u8>,
+    #[serde(with = "hex::serde")]
+    ciphertext: Vec<u8>,
+}
+
+/// Public part of a keystore file
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeystoreEntry {
+    pub version: u32,
+    #[serde(with = "hex_utils::address_serde")]
+    pub address: Address,
+    pub sig_type: SignatureType,
+    #[serde(with = "hex::serde")]
+    pub public_key: Vec<u8>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct KeystoreFile {
+    #[serde(flatten)]
+    entry: KeystoreEntry,
+    crypto: EncryptedSecret,
+}
+
+/// Public part of a stored BLS vote key
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlsKeystoreEntry {
+    pub version: u32,
+    /// Validator that votes with the key
+    #[serde(with = "hex_utils::address_serde")]
+    pub validator: Address,
+    #[serde(with = "hex::serde")]
+    pub public_key: Vec<u8>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct BlsKeystoreFile {
+    #[serde(flatten)]
+    entry: BlsKeystoreEntry,
+    crypto: EncryptedSecret,
+}
+
+fn derive_key(password: &str, params: &KdfParams) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    match params {
+        KdfParams::Scrypt(params) => {
+            let scrypt_params = scrypt::Params::new(params.log_n, params.r, params.p, 32)
+                .map_err(|e| anyhow!("Invalid scrypt parameters: {}", e))?;
+            scrypt::scrypt(password.as_bytes(), &params.salt, &scrypt_params, &mut key)
+                .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+        }
+        KdfParams::Argon2id(params) => {
+            let argon2_params = argon2::Params::new(params.memory_kib, params.iterations, params.parallelism, Some(32))
+                .map_err(|e| anyhow!("Invalid Argon2 parameters: {}", e))?;
+            argon2::Argon2::new(argon2::Algorithm::Argon2id, argon2::Version::V0x13, argon2_params)
+                .hash_password_into(password.as_bytes(), &params.salt, &mut key)
+                .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+        }
+    }
+    Ok(key)
+}
+
+fn encrypt(secret: &[u8; 32], password: &str, kdf: Kdf) -> Result<EncryptedSecret> {
+    let mut salt = vec![0u8; 32];
+    OsRng.fill_bytes(&mut salt);
+    let kdf = match kdf {
+        Kdf::Scrypt { log_n } => KdfParams::Scrypt(ScryptParams { log_n, r: SCRYPT_R, p: SCRYPT_P, salt }),
+        Kdf::Argon2id { memory_kib, iterations } => KdfParams::Argon2id(Argon2Params {
+            memory_kib,
+            iterations,
+            parallelism: ARGON2_PARALLELISM,
+            salt,
+        }),
+    };
+    let key = derive_key(password, &kdf)?;
+
+    let mut nonce = vec![0u8; 12];
+    OsRng.fill_bytes(&mut nonce);
+    let ciphertext = Aes256Gcm::new_from_slice(&key)
+        .map_err(|_| anyhow!("Invalid encryption key length"))?
+        .encrypt(Nonce::from_slice(&nonce), secret.as_slice())
+        .map_err(|_| anyhow!("Encryption failed"))?;
+
+    Ok(EncryptedSecret {
+        kdf,
+        cipher: "aes-256-gcm".to_string(),
+        nonce,
+        ciphertext,
+    })
+}
+
+fn decrypt(crypto: &EncryptedSecret, password: &str) -> Result<Vec<u8>> {
+    if crypto.cipher != "aes-256-gcm" {
+        bail!("Unsupported keystore cipher {}", crypto.cipher);
+    }
+    if crypto.nonce.len() != 12 {
+        bail!("Invalid keystore nonce length {}", crypto.nonce.len());
+    }
+    let key = derive_key(password, &crypto.kdf)?;
+    Aes256Gcm::new_from_slice(&key)
+        .map_err(|_| anyhow!("Invalid encryption key length"))?
+        .decrypt(Nonce::from_slice(&crypto.nonce), crypto.ciphertext.as_slice())
+        .map_err(|_| anyhow!("Wrong password or corrupted keystore file"))
+}
+
+/// Directory of encrypted key files
+pub struct Keystore {
+    dir: PathBuf,
+    kdf: Kdf,
+}
+
+impl Keystore {
+    /// Open `dir`, creating it if needed
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Self> {
+        let dir = dir.into();
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create keystore directory {}", dir.display()))?;
+        Ok(Self { dir, kdf: Kdf::default() })
+    }
+
+    /// scrypt cost for newly stored keys, as a power of two
+    pub fn with_kdf_cost(mut self, log_n: u8) -> Self {
+        self.kdf = Kdf::Scrypt { log_n };
+        self
+    }
+
+    /// Password hashing for newly stored keys; existing files keep theirs
+    pub fn with_kdf(mut self, kdf: Kdf) -> Self {
+        self.kdf = kdf;
+        self
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn path_for(&self, address: &Address) -> PathBuf {
+        self.dir.join(format!("{}.json", hex_utils::hash_to_hex_prefixed(&address.0)))
+    }
+
+    fn bls_path_for(&self, validator: &Address) -> PathBuf {
+        self.dir.join("bls").join(format!("{}.json", hex_utils::hash_to_hex_prefixed(&validator.0)))
+    }
+
+    /// Encrypt `key` with `password` and write it; fails if the address is already stored
+    pub fn insert(&self, key: &KeyPair, password: &str) -> Result<KeystoreEntry> {
+        let entry = KeystoreEntry {
+            version: KEYSTORE_VERSION,
+            address: key.address(),
+            sig_type: key.sig_type().clone(),
+            public_key: key.public_key(),
+            created_at: chrono::Utc::now(),
+        };
+        let path = self.path_for(&entry.address);
+        if path.exists() {
+            bail!("Key {} is already in the keystore", hex_utils::hash_to_hex_prefixed(&entry.address.0));
+        }
+
+        let file = KeystoreFile { entry: entry.clone(), crypto: encrypt(key.secret(), password, self.kdf)? };
+        write_private(&path, serde_json::to_string_pretty(&file)?.as_bytes())?;
+        info!("🔑 Stored {:?} key {} in keystore", entry.sig_type, path.display());
+        Ok(entry)
+    }
+
+    /// Every stored key, without decrypting anything
+    pub fn list(&self) -> Result<Vec<KeystoreEntry>> {
+        let mut entries = Vec::new();
+        for dir_entry in std::fs::read_dir(&self.dir)? {
+            let path = dir_entry?.path();
+            if path.extension().is_some_and(|ext| ext == "json") {
+                entries.push(read_file::<KeystoreFile>(&path)?.entry);
+            }
+        }
+        entries.sort_by_key(|entry| entry.created_at);
+        Ok(entries)
+    }
+
+    /// Decrypt the key for `address`
+    pub fn unlock(&self, address: &Address, password: &str) -> Result<KeyPair> {
+        let file: KeystoreFile = read_file(&self.path_for(address))?;
+        let secret = decrypt(&file.crypto, password)?;
+        let key = KeyPair::from_secret(file.entry.sig_type, &secret)?;
+        if key.address() != file.entry.address {
+            bail!("Keystore file for {} holds a different key", hex_utils::hash_to_hex_prefixed(&address.0));
+        }
+        Ok(key)
+    }
+
+    /// Encrypt the BLS vote key of `validator` with `password` and write it;
+    /// fails if the validator already has one
+    pub fn insert_bls(&self, validator: &Address, key: &BlsKeyPair, password: &str) -> Result<BlsKeystoreEntry> {
+        let entry = BlsKeystoreEntry {
+            version: KEYSTORE_VERSION,
+            validator: *validator,
+            public_key: key.public_key(),
+            created_at: chrono::Utc::now(),
+        };
+        let path = self.bls_path_for(validator);
+        if path.exists() {
+            bail!("Validator {} already has a BLS key in the keystore", hex_utils::hash_to_hex_prefixed(&validator.0));
+        }
+        std::fs::create_dir_all(self.dir.join("bls"))?;
+
+        let file = BlsKeystoreFile { entry: entry.clone(), crypto: encrypt(&key.secret(), password, self.kdf)? };
+        write_private(&path, serde_json::to_string_pretty(&file)?.as_bytes())?;
+        info!("🔑 Stored BLS vote key {} in keystore", path.display());
+        Ok(entry)
+    }
+
+    pub fn has_bls_key(&self, validator: &Address) -> bool {
+        self.bls_path_for(validator).exists()
+    }
+
+    /// Decrypt the BLS vote key of `validator`
+    pub fn unlock_bls(&self, validator: &Address, password: &str) -> Result<BlsKeyPair> {
+        let file: BlsKeystoreFile = read_file(&self.bls_path_for(validator))?;
+        let key = BlsKeyPair::from_secret(&decrypt(&file.crypto, password)?)?;
+        if key.public_key() != file.entry.public_key || file.entry.validator != *validator {
+            bail!("BLS keystore file for {} holds a different key", hex_utils::hash_to_hex_prefixed(&validator.0));
+        }
+        Ok(key)
+    }
+}
+
+trait Versioned {
+    fn version(&self) -> u32;
+}
+
+impl Versioned for KeystoreFile {
+    fn version(&self) -> u32 {
+        self.entry.version
+    }
+}
+
+impl Versioned for BlsKeystoreFile {
+    fn version(&self) -> u32 {
+        self.entry.version
+    }
+}
+
+fn read_file<T: DeserializeOwned + Versioned>(path: &Path) -> Result<T> {
+    let text = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read keystore file {}", path.display()))?;
+    let file: T = serde_json::from_str(&text)
+        .with_context(|| format!("Invalid keystore file {}", path.display()))?;
+    if file.version() != KEYSTORE_VERSION {
+        bail!("Unsupported keystore version {} in {}", file.version(), path.display());
+    }
+    Ok(file)
+}
+
+/// Write a file readable only by the current user
+fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    use std::io::Write;
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+        .with_context(|| format!("Failed to create {}", path.display()))?
+        .write_all(contents)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_key_type_signs_and_verifies() {
+        for sig_type in [SignatureType::Ed25519, SignatureType::Secp256k1, SignatureType::PostQuantum] {
+            let key = KeyPair::generate(sig_type.clone());
+            let signature = key.sign(b"attestation").unwrap();
+            verify_signature(&sig_type, &key.public_key(), b"attestation", &signature).unwrap();
+            assert!(verify_signature(&sig_type, &key.public_key(), b"tampered", &signature).is_err());
+        }
+    }
+
+    #[test]
+    fn test_keystore_roundtrip_and_wrong_password() {
+        let dir = std::env::temp_dir().join(format!("zksac-keystore-{}", uuid::Uuid::new_v4()));
+        let keystore = Keystore::open(&dir).unwrap().with_kdf_cost(10);
+
+        let key = KeyPair::generate(SignatureType::Secp256k1);
+        let entry = keystore.insert(&key, "correct horse").unwrap();
+        assert!(keystore.insert(&key, "correct horse").is_err());
+
+        let listed = keystore.list().unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].public_key, key.public_key());
+
+        let unlocked = keystore.unlock(&entry.address, "correct horse").unwrap();
+        assert_eq!(unlocked.secret(), key.secret());
+        assert!(keystore.unlock(&entry.address, "battery staple").is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_argon2_keystore_holds_validator_and_bls_keys() {
+        let dir = std::env::temp_dir().join(format!("zksac-keystore-{}", uuid::Uuid::new_v4()));
+        let keystore = Keystore::open(&dir).unwrap().with_kdf(Kdf::Argon2id { memory_kib: 256, iterations: 1 });
+
+        let key = KeyPair::generate(SignatureType::Ed25519);
+        let entry = keystore.insert(&key, "correct horse").unwrap();
+        let text = std::fs::read_to_string(keystore.path_for(&entry.address)).unwrap();
+        assert!(text.contains("\"kdf\": \"argon2id\""));
+
+        let bls_key = BlsKeyPair::generate();
+        assert!(!keystore.has_bls_key(&entry.address));
+        keystore.insert_bls(&entry.address, &bls_key, "correct horse").unwrap();
+        assert!(keystore.insert_bls(&entry.address, &bls_key, "correct horse").is_err());
+        // BLS keys live apart from the validator keys
+        assert_eq!(keystore.list().unwrap().len(), 1);
+
+        // A fresh handle, as after a restart, unlocks the same keys
+        let reopened = Keystore::open(&dir).unwrap();
+        assert_eq!(reopened.unlock(&entry.address, "correct horse").unwrap().secret(), key.secret());
+        assert!(reopened.has_bls_key(&entry.address));
+        assert_eq!(reopened.unlock_bls(&entry.address, "correct horse").unwrap().public_key(), bls_key.public_key());
+        assert!(reopened.unlock_bls(&entry.address, "battery staple").is_err());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
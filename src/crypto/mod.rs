@@ -1,5 +1,8 @@
 pub mod signatures;
 pub mod hash;
+pub mod keystore;
+pub mod bls;
+pub mod randomness;
 
 pub use signatures::*;
 pub use hash::*; 
\ No newline at end of file
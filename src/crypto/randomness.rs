@@ -0,0 +1,223 @@
+//! Verifiable per-block randomness from a Wesolowski VDF
+//!
+//! Each producer evaluates a verifiable delay function over a seed fixed by
+//! the block's parent, `x^(2^T)` in the RSA-2048 group, and records the
+//! output with a proof in its header. Nobody knows the factorization of the
+//! modulus, so the output takes `T` sequential squarings to compute, while
+//! the proof `π = x^⌊2^T / ℓ⌋` for a prime challenge `ℓ` checks in two short
+//! exponentiations: `π^ℓ · x^(2^T mod ℓ) = y`. A producer that wants to
+//! steer the next producer selection by trying out blocks has to evaluate
+//! the VDF once per attempt.
+
+use super::hash::{keccak256_hash, shake256_hash};
+use crate::types::{BlockHash, RandomnessProof};
+use num_bigint::BigUint;
+use rand::RngCore;
+use rand::rngs::OsRng;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// Squarings per block unless the protocol config says otherwise
+pub const DEFAULT_VDF_ITERATIONS: u64 = 1 << 10;
+/// Width of group elements in a [`RandomnessProof`]
+pub const ELEMENT_LEN: usize = 256;
+
+const SEED_DOMAIN: &[u8] = b"zk-sac/beacon-seed/v1";
+const GROUP_DOMAIN: &[u8] = b"zk-sac/vdf-input/v1";
+const PRIME_DOMAIN: &[u8] = b"zk-sac/vdf-prime/v1";
+const OUTPUT_DOMAIN: &[u8] = b"zk-sac/beacon-output/v1";
+
+/// RSA-2048 from the RSA Factoring Challenge, whose factors were never published
+const RSA_2048: &str = "25195908475657893494027183240048398571429282126204032027777137836043662020707595556264018525880784406918290641249515082189298559149176184502808489120072844992687392807287776735971418347270261896375014971824691165077613379859095700097330459748808428401797429100642458691817195118746121515172654632282216869987549182422433637259085141865462043576798423387184774447920739934236584823824281198163815010674810451660377306056201619676256133844143603833904414952634432190114657544454178424020924616515723350778707749817125772467962926386356373289912154831438167899885040445364023527381951378636564391212010397122822120720357";
+
+fn modulus() -> &'static BigUint {
+    static MODULUS: OnceLock<BigUint> = OnceLock::new();
+    MODULUS.get_or_init(|| RSA_2048.parse().expect("valid decimal modulus"))
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum RandomnessError {
+    #[error("beacon output and proof must be {ELEMENT_LEN}-byte group elements")]
+    Malformed,
+    #[error("beacon proof does not match its output")]
+    InvalidProof,
+}
+
+/// VDF seed for block `block_number`. It is fixed once the parent exists,
+/// so the producer cannot choose it.
+pub fn beacon_seed(previous_hash: &BlockHash, block_number: u64) -> [u8; 32] {
+    let mut input = SEED_DOMAIN.to_vec();
+    input.extend_from_slice(&previous_hash.0);
+    input.extend_from_slice(&block_number.to_le_bytes());
+    keccak256_hash(&input)
+}
+
+/// The 32 bytes of randomness a beacon proof commits to
+pub fn beacon_randomness(proof: &RandomnessProof) -> [u8; 32] {
+    keccak256_hash(&[OUTPUT_DOMAIN, &proof.output].concat())
+}
+
+/// Wesolowski VDF over the RSA-2048 group with `iterations` squarings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Vdf {
+    iterations: u64,
+}
+
+impl Default for Vdf {
+    fn default() -> Self {
+        Self::new(DEFAULT_VDF_ITERATIONS)
+    }
+}
+
+impl Vdf {
+    pub fn new(iterations: u64) -> Self {
+        Self { iterations }
+    }
+
+    pub fn iterations(&self) -> u64 {
+        self.iterations
+    }
+
+    /// Compute `y = x^(2^T)` for the group element `x` the seed hashes to,
+    /// and the proof that `y` is correct
+    pub fn evaluate(&self, seed: &[u8]) -> RandomnessProof {
+        let n = modulus();
+        let x = hash_to_group(seed);
+        let mut y = x.clone();
+        for _ in 0..self.iterations {
+            y = &y * &y % n;
+        }
+
+        // π = x^⌊2^T / ℓ⌋, one bit of the quotient per step of long division
+        let l = hash_to_prime(&x, &y);
+        let (mut pi, mut r) = (BigUint::from(1u8), 1u128);
+        for _ in 0..self.iterations {
+            let doubled = 2 * r;
+            pi = &pi * &pi % n;
+            if doubled >= l {
+                pi = &pi * &x % n;
+            }
+            r = doubled % l;
+        }
+        RandomnessProof { output: to_element(&y), proof: to_element(&pi) }
+    }
+
+    /// Check that `proof` holds the VDF output for `seed`
+    pub fn verify(&self, seed: &[u8], proof: &RandomnessProof) -> Result<(), RandomnessError> {
+        let n = modulus();
+        let y = from_element(&proof.output)?;
+        let pi = from_element(&proof.proof)?;
+        let x = hash_to_group(seed);
+        let l = BigUint::from(hash_to_prime(&x, &y));
+        let r = BigUint::from(2u8).modpow(&BigUint::from(self.iterations), &l);
+        if pi.modpow(&l, n) * x.modpow(&r, n) % n == y {
+            Ok(())
+        } else {
+            Err(RandomnessError::InvalidProof)
+        }
+    }
+}
+
+fn hash_to_group(seed: &[u8]) -> BigUint {
+    // 64 bits wider than the modulus, so the reduction is close to uniform
+    let wide = shake256_hash(&[GROUP_DOMAIN, seed].concat(), ELEMENT_LEN + 8);
+    BigUint::from_bytes_be(&wide) % modulus()
+}
+
+/// Fiat-Shamir challenge: a prime in [2^126, 2^127) bound to the input and
+/// output. Kept below 2^127 so the long division in `evaluate` fits a u128.
+fn hash_to_prime(x: &BigUint, y: &BigUint) -> u128 {
+    let mut input = [PRIME_DOMAIN, &to_element(x), &to_element(y)].concat();
+    let counter_at = input.len();
+    input.extend_from_slice(&0u64.to_le_bytes());
+    for counter in 0u64.. {
+        input[counter_at..].copy_from_slice(&counter.to_le_bytes());
+        let bytes: [u8; 16] = shake256_hash(&input, 16).try_into().expect("16 bytes");
+        let candidate = (u128::from_le_bytes(bytes) >> 2) | (1 << 126) | 1;
+        if is_probable_prime(candidate) {
+            return candidate;
+        }
+    }
+    unreachable!("primes are dense enough to be found before the counter wraps")
+}
+
+/// Miller-Rabin with fixed bases, so prover and verifier agree on every candidate
+fn is_probable_prime(n: u128) -> bool {
+    const BASES: [u8; 12] = [2, 3, 5, 7, 11, 13, 17, 19, 23, 29, 31, 37];
+    if BASES.iter().any(|&p| n.is_multiple_of(u128::from(p))) {
+        return BASES.iter().any(|&p| n == u128::from(p));
+    }
+    let (one, minus_one) = (BigUint::from(1u8), BigUint::from(n - 1));
+    let trailing = (n - 1).trailing_zeros();
+    let d = BigUint::from((n - 1) >> trailing);
+    let n = BigUint::from(n);
+    BASES.iter().all(|&base| {
+        let mut x = BigUint::from(base).modpow(&d, &n);
+        if x == one || x == minus_one {
+            return true;
+        }
+        for _ in 1..trailing {
+            x = &x * &x % &n;
+            if x == minus_one {
+                return true;
+            }
+        }
+        false
+    })
+}
+
+fn to_element(value: &BigUint) -> Vec<u8> {
+    let bytes = value.to_bytes_be();
+    let mut element = vec![0u8; ELEMENT_LEN - bytes.len()];
+    element.extend_from_slice(&bytes);
+    element
+}
+
+fn from_element(bytes: &[u8]) -> Result<BigUint, RandomnessError> {
+    if bytes.len() != ELEMENT_LEN {
+        return Err(RandomnessError::Malformed);
+    }
+    let value = BigUint::from_bytes_be(bytes);
+    if &value >= modulus() {
+        return Err(RandomnessError::Malformed);
+    }
+    Ok(value)
+}
+
+/// 64 bits of randomness from a VDF over a fresh seed with `difficulty` squarings
+pub fn vdf_nonce(difficulty: u64) -> u64 {
+    let mut seed = [0u8; 32];
+    OsRng.fill_bytes(&mut seed);
+    let proof = Vdf::new(difficulty).evaluate(&seed);
+    u64::from_le_bytes(beacon_randomness(&proof)[..8].try_into().expect("8 bytes"))
+}
+
+/// 64 bits of OS randomness run through the beacon output hash
+pub fn random_beacon_nonce() -> u64 {
+    let mut output = vec![0u8; ELEMENT_LEN];
+    OsRng.fill_bytes(&mut output);
+    let randomness = beacon_randomness(&RandomnessProof { output, proof: Vec::new() });
+    u64::from_le_bytes(randomness[..8].try_into().expect("8 bytes"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_vdf_output_verifies_and_tampering_is_caught() {
+        let vdf = Vdf::new(64);
+        let seed = beacon_seed(&BlockHash([7; 32]), 3);
+        let proof = vdf.evaluate(&seed);
+        assert_eq!(proof, vdf.evaluate(&seed));
+        assert!(vdf.verify(&seed, &proof).is_ok());
+
+        // Another seed, iteration count or output does not verify
+        assert_eq!(vdf.verify(&beacon_seed(&BlockHash([7; 32]), 4), &proof), Err(RandomnessError::InvalidProof));
+        assert_eq!(Vdf::new(65).verify(&seed, &proof), Err(RandomnessError::InvalidProof));
+        let mut forged = proof.clone();
+        forged.output[ELEMENT_LEN - 1] ^= 1;
+        assert_eq!(vdf.verify(&seed, &forged), Err(RandomnessError::InvalidProof));
+        assert_eq!(vdf.verify(&seed, &RandomnessProof::default()), Err(RandomnessError::Malformed));
+    }
+}
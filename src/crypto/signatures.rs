@@ -1,8 +1,10 @@
+use crate::crypto::keystore::KeyPair;
 use crate::types::{Address, SignatureType};
 use anyhow::{Result, anyhow};
 use tracing::{info, debug, warn};
 use std::collections::HashMap;
 use rand::rngs::OsRng;
+use thiserror::Error;
 
 // Ed25519-dalek 2.2.0 API
 use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, Verifier};
@@ -12,6 +14,15 @@ use ed25519_dalek::{SigningKey, VerifyingKey, Signature, Signer, Verifier};
 
 pub struct SignatureEngine {
     ed25519_keys: HashMap<Address, SigningKey>,
+    secp256k1_keys: HashMap<Address, k256::ecdsa::SigningKey>,
+}
+
+/// The first signature of a batch that does not verify on its own
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("signature {index} of the batch does not verify: {reason}")]
+pub struct BatchVerifyError {
+    pub index: usize,
+    pub reason: String,
 }
 
 pub struct PostQuantumSigner {
@@ -24,6 +35,7 @@ impl SignatureEngine {
         info!("🔐 Initializing signature engine");
         SignatureEngine {
             ed25519_keys: HashMap::new(),
+            secp256k1_keys: HashMap::new(),
         }
     }
 
@@ -83,6 +95,24 @@ impl SignatureEngine {
         Ok(public_key_bytes)
     }
     
+    /// Sign with a key unlocked from the keystore, under its own address
+    pub fn add_key(&mut self, key: &KeyPair) -> Result<Address> {
+        let address = key.address();
+        match key.sig_type() {
+            SignatureType::Ed25519 => {
+                self.ed25519_keys.insert(address, SigningKey::from_bytes(key.secret()));
+            }
+            SignatureType::Secp256k1 => {
+                let signing_key = k256::ecdsa::SigningKey::from_slice(key.secret())
+                    .map_err(|_| anyhow!("Invalid secp256k1 secret key"))?;
+                self.secp256k1_keys.insert(address, signing_key);
+            }
+            SignatureType::PostQuantum => return Err(anyhow!("Post-quantum keys are signed with the PostQuantumSigner")),
+        }
+        debug!("🔑 Loaded {:?} key for {:?}", key.sig_type(), address);
+        Ok(address)
+    }
+
     /// Get public key for an address
     pub fn get_public_key(&self, address: &Address) -> Result<Vec<u8>> {
         let signing_key = self.ed25519_keys.get(address)
@@ -114,6 +144,124 @@ impl SignatureEngine {
         debug!("✅ Ed25519 signature verified with public key");
         Ok(())
     }
+
+    /// Verify `(signature, public_key, message)` triples together, which is
+    /// much cheaper than one at a time. A failing batch does not say which
+    /// signature is bad, so it is then verified one by one to find it.
+    pub fn verify_batch_ed25519(&self, items: &[(&[u8], &[u8], &[u8])]) -> Result<(), BatchVerifyError> {
+        if items.is_empty() {
+            return Ok(());
+        }
+        let parsed: Option<(Vec<Signature>, Vec<VerifyingKey>)> = items.iter()
+            .map(|(signature, public_key, _)| {
+                let signature = Signature::from_slice(signature).ok()?;
+                let public_key = VerifyingKey::from_bytes((*public_key).try_into().ok()?).ok()?;
+                Some((signature, public_key))
+            })
+            .collect::<Option<Vec<_>>>()
+            .map(|pairs| pairs.into_iter().unzip());
+        if let Some((signatures, public_keys)) = parsed {
+            let messages: Vec<&[u8]> = items.iter().map(|(_, _, message)| *message).collect();
+            if ed25519_dalek::verify_batch(&messages, &signatures, &public_keys).is_ok() {
+                debug!("✅ Batch of {} Ed25519 signatures verified", items.len());
+                return Ok(());
+            }
+        }
+        for (index, (signature, public_key, message)) in items.iter().enumerate() {
+            self.verify_with_public_key(signature, public_key, message)
+                .map_err(|e| BatchVerifyError { index, reason: format!("{:#}", e) })?;
+        }
+        Ok(())
+    }
+}
+
+impl SignatureEngine {
+    /// Generate a secp256k1 key; returns the address it controls and its
+    /// compressed public key
+    pub fn generate_secp256k1_keypair(&mut self) -> Result<(Address, Vec<u8>)> {
+        let signing_key = k256::ecdsa::SigningKey::random(&mut OsRng);
+        let public_key = signing_key.verifying_key().to_encoded_point(true).as_bytes().to_vec();
+        let address = Address::from_secp256k1_pubkey(&public_key)?;
+        self.secp256k1_keys.insert(address, signing_key);
+        debug!("🔑 Generated secp256k1 keypair for {:?}", address);
+        Ok((address, public_key))
+    }
+
+    pub fn sign_secp256k1(&self, address: &Address, message: &[u8]) -> Result<Vec<u8>> {
+        let signing_key = self.secp256k1_keys.get(address)
+            .ok_or_else(|| anyhow!("No secp256k1 key found for address {:?}", address))?;
+        secp256k1::sign(signing_key, message)
+    }
+
+    pub fn verify_secp256k1(&self, signature: &[u8], public_key: &[u8], message: &[u8]) -> Result<()> {
+        secp256k1::verify(public_key, message, signature)
+    }
+
+    /// Address that signed `message`, from a recoverable signature alone
+    pub fn recover_secp256k1_signer(&self, signature: &[u8], message: &[u8]) -> Result<Address> {
+        secp256k1::recover_address(message, signature)
+    }
+}
+
+/// Ethereum-style ECDSA: signatures are over the Keccak-256 hash of the
+/// message and are 65 bytes, `r || s || v`, where the recovery id `v` lets
+/// a verifier recover the signer's public key without having stored it
+pub mod secp256k1 {
+    use super::*;
+    use crate::crypto::hash::keccak256_hash;
+    use k256::ecdsa::{RecoveryId, Signature, SigningKey, VerifyingKey};
+
+    pub const SIGNATURE_LEN: usize = 65;
+
+    pub fn sign(key: &SigningKey, message: &[u8]) -> Result<Vec<u8>> {
+        let (signature, recovery_id) = key.sign_prehash_recoverable(&keccak256_hash(message))
+            .map_err(|e| anyhow!("secp256k1 signing failed: {}", e))?;
+        let mut bytes = signature.to_bytes().to_vec();
+        bytes.push(recovery_id.to_byte());
+        Ok(bytes)
+    }
+
+    /// Check `signature` against `public_key`; a 64-byte signature without
+    /// a recovery id is accepted too
+    pub fn verify(public_key: &[u8], message: &[u8], signature: &[u8]) -> Result<()> {
+        use k256::ecdsa::signature::hazmat::PrehashVerifier;
+        let key = VerifyingKey::from_sec1_bytes(public_key)
+            .map_err(|e| anyhow!("Invalid secp256k1 public key: {}", e))?;
+        let compact = signature.get(..64).filter(|_| matches!(signature.len(), 64 | SIGNATURE_LEN))
+            .ok_or_else(|| anyhow!("Invalid secp256k1 signature length {}", signature.len()))?;
+        let parsed = Signature::from_slice(compact)
+            .map_err(|e| anyhow!("Invalid secp256k1 signature format: {}", e))?;
+        key.verify_prehash(&keccak256_hash(message), &parsed)
+            .map_err(|e| anyhow!("secp256k1 signature verification failed: {}", e))?;
+        if signature.len() == SIGNATURE_LEN && recover_key(message, signature)? != key {
+            return Err(anyhow!("secp256k1 recovery id does not match the public key"));
+        }
+        Ok(())
+    }
+
+    /// Public key, compressed, that produced a 65-byte `signature` over `message`
+    pub fn recover_public_key(message: &[u8], signature: &[u8]) -> Result<Vec<u8>> {
+        Ok(recover_key(message, signature)?.to_encoded_point(true).as_bytes().to_vec())
+    }
+
+    /// Address of the key that produced a 65-byte `signature` over `message`
+    pub fn recover_address(message: &[u8], signature: &[u8]) -> Result<Address> {
+        Ok(Address::from_secp256k1_pubkey(&recover_public_key(message, signature)?)?)
+    }
+
+    fn recover_key(message: &[u8], signature: &[u8]) -> Result<VerifyingKey> {
+        if signature.len() != SIGNATURE_LEN {
+            return Err(anyhow!("Recoverable secp256k1 signatures are {} bytes, got {}", SIGNATURE_LEN, signature.len()));
+        }
+        let parsed = Signature::from_slice(&signature[..64])
+            .map_err(|e| anyhow!("Invalid secp256k1 signature format: {}", e))?;
+        // Ethereum encodes the recovery id as 27 or 28
+        let v = signature[64];
+        let recovery_id = RecoveryId::from_byte(if v >= 27 { v - 27 } else { v })
+            .ok_or_else(|| anyhow!("Invalid secp256k1 recovery id {}", v))?;
+        VerifyingKey::recover_from_prehash(&keccak256_hash(message), &parsed, recovery_id)
+            .map_err(|e| anyhow!("secp256k1 key recovery failed: {}", e))
+    }
 }
 
 impl PostQuantumSigner {
@@ -162,7 +310,7 @@ impl PostQuantumSigner {
             
             // Add some deterministic data
             signature[0..4].copy_from_slice(&address.0[0..4]);
-            signature[4..8].copy_from_slice(&message.len().to_le_bytes());
+            signature[4..8].copy_from_slice(&(message.len() as u32).to_le_bytes());
             signature[8] = 0xAA; // LMS signature marker
             
             // Fill rest with hash of message for determinism
@@ -208,7 +356,8 @@ impl PostQuantumSigner {
     pub fn get_signature_size(&self, sig_type: &SignatureType) -> usize {
         match sig_type {
             SignatureType::PostQuantum => 1024, // Typical LMS signature size
-            _ => 64, // Ed25519 size
+            SignatureType::Secp256k1 => secp256k1::SIGNATURE_LEN,
+            SignatureType::Ed25519 => 64,
         }
     }
 }
@@ -280,6 +429,73 @@ mod tests {
         engine.verify_ed25519(&signature, &address, message).unwrap();
     }
 
+    #[test]
+    fn test_keys_from_the_keystore_sign_under_their_address() {
+        let mut engine = SignatureEngine::new();
+        let ed25519 = KeyPair::generate(SignatureType::Ed25519);
+        let address = engine.add_key(&ed25519).unwrap();
+        let signature = engine.sign_ed25519(&address, b"vote").unwrap();
+        engine.verify_with_public_key(&signature, &ed25519.public_key(), b"vote").unwrap();
+
+        let secp256k1 = KeyPair::generate(SignatureType::Secp256k1);
+        let address = engine.add_key(&secp256k1).unwrap();
+        let signature = engine.sign_secp256k1(&address, b"vote").unwrap();
+        assert_eq!(engine.recover_secp256k1_signer(&signature, b"vote").unwrap(), address);
+        assert!(engine.add_key(&KeyPair::generate(SignatureType::PostQuantum)).is_err());
+    }
+
+    #[test]
+    fn test_batch_verification_finds_the_bad_signature() {
+        let mut engine = SignatureEngine::new();
+        let mut signed: Vec<(Vec<u8>, Vec<u8>, Vec<u8>)> = Vec::new();
+        for id in 1..=4u8 {
+            let address = Address::new(id);
+            let public_key = engine.generate_ed25519_keypair(address).unwrap();
+            let message = vec![id; 8];
+            signed.push((engine.sign_ed25519(&address, &message).unwrap(), public_key, message));
+        }
+        let check = |items: &[(Vec<u8>, Vec<u8>, Vec<u8>)]| {
+            let refs: Vec<(&[u8], &[u8], &[u8])> = items.iter()
+                .map(|(signature, public_key, message)| (signature.as_slice(), public_key.as_slice(), message.as_slice()))
+                .collect();
+            engine.verify_batch_ed25519(&refs)
+        };
+        check(&signed).unwrap();
+        check(&[]).unwrap();
+
+        let mut tampered = signed.clone();
+        tampered[2].2[0] ^= 1;
+        assert_eq!(check(&tampered).unwrap_err().index, 2);
+        let mut truncated = signed.clone();
+        truncated[1].0.pop();
+        assert_eq!(check(&truncated).unwrap_err().index, 1);
+    }
+
+    #[test]
+    fn test_secp256k1_signatures_recover_the_signer() {
+        let mut engine = SignatureEngine::new();
+        let (address, public_key) = engine.generate_secp256k1_keypair().unwrap();
+        let message = b"ethereum-style transaction";
+
+        let signature = engine.sign_secp256k1(&address, message).unwrap();
+        assert_eq!(signature.len(), secp256k1::SIGNATURE_LEN);
+        engine.verify_secp256k1(&signature, &public_key, message).unwrap();
+        engine.verify_secp256k1(&signature[..64], &public_key, message).unwrap();
+        assert_eq!(engine.recover_secp256k1_signer(&signature, message).unwrap(), address);
+        assert_eq!(secp256k1::recover_public_key(message, &signature).unwrap(), public_key);
+
+        // Ethereum's 27/28 recovery ids are accepted
+        let mut legacy_v = signature.clone();
+        legacy_v[64] += 27;
+        assert_eq!(engine.recover_secp256k1_signer(&legacy_v, message).unwrap(), address);
+
+        assert!(engine.verify_secp256k1(&signature, &public_key, b"another message").is_err());
+        assert_ne!(engine.recover_secp256k1_signer(&signature, b"another message").ok(), Some(address));
+        let mut wrong_id = signature.clone();
+        wrong_id[64] ^= 1;
+        assert!(engine.verify_secp256k1(&wrong_id, &public_key, message).is_err());
+    }
+
     #[tokio::test]
     async fn test_lms_signature_cycle() {
         let mut signer = PostQuantumSigner::new().unwrap();
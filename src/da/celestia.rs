@@ -0,0 +1,140 @@
+//! Celestia DA adapter
+//!
+//! Talks to a celestia-node (light or full) over its JSON-RPC API. Blobs are
+//! submitted with `blob.Submit`, and the commitment the network computed is
+//! read back with `blob.GetAll` at the inclusion height. Retrieval goes
+//! through `blob.Get`, which on a light node only succeeds once data
+//! availability sampling for that height has passed.
+
+use super::{DaReceipt, DataAvailability, Namespace};
+use crate::crypto::hash::keccak256_hash;
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+pub struct CelestiaDa {
+    endpoint: String,
+    auth_token: Option<String>,
+    namespace: Namespace,
+    client: reqwest::Client,
+    next_id: AtomicU64,
+}
+
+impl CelestiaDa {
+    pub fn new(endpoint: impl Into<String>, namespace: Namespace) -> Self {
+        Self {
+            endpoint: endpoint.into(),
+            auth_token: None,
+            namespace,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(60))
+                .build()
+                .unwrap_or_default(),
+            next_id: AtomicU64::new(1),
+        }
+    }
+
+    /// Token from `celestia <node-type> auth write`
+    pub fn with_auth_token(mut self, token: impl Into<String>) -> Self {
+        self.auth_token = Some(token.into());
+        self
+    }
+
+    /// The full JSON-RPC response, so callers can inspect errors
+    async fn request(&self, method: &str, params: Value) -> Result<Value> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params,
+        });
+        let mut builder = self.client.post(&self.endpoint).json(&request);
+        if let Some(token) = &self.auth_token {
+            builder = builder.bearer_auth(token);
+        }
+        Ok(builder.send()
+            .await
+            .with_context(|| format!("{} request to {} failed", method, self.endpoint))?
+            .error_for_status()?
+            .json()
+            .await?)
+    }
+
+    async fn call(&self, method: &str, params: Value) -> Result<Value> {
+        let response = self.request(method, params).await?;
+        if let Some(error) = response.get("error") {
+            bail!("{} failed: {}", method, error_message(error));
+        }
+        Ok(response["result"].clone())
+    }
+
+    fn encoded_namespace(&self) -> String {
+        BASE64.encode(self.namespace.0)
+    }
+}
+
+fn error_message(error: &Value) -> &str {
+    error["message"].as_str().unwrap_or("unknown error")
+}
+
+fn decode_field(blob: &Value, field: &str) -> Result<Vec<u8>> {
+    let text = blob[field].as_str().ok_or_else(|| anyhow!("Blob without {}", field))?;
+    BASE64.decode(text).with_context(|| format!("Invalid base64 in blob {}", field))
+}
+
+#[async_trait]
+impl DataAvailability for CelestiaDa {
+    async fn submit(&self, blob: &[u8]) -> Result<DaReceipt> {
+        let namespace = self.encoded_namespace();
+        let entry = json!({
+            "namespace": namespace,
+            "data": BASE64.encode(blob),
+            "share_version": 0,
+            "commitment": null,
+        });
+        let height = self.call("blob.Submit", json!([[entry], {}])).await?
+            .as_u64()
+            .ok_or_else(|| anyhow!("blob.Submit returned no height"))?;
+
+        let included = self.call("blob.GetAll", json!([height, [namespace]])).await?;
+        let commitment = included.as_array()
+            .into_iter()
+            .flatten()
+            .find(|candidate| decode_field(candidate, "data").is_ok_and(|data| data == blob))
+            .map(|candidate| decode_field(candidate, "commitment"))
+            .transpose()?
+            .ok_or_else(|| anyhow!("Submitted blob not found at Celestia height {}", height))?;
+
+        Ok(DaReceipt { height, commitment, data_hash: keccak256_hash(blob) })
+    }
+
+    async fn retrieve(&self, receipt: &DaReceipt) -> Result<Option<Vec<u8>>> {
+        let params = json!([receipt.height, self.encoded_namespace(), BASE64.encode(&receipt.commitment)]);
+        let response = self.request("blob.Get", params).await?;
+        if let Some(error) = response.get("error") {
+            let message = error_message(error);
+            if message.contains("not found") {
+                return Ok(None);
+            }
+            bail!("blob.Get failed: {}", message);
+        }
+        Ok(Some(decode_field(&response["result"], "data")?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decodes_blob_fields() {
+        let blob = json!({ "data": BASE64.encode(b"block"), "commitment": BASE64.encode([7u8; 32]) });
+        assert_eq!(decode_field(&blob, "data").unwrap(), b"block");
+        assert_eq!(decode_field(&blob, "commitment").unwrap(), vec![7; 32]);
+        assert!(decode_field(&blob, "namespace").is_err());
+    }
+}
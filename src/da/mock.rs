@@ -0,0 +1,60 @@
+//! In-memory DA layer for tests and the devnet
+
+use super::{DaReceipt, DataAvailability};
+use crate::crypto::hash::keccak256_hash;
+use anyhow::Result;
+use async_trait::async_trait;
+use parking_lot::Mutex;
+
+#[derive(Debug, Default)]
+struct MockStore {
+    /// Blob at each height, and whether it is being withheld
+    blobs: Vec<(Vec<u8>, bool)>,
+    withholding: bool,
+}
+
+/// Includes every blob at a new height; while withholding, blobs are
+/// included but never served, like a block whose data was not released
+#[derive(Debug, Default)]
+pub struct MockDa {
+    inner: Mutex<MockStore>,
+}
+
+impl MockDa {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_withholding(&self, withholding: bool) {
+        self.inner.lock().withholding = withholding;
+    }
+
+    /// Number of blobs that can be retrieved
+    pub fn blob_count(&self) -> usize {
+        self.inner.lock().blobs.iter().filter(|(_, withheld)| !withheld).count()
+    }
+}
+
+#[async_trait]
+impl DataAvailability for MockDa {
+    async fn submit(&self, blob: &[u8]) -> Result<DaReceipt> {
+        let mut store = self.inner.lock();
+        let withheld = store.withholding;
+        store.blobs.push((blob.to_vec(), withheld));
+        let data_hash = keccak256_hash(blob);
+        Ok(DaReceipt {
+            height: store.blobs.len() as u64,
+            commitment: data_hash.to_vec(),
+            data_hash,
+        })
+    }
+
+    async fn retrieve(&self, receipt: &DaReceipt) -> Result<Option<Vec<u8>>> {
+        let store = self.inner.lock();
+        let index = receipt.height.checked_sub(1).map(|index| index as usize);
+        Ok(index
+            .and_then(|index| store.blobs.get(index))
+            .filter(|(_, withheld)| !withheld)
+            .map(|(blob, _)| blob.clone()))
+    }
+}
@@ -0,0 +1,217 @@
+//! Data availability layer
+//!
+//! For a rollup-style deployment the engine does not keep block bodies to
+//! itself: each block is published as a blob to an external DA network, and
+//! it is only finalized once the blob can be retrieved back and matches the
+//! block. The [`DataAvailability`] trait is the adapter boundary;
+//! [`CelestiaDa`] talks to a Celestia node and [`MockDa`] keeps blobs in
+//! memory.
+
+pub mod celestia;
+pub mod mock;
+
+pub use celestia::CelestiaDa;
+pub use mock::MockDa;
+
+use crate::crypto::hash::keccak256_hash;
+use crate::serialization::{Codec, DecodeLimits, open, seal};
+use crate::types::Block;
+use anyhow::{Context, Result, bail};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use tracing::{debug, info};
+
+pub const NAMESPACE_LEN: usize = 29;
+const NAMESPACE_ID_V0_LEN: usize = 10;
+
+/// Celestia-style namespace: a version byte followed by a 28-byte id
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Namespace(#[serde(with = "hex::serde")] pub [u8; NAMESPACE_LEN]);
+
+impl Namespace {
+    /// Version 0 namespace; `id` is at most 10 bytes and is right-aligned
+    /// behind the 18 zero bytes version 0 requires
+    pub fn v0(id: &[u8]) -> Result<Self> {
+        if id.is_empty() || id.len() > NAMESPACE_ID_V0_LEN {
+            bail!("Namespace id must be 1 to {} bytes, got {}", NAMESPACE_ID_V0_LEN, id.len());
+        }
+        let mut bytes = [0u8; NAMESPACE_LEN];
+        bytes[NAMESPACE_LEN - id.len()..].copy_from_slice(id);
+        Ok(Self(bytes))
+    }
+}
+
+/// Where a published blob can be found
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DaReceipt {
+    /// DA network height the blob was included at
+    pub height: u64,
+    /// Commitment the DA network computed for the blob
+    #[serde(with = "hex::serde")]
+    pub commitment: Vec<u8>,
+    /// Keccak hash of the blob, to check retrieved data against
+    #[serde(with = "hex::serde")]
+    pub data_hash: [u8; 32],
+}
+
+/// An external data availability network
+#[async_trait]
+pub trait DataAvailability: Send + Sync {
+    /// Publish `blob`, returning once the network has included it
+    async fn submit(&self, blob: &[u8]) -> Result<DaReceipt>;
+    /// The blob behind `receipt`, or `None` if the network cannot serve it
+    async fn retrieve(&self, receipt: &DaReceipt) -> Result<Option<Vec<u8>>>;
+}
+
+#[derive(Debug, Clone)]
+pub struct DaConfig {
+    /// How long to wait for a published blob to become retrievable
+    pub availability_timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for DaConfig {
+    fn default() -> Self {
+        Self {
+            availability_timeout: Duration::from_secs(30),
+            poll_interval: Duration::from_secs(1),
+        }
+    }
+}
+
+impl DaConfig {
+    pub fn with_availability_timeout(mut self, timeout: Duration) -> Self {
+        self.availability_timeout = timeout;
+        self
+    }
+
+    pub fn with_poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+}
+
+/// Blob a block is published as
+pub fn encode_block(block: &Block) -> Result<Vec<u8>> {
+    seal(block, Codec::BincodeZstd)
+}
+
+pub fn decode_block(blob: &[u8], limits: &DecodeLimits) -> Result<Block> {
+    open(blob, limits)
+}
+
+/// Publishes blocks to a DA layer and holds them back from finalization
+/// until they are available
+pub struct AvailabilityGate {
+    layer: Arc<dyn DataAvailability>,
+    config: DaConfig,
+}
+
+impl AvailabilityGate {
+    pub fn new(layer: Arc<dyn DataAvailability>, config: DaConfig) -> Self {
+        Self { layer, config }
+    }
+
+    /// Publish `block` and wait until it can be retrieved; the block must not
+    /// be finalized if this fails
+    pub async fn publish(&self, block: &Block) -> Result<DaReceipt> {
+        let number = block.header.block_number;
+        let blob = encode_block(block)?;
+        let receipt = self.layer.submit(&blob).await
+            .with_context(|| format!("Failed to publish block {} to the DA layer", number))?;
+        debug!("📦 Block {} published at DA height {} ({} bytes)", number, receipt.height, blob.len());
+
+        self.await_available(number, &receipt).await?;
+        info!("📦 Block {} available at DA height {}", number, receipt.height);
+        Ok(receipt)
+    }
+
+    /// Wait until the blob behind `receipt` is retrievable and matches its hash
+    pub async fn await_available(&self, block_number: u64, receipt: &DaReceipt) -> Result<()> {
+        let deadline = Instant::now() + self.config.availability_timeout;
+        loop {
+            if let Some(blob) = self.layer.retrieve(receipt).await? {
+                if keccak256_hash(&blob) != receipt.data_hash {
+                    bail!("DA layer returned different data for block {} at height {}", block_number, receipt.height);
+                }
+                return Ok(());
+            }
+            if Instant::now() >= deadline {
+                bail!(
+                    "Block {} was not available at DA height {} within {:?}",
+                    block_number, receipt.height, self.config.availability_timeout
+                );
+            }
+            tokio::time::sleep(self.config.poll_interval).await;
+        }
+    }
+
+    /// Fetch and decode the block published under `receipt`
+    pub async fn fetch_block(&self, receipt: &DaReceipt, limits: &DecodeLimits) -> Result<Option<Block>> {
+        match self.layer.retrieve(receipt).await? {
+            Some(blob) if keccak256_hash(&blob) == receipt.data_hash => Ok(Some(decode_block(&blob, limits)?)),
+            Some(_) => bail!("DA layer returned different data at height {}", receipt.height),
+            None => Ok(None),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{Address, BlockHash, BlockHeader, ProofType, RandomnessProof, ZkProof};
+
+    fn block(number: u64) -> Block {
+        Block {
+            header: BlockHeader {
+                previous_hash: BlockHash::zero(),
+                merkle_root: BlockHash::zero(),
+                prev_state_root: BlockHash::zero(),
+                state_root: BlockHash::zero(),
+                timestamp: 0,
+                block_number: number,
+                gas_limit: 0,
+                gas_used: 0,
+                producer: Address::new(1),
+                extra_data: Vec::new(),
+                randomness: RandomnessProof::default(),
+            },
+            transactions: Vec::new(),
+            validator_signatures: Vec::new(),
+            recursive_proof: ZkProof { proof_data: vec![1; 32], public_inputs: vec![], verification_key: vec![], proof_type: ProofType::Risc0 },
+            protocol_updates: Vec::new(),
+            proof_signature: Vec::new(),
+            aggregate_signature: None,
+        }
+    }
+
+    #[test]
+    fn test_v0_namespace_layout() {
+        let namespace = Namespace::v0(b"zksac").unwrap();
+        assert_eq!(&namespace.0[..24], &[0u8; 24]);
+        assert_eq!(&namespace.0[24..], b"zksac");
+        assert!(Namespace::v0(&[1; 11]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_blocks_gate_on_availability() {
+        let da = Arc::new(MockDa::new());
+        let config = DaConfig::default()
+            .with_availability_timeout(Duration::from_millis(50))
+            .with_poll_interval(Duration::from_millis(10));
+        let gate = AvailabilityGate::new(da.clone(), config);
+
+        let receipt = gate.publish(&block(1)).await.unwrap();
+        let fetched = gate.fetch_block(&receipt, &DecodeLimits::default()).await.unwrap().unwrap();
+        assert_eq!(fetched.header.hash(), block(1).header.hash());
+
+        da.set_withholding(true);
+        assert!(gate.publish(&block(2)).await.is_err());
+        da.set_withholding(false);
+        gate.publish(&block(2)).await.unwrap();
+        assert_eq!(da.blob_count(), 2);
+    }
+}
@@ -0,0 +1,27 @@
+//! Top-level error type
+//!
+//! Each module reports failures with its own enum: [`ConsensusError`] from
+//! the engine, [`ProofError`] from the zkVM backends, [`TxValidationError`]
+//! from transaction admission and [`SerializationError`] from the encoding
+//! helpers. [`EngineError`] wraps them all for callers that handle any of
+//! them in one place, and still lets them match on the cause.
+
+use crate::consensus::ConsensusError;
+use crate::mempool::TxValidationError;
+use crate::serialization::SerializationError;
+use crate::zkvm::ProofError;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum EngineError {
+    #[error(transparent)]
+    Consensus(#[from] ConsensusError),
+    #[error(transparent)]
+    Proof(#[from] ProofError),
+    #[error(transparent)]
+    Transaction(#[from] TxValidationError),
+    #[error(transparent)]
+    Serialization(#[from] SerializationError),
+    #[error("i/o: {0}")]
+    Io(#[from] std::io::Error),
+}
@@ -0,0 +1,195 @@
+//! Fault injection for chaos testing
+//!
+//! Engine code calls [`check`], [`delay`] or [`triggered`] at the points
+//! where real deployments fail: proof generation, storage writes, signature
+//! collection and gossip. With the `fault-injection` feature, a test installs
+//! a [`FaultConfig`] giving each point a probability, and the hooks fail at
+//! random (from a seeded RNG) until the returned [`FaultGuard`] is dropped.
+//! Without the feature every hook is an inlined no-op.
+
+use std::fmt;
+use std::time::Duration;
+use thiserror::Error;
+
+#[cfg(feature = "fault-injection")]
+use {
+    parking_lot::Mutex,
+    rand::{Rng, SeedableRng, rngs::StdRng},
+    std::collections::HashMap,
+    tracing::{debug, info},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FaultPoint {
+    /// The zkVM or the engine fails to produce a proof
+    ProofGeneration,
+    /// Writing a block to the block log fails
+    StorageWrite,
+    /// Collecting validator signatures takes longer than usual
+    SignatureCollection,
+    /// A gossiped message arrives corrupted
+    GossipCorruption,
+}
+
+impl fmt::Display for FaultPoint {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FaultPoint::ProofGeneration => "proof generation",
+            FaultPoint::StorageWrite => "storage write",
+            FaultPoint::SignatureCollection => "signature collection",
+            FaultPoint::GossipCorruption => "gossip corruption",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("injected {0} fault")]
+pub struct InjectedFault(pub FaultPoint);
+
+#[derive(Debug, Clone)]
+pub struct FaultConfig {
+    pub seed: u64,
+    pub probabilities: Vec<(FaultPoint, f64)>,
+    /// How long an injected signature collection delay lasts
+    pub signature_delay: Duration,
+}
+
+impl Default for FaultConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0,
+            probabilities: Vec::new(),
+            signature_delay: Duration::from_millis(200),
+        }
+    }
+}
+
+impl FaultConfig {
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Fail `point` with `probability` (clamped to 0..=1) each time it is reached
+    pub fn with_probability(mut self, point: FaultPoint, probability: f64) -> Self {
+        self.probabilities.retain(|(existing, _)| *existing != point);
+        self.probabilities.push((point, probability.clamp(0.0, 1.0)));
+        self
+    }
+
+    pub fn with_signature_delay(mut self, delay: Duration) -> Self {
+        self.signature_delay = delay;
+        self
+    }
+}
+
+#[cfg(feature = "fault-injection")]
+struct Injector {
+    config: FaultConfig,
+    rng: StdRng,
+    injected: HashMap<FaultPoint, u64>,
+}
+
+#[cfg(feature = "fault-injection")]
+static INJECTOR: Mutex<Option<Injector>> = Mutex::new(None);
+
+/// Removes the installed faults when dropped
+#[must_use = "faults are removed when the guard is dropped"]
+pub struct FaultGuard(());
+
+impl Drop for FaultGuard {
+    fn drop(&mut self) {
+        clear();
+    }
+}
+
+/// Start injecting faults as `config` describes, replacing earlier faults
+#[cfg(feature = "fault-injection")]
+pub fn install(config: FaultConfig) -> FaultGuard {
+    info!("💥 Fault injection enabled: {:?}", config.probabilities);
+    let rng = StdRng::seed_from_u64(config.seed);
+    *INJECTOR.lock() = Some(Injector { config, rng, injected: HashMap::new() });
+    FaultGuard(())
+}
+
+/// Stop injecting faults
+pub fn clear() {
+    #[cfg(feature = "fault-injection")]
+    {
+        *INJECTOR.lock() = None;
+    }
+}
+
+/// Number of faults injected at `point` since [`install`]
+#[cfg(feature = "fault-injection")]
+pub fn injected(point: FaultPoint) -> u64 {
+    INJECTOR.lock().as_ref().map_or(0, |injector| injector.injected.get(&point).copied().unwrap_or(0))
+}
+
+/// Whether a fault fires at `point` this time
+#[cfg(feature = "fault-injection")]
+pub fn triggered(point: FaultPoint) -> bool {
+    let mut guard = INJECTOR.lock();
+    let Some(injector) = guard.as_mut() else { return false };
+    let Some(&(_, probability)) = injector.config.probabilities.iter().find(|(p, _)| *p == point) else {
+        return false;
+    };
+    let fired = injector.rng.gen_bool(probability);
+    if fired {
+        *injector.injected.entry(point).or_default() += 1;
+        debug!("💥 Injecting {} fault", point);
+    }
+    fired
+}
+
+#[cfg(not(feature = "fault-injection"))]
+#[inline(always)]
+pub fn triggered(_point: FaultPoint) -> bool {
+    false
+}
+
+/// `Err` when a fault fires at `point`
+#[inline]
+pub fn check(point: FaultPoint) -> Result<(), InjectedFault> {
+    if triggered(point) {
+        Err(InjectedFault(point))
+    } else {
+        Ok(())
+    }
+}
+
+/// Extra time to spend at `point`: the configured delay when a fault fires,
+/// otherwise zero
+#[inline]
+pub fn delay(point: FaultPoint) -> Duration {
+    if !triggered(point) {
+        return Duration::ZERO;
+    }
+    #[cfg(feature = "fault-injection")]
+    {
+        INJECTOR.lock().as_ref().map_or(Duration::ZERO, |injector| injector.config.signature_delay)
+    }
+    #[cfg(not(feature = "fault-injection"))]
+    {
+        Duration::ZERO
+    }
+}
+
+#[cfg(all(test, feature = "fault-injection"))]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[serial_test::serial(faults)]
+    fn test_faults_fire_at_configured_rate_until_cleared() {
+        let guard = install(FaultConfig::default().with_seed(7).with_probability(FaultPoint::StorageWrite, 0.25));
+        let failures = (0..1000).filter(|_| check(FaultPoint::StorageWrite).is_err()).count();
+        assert!((150..350).contains(&failures), "{} failures", failures);
+        assert_eq!(injected(FaultPoint::StorageWrite), failures as u64);
+        assert!(check(FaultPoint::ProofGeneration).is_ok());
+
+        drop(guard);
+        assert!(check(FaultPoint::StorageWrite).is_ok());
+    }
+}
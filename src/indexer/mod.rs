@@ -0,0 +1,130 @@
+//! Chain indexer
+//!
+//! Subscribes to the engine's [`EventBus`] and writes blocks, transactions,
+//! receipts, balance changes, validator events and performance samples into
+//! SQLite, with an optional Parquet export of the same tables. The table
+//! layout in [`schema`] is stable, so explorers and analytics can query the
+//! files without their own ingestion code; [`query`] answers the common
+//! per-account lookups directly.
+
+pub mod parquet;
+pub mod query;
+pub mod schema;
+pub mod sqlite;
+
+pub use query::{BalanceEntry, IndexedBlock, IndexedTransaction};
+pub use sqlite::SqliteIndex;
+
+use crate::consensus::events::{ConsensusEvent, EventBus};
+use anyhow::Result;
+use std::path::PathBuf;
+use tokio::sync::broadcast;
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+#[derive(Debug, Clone)]
+pub struct IndexerConfig {
+    pub database: PathBuf,
+    /// Export the tables to Parquet here when the indexer stops
+    pub parquet_dir: Option<PathBuf>,
+}
+
+impl IndexerConfig {
+    pub fn new(database: impl Into<PathBuf>) -> Self {
+        Self { database: database.into(), parquet_dir: None }
+    }
+
+    pub fn with_parquet_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.parquet_dir = Some(dir.into());
+        self
+    }
+}
+
+pub struct Indexer {
+    index: SqliteIndex,
+    events: broadcast::Receiver<ConsensusEvent>,
+    config: IndexerConfig,
+}
+
+impl Indexer {
+    /// Open the index and subscribe to `bus`; events from now on are indexed
+    pub fn new(config: IndexerConfig, bus: &EventBus) -> Result<Self> {
+        let index = SqliteIndex::open(&config.database)?;
+        info!("🗂️  Indexing into {} (at height {})", config.database.display(), index.height()?);
+        Ok(Self { index, events: bus.subscribe(), config })
+    }
+
+    /// Index events until `shutdown` fires or the bus closes, then export
+    /// Parquet if configured
+    pub async fn run(mut self, shutdown: CancellationToken) -> Result<SqliteIndex> {
+        loop {
+            let event = tokio::select! {
+                _ = shutdown.cancelled() => break,
+                event = self.events.recv() => event,
+            };
+            match event {
+                Ok(event) => self.index.apply(&event)?,
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("⚠️  Indexer lagged behind the event bus, skipped {} events", skipped);
+                }
+                Err(broadcast::error::RecvError::Closed) => break,
+            }
+        }
+
+        // Index what was already published before stopping
+        while let Ok(event) = self.events.try_recv() {
+            self.index.apply(&event)?;
+        }
+        if let Some(dir) = &self.config.parquet_dir {
+            parquet::export(&self.index, dir)?;
+        }
+        Ok(self.index)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::engine::{ConsensusEngine, ZkSacConsensusEngine};
+    use crate::crypto::keystore::KeyPair;
+    use crate::types::*;
+
+    #[tokio::test]
+    async fn test_indexes_blocks_and_finality_from_the_bus() {
+        let dir = std::env::temp_dir().join(format!("zksac-indexer-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let key = KeyPair::generate(SignatureType::Ed25519);
+        let producer = key.address();
+        let mut state = WorldState::default();
+        state.accounts.insert(producer, Account::new(1_000_000));
+        let validators = vec![Validator { address: producer, stake: 100, public_key: key.public_key(), performance_score: 1.0, bls_public_key: Vec::new() }];
+        let mut engine = ZkSacConsensusEngine::new(state, validators, ProtocolConfig::default()).unwrap();
+        engine.add_validator_key(key.clone()).unwrap();
+
+        let config = IndexerConfig::new(dir.join("index.sqlite")).with_parquet_dir(dir.join("parquet"));
+        let indexer = Indexer::new(config, &engine.events).unwrap();
+        let shutdown = CancellationToken::new();
+        let task = tokio::spawn(indexer.run(shutdown.clone()));
+
+        for nonce in 0..2 {
+            engine.add_local_transaction(Transaction::new(producer, Address::new(2), 10, nonce).signed(&key).unwrap()).unwrap();
+            let block = engine.produce_block(producer).await.unwrap();
+            engine.apply_block(block).await.unwrap();
+        }
+        shutdown.cancel();
+
+        let index = task.await.unwrap().unwrap();
+        assert_eq!(index.height().unwrap(), 2);
+        assert_eq!(index.count("transactions").unwrap(), 2);
+        assert_eq!(index.count("receipts").unwrap(), 2);
+        let finalized: u64 = index.connection()
+            .query_row("SELECT COUNT(*) FROM blocks WHERE finalized = 1", [], |row| row.get(0))
+            .unwrap();
+        // The only validator's vote in each block finalizes it
+        assert_eq!(finalized, 2);
+        assert!(dir.join("parquet/blocks.parquet").exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,98 @@
+//! Parquet export of the index
+//!
+//! Each table is written to `<dir>/<table>.parquet` with the same columns as
+//! in SQLite, for analytics tools that read Parquet directly.
+
+use super::schema::{ColumnType, TABLES, Table};
+use super::sqlite::SqliteIndex;
+use anyhow::{Context, Result};
+use arrow_array::builder::{BinaryBuilder, Float64Builder, Int64Builder, StringBuilder};
+use arrow_array::{ArrayRef, RecordBatch};
+use arrow_schema::{DataType, Field, Schema};
+use ::parquet::arrow::ArrowWriter;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tracing::info;
+
+enum ColumnBuilder {
+    Integer(Int64Builder),
+    Real(Float64Builder),
+    Text(StringBuilder),
+    Blob(BinaryBuilder),
+}
+
+impl ColumnBuilder {
+    fn new(kind: ColumnType) -> Self {
+        match kind {
+            ColumnType::Integer => ColumnBuilder::Integer(Int64Builder::new()),
+            ColumnType::Real => ColumnBuilder::Real(Float64Builder::new()),
+            ColumnType::Text => ColumnBuilder::Text(StringBuilder::new()),
+            ColumnType::Blob => ColumnBuilder::Blob(BinaryBuilder::new()),
+        }
+    }
+
+    fn append(&mut self, row: &rusqlite::Row, index: usize) -> rusqlite::Result<()> {
+        match self {
+            ColumnBuilder::Integer(builder) => builder.append_option(row.get::<_, Option<i64>>(index)?),
+            ColumnBuilder::Real(builder) => builder.append_option(row.get::<_, Option<f64>>(index)?),
+            ColumnBuilder::Text(builder) => builder.append_option(row.get::<_, Option<String>>(index)?),
+            ColumnBuilder::Blob(builder) => builder.append_option(row.get::<_, Option<Vec<u8>>>(index)?),
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> ArrayRef {
+        match self {
+            ColumnBuilder::Integer(mut builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Real(mut builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Text(mut builder) => Arc::new(builder.finish()),
+            ColumnBuilder::Blob(mut builder) => Arc::new(builder.finish()),
+        }
+    }
+}
+
+fn arrow_schema(table: &Table) -> Schema {
+    Schema::new(table.columns.iter()
+        .map(|(name, kind)| {
+            let data_type = match kind {
+                ColumnType::Integer => DataType::Int64,
+                ColumnType::Real => DataType::Float64,
+                ColumnType::Text => DataType::Utf8,
+                ColumnType::Blob => DataType::Binary,
+            };
+            Field::new(*name, data_type, true)
+        })
+        .collect::<Vec<_>>())
+}
+
+fn table_batch(index: &SqliteIndex, table: &Table) -> Result<RecordBatch> {
+    let mut builders: Vec<ColumnBuilder> = table.columns.iter().map(|(_, kind)| ColumnBuilder::new(*kind)).collect();
+    let sql = format!("SELECT {} FROM {} ORDER BY {}", table.column_names().join(", "), table.name, table.columns[0].0);
+    let mut statement = index.connection().prepare(&sql)?;
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        for (column, builder) in builders.iter_mut().enumerate() {
+            builder.append(row, column)?;
+        }
+    }
+    let columns = builders.into_iter().map(ColumnBuilder::finish).collect();
+    Ok(RecordBatch::try_new(Arc::new(arrow_schema(table)), columns)?)
+}
+
+/// Write every table to `dir`, returning the files written
+pub fn export(index: &SqliteIndex, dir: &Path) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let mut written = Vec::with_capacity(TABLES.len());
+    for table in TABLES {
+        let batch = table_batch(index, table)?;
+        let path = dir.join(format!("{}.parquet", table.name));
+        let file = File::create(&path).with_context(|| format!("Failed to create {}", path.display()))?;
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), None)?;
+        writer.write(&batch)?;
+        writer.close()?;
+        info!("🗂️  Exported {} rows of {} to {}", batch.num_rows(), table.name, path.display());
+        written.push(path);
+    }
+    Ok(written)
+}
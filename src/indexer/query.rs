@@ -0,0 +1,174 @@
+//! Explorer queries
+//!
+//! Lookups over the secondary indexes the store keeps: transactions by
+//! sender and recipient, blocks by producer and balances by account. They
+//! let explorers and wallets answer per-account questions without scanning
+//! blocks. Ranges are of block heights, end exclusive.
+
+use super::sqlite::SqliteIndex;
+use crate::types::{Address, BlockHash};
+use anyhow::Result;
+use rusqlite::{Row, params, types::Type};
+use std::ops::Range;
+
+/// A transaction as indexed, with the gas its receipt reports
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedTransaction {
+    pub hash: BlockHash,
+    pub block_number: u64,
+    pub position: u32,
+    pub from: Address,
+    pub to: Address,
+    pub value: u64,
+    pub nonce: u64,
+    pub gas_used: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexedBlock {
+    pub number: u64,
+    pub hash: BlockHash,
+    pub producer: Address,
+    pub timestamp: u64,
+    pub transaction_count: u64,
+    pub gas_used: u64,
+    pub finalized: bool,
+}
+
+/// Balance of an account after a block that changed it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceEntry {
+    pub block_number: u64,
+    pub balance: u64,
+}
+
+impl SqliteIndex {
+    /// Transactions `address` sent or received in `blocks`, oldest first
+    pub fn txs_for_address(&self, address: &Address, blocks: Range<u64>) -> Result<Vec<IndexedTransaction>> {
+        let (start, end) = bounds(&blocks);
+        let mut query = self.connection().prepare_cached(
+            "SELECT t.hash, t.block_number, t.position, t.sender, t.recipient, t.value, t.nonce, r.gas_used \
+             FROM transactions t LEFT JOIN receipts r ON r.transaction_hash = t.hash \
+             WHERE (t.sender = ?1 OR t.recipient = ?1) AND t.block_number >= ?2 AND t.block_number < ?3 \
+             ORDER BY t.block_number, t.position",
+        )?;
+        let rows = query.query_map(params![address.0, start, end], |row| {
+            Ok(IndexedTransaction {
+                hash: BlockHash(fixed(row, 0)?),
+                block_number: row.get(1)?,
+                position: row.get(2)?,
+                from: Address(fixed(row, 3)?),
+                to: Address(fixed(row, 4)?),
+                value: row.get(5)?,
+                nonce: row.get(6)?,
+                gas_used: row.get(7)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Blocks `producer` produced in `blocks`, oldest first
+    pub fn blocks_by_producer(&self, producer: &Address, blocks: Range<u64>) -> Result<Vec<IndexedBlock>> {
+        let (start, end) = bounds(&blocks);
+        let mut query = self.connection().prepare_cached(
+            "SELECT number, hash, producer, timestamp, transaction_count, gas_used, finalized FROM blocks \
+             WHERE producer = ?1 AND number >= ?2 AND number < ?3 ORDER BY number",
+        )?;
+        let rows = query.query_map(params![producer.0, start, end], |row| {
+            Ok(IndexedBlock {
+                number: row.get(0)?,
+                hash: BlockHash(fixed(row, 1)?),
+                producer: Address(fixed(row, 2)?),
+                timestamp: row.get(3)?,
+                transaction_count: row.get(4)?,
+                gas_used: row.get(5)?,
+                finalized: row.get(6)?,
+            })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Balances of `address` after each block in `blocks` that changed it, oldest first
+    pub fn balance_history(&self, address: &Address, blocks: Range<u64>) -> Result<Vec<BalanceEntry>> {
+        let (start, end) = bounds(&blocks);
+        let mut query = self.connection().prepare_cached(
+            "SELECT block_number, balance FROM balances \
+             WHERE address = ?1 AND block_number >= ?2 AND block_number < ?3 ORDER BY block_number",
+        )?;
+        let rows = query.query_map(params![address.0, start, end], |row| {
+            Ok(BalanceEntry { block_number: row.get(0)?, balance: row.get(1)? })
+        })?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    /// Balance of `address` after block `block_number`, or `None` if no
+    /// indexed block up to it changed the balance
+    pub fn balance_at(&self, address: &Address, block_number: u64) -> Result<Option<u64>> {
+        let history = self.balance_history(address, 0..block_number.saturating_add(1))?;
+        Ok(history.last().map(|entry| entry.balance))
+    }
+}
+
+/// SQLite integers are signed, so heights are clamped to `i64::MAX`
+fn bounds(blocks: &Range<u64>) -> (i64, i64) {
+    let clamp = |height: u64| i64::try_from(height).unwrap_or(i64::MAX);
+    (clamp(blocks.start), clamp(blocks.end))
+}
+
+/// A hash or address column
+fn fixed<const N: usize>(row: &Row, index: usize) -> rusqlite::Result<[u8; N]> {
+    let bytes: Vec<u8> = row.get(index)?;
+    bytes.try_into().map_err(|bytes: Vec<u8>| {
+        rusqlite::Error::FromSqlConversionFailure(index, Type::Blob, format!("expected {} bytes, found {}", N, bytes.len()).into())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::engine::{ConsensusEngine, ZkSacConsensusEngine};
+    use crate::crypto::keystore::KeyPair;
+    use crate::types::*;
+
+    #[tokio::test]
+    async fn test_queries_by_account_and_producer() {
+        let key = KeyPair::generate(SignatureType::Ed25519);
+        let producer = key.address();
+        let mut state = WorldState::default();
+        state.accounts.insert(producer, Account::new(1_000_000));
+        let validators = vec![Validator { address: producer, stake: 100, public_key: key.public_key(), performance_score: 1.0, bls_public_key: Vec::new() }];
+        let mut engine = ZkSacConsensusEngine::new(state, validators, ProtocolConfig::default()).unwrap();
+        engine.add_validator_key(key.clone()).unwrap();
+        let mut events = engine.events.subscribe();
+
+        let recipient = Address::new(2);
+        for nonce in 0..3 {
+            engine.add_local_transaction(Transaction::new(producer, recipient, 10, nonce).signed(&key).unwrap()).unwrap();
+            let block = engine.produce_block(producer).await.unwrap();
+            engine.apply_block(block).await.unwrap();
+        }
+        let mut index = SqliteIndex::in_memory().unwrap();
+        while let Ok(event) = events.try_recv() {
+            index.apply(&event).unwrap();
+        }
+
+        let received = index.txs_for_address(&recipient, 0..u64::MAX).unwrap();
+        assert_eq!(received.iter().map(|tx| tx.block_number).collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(received.iter().all(|tx| tx.from == producer && tx.gas_used == Some(21_000)));
+        assert_eq!(index.txs_for_address(&producer, 2..3).unwrap().len(), 1);
+
+        let produced = index.blocks_by_producer(&producer, 1..3).unwrap();
+        assert_eq!(produced.iter().map(|block| block.number).collect::<Vec<_>>(), vec![1, 2]);
+        assert!(produced.iter().all(|block| block.finalized && block.transaction_count == 1));
+        assert!(index.blocks_by_producer(&recipient, 0..u64::MAX).unwrap().is_empty());
+
+        let history = index.balance_history(&recipient, 0..u64::MAX).unwrap();
+        assert_eq!(history, vec![
+            BalanceEntry { block_number: 1, balance: 10 },
+            BalanceEntry { block_number: 2, balance: 20 },
+            BalanceEntry { block_number: 3, balance: 30 },
+        ]);
+        assert_eq!(index.balance_at(&recipient, 2).unwrap(), Some(20));
+        assert_eq!(index.balance_at(&recipient, 0).unwrap(), None);
+    }
+}
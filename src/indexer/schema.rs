@@ -0,0 +1,139 @@
+//! Index tables
+//!
+//! The schema is part of the indexer's interface: columns are only ever
+//! added, and [`SCHEMA_VERSION`] is bumped when they are. SQLite tables and
+//! Parquet files are both generated from [`TABLES`], so the two never drift.
+//! Hashes and addresses are raw bytes; amounts and counts are integers.
+
+pub const SCHEMA_VERSION: i64 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Integer,
+    Real,
+    Text,
+    Blob,
+}
+
+impl ColumnType {
+    fn sql(self) -> &'static str {
+        match self {
+            ColumnType::Integer => "INTEGER",
+            ColumnType::Real => "REAL",
+            ColumnType::Text => "TEXT",
+            ColumnType::Blob => "BLOB",
+        }
+    }
+}
+
+pub struct Table {
+    pub name: &'static str,
+    /// First column is the primary key
+    pub columns: &'static [(&'static str, ColumnType)],
+}
+
+use ColumnType::*;
+
+pub const BLOCKS: Table = Table {
+    name: "blocks",
+    columns: &[
+        ("number", Integer),
+        ("hash", Blob),
+        ("parent_hash", Blob),
+        ("state_root", Blob),
+        ("timestamp", Integer),
+        ("producer", Blob),
+        ("gas_used", Integer),
+        ("gas_limit", Integer),
+        ("transaction_count", Integer),
+        ("proof_type", Text),
+        ("proof_bytes", Integer),
+        ("finalized", Integer),
+    ],
+};
+
+pub const TRANSACTIONS: Table = Table {
+    name: "transactions",
+    columns: &[
+        ("hash", Blob),
+        ("block_number", Integer),
+        ("position", Integer),
+        ("sender", Blob),
+        ("recipient", Blob),
+        ("value", Integer),
+        ("nonce", Integer),
+        ("gas_limit", Integer),
+        ("gas_price", Integer),
+        ("data_bytes", Integer),
+        ("sig_type", Text),
+    ],
+};
+
+pub const RECEIPTS: Table = Table {
+    name: "receipts",
+    columns: &[
+        ("transaction_hash", Blob),
+        ("block_hash", Blob),
+        ("block_number", Integer),
+        ("position", Integer),
+        ("gas_used", Integer),
+    ],
+};
+
+/// Balance of an account after each block that changed it
+pub const BALANCES: Table = Table {
+    name: "balances",
+    columns: &[
+        ("id", Integer),
+        ("address", Blob),
+        ("block_number", Integer),
+        ("balance", Integer),
+    ],
+};
+
+pub const VALIDATOR_EVENTS: Table = Table {
+    name: "validator_events",
+    columns: &[
+        ("id", Integer),
+        ("block_number", Integer),
+        ("kind", Text),
+        ("address", Blob),
+        ("stake", Integer),
+        ("effective_epoch", Integer),
+    ],
+};
+
+pub const PERFORMANCE_SAMPLES: Table = Table {
+    name: "performance_samples",
+    columns: &[
+        ("id", Integer),
+        ("timestamp", Integer),
+        ("block_number", Integer),
+        ("transaction_count", Integer),
+        ("block_production_time_ms", Integer),
+        ("proof_generation_time_ms", Integer),
+        ("validation_time_ms", Integer),
+        ("transactions_per_second", Real),
+        ("proof_size_bytes", Integer),
+        ("memory_usage_mb", Real),
+        ("cpu_usage_percent", Real),
+    ],
+};
+
+pub const TABLES: &[Table] = &[BLOCKS, TRANSACTIONS, RECEIPTS, BALANCES, VALIDATOR_EVENTS, PERFORMANCE_SAMPLES];
+
+impl Table {
+    pub fn create_sql(&self) -> String {
+        let columns: Vec<String> = self.columns.iter().enumerate()
+            .map(|(index, (name, kind))| {
+                let key = if index == 0 { " PRIMARY KEY" } else { "" };
+                format!("{} {}{}", name, kind.sql(), key)
+            })
+            .collect();
+        format!("CREATE TABLE IF NOT EXISTS {} ({})", self.name, columns.join(", "))
+    }
+
+    pub fn column_names(&self) -> Vec<&'static str> {
+        self.columns.iter().map(|(name, _)| *name).collect()
+    }
+}
@@ -0,0 +1,207 @@
+//! SQLite index store
+
+use super::schema::{SCHEMA_VERSION, TABLES};
+use crate::consensus::events::{BalanceChange, ConsensusEvent, TransactionReceipt, ValidatorEvent};
+use crate::performance::SystemBenchmark;
+use crate::types::{Block, ProofType, SignatureType};
+use anyhow::{Context, Result, bail};
+use rusqlite::{Connection, Transaction as SqlTransaction, params};
+use std::path::Path;
+
+pub struct SqliteIndex {
+    conn: Connection,
+}
+
+impl SqliteIndex {
+    /// Open or create the index at `path`
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open index {}", path.display()))?;
+        // WAL lets explorers read while the indexer writes
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        Self::init(conn)
+    }
+
+    pub fn in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version > SCHEMA_VERSION {
+            bail!("Index schema version {} is newer than this indexer ({})", version, SCHEMA_VERSION);
+        }
+        for table in TABLES {
+            conn.execute(&table.create_sql(), [])?;
+        }
+        conn.execute("CREATE INDEX IF NOT EXISTS transactions_by_block ON transactions (block_number)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS transactions_by_sender ON transactions (sender)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS transactions_by_recipient ON transactions (recipient)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS blocks_by_producer ON blocks (producer)", [])?;
+        conn.execute("CREATE INDEX IF NOT EXISTS balances_by_address ON balances (address, block_number)", [])?;
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+        Ok(Self { conn })
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Write one event in its own transaction
+    pub fn apply(&mut self, event: &ConsensusEvent) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        match event {
+            ConsensusEvent::BlockApplied { block, receipts, balances } => {
+                insert_block(&tx, block, receipts)?;
+                insert_balances(&tx, block.header.block_number, balances)?;
+            }
+            ConsensusEvent::Finalized { block_number, .. } => {
+                tx.execute("UPDATE blocks SET finalized = 1 WHERE number <= ?1 AND finalized = 0", params![block_number])?;
+            }
+            ConsensusEvent::Reorged { fork_height, .. } => {
+                tx.execute("DELETE FROM blocks WHERE number > ?1", params![fork_height])?;
+                for table in ["transactions", "receipts", "balances", "validator_events"] {
+                    tx.execute(&format!("DELETE FROM {} WHERE block_number > ?1", table), params![fork_height])?;
+                }
+            }
+            ConsensusEvent::Validator { block_number, event } => insert_validator_event(&tx, *block_number, event)?,
+            ConsensusEvent::Performance(sample) => insert_sample(&tx, sample)?,
+            ConsensusEvent::Governance { .. } | ConsensusEvent::TransactionPooled { .. } => {}
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Highest indexed block, so a restarted indexer knows where it stands
+    pub fn height(&self) -> Result<u64> {
+        let height: Option<u64> = self.conn.query_row("SELECT MAX(number) FROM blocks", [], |row| row.get(0))?;
+        Ok(height.unwrap_or(0))
+    }
+
+    pub fn count(&self, table: &str) -> Result<u64> {
+        if !TABLES.iter().any(|known| known.name == table) {
+            bail!("Unknown index table {}", table);
+        }
+        Ok(self.conn.query_row(&format!("SELECT COUNT(*) FROM {}", table), [], |row| row.get(0))?)
+    }
+}
+
+fn proof_type_name(proof_type: &ProofType) -> &'static str {
+    match proof_type {
+        ProofType::SP1 => "sp1",
+        ProofType::Risc0 => "risc0",
+        ProofType::Plonky3 => "plonky3",
+        ProofType::Deferred => "deferred",
+    }
+}
+
+fn sig_type_name(sig_type: &SignatureType) -> &'static str {
+    match sig_type {
+        SignatureType::Ed25519 => "ed25519",
+        SignatureType::Secp256k1 => "secp256k1",
+        SignatureType::PostQuantum => "post_quantum",
+    }
+}
+
+fn insert_block(tx: &SqlTransaction, block: &Block, receipts: &[TransactionReceipt]) -> Result<()> {
+    let header = &block.header;
+    // Replayed blocks replace their earlier rows
+    tx.execute(
+        "INSERT OR REPLACE INTO blocks VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, 0)",
+        params![
+            header.block_number,
+            header.hash().0,
+            header.previous_hash.0,
+            header.state_root.0,
+            header.timestamp,
+            header.producer.0,
+            header.gas_used,
+            header.gas_limit,
+            block.transactions.len() as u64,
+            proof_type_name(&block.recursive_proof.proof_type),
+            block.recursive_proof.proof_data.len() as u64,
+        ],
+    )?;
+
+    let mut insert_tx = tx.prepare_cached(
+        "INSERT OR REPLACE INTO transactions VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+    )?;
+    for (position, transaction) in block.transactions.iter().enumerate() {
+        insert_tx.execute(params![
+            transaction.hash().0,
+            header.block_number,
+            position as u64,
+            transaction.from.0,
+            transaction.to.0,
+            transaction.value,
+            transaction.nonce,
+            transaction.gas_limit,
+            transaction.gas_price,
+            transaction.data.len() as u64,
+            sig_type_name(&transaction.sig_type),
+        ])?;
+    }
+
+    let mut insert_receipt = tx.prepare_cached("INSERT OR REPLACE INTO receipts VALUES (?1, ?2, ?3, ?4, ?5)")?;
+    for receipt in receipts {
+        insert_receipt.execute(params![
+            receipt.transaction_hash.0,
+            receipt.block_hash.0,
+            receipt.block_number,
+            receipt.index,
+            receipt.gas_used,
+        ])?;
+    }
+    Ok(())
+}
+
+fn insert_balances(tx: &SqlTransaction, block_number: u64, balances: &[BalanceChange]) -> Result<()> {
+    // Replayed blocks replace their earlier rows
+    tx.execute("DELETE FROM balances WHERE block_number = ?1", params![block_number])?;
+    let mut insert = tx.prepare_cached("INSERT INTO balances (address, block_number, balance) VALUES (?1, ?2, ?3)")?;
+    for change in balances {
+        insert.execute(params![change.address.0, block_number, change.balance])?;
+    }
+    Ok(())
+}
+
+fn insert_validator_event(tx: &SqlTransaction, block_number: u64, event: &ValidatorEvent) -> Result<()> {
+    let (kind, address, stake, effective_epoch) = match event {
+        ValidatorEvent::Registered { address, stake } => ("registered", address, Some(*stake), None),
+        ValidatorEvent::KeyRotated { address, effective_epoch } => ("key_rotated", address, None, Some(*effective_epoch)),
+        // The stake column holds the amount taken
+        ValidatorEvent::Slashed { address, amount, .. } => ("slashed", address, Some(*amount), None),
+        ValidatorEvent::Removed { address } => ("removed", address, None, None),
+        ValidatorEvent::Staked { address, stake, activation_epoch } => ("staked", address, Some(*stake), Some(*activation_epoch)),
+        ValidatorEvent::Unstaked { address, exit_epoch } => ("unstaked", address, None, Some(*exit_epoch)),
+        ValidatorEvent::Activated { address, stake } => ("activated", address, Some(*stake), None),
+        ValidatorEvent::Exited { address } => ("exited", address, None, None),
+    };
+    tx.execute(
+        "INSERT INTO validator_events (block_number, kind, address, stake, effective_epoch) VALUES (?1, ?2, ?3, ?4, ?5)",
+        params![block_number, kind, address.0, stake, effective_epoch],
+    )?;
+    Ok(())
+}
+
+fn insert_sample(tx: &SqlTransaction, sample: &SystemBenchmark) -> Result<()> {
+    let metrics = &sample.metrics;
+    tx.execute(
+        "INSERT INTO performance_samples (timestamp, block_number, transaction_count, block_production_time_ms, \
+         proof_generation_time_ms, validation_time_ms, transactions_per_second, proof_size_bytes, memory_usage_mb, \
+         cpu_usage_percent) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+        params![
+            sample.timestamp,
+            sample.block_number,
+            sample.transaction_count,
+            metrics.block_production_time_ms,
+            metrics.proof_generation_time_ms,
+            metrics.validation_time_ms,
+            metrics.transactions_per_second,
+            metrics.proof_size_bytes as u64,
+            metrics.memory_usage_mb,
+            metrics.cpu_usage_percent,
+        ],
+    )?;
+    Ok(())
+}
@@ -5,9 +5,25 @@ pub mod zkvm;
 pub mod performance;
 pub mod serialization;
 pub mod async_utils;
+pub mod mempool;
+pub mod node;
+pub mod rpc;
+pub mod simulation;
+pub mod state;
+pub mod light_client;
+pub mod bridge;
+pub mod da;
+pub mod conformance;
+pub mod fault;
+pub mod error;
+#[cfg(feature = "indexer")]
+pub mod indexer;
+#[cfg(feature = "proto")]
+pub mod proto;
 
 pub use types::*;
 pub use consensus::engine::{ZkSacConsensusEngine, ConsensusEngine};
+pub use error::EngineError;
 
 // Re-export commonly used items
 pub use anyhow::Result;
@@ -76,6 +92,8 @@ mod tests {
             value: 1000,
             data: vec![1, 2, 3, 4],
             nonce: 42,
+            chain_id: types::DEFAULT_CHAIN_ID,
+            access_list: None,
             gas_limit: 21000,
             gas_price: 20,
             signature: vec![0; 64],
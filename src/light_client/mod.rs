@@ -0,0 +1,488 @@
+//! Light client: follows the chain from headers and proofs alone
+//!
+//! A [`LightClient`] keeps a bounded window of finalized headers with their
+//! recursive proofs and the current validator set with its commitment. A
+//! header is accepted when it extends the head, carries a quorum certificate
+//! signed by more than 2/3 of the stake, individually or through one BLS
+//! aggregate, and its recursive proof verifies. Proofs are checked by a
+//! [`BackendProofVerifier`] over a zkVM prover backend, by default the one
+//! whose feature is enabled. Balances are answered from
+//! [`AccountProof`]s against an accepted header's state root, and transaction
+//! inclusion from [`MerkleProof`]s against its transaction root, so the client
+//! needs neither block bodies, storage nor execution.
+
+use crate::crypto::bls::{self, BlsError};
+use crate::crypto::hash::{MerkleProof, keccak256_hash};
+use crate::crypto::keystore::{KeyPair, verify_signature};
+use crate::serialization::canonical::CanonicalEncode;
+use crate::state::AccountProof;
+use crate::types::*;
+use crate::zkvm::{ProofError, ProverBackend, check_committed_output, prover_backend};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::Arc;
+use thiserror::Error;
+use tracing::{debug, info};
+
+const HEADER_VOTE_DOMAIN: &[u8] = b"zk-sac/header-vote/v2";
+const VALIDATOR_SET_DOMAIN: &[u8] = b"zk-sac/validator-set/v2";
+const VALIDATOR_SET_VOTE_DOMAIN: &[u8] = b"zk-sac/validator-set-vote/v1";
+const DEFAULT_MAX_HEADERS: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum LightClientError {
+    #[error("header {got} does not extend head {head} (expected number {}, parent {expected_parent:?})", .head + 1)]
+    NotNextHeader { head: u64, got: u64, expected_parent: BlockHash },
+    #[error("certificate is for {certified:?}, header hashes to {header:?}")]
+    CertificateMismatch { certified: BlockHash, header: BlockHash },
+    #[error("signature from {0:?}, which is not in the validator set")]
+    UnknownValidator(Address),
+    #[error("invalid signature from {0:?}")]
+    InvalidSignature(Address),
+    #[error("invalid aggregate signature: {0}")]
+    InvalidAggregate(BlsError),
+    #[error("quorum not reached: {signed} of {total} stake signed, more than 2/3 required")]
+    InsufficientQuorum { signed: u64, total: u64 },
+    #[error("recursive proof rejected: {0}")]
+    InvalidProof(String),
+    #[error("no accepted header at height {0}")]
+    UnknownHeader(u64),
+    #[error("transaction {0:?} is not included in the header's transactions")]
+    TransactionNotIncluded(BlockHash),
+    #[error(transparent)]
+    StateProof(#[from] crate::state::StateProofError),
+}
+
+/// Bytes a validator signs to vote for a header; the height is signed too,
+/// so a vote cannot be relabeled as one for another height
+pub fn header_signing_bytes(block_number: u64, header_hash: &BlockHash) -> Vec<u8> {
+    let mut bytes = HEADER_VOTE_DOMAIN.to_vec();
+    bytes.extend_from_slice(&block_number.to_be_bytes());
+    bytes.extend_from_slice(&header_hash.0);
+    bytes
+}
+
+/// Bytes a validator signs to approve the next validator set
+pub fn validator_set_signing_bytes(commitment: &BlockHash) -> Vec<u8> {
+    let mut bytes = VALIDATOR_SET_VOTE_DOMAIN.to_vec();
+    bytes.extend_from_slice(&commitment.0);
+    bytes
+}
+
+/// Vote for `header` with `key`
+pub fn sign_header(key: &KeyPair, stake_weight: u64, header: &BlockHeader) -> anyhow::Result<ValidatorSignature> {
+    Ok(ValidatorSignature {
+        validator_address: key.address(),
+        stake_weight,
+        signature: key.sign(&header_signing_bytes(header.block_number, &header.hash()))?,
+        sig_type: key.sig_type().clone(),
+    })
+}
+
+/// Hash of the validators' addresses, stakes and public keys, BLS keys
+/// included, in set order
+pub fn validator_set_commitment(set: &ValidatorSet) -> BlockHash {
+    let mut bytes = VALIDATOR_SET_DOMAIN.to_vec();
+    for validator in &set.validators {
+        validator.address.encode_canonical(&mut bytes);
+        validator.stake.encode_canonical(&mut bytes);
+        validator.public_key.encode_canonical(&mut bytes);
+        validator.bls_public_key.encode_canonical(&mut bytes);
+    }
+    BlockHash(keccak256_hash(&bytes))
+}
+
+/// Checks a block's recursive proof against its header
+pub trait ProofVerifier: Send + Sync {
+    fn verify(&self, header: &BlockHeader, proof: &ZkProof) -> Result<(), String>;
+}
+
+/// Checks proofs with a zkVM prover backend: a proof must be the backend's
+/// type, commit to the header's transition and verify
+pub struct BackendProofVerifier {
+    backend: Arc<dyn ProverBackend>,
+}
+
+impl BackendProofVerifier {
+    pub fn new(backend: Arc<dyn ProverBackend>) -> Self {
+        Self { backend }
+    }
+
+    /// Verifier over the backend `config` selects
+    pub fn from_config(config: &ZkVMConfig) -> Result<Self, ProofError> {
+        Ok(Self::new(prover_backend(config)?.into()))
+    }
+}
+
+impl ProofVerifier for BackendProofVerifier {
+    fn verify(&self, header: &BlockHeader, proof: &ZkProof) -> Result<(), String> {
+        let block_number = header.block_number;
+        if matches!(proof.proof_type, ProofType::Deferred) {
+            return Err(format!("proof for block {} is still deferred", block_number));
+        }
+        let expected = self.backend.proof_type();
+        if proof.proof_type != expected {
+            return Err(ProofError::WrongProofType { block_number, found: proof.proof_type.clone(), expected }.to_string());
+        }
+        if proof.proof_data.is_empty() {
+            return Err(format!("block {} has an empty proof", block_number));
+        }
+        // Proofs committing to nothing, as mock proofs do, can only be held
+        // to the public inputs attached beside them
+        match self.backend.committed_output(&proof.proof_data).map_err(|e| e.to_string())? {
+            Some(output) => check_committed_output(&output, &header.prev_state_root, header, None)
+                .map_err(|e| e.to_string())?,
+            None if proof.public_inputs != header.public_inputs().encode() => {
+                return Err(ProofError::PublicInputsMismatch(block_number).to_string());
+            }
+            None => {}
+        }
+        // Backends verify on the calling thread, so nothing needs a runtime
+        match futures::executor::block_on(self.backend.verify_proof(&proof.proof_data)) {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(ProofError::Invalid(block_number).to_string()),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+/// Verifier a light client starts with: the backend whose feature is
+/// enabled, or the default backend's mock proofs without one
+fn default_verifier() -> Box<dyn ProofVerifier> {
+    let backend = if cfg!(feature = "risc0") {
+        ProverBackendKind::Risc0
+    } else if cfg!(feature = "sp1") {
+        ProverBackendKind::Sp1
+    } else if cfg!(feature = "plonky3") {
+        ProverBackendKind::Plonky3
+    } else {
+        ProverBackendKind::default()
+    };
+    let config = ZkVMConfig { backend, ..ZkVMConfig::default() };
+    let verifier = BackendProofVerifier::from_config(&config)
+        .expect("prover backends are built without fallible setup");
+    Box::new(verifier)
+}
+
+/// Accepts anything present and not deferred, for tests that do not
+/// exercise proofs
+#[cfg(test)]
+#[derive(Debug, Clone, Default)]
+pub struct MockProofVerifier;
+
+#[cfg(test)]
+impl ProofVerifier for MockProofVerifier {
+    fn verify(&self, header: &BlockHeader, proof: &ZkProof) -> Result<(), String> {
+        if matches!(proof.proof_type, ProofType::Deferred) {
+            return Err(format!("proof for block {} is still deferred", header.block_number));
+        }
+        if proof.proof_data.is_empty() {
+            return Err(format!("block {} has an empty proof", header.block_number));
+        }
+        Ok(())
+    }
+}
+
+/// Validator votes for one header
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QuorumCertificate {
+    pub block_hash: BlockHash,
+    pub signatures: Vec<ValidatorSignature>,
+    #[serde(default)]
+    pub aggregate: Option<AggregateSignature>,
+}
+
+/// Everything a light client needs to advance by one block
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HeaderUpdate {
+    pub header: BlockHeader,
+    pub certificate: QuorumCertificate,
+    pub recursive_proof: ZkProof,
+}
+
+impl HeaderUpdate {
+    /// Strip a full block down to its header, votes and proof
+    pub fn from_block(block: &Block) -> Self {
+        Self {
+            header: block.header.clone(),
+            certificate: QuorumCertificate {
+                block_hash: block.header.hash(),
+                signatures: block.validator_signatures.clone(),
+                aggregate: block.aggregate_signature.clone(),
+            },
+            recursive_proof: block.recursive_proof.clone(),
+        }
+    }
+}
+
+/// Accepted header and the recursive proof it was accepted with
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrustedHeader {
+    pub header: BlockHeader,
+    /// `None` for a checkpoint, which is trusted without one
+    pub recursive_proof: Option<ZkProof>,
+}
+
+pub struct LightClient {
+    headers: VecDeque<TrustedHeader>,
+    max_headers: usize,
+    validators: ValidatorSet,
+    commitment: BlockHash,
+    verifier: Box<dyn ProofVerifier>,
+}
+
+impl LightClient {
+    /// Start from genesis with the genesis validator set
+    pub fn new(validators: ValidatorSet) -> Self {
+        let commitment = validator_set_commitment(&validators);
+        Self {
+            headers: VecDeque::new(),
+            max_headers: DEFAULT_MAX_HEADERS,
+            validators,
+            commitment,
+            verifier: default_verifier(),
+        }
+    }
+
+    /// Start from a header obtained out of band, e.g. a published checkpoint
+    pub fn from_checkpoint(header: BlockHeader, validators: ValidatorSet) -> Self {
+        let mut client = Self::new(validators);
+        client.headers.push_back(TrustedHeader { header, recursive_proof: None });
+        client
+    }
+
+    pub fn with_verifier(mut self, verifier: Box<dyn ProofVerifier>) -> Self {
+        self.verifier = verifier;
+        self
+    }
+
+    /// Number of recent headers kept; older ones are forgotten
+    pub fn with_max_headers(mut self, max_headers: usize) -> Self {
+        self.max_headers = max_headers.max(1);
+        self
+    }
+
+    pub fn head(&self) -> Option<&BlockHeader> {
+        self.headers.back().map(|trusted| &trusted.header)
+    }
+
+    pub fn height(&self) -> u64 {
+        self.head().map_or(0, |header| header.block_number)
+    }
+
+    pub fn header(&self, number: u64) -> Option<&BlockHeader> {
+        self.trusted(number).map(|trusted| &trusted.header)
+    }
+
+    /// Recursive proof accepted header `number` was verified with, e.g. to
+    /// hand to another light client
+    pub fn recursive_proof(&self, number: u64) -> Option<&ZkProof> {
+        self.trusted(number)?.recursive_proof.as_ref()
+    }
+
+    fn trusted(&self, number: u64) -> Option<&TrustedHeader> {
+        self.headers.iter().find(|trusted| trusted.header.block_number == number)
+    }
+
+    pub fn validator_set_commitment(&self) -> BlockHash {
+        self.commitment
+    }
+
+    /// Check `update` without applying it
+    pub fn verify_update(&self, update: &HeaderUpdate) -> Result<(), LightClientError> {
+        let header = &update.header;
+        let expected_parent = self.head().map_or(BlockHash::zero(), BlockHeader::hash);
+        if header.block_number != self.height() + 1 || header.previous_hash != expected_parent {
+            return Err(LightClientError::NotNextHeader {
+                head: self.height(),
+                got: header.block_number,
+                expected_parent,
+            });
+        }
+
+        let hash = header.hash();
+        if update.certificate.block_hash != hash {
+            return Err(LightClientError::CertificateMismatch { certified: update.certificate.block_hash, header: hash });
+        }
+        self.verify_quorum(&header_signing_bytes(header.block_number, &hash), &update.certificate.signatures, update.certificate.aggregate.as_ref())?;
+
+        self.verifier.verify(header, &update.recursive_proof)
+            .map_err(LightClientError::InvalidProof)
+    }
+
+    /// Verify and append the header in `update`
+    pub fn apply_update(&mut self, update: HeaderUpdate) -> Result<(), LightClientError> {
+        self.verify_update(&update)?;
+        debug!("🪶 Light client accepted header {}", update.header.block_number);
+        self.headers.push_back(TrustedHeader { header: update.header, recursive_proof: Some(update.recursive_proof) });
+        while self.headers.len() > self.max_headers {
+            self.headers.pop_front();
+        }
+        Ok(())
+    }
+
+    /// Switch to `next`, which more than 2/3 of the current stake must have
+    /// signed by voting for its commitment
+    pub fn rotate_validator_set(&mut self, next: ValidatorSet, signatures: &[ValidatorSignature]) -> Result<(), LightClientError> {
+        let commitment = validator_set_commitment(&next);
+        self.verify_quorum(&validator_set_signing_bytes(&commitment), signatures, None)?;
+        info!("🪶 Light client rotated to {} validators", next.validators.len());
+        self.validators = next;
+        self.commitment = commitment;
+        Ok(())
+    }
+
+    /// Balance of the proven account as of accepted header `block_number`
+    pub fn verify_balance(&self, block_number: u64, proof: &AccountProof) -> Result<u64, LightClientError> {
+        let header = self.header(block_number).ok_or(LightClientError::UnknownHeader(block_number))?;
+        proof.verify(&header.state_root)?;
+        Ok(proof.leaf.balance)
+    }
+
+    /// Check that `transaction` is part of accepted header `block_number`
+    pub fn verify_transaction(&self, block_number: u64, transaction: &Transaction, proof: &MerkleProof) -> Result<(), LightClientError> {
+        let header = self.header(block_number).ok_or(LightClientError::UnknownHeader(block_number))?;
+        let hash = transaction.hash();
+        if proof.verify(&hash.0, &header.merkle_root.0) {
+            Ok(())
+        } else {
+            Err(LightClientError::TransactionNotIncluded(hash))
+        }
+    }
+
+    fn verify_quorum(
+        &self,
+        message: &[u8],
+        signatures: &[ValidatorSignature],
+        aggregate: Option<&AggregateSignature>,
+    ) -> Result<(), LightClientError> {
+        let total: u64 = self.validators.validators.iter().map(|validator| validator.stake).sum();
+        let mut seen = HashSet::new();
+        let mut signed = 0u64;
+
+        for vote in signatures {
+            let validator = self.validators.validators.iter()
+                .find(|validator| validator.address == vote.validator_address)
+                .ok_or(LightClientError::UnknownValidator(vote.validator_address))?;
+            if !seen.insert(vote.validator_address) {
+                continue;
+            }
+            verify_signature(&vote.sig_type, &validator.public_key, message, &vote.signature)
+                .map_err(|_| LightClientError::InvalidSignature(vote.validator_address))?;
+            // Count the stake the client knows, not the weight the vote claims
+            signed = signed.saturating_add(validator.stake);
+        }
+        if let Some(aggregate) = aggregate {
+            let signers = bls::verify_aggregate_vote(&self.validators, message, aggregate)
+                .map_err(LightClientError::InvalidAggregate)?;
+            for validator in signers {
+                if seen.insert(validator.address) {
+                    signed = signed.saturating_add(validator.stake);
+                }
+            }
+        }
+
+        if u128::from(signed) * 3 > u128::from(total) * 2 {
+            Ok(())
+        } else {
+            Err(LightClientError::InsufficientQuorum { signed, total })
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::account_proof;
+
+    fn validators(keys: &[KeyPair]) -> ValidatorSet {
+        ValidatorSet::new(keys.iter()
+            .map(|key| Validator { address: key.address(), stake: 100, public_key: key.public_key(), performance_score: 1.0, bls_public_key: Vec::new() })
+            .collect())
+    }
+
+    fn header(number: u64, previous_hash: BlockHash, state_root: BlockHash) -> BlockHeader {
+        BlockHeader {
+            previous_hash,
+            merkle_root: BlockHash::zero(),
+            prev_state_root: BlockHash::zero(),
+            state_root,
+            timestamp: number * 4,
+            block_number: number,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            producer: Address::new(1),
+            extra_data: Vec::new(),
+            randomness: RandomnessProof::default(),
+        }
+    }
+
+    fn update(header: BlockHeader, signers: &[KeyPair]) -> HeaderUpdate {
+        HeaderUpdate {
+            certificate: QuorumCertificate {
+                block_hash: header.hash(),
+                signatures: signers.iter().map(|key| sign_header(key, 100, &header).unwrap()).collect(),
+                aggregate: None,
+            },
+            recursive_proof: ZkProof {
+                proof_data: vec![0; 32],
+                public_inputs: header.public_inputs().encode(),
+                verification_key: vec![],
+                proof_type: ProofType::Risc0,
+            },
+            header,
+        }
+    }
+
+    #[test]
+    fn test_follows_quorum_signed_headers_and_proves_balances() {
+        let keys: Vec<KeyPair> = (0..4).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+        let mut client = LightClient::new(validators(&keys));
+
+        let mut state = WorldState::default();
+        state.accounts.insert(Address::new(7), Account::new(1234));
+        state.accounts.insert(Address::new(8), Account::new(1));
+        let first = header(1, BlockHash::zero(), state.compute_state_root());
+
+        let err = client.apply_update(update(first.clone(), &keys[..2])).unwrap_err();
+        assert_eq!(err, LightClientError::InsufficientQuorum { signed: 200, total: 400 });
+        client.apply_update(update(first.clone(), &keys[..3])).unwrap();
+
+        let orphan = header(2, BlockHash::random(), state.compute_state_root());
+        assert!(matches!(client.apply_update(update(orphan, &keys)), Err(LightClientError::NotNextHeader { .. })));
+
+        let proof = account_proof(&state, &Address::new(7)).unwrap();
+        assert_eq!(client.verify_balance(1, &proof), Ok(1234));
+        let mut forged = proof;
+        forged.leaf.balance = 1_000_000;
+        assert!(client.verify_balance(1, &forged).is_err());
+
+        assert_eq!(client.recursive_proof(1).map(|proof| proof.proof_data.len()), Some(32));
+        assert!(client.recursive_proof(2).is_none());
+
+        let outsider = KeyPair::generate(SignatureType::Ed25519);
+        let second = header(2, first.hash(), state.compute_state_root());
+        let mut forged_update = update(second.clone(), &keys[..2]);
+        forged_update.certificate.signatures.push(sign_header(&outsider, 100, &second).unwrap());
+        assert_eq!(client.apply_update(forged_update), Err(LightClientError::UnknownValidator(outsider.address())));
+    }
+
+    #[test]
+    fn test_proofs_are_checked_by_the_prover_backend() {
+        let keys: Vec<KeyPair> = (0..3).map(|_| KeyPair::generate(SignatureType::Ed25519)).collect();
+        let mut client = LightClient::new(validators(&keys));
+        let first = header(1, BlockHash::zero(), BlockHash([1; 32]));
+
+        let mut other_type = update(first.clone(), &keys);
+        other_type.recursive_proof.proof_type = ProofType::SP1;
+        assert!(matches!(client.apply_update(other_type), Err(LightClientError::InvalidProof(_))));
+
+        // Mock proofs commit to nothing, so they are held to their public inputs
+        let mut other_block = update(first.clone(), &keys);
+        other_block.recursive_proof.public_inputs = header(1, BlockHash::zero(), BlockHash([2; 32])).public_inputs().encode();
+        assert!(matches!(client.apply_update(other_block), Err(LightClientError::InvalidProof(_))));
+
+        client.apply_update(update(first, &keys)).unwrap();
+        assert_eq!(client.height(), 1);
+    }
+}
@@ -1,4 +1,6 @@
 use zk_sac_engine::consensus::engine::{ZkSacConsensusEngine, ConsensusEngine};
+use zk_sac_engine::consensus::genesis::{GenesisAccount, GenesisConfig, GenesisValidator};
+use zk_sac_engine::crypto::keystore::KeyPair;
 use zk_sac_engine::types::*;
 use std::collections::HashMap;
 
@@ -9,102 +11,64 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logging
     tracing_subscriber::fmt::init();
     
-    // Create genesis state
-    let mut accounts = HashMap::new();
-    accounts.insert(
-        Address::new(1),
-        Account {
-            balance: 1_000_000,
-            nonce: 0,
-            code: Vec::new(),
-            storage: HashMap::new(),
-        }
-    );
-    
-    let genesis_state = WorldState {
-        accounts,
-        global_nonce: 0,
-        state_root: BlockHash::zero(),
-        block_number: 0,
+    // Deterministic demo keys; every account is also a validator
+    let keys: Vec<KeyPair> = (1..=3u8)
+        .map(|i| KeyPair::from_secret(SignatureType::Ed25519, &[i; 32]))
+        .collect::<Result<_, _>>()?;
+
+    // Genesis funds the first key; a real chain loads this from a file
+    // with `ZkSacConsensusEngine::from_genesis_file`
+    let stakes = [32_000_000_000, 16_000_000_000, 8_000_000_000];
+    let genesis = GenesisConfig {
+        chain_id: None,
+        protocol: Some(ProtocolConfig::default()),
+        accounts: vec![GenesisAccount { address: keys[0].address(), balance: 1_000_000 }],
+        validators: keys.iter().zip(stakes)
+            .map(|(key, stake)| GenesisValidator {
+                address: key.address(),
+                stake,
+                public_key: key.public_key(),
+                bls_public_key: Vec::new(),
+            })
+            .collect(),
     };
-    
-    // Create validators
-    let validators = vec![
-        Validator {
-            address: Address::new(1),
-            stake: 32_000_000_000,
-            public_key: vec![1; 32],
-            performance_score: 1.0,
-        },
-        Validator {
-            address: Address::new(2), 
-            stake: 16_000_000_000,
-            public_key: vec![2; 32],
-            performance_score: 0.9,
-        },
-        Validator {
-            address: Address::new(3),
-            stake: 8_000_000_000,
-            public_key: vec![3; 32],
-            performance_score: 0.8,
-        },
-    ];
-    
+
     // Create consensus engine
-    let mut engine = ZkSacConsensusEngine::new(
-        genesis_state, 
-        validators, 
-        ProtocolConfig::default()
-    )?;
+    let mut engine = ZkSacConsensusEngine::from_genesis(&genesis)?;
+    for key in &keys {
+        engine.add_validator_key(key.clone())?;
+    }
     
     println!("✅ Consensus engine initialized");
     
     // Create test transactions
     let transactions = vec![
-        Transaction {
-            from: Address::new(1),
-            to: Address::new(2),
-            value: 1000,
-            data: vec![],
-            gas_limit: 21000,
-            nonce: 0,
-            signature: vec![0; 64],
-            sig_type: SignatureType::Ed25519,
-        },
-        Transaction {
-            from: Address::new(2),
-            to: Address::new(3),
-            value: 500,
-            data: vec![],
-            gas_limit: 21000,
-            nonce: 0,
-            signature: vec![0; 64],
-            sig_type: SignatureType::Ed25519,
-        },
+        Transaction::new(keys[0].address(), keys[1].address(), 1000, 0).signed(&keys[0])?,
+        Transaction::new(keys[1].address(), keys[2].address(), 500, 0).signed(&keys[1])?,
     ];
     
     // Add transactions to pending pool
     for tx in transactions {
-        engine.pending_transactions.push(tx);
+        engine.add_local_transaction(tx)?;
     }
     
-    println!("📝 Added {} transactions to pool", engine.pending_transactions.len());
+    println!("📝 Added {} transactions to pool", engine.mempool.len());
     
     // Select block producer
     let producer = engine.select_block_producer(1)?;
     println!("🎯 Selected block producer: {:?}", producer);
     
     // Produce a block
-    let block = engine.produce_block(producer)?;
+    let block = engine.produce_block(producer).await?;
     println!("🔨 Produced block {}", block.header.block_number);
     
     // Validate the block
-    let is_valid = engine.validate_block(&block)?;
+    let is_valid = engine.validate_block(&block).await?;
     println!("🔍 Block validation result: {}", is_valid);
     
     // Apply the block to the chain
     if is_valid {
-        engine.apply_block(block)?;
+        engine.apply_block(block).await?;
         println!("✅ Block applied to chain");
         println!("📊 Total blocks in chain: {}", engine.blocks.len());
     }
@@ -149,7 +113,7 @@ mod tests {
         
         // Produce block
         let producer = engine.select_block_producer(1)?;
-        let mut block = engine.produce_block(producer)?;
+        let mut block = engine.produce_block(producer).await?;
         
         // Collect signatures and verify
         engine.collect_validator_signatures(&mut block)?;
@@ -191,7 +155,7 @@ mod tests {
             engine.add_transaction(tx)?;
             
             let producer = engine.select_block_producer(i + 1)?;
-            let mut block = engine.produce_block(producer)?;
+            let mut block = engine.produce_block(producer).await?;
             engine.collect_validator_signatures(&mut block)?;
             engine.verify_and_add_block(block)?;
         }
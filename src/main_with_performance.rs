@@ -1,10 +1,19 @@
 use zk_sac_engine::consensus::engine::{ZkSacConsensusEngine, ConsensusEngine};
+use zk_sac_engine::crypto::keystore::KeyPair;
+use zk_sac_engine::crypto::signatures::PostQuantumSigner;
 use zk_sac_engine::types::*;
-use zk_sac_engine::performance::{PerformanceMonitor, PerformanceTest};
+use zk_sac_engine::performance::{Operation, PerformanceMonitor, PerformanceTest, SoakConfig};
+use zk_sac_engine::zkvm::programs::guest_program::StateTransitionInput;
 use zk_sac_engine::zkvm::real_proofs::RealZKProver;
+use zk_sac_engine::zkvm::state_transition_input;
 use std::collections::HashMap;
 use tracing::{info, error};
 
+#[cfg(feature = "alloc-tracking")]
+#[global_allocator]
+static ALLOC: zk_sac_engine::performance::alloc::TrackingAllocator =
+    zk_sac_engine::performance::alloc::TrackingAllocator;
+
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Initialize logging
@@ -13,28 +22,36 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     println!("🚀 ZK-SAC Engine - Performance Demonstration");
     println!("============================================");
-    
+
+    // On-demand CPU profiles, e.g. ZKSAC_PROFILE_DIR=./profiles then `kill -USR1 <pid>`
+    #[cfg(all(feature = "profiling", unix))]
+    if let Ok(dir) = std::env::var("ZKSAC_PROFILE_DIR") {
+        zk_sac_engine::performance::profiling::spawn_signal_trigger(
+            zk_sac_engine::performance::profiling::ProfileConfig::default().with_output_dir(dir),
+        )?;
+    }
+
     // 1. Performance Monitor Test
     println!("\n📊 Phase 1: Performance Monitoring System");
     let mut monitor = PerformanceMonitor::new();
     
     // Simulate various operations
     for i in 1..=5 {
-        monitor.start_timer("consensus_cycle");
+        let cycle_timer = monitor.time(Operation::ConsensusCycle);
         
-        monitor.start_timer("block_production");
+        let timer = monitor.time(Operation::BlockProduction);
         tokio::time::sleep(tokio::time::Duration::from_millis(10 + i * 2)).await;
-        let block_time = monitor.end_timer("block_production");
+        let block_time = timer.stop();
         
-        monitor.start_timer("proof_generation");
+        let timer = monitor.time(Operation::ProofGeneration);
         tokio::time::sleep(tokio::time::Duration::from_millis(50 + i * 5)).await;
-        let proof_time = monitor.end_timer("proof_generation");
+        let proof_time = timer.stop();
         
-        monitor.start_timer("validation");
+        let timer = monitor.time(Operation::Validation);
         tokio::time::sleep(tokio::time::Duration::from_millis(5 + i)).await;
-        let validation_time = monitor.end_timer("validation");
+        let validation_time = timer.stop();
         
-        let cycle_time = monitor.end_timer("consensus_cycle");
+        let cycle_time = cycle_timer.stop();
         
         // Create benchmark
         monitor.create_benchmark(
@@ -64,6 +81,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   🚀 Average TPS: {:.2}", summary.average_tps);
     println!("   🏆 Peak TPS: {:.2}", summary.max_tps);
     println!("   ⚡ Avg block time: {:.2} ms", summary.average_block_time_ms);
+    println!("   🔬 Prover: {} jobs, {:.0} cycles/s, {:.0} proof bytes/block",
+             summary.prover.jobs_completed, summary.prover.cycles_per_second,
+             summary.prover.average_proof_bytes_per_block);
     
     // 3. Real ZK Proof System Test (Mock Mode)
     println!("\n🔬 Phase 3: ZK Proof System");
@@ -77,8 +97,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     to: Address::new(2),
                     value: 1000,
                     data: vec![0x01, 0x02, 0x03],
-                    gas_limit: 21000,
+                    gas_limit: 30_000,
+                    gas_price: 1,
                     nonce: 0,
+                    chain_id: DEFAULT_CHAIN_ID,
+                    access_list: None,
                     signature: vec![0; 64],
                     sig_type: SignatureType::Ed25519,
                 },
@@ -87,8 +110,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     to: Address::new(3),
                     value: 500,
                     data: vec![0x04, 0x05, 0x06],
-                    gas_limit: 21000,
+                    gas_limit: 30_000,
+                    gas_price: 1,
                     nonce: 1,
+                    chain_id: DEFAULT_CHAIN_ID,
+                    access_list: None,
                     signature: vec![0; 64],
                     sig_type: SignatureType::Ed25519,
                 },
@@ -96,12 +122,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             
             let start_time = std::time::Instant::now();
             
-            let proof_result = prover.generate_state_transition_proof(
-                BlockHash::zero(),
-                &transactions,
-                1,
-                1640995200,
-            ).await?;
+            let proof_result = prover.generate_state_transition_proof(&proving_input(&transactions)).await?;
             
             let generation_time = start_time.elapsed();
             
@@ -110,6 +131,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("      ⏱️  Time: {:?}", generation_time);
             println!("      📊 Transactions: {}", proof_result.public_outputs.transaction_count);
             println!("      ⛽ Gas used: {}", proof_result.public_outputs.gas_used);
+            println!("      🔢 Cycles: {}", proof_result.cycles);
             
             // Verify the proof
             let verification_start = std::time::Instant::now();
@@ -141,11 +163,18 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let config = ProtocolConfig::default();
     
     let mut engine = ZkSacConsensusEngine::new(genesis_state, validators, config)?;
+    for i in 1..=10 {
+        let key = account_key(i);
+        engine.register_account_key(key.sig_type(), key.public_key());
+    }
+    for i in 1..=4 {
+        engine.add_validator_key(account_key(i))?;
+    }
     
     // Add test transactions
-    let test_transactions = create_test_transactions(50);
+    let test_transactions = create_test_transactions(50)?;
     for tx in test_transactions {
-        engine.pending_transactions.push(tx);
+        engine.add_local_transaction(tx)?;
     }
     
     let integration_start = std::time::Instant::now();
@@ -153,11 +182,11 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Process multiple blocks
     for block_num in 1..=10 {
         let producer = engine.select_block_producer(block_num)?;
-        let block = engine.produce_block(producer)?;
-        let is_valid = engine.validate_block(&block)?;
+        let block = engine.produce_block(producer).await?;
+        let is_valid = engine.validate_block(&block).await?;
         
         if is_valid {
-            engine.apply_block(block.clone())?;
+            engine.apply_block(block.clone()).await?;
             info!("✅ Block {} processed with {} transactions", 
                   block_num, block.transactions.len());
         } else {
@@ -170,6 +199,17 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     println!("   🏗️  Processed 10 blocks in {:?}", integration_time);
     println!("   📊 Average time per block: {:?}", integration_time / 10);
     
+    // Optional soak run, e.g. ZKSAC_SOAK_SECS=14400 for a four hour soak
+    if let Some(secs) = std::env::var("ZKSAC_SOAK_SECS").ok().and_then(|s| s.parse::<u64>().ok()) {
+        println!("\n🧪 Soak Test ({} seconds)", secs);
+        let config = SoakConfig {
+            duration: std::time::Duration::from_secs(secs),
+            ..SoakConfig::default()
+        };
+        let report = stress_test.run_soak_test(&mut engine, config).await?;
+        println!("   🧪 {} blocks, healthy: {}", report.blocks, report.is_healthy());
+    }
+    
     // 5. Export Performance Data
     println!("\n📄 Phase 5: Data Export");
     
@@ -190,11 +230,40 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 }
 
 // Helper functions
+/// Deterministic key of demo account `i`; accounts 1 to 4 are also the validators
+fn account_key(i: u8) -> KeyPair {
+    KeyPair::from_secret(SignatureType::Ed25519, &[i; 32]).expect("32-byte secret")
+}
+
+/// Guest input for block 1 of `transactions`, on a state funding each
+/// sender at the transaction's nonce
+fn proving_input(transactions: &[Transaction]) -> StateTransitionInput {
+    let mut state = WorldState::default();
+    for tx in transactions {
+        state.accounts.insert(tx.from, Account { nonce: tx.nonce, ..Account::new(1_000_000) });
+    }
+    state.state_root = state.compute_state_root();
+    let header = BlockHeader {
+        previous_hash: BlockHash::zero(),
+        merkle_root: Block::transactions_root(transactions),
+        prev_state_root: state.state_root,
+        state_root: BlockHash::zero(),
+        timestamp: 1640995200,
+        block_number: 1,
+        gas_limit: 30_000_000,
+        gas_used: 0,
+        producer: Address::new(99),
+        extra_data: Vec::new(),
+        randomness: RandomnessProof::default(),
+    };
+    state_transition_input(&state, &GasSchedule::default(), &header, transactions)
+}
+
 fn create_test_genesis_state() -> WorldState {
     let mut accounts = HashMap::new();
     for i in 1..=10 {
         accounts.insert(
-            Address::new(i),
+            account_key(i).address(),
             Account {
                 balance: 1_000_000 + (i as u64 * 100_000),
                 nonce: 0,
@@ -213,49 +282,43 @@ fn create_test_genesis_state() -> WorldState {
 }
 
 fn create_test_validators() -> Vec<Validator> {
-    vec![
-        Validator {
-            address: Address::new(1),
-            stake: 32_000_000_000,
-            public_key: vec![1; 32],
-            performance_score: 1.0,
-        },
-        Validator {
-            address: Address::new(2),
-            stake: 48_000_000_000,
-            public_key: vec![2; 32],
-            performance_score: 0.98,
-        },
-        Validator {
-            address: Address::new(3),
-            stake: 16_000_000_000,
-            public_key: vec![3; 32],
-            performance_score: 0.95,
-        },
-        Validator {
-            address: Address::new(4),
-            stake: 24_000_000_000,
-            public_key: vec![4; 32],
-            performance_score: 0.92,
-        },
-    ]
+    [(1, 32_000_000_000, 1.0), (2, 48_000_000_000, 0.98), (3, 16_000_000_000, 0.95), (4, 24_000_000_000, 0.92)]
+        .into_iter()
+        .map(|(i, stake, performance_score)| {
+            let key = account_key(i);
+            Validator {
+                address: key.address(),
+                stake,
+                public_key: key.public_key(),
+                performance_score,
+                bls_public_key: Vec::new(),
+            }
+        })
+        .collect()
 }
 
-fn create_test_transactions(count: usize) -> Vec<Transaction> {
+fn create_test_transactions(count: usize) -> anyhow::Result<Vec<Transaction>> {
+    let post_quantum = PostQuantumSigner::new()?;
     (0..count).map(|i| {
-        Transaction {
-            from: Address::new((i % 8 + 1) as u8),
-            to: Address::new((i % 8 + 2) as u8),
+        let key = account_key((i % 8 + 1) as u8);
+        let tx = Transaction {
+            from: key.address(),
+            to: account_key((i % 8 + 2) as u8).address(),
             value: 100 + (i as u64 * 50),
             data: vec![i as u8; (i % 32) + 1],
             gas_limit: 21000 + (i as u64 * 500),
+            gas_price: 1,
             nonce: i as u64,
-            signature: vec![0; 64],
-            sig_type: if i % 4 == 0 { 
-                SignatureType::PostQuantum 
-            } else { 
-                SignatureType::Ed25519 
-            },
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
+            signature: Vec::new(),
+            sig_type: SignatureType::PostQuantum,
+        };
+        if i % 4 == 0 {
+            let signature = post_quantum.sign_lms(&tx.from, &tx.signing_hash().0)?;
+            Ok(Transaction { signature, ..tx })
+        } else {
+            tx.signed(&key)
         }
     }).collect()
 } 
\ No newline at end of file
@@ -0,0 +1,264 @@
+//! Sub-pool for transactions carrying large data payloads
+//!
+//! Large payloads (future DA blobs) are accounted separately from ordinary
+//! transfers: they have their own byte budget in the pool, their own
+//! per-block inclusion caps, and an independent base fee that tracks blob
+//! demand, so big payloads cannot crowd out regular transactions.
+
+use super::{PooledTransaction, TxOrigin, TxValidationError};
+use crate::types::{Address, Transaction};
+use std::collections::BTreeMap;
+use std::time::Instant;
+use tracing::debug;
+
+/// Limits and fee parameters for the blob sub-pool
+#[derive(Debug, Clone)]
+pub struct BlobPoolConfig {
+    /// Payloads at least this large are routed to the blob pool
+    pub min_blob_size: usize,
+    pub max_blob_size: usize,
+    pub max_pool_bytes: usize,
+    pub max_blobs_per_block: usize,
+    pub max_blob_bytes_per_block: usize,
+    /// Blob bytes per block at which the blob base fee stays constant
+    pub target_blob_bytes_per_block: usize,
+    pub min_blob_base_fee: u64,
+}
+
+impl Default for BlobPoolConfig {
+    fn default() -> Self {
+        Self {
+            min_blob_size: 16 * 1024,                 // 16KB
+            max_blob_size: 128 * 1024,                // 128KB
+            max_pool_bytes: 64 * 1024 * 1024,         // 64MB
+            max_blobs_per_block: 6,
+            max_blob_bytes_per_block: 512 * 1024,     // 512KB
+            target_blob_bytes_per_block: 256 * 1024,  // 256KB
+            min_blob_base_fee: 1,
+        }
+    }
+}
+
+/// EIP-1559 style base fee that tracks blob usage independently of gas
+#[derive(Debug, Clone)]
+pub struct BlobFeeMarket {
+    base_fee: u64,
+    min_base_fee: u64,
+    target_bytes: usize,
+}
+
+impl BlobFeeMarket {
+    /// Maximum base fee change per block is 1/8 (12.5%)
+    const ADJUSTMENT_DENOMINATOR: u64 = 8;
+
+    pub fn new(min_base_fee: u64, target_bytes: usize) -> Self {
+        Self {
+            base_fee: min_base_fee,
+            min_base_fee,
+            target_bytes,
+        }
+    }
+
+    pub fn base_fee(&self) -> u64 {
+        self.base_fee
+    }
+
+    /// Adjust the base fee after a block containing `used_bytes` of blob payload
+    pub fn update(&mut self, used_bytes: usize) {
+        let target = self.target_bytes.max(1) as u128;
+        let used = used_bytes as u128;
+        let base = self.base_fee as u128;
+
+        if used > target {
+            let delta = (base * (used - target) / target / Self::ADJUSTMENT_DENOMINATOR as u128).max(1);
+            self.base_fee = (base + delta).min(u64::MAX as u128) as u64;
+        } else if used < target {
+            let delta = base * (target - used) / target / Self::ADJUSTMENT_DENOMINATOR as u128;
+            self.base_fee = (base - delta) as u64;
+        }
+        self.base_fee = self.base_fee.max(self.min_base_fee);
+    }
+}
+
+/// Outcome of inserting a blob transaction
+#[derive(Debug, Default)]
+pub struct BlobInsertion {
+    pub replaced: Option<PooledTransaction>,
+    pub evicted: Vec<PooledTransaction>,
+}
+
+/// Pool of large-payload transactions with byte-based accounting
+pub struct BlobPool {
+    config: BlobPoolConfig,
+    fee_market: BlobFeeMarket,
+    transactions: BTreeMap<(Address, u64), PooledTransaction>,
+    total_bytes: usize,
+}
+
+impl BlobPool {
+    pub fn new(config: BlobPoolConfig) -> Self {
+        let fee_market = BlobFeeMarket::new(config.min_blob_base_fee, config.target_blob_bytes_per_block);
+        Self {
+            config,
+            fee_market,
+            transactions: BTreeMap::new(),
+            total_bytes: 0,
+        }
+    }
+
+    /// Whether a transaction belongs in the blob pool rather than the ordinary pool
+    pub fn is_blob(&self, transaction: &Transaction) -> bool {
+        transaction.data.len() >= self.config.min_blob_size
+    }
+
+    pub fn insert(&mut self, transaction: Transaction, origin: TxOrigin, sequence: u64) -> Result<BlobInsertion, TxValidationError> {
+        let size = transaction.data.len();
+        if size > self.config.max_blob_size {
+            return Err(TxValidationError::BlobTooLarge { size, limit: self.config.max_blob_size });
+        }
+        if transaction.gas_price < self.fee_market.base_fee() {
+            return Err(TxValidationError::BlobFeeTooLow {
+                price: transaction.gas_price,
+                base_fee: self.fee_market.base_fee(),
+            });
+        }
+
+        let key = (transaction.from, transaction.nonce);
+        let replaced_size = self.transactions.get(&key).map_or(0, |tx| tx.transaction.data.len());
+        let mut insertion = BlobInsertion::default();
+
+        // Make room by evicting the cheapest remote blobs, never locals
+        while self.total_bytes - replaced_size + size > self.config.max_pool_bytes {
+            let victim = self.transactions.iter()
+                .filter(|(k, tx)| !tx.is_local() && **k != key)
+                .min_by_key(|(_, tx)| (tx.transaction.gas_price, std::cmp::Reverse(tx.sequence)))
+                .map(|(k, _)| *k);
+
+            match victim {
+                Some(victim) if self.transactions[&victim].transaction.gas_price < transaction.gas_price
+                    || origin == TxOrigin::Local => {
+                    if let Some(evicted) = self.remove(&victim.0, victim.1) {
+                        insertion.evicted.push(evicted);
+                    }
+                }
+                _ => return Err(TxValidationError::BlobPoolFull(self.config.max_pool_bytes)),
+            }
+        }
+
+        let pooled = PooledTransaction {
+            transaction,
+            origin,
+            received_at: Instant::now(),
+            sequence,
+        };
+        self.total_bytes += size;
+        if let Some(previous) = self.transactions.insert(key, pooled) {
+            self.total_bytes -= previous.transaction.data.len();
+            insertion.replaced = Some(previous);
+        }
+
+        debug!("🫧 Blob pool: {} txs, {} bytes", self.transactions.len(), self.total_bytes);
+        Ok(insertion)
+    }
+
+    /// The pending blob with `sender`'s lowest nonce
+    pub fn first_of(&self, sender: &Address) -> Option<&PooledTransaction> {
+        self.transactions.range((*sender, 0)..=(*sender, u64::MAX)).next().map(|(_, tx)| tx)
+    }
+
+    /// Senders with a pending blob, each once
+    pub fn senders(&self) -> impl Iterator<Item = Address> + '_ {
+        let mut last = None;
+        self.transactions.keys()
+            .map(|(sender, _)| *sender)
+            .filter(move |sender| last.replace(*sender) != Some(*sender))
+    }
+
+    /// Update the blob fee market with the blob bytes included in a block
+    pub fn on_block_included(&mut self, transactions: &[Transaction]) {
+        let used: usize = transactions.iter()
+            .filter(|tx| self.is_blob(tx))
+            .map(|tx| tx.data.len())
+            .sum();
+        self.fee_market.update(used);
+    }
+
+    pub fn remove(&mut self, sender: &Address, nonce: u64) -> Option<PooledTransaction> {
+        let removed = self.transactions.remove(&(*sender, nonce))?;
+        self.total_bytes -= removed.transaction.data.len();
+        Some(removed)
+    }
+
+    pub fn get(&self, sender: &Address, nonce: u64) -> Option<&PooledTransaction> {
+        self.transactions.get(&(*sender, nonce))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PooledTransaction> {
+        self.transactions.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.transactions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.transactions.is_empty()
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.total_bytes
+    }
+
+    pub fn base_fee(&self) -> u64 {
+        self.fee_market.base_fee()
+    }
+
+    pub fn config(&self) -> &BlobPoolConfig {
+        &self.config
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blob_tx(sender: u8, nonce: u64, size: usize, gas_price: u64) -> Transaction {
+        let mut tx = Transaction::new(Address::new(sender), Address::new(99), 0, nonce);
+        tx.data = vec![0xAB; size];
+        tx.gas_price = gas_price;
+        tx
+    }
+
+    #[test]
+    fn test_byte_limit_evicts_cheapest_remote() {
+        let mut pool = BlobPool::new(BlobPoolConfig {
+            min_blob_size: 10,
+            max_pool_bytes: 200,
+            ..BlobPoolConfig::default()
+        });
+        pool.insert(blob_tx(1, 0, 100, 1), TxOrigin::Remote, 0).unwrap();
+        pool.insert(blob_tx(2, 0, 100, 5), TxOrigin::Remote, 1).unwrap();
+
+        let insertion = pool.insert(blob_tx(3, 0, 100, 10), TxOrigin::Remote, 2).unwrap();
+        assert_eq!(insertion.evicted.len(), 1);
+        assert_eq!(insertion.evicted[0].transaction.from, Address::new(1));
+
+        // A cheaper bid cannot push out better-paying blobs
+        assert!(pool.insert(blob_tx(4, 0, 100, 2), TxOrigin::Remote, 3).is_err());
+    }
+
+    #[test]
+    fn test_fee_market_tracks_usage() {
+        let mut market = BlobFeeMarket::new(100, 1000);
+        market.update(1000);
+        assert_eq!(market.base_fee(), 100);
+        market.update(2000);
+        assert_eq!(market.base_fee(), 112);
+
+        // Empty blocks decay the fee back down, but never below the floor
+        for _ in 0..100 {
+            market.update(0);
+        }
+        assert_eq!(market.base_fee(), 100);
+    }
+}
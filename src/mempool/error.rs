@@ -0,0 +1,58 @@
+//! Reasons a transaction is refused by the pool or the engine's admission checks
+
+use crate::bridge::BridgeError;
+use crate::consensus::governance::GovernanceError;
+use crate::consensus::slashing::SlashingError;
+use crate::consensus::staking::StakingError;
+use crate::types::{Address, SignatureType};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum TxValidationError {
+    #[error("transaction from {0:?} is unsigned")]
+    Unsigned(Address),
+    #[error("transaction is signed for chain {chain_id}, this is chain {expected}")]
+    WrongChain { chain_id: u64, expected: u64 },
+    #[error("no public key known for sender {0:?}")]
+    UnknownSender(Address),
+    #[error("sender {sender:?} does not hold a {sig_type:?} key")]
+    KeyMismatch { sender: Address, sig_type: SignatureType },
+    #[error("invalid signature from {sender:?}: {reason}")]
+    InvalidSignature { sender: Address, reason: String },
+    #[error("gas price {price} below pool minimum {minimum}")]
+    GasPriceTooLow { price: u64, minimum: u64 },
+    #[error("gas limit {gas_limit} below intrinsic gas {intrinsic}")]
+    IntrinsicGasTooLow { gas_limit: u64, intrinsic: u64 },
+    #[error("gas limit {gas_limit} above block gas limit {block_limit}")]
+    GasLimitTooHigh { gas_limit: u64, block_limit: u64 },
+    #[error("{size} bytes of data exceed the limit of {limit}")]
+    DataTooLarge { size: usize, limit: usize },
+    #[error("nonce {nonce} already used; the sender's next nonce is {expected}")]
+    StaleNonce { nonce: u64, expected: u64 },
+    #[error("sender {0:?} is rate limited")]
+    RateLimited(Address),
+    #[error("sender {sender:?} exceeds per-sender pool limit of {limit}")]
+    SenderLimit { sender: Address, limit: usize },
+    #[error("replacement gas price {price} below the required {required}")]
+    ReplacementUnderpriced { price: u64, required: u64 },
+    #[error("remote transaction cannot replace local transaction {sender:?}/{nonce}")]
+    ReplacesLocal { sender: Address, nonce: u64 },
+    #[error("transaction pool is full ({0} transactions)")]
+    PoolFull(usize),
+    #[error("blob payload of {size} bytes exceeds limit of {limit}")]
+    BlobTooLarge { size: usize, limit: usize },
+    #[error("blob gas price {price} below current blob base fee {base_fee}")]
+    BlobFeeTooLow { price: u64, base_fee: u64 },
+    #[error("blob pool byte limit of {0} reached")]
+    BlobPoolFull(usize),
+    #[error("{sender:?} cannot cover a stake of {stake} with a balance of {balance}")]
+    InsufficientBalance { sender: Address, stake: u64, balance: u64 },
+    #[error(transparent)]
+    Bridge(#[from] BridgeError),
+    #[error(transparent)]
+    Governance(#[from] GovernanceError),
+    #[error(transparent)]
+    Slashing(#[from] SlashingError),
+    #[error(transparent)]
+    Staking(#[from] StakingError),
+}
@@ -0,0 +1,709 @@
+//! Transaction pool for pending transactions awaiting block inclusion
+//!
+//! Transactions are tracked by origin: locally submitted transactions (RPC,
+//! the node operator's own wallet) are exempt from lifetime and capacity
+//! eviction and are packed ahead of transactions received over gossip.
+//! A pending transaction is replaced only by one from the same origin class
+//! or a local one, paying at least [`PoolConfig::price_bump_percent`] more.
+//! Transactions with large data payloads are held in a separate blob pool
+//! with its own accounting (see [`blob`]). Gossip-received transactions
+//! pass a minimum-fee floor and per-sender throttle first (see [`spam`]).
+
+pub mod blob;
+pub mod error;
+pub mod spam;
+
+use crate::types::{Address, Transaction};
+use blob::{BlobPool, BlobPoolConfig};
+pub use error::TxValidationError;
+use spam::{SpamFilter, SpamFilterConfig};
+use futures::stream::{self, Stream};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+use tracing::{debug, info, warn};
+
+/// Capacity of the event channel; slow subscribers lag past this many events
+const EVENT_CHANNEL_CAPACITY: usize = 4096;
+
+/// Where a pooled transaction came from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TxOrigin {
+    /// Submitted through this node's RPC or by the operator directly
+    Local,
+    /// Received from a peer over gossip
+    Remote,
+}
+
+/// Why a transaction left the pool without being included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DropReason {
+    /// Pending longer than the remote lifetime
+    Expired,
+    /// Evicted to make room for a new transaction
+    Evicted,
+    /// Removed explicitly by the caller
+    Removed,
+}
+
+/// Change notifications published by the pool
+#[derive(Debug, Clone)]
+pub enum PoolEvent {
+    Added { transaction: Transaction, origin: TxOrigin },
+    Replaced { previous: Transaction, transaction: Transaction, origin: TxOrigin },
+    Dropped { sender: Address, nonce: u64, reason: DropReason },
+    Included { sender: Address, nonce: u64, block_number: u64 },
+}
+
+/// Limits applied to the transaction pool
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub max_pool_size: usize,
+    pub max_per_sender: usize,
+    pub remote_lifetime: Duration,
+    /// Percentage a replacement must raise the gas price of the transaction it replaces by
+    pub price_bump_percent: u64,
+    pub blob: BlobPoolConfig,
+    pub spam: SpamFilterConfig,
+}
+
+impl Default for PoolConfig {
+    fn default() -> Self {
+        Self {
+            max_pool_size: 50_000,
+            max_per_sender: 64,
+            remote_lifetime: Duration::from_secs(3 * 60 * 60), // 3 hours
+            price_bump_percent: 10,
+            blob: BlobPoolConfig::default(),
+            spam: SpamFilterConfig::default(),
+        }
+    }
+}
+
+/// A transaction held in the pool together with its admission metadata
+#[derive(Debug, Clone)]
+pub struct PooledTransaction {
+    pub transaction: Transaction,
+    pub origin: TxOrigin,
+    pub received_at: Instant,
+    sequence: u64,
+}
+
+impl PooledTransaction {
+    pub fn is_local(&self) -> bool {
+        self.origin == TxOrigin::Local
+    }
+}
+
+/// Pending transaction pool keyed by sender and nonce
+pub struct TransactionPool {
+    config: PoolConfig,
+    by_sender: HashMap<Address, BTreeMap<u64, PooledTransaction>>,
+    next_sequence: u64,
+    len: usize,
+    blob_pool: BlobPool,
+    spam_filter: SpamFilter,
+    events: broadcast::Sender<PoolEvent>,
+}
+
+impl TransactionPool {
+    pub fn new(config: PoolConfig) -> Self {
+        info!("🏊 Initializing transaction pool (max {} txs)", config.max_pool_size);
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        let blob_pool = BlobPool::new(config.blob.clone());
+        let spam_filter = SpamFilter::new(config.spam.clone());
+        Self {
+            config,
+            by_sender: HashMap::new(),
+            next_sequence: 0,
+            len: 0,
+            blob_pool,
+            spam_filter,
+            events,
+        }
+    }
+
+    /// Subscribe to pool events from this point on
+    pub fn subscribe(&self) -> broadcast::Receiver<PoolEvent> {
+        self.events.subscribe()
+    }
+
+    /// Pool events as an async stream; events missed by a lagging consumer are skipped
+    pub fn event_stream(&self) -> impl Stream<Item = PoolEvent> {
+        stream::unfold(self.subscribe(), |mut receiver| async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(event) => return Some((event, receiver)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("⚠️  Pool event subscriber lagged, skipped {} events", skipped);
+                    }
+                    Err(broadcast::error::RecvError::Closed) => return None,
+                }
+            }
+        })
+    }
+
+    /// Remove transactions included in a block and notify subscribers
+    pub fn mark_included(&mut self, transactions: &[Transaction], block_number: u64) {
+        self.blob_pool.on_block_included(transactions);
+        for tx in transactions {
+            self.remove_entry(&tx.from, tx.nonce);
+            self.publish(PoolEvent::Included {
+                sender: tx.from,
+                nonce: tx.nonce,
+                block_number,
+            });
+        }
+    }
+
+    /// Add a transaction to the pool, replacing any pending transaction with the same sender and nonce
+    pub fn add(&mut self, transaction: Transaction, origin: TxOrigin) -> Result<(), TxValidationError> {
+        if origin != TxOrigin::Remote {
+            return self.insert(transaction, origin);
+        }
+        self.spam_filter.check_remote(&transaction)?;
+        // Only transactions the pool takes count against the sender's budget
+        let sender = transaction.from;
+        let inserted = self.insert(transaction, origin);
+        if inserted.is_err() {
+            self.spam_filter.refund_remote(&sender);
+        }
+        inserted
+    }
+
+    fn insert(&mut self, transaction: Transaction, origin: TxOrigin) -> Result<(), TxValidationError> {
+        if let Some(previous) = self.get(&transaction.from, transaction.nonce) {
+            self.check_replacement(previous, &transaction, origin)?;
+        }
+
+        if self.blob_pool.is_blob(&transaction) {
+            return self.add_blob(transaction, origin);
+        }
+
+        let sender = transaction.from;
+        let nonce = transaction.nonce;
+        // A blob it replaces counts too: the pool does not grow
+        let is_replacement = self.get(&sender, nonce).is_some();
+
+        if !is_replacement {
+            let sender_count = self.by_sender.get(&sender).map_or(0, |txs| txs.len());
+            if origin == TxOrigin::Remote && sender_count >= self.config.max_per_sender {
+                return Err(TxValidationError::SenderLimit { sender, limit: self.config.max_per_sender });
+            }
+
+            if self.len >= self.config.max_pool_size && !self.evict_one_remote() {
+                return Err(TxValidationError::PoolFull(self.len));
+            }
+        }
+
+        let pooled = PooledTransaction {
+            transaction: transaction.clone(),
+            origin,
+            received_at: Instant::now(),
+            sequence: self.next_sequence,
+        };
+        self.next_sequence += 1;
+
+        let replaced = self.by_sender.entry(sender).or_default().insert(nonce, pooled);
+        if replaced.is_none() {
+            self.len += 1;
+        }
+        // A replacement of a blob takes it out of the blob pool
+        if let Some(previous) = replaced.or_else(|| self.blob_pool.remove(&sender, nonce)) {
+            debug!("🔁 Replaced pooled transaction {:?}/{} ({:?})", sender, nonce, previous.origin);
+            self.publish(PoolEvent::Replaced {
+                previous: previous.transaction,
+                transaction,
+                origin,
+            });
+        } else {
+            debug!("➕ Pooled transaction {:?}/{} ({:?})", sender, nonce, origin);
+            self.publish(PoolEvent::Added { transaction, origin });
+        }
+
+        Ok(())
+    }
+
+    /// Whether `transaction` from `origin` may replace `previous`: a remote
+    /// transaction never replaces a local one, and the gas price must rise
+    /// by the configured bump
+    fn check_replacement(&self, previous: &PooledTransaction, transaction: &Transaction, origin: TxOrigin) -> Result<(), TxValidationError> {
+        if previous.is_local() && origin == TxOrigin::Remote {
+            return Err(TxValidationError::ReplacesLocal { sender: transaction.from, nonce: transaction.nonce });
+        }
+        let price = u128::from(previous.transaction.gas_price);
+        let required = (price * u128::from(100 + self.config.price_bump_percent)).div_ceil(100);
+        let required = u64::try_from(required).unwrap_or(u64::MAX);
+        if transaction.gas_price < required {
+            return Err(TxValidationError::ReplacementUnderpriced { price: transaction.gas_price, required });
+        }
+        Ok(())
+    }
+
+    fn add_blob(&mut self, transaction: Transaction, origin: TxOrigin) -> Result<(), TxValidationError> {
+        let sequence = self.next_sequence;
+        self.next_sequence += 1;
+
+        let insertion = self.blob_pool.insert(transaction.clone(), origin, sequence)?;
+        for evicted in insertion.evicted {
+            self.publish(PoolEvent::Dropped {
+                sender: evicted.transaction.from,
+                nonce: evicted.transaction.nonce,
+                reason: DropReason::Evicted,
+            });
+        }
+        // A blob replacing an ordinary transaction takes it out of the ordinary pool
+        let replaced = insertion.replaced.or_else(|| self.remove_ordinary(&transaction.from, transaction.nonce));
+        match replaced {
+            Some(previous) => self.publish(PoolEvent::Replaced {
+                previous: previous.transaction,
+                transaction,
+                origin,
+            }),
+            None => self.publish(PoolEvent::Added { transaction, origin }),
+        }
+        Ok(())
+    }
+
+    /// Remove and return up to `max` transactions for block packing.
+    ///
+    /// Ordinary transactions are packed before blobs, local transactions
+    /// before remote ones, and within a class earlier arrivals go first;
+    /// blobs are ranked by price, subject to the blob pool's own per-block
+    /// caps. Each sender's transactions, ordinary and blob alike, are always
+    /// taken in nonce order: a sender whose next blob does not fit is done
+    /// for the block.
+    pub fn take_for_block(&mut self, max: usize) -> Vec<Transaction> {
+        self.take_for_block_within(max, |_| true)
+    }
+
+    /// Like [`take_for_block`](Self::take_for_block), but stops packing at the
+    /// first candidate `admit` rejects, e.g. because the block would exceed a
+    /// proving budget. Rejected transactions stay in the pool.
+    pub fn take_for_block_within(
+        &mut self,
+        max: usize,
+        mut admit: impl FnMut(&Transaction) -> bool,
+    ) -> Vec<Transaction> {
+        let mut selected = Vec::with_capacity(max.min(self.len()));
+        let mut senders: HashSet<Address> = self.by_sender.keys().copied().chain(self.blob_pool.senders()).collect();
+        let mut blobs_left = self.blob_pool.config().max_blobs_per_block;
+        let mut blob_bytes_left = self.blob_pool.config().max_blob_bytes_per_block;
+
+        while selected.len() < max {
+            let next = senders.iter()
+                .filter_map(|sender| self.next_of(sender))
+                .min_by_key(|tx| self.packing_rank(tx))
+                .map(|tx| (tx.transaction.from, tx.transaction.nonce));
+
+            let Some((sender, nonce)) = next else { break };
+            let candidate = &self.get(&sender, nonce).expect("candidate is pooled").transaction;
+            let blob_size = self.blob_pool.is_blob(candidate).then_some(candidate.data.len());
+            if blob_size.is_some_and(|size| blobs_left == 0 || size > blob_bytes_left) {
+                // Its later nonces cannot go in without this one
+                senders.remove(&sender);
+                continue;
+            }
+            if !admit(candidate) {
+                break;
+            }
+            if let Some(size) = blob_size {
+                blobs_left -= 1;
+                blob_bytes_left -= size;
+            }
+            if let Some(pooled) = self.remove_entry(&sender, nonce) {
+                selected.push(pooled.transaction);
+            }
+        }
+
+        debug!("📦 Took {} transactions from pool ({} remaining)", selected.len(), self.len());
+        selected
+    }
+
+    /// The pending transaction with `sender`'s lowest nonce, in either pool
+    fn next_of(&self, sender: &Address) -> Option<&PooledTransaction> {
+        let ordinary = self.by_sender.get(sender).and_then(|txs| txs.values().next());
+        match (ordinary, self.blob_pool.first_of(sender)) {
+            (Some(ordinary), Some(blob)) if blob.transaction.nonce < ordinary.transaction.nonce => Some(blob),
+            (ordinary, blob) => ordinary.or(blob),
+        }
+    }
+
+    /// Packing order among the senders' next transactions; lower goes first
+    fn packing_rank(&self, tx: &PooledTransaction) -> (bool, bool, Reverse<u64>, u64) {
+        let is_blob = self.blob_pool.is_blob(&tx.transaction);
+        let blob_price = if is_blob { tx.transaction.gas_price } else { 0 };
+        (is_blob, !tx.is_local(), Reverse(blob_price), tx.sequence)
+    }
+
+    /// Drop remote transactions that have been pending longer than the configured lifetime
+    pub fn evict_expired(&mut self) -> usize {
+        let lifetime = self.config.remote_lifetime;
+        let expired: Vec<(Address, u64)> = self.iter()
+            .filter(|tx| !tx.is_local() && tx.received_at.elapsed() > lifetime)
+            .map(|tx| (tx.transaction.from, tx.transaction.nonce))
+            .collect();
+
+        for (sender, nonce) in &expired {
+            self.drop_entry(sender, *nonce, DropReason::Expired);
+        }
+
+        self.spam_filter.prune();
+
+        if !expired.is_empty() {
+            info!("🧹 Evicted {} expired remote transactions", expired.len());
+        }
+        expired.len()
+    }
+
+    pub fn remove(&mut self, sender: &Address, nonce: u64) -> Option<PooledTransaction> {
+        self.drop_entry(sender, nonce, DropReason::Removed)
+    }
+
+    /// Take one transaction for a block outside the packing order; unlike
+    /// [`remove`](Self::remove) it is not reported as dropped
+    pub fn take(&mut self, sender: &Address, nonce: u64) -> Option<Transaction> {
+        self.remove_entry(sender, nonce).map(|pooled| pooled.transaction)
+    }
+
+    fn drop_entry(&mut self, sender: &Address, nonce: u64, reason: DropReason) -> Option<PooledTransaction> {
+        let removed = self.remove_entry(sender, nonce)?;
+        self.publish(PoolEvent::Dropped { sender: *sender, nonce, reason });
+        Some(removed)
+    }
+
+    fn remove_entry(&mut self, sender: &Address, nonce: u64) -> Option<PooledTransaction> {
+        self.blob_pool.remove(sender, nonce).or_else(|| self.remove_ordinary(sender, nonce))
+    }
+
+    fn remove_ordinary(&mut self, sender: &Address, nonce: u64) -> Option<PooledTransaction> {
+        let txs = self.by_sender.get_mut(sender)?;
+        let removed = txs.remove(&nonce)?;
+        if txs.is_empty() {
+            self.by_sender.remove(sender);
+        }
+        self.len -= 1;
+        Some(removed)
+    }
+
+    pub fn get(&self, sender: &Address, nonce: u64) -> Option<&PooledTransaction> {
+        self.by_sender.get(sender)
+            .and_then(|txs| txs.get(&nonce))
+            .or_else(|| self.blob_pool.get(sender, nonce))
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PooledTransaction> {
+        self.by_sender.values()
+            .flat_map(|txs| txs.values())
+            .chain(self.blob_pool.iter())
+    }
+
+    /// Number of pooled transactions, blobs included
+    pub fn len(&self) -> usize {
+        self.len + self.blob_pool.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn local_count(&self) -> usize {
+        self.iter().filter(|tx| tx.is_local()).count()
+    }
+
+    pub fn remote_count(&self) -> usize {
+        self.len() - self.local_count()
+    }
+
+    pub fn spam_filter_mut(&mut self) -> &mut SpamFilter {
+        &mut self.spam_filter
+    }
+
+    pub fn blob_pool(&self) -> &BlobPool {
+        &self.blob_pool
+    }
+
+    pub fn config(&self) -> &PoolConfig {
+        &self.config
+    }
+
+    fn publish(&self, event: PoolEvent) {
+        // Sending only fails when nobody is subscribed, which is fine
+        let _ = self.events.send(event);
+    }
+
+    // Evict the oldest remote transaction, always taking the highest nonce
+    // of its sender so no nonce gaps are left behind.
+    fn evict_one_remote(&mut self) -> bool {
+        let victim = self.by_sender.iter()
+            .filter_map(|(sender, txs)| txs.values().next_back().map(|tx| (*sender, tx)))
+            .filter(|(_, tx)| !tx.is_local())
+            .min_by_key(|(_, tx)| tx.sequence)
+            .map(|(sender, tx)| (sender, tx.transaction.nonce));
+
+        match victim {
+            Some((sender, nonce)) => {
+                warn!("🗑️  Pool full, evicting remote transaction {:?}/{}", sender, nonce);
+                self.drop_entry(&sender, nonce, DropReason::Evicted).is_some()
+            }
+            None => false,
+        }
+    }
+}
+
+impl Default for TransactionPool {
+    fn default() -> Self {
+        Self::new(PoolConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool_with_capacity(max_pool_size: usize) -> TransactionPool {
+        TransactionPool::new(PoolConfig {
+            max_pool_size,
+            ..PoolConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_locals_are_packed_first() {
+        let mut pool = TransactionPool::default();
+        pool.add(Transaction::new(Address::new(1), Address::new(9), 10, 0), TxOrigin::Remote).unwrap();
+        pool.add(Transaction::new(Address::new(2), Address::new(9), 20, 0), TxOrigin::Local).unwrap();
+
+        let packed = pool.take_for_block(2);
+        assert_eq!(packed[0].from, Address::new(2));
+        assert_eq!(packed[1].from, Address::new(1));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_take_within_stops_at_rejected_candidate() {
+        let mut pool = TransactionPool::default();
+        for nonce in 0..5 {
+            pool.add(Transaction::new(Address::new(1), Address::new(9), 10, nonce), TxOrigin::Local).unwrap();
+        }
+
+        let mut room = 3;
+        let packed = pool.take_for_block_within(10, |_| {
+            room -= 1;
+            room >= 0
+        });
+        assert_eq!(packed.len(), 3);
+        assert_eq!(pool.len(), 2);
+    }
+
+    #[test]
+    fn test_sender_nonce_order_preserved() {
+        let mut pool = TransactionPool::default();
+        pool.add(Transaction::new(Address::new(1), Address::new(9), 10, 1), TxOrigin::Local).unwrap();
+        pool.add(Transaction::new(Address::new(1), Address::new(9), 10, 0), TxOrigin::Local).unwrap();
+
+        let packed = pool.take_for_block(10);
+        assert_eq!(packed.iter().map(|tx| tx.nonce).collect::<Vec<_>>(), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_full_pool_evicts_remote_not_local() {
+        let mut pool = pool_with_capacity(2);
+        pool.add(Transaction::new(Address::new(1), Address::new(9), 10, 0), TxOrigin::Local).unwrap();
+        pool.add(Transaction::new(Address::new(2), Address::new(9), 10, 0), TxOrigin::Remote).unwrap();
+        pool.add(Transaction::new(Address::new(3), Address::new(9), 10, 0), TxOrigin::Local).unwrap();
+
+        assert_eq!(pool.len(), 2);
+        assert!(pool.get(&Address::new(1), 0).is_some());
+        assert!(pool.get(&Address::new(2), 0).is_none());
+
+        // Only locals left: admission must fail rather than evict them
+        assert_eq!(pool.add(Transaction::new(Address::new(4), Address::new(9), 10, 0), TxOrigin::Remote),
+                   Err(TxValidationError::PoolFull(2)));
+    }
+
+    #[test]
+    fn test_expiry_skips_locals() {
+        let mut pool = TransactionPool::new(PoolConfig {
+            remote_lifetime: Duration::from_millis(0),
+            ..PoolConfig::default()
+        });
+        pool.add(Transaction::new(Address::new(1), Address::new(9), 10, 0), TxOrigin::Local).unwrap();
+        pool.add(Transaction::new(Address::new(2), Address::new(9), 10, 0), TxOrigin::Remote).unwrap();
+        std::thread::sleep(Duration::from_millis(1));
+
+        assert_eq!(pool.evict_expired(), 1);
+        assert_eq!(pool.local_count(), 1);
+        assert_eq!(pool.remote_count(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_event_stream_lifecycle() {
+        use futures::StreamExt;
+
+        let mut pool = TransactionPool::default();
+        let events = pool.event_stream();
+        futures::pin_mut!(events);
+
+        let tx = Transaction::new(Address::new(1), Address::new(9), 10, 0);
+        pool.add(tx.clone(), TxOrigin::Local).unwrap();
+        let mut replacement = Transaction::new(Address::new(1), Address::new(9), 20, 0);
+        replacement.gas_price = 2;
+        pool.add(replacement, TxOrigin::Local).unwrap();
+        pool.mark_included(&[tx], 7);
+
+        assert!(matches!(events.next().await, Some(PoolEvent::Added { origin: TxOrigin::Local, .. })));
+        assert!(matches!(events.next().await, Some(PoolEvent::Replaced { .. })));
+        assert!(matches!(events.next().await, Some(PoolEvent::Included { nonce: 0, block_number: 7, .. })));
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_replacements_pay_a_price_bump_and_keep_locals() {
+        let mut pool = TransactionPool::default();
+        let mut tx = Transaction::new(Address::new(1), Address::new(9), 10, 0);
+        tx.gas_price = 100;
+        pool.add(tx.clone(), TxOrigin::Local).unwrap();
+
+        // Same price, then under the 10% bump
+        assert_eq!(pool.add(tx.clone(), TxOrigin::Local),
+                   Err(TxValidationError::ReplacementUnderpriced { price: 100, required: 110 }));
+        tx.gas_price = 109;
+        assert!(pool.add(tx.clone(), TxOrigin::Local).is_err());
+
+        // A remote transaction cannot displace a local one, whatever it pays
+        tx.gas_price = 1_000;
+        assert_eq!(pool.add(tx.clone(), TxOrigin::Remote),
+                   Err(TxValidationError::ReplacesLocal { sender: Address::new(1), nonce: 0 }));
+        assert!(pool.get(&Address::new(1), 0).unwrap().is_local());
+
+        tx.gas_price = 110;
+        pool.add(tx, TxOrigin::Local).unwrap();
+        assert_eq!(pool.get(&Address::new(1), 0).unwrap().transaction.gas_price, 110);
+        assert_eq!(pool.len(), 1);
+    }
+
+    #[test]
+    fn test_rejected_remotes_keep_their_rate_budget() {
+        let mut pool = TransactionPool::new(PoolConfig {
+            max_per_sender: 1,
+            spam: SpamFilterConfig { max_remote_per_window: 2, window: Duration::from_secs(60), ..SpamFilterConfig::default() },
+            ..PoolConfig::default()
+        });
+        let remote = |nonce| Transaction::new(Address::new(2), Address::new(9), 10, nonce);
+        pool.add(remote(0), TxOrigin::Remote).unwrap();
+        for _ in 0..5 {
+            assert_eq!(pool.add(remote(1), TxOrigin::Remote),
+                       Err(TxValidationError::SenderLimit { sender: Address::new(2), limit: 1 }));
+        }
+
+        // The refused attempts did not use up the window
+        pool.take_for_block(1);
+        pool.add(remote(1), TxOrigin::Remote).unwrap();
+        pool.take_for_block(1);
+        assert_eq!(pool.add(remote(2), TxOrigin::Remote), Err(TxValidationError::RateLimited(Address::new(2))));
+    }
+
+    #[test]
+    fn test_zero_fee_remote_rejected_local_accepted() {
+        let mut pool = TransactionPool::default();
+        let mut tx = Transaction::new(Address::new(1), Address::new(9), 10, 0);
+        tx.gas_price = 0;
+
+        assert!(pool.add(tx.clone(), TxOrigin::Remote).is_err());
+        assert!(pool.add(tx, TxOrigin::Local).is_ok());
+    }
+
+    #[test]
+    fn test_blobs_do_not_crowd_out_transfers() {
+        let mut pool = TransactionPool::default();
+        let blob_size = pool.config().blob.min_blob_size;
+        for sender in 1..=10 {
+            let mut blob = Transaction::new(Address::new(sender), Address::new(99), 0, 0);
+            blob.data = vec![0; blob_size];
+            pool.add(blob, TxOrigin::Local).unwrap();
+        }
+        pool.add(Transaction::new(Address::new(50), Address::new(99), 10, 0), TxOrigin::Remote).unwrap();
+        assert_eq!(pool.blob_pool().len(), 10);
+
+        let packed = pool.take_for_block(100);
+        assert_eq!(packed[0].from, Address::new(50));
+        assert_eq!(packed.len(), 1 + pool.config().blob.max_blobs_per_block);
+    }
+
+    fn blob_pool_with_caps(max_blobs_per_block: usize, max_blob_bytes_per_block: usize) -> TransactionPool {
+        TransactionPool::new(PoolConfig {
+            blob: BlobPoolConfig { min_blob_size: 10, max_blobs_per_block, max_blob_bytes_per_block, ..BlobPoolConfig::default() },
+            ..PoolConfig::default()
+        })
+    }
+
+    fn blob_tx(sender: u8, nonce: u64, size: usize, gas_price: u64) -> Transaction {
+        let mut tx = Transaction::new(Address::new(sender), Address::new(99), 0, nonce);
+        tx.data = vec![0xAB; size];
+        tx.gas_price = gas_price;
+        tx
+    }
+
+    #[test]
+    fn test_blob_per_block_caps() {
+        let mut pool = blob_pool_with_caps(2, 250);
+        for sender in 1..=4 {
+            pool.add(blob_tx(sender, 0, 100, sender as u64), TxOrigin::Remote).unwrap();
+        }
+
+        let taken = pool.take_for_block(10);
+        assert_eq!(taken.len(), 2);
+        // Highest bidders are packed first
+        assert!(taken.iter().all(|tx| tx.gas_price >= 3));
+        assert_eq!(pool.blob_pool().total_bytes(), 200);
+    }
+
+    #[test]
+    fn test_senders_are_packed_in_nonce_order_across_pools() {
+        let mut pool = blob_pool_with_caps(1, 250);
+        pool.add(Transaction::new(Address::new(1), Address::new(9), 10, 0), TxOrigin::Local).unwrap();
+        pool.add(blob_tx(1, 1, 100, 5), TxOrigin::Local).unwrap();
+        pool.add(Transaction::new(Address::new(1), Address::new(9), 10, 2), TxOrigin::Local).unwrap();
+        // Sender 2's blob pays more, but comes after its ordinary nonce 0
+        pool.add(Transaction::new(Address::new(2), Address::new(9), 10, 0), TxOrigin::Local).unwrap();
+        pool.add(blob_tx(2, 1, 100, 50), TxOrigin::Local).unwrap();
+
+        let packed: Vec<(Address, u64)> = pool.take_for_block(10).iter().map(|tx| (tx.from, tx.nonce)).collect();
+        assert_eq!(packed, vec![
+            (Address::new(1), 0),
+            (Address::new(2), 0),
+            (Address::new(2), 1),
+        ]);
+        // With the one blob slot taken, sender 1 stops at its blob rather than skipping it
+        assert!(pool.get(&Address::new(1), 1).is_some());
+        assert!(pool.get(&Address::new(1), 2).is_some());
+
+        let packed: Vec<u64> = pool.take_for_block(10).iter().map(|tx| tx.nonce).collect();
+        assert_eq!(packed, vec![1, 2]);
+        assert!(pool.is_empty());
+    }
+
+    #[test]
+    fn test_replacements_move_between_pools() {
+        let mut pool = blob_pool_with_caps(6, 1_000);
+        let mut transfer = Transaction::new(Address::new(1), Address::new(9), 10, 0);
+        transfer.gas_price = 10;
+        pool.add(transfer.clone(), TxOrigin::Local).unwrap();
+
+        pool.add(blob_tx(1, 0, 100, 20), TxOrigin::Local).unwrap();
+        assert_eq!(pool.len(), 1);
+        assert_eq!(pool.blob_pool().len(), 1);
+        assert_eq!(pool.get(&Address::new(1), 0).unwrap().transaction.gas_price, 20);
+
+        transfer.gas_price = 30;
+        pool.add(transfer, TxOrigin::Local).unwrap();
+        assert_eq!(pool.len(), 1);
+        assert!(pool.blob_pool().is_empty());
+        assert_eq!(pool.get(&Address::new(1), 0).unwrap().transaction.gas_price, 30);
+
+        assert_eq!(pool.take_for_block(10).len(), 1);
+        assert!(pool.is_empty());
+    }
+}
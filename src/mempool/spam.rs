@@ -0,0 +1,134 @@
+//! Admission filtering against transaction spam
+//!
+//! Remote transactions must pay at least the configured minimum gas price
+//! and each gossip sender is limited to a fixed number of admissions per
+//! time window. Local transactions bypass both checks.
+
+use super::TxValidationError;
+use crate::types::{Address, Transaction};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+#[derive(Debug, Clone)]
+pub struct SpamFilterConfig {
+    pub min_gas_price: u64,
+    /// Remote admissions allowed per sender within one window
+    pub max_remote_per_window: u32,
+    pub window: Duration,
+}
+
+impl Default for SpamFilterConfig {
+    fn default() -> Self {
+        Self {
+            min_gas_price: 1,
+            max_remote_per_window: 16,
+            window: Duration::from_secs(10),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SenderWindow {
+    started: Instant,
+    admitted: u32,
+}
+
+/// Minimum-fee floor plus fixed-window per-sender throttling
+#[derive(Debug)]
+pub struct SpamFilter {
+    config: SpamFilterConfig,
+    windows: HashMap<Address, SenderWindow>,
+}
+
+impl SpamFilter {
+    pub fn new(config: SpamFilterConfig) -> Self {
+        Self {
+            config,
+            windows: HashMap::new(),
+        }
+    }
+
+    /// Check a gossip-received transaction, counting it against its sender's budget if admitted
+    pub fn check_remote(&mut self, transaction: &Transaction) -> Result<(), TxValidationError> {
+        if transaction.gas_price < self.config.min_gas_price {
+            return Err(TxValidationError::GasPriceTooLow {
+                price: transaction.gas_price,
+                minimum: self.config.min_gas_price,
+            });
+        }
+
+        let now = Instant::now();
+        let window = self.windows.entry(transaction.from).or_insert(SenderWindow {
+            started: now,
+            admitted: 0,
+        });
+        if now.duration_since(window.started) >= self.config.window {
+            *window = SenderWindow { started: now, admitted: 0 };
+        }
+        if window.admitted >= self.config.max_remote_per_window {
+            debug!("🚦 Throttling sender {:?} ({} txs this window)", transaction.from, window.admitted);
+            return Err(TxValidationError::RateLimited(transaction.from));
+        }
+
+        window.admitted += 1;
+        Ok(())
+    }
+
+    /// Give back the admission a transaction from `sender` was counted for
+    /// when the pool then refused it
+    pub fn refund_remote(&mut self, sender: &Address) {
+        if let Some(window) = self.windows.get_mut(sender) {
+            window.admitted = window.admitted.saturating_sub(1);
+        }
+    }
+
+    /// Forget senders whose window has elapsed
+    pub fn prune(&mut self) {
+        let window = self.config.window;
+        self.windows.retain(|_, w| w.started.elapsed() < window);
+    }
+
+    pub fn min_gas_price(&self) -> u64 {
+        self.config.min_gas_price
+    }
+
+    pub fn set_min_gas_price(&mut self, min_gas_price: u64) {
+        self.config.min_gas_price = min_gas_price;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_min_gas_price_floor() {
+        let mut filter = SpamFilter::new(SpamFilterConfig {
+            min_gas_price: 5,
+            ..SpamFilterConfig::default()
+        });
+        let mut tx = Transaction::new(Address::new(1), Address::new(2), 1, 0);
+        tx.gas_price = 0;
+        assert!(filter.check_remote(&tx).is_err());
+        tx.gas_price = 5;
+        assert!(filter.check_remote(&tx).is_ok());
+    }
+
+    #[test]
+    fn test_sender_throttling() {
+        let mut filter = SpamFilter::new(SpamFilterConfig {
+            max_remote_per_window: 2,
+            window: Duration::from_secs(60),
+            ..SpamFilterConfig::default()
+        });
+        let spammer = Transaction::new(Address::new(1), Address::new(2), 1, 0);
+        assert!(filter.check_remote(&spammer).is_ok());
+        assert!(filter.check_remote(&spammer).is_ok());
+        assert_eq!(filter.check_remote(&spammer), Err(TxValidationError::RateLimited(Address::new(1))));
+
+        // Other senders are unaffected
+        let honest = Transaction::new(Address::new(3), Address::new(2), 1, 0);
+        assert!(filter.check_remote(&honest).is_ok());
+    }
+}
@@ -0,0 +1,364 @@
+//! Node configuration file and genesis
+//!
+//! `config.toml` has one table per subsystem. Every setting has a default, so
+//! a file only lists what it changes. Any setting can be overridden with an
+//! environment variable named `ZKSAC_<SECTION>_<KEY>`, for example
+//! `ZKSAC_CONSENSUS_BLOCK_TIME=2s` or `ZKSAC_RPC_LISTEN_ADDR=0.0.0.0:8545`.
+//! Override values are parsed as TOML, falling back to a plain string.
+
+use crate::types::human;
+use crate::types::*;
+pub use crate::consensus::genesis::{GenesisAccount, GenesisConfig, GenesisValidator};
+use anyhow::{Context, Result, anyhow};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+pub const ENV_PREFIX: &str = "ZKSAC_";
+const SECTIONS: &[&str] = &["consensus", "zkvm", "network", "storage", "rpc", "da", "indexer", "telemetry"];
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NodeConfig {
+    pub consensus: ConsensusSettings,
+    pub zkvm: ZkVMConfig,
+    pub network: NetworkSettings,
+    pub storage: StorageSettings,
+    pub rpc: RpcSettings,
+    pub da: DaSettings,
+    pub indexer: IndexerSettings,
+    pub telemetry: TelemetrySettings,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConsensusSettings {
+    /// Network id transactions must be signed for
+    pub chain_id: u64,
+    #[serde(with = "human::duration")]
+    pub block_time: Duration,
+    #[serde(with = "human::byte_size")]
+    pub max_block_size: usize,
+    pub max_transactions_per_block: usize,
+    pub min_stake_threshold: u64,
+    pub slashing_rate: f64,
+    /// Blocks within which a deferred block proof must be supplied on chain
+    pub proof_deadline: u64,
+    pub reward_rate: f64,
+    /// Blocks per epoch; the validator set changes between epochs
+    pub epoch_length: u64,
+    /// Epochs a stake or unstake waits before taking effect
+    pub activation_delay: u64,
+    /// Most validators joining, and most leaving, per epoch
+    pub churn_limit: usize,
+    /// Most gas the transactions of one block may use
+    pub block_gas_limit: u64,
+    /// Squarings in the randomness beacon VDF; every node must agree
+    pub vdf_iterations: u64,
+    /// Environment transactions execute in; every node must agree
+    pub execution_backend: ExecutionBackendKind,
+    /// Produce blocks for the selected validator; off for a following node
+    pub produce_blocks: bool,
+}
+
+impl Default for ConsensusSettings {
+    fn default() -> Self {
+        let protocol = ProtocolConfig::default();
+        Self {
+            chain_id: protocol.chain_id,
+            block_time: protocol.block_time,
+            max_block_size: protocol.max_block_size,
+            max_transactions_per_block: protocol.max_transactions_per_block,
+            min_stake_threshold: protocol.min_stake_threshold,
+            slashing_rate: protocol.slashing_rate,
+            proof_deadline: protocol.proof_deadline,
+            reward_rate: protocol.reward_rate,
+            epoch_length: protocol.epoch_schedule.epoch_length,
+            activation_delay: protocol.epoch_schedule.activation_delay,
+            churn_limit: protocol.epoch_schedule.churn_limit,
+            block_gas_limit: protocol.gas_schedule.block_gas_limit,
+            vdf_iterations: protocol.vdf_iterations,
+            execution_backend: protocol.execution_backend,
+            produce_blocks: true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct NetworkSettings {
+    /// libp2p multiaddr to listen on
+    pub listen_addr: String,
+    pub bootnodes: Vec<String>,
+    pub max_peers: usize,
+}
+
+impl Default for NetworkSettings {
+    fn default() -> Self {
+        Self {
+            listen_addr: "/ip4/0.0.0.0/tcp/30333".to_string(),
+            bootnodes: Vec::new(),
+            max_peers: 50,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StorageSettings {
+    pub data_dir: PathBuf,
+    /// Genesis file, JSON if it ends in `.json` and TOML otherwise;
+    /// `genesis.toml` in the data dir when unset
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genesis_file: Option<PathBuf>,
+}
+
+impl Default for StorageSettings {
+    fn default() -> Self {
+        Self { data_dir: PathBuf::from("./data"), genesis_file: None }
+    }
+}
+
+impl StorageSettings {
+    pub fn genesis_path(&self) -> PathBuf {
+        self.genesis_file.clone().unwrap_or_else(|| self.data_dir.join("genesis.toml"))
+    }
+
+    pub fn block_log_path(&self) -> PathBuf {
+        self.data_dir.join("blocks.log")
+    }
+
+    pub fn keystore_dir(&self) -> PathBuf {
+        self.data_dir.join("keystore")
+    }
+
+    pub fn index_path(&self) -> PathBuf {
+        self.data_dir.join("index.sqlite")
+    }
+
+    pub fn parquet_dir(&self) -> PathBuf {
+        self.data_dir.join("parquet")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RpcSettings {
+    pub enabled: bool,
+    pub listen_addr: SocketAddr,
+    /// WebSocket endpoint for `eth_subscribe`
+    pub ws_listen_addr: SocketAddr,
+}
+
+impl Default for RpcSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            listen_addr: SocketAddr::from(([127, 0, 0, 1], 8545)),
+            ws_listen_addr: SocketAddr::from(([127, 0, 0, 1], 8546)),
+        }
+    }
+}
+
+/// Where block bodies are published before blocks are finalized
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DaMode {
+    /// Blocks are finalized locally
+    #[default]
+    Disabled,
+    /// Rollup mode: blocks are finalized once available on Celestia
+    Celestia,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DaSettings {
+    pub mode: DaMode,
+    /// celestia-node JSON-RPC endpoint
+    pub endpoint: String,
+    /// celestia-node auth token; empty for none
+    pub auth_token: String,
+    /// Hex namespace id, at most 10 bytes
+    pub namespace: String,
+    #[serde(with = "human::duration")]
+    pub availability_timeout: Duration,
+}
+
+impl Default for DaSettings {
+    fn default() -> Self {
+        Self {
+            mode: DaMode::Disabled,
+            endpoint: "http://127.0.0.1:26658".to_string(),
+            auth_token: String::new(),
+            namespace: hex::encode(b"zksac"),
+            availability_timeout: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Chain indexer; needs the `indexer` feature
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IndexerSettings {
+    pub enabled: bool,
+    /// Export the index to Parquet on shutdown
+    pub export_parquet: bool,
+}
+
+/// Log filtering and trace export; exporting needs the `otel` feature
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TelemetrySettings {
+    /// `RUST_LOG`-style filter, used when `RUST_LOG` is not set
+    pub log_filter: String,
+    /// OTLP/gRPC collector to export spans to, e.g. `http://localhost:4317`; empty for none
+    pub otlp_endpoint: String,
+    /// `service.name` of exported spans
+    pub service_name: String,
+}
+
+impl Default for TelemetrySettings {
+    fn default() -> Self {
+        Self {
+            log_filter: "info".to_string(),
+            otlp_endpoint: String::new(),
+            service_name: "zk-sac-node".to_string(),
+        }
+    }
+}
+
+impl NodeConfig {
+    /// Load `path` (defaults if `None`) and apply `ZKSAC_*` environment overrides
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let text = match path {
+            Some(path) => std::fs::read_to_string(path)
+                .with_context(|| format!("Failed to read config file {}", path.display()))?,
+            None => String::new(),
+        };
+        Self::from_toml_with_env(&text, std::env::vars())
+            .with_context(|| match path {
+                Some(path) => format!("Invalid config file {}", path.display()),
+                None => "Invalid configuration".to_string(),
+            })
+    }
+
+    /// Parse `text` after applying overrides from `vars`
+    pub fn from_toml_with_env(text: &str, vars: impl IntoIterator<Item = (String, String)>) -> Result<Self> {
+        let mut table: toml::Table = toml::from_str(text)?;
+        for (name, value) in vars {
+            let Some((section_name, key)) = override_target(&name) else { continue };
+            let section = table.entry(section_name)
+                .or_insert_with(|| toml::Value::Table(toml::Table::new()))
+                .as_table_mut()
+                .ok_or_else(|| anyhow!("[{}] must be a table", section_name))?;
+            section.insert(key, parse_override(&value));
+        }
+        Ok(toml::Value::Table(table).try_into()?)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    pub fn protocol_config(&self) -> ProtocolConfig {
+        ProtocolConfig {
+            chain_id: self.consensus.chain_id,
+            block_time: self.consensus.block_time,
+            max_block_size: self.consensus.max_block_size,
+            max_transactions_per_block: self.consensus.max_transactions_per_block,
+            min_stake_threshold: self.consensus.min_stake_threshold,
+            slashing_rate: self.consensus.slashing_rate,
+            proof_deadline: self.consensus.proof_deadline,
+            reward_rate: self.consensus.reward_rate,
+            zkvm_config: self.zkvm.clone(),
+            epoch_schedule: EpochSchedule {
+                epoch_length: self.consensus.epoch_length,
+                activation_delay: self.consensus.activation_delay,
+                churn_limit: self.consensus.churn_limit,
+            },
+            gas_schedule: GasSchedule {
+                block_gas_limit: self.consensus.block_gas_limit,
+                ..GasSchedule::default()
+            },
+            vdf_iterations: self.consensus.vdf_iterations,
+            transaction_hooks: Vec::new(),
+            execution_backend: self.consensus.execution_backend,
+        }
+    }
+}
+
+/// `ZKSAC_RPC_LISTEN_ADDR` -> `("rpc", "listen_addr")`; other variables are ignored
+fn override_target(name: &str) -> Option<(&'static str, String)> {
+    let rest = name.strip_prefix(ENV_PREFIX)?.to_ascii_lowercase();
+    SECTIONS.iter().find_map(|&section| {
+        let key = rest.strip_prefix(section)?.strip_prefix('_')?;
+        (!key.is_empty()).then(|| (section, key.to_string()))
+    })
+}
+
+fn parse_override(value: &str) -> toml::Value {
+    toml::from_str::<toml::Table>(&format!("value = {}", value))
+        .ok()
+        .and_then(|mut table| table.remove("value"))
+        .unwrap_or_else(|| toml::Value::String(value.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crypto::keystore::KeyPair;
+
+    #[test]
+    fn test_partial_file_with_env_overrides() {
+        let text = r#"
+            [consensus]
+            block_time = "2s"
+
+            [storage]
+            data_dir = "/var/lib/zksac"
+        "#;
+        let vars = vec![
+            ("ZKSAC_RPC_LISTEN_ADDR".to_string(), "0.0.0.0:9000".to_string()),
+            ("ZKSAC_CONSENSUS_MAX_TRANSACTIONS_PER_BLOCK".to_string(), "500".to_string()),
+            ("ZKSAC_ZKVM_MEMORY_LIMIT".to_string(), "512MiB".to_string()),
+            ("ZKSAC_DA_MODE".to_string(), "celestia".to_string()),
+            ("ZKSAC_TELEMETRY_OTLP_ENDPOINT".to_string(), "http://localhost:4317".to_string()),
+            ("ZKSAC_PROFILE_DIR".to_string(), "/tmp".to_string()),
+            ("PATH".to_string(), "/usr/bin".to_string()),
+        ];
+        let config = NodeConfig::from_toml_with_env(text, vars).unwrap();
+
+        assert_eq!(config.consensus.block_time, Duration::from_secs(2));
+        assert_eq!(config.consensus.max_transactions_per_block, 500);
+        assert_eq!(config.zkvm.memory_limit, 512 << 20);
+        assert_eq!(config.rpc.listen_addr, "0.0.0.0:9000".parse().unwrap());
+        assert_eq!(config.storage.block_log_path(), PathBuf::from("/var/lib/zksac/blocks.log"));
+        assert_eq!(config.network.max_peers, NetworkSettings::default().max_peers);
+        assert_eq!(config.da.mode, DaMode::Celestia);
+        assert_eq!(config.telemetry.otlp_endpoint, "http://localhost:4317");
+        assert_eq!(config.telemetry.log_filter, "info");
+    }
+
+    #[test]
+    fn test_rejects_unknown_keys() {
+        let err = NodeConfig::from_toml_with_env("[rpc]\nlisten = \"x\"", Vec::new()).unwrap_err();
+        assert!(format!("{:#}", err).contains("listen"));
+
+        let vars = vec![("ZKSAC_RPC_PORT".to_string(), "1".to_string())];
+        assert!(NodeConfig::from_toml_with_env("", vars).is_err());
+    }
+
+    #[test]
+    fn test_default_config_and_genesis_roundtrip() {
+        let config = NodeConfig::from_toml_with_env(&NodeConfig::default().to_toml().unwrap(), Vec::new()).unwrap();
+        assert_eq!(config.consensus.block_time, ProtocolConfig::default().block_time);
+
+        let key = KeyPair::generate(SignatureType::Ed25519);
+        let genesis = GenesisConfig::from_toml(&GenesisConfig::dev(&key).to_toml().unwrap()).unwrap();
+        assert_eq!(genesis.validators[0].address, key.address());
+        assert_eq!(genesis.validators[0].public_key, key.public_key());
+        assert_eq!(genesis.world_state().accounts.len(), 2);
+    }
+}
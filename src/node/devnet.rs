@@ -0,0 +1,426 @@
+//! Local devnet: several validators in one process
+//!
+//! [`Devnet::start`] creates one consensus engine per validator, connects
+//! them through a [`SimulatedNetwork`] and produces blocks on a timer. Each
+//! node is driven by its own task; [`DevnetNode`] handles query chain state
+//! and submit transactions the way an RPC client would. Nodes also answer
+//! sync requests, so a node joining the network later can catch up.
+
+use super::config::{GenesisAccount, GenesisConfig, GenesisValidator};
+use super::network::{NetworkEndpoint, NetworkMessage, NodeId, SimulatedNetwork};
+use super::sync::SyncServer;
+use crate::consensus::engine::{ConsensusEngine, ZkSacConsensusEngine};
+use crate::crypto::keystore::KeyPair;
+use crate::fault::{self, FaultPoint};
+use crate::types::*;
+use anyhow::{Result, anyhow, bail};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn};
+
+#[derive(Debug, Clone)]
+pub struct DevnetConfig {
+    pub validators: usize,
+    pub block_time: Duration,
+    pub network_latency: Duration,
+    /// Genesis balance of every validator account
+    pub initial_balance: u64,
+    pub stake: u64,
+}
+
+impl Default for DevnetConfig {
+    fn default() -> Self {
+        Self {
+            validators: 4,
+            block_time: Duration::from_secs(1),
+            network_latency: Duration::ZERO,
+            initial_balance: 1_000_000,
+            stake: 32_000_000_000,
+        }
+    }
+}
+
+impl DevnetConfig {
+    pub fn with_validators(mut self, validators: usize) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    pub fn with_block_time(mut self, block_time: Duration) -> Self {
+        self.block_time = block_time;
+        self
+    }
+
+    pub fn with_network_latency(mut self, latency: Duration) -> Self {
+        self.network_latency = latency;
+        self
+    }
+}
+
+/// Handle to one running devnet node
+#[derive(Clone)]
+pub struct DevnetNode {
+    pub id: NodeId,
+    pub address: Address,
+    pub public_key: Vec<u8>,
+    engine: Arc<Mutex<ZkSacConsensusEngine>>,
+    network: SimulatedNetwork,
+}
+
+impl DevnetNode {
+    pub async fn height(&self) -> u64 {
+        self.engine.lock().await.blocks.len() as u64
+    }
+
+    pub async fn finalized_height(&self) -> u64 {
+        self.engine.lock().await.finalized_height()
+    }
+
+    pub async fn head_hash(&self) -> Option<BlockHash> {
+        self.engine.lock().await.blocks.last().map(|block| block.header.hash())
+    }
+
+    pub async fn block(&self, number: u64) -> Option<Block> {
+        let engine = self.engine.lock().await;
+        engine.blocks.iter().find(|block| block.header.block_number == number).cloned()
+    }
+
+    pub async fn balance(&self, address: &Address) -> u64 {
+        let engine = self.engine.lock().await;
+        engine.current_state.accounts.get(address).map_or(0, |account| account.balance)
+    }
+
+    /// Add `transaction` to this node's pool and gossip it to the others
+    pub async fn submit_transaction(&self, transaction: Transaction) -> Result<()> {
+        self.engine.lock().await.add_local_transaction(transaction.clone())?;
+        // Gossip as if from this node so it is not delivered back to itself
+        self.network.broadcast_from(self.id, NetworkMessage::Transaction(transaction));
+        Ok(())
+    }
+}
+
+pub struct Devnet {
+    nodes: Vec<DevnetNode>,
+    network: SimulatedNetwork,
+    tasks: Vec<JoinHandle<Result<()>>>,
+    shutdown: CancellationToken,
+}
+
+impl Devnet {
+    /// Create the validators and start producing blocks
+    pub fn start(config: DevnetConfig) -> Result<Self> {
+        if config.validators == 0 {
+            bail!("A devnet needs at least one validator");
+        }
+        info!("🧪 Starting devnet with {} validators, {:?} block time", config.validators, config.block_time);
+
+        let keys: Vec<KeyPair> = (0..config.validators)
+            .map(|_| KeyPair::generate(SignatureType::Ed25519))
+            .collect();
+        let genesis = GenesisConfig {
+            chain_id: None,
+            protocol: Some(ProtocolConfig { block_time: config.block_time, ..ProtocolConfig::default() }),
+            accounts: keys.iter()
+                .map(|key| GenesisAccount { address: key.address(), balance: config.initial_balance })
+                .collect(),
+            validators: keys.iter()
+                .map(|key| GenesisValidator { address: key.address(), stake: config.stake, public_key: key.public_key(), bls_public_key: Vec::new() })
+                .collect(),
+        };
+
+        let network = SimulatedNetwork::new().with_latency(config.network_latency);
+        let shutdown = CancellationToken::new();
+        let mut nodes = Vec::with_capacity(config.validators);
+        let mut tasks = Vec::with_capacity(config.validators);
+
+        for key in keys {
+            let node_address = key.address();
+            let public_key = key.public_key();
+            let mut engine = ZkSacConsensusEngine::from_genesis(&genesis)?;
+            engine.add_validator_key(key)?;
+            let engine = Arc::new(Mutex::new(engine));
+            let endpoint = network.join();
+            let node = DevnetNode {
+                id: endpoint.id(),
+                address: node_address,
+                public_key,
+                engine: engine.clone(),
+                network: network.clone(),
+            };
+            info!("   🔑 Node {} validator {:?}", node.id, node.address);
+
+            tasks.push(tokio::spawn(run_node(engine, node.address, endpoint, config.block_time, shutdown.clone())));
+            nodes.push(node);
+        }
+
+        Ok(Self { nodes, network, tasks, shutdown })
+    }
+
+    pub fn nodes(&self) -> &[DevnetNode] {
+        &self.nodes
+    }
+
+    pub fn node(&self, id: NodeId) -> Option<&DevnetNode> {
+        self.nodes.get(id)
+    }
+
+    /// The network the nodes share, for example to join a syncing node
+    pub fn network(&self) -> &SimulatedNetwork {
+        &self.network
+    }
+
+    /// Wait until every node has reached `height`
+    pub async fn wait_for_height(&self, height: u64, timeout: Duration) -> Result<()> {
+        self.wait_for(height, timeout, false).await
+    }
+
+    /// Wait until every node has finalized block `height`
+    pub async fn wait_for_finalized(&self, height: u64, timeout: Duration) -> Result<()> {
+        self.wait_for(height, timeout, true).await
+    }
+
+    async fn wait_for(&self, height: u64, timeout: Duration, finalized: bool) -> Result<()> {
+        let what = if finalized { "finalized height" } else { "height" };
+        let deadline = Instant::now() + timeout;
+        loop {
+            let mut lowest = u64::MAX;
+            for node in &self.nodes {
+                let reached = if finalized { node.finalized_height().await } else { node.height().await };
+                lowest = lowest.min(reached);
+            }
+            if lowest >= height {
+                return Ok(());
+            }
+            if self.tasks.iter().any(|task| task.is_finished()) {
+                bail!("A devnet node stopped before reaching {} {}", what, height);
+            }
+            if Instant::now() >= deadline {
+                bail!("Devnet did not reach {} {} within {:?} (lowest node at {})", what, height, timeout, lowest);
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    }
+
+    /// Stop every node, returning the first node error
+    pub async fn shutdown(self) -> Result<()> {
+        info!("🛑 Stopping devnet");
+        self.shutdown.cancel();
+        let mut result = Ok(());
+        for (id, task) in self.tasks.into_iter().enumerate() {
+            let outcome = task.await
+                .map_err(|e| anyhow!("Devnet node {} panicked: {}", id, e))
+                .and_then(|outcome| outcome);
+            if let Err(e) = outcome {
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        for node in &self.nodes {
+            node.engine.lock().await.shutdown(Duration::from_secs(1)).await?;
+        }
+        result
+    }
+}
+
+async fn run_node(
+    engine: Arc<Mutex<ZkSacConsensusEngine>>,
+    address: Address,
+    mut endpoint: NetworkEndpoint,
+    block_time: Duration,
+    shutdown: CancellationToken,
+) -> Result<()> {
+    let mut ticker = tokio::time::interval(block_time);
+    let mut sync = SyncServer::default();
+
+    loop {
+        tokio::select! {
+            _ = shutdown.cancelled() => return Ok(()),
+            _ = ticker.tick() => {
+                let block = {
+                    let mut engine = engine.lock().await;
+                    let next = engine.blocks.len() as u64 + 1;
+                    endpoint.broadcast(NetworkMessage::Status { height: next - 1 });
+                    if engine.select_block_producer(next)? != address {
+                        continue;
+                    }
+                    // A failed slot is retried on the next tick
+                    let block = match engine.produce_block(address).await {
+                        Ok(block) => block,
+                        Err(e) => {
+                            warn!("❌ Node {} failed to produce block {}: {:#}", endpoint.id(), next, e);
+                            continue;
+                        }
+                    };
+                    if !engine.validate_block(&block).await? {
+                        warn!("❌ Node {} produced invalid block {}", endpoint.id(), next);
+                        continue;
+                    }
+                    block
+                };
+
+                let delay = fault::delay(FaultPoint::SignatureCollection);
+                if !delay.is_zero() {
+                    debug!("⏳ Node {} signature collection delayed by {:?}", endpoint.id(), delay);
+                    tokio::time::sleep(delay).await;
+                }
+
+                let mut engine = engine.lock().await;
+                // Blocks from peers may have extended the chain while waiting
+                if engine.blocks.len() as u64 + 1 != block.header.block_number {
+                    continue;
+                }
+                endpoint.broadcast(NetworkMessage::Block(Box::new(block.clone())));
+                engine.apply_block(block).await?;
+                attest_head(&mut engine, &mut sync, &endpoint)?;
+                // Slots too short to prove in are proven after the fact, and
+                // the proofs gossiped for whoever produces next to include
+                match engine.prove_deferred().await {
+                    Ok(supplied) => {
+                        for transaction in supplied {
+                            endpoint.broadcast(NetworkMessage::Transaction(transaction));
+                        }
+                    }
+                    Err(e) => warn!("❌ Node {} failed to prove deferred blocks: {:#}", endpoint.id(), e),
+                }
+            }
+            Some((from, message)) = endpoint.recv() => {
+                handle_message(&mut *engine.lock().await, &mut sync, &endpoint, from, message).await?;
+            }
+        }
+    }
+}
+
+/// Act on one message from peer `from`
+#[instrument(name = "handle_message", skip_all, fields(node = endpoint.id(), peer = from, kind = message.kind(), block_number = message.block_number()))]
+async fn handle_message(
+    engine: &mut ZkSacConsensusEngine,
+    sync: &mut SyncServer,
+    endpoint: &NetworkEndpoint,
+    from: NodeId,
+    message: NetworkMessage,
+) -> Result<()> {
+    match message {
+        NetworkMessage::Block(block) => {
+            if let Some(evidence) = engine.detect_offense(&block) {
+                match engine.report_offense(&evidence) {
+                    Ok(transaction) => endpoint.broadcast(NetworkMessage::Transaction(transaction)),
+                    Err(e) => warn!("❌ Node {} could not report {:?}: {:#}", endpoint.id(), evidence.offense(), e),
+                }
+            }
+            let expected = engine.blocks.len() as u64 + 1;
+            if block.header.block_number > expected {
+                debug!("📥 Node {} is behind (at {}, got {}), syncing from node {}",
+                       endpoint.id(), expected - 1, block.header.block_number, from);
+                endpoint.send(from, NetworkMessage::RequestBlocks { from: expected });
+                return Ok(());
+            }
+            if block.header.block_number < expected {
+                return Ok(());
+            }
+            if engine.validate_block(&block).await? {
+                engine.apply_block(*block).await?;
+                attest_head(engine, sync, endpoint)?;
+            } else {
+                warn!("❌ Node {} rejected block {} from node {}", endpoint.id(), expected, from);
+            }
+        }
+        NetworkMessage::Status { height } => {
+            let own = engine.blocks.len() as u64;
+            if height > own {
+                endpoint.send(from, NetworkMessage::RequestBlocks { from: own + 1 });
+            }
+        }
+        NetworkMessage::RequestBlocks { from: start } => {
+            let blocks: Vec<Block> = engine.blocks.iter()
+                .filter(|block| block.header.block_number >= start)
+                .cloned()
+                .collect();
+            if !blocks.is_empty() {
+                endpoint.send(from, NetworkMessage::Blocks(blocks));
+            }
+        }
+        NetworkMessage::Blocks(blocks) => {
+            let height = engine.blocks.len();
+            for block in blocks {
+                if block.header.block_number != engine.blocks.len() as u64 + 1 {
+                    continue;
+                }
+                if !engine.validate_block(&block).await? {
+                    warn!("❌ Node {} rejected synced block {} from node {}",
+                          endpoint.id(), block.header.block_number, from);
+                    break;
+                }
+                engine.apply_block(block).await?;
+            }
+            if engine.blocks.len() > height {
+                attest_head(engine, sync, endpoint)?;
+            }
+        }
+        NetworkMessage::Transaction(transaction) => {
+            if let Err(e) = engine.add_remote_transaction(transaction) {
+                debug!("Node {} dropped gossiped transaction: {}", endpoint.id(), e);
+            }
+        }
+        NetworkMessage::Attestation(attestation) => {
+            if let Err(e) = engine.add_attestation(&attestation) {
+                debug!("Node {} dropped attestation from node {}: {}", endpoint.id(), from, e);
+            }
+            // Votes after finality still strengthen the certificates served to syncing nodes
+            sync.record_attestation(&engine.validator_set, &attestation);
+        }
+        NetworkMessage::RequestHeaders { .. }
+        | NetworkMessage::RequestBodies { .. }
+        | NetworkMessage::RequestSnapshot
+        | NetworkMessage::RequestStateChunk { .. } => {
+            if let Some(response) = sync.respond(engine, &message) {
+                endpoint.send(from, response);
+            }
+        }
+        // Answers to sync requests, which only syncing nodes send
+        NetworkMessage::Headers(_) | NetworkMessage::Bodies(_) | NetworkMessage::Snapshot(_) | NetworkMessage::StateChunk { .. } => {}
+    }
+    Ok(())
+}
+
+/// Attest the node's newest block and gossip the attestation; attesting a
+/// block also attests its ancestors
+fn attest_head(engine: &mut ZkSacConsensusEngine, sync: &mut SyncServer, endpoint: &NetworkEndpoint) -> Result<()> {
+    for attestation in engine.attest(engine.blocks.len() as u64)? {
+        engine.add_attestation(&attestation)?;
+        sync.record_attestation(&engine.validator_set, &attestation);
+        endpoint.broadcast(NetworkMessage::Attestation(attestation));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_validators_agree_on_chain() {
+        let config = DevnetConfig::default()
+            .with_validators(3)
+            .with_block_time(Duration::from_millis(50));
+        let devnet = Devnet::start(config).unwrap();
+        devnet.wait_for_height(4, Duration::from_secs(10)).await.unwrap();
+        devnet.wait_for_finalized(4, Duration::from_secs(10)).await.unwrap();
+
+        let expected = devnet.nodes()[0].block(4).await.unwrap().header.hash();
+        for node in devnet.nodes() {
+            assert_eq!(node.block(4).await.unwrap().header.hash(), expected);
+        }
+        // Every block came from a validator the engine selected for it
+        let validators: std::collections::HashSet<Address> = devnet.nodes().iter().map(|node| node.address).collect();
+        for number in 1..=4 {
+            assert!(validators.contains(&devnet.nodes()[1].block(number).await.unwrap().header.producer));
+        }
+        assert!(devnet.nodes()[2].engine.lock().await.is_finalized(&expected));
+
+        devnet.shutdown().await.unwrap();
+    }
+}
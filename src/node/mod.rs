@@ -0,0 +1,15 @@
+//! Building blocks of the `zk-sac-node` binary: configuration, genesis,
+//! block storage, telemetry, chain sync and the in-process devnet
+
+pub mod config;
+pub mod devnet;
+pub mod network;
+pub mod store;
+pub mod sync;
+pub mod telemetry;
+
+pub use config::{GenesisConfig, NodeConfig};
+pub use devnet::{Devnet, DevnetConfig, DevnetNode};
+pub use network::{NetworkEndpoint, NetworkMessage, SimulatedNetwork};
+pub use store::BlockLog;
+pub use sync::{SyncConfig, SyncServer, Syncer};
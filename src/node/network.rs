@@ -0,0 +1,230 @@
+//! In-process network connecting several nodes in one process
+//!
+//! Every message is delivered to every other joined node, optionally after a
+//! fixed latency. Used by the devnet until the libp2p transport exists.
+//! Deliveries pass the [`FaultPoint::GossipCorruption`] hook, which corrupts
+//! the copy one receiver gets.
+
+use crate::consensus::finality::Attestation;
+use crate::fault::{self, FaultPoint};
+use crate::light_client::HeaderUpdate;
+use crate::state::snapshot::{SnapshotChunk, SnapshotManifest};
+use crate::types::{Block, Transaction};
+use std::sync::{Arc, Mutex};
+use tokio::sync::mpsc;
+use tokio::time::Duration;
+
+pub type NodeId = usize;
+
+/// Sending half of a node's inbox: messages tagged with the sender
+type Inbox = mpsc::UnboundedSender<(NodeId, NetworkMessage)>;
+
+#[derive(Debug, Clone)]
+pub enum NetworkMessage {
+    Block(Box<Block>),
+    Transaction(Transaction),
+    /// Ask a peer for its blocks from `from` on
+    RequestBlocks { from: u64 },
+    Blocks(Vec<Block>),
+    /// Periodic chain height announcement, so lagging peers notice and catch up
+    Status { height: u64 },
+    Attestation(Attestation),
+    /// Ask a peer for up to `max` finalized headers from `from` on
+    RequestHeaders { from: u64, max: u64 },
+    Headers(Vec<HeaderUpdate>),
+    /// Ask a peer for blocks `from..=to`, answered with `Bodies`
+    RequestBodies { from: u64, to: u64 },
+    Bodies(Vec<Block>),
+    /// Ask a peer for the manifest of the state after its finalized block
+    RequestSnapshot,
+    Snapshot(Box<SnapshotManifest>),
+    RequestStateChunk { height: u64, index: usize },
+    StateChunk { height: u64, index: usize, chunk: SnapshotChunk },
+}
+
+impl NetworkMessage {
+    /// Short name of the message type, for logs and trace spans
+    pub fn kind(&self) -> &'static str {
+        match self {
+            NetworkMessage::Block(_) => "block",
+            NetworkMessage::Transaction(_) => "transaction",
+            NetworkMessage::RequestBlocks { .. } => "request_blocks",
+            NetworkMessage::Blocks(_) => "blocks",
+            NetworkMessage::Status { .. } => "status",
+            NetworkMessage::Attestation(_) => "attestation",
+            NetworkMessage::RequestHeaders { .. } => "request_headers",
+            NetworkMessage::Headers(_) => "headers",
+            NetworkMessage::RequestBodies { .. } => "request_bodies",
+            NetworkMessage::Bodies(_) => "bodies",
+            NetworkMessage::RequestSnapshot => "request_snapshot",
+            NetworkMessage::Snapshot(_) => "snapshot",
+            NetworkMessage::RequestStateChunk { .. } => "request_state_chunk",
+            NetworkMessage::StateChunk { .. } => "state_chunk",
+        }
+    }
+
+    /// The block a message carries or is about, if any
+    pub fn block_number(&self) -> Option<u64> {
+        match self {
+            NetworkMessage::Block(block) => Some(block.header.block_number),
+            NetworkMessage::Blocks(blocks) | NetworkMessage::Bodies(blocks) => blocks.first().map(|block| block.header.block_number),
+            NetworkMessage::Headers(updates) => updates.first().map(|update| update.header.block_number),
+            NetworkMessage::RequestBlocks { from }
+            | NetworkMessage::RequestHeaders { from, .. }
+            | NetworkMessage::RequestBodies { from, .. } => Some(*from),
+            NetworkMessage::Attestation(attestation) => Some(attestation.block_number),
+            NetworkMessage::Snapshot(manifest) => Some(manifest.height),
+            NetworkMessage::RequestStateChunk { height, .. } | NetworkMessage::StateChunk { height, .. } => Some(*height),
+            NetworkMessage::Transaction(_) | NetworkMessage::Status { .. } | NetworkMessage::RequestSnapshot => None,
+        }
+    }
+}
+
+/// Damage `message` the way a bad link would: blocks no longer link to
+/// their parent, transactions and attestations carry a broken signature,
+/// state chunks no longer match their hash
+fn corrupt(message: &mut NetworkMessage) {
+    match message {
+        NetworkMessage::Block(block) => block.header.previous_hash.0[0] ^= 0xff,
+        NetworkMessage::Blocks(blocks) | NetworkMessage::Bodies(blocks) => {
+            if let Some(block) = blocks.first_mut() {
+                block.header.previous_hash.0[0] ^= 0xff;
+            }
+        }
+        NetworkMessage::Headers(updates) => {
+            if let Some(update) = updates.first_mut() {
+                update.header.previous_hash.0[0] ^= 0xff;
+            }
+        }
+        NetworkMessage::Transaction(transaction) => transaction.signature.push(0xff),
+        NetworkMessage::Attestation(attestation) => attestation.vote.signature.push(0xff),
+        NetworkMessage::StateChunk { chunk, .. } => chunk.data.push(0xff),
+        NetworkMessage::RequestBlocks { .. }
+        | NetworkMessage::Status { .. }
+        | NetworkMessage::RequestHeaders { .. }
+        | NetworkMessage::RequestBodies { .. }
+        | NetworkMessage::RequestSnapshot
+        | NetworkMessage::Snapshot(_)
+        | NetworkMessage::RequestStateChunk { .. } => {}
+    }
+}
+
+/// The copy of `message` a receiver gets
+fn deliverable(message: &NetworkMessage) -> NetworkMessage {
+    let mut message = message.clone();
+    if fault::triggered(FaultPoint::GossipCorruption) {
+        corrupt(&mut message);
+    }
+    message
+}
+
+#[derive(Clone, Default)]
+pub struct SimulatedNetwork {
+    peers: Arc<Mutex<Vec<Inbox>>>,
+    latency: Duration,
+}
+
+impl SimulatedNetwork {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Delay every delivery by `latency`
+    pub fn with_latency(mut self, latency: Duration) -> Self {
+        self.latency = latency;
+        self
+    }
+
+    /// Connect a new node
+    pub fn join(&self) -> NetworkEndpoint {
+        let (sender, inbox) = mpsc::unbounded_channel();
+        let mut peers = self.peers.lock().unwrap();
+        peers.push(sender);
+        NetworkEndpoint { id: peers.len() - 1, network: self.clone(), inbox }
+    }
+
+    pub fn peer_count(&self) -> usize {
+        self.peers.lock().unwrap().len()
+    }
+
+    /// Deliver `message` to every node except `from`
+    pub fn broadcast_from(&self, from: NodeId, message: NetworkMessage) {
+        let targets: Vec<_> = self.peers.lock().unwrap().iter()
+            .enumerate()
+            .filter(|(id, _)| *id != from)
+            .map(|(_, peer)| peer.clone())
+            .collect();
+        self.deliver(from, targets, message);
+    }
+
+    /// Deliver `message` to node `to` only
+    pub fn send(&self, from: NodeId, to: NodeId, message: NetworkMessage) {
+        let target = self.peers.lock().unwrap().get(to).cloned();
+        self.deliver(from, target.into_iter().collect(), message);
+    }
+
+    fn deliver(&self, from: NodeId, targets: Vec<Inbox>, message: NetworkMessage) {
+        if self.latency.is_zero() {
+            for peer in targets {
+                // A closed inbox means the node has stopped
+                let _ = peer.send((from, deliverable(&message)));
+            }
+        } else {
+            let latency = self.latency;
+            tokio::spawn(async move {
+                tokio::time::sleep(latency).await;
+                for peer in targets {
+                    let _ = peer.send((from, deliverable(&message)));
+                }
+            });
+        }
+    }
+}
+
+/// One node's connection to a [`SimulatedNetwork`]
+pub struct NetworkEndpoint {
+    id: NodeId,
+    network: SimulatedNetwork,
+    inbox: mpsc::UnboundedReceiver<(NodeId, NetworkMessage)>,
+}
+
+impl NetworkEndpoint {
+    pub fn id(&self) -> NodeId {
+        self.id
+    }
+
+    /// Send `message` to every other node
+    pub fn broadcast(&self, message: NetworkMessage) {
+        self.network.broadcast_from(self.id, message);
+    }
+
+    /// Send `message` to node `to`
+    pub fn send(&self, to: NodeId, message: NetworkMessage) {
+        self.network.send(self.id, to, message);
+    }
+
+    /// Next message and its sender
+    pub async fn recv(&mut self) -> Option<(NodeId, NetworkMessage)> {
+        self.inbox.recv().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Address;
+
+    #[tokio::test]
+    async fn test_broadcast_reaches_every_other_node() {
+        let network = SimulatedNetwork::new();
+        let sender = network.join();
+        let mut receivers = vec![network.join(), network.join()];
+
+        sender.broadcast(NetworkMessage::Transaction(Transaction::new(Address::new(1), Address::new(2), 5, 0)));
+        for receiver in &mut receivers {
+            let (from, message) = receiver.recv().await.unwrap();
+            assert_eq!(from, sender.id());
+            assert!(matches!(message, NetworkMessage::Transaction(tx) if tx.value == 5));
+        }
+    }
+}
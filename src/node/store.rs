@@ -0,0 +1,74 @@
+//! Append-only block log
+//!
+//! Blocks are stored in the [`crate::serialization::stream`] block format,
+//! one after another, so the same file doubles as an export that another
+//! node can `import`.
+
+use crate::fault::{self, FaultPoint};
+use crate::serialization::{DecodeLimits, FrameReader, FrameWriter, read_next_block, write_block};
+use crate::types::Block;
+use anyhow::{Context, Result};
+use std::path::{Path, PathBuf};
+use tokio::fs::{File, OpenOptions};
+use tokio::io::BufReader;
+
+pub struct BlockLog {
+    path: PathBuf,
+    limits: DecodeLimits,
+}
+
+impl BlockLog {
+    pub fn new(path: impl Into<PathBuf>, limits: DecodeLimits) -> Self {
+        Self { path: path.into(), limits }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Every block in the log, oldest first; empty if the log does not exist yet
+    pub async fn read_all(&self) -> Result<Vec<Block>> {
+        if !tokio::fs::try_exists(&self.path).await? {
+            return Ok(Vec::new());
+        }
+        read_blocks(&self.path, self.limits).await
+    }
+
+    pub async fn append(&self, block: &Block) -> Result<()> {
+        fault::check(FaultPoint::StorageWrite)?;
+        let file = OpenOptions::new().create(true).append(true).open(&self.path).await
+            .with_context(|| format!("Failed to open block log {}", self.path.display()))?;
+        let mut frames = FrameWriter::new(file);
+        write_block(&mut frames, block).await?;
+        frames.into_inner().sync_data().await?;
+        Ok(())
+    }
+}
+
+/// Read a file of blocks written by [`write_block`]
+pub async fn read_blocks(path: &Path, limits: DecodeLimits) -> Result<Vec<Block>> {
+    let file = File::open(path).await
+        .with_context(|| format!("Failed to open {}", path.display()))?;
+    let mut frames = FrameReader::new(BufReader::new(file), limits);
+    let mut blocks = Vec::new();
+    while let Some(block) = read_next_block(&mut frames).await
+        .with_context(|| format!("Corrupt block {} in {}", blocks.len(), path.display()))?
+    {
+        blocks.push(block);
+    }
+    Ok(blocks)
+}
+
+/// Write `blocks` to a new file at `path`
+pub async fn write_blocks<'a>(path: &Path, blocks: impl IntoIterator<Item = &'a Block>) -> Result<usize> {
+    let file = File::create(path).await
+        .with_context(|| format!("Failed to create {}", path.display()))?;
+    let mut frames = FrameWriter::new(file);
+    let mut written = 0;
+    for block in blocks {
+        write_block(&mut frames, block).await?;
+        written += 1;
+    }
+    frames.into_inner().sync_all().await?;
+    Ok(written)
+}
@@ -0,0 +1,599 @@
+//! Header-first chain sync for nodes joining late
+//!
+//! A [`Syncer`] downloads headers from its peers and accepts each only once a
+//! [`LightClient`] has checked the header's quorum certificate and recursive
+//! proof. Once it trusts the header of the block a peer's finalized state
+//! snapshot is of, it fetches the block bodies up to that block and the
+//! snapshot's state chunks from all peers at once. Every body is checked
+//! against its trusted header and every chunk against the manifest, which
+//! is itself checked against the header. Requests a peer leaves unanswered
+//! are retried with another peer.
+//!
+//! Everything verified is saved to a [`SyncCheckpoint`] after every
+//! response, so a sync restarted with the same checkpoint file continues
+//! where it stopped. Peers answer sync requests through a [`SyncServer`].
+
+use super::network::{NetworkEndpoint, NetworkMessage, NodeId};
+use crate::consensus::engine::ZkSacConsensusEngine;
+use crate::consensus::error::ConsensusError;
+use crate::consensus::finality::Attestation;
+use crate::crypto::keystore::verify_signature;
+use crate::light_client::{HeaderUpdate, LightClient, ProofVerifier, TrustedHeader, header_signing_bytes};
+use crate::state::snapshot::{DEFAULT_CHUNK_ACCOUNTS, SnapshotChunk, SnapshotManifest, StateSnapshot};
+use crate::types::*;
+use anyhow::{Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+use tokio::sync::watch;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// Most headers or bodies a server sends in one response
+const MAX_BLOCKS_PER_RESPONSE: u64 = 256;
+/// Snapshots a server keeps exported for peers still fetching their chunks
+const SERVED_SNAPSHOTS: usize = 2;
+
+#[derive(Debug, Clone)]
+pub struct SyncConfig {
+    /// Headers asked for per request
+    pub header_batch: u64,
+    /// Block bodies asked for per request
+    pub body_batch: u64,
+    /// How long a peer has to answer before the request goes to another
+    pub request_timeout: Duration,
+    /// Unanswered or rejected requests in a row before giving up
+    pub max_failures: usize,
+    /// File progress is saved to; without one a restarted sync starts over
+    pub checkpoint_path: Option<PathBuf>,
+}
+
+impl Default for SyncConfig {
+    fn default() -> Self {
+        Self {
+            header_batch: 128,
+            body_batch: 32,
+            request_timeout: Duration::from_secs(2),
+            max_failures: 16,
+            checkpoint_path: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SyncPhase {
+    #[default]
+    Headers,
+    /// Bodies and state chunks, downloaded together
+    Download,
+    Done,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SyncProgress {
+    pub phase: SyncPhase,
+    /// Trusted headers
+    pub headers: u64,
+    /// Height of the snapshot being synced to, once known
+    pub target: Option<u64>,
+    pub bodies: u64,
+    pub chunks: usize,
+    pub total_chunks: usize,
+}
+
+/// Everything a sync has verified so far
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SyncCheckpoint {
+    /// Trusted headers from block 1 on
+    pub headers: Vec<TrustedHeader>,
+    pub manifest: Option<SnapshotManifest>,
+    pub bodies: BTreeMap<u64, Block>,
+    pub chunks: BTreeMap<usize, SnapshotChunk>,
+}
+
+impl SyncCheckpoint {
+    /// The checkpoint saved at `path`, if there is one
+    pub fn load(path: &Path) -> Result<Option<Self>> {
+        match std::fs::read(path) {
+            Ok(bytes) => bincode::deserialize(&bytes)
+                .map(Some)
+                .map_err(|e| anyhow!("Corrupt sync checkpoint {}: {}", path.display(), e)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(anyhow!("Failed to read sync checkpoint {}: {}", path.display(), e)),
+        }
+    }
+
+    /// Write to a temporary file first, so a crash never leaves half a checkpoint
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("partial");
+        std::fs::write(&partial, bincode::serialize(self)?)?;
+        std::fs::rename(&partial, path)?;
+        Ok(())
+    }
+
+    pub fn header(&self, number: u64) -> Option<&BlockHeader> {
+        let index = usize::try_from(number.checked_sub(1)?).ok()?;
+        self.headers.get(index)
+            .map(|trusted| &trusted.header)
+            .filter(|header| header.block_number == number)
+    }
+
+    pub fn height(&self) -> u64 {
+        self.headers.last().map_or(0, |trusted| trusted.header.block_number)
+    }
+}
+
+/// Result of a completed sync
+#[derive(Debug, Clone)]
+pub struct SyncedChain {
+    /// Header of the block the snapshot is of
+    pub header: BlockHeader,
+    pub snapshot: StateSnapshot,
+    /// Bodies of every block up to and including that block
+    pub blocks: Vec<Block>,
+}
+
+impl SyncedChain {
+    /// Engine continuing from the synced block; the rebuilt state is checked
+    /// against the header's state root
+    pub fn into_engine(self, config: ProtocolConfig) -> Result<ZkSacConsensusEngine, ConsensusError> {
+        ZkSacConsensusEngine::from_snapshot(&self.snapshot, &self.header, config)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Fetch {
+    Bodies { from: u64, to: u64 },
+    Chunk(usize),
+}
+
+impl Fetch {
+    fn request(&self, height: u64) -> NetworkMessage {
+        match *self {
+            Fetch::Bodies { from, to } => NetworkMessage::RequestBodies { from, to },
+            Fetch::Chunk(index) => NetworkMessage::RequestStateChunk { height, index },
+        }
+    }
+}
+
+pub struct Syncer {
+    endpoint: NetworkEndpoint,
+    peers: Vec<NodeId>,
+    client: LightClient,
+    checkpoint: SyncCheckpoint,
+    config: SyncConfig,
+    progress: watch::Sender<SyncProgress>,
+    /// Peer the next header or manifest request goes to
+    next_peer: usize,
+}
+
+impl Syncer {
+    /// Sync from `peers`, trusting headers signed by `validators`, the
+    /// genesis validator set; resumes from `config.checkpoint_path` if a
+    /// checkpoint was saved there
+    pub fn new(endpoint: NetworkEndpoint, peers: Vec<NodeId>, validators: ValidatorSet, config: SyncConfig) -> Result<Self> {
+        if peers.is_empty() {
+            bail!("Sync needs at least one peer");
+        }
+        let checkpoint = match &config.checkpoint_path {
+            Some(path) => SyncCheckpoint::load(path)?.unwrap_or_default(),
+            None => SyncCheckpoint::default(),
+        };
+        let client = match checkpoint.headers.last() {
+            Some(last) => {
+                info!("🔁 Resuming sync from header {}", last.header.block_number);
+                LightClient::from_checkpoint(last.header.clone(), validators)
+            }
+            None => LightClient::new(validators),
+        };
+        let syncer = Self {
+            endpoint,
+            peers,
+            client,
+            checkpoint,
+            config,
+            progress: watch::channel(SyncProgress::default()).0,
+            next_peer: 0,
+        };
+        syncer.report(SyncPhase::Headers);
+        Ok(syncer)
+    }
+
+    /// Check recursive proofs with `verifier` instead of the light client's default
+    pub fn with_verifier(mut self, verifier: Box<dyn ProofVerifier>) -> Self {
+        self.client = self.client.with_verifier(verifier);
+        self
+    }
+
+    pub fn progress(&self) -> watch::Receiver<SyncProgress> {
+        self.progress.subscribe()
+    }
+
+    pub async fn run(mut self) -> Result<SyncedChain> {
+        let manifest = match self.checkpoint.manifest.clone() {
+            Some(manifest) => manifest,
+            None => self.fetch_manifest().await?,
+        };
+        self.report(SyncPhase::Download);
+        self.download(&manifest).await?;
+
+        let header = self.checkpoint.header(manifest.height)
+            .cloned()
+            .ok_or_else(|| anyhow!("No trusted header for snapshot block {}", manifest.height))?;
+        let checkpoint = std::mem::take(&mut self.checkpoint);
+        self.report(SyncPhase::Done);
+        info!("✅ Synced {} blocks and {} state chunks up to block {}", checkpoint.bodies.len(), checkpoint.chunks.len(), manifest.height);
+        Ok(SyncedChain {
+            header,
+            snapshot: StateSnapshot { manifest, chunks: checkpoint.chunks.into_values().collect() },
+            blocks: checkpoint.bodies.into_values().collect(),
+        })
+    }
+
+    /// Manifest of a peer's finalized state, with the headers up to its block
+    async fn fetch_manifest(&mut self) -> Result<SnapshotManifest> {
+        let mut failures = 0;
+        loop {
+            if failures > self.config.max_failures {
+                bail!("No peer served a usable state snapshot");
+            }
+            let peer = self.rotate_peer();
+            self.endpoint.send(peer, NetworkMessage::RequestSnapshot);
+            let deadline = Instant::now() + self.config.request_timeout;
+            let Some(NetworkMessage::Snapshot(manifest)) = self.response_from(peer, deadline).await else {
+                failures += 1;
+                continue;
+            };
+            let manifest = *manifest;
+
+            self.sync_headers(manifest.height).await?;
+            let header = self.checkpoint.header(manifest.height)
+                .ok_or_else(|| anyhow!("No trusted header for snapshot block {}", manifest.height))?;
+            if let Err(e) = manifest.verify_header(header) {
+                warn!("❌ Peer {} sent a snapshot manifest for an untrusted block: {}", peer, e);
+                failures += 1;
+                continue;
+            }
+            info!("📸 Syncing to the snapshot of block {} ({} chunks)", manifest.height, manifest.chunk_hashes.len());
+            self.checkpoint.manifest = Some(manifest.clone());
+            self.save()?;
+            return Ok(manifest);
+        }
+    }
+
+    /// Download and verify headers until `target` is trusted
+    async fn sync_headers(&mut self, target: u64) -> Result<()> {
+        let mut failures = 0;
+        while self.checkpoint.height() < target {
+            let from = self.checkpoint.height() + 1;
+            let peer = self.rotate_peer();
+            self.endpoint.send(peer, NetworkMessage::RequestHeaders { from, max: self.config.header_batch.min(target - from + 1) });
+            let deadline = Instant::now() + self.config.request_timeout;
+            let accepted = match self.response_from(peer, deadline).await {
+                Some(NetworkMessage::Headers(updates)) => self.accept_headers(peer, updates),
+                _ => 0,
+            };
+            if accepted == 0 {
+                failures += 1;
+                if failures > self.config.max_failures {
+                    bail!("Could not get headers from {} on from any peer", from);
+                }
+                continue;
+            }
+            failures = 0;
+            self.save()?;
+            self.report(SyncPhase::Headers);
+        }
+        Ok(())
+    }
+
+    /// Keep the headers of `updates` up to the first one the light client
+    /// rejects; returns how many were kept
+    fn accept_headers(&mut self, peer: NodeId, updates: Vec<HeaderUpdate>) -> usize {
+        let mut accepted = 0;
+        for update in updates {
+            let trusted = TrustedHeader { header: update.header.clone(), recursive_proof: Some(update.recursive_proof.clone()) };
+            if let Err(e) = self.client.apply_update(update) {
+                warn!("❌ Peer {} sent header {} the light client rejected: {}", peer, trusted.header.block_number, e);
+                break;
+            }
+            self.checkpoint.headers.push(trusted);
+            accepted += 1;
+        }
+        debug!("📥 Accepted {} headers from peer {}", accepted, peer);
+        accepted
+    }
+
+    /// Fetch the missing bodies up to the snapshot block and the missing
+    /// state chunks, keeping one request in flight per peer. A request a
+    /// peer failed goes to the peers that have not tried it first, since a
+    /// peer whose finalized block moved on may no longer have the snapshot.
+    async fn download(&mut self, manifest: &SnapshotManifest) -> Result<()> {
+        let mut missing = Vec::new();
+        let mut from = 1;
+        while from <= manifest.height {
+            let to = (from + self.config.body_batch.max(1) - 1).min(manifest.height);
+            if (from..=to).any(|number| !self.checkpoint.bodies.contains_key(&number)) {
+                missing.push(Fetch::Bodies { from, to });
+            }
+            from = to + 1;
+        }
+        missing.extend((0..manifest.chunk_hashes.len())
+            .filter(|index| !self.checkpoint.chunks.contains_key(index))
+            .map(Fetch::Chunk));
+
+        // Each fetch with the peers that failed it
+        let mut pending: VecDeque<(Fetch, Vec<NodeId>)> = missing.into_iter().map(|fetch| (fetch, Vec::new())).collect();
+        let mut in_flight: HashMap<NodeId, (Fetch, Vec<NodeId>, Instant)> = HashMap::new();
+        let mut failures = 0;
+        while !pending.is_empty() || !in_flight.is_empty() {
+            // Fetches every peer has failed start over
+            for (_, tried) in pending.iter_mut().filter(|(_, tried)| tried.len() >= self.peers.len()) {
+                tried.clear();
+            }
+            for &peer in &self.peers {
+                if in_flight.contains_key(&peer) {
+                    continue;
+                }
+                let Some(position) = pending.iter().position(|(_, tried)| !tried.contains(&peer)) else { continue };
+                let (fetch, tried) = pending.remove(position).expect("position is in range");
+                self.endpoint.send(peer, fetch.request(manifest.height));
+                in_flight.insert(peer, (fetch, tried, Instant::now() + self.config.request_timeout));
+            }
+
+            let deadline = in_flight.values().map(|(_, _, deadline)| *deadline).min().unwrap_or_else(Instant::now);
+            let (peer, message) = match tokio::time::timeout_at(deadline, self.endpoint.recv()).await {
+                Ok(Some(received)) => received,
+                Ok(None) => bail!("Network endpoint closed during sync"),
+                Err(_) => {
+                    let now = Instant::now();
+                    let expired: Vec<NodeId> = in_flight.iter()
+                        .filter(|(_, (_, _, deadline))| *deadline <= now)
+                        .map(|(peer, _)| *peer)
+                        .collect();
+                    for peer in expired {
+                        let (fetch, mut tried, _) = in_flight.remove(&peer).expect("expired fetch is in flight");
+                        debug!("⌛ Peer {} did not answer {:?}", peer, fetch);
+                        tried.push(peer);
+                        pending.push_back((fetch, tried));
+                        failures += 1;
+                    }
+                    if failures > self.config.max_failures {
+                        bail!("Sync stalled: {} requests in a row went unanswered or were rejected", failures);
+                    }
+                    continue;
+                }
+            };
+            let Some((fetch, _, _)) = in_flight.get(&peer) else { continue };
+            let complete = match (*fetch, message) {
+                (Fetch::Bodies { from, to }, NetworkMessage::Bodies(blocks)) => self.accept_bodies(peer, from, to, blocks),
+                (Fetch::Chunk(index), NetworkMessage::StateChunk { height, index: got, chunk }) if height == manifest.height && got == index => {
+                    self.accept_chunk(peer, manifest, index, chunk)
+                }
+                // Gossip, or the answer to a request that timed out
+                _ => continue,
+            };
+            let (fetch, mut tried, _) = in_flight.remove(&peer).expect("answered fetch is in flight");
+            if complete {
+                failures = 0;
+            } else {
+                tried.push(peer);
+                pending.push_back((fetch, tried));
+                failures += 1;
+                if failures > self.config.max_failures {
+                    bail!("Sync stalled: {} requests in a row went unanswered or were rejected", failures);
+                }
+            }
+            self.save()?;
+            self.report(SyncPhase::Download);
+        }
+        Ok(())
+    }
+
+    /// Keep the bodies that match their trusted header; returns whether all
+    /// of `from..=to` are now present
+    fn accept_bodies(&mut self, peer: NodeId, from: u64, to: u64, blocks: Vec<Block>) -> bool {
+        for block in blocks {
+            let number = block.header.block_number;
+            if !(from..=to).contains(&number) {
+                continue;
+            }
+            let Some(header) = self.checkpoint.header(number) else { continue };
+            if block.header.hash() != header.hash() || Block::transactions_root(&block.transactions) != header.merkle_root {
+                warn!("❌ Peer {} sent a body for block {} that does not match its header", peer, number);
+                continue;
+            }
+            self.checkpoint.bodies.insert(number, block);
+        }
+        (from..=to).all(|number| self.checkpoint.bodies.contains_key(&number))
+    }
+
+    fn accept_chunk(&mut self, peer: NodeId, manifest: &SnapshotManifest, index: usize, chunk: SnapshotChunk) -> bool {
+        if let Err(e) = manifest.verify_chunk(index, &chunk) {
+            warn!("❌ Peer {} sent a bad state chunk: {}", peer, e);
+            return false;
+        }
+        self.checkpoint.chunks.insert(index, chunk);
+        true
+    }
+
+    /// The next message from `peer` that answers a request, skipping gossip
+    async fn response_from(&mut self, peer: NodeId, deadline: Instant) -> Option<NetworkMessage> {
+        loop {
+            let (from, message) = tokio::time::timeout_at(deadline, self.endpoint.recv()).await.ok()??;
+            if from == peer && matches!(message, NetworkMessage::Headers(_) | NetworkMessage::Snapshot(_)) {
+                return Some(message);
+            }
+        }
+    }
+
+    fn rotate_peer(&mut self) -> NodeId {
+        let peer = self.peers[self.next_peer % self.peers.len()];
+        self.next_peer += 1;
+        peer
+    }
+
+    fn save(&self) -> Result<()> {
+        match &self.config.checkpoint_path {
+            Some(path) => self.checkpoint.save(path),
+            None => Ok(()),
+        }
+    }
+
+    fn report(&self, phase: SyncPhase) {
+        let manifest = self.checkpoint.manifest.as_ref();
+        self.progress.send_replace(SyncProgress {
+            phase,
+            headers: self.checkpoint.height(),
+            target: manifest.map(|manifest| manifest.height),
+            bodies: self.checkpoint.bodies.len() as u64,
+            chunks: self.checkpoint.chunks.len(),
+            total_chunks: manifest.map_or(0, |manifest| manifest.chunk_hashes.len()),
+        });
+    }
+}
+
+/// Answers the sync requests of peers from a node's engine
+pub struct SyncServer {
+    /// Valid votes seen for each block, kept after finality so served
+    /// headers carry a certificate a light client accepts
+    votes: BTreeMap<u64, HashMap<BlockHash, Vec<ValidatorSignature>>>,
+    snapshots: BTreeMap<u64, StateSnapshot>,
+    chunk_accounts: usize,
+}
+
+impl Default for SyncServer {
+    fn default() -> Self {
+        Self::new(DEFAULT_CHUNK_ACCOUNTS)
+    }
+}
+
+impl SyncServer {
+    /// Serve snapshots with at most `chunk_accounts` accounts per chunk
+    pub fn new(chunk_accounts: usize) -> Self {
+        Self { votes: BTreeMap::new(), snapshots: BTreeMap::new(), chunk_accounts }
+    }
+
+    /// Keep `attestation` for the certificate of its block if its signature
+    /// is valid; returns whether it was kept
+    pub fn record_attestation(&mut self, validators: &ValidatorSet, attestation: &Attestation) -> bool {
+        let vote = &attestation.vote;
+        let Some(validator) = validators.validators.iter().find(|validator| validator.address == vote.validator_address) else {
+            return false;
+        };
+        let votes = self.votes.entry(attestation.block_number).or_default().entry(attestation.block_hash).or_default();
+        if votes.iter().any(|known| known.validator_address == vote.validator_address) {
+            return false;
+        }
+        if verify_signature(&vote.sig_type, &validator.public_key, &header_signing_bytes(attestation.block_number, &attestation.block_hash), &vote.signature).is_err() {
+            return false;
+        }
+        votes.push(vote.clone());
+        true
+    }
+
+    /// Answer to `request`, or `None` if it is not a sync request or this
+    /// node cannot serve it
+    pub fn respond(&mut self, engine: &ZkSacConsensusEngine, request: &NetworkMessage) -> Option<NetworkMessage> {
+        match *request {
+            NetworkMessage::RequestHeaders { from, max } => {
+                let finalized = engine.finalized_height();
+                let updates: Vec<HeaderUpdate> = engine.blocks.iter()
+                    .filter(|block| block.header.block_number >= from && block.header.block_number <= finalized)
+                    .take(max.min(MAX_BLOCKS_PER_RESPONSE) as usize)
+                    // Headers are only useful to a light client once proven
+                    .take_while(|block| !matches!(block.recursive_proof.proof_type, ProofType::Deferred))
+                    .map(|block| self.header_update(block))
+                    .collect();
+                Some(NetworkMessage::Headers(updates))
+            }
+            NetworkMessage::RequestBodies { from, to } => {
+                let blocks: Vec<Block> = engine.blocks.iter()
+                    .filter(|block| (from..=to).contains(&block.header.block_number))
+                    .take(MAX_BLOCKS_PER_RESPONSE as usize)
+                    .cloned()
+                    .collect();
+                Some(NetworkMessage::Bodies(blocks))
+            }
+            NetworkMessage::RequestSnapshot => {
+                let snapshot = self.snapshot(engine, engine.finalized_height())?;
+                Some(NetworkMessage::Snapshot(Box::new(snapshot.manifest.clone())))
+            }
+            NetworkMessage::RequestStateChunk { height, index } => {
+                let chunk = self.snapshot(engine, height)?.chunks.get(index)?.clone();
+                Some(NetworkMessage::StateChunk { height, index, chunk })
+            }
+            _ => None,
+        }
+    }
+
+    /// `block` with every vote seen for it
+    fn header_update(&self, block: &Block) -> HeaderUpdate {
+        let mut update = HeaderUpdate::from_block(block);
+        let seen = self.votes.get(&block.header.block_number).and_then(|votes| votes.get(&update.certificate.block_hash));
+        for vote in seen.into_iter().flatten() {
+            if !update.certificate.signatures.iter().any(|known| known.validator_address == vote.validator_address) {
+                update.certificate.signatures.push(vote.clone());
+            }
+        }
+        update
+    }
+
+    /// Snapshot after block `height`, exported on first use
+    fn snapshot(&mut self, engine: &ZkSacConsensusEngine, height: u64) -> Option<&StateSnapshot> {
+        if !self.snapshots.contains_key(&height) {
+            let snapshot = engine.export_snapshot_at(height, self.chunk_accounts).ok()?;
+            self.snapshots.insert(height, snapshot);
+            while self.snapshots.len() > SERVED_SNAPSHOTS {
+                self.snapshots.pop_first();
+            }
+        }
+        self.snapshots.get(&height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::devnet::{Devnet, DevnetConfig};
+
+    #[tokio::test]
+    async fn test_syncs_finalized_chain_from_devnet_and_resumes() {
+        let config = DevnetConfig::default()
+            .with_validators(3)
+            .with_block_time(Duration::from_millis(50));
+        let stake = config.stake;
+        let devnet = Devnet::start(config).unwrap();
+        devnet.wait_for_finalized(5, Duration::from_secs(10)).await.unwrap();
+
+        let validators = ValidatorSet::new(devnet.nodes().iter()
+            .map(|node| Validator { address: node.address, stake, public_key: node.public_key.clone(), performance_score: 1.0, bls_public_key: Vec::new() })
+            .collect());
+        let peers: Vec<NodeId> = devnet.nodes().iter().map(|node| node.id).collect();
+        let path = std::env::temp_dir().join(format!("zksac-sync-{}.bin", uuid::Uuid::new_v4()));
+        let config = SyncConfig { body_batch: 2, checkpoint_path: Some(path.clone()), ..SyncConfig::default() };
+
+        let syncer = Syncer::new(devnet.network().join(), peers.clone(), validators.clone(), config.clone()).unwrap();
+        let progress = syncer.progress();
+        let synced = syncer.run().await.unwrap();
+        assert_eq!(progress.borrow().phase, SyncPhase::Done);
+        let height = synced.header.block_number;
+        assert!(height >= 5);
+        assert_eq!(synced.blocks.len() as u64, height);
+        assert_eq!(synced.blocks.last().unwrap().header.hash(), devnet.nodes()[0].block(height).await.unwrap().header.hash());
+        let engine = synced.clone().into_engine(ProtocolConfig::default()).unwrap();
+        assert_eq!(engine.height(), height);
+
+        // A restarted sync finds everything in the checkpoint
+        let checkpoint = SyncCheckpoint::load(&path).unwrap().unwrap();
+        assert_eq!(checkpoint.height(), height);
+        let resumed = Syncer::new(devnet.network().join(), peers, validators, config).unwrap().run().await.unwrap();
+        assert_eq!(resumed.header.hash(), synced.header.hash());
+
+        std::fs::remove_file(&path).unwrap();
+        devnet.shutdown().await.unwrap();
+    }
+}
@@ -0,0 +1,94 @@
+//! Log output and trace export for the node
+//!
+//! Logs go to stdout, filtered by `RUST_LOG` or else `[telemetry] log_filter`.
+//! With the `otel` feature and an `otlp_endpoint`, the spans the engine
+//! opens around block production, proving, validation and message handling
+//! are also exported over OTLP/gRPC, for example to Jaeger on port 4317.
+
+use super::config::TelemetrySettings;
+use anyhow::{Result, anyhow};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{EnvFilter, fmt};
+
+/// Flushes exported spans when dropped; keep it alive until the node exits
+#[must_use = "dropping the guard stops trace export"]
+pub struct TelemetryGuard {
+    #[cfg(feature = "otel")]
+    provider: Option<opentelemetry_sdk::trace::TracerProvider>,
+}
+
+impl Drop for TelemetryGuard {
+    fn drop(&mut self) {
+        #[cfg(feature = "otel")]
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush trace export: {}", e);
+            }
+        }
+    }
+}
+
+/// Install the global subscriber for `settings`
+pub fn init(settings: &TelemetrySettings) -> Result<TelemetryGuard> {
+    let filter = match EnvFilter::try_from_default_env() {
+        Ok(filter) => filter,
+        Err(_) => EnvFilter::try_new(&settings.log_filter)
+            .map_err(|e| anyhow!("Invalid [telemetry] log_filter {:?}: {}", settings.log_filter, e))?,
+    };
+    let registry = tracing_subscriber::registry().with(filter).with(fmt::layer());
+
+    #[cfg(feature = "otel")]
+    {
+        let provider = otlp::provider(settings)?;
+        let layer = provider.as_ref().map(otlp::layer);
+        registry.with(layer).try_init()?;
+        Ok(TelemetryGuard { provider })
+    }
+
+    #[cfg(not(feature = "otel"))]
+    {
+        registry.try_init()?;
+        if !settings.otlp_endpoint.is_empty() {
+            tracing::warn!("⚠️  [telemetry] otlp_endpoint is set but this binary was built without the `otel` feature");
+        }
+        Ok(TelemetryGuard {})
+    }
+}
+
+#[cfg(feature = "otel")]
+mod otlp {
+    use super::TelemetrySettings;
+    use anyhow::Result;
+    use opentelemetry::KeyValue;
+    use opentelemetry::trace::TracerProvider as _;
+    use opentelemetry_otlp::WithExportConfig;
+    use opentelemetry_sdk::trace::TracerProvider;
+    use opentelemetry_sdk::{Resource, runtime};
+    use tracing::Subscriber;
+    use tracing_subscriber::Layer;
+    use tracing_subscriber::registry::LookupSpan;
+
+    /// Batching span exporter, if an endpoint is configured
+    pub(super) fn provider(settings: &TelemetrySettings) -> Result<Option<TracerProvider>> {
+        if settings.otlp_endpoint.is_empty() {
+            return Ok(None);
+        }
+        let exporter = opentelemetry_otlp::SpanExporter::builder()
+            .with_tonic()
+            .with_endpoint(&settings.otlp_endpoint)
+            .build()?;
+        let provider = TracerProvider::builder()
+            .with_batch_exporter(exporter, runtime::Tokio)
+            .with_resource(Resource::new([KeyValue::new("service.name", settings.service_name.clone())]))
+            .build();
+        Ok(Some(provider))
+    }
+
+    pub(super) fn layer<S>(provider: &TracerProvider) -> impl Layer<S>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(provider.tracer(env!("CARGO_PKG_NAME")))
+    }
+}
@@ -0,0 +1,357 @@
+//! Threshold alerting on monitor metrics
+//!
+//! Rules are evaluated every time the monitor records a block or an error.
+//! An alert fires once when its rule starts breaching and is not repeated
+//! until the rule has recovered, so notifiers are not flooded while a
+//! condition persists.
+
+use super::errors::ErrorCategory;
+use super::window::{Window, WindowStats};
+use anyhow::{Result, anyhow};
+use async_trait::async_trait;
+use serde::{Serialize, Deserialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{error, info, warn};
+
+/// A condition the monitor watches for
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AlertRule {
+    /// Latest block took longer than `factor` times the target block time
+    BlockTimeAboveTarget { target_ms: u64, factor: f64 },
+    /// Errors per block over `window` exceeded `max_rate`
+    ErrorRateAbove { window: Window, max_rate: f64 },
+    /// Errors of one category per block over `window` exceeded `max_rate`
+    CategoryErrorRateAbove { category: ErrorCategory, window: Window, max_rate: f64 },
+    /// Finalized epoch trails the current epoch by more than `max_epochs`
+    FinalityLagAbove { max_epochs: u64 },
+}
+
+impl AlertRule {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertRule::BlockTimeAboveTarget { .. } => "block_time_above_target",
+            AlertRule::ErrorRateAbove { .. } => "error_rate_above",
+            AlertRule::CategoryErrorRateAbove { .. } => "category_error_rate_above",
+            AlertRule::FinalityLagAbove { .. } => "finality_lag_above",
+        }
+    }
+
+    /// Returns `(observed, threshold)` if the rule is currently breached
+    fn check(&self, context: &AlertContext) -> Option<(f64, f64)> {
+        match self {
+            AlertRule::BlockTimeAboveTarget { target_ms, factor } => {
+                let observed = context.last_block_time?.as_millis() as f64;
+                let threshold = *target_ms as f64 * factor;
+                (observed > threshold).then_some((observed, threshold))
+            }
+            AlertRule::ErrorRateAbove { window, max_rate } => {
+                let observed = context.window(*window)?.error_rate;
+                (observed > *max_rate).then_some((observed, *max_rate))
+            }
+            AlertRule::CategoryErrorRateAbove { category, window, max_rate } => {
+                let observed = context.window(*window)?.category_error_rate(*category);
+                (observed > *max_rate).then_some((observed, *max_rate))
+            }
+            AlertRule::FinalityLagAbove { max_epochs } => {
+                let observed = context.finality_lag?;
+                (observed > *max_epochs).then_some((observed as f64, *max_epochs as f64))
+            }
+        }
+    }
+}
+
+/// Metric values the rules are evaluated against
+#[derive(Debug, Clone, Default)]
+pub struct AlertContext {
+    pub last_block_time: Option<Duration>,
+    pub windows: Vec<(Window, WindowStats)>,
+    pub finality_lag: Option<u64>,
+}
+
+impl AlertContext {
+    fn window(&self, window: Window) -> Option<&WindowStats> {
+        self.windows.iter().find(|(w, _)| *w == window).map(|(_, stats)| stats)
+    }
+}
+
+/// A fired alert
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub rule: AlertRule,
+    pub observed: f64,
+    pub threshold: f64,
+    pub timestamp: u64,
+}
+
+impl fmt::Display for Alert {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: observed {:.2}, threshold {:.2}", self.rule.name(), self.observed, self.threshold)
+    }
+}
+
+/// Destination for fired alerts
+#[async_trait]
+pub trait AlertNotifier: Send + Sync {
+    async fn notify(&self, alert: &Alert) -> Result<()>;
+}
+
+/// Writes alerts to the tracing log
+#[derive(Debug, Default)]
+pub struct LogNotifier;
+
+#[async_trait]
+impl AlertNotifier for LogNotifier {
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        warn!("🚨 ALERT {}", alert);
+        Ok(())
+    }
+}
+
+/// POSTs alerts as JSON to an HTTP endpoint
+#[derive(Debug)]
+pub struct WebhookNotifier {
+    url: String,
+    client: reqwest::Client,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: impl Into<String>) -> Self {
+        Self {
+            url: url.into(),
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+}
+
+#[async_trait]
+impl AlertNotifier for WebhookNotifier {
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        self.client.post(&self.url)
+            .json(alert)
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(())
+    }
+}
+
+/// Runs a command for each alert, passing the alert as JSON in `ZKSAC_ALERT`
+#[derive(Debug)]
+pub struct CommandNotifier {
+    program: String,
+    args: Vec<String>,
+}
+
+impl CommandNotifier {
+    pub fn new(program: impl Into<String>, args: Vec<String>) -> Self {
+        Self {
+            program: program.into(),
+            args,
+        }
+    }
+}
+
+#[async_trait]
+impl AlertNotifier for CommandNotifier {
+    async fn notify(&self, alert: &Alert) -> Result<()> {
+        let status = tokio::process::Command::new(&self.program)
+            .args(&self.args)
+            .env("ZKSAC_ALERT", serde_json::to_string(alert)?)
+            .env("ZKSAC_ALERT_RULE", alert.rule.name())
+            .status()
+            .await?;
+        if !status.success() {
+            return Err(anyhow!("Alert command {} exited with {}", self.program, status));
+        }
+        Ok(())
+    }
+}
+
+/// Evaluates alert rules and fans fired alerts out to notifiers
+#[derive(Default)]
+pub struct AlertManager {
+    rules: Vec<AlertRule>,
+    notifiers: Vec<Arc<dyn AlertNotifier>>,
+    firing: HashSet<usize>,
+}
+
+impl AlertManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_rule(mut self, rule: AlertRule) -> Self {
+        self.rules.push(rule);
+        self
+    }
+
+    pub fn with_notifier(mut self, notifier: Arc<dyn AlertNotifier>) -> Self {
+        self.notifiers.push(notifier);
+        self
+    }
+
+    pub fn add_rule(&mut self, rule: AlertRule) {
+        self.rules.push(rule);
+    }
+
+    pub fn add_notifier(&mut self, notifier: Arc<dyn AlertNotifier>) {
+        self.notifiers.push(notifier);
+    }
+
+    pub fn rules(&self) -> &[AlertRule] {
+        &self.rules
+    }
+
+    pub fn is_firing(&self, rule: &AlertRule) -> bool {
+        self.rules.iter()
+            .position(|r| r == rule)
+            .is_some_and(|index| self.firing.contains(&index))
+    }
+
+    /// Evaluate all rules, returning alerts that started firing on this evaluation
+    pub fn evaluate(&mut self, context: &AlertContext) -> Vec<Alert> {
+        let timestamp = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+
+        let mut fired = Vec::new();
+        for (index, rule) in self.rules.iter().enumerate() {
+            match rule.check(context) {
+                Some((observed, threshold)) => {
+                    if self.firing.insert(index) {
+                        fired.push(Alert {
+                            rule: rule.clone(),
+                            observed,
+                            threshold,
+                            timestamp,
+                        });
+                    }
+                }
+                None => {
+                    if self.firing.remove(&index) {
+                        info!("✅ Alert resolved: {}", rule.name());
+                    }
+                }
+            }
+        }
+        fired
+    }
+
+    /// Deliver alerts to every notifier, logging delivery failures
+    pub async fn dispatch(notifiers: Vec<Arc<dyn AlertNotifier>>, alerts: Vec<Alert>) {
+        for alert in &alerts {
+            for notifier in &notifiers {
+                if let Err(e) = notifier.notify(alert).await {
+                    error!("❌ Failed to deliver alert {}: {}", alert.rule.name(), e);
+                }
+            }
+        }
+    }
+
+    /// Deliver alerts in the background if a runtime is available, otherwise only log them
+    pub fn notify(&self, alerts: Vec<Alert>) {
+        if alerts.is_empty() {
+            return;
+        }
+        match tokio::runtime::Handle::try_current() {
+            Ok(handle) => {
+                handle.spawn(Self::dispatch(self.notifiers.clone(), alerts));
+            }
+            Err(_) => {
+                for alert in &alerts {
+                    warn!("🚨 ALERT {} (no runtime, notifiers skipped)", alert);
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Debug for AlertManager {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AlertManager")
+            .field("rules", &self.rules)
+            .field("notifiers", &self.notifiers.len())
+            .field("firing", &self.firing)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alert_fires_once_until_resolved() {
+        let rule = AlertRule::BlockTimeAboveTarget { target_ms: 100, factor: 2.0 };
+        let mut manager = AlertManager::new().with_rule(rule.clone());
+
+        let slow = AlertContext {
+            last_block_time: Some(Duration::from_millis(250)),
+            ..AlertContext::default()
+        };
+        let fast = AlertContext {
+            last_block_time: Some(Duration::from_millis(150)),
+            ..AlertContext::default()
+        };
+
+        let fired = manager.evaluate(&slow);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].threshold, 200.0);
+        assert!(manager.evaluate(&slow).is_empty());
+        assert!(manager.is_firing(&rule));
+
+        assert!(manager.evaluate(&fast).is_empty());
+        assert!(!manager.is_firing(&rule));
+        assert_eq!(manager.evaluate(&slow).len(), 1);
+    }
+
+    #[test]
+    fn test_error_rate_and_finality_rules() {
+        let mut manager = AlertManager::new()
+            .with_rule(AlertRule::ErrorRateAbove { window: Window::OneMinute, max_rate: 0.1 })
+            .with_rule(AlertRule::FinalityLagAbove { max_epochs: 2 });
+
+        let minute = WindowStats {
+            blocks: 4,
+            errors: 1,
+            error_rate: 0.25,
+            errors_by_category: [(ErrorCategory::Signature, 1)].into_iter().collect(),
+            ..WindowStats::default()
+        };
+        let context = AlertContext {
+            last_block_time: None,
+            windows: vec![(Window::OneMinute, minute), (Window::OneHour, WindowStats::default())],
+            finality_lag: Some(2),
+        };
+        let fired = manager.evaluate(&context);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule.name(), "error_rate_above");
+
+        manager.add_rule(AlertRule::CategoryErrorRateAbove {
+            category: ErrorCategory::Signature,
+            window: Window::OneMinute,
+            max_rate: 0.2,
+        });
+        let fired = manager.evaluate(&context);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].rule.name(), "category_error_rate_above");
+    }
+
+    #[test]
+    fn test_rules_deserialize_from_config() {
+        let rules: Vec<AlertRule> = serde_json::from_str(
+            r#"[{"kind": "finality_lag_above", "max_epochs": 3},
+                {"kind": "block_time_above_target", "target_ms": 1000, "factor": 2.0}]"#,
+        ).unwrap();
+        assert_eq!(rules[0], AlertRule::FinalityLagAbove { max_epochs: 3 });
+        assert_eq!(rules[1].name(), "block_time_above_target");
+    }
+}
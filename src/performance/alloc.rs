@@ -0,0 +1,245 @@
+//! Per-subsystem heap allocation tracking
+//!
+//! Code marks the subsystem it is working on with [`enter`]; while the
+//! returned guard is alive, allocations made on that thread are attributed to
+//! the subsystem. Attribution is only recorded when the `alloc-tracking`
+//! feature is enabled and the binary installs [`TrackingAllocator`] as its
+//! global allocator:
+//!
+//! ```ignore
+//! #[global_allocator]
+//! static ALLOC: zk_sac_engine::performance::alloc::TrackingAllocator =
+//!     zk_sac_engine::performance::alloc::TrackingAllocator;
+//! ```
+//!
+//! Without the feature, [`enter`] is a thread-local write and [`usage`]
+//! returns nothing.
+//!
+//! The allocator also counts what each thread allocates, which
+//! [`super::timer::TimerGuard`] uses to attribute allocations to the
+//! operation it times.
+
+use serde::{Serialize, Deserialize};
+use std::cell::Cell;
+use std::fmt;
+
+/// Subsystems allocations are attributed to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[repr(u8)]
+pub enum Subsystem {
+    Other = 0,
+    Consensus = 1,
+    Zkvm = 2,
+    Network = 3,
+    Storage = 4,
+}
+
+impl Subsystem {
+    pub const ALL: [Subsystem; 5] = [
+        Subsystem::Other,
+        Subsystem::Consensus,
+        Subsystem::Zkvm,
+        Subsystem::Network,
+        Subsystem::Storage,
+    ];
+
+    #[cfg_attr(not(feature = "alloc-tracking"), allow(dead_code))]
+    fn from_tag(tag: u8) -> Self {
+        Self::ALL.get(tag as usize).copied().unwrap_or(Subsystem::Other)
+    }
+}
+
+impl fmt::Display for Subsystem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Subsystem::Other => "other",
+            Subsystem::Consensus => "consensus",
+            Subsystem::Zkvm => "zkvm",
+            Subsystem::Network => "network",
+            Subsystem::Storage => "storage",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Current and peak heap usage of one subsystem
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SubsystemUsage {
+    pub subsystem: Subsystem,
+    pub current_bytes: u64,
+    pub peak_bytes: u64,
+    pub allocations: u64,
+}
+
+/// Bytes and allocations made on one thread, or during one operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AllocationCount {
+    pub bytes: u64,
+    pub allocations: u64,
+}
+
+impl AllocationCount {
+    /// What was allocated between `earlier` and this count
+    pub fn since(&self, earlier: &AllocationCount) -> AllocationCount {
+        AllocationCount {
+            bytes: self.bytes.saturating_sub(earlier.bytes),
+            allocations: self.allocations.saturating_sub(earlier.allocations),
+        }
+    }
+}
+
+thread_local! {
+    static CURRENT: Cell<u8> = const { Cell::new(Subsystem::Other as u8) };
+    static THREAD_ALLOCATED: Cell<AllocationCount> = const { Cell::new(AllocationCount { bytes: 0, allocations: 0 }) };
+}
+
+/// Everything this thread allocated so far; zero unless allocation tracking is compiled in
+pub fn thread_allocated() -> AllocationCount {
+    THREAD_ALLOCATED.try_with(Cell::get).unwrap_or_default()
+}
+
+/// Restores the previously active subsystem when dropped
+#[must_use = "allocations are only attributed while the guard is alive"]
+pub struct SubsystemGuard {
+    previous: u8,
+}
+
+impl Drop for SubsystemGuard {
+    fn drop(&mut self) {
+        let _ = CURRENT.try_with(|current| current.set(self.previous));
+    }
+}
+
+/// Attribute allocations on this thread to `subsystem` until the guard drops
+pub fn enter(subsystem: Subsystem) -> SubsystemGuard {
+    let previous = CURRENT
+        .try_with(|current| current.replace(subsystem as u8))
+        .unwrap_or(Subsystem::Other as u8);
+    SubsystemGuard { previous }
+}
+
+pub fn current() -> Subsystem {
+    Subsystem::from_tag(CURRENT.try_with(Cell::get).unwrap_or(0))
+}
+
+#[cfg(feature = "alloc-tracking")]
+pub use tracking::TrackingAllocator;
+
+/// Usage per subsystem, empty unless allocation tracking is compiled in
+#[cfg(feature = "alloc-tracking")]
+pub fn usage() -> Vec<SubsystemUsage> {
+    tracking::usage()
+}
+
+#[cfg(not(feature = "alloc-tracking"))]
+pub fn usage() -> Vec<SubsystemUsage> {
+    Vec::new()
+}
+
+#[cfg(feature = "alloc-tracking")]
+mod tracking {
+    use super::{AllocationCount, Subsystem, SubsystemUsage, CURRENT, THREAD_ALLOCATED};
+    use std::alloc::{GlobalAlloc, Layout, System};
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    struct Counters {
+        current: AtomicU64,
+        peak: AtomicU64,
+        allocations: AtomicU64,
+    }
+
+    #[allow(clippy::declare_interior_mutable_const)]
+    const ZERO: Counters = Counters {
+        current: AtomicU64::new(0),
+        peak: AtomicU64::new(0),
+        allocations: AtomicU64::new(0),
+    };
+
+    static COUNTERS: [Counters; Subsystem::ALL.len()] = [ZERO; Subsystem::ALL.len()];
+
+    /// Global allocator wrapping the system allocator that records which
+    /// subsystem owns each allocation.
+    ///
+    /// Every block carries a small header holding the owning subsystem's tag,
+    /// so memory freed from a different thread or subsystem is still credited
+    /// back to the one that allocated it.
+    pub struct TrackingAllocator;
+
+    fn header_size(layout: &Layout) -> usize {
+        layout.align().max(std::mem::size_of::<usize>())
+    }
+
+    fn padded(layout: &Layout) -> Option<Layout> {
+        Layout::from_size_align(layout.size().checked_add(header_size(layout))?, layout.align()).ok()
+    }
+
+    unsafe impl GlobalAlloc for TrackingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let Some(padded) = padded(&layout) else { return std::ptr::null_mut() };
+            let base = System.alloc(padded);
+            if base.is_null() {
+                return base;
+            }
+
+            let tag = CURRENT.try_with(|current| current.get()).unwrap_or(0);
+            let ptr = base.add(header_size(&layout));
+            ptr.sub(1).write(tag);
+
+            let counters = &COUNTERS[Subsystem::from_tag(tag) as usize];
+            let size = layout.size() as u64;
+            let now = counters.current.fetch_add(size, Ordering::Relaxed) + size;
+            counters.peak.fetch_max(now, Ordering::Relaxed);
+            counters.allocations.fetch_add(1, Ordering::Relaxed);
+            let _ = THREAD_ALLOCATED.try_with(|count| {
+                let AllocationCount { bytes, allocations } = count.get();
+                count.set(AllocationCount { bytes: bytes + size, allocations: allocations + 1 });
+            });
+            ptr
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            let tag = ptr.sub(1).read();
+            COUNTERS[Subsystem::from_tag(tag) as usize]
+                .current
+                .fetch_sub(layout.size() as u64, Ordering::Relaxed);
+
+            let header = header_size(&layout);
+            // `padded` succeeded when this block was allocated
+            let padded = Layout::from_size_align_unchecked(layout.size() + header, layout.align());
+            System.dealloc(ptr.sub(header), padded);
+        }
+    }
+
+    pub fn usage() -> Vec<SubsystemUsage> {
+        Subsystem::ALL.iter()
+            .map(|&subsystem| {
+                let counters = &COUNTERS[subsystem as usize];
+                SubsystemUsage {
+                    subsystem,
+                    current_bytes: counters.current.load(Ordering::Relaxed),
+                    peak_bytes: counters.peak.load(Ordering::Relaxed),
+                    allocations: counters.allocations.load(Ordering::Relaxed),
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guards_nest_and_restore() {
+        assert_eq!(current(), Subsystem::Other);
+        {
+            let _consensus = enter(Subsystem::Consensus);
+            {
+                let _zkvm = enter(Subsystem::Zkvm);
+                assert_eq!(current(), Subsystem::Zkvm);
+            }
+            assert_eq!(current(), Subsystem::Consensus);
+        }
+        assert_eq!(current(), Subsystem::Other);
+    }
+}
@@ -0,0 +1,185 @@
+//! Benchmark persistence and regression comparison against a stored baseline
+
+use super::{PerformanceSummary, SystemBenchmark};
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use std::path::Path;
+use tracing::{info, warn};
+
+/// Format version written into saved baselines
+pub const BASELINE_FORMAT_VERSION: u32 = 1;
+
+/// A persisted benchmark run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PerformanceBaseline {
+    pub format_version: u32,
+    pub created_at: u64,
+    pub crate_version: String,
+    pub summary: PerformanceSummary,
+    pub benchmarks: Vec<SystemBenchmark>,
+}
+
+impl PerformanceBaseline {
+    pub fn new(summary: PerformanceSummary, benchmarks: Vec<SystemBenchmark>) -> Self {
+        Self {
+            format_version: BASELINE_FORMAT_VERSION,
+            created_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+            crate_version: env!("CARGO_PKG_VERSION").to_string(),
+            summary,
+            benchmarks,
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(self)?)?;
+        info!("💾 Saved performance baseline to {}", path.display());
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path)
+            .map_err(|e| anyhow!("Failed to read baseline {}: {}", path.display(), e))?;
+        let baseline: Self = serde_json::from_slice(&bytes)?;
+        if baseline.format_version > BASELINE_FORMAT_VERSION {
+            return Err(anyhow!(
+                "Baseline format version {} is newer than supported version {}",
+                baseline.format_version,
+                BASELINE_FORMAT_VERSION
+            ));
+        }
+        Ok(baseline)
+    }
+}
+
+/// Whether a larger value of a metric is an improvement or a regression
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Direction {
+    LowerIsBetter,
+    HigherIsBetter,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricComparison {
+    pub metric: String,
+    pub baseline: f64,
+    pub current: f64,
+    /// Signed change relative to the baseline, in percent
+    pub change_percent: f64,
+    pub direction: Direction,
+    pub regressed: bool,
+}
+
+/// Result of comparing a run against a baseline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RegressionReport {
+    pub threshold_percent: f64,
+    pub comparisons: Vec<MetricComparison>,
+}
+
+impl RegressionReport {
+    pub fn compare(baseline: &PerformanceSummary, current: &PerformanceSummary, threshold_percent: f64) -> Self {
+        use Direction::*;
+
+        let metrics = [
+            ("average_block_time_ms", baseline.average_block_time_ms, current.average_block_time_ms, LowerIsBetter),
+            ("average_proof_time_ms", baseline.average_proof_time_ms, current.average_proof_time_ms, LowerIsBetter),
+            ("average_tps", baseline.average_tps, current.average_tps, HigherIsBetter),
+            ("max_tps", baseline.max_tps, current.max_tps, HigherIsBetter),
+            ("average_proof_size_bytes", baseline.average_proof_size_bytes as f64, current.average_proof_size_bytes as f64, LowerIsBetter),
+            ("block_production_p95_ms", baseline.block_production_percentiles.p95_ms, current.block_production_percentiles.p95_ms, LowerIsBetter),
+            ("block_production_p99_ms", baseline.block_production_percentiles.p99_ms, current.block_production_percentiles.p99_ms, LowerIsBetter),
+            ("proof_generation_p95_ms", baseline.proof_generation_percentiles.p95_ms, current.proof_generation_percentiles.p95_ms, LowerIsBetter),
+            ("proof_generation_p99_ms", baseline.proof_generation_percentiles.p99_ms, current.proof_generation_percentiles.p99_ms, LowerIsBetter),
+            ("validation_p99_ms", baseline.validation_percentiles.p99_ms, current.validation_percentiles.p99_ms, LowerIsBetter),
+        ];
+
+        let comparisons = metrics.into_iter()
+            .map(|(metric, baseline, current, direction)| {
+                let change_percent = if baseline != 0.0 {
+                    (current - baseline) / baseline * 100.0
+                } else {
+                    0.0
+                };
+                let regressed = match direction {
+                    LowerIsBetter => change_percent > threshold_percent,
+                    HigherIsBetter => change_percent < -threshold_percent,
+                };
+                MetricComparison {
+                    metric: metric.to_string(),
+                    baseline,
+                    current,
+                    change_percent,
+                    direction,
+                    regressed,
+                }
+            })
+            .collect();
+
+        Self { threshold_percent, comparisons }
+    }
+
+    pub fn regressions(&self) -> Vec<&MetricComparison> {
+        self.comparisons.iter().filter(|c| c.regressed).collect()
+    }
+
+    pub fn has_regressions(&self) -> bool {
+        self.comparisons.iter().any(|c| c.regressed)
+    }
+
+    pub fn print(&self) {
+        info!("📉 REGRESSION REPORT (threshold {:.1}%)", self.threshold_percent);
+        for c in &self.comparisons {
+            if c.regressed {
+                warn!("   ❌ {}: {:.2} -> {:.2} ({:+.1}%)", c.metric, c.baseline, c.current, c.change_percent);
+            } else {
+                info!("   ✅ {}: {:.2} -> {:.2} ({:+.1}%)", c.metric, c.baseline, c.current, c.change_percent);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_regression_detection_respects_direction() {
+        let baseline = PerformanceSummary {
+            average_block_time_ms: 100.0,
+            average_tps: 1000.0,
+            ..PerformanceSummary::default()
+        };
+        let current = PerformanceSummary {
+            average_block_time_ms: 120.0, // 20% slower
+            average_tps: 1100.0,          // 10% faster
+            ..PerformanceSummary::default()
+        };
+
+        let report = RegressionReport::compare(&baseline, &current, 10.0);
+        let regressions: Vec<_> = report.regressions().iter().map(|c| c.metric.as_str()).collect();
+        assert_eq!(regressions, vec!["average_block_time_ms"]);
+    }
+
+    #[test]
+    fn test_baseline_round_trip() {
+        let path = std::env::temp_dir().join(format!("zksac-baseline-{}.json", std::process::id()));
+        let summary = PerformanceSummary {
+            total_blocks: 7,
+            ..PerformanceSummary::default()
+        };
+        PerformanceBaseline::new(summary, Vec::new()).save(&path).unwrap();
+
+        let loaded = PerformanceBaseline::load(&path).unwrap();
+        assert_eq!(loaded.summary.total_blocks, 7);
+        assert_eq!(loaded.format_version, BASELINE_FORMAT_VERSION);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,334 @@
+//! Proof cost analytics
+//!
+//! Records per-block shape (transactions, calldata, cycles) alongside proof
+//! size and proving time, reports how strongly they correlate, and fits a
+//! linear model of proving time that the block packer uses to keep blocks
+//! within a proving budget.
+
+use crate::types::Transaction;
+use serde::{Serialize, Deserialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+
+/// Number of recent blocks kept for analysis
+pub const DEFAULT_COST_SAMPLES: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BlockProofSample {
+    pub block_number: u64,
+    pub transaction_count: u64,
+    pub calldata_bytes: u64,
+    pub cycles: u64,
+    pub proof_size_bytes: u64,
+    pub proof_time_ms: f64,
+}
+
+impl BlockProofSample {
+    pub fn from_transactions(
+        block_number: u64,
+        transactions: &[Transaction],
+        cycles: u64,
+        proof_size_bytes: usize,
+        proof_time: Duration,
+    ) -> Self {
+        Self {
+            block_number,
+            transaction_count: transactions.len() as u64,
+            calldata_bytes: transactions.iter().map(|tx| tx.data.len() as u64).sum(),
+            cycles,
+            proof_size_bytes: proof_size_bytes as u64,
+            proof_time_ms: proof_time.as_secs_f64() * 1000.0,
+        }
+    }
+}
+
+/// Pearson correlation of block shape against proof cost; `None` where undefined
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProofCostCorrelations {
+    pub transactions_vs_proof_time: Option<f64>,
+    pub calldata_vs_proof_time: Option<f64>,
+    pub cycles_vs_proof_time: Option<f64>,
+    pub transactions_vs_proof_size: Option<f64>,
+    pub calldata_vs_proof_size: Option<f64>,
+}
+
+/// Linear estimate of proving time from block shape
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProofCostModel {
+    pub base_ms: f64,
+    pub per_transaction_ms: f64,
+    pub per_calldata_byte_ms: f64,
+    pub samples: usize,
+}
+
+impl ProofCostModel {
+    pub fn estimate_ms(&self, transaction_count: u64, calldata_bytes: u64) -> f64 {
+        (self.base_ms
+            + self.per_transaction_ms * transaction_count as f64
+            + self.per_calldata_byte_ms * calldata_bytes as f64)
+            .max(0.0)
+    }
+}
+
+/// Rolling store of block proof samples
+#[derive(Debug)]
+pub struct ProofCostAnalyzer {
+    samples: VecDeque<BlockProofSample>,
+    capacity: usize,
+}
+
+impl ProofCostAnalyzer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity.min(DEFAULT_COST_SAMPLES)),
+            capacity: capacity.max(1),
+        }
+    }
+
+    pub fn record(&mut self, sample: BlockProofSample) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(sample);
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+
+    pub fn samples(&self) -> impl Iterator<Item = &BlockProofSample> {
+        self.samples.iter()
+    }
+
+    pub fn correlations(&self) -> ProofCostCorrelations {
+        let column = |f: fn(&BlockProofSample) -> f64| self.samples.iter().map(f).collect::<Vec<_>>();
+        let transactions = column(|s| s.transaction_count as f64);
+        let calldata = column(|s| s.calldata_bytes as f64);
+        let cycles = column(|s| s.cycles as f64);
+        let proof_time = column(|s| s.proof_time_ms);
+        let proof_size = column(|s| s.proof_size_bytes as f64);
+
+        ProofCostCorrelations {
+            transactions_vs_proof_time: pearson(&transactions, &proof_time),
+            calldata_vs_proof_time: pearson(&calldata, &proof_time),
+            cycles_vs_proof_time: pearson(&cycles, &proof_time),
+            transactions_vs_proof_size: pearson(&transactions, &proof_size),
+            calldata_vs_proof_size: pearson(&calldata, &proof_size),
+        }
+    }
+
+    /// Least-squares fit of proof time against transaction count and calldata bytes.
+    /// Falls back to a transaction-count-only fit when calldata does not vary
+    /// independently.
+    pub fn fit(&self) -> Option<ProofCostModel> {
+        let n = self.samples.len();
+        if n < 3 {
+            return None;
+        }
+
+        let rows: Vec<[f64; 3]> = self.samples.iter()
+            .map(|s| [1.0, s.transaction_count as f64, s.calldata_bytes as f64])
+            .collect();
+        let ys: Vec<f64> = self.samples.iter().map(|s| s.proof_time_ms).collect();
+
+        // Normal equations: (XᵀX) β = Xᵀy
+        let mut xtx = [[0.0; 3]; 3];
+        let mut xty = [0.0; 3];
+        for (row, y) in rows.iter().zip(&ys) {
+            for i in 0..3 {
+                xty[i] += row[i] * y;
+                for j in 0..3 {
+                    xtx[i][j] += row[i] * row[j];
+                }
+            }
+        }
+
+        if let Some([base_ms, per_transaction_ms, per_calldata_byte_ms]) = solve3(xtx, xty) {
+            return Some(ProofCostModel { base_ms, per_transaction_ms, per_calldata_byte_ms, samples: n });
+        }
+
+        let xs: Vec<f64> = rows.iter().map(|row| row[1]).collect();
+        let (intercept, slope) = simple_regression(&xs, &ys)?;
+        Some(ProofCostModel {
+            base_ms: intercept,
+            per_transaction_ms: slope,
+            per_calldata_byte_ms: 0.0,
+            samples: n,
+        })
+    }
+}
+
+impl Default for ProofCostAnalyzer {
+    fn default() -> Self {
+        Self::new(DEFAULT_COST_SAMPLES)
+    }
+}
+
+/// Caps the estimated proving time of a block being packed
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ProvingBudget {
+    pub model: ProofCostModel,
+    pub max_proof_time: Duration,
+}
+
+impl ProvingBudget {
+    pub fn new(model: ProofCostModel, max_proof_time: Duration) -> Self {
+        Self { model, max_proof_time }
+    }
+
+    /// Start packing a new block against this budget
+    pub fn tracker(&self) -> BudgetTracker {
+        BudgetTracker {
+            budget: *self,
+            transactions: 0,
+            calldata_bytes: 0,
+        }
+    }
+}
+
+/// Running totals of a block being packed
+#[derive(Debug, Clone, Copy)]
+pub struct BudgetTracker {
+    budget: ProvingBudget,
+    transactions: u64,
+    calldata_bytes: u64,
+}
+
+impl BudgetTracker {
+    /// Add `tx` if the block would still fit the budget afterwards
+    pub fn try_add(&mut self, tx: &Transaction) -> bool {
+        let transactions = self.transactions + 1;
+        let calldata_bytes = self.calldata_bytes + tx.data.len() as u64;
+        let estimate = self.budget.model.estimate_ms(transactions, calldata_bytes);
+        if estimate > self.budget.max_proof_time.as_secs_f64() * 1000.0 {
+            return false;
+        }
+        self.transactions = transactions;
+        self.calldata_bytes = calldata_bytes;
+        true
+    }
+
+    pub fn estimated_ms(&self) -> f64 {
+        self.budget.model.estimate_ms(self.transactions, self.calldata_bytes)
+    }
+}
+
+fn pearson(xs: &[f64], ys: &[f64]) -> Option<f64> {
+    let n = xs.len();
+    if n < 2 || n != ys.len() {
+        return None;
+    }
+    let mean_x = xs.iter().sum::<f64>() / n as f64;
+    let mean_y = ys.iter().sum::<f64>() / n as f64;
+    let mut covariance = 0.0;
+    let mut var_x = 0.0;
+    let mut var_y = 0.0;
+    for (x, y) in xs.iter().zip(ys) {
+        covariance += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x == 0.0 || var_y == 0.0 {
+        return None;
+    }
+    Some(covariance / (var_x.sqrt() * var_y.sqrt()))
+}
+
+fn simple_regression(xs: &[f64], ys: &[f64]) -> Option<(f64, f64)> {
+    let n = xs.len() as f64;
+    let mean_x = xs.iter().sum::<f64>() / n;
+    let mean_y = ys.iter().sum::<f64>() / n;
+    let covariance: f64 = xs.iter().zip(ys).map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = xs.iter().map(|x| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+    let slope = covariance / variance;
+    Some((mean_y - slope * mean_x, slope))
+}
+
+/// Solve a 3x3 linear system by Gaussian elimination with partial pivoting
+fn solve3(mut a: [[f64; 3]; 3], mut b: [f64; 3]) -> Option<[f64; 3]> {
+    for col in 0..3 {
+        let pivot = (col..3).max_by(|&i, &j| a[i][col].abs().total_cmp(&a[j][col].abs()))?;
+        let scale = a.iter().flatten().fold(0.0f64, |m, v| m.max(v.abs())).max(1.0);
+        if a[pivot][col].abs() < 1e-9 * scale {
+            return None;
+        }
+        a.swap(col, pivot);
+        b.swap(col, pivot);
+
+        for row in (col + 1)..3 {
+            let factor = a[row][col] / a[col][col];
+            for k in col..3 {
+                a[row][k] -= factor * a[col][k];
+            }
+            b[row] -= factor * b[col];
+        }
+    }
+
+    let mut x = [0.0; 3];
+    for row in (0..3).rev() {
+        let sum: f64 = ((row + 1)..3).map(|k| a[row][k] * x[k]).sum();
+        x[row] = (b[row] - sum) / a[row][row];
+    }
+    Some(x)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Address;
+
+    fn sample(transaction_count: u64, calldata_bytes: u64, proof_time_ms: f64) -> BlockProofSample {
+        BlockProofSample {
+            block_number: 0,
+            transaction_count,
+            calldata_bytes,
+            cycles: 0,
+            proof_size_bytes: 1024,
+            proof_time_ms,
+        }
+    }
+
+    #[test]
+    fn test_fit_recovers_linear_costs() {
+        let mut analyzer = ProofCostAnalyzer::default();
+        for (txs, bytes) in [(10, 100), (20, 500), (30, 200), (40, 900), (50, 50)] {
+            analyzer.record(sample(txs, bytes, 100.0 + 2.0 * txs as f64 + 0.5 * bytes as f64));
+        }
+
+        let model = analyzer.fit().unwrap();
+        assert!((model.base_ms - 100.0).abs() < 1e-6);
+        assert!((model.per_transaction_ms - 2.0).abs() < 1e-6);
+        assert!((model.per_calldata_byte_ms - 0.5).abs() < 1e-6);
+
+        let correlations = analyzer.correlations();
+        assert!(correlations.calldata_vs_proof_time.unwrap() > 0.9);
+        assert_eq!(correlations.transactions_vs_proof_size, None);
+    }
+
+    #[test]
+    fn test_fit_falls_back_without_calldata_variation() {
+        let mut analyzer = ProofCostAnalyzer::default();
+        for txs in [1, 2, 3, 4] {
+            analyzer.record(sample(txs, 0, 10.0 + 5.0 * txs as f64));
+        }
+        let model = analyzer.fit().unwrap();
+        assert!((model.per_transaction_ms - 5.0).abs() < 1e-9);
+        assert_eq!(model.per_calldata_byte_ms, 0.0);
+    }
+
+    #[test]
+    fn test_budget_tracker_stops_at_limit() {
+        let model = ProofCostModel { base_ms: 10.0, per_transaction_ms: 1.0, per_calldata_byte_ms: 0.0, samples: 3 };
+        let mut tracker = ProvingBudget::new(model, Duration::from_millis(15)).tracker();
+        let tx = Transaction::new(Address::new(1), Address::new(2), 1, 0);
+        let admitted = (0..10).take_while(|_| tracker.try_add(&tx)).count();
+        assert_eq!(admitted, 5);
+    }
+}
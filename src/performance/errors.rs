@@ -0,0 +1,115 @@
+//! Structured error events
+//!
+//! Errors are recorded with a fixed [`ErrorCategory`] plus a short `kind`
+//! code and free-form context, so reports and alert rules can group them
+//! without parsing strings.
+
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Subsystem an error originated in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    Network,
+    Proving,
+    Validation,
+    Storage,
+    Signature,
+}
+
+impl ErrorCategory {
+    pub const ALL: [ErrorCategory; 5] = [
+        ErrorCategory::Network,
+        ErrorCategory::Proving,
+        ErrorCategory::Validation,
+        ErrorCategory::Storage,
+        ErrorCategory::Signature,
+    ];
+}
+
+impl fmt::Display for ErrorCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ErrorCategory::Network => "network",
+            ErrorCategory::Proving => "proving",
+            ErrorCategory::Validation => "validation",
+            ErrorCategory::Storage => "storage",
+            ErrorCategory::Signature => "signature",
+        };
+        f.write_str(name)
+    }
+}
+
+/// A single recorded error
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ErrorEvent {
+    pub category: ErrorCategory,
+    /// Short machine-readable code, e.g. `timeout` or `invalid_proof`
+    pub kind: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context: BTreeMap<String, String>,
+}
+
+impl ErrorEvent {
+    pub fn new(category: ErrorCategory, kind: impl Into<String>) -> Self {
+        Self {
+            category,
+            kind: kind.into(),
+            message: String::new(),
+            block_number: None,
+            context: BTreeMap::new(),
+        }
+    }
+
+    pub fn with_message(mut self, message: impl Into<String>) -> Self {
+        self.message = message.into();
+        self
+    }
+
+    pub fn at_block(mut self, block_number: u64) -> Self {
+        self.block_number = Some(block_number);
+        self
+    }
+
+    pub fn with_context(mut self, key: impl Into<String>, value: impl ToString) -> Self {
+        self.context.insert(key.into(), value.to_string());
+        self
+    }
+}
+
+impl fmt::Display for ErrorEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.category, self.kind)?;
+        if let Some(block) = self.block_number {
+            write!(f, " at block {}", block)?;
+        }
+        if !self.message.is_empty() {
+            write!(f, ": {}", self.message)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_event_display_and_serde() {
+        let event = ErrorEvent::new(ErrorCategory::Proving, "timeout")
+            .with_message("prover did not respond")
+            .at_block(12)
+            .with_context("attempt", 3);
+        assert_eq!(event.to_string(), "proving/timeout at block 12: prover did not respond");
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json["category"], "proving");
+        assert_eq!(json["context"]["attempt"], "3");
+    }
+}
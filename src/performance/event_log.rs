@@ -0,0 +1,230 @@
+//! Append-only JSONL telemetry log with size-based rotation
+//!
+//! Every event is written as one JSON object per line. When the active file
+//! grows past `max_file_bytes` it is rotated to `<path>.1.gz` (or `<path>.1`
+//! without compression), shifting older rotations up and deleting the oldest.
+
+use super::SystemBenchmark;
+use super::errors::ErrorEvent;
+use super::system::SystemMetrics;
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::{Serialize, Deserialize};
+use std::fs::{File, OpenOptions};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use tracing::{debug, info};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventLogConfig {
+    pub path: PathBuf,
+    pub max_file_bytes: u64,
+    /// Number of rotated files kept alongside the active one
+    pub max_rotated_files: usize,
+    pub compress: bool,
+}
+
+impl EventLogConfig {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            ..Self::default()
+        }
+    }
+}
+
+impl Default for EventLogConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("telemetry.jsonl"),
+            max_file_bytes: 64 * 1024 * 1024, // 64MB
+            max_rotated_files: 5,
+            compress: true,
+        }
+    }
+}
+
+/// A telemetry event written to the log
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum TelemetryEvent {
+    Benchmark(SystemBenchmark),
+    Error {
+        #[serde(flatten)]
+        error: ErrorEvent,
+        /// Errors recorded so far with the same category and kind
+        total: u32,
+    },
+    Consensus {
+        kind: String,
+        block_number: u64,
+        #[serde(default)]
+        details: serde_json::Value,
+    },
+    System(SystemMetrics),
+}
+
+#[derive(Serialize)]
+struct Record<'a> {
+    timestamp_ms: u64,
+    #[serde(flatten)]
+    event: &'a TelemetryEvent,
+}
+
+/// Writer for the JSONL telemetry log
+#[derive(Debug)]
+pub struct EventLog {
+    config: EventLogConfig,
+    writer: BufWriter<File>,
+    written: u64,
+}
+
+impl EventLog {
+    pub fn open(config: EventLogConfig) -> Result<Self> {
+        if let Some(parent) = config.path.parent() {
+            if !parent.as_os_str().is_empty() {
+                std::fs::create_dir_all(parent)?;
+            }
+        }
+        let file = OpenOptions::new().create(true).append(true).open(&config.path)?;
+        let written = file.metadata()?.len();
+        info!("📝 Telemetry event log at {}", config.path.display());
+
+        Ok(Self {
+            config,
+            writer: BufWriter::new(file),
+            written,
+        })
+    }
+
+    pub fn append(&mut self, event: &TelemetryEvent) -> Result<()> {
+        let record = Record {
+            timestamp_ms: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_millis() as u64,
+            event,
+        };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+
+        if self.written > 0 && self.written + line.len() as u64 > self.config.max_file_bytes {
+            self.rotate()?;
+        }
+
+        self.writer.write_all(&line)?;
+        // Flush per event so tailing pipelines see complete lines promptly
+        self.writer.flush()?;
+        self.written += line.len() as u64;
+        Ok(())
+    }
+
+    /// Rotate the active file immediately
+    pub fn rotate(&mut self) -> Result<()> {
+        self.writer.flush()?;
+
+        let keep = self.config.max_rotated_files;
+        if keep == 0 {
+            std::fs::remove_file(&self.config.path)?;
+        } else {
+            let oldest = self.rotated_path(keep);
+            if oldest.exists() {
+                std::fs::remove_file(&oldest)?;
+            }
+            for index in (1..keep).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+
+            let target = self.rotated_path(1);
+            if self.config.compress {
+                let mut input = File::open(&self.config.path)?;
+                let mut encoder = GzEncoder::new(File::create(&target)?, Compression::default());
+                std::io::copy(&mut input, &mut encoder)?;
+                encoder.finish()?;
+                std::fs::remove_file(&self.config.path)?;
+            } else {
+                std::fs::rename(&self.config.path, &target)?;
+            }
+            debug!("🔄 Rotated event log to {}", target.display());
+        }
+
+        let file = OpenOptions::new().create(true).append(true).open(&self.config.path)?;
+        self.writer = BufWriter::new(file);
+        self.written = 0;
+        Ok(())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.config.path
+    }
+
+    /// Path of the `index`th rotated file (1 is the most recent)
+    pub fn rotated_path(&self, index: usize) -> PathBuf {
+        let suffix = if self.config.compress { ".gz" } else { "" };
+        let mut name = self.config.path.as_os_str().to_owned();
+        name.push(format!(".{}{}", index, suffix));
+        PathBuf::from(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::errors::ErrorCategory;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    fn error_event(total: u32) -> TelemetryEvent {
+        TelemetryEvent::Error {
+            error: ErrorEvent::new(ErrorCategory::Network, "timeout"),
+            total,
+        }
+    }
+
+    #[test]
+    fn test_events_are_json_lines() {
+        let dir = std::env::temp_dir().join(format!("zksac-eventlog-{}", std::process::id()));
+        let mut log = EventLog::open(EventLogConfig::new(dir.join("events.jsonl"))).unwrap();
+        log.append(&error_event(1)).unwrap();
+        log.append(&error_event(2)).unwrap();
+
+        let contents = std::fs::read_to_string(log.path()).unwrap();
+        let lines: Vec<serde_json::Value> = contents.lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[1]["type"], "error");
+        assert_eq!(lines[1]["total"], 2);
+        assert_eq!(lines[1]["category"], "network");
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotation_compresses_and_limits_files() {
+        let dir = std::env::temp_dir().join(format!("zksac-eventlog-rotate-{}", std::process::id()));
+        let config = EventLogConfig {
+            path: dir.join("events.jsonl"),
+            max_file_bytes: 200,
+            max_rotated_files: 2,
+            compress: true,
+        };
+        let mut log = EventLog::open(config).unwrap();
+        for total in 0..20 {
+            log.append(&error_event(total)).unwrap();
+        }
+
+        assert!(log.rotated_path(1).exists());
+        assert!(log.rotated_path(2).exists());
+        assert!(!log.rotated_path(3).exists());
+        assert!(std::fs::metadata(log.path()).unwrap().len() <= 200);
+
+        let mut decoded = String::new();
+        GzDecoder::new(File::open(log.rotated_path(1)).unwrap()).read_to_string(&mut decoded).unwrap();
+        assert!(decoded.lines().all(|line| serde_json::from_str::<serde_json::Value>(line).is_ok()));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,155 @@
+//! HDR-style latency histogram
+//!
+//! Values are bucketed log-linearly: exact below 128, and with 64 linear
+//! sub-buckets per power of two above that, bounding the relative error of
+//! any reported percentile to about 1.6% at constant memory.
+
+use serde::{Serialize, Deserialize};
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+const LINEAR_LIMIT: u64 = 128;
+const SUB_BUCKET_BITS: u32 = 6;
+
+/// p50/p95/p99 snapshot in milliseconds
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Percentiles {
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+    pub p99_ms: f64,
+    pub max_ms: f64,
+}
+
+/// Log-linear histogram of durations recorded with microsecond resolution
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct LatencyHistogram {
+    buckets: BTreeMap<u32, u64>,
+    count: u64,
+    max_us: u64,
+}
+
+impl LatencyHistogram {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, duration: Duration) {
+        self.record_micros(duration.as_micros().min(u64::MAX as u128) as u64);
+    }
+
+    pub fn record_micros(&mut self, value: u64) {
+        *self.buckets.entry(bucket_index(value)).or_insert(0) += 1;
+        self.count += 1;
+        self.max_us = self.max_us.max(value);
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Value at quantile `q` (0.0..=1.0) in microseconds
+    pub fn value_at_quantile(&self, q: f64) -> u64 {
+        if self.count == 0 {
+            return 0;
+        }
+
+        let rank = ((q.clamp(0.0, 1.0) * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (&index, &count) in &self.buckets {
+            seen += count;
+            if seen >= rank {
+                return bucket_upper_bound(index).min(self.max_us);
+            }
+        }
+        self.max_us
+    }
+
+    pub fn percentiles(&self) -> Percentiles {
+        let to_ms = |us: u64| us as f64 / 1000.0;
+        Percentiles {
+            p50_ms: to_ms(self.value_at_quantile(0.50)),
+            p95_ms: to_ms(self.value_at_quantile(0.95)),
+            p99_ms: to_ms(self.value_at_quantile(0.99)),
+            max_ms: to_ms(self.max_us),
+        }
+    }
+
+    /// Fold another histogram's samples into this one
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        for (&index, &count) in &other.buckets {
+            *self.buckets.entry(index).or_insert(0) += count;
+        }
+        self.count += other.count;
+        self.max_us = self.max_us.max(other.max_us);
+    }
+
+    pub fn clear(&mut self) {
+        *self = Self::default();
+    }
+}
+
+fn bucket_index(value: u64) -> u32 {
+    if value < LINEAR_LIMIT {
+        return value as u32;
+    }
+    let exponent = 63 - value.leading_zeros();
+    let shift = exponent - SUB_BUCKET_BITS;
+    let sub_bucket = ((value >> shift) as u32) & ((1 << SUB_BUCKET_BITS) - 1);
+    LINEAR_LIMIT as u32 + (exponent - 7) * (1 << SUB_BUCKET_BITS) + sub_bucket
+}
+
+fn bucket_upper_bound(index: u32) -> u64 {
+    if (index as u64) < LINEAR_LIMIT {
+        return index as u64;
+    }
+    let offset = index - LINEAR_LIMIT as u32;
+    let exponent = offset / (1 << SUB_BUCKET_BITS) + 7;
+    let sub_bucket = (offset % (1 << SUB_BUCKET_BITS)) as u64;
+    let shift = exponent - SUB_BUCKET_BITS;
+    (((1u64 << SUB_BUCKET_BITS) + sub_bucket) << shift) + (1u64 << shift) - 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentiles_within_error_bound() {
+        let mut histogram = LatencyHistogram::new();
+        for us in 1..=10_000u64 {
+            histogram.record_micros(us);
+        }
+
+        for (q, expected) in [(0.5, 5_000.0), (0.95, 9_500.0), (0.99, 9_900.0)] {
+            let actual = histogram.value_at_quantile(q) as f64;
+            assert!((actual - expected).abs() / expected < 0.02, "q={} got {}", q, actual);
+        }
+        assert_eq!(histogram.value_at_quantile(1.0), 10_000);
+    }
+
+    #[test]
+    fn test_tail_visible_despite_average() {
+        let mut histogram = LatencyHistogram::new();
+        for _ in 0..98 {
+            histogram.record(Duration::from_millis(10));
+        }
+        histogram.record(Duration::from_millis(900));
+        histogram.record(Duration::from_millis(1000));
+
+        let p = histogram.percentiles();
+        assert!(p.p50_ms < 11.0);
+        assert!(p.p99_ms > 850.0);
+        assert_eq!(p.max_ms, 1000.0);
+    }
+
+    #[test]
+    fn test_bucket_bounds_contain_values() {
+        for value in [0, 127, 128, 129, 1_000, 65_535, 1 << 40] {
+            assert!(bucket_upper_bound(bucket_index(value)) >= value);
+        }
+    }
+}
@@ -0,0 +1,233 @@
+//! End-to-end transaction latency
+//!
+//! Follows each transaction through received → pooled → included → proven →
+//! finalized, keyed by transaction hash, and keeps latency histograms
+//! measured from the moment the node first saw the transaction.
+
+use super::histogram::{LatencyHistogram, Percentiles};
+use crate::types::BlockHash;
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// How long an unfinalized transaction is tracked before being forgotten
+pub const DEFAULT_LATENCY_RETENTION: Duration = Duration::from_secs(30 * 60);
+
+/// Lifecycle stages of a transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum TxStage {
+    Received,
+    Pooled,
+    Included,
+    Proven,
+    Finalized,
+}
+
+/// Timestamps at which a transaction reached each stage
+#[derive(Debug, Clone, Copy)]
+pub struct TxTimeline {
+    pub received: Instant,
+    pub pooled: Option<Instant>,
+    pub included: Option<Instant>,
+    pub proven: Option<Instant>,
+    pub finalized: Option<Instant>,
+    pub block_number: Option<u64>,
+}
+
+impl TxTimeline {
+    pub fn stage(&self) -> TxStage {
+        if self.finalized.is_some() {
+            TxStage::Finalized
+        } else if self.proven.is_some() {
+            TxStage::Proven
+        } else if self.included.is_some() {
+            TxStage::Included
+        } else if self.pooled.is_some() {
+            TxStage::Pooled
+        } else {
+            TxStage::Received
+        }
+    }
+}
+
+/// Inclusion, proving, and finality latency percentiles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct TxLatencyReport {
+    pub inclusion: Percentiles,
+    pub proving: Percentiles,
+    pub finality: Percentiles,
+    pub tracked: usize,
+}
+
+#[derive(Debug)]
+struct State {
+    timelines: HashMap<BlockHash, TxTimeline>,
+    blocks: BTreeMap<u64, Vec<BlockHash>>,
+    inclusion: LatencyHistogram,
+    proving: LatencyHistogram,
+    finality: LatencyHistogram,
+    retention: Duration,
+}
+
+/// Shared per-transaction timeline tracker
+#[derive(Debug, Clone)]
+pub struct TxLatencyTracker {
+    inner: Arc<Mutex<State>>,
+}
+
+impl TxLatencyTracker {
+    pub fn new() -> Self {
+        Self::with_retention(DEFAULT_LATENCY_RETENTION)
+    }
+
+    pub fn with_retention(retention: Duration) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(State {
+                timelines: HashMap::new(),
+                blocks: BTreeMap::new(),
+                inclusion: LatencyHistogram::new(),
+                proving: LatencyHistogram::new(),
+                finality: LatencyHistogram::new(),
+                retention,
+            })),
+        }
+    }
+
+    pub fn received(&self, hash: BlockHash) {
+        let mut guard = self.inner.lock();
+        let state = &mut *guard;
+        let now = Instant::now();
+        let before = state.timelines.len();
+        let retention = state.retention;
+        state.timelines.retain(|_, timeline| now.duration_since(timeline.received) <= retention);
+        if state.timelines.len() < before {
+            let timelines = &state.timelines;
+            state.blocks.retain(|_, hashes| hashes.iter().any(|hash| timelines.contains_key(hash)));
+        }
+        state.timelines.entry(hash).or_insert(TxTimeline {
+            received: now,
+            pooled: None,
+            included: None,
+            proven: None,
+            finalized: None,
+            block_number: None,
+        });
+    }
+
+    pub fn pooled(&self, hash: BlockHash) {
+        if let Some(timeline) = self.inner.lock().timelines.get_mut(&hash) {
+            timeline.pooled.get_or_insert_with(Instant::now);
+        }
+    }
+
+    /// Stop tracking a transaction that was rejected or dropped
+    pub fn forget(&self, hash: &BlockHash) {
+        self.inner.lock().timelines.remove(hash);
+    }
+
+    pub fn included(&self, hashes: &[BlockHash], block_number: u64) {
+        let mut guard = self.inner.lock();
+        let state = &mut *guard;
+        let now = Instant::now();
+        let mut tracked = Vec::new();
+        for hash in hashes {
+            if let Some(timeline) = state.timelines.get_mut(hash) {
+                timeline.included = Some(now);
+                timeline.block_number = Some(block_number);
+                let latency = now.duration_since(timeline.received);
+                tracked.push(*hash);
+                state.inclusion.record(latency);
+            }
+        }
+        state.blocks.entry(block_number).or_default().extend(tracked);
+    }
+
+    /// Mark every tracked transaction in `block_number` as covered by a proof
+    pub fn proven(&self, block_number: u64) {
+        let mut guard = self.inner.lock();
+        let state = &mut *guard;
+        let now = Instant::now();
+        let Some(hashes) = state.blocks.get(&block_number).cloned() else { return };
+        for hash in hashes {
+            if let Some(timeline) = state.timelines.get_mut(&hash) {
+                if timeline.proven.is_none() {
+                    timeline.proven = Some(now);
+                    let latency = now.duration_since(timeline.received);
+                    state.proving.record(latency);
+                }
+            }
+        }
+    }
+
+    /// Mark every transaction in blocks up to and including `block_number` as final.
+    /// Finalized transactions are no longer tracked.
+    pub fn finalized(&self, block_number: u64) {
+        let mut state = self.inner.lock();
+        let now = Instant::now();
+        let remaining = state.blocks.split_off(&(block_number + 1));
+        let finalized = std::mem::replace(&mut state.blocks, remaining);
+        for hash in finalized.into_values().flatten() {
+            if let Some(timeline) = state.timelines.remove(&hash) {
+                state.finality.record(now.duration_since(timeline.received));
+            }
+        }
+    }
+
+    pub fn timeline(&self, hash: &BlockHash) -> Option<TxTimeline> {
+        self.inner.lock().timelines.get(hash).copied()
+    }
+
+    pub fn report(&self) -> TxLatencyReport {
+        let state = self.inner.lock();
+        TxLatencyReport {
+            inclusion: state.inclusion.percentiles(),
+            proving: state.proving.percentiles(),
+            finality: state.finality.percentiles(),
+            tracked: state.timelines.len(),
+        }
+    }
+}
+
+impl Default for TxLatencyTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_timeline_through_finality() {
+        let tracker = TxLatencyTracker::new();
+        let hash = BlockHash([7; 32]);
+
+        tracker.received(hash);
+        tracker.pooled(hash);
+        std::thread::sleep(Duration::from_millis(5));
+        tracker.included(&[hash], 3);
+        assert_eq!(tracker.timeline(&hash).unwrap().stage(), TxStage::Included);
+
+        tracker.proven(3);
+        assert_eq!(tracker.timeline(&hash).unwrap().stage(), TxStage::Proven);
+
+        tracker.finalized(3);
+        assert!(tracker.timeline(&hash).is_none());
+
+        let report = tracker.report();
+        assert!(report.inclusion.p50_ms >= 5.0);
+        assert!(report.finality.max_ms >= report.inclusion.max_ms);
+        assert_eq!(report.tracked, 0);
+    }
+
+    #[test]
+    fn test_untracked_transactions_ignored() {
+        let tracker = TxLatencyTracker::new();
+        tracker.included(&[BlockHash([1; 32])], 1);
+        tracker.finalized(1);
+        assert_eq!(tracker.report().finality.max_ms, 0.0);
+    }
+}
@@ -1,7 +1,47 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 use serde::{Serialize, Deserialize};
-use tracing::{info, debug, warn};
+use tracing::{info, debug, warn, error};
+use crate::zkvm::cache::{ProofCache, ProofCacheStats};
+
+pub mod alert;
+pub mod alloc;
+pub mod baseline;
+pub mod cost_model;
+pub mod errors;
+pub mod event_log;
+pub mod histogram;
+pub mod latency;
+#[cfg(feature = "profiling")]
+pub mod profiling;
+pub mod prover;
+pub mod sink;
+pub mod soak;
+pub mod system;
+pub mod timer;
+pub mod window;
+pub mod workload;
+
+pub use alloc::{AllocationCount, Subsystem, SubsystemUsage};
+pub use alert::{Alert, AlertContext, AlertManager, AlertNotifier, AlertRule, CommandNotifier, LogNotifier, WebhookNotifier};
+pub use baseline::{PerformanceBaseline, RegressionReport};
+pub use cost_model::{BlockProofSample, ProofCostAnalyzer, ProofCostCorrelations, ProofCostModel, ProvingBudget};
+pub use errors::{ErrorCategory, ErrorEvent};
+pub use event_log::{EventLog, EventLogConfig, TelemetryEvent};
+pub use histogram::{LatencyHistogram, Percentiles};
+pub use latency::{TxLatencyReport, TxLatencyTracker, TxStage};
+pub use prover::{ProverMetrics, ProverUtilization};
+pub use sink::{MemorySink, MetricsSink, PrometheusSink};
+pub use soak::{SoakConfig, SoakReport};
+pub use system::{SystemMetrics, SystemSampler};
+pub use timer::{Operation, OperationAllocations, TimerGuard, TimingRecorder};
+pub use window::{EpochAggregator, EpochStats, RollingAggregator, Window, WindowStats};
+pub use workload::{WorkloadConfig, WorkloadGenerator};
+
+/// Default number of blocks per epoch used for per-epoch aggregation
+pub const DEFAULT_EPOCH_LENGTH: u64 = 32;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PerformanceMetrics {
@@ -13,6 +53,10 @@ pub struct PerformanceMetrics {
     pub memory_usage_mb: f64,
     pub cpu_usage_percent: f64,
     pub network_latency_ms: u64,
+    #[serde(default)]
+    pub open_fds: Option<u64>,
+    #[serde(default)]
+    pub data_dir_bytes: Option<u64>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -29,7 +73,22 @@ pub struct PerformanceMonitor {
     start_time: Instant,
     benchmarks: Vec<SystemBenchmark>,
     active_timers: HashMap<String, Instant>,
-    error_counts: HashMap<String, u32>,
+    error_counts: BTreeMap<(ErrorCategory, String), u32>,
+    system_sampler: SystemSampler,
+    last_system_metrics: Option<SystemMetrics>,
+    block_production_histogram: LatencyHistogram,
+    proof_generation_histogram: LatencyHistogram,
+    validation_histogram: LatencyHistogram,
+    rolling: RollingAggregator,
+    epochs: EpochAggregator,
+    timings: TimingRecorder,
+    prover: ProverMetrics,
+    alerts: AlertManager,
+    finalized_epoch: Option<u64>,
+    sinks: Vec<Box<dyn MetricsSink>>,
+    tx_latency: TxLatencyTracker,
+    proof_cache: ProofCache,
+    proof_costs: ProofCostAnalyzer,
 }
 
 impl PerformanceMonitor {
@@ -39,15 +98,185 @@ impl PerformanceMonitor {
             start_time: Instant::now(),
             benchmarks: Vec::new(),
             active_timers: HashMap::new(),
-            error_counts: HashMap::new(),
+            error_counts: BTreeMap::new(),
+            system_sampler: SystemSampler::new(),
+            last_system_metrics: None,
+            block_production_histogram: LatencyHistogram::new(),
+            proof_generation_histogram: LatencyHistogram::new(),
+            validation_histogram: LatencyHistogram::new(),
+            rolling: RollingAggregator::new(),
+            epochs: EpochAggregator::new(DEFAULT_EPOCH_LENGTH),
+            timings: TimingRecorder::new(),
+            prover: ProverMetrics::new(),
+            alerts: AlertManager::new(),
+            finalized_epoch: None,
+            sinks: Vec::new(),
+            tx_latency: TxLatencyTracker::new(),
+            proof_cache: ProofCache::default(),
+            proof_costs: ProofCostAnalyzer::default(),
         }
     }
 
+    /// Aggregate per-epoch statistics using epochs of `epoch_length` blocks
+    pub fn with_epoch_length(mut self, epoch_length: u64) -> Self {
+        self.epochs = EpochAggregator::new(epoch_length);
+        self
+    }
+
+    /// Include disk usage of the node's data directory in system metrics
+    pub fn with_data_dir(mut self, data_dir: impl Into<PathBuf>) -> Self {
+        self.system_sampler = SystemSampler::new().with_data_dir(data_dir);
+        self
+    }
+
+    /// Report transaction latencies from `tracker`, typically the engine's
+    pub fn with_tx_latency(mut self, tracker: TxLatencyTracker) -> Self {
+        self.tx_latency = tracker;
+        self
+    }
+
+    pub fn tx_latency(&self) -> TxLatencyReport {
+        self.tx_latency.report()
+    }
+
+    /// Report proof cache hits and misses from `cache`, typically the engine's
+    pub fn with_proof_cache(mut self, cache: ProofCache) -> Self {
+        self.proof_cache = cache;
+        self
+    }
+
+    pub fn proof_cache(&self) -> ProofCacheStats {
+        self.proof_cache.stats()
+    }
+
+    /// Send every benchmark, error, and telemetry event to `sink`
+    pub fn with_sink(mut self, sink: impl MetricsSink + 'static) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    pub fn add_sink(&mut self, sink: impl MetricsSink + 'static) {
+        self.sinks.push(Box::new(sink));
+    }
+
+    /// Append benchmarks, errors, and consensus events to a JSONL telemetry log
+    pub fn with_event_log(self, event_log: EventLog) -> Self {
+        self.with_sink(event_log)
+    }
+
+    /// Hand an event to every registered sink
+    pub fn log_event(&mut self, event: TelemetryEvent) {
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.record(&event) {
+                warn!("⚠️  Metrics sink failed to record event: {}", e);
+            }
+        }
+    }
+
+    pub fn flush_sinks(&mut self) {
+        for sink in &mut self.sinks {
+            if let Err(e) = sink.flush() {
+                warn!("⚠️  Failed to flush metrics sink: {}", e);
+            }
+        }
+    }
+
+    /// Watch for `rule` on every recorded block and error
+    pub fn with_alert_rule(mut self, rule: AlertRule) -> Self {
+        self.alerts.add_rule(rule);
+        self
+    }
+
+    pub fn with_alert_notifier(mut self, notifier: Arc<dyn AlertNotifier>) -> Self {
+        self.alerts.add_notifier(notifier);
+        self
+    }
+
+    pub fn alerts_mut(&mut self) -> &mut AlertManager {
+        &mut self.alerts
+    }
+
+    /// Record the latest finalized epoch, used by finality lag alerts
+    pub fn record_finalized_epoch(&mut self, epoch: u64) {
+        self.finalized_epoch = Some(epoch);
+        self.check_alerts();
+    }
+
+    /// Evaluate alert rules against current metrics and notify on newly fired alerts
+    pub fn check_alerts(&mut self) -> Vec<Alert> {
+        let context = AlertContext {
+            last_block_time: self.benchmarks.last()
+                .map(|b| Duration::from_millis(b.metrics.block_production_time_ms)),
+            windows: Window::ALL.iter()
+                .map(|&window| (window, self.rolling.stats(window)))
+                .collect(),
+            finality_lag: match (self.epochs.current(), self.finalized_epoch) {
+                (Some(current), Some(finalized)) => Some(current.epoch.saturating_sub(finalized)),
+                _ => None,
+            },
+        };
+
+        let fired = self.alerts.evaluate(&context);
+        self.alerts.notify(fired.clone());
+        fired
+    }
+
+    /// Time an operation until the returned guard is stopped or dropped
+    pub fn time(&self, operation: Operation) -> TimerGuard {
+        self.timings.start(operation)
+    }
+
+    /// Latency percentiles of all completed timers for `operation`
+    pub fn operation_percentiles(&self, operation: Operation) -> Percentiles {
+        self.timings.percentiles(operation)
+    }
+
+    pub fn operation_count(&self, operation: Operation) -> u64 {
+        self.timings.count(operation)
+    }
+
+    /// Shared recorder, for timing operations from other tasks
+    pub fn timings(&self) -> TimingRecorder {
+        self.timings.clone()
+    }
+
+    /// Shared prover job counters, for reporting from wherever proofs are generated
+    pub fn prover_metrics(&self) -> ProverMetrics {
+        self.prover.clone()
+    }
+
+    pub fn prover_utilization(&self) -> ProverUtilization {
+        self.prover.snapshot()
+    }
+
+    /// Record the shape and proving cost of a block for cost model fitting
+    pub fn record_block_proof(&mut self, sample: BlockProofSample) {
+        debug!("📐 Block {}: {} txs, {} calldata bytes, {:.2} ms proving",
+               sample.block_number, sample.transaction_count, sample.calldata_bytes, sample.proof_time_ms);
+        self.proof_costs.record(sample);
+    }
+
+    pub fn proof_correlations(&self) -> ProofCostCorrelations {
+        self.proof_costs.correlations()
+    }
+
+    /// Proving time model fitted to recorded blocks, once enough have been seen
+    pub fn proof_cost_model(&self) -> Option<ProofCostModel> {
+        self.proof_costs.fit()
+    }
+
+    /// Packing budget from the current cost model, for `ZkSacConsensusEngine::set_proving_budget`
+    pub fn proving_budget(&self, max_proof_time: Duration) -> Option<ProvingBudget> {
+        self.proof_cost_model().map(|model| ProvingBudget::new(model, max_proof_time))
+    }
+
+    #[deprecated(note = "use `PerformanceMonitor::time`, which cannot mismatch timer names")]
     pub fn start_timer(&mut self, operation: &str) {
         self.active_timers.insert(operation.to_string(), Instant::now());
         debug!("⏱️  Started timer for: {}", operation);
     }
 
+    #[deprecated(note = "use `PerformanceMonitor::time`, which cannot mismatch timer names")]
     pub fn end_timer(&mut self, operation: &str) -> Duration {
         if let Some(start_time) = self.active_timers.remove(operation) {
             let duration = start_time.elapsed();
@@ -59,9 +288,24 @@ impl PerformanceMonitor {
         }
     }
 
-    pub fn record_error(&mut self, error_type: &str) {
-        *self.error_counts.entry(error_type.to_string()).or_insert(0) += 1;
-        warn!("❌ Recorded error: {} (total: {})", error_type, self.error_counts[error_type]);
+    pub fn record_error(&mut self, error: ErrorEvent) {
+        self.rolling.record_error(error.category);
+        self.epochs.record_error(error.category);
+        let count = self.error_counts.entry((error.category, error.kind.clone())).or_insert(0);
+        *count += 1;
+        let total = *count;
+        warn!("❌ Recorded error: {} (total: {})", error, total);
+        self.log_event(TelemetryEvent::Error { error, total });
+        self.check_alerts();
+    }
+
+    /// Error counts per category
+    pub fn errors_by_category(&self) -> BTreeMap<ErrorCategory, u32> {
+        let mut totals = BTreeMap::new();
+        for ((category, _), count) in &self.error_counts {
+            *totals.entry(*category).or_insert(0) += count;
+        }
+        totals
     }
 
     pub fn create_benchmark(
@@ -83,8 +327,15 @@ impl PerformanceMonitor {
             0.0
         };
 
+        self.block_production_histogram.record(block_production_time);
+        self.proof_generation_histogram.record(proof_generation_time);
+        self.validation_histogram.record(validation_time);
+        self.rolling.record_block(transaction_count, proof_generation_time);
+        self.epochs.record_block(block_number, transaction_count, block_production_time, proof_generation_time);
+
         // Get system metrics
-        let (memory_mb, cpu_percent) = self.get_system_metrics();
+        let system = self.sample_system_metrics();
+        let (memory_mb, cpu_percent) = (system.memory_mb(), system.cpu_usage_percent);
 
         let metrics = PerformanceMetrics {
             block_production_time_ms: block_production_time.as_millis() as u64,
@@ -95,10 +346,12 @@ impl PerformanceMonitor {
             memory_usage_mb: memory_mb,
             cpu_usage_percent: cpu_percent,
             network_latency_ms: 0, // TODO: Implement network monitoring
+            open_fds: system.open_fds,
+            data_dir_bytes: system.data_dir_bytes,
         };
 
         let errors: Vec<String> = self.error_counts.iter()
-            .map(|(error_type, count)| format!("{}: {}", error_type, count))
+            .map(|((category, kind), count)| format!("{}/{}: {}", category, kind, count))
             .collect();
 
         let benchmark = SystemBenchmark {
@@ -113,6 +366,8 @@ impl PerformanceMonitor {
         };
 
         self.benchmarks.push(benchmark.clone());
+        self.log_event(TelemetryEvent::Benchmark(benchmark.clone()));
+        self.check_alerts();
         
         info!("📊 Benchmark recorded for block {}", block_number);
         info!("   ⚡ Block production: {:?}", block_production_time);
@@ -128,7 +383,15 @@ impl PerformanceMonitor {
 
     pub fn get_performance_summary(&self) -> PerformanceSummary {
         if self.benchmarks.is_empty() {
-            return PerformanceSummary::default();
+            return PerformanceSummary {
+                total_errors: self.error_counts.values().sum(),
+                errors_by_category: self.errors_by_category(),
+                prover: self.prover.snapshot(),
+                tx_latency: self.tx_latency.report(),
+                proof_cache: self.proof_cache.stats(),
+                proof_cost_model: self.proof_cost_model(),
+                ..PerformanceSummary::default()
+            };
         }
 
         let total_blocks = self.benchmarks.len() as u64;
@@ -168,6 +431,14 @@ impl PerformanceMonitor {
             max_tps,
             average_proof_size_bytes: avg_proof_size as usize,
             total_errors: self.error_counts.values().sum(),
+            errors_by_category: self.errors_by_category(),
+            block_production_percentiles: self.block_production_histogram.percentiles(),
+            proof_generation_percentiles: self.proof_generation_histogram.percentiles(),
+            validation_percentiles: self.validation_histogram.percentiles(),
+            prover: self.prover.snapshot(),
+            tx_latency: self.tx_latency.report(),
+            proof_cache: self.proof_cache.stats(),
+            proof_cost_model: self.proof_cost_model(),
         }
     }
 
@@ -185,12 +456,66 @@ impl PerformanceMonitor {
         info!("🏆 Peak TPS: {:.2}", summary.max_tps);
         info!("📏 Average proof size: {} bytes", summary.average_proof_size_bytes);
         info!("❌ Total errors: {}", summary.total_errors);
+        for (label, p) in [
+            ("Block production", &summary.block_production_percentiles),
+            ("Proof generation", &summary.proof_generation_percentiles),
+            ("Validation", &summary.validation_percentiles),
+        ] {
+            info!("📈 {} p50/p95/p99: {:.2} / {:.2} / {:.2} ms (max {:.2})",
+                  label, p.p50_ms, p.p95_ms, p.p99_ms, p.max_ms);
+        }
+        for (label, p) in [
+            ("Inclusion", &summary.tx_latency.inclusion),
+            ("Proving", &summary.tx_latency.proving),
+            ("Finality", &summary.tx_latency.finality),
+        ] {
+            info!("⏳ Tx {} latency p50/p95/p99: {:.2} / {:.2} / {:.2} ms",
+                  label, p.p50_ms, p.p95_ms, p.p99_ms);
+        }
+        let prover = &summary.prover;
+        info!("🔬 Prover jobs: {} queued, {} in flight, {} completed, {} failed",
+              prover.jobs_queued, prover.jobs_in_flight, prover.jobs_completed, prover.jobs_failed);
+        info!("🔬 Prover throughput: {:.0} cycles/s, {:.0} proof bytes/block, {:.1}% busy",
+              prover.cycles_per_second, prover.average_proof_bytes_per_block, prover.busy_fraction * 100.0);
+        let cache = &summary.proof_cache;
+        info!("🗃️ Proof cache: {} hits, {} misses ({:.1}% hit rate), {} cached in {} bytes, {} evicted",
+              cache.hits, cache.misses, cache.hit_rate() * 100.0, cache.entries, cache.bytes, cache.evictions);
+        if let Some(model) = &summary.proof_cost_model {
+            info!("📐 Proof cost model: {:.2} ms + {:.3} ms/tx + {:.5} ms/calldata byte ({} blocks)",
+                  model.base_ms, model.per_transaction_ms, model.per_calldata_byte_ms, model.samples);
+        }
+        for window in Window::ALL {
+            let stats = self.window_stats(window);
+            info!("🪟 Last {}: {} blocks, {:.2} TPS, {:.2} ms avg proof, {:.2} errors/block",
+                  window.label(), stats.blocks, stats.tps, stats.average_proof_time_ms, stats.error_rate);
+        }
+        if let Some(epoch) = self.epochs.current() {
+            info!("🗓️  Epoch {}: {} blocks, {:.2} TPS, {:.2} ms avg proof, {:.2} errors/block",
+                  epoch.epoch, epoch.stats.blocks, epoch.stats.tps,
+                  epoch.stats.average_proof_time_ms, epoch.stats.error_rate);
+        }
+        for usage in self.allocation_usage() {
+            info!("🧠 {} heap: {:.2} MB current, {:.2} MB peak, {} allocations",
+                  usage.subsystem,
+                  usage.current_bytes as f64 / (1024.0 * 1024.0),
+                  usage.peak_bytes as f64 / (1024.0 * 1024.0),
+                  usage.allocations);
+        }
+        let mut allocations: Vec<_> = self.operation_allocations().into_iter().collect();
+        allocations.sort_by_key(|(operation, _)| operation.to_string());
+        for (operation, usage) in allocations {
+            info!("🧮 {} allocated {:.2} KB per run over {} runs ({} allocations)",
+                  operation, usage.average_bytes() / 1024.0, usage.samples, usage.allocations);
+        }
         info!("==========================================");
 
         if !self.error_counts.is_empty() {
             info!("🔍 Error breakdown:");
-            for (error_type, count) in &self.error_counts {
-                info!("   {} errors: {}", error_type, count);
+            for (category, total) in &summary.errors_by_category {
+                info!("   {} errors: {}", category, total);
+                for ((_, kind), count) in self.error_counts.iter().filter(|((c, _), _)| c == category) {
+                    info!("      {}: {}", kind, count);
+                }
             }
         }
     }
@@ -199,6 +524,34 @@ impl PerformanceMonitor {
         serde_json::to_string_pretty(&self.benchmarks)
     }
 
+    /// Persist the current summary and benchmarks so later runs can compare against them
+    pub fn save_to(&self, path: impl AsRef<Path>) -> anyhow::Result<()> {
+        PerformanceBaseline::new(self.get_performance_summary(), self.benchmarks.clone()).save(path)
+    }
+
+    pub fn load_baseline(path: impl AsRef<Path>) -> anyhow::Result<PerformanceBaseline> {
+        PerformanceBaseline::load(path)
+    }
+
+    /// Compare this run against a baseline, flagging metrics that got worse by more than `threshold_percent`
+    pub fn compare_with_baseline(&self, baseline: &PerformanceBaseline, threshold_percent: f64) -> RegressionReport {
+        RegressionReport::compare(&baseline.summary, &self.get_performance_summary(), threshold_percent)
+    }
+
+    /// TPS, proof time, and error rate over a recent time window
+    pub fn window_stats(&self, window: Window) -> WindowStats {
+        self.rolling.stats(window)
+    }
+
+    /// Aggregates for a specific epoch, if any blocks were recorded in it
+    pub fn epoch_stats(&self, epoch: u64) -> Option<&EpochStats> {
+        self.epochs.get(epoch)
+    }
+
+    pub fn all_epoch_stats(&self) -> Vec<&EpochStats> {
+        self.epochs.all().collect()
+    }
+
     pub fn get_latest_benchmark(&self) -> Option<&SystemBenchmark> {
         self.benchmarks.last()
     }
@@ -209,13 +562,36 @@ impl PerformanceMonitor {
             .collect()
     }
 
-    // Helper method to get system metrics
-    fn get_system_metrics(&self) -> (f64, f64) {
-        // In a real implementation, this would use system APIs to get actual metrics
-        // For now, return simulated values
-        let memory_mb = 128.0 + (rand::random::<f64>() * 64.0); // 128-192 MB
-        let cpu_percent = 15.0 + (rand::random::<f64>() * 25.0); // 15-40% CPU
-        (memory_mb, cpu_percent)
+    /// Heap usage per subsystem; empty unless built with `alloc-tracking`
+    pub fn allocation_usage(&self) -> Vec<SubsystemUsage> {
+        alloc::usage()
+    }
+
+    /// Heap allocated while each timed operation ran; empty unless built with `alloc-tracking`
+    pub fn operation_allocations(&self) -> HashMap<Operation, OperationAllocations> {
+        self.timings.allocations()
+    }
+
+    /// Take a fresh process resource sample and remember it as the latest
+    pub fn sample_system_metrics(&mut self) -> SystemMetrics {
+        let metrics = self.system_sampler.sample();
+        self.last_system_metrics = Some(metrics.clone());
+        self.log_event(TelemetryEvent::System(metrics.clone()));
+        metrics
+    }
+
+    pub fn latest_system_metrics(&self) -> Option<&SystemMetrics> {
+        self.last_system_metrics.as_ref()
+    }
+
+    /// Latest system metrics and proof cache counters in Prometheus text exposition format
+    pub fn prometheus_metrics(&self) -> String {
+        let mut out = self.last_system_metrics
+            .as_ref()
+            .map(SystemMetrics::to_prometheus)
+            .unwrap_or_default();
+        out.push_str(&self.proof_cache.stats().to_prometheus());
+        out
     }
 }
 
@@ -230,6 +606,19 @@ pub struct PerformanceSummary {
     pub max_tps: f64,
     pub average_proof_size_bytes: usize,
     pub total_errors: u32,
+    #[serde(default)]
+    pub errors_by_category: BTreeMap<ErrorCategory, u32>,
+    pub block_production_percentiles: Percentiles,
+    pub proof_generation_percentiles: Percentiles,
+    pub validation_percentiles: Percentiles,
+    #[serde(default)]
+    pub prover: ProverUtilization,
+    #[serde(default)]
+    pub tx_latency: TxLatencyReport,
+    #[serde(default)]
+    pub proof_cache: ProofCacheStats,
+    #[serde(default)]
+    pub proof_cost_model: Option<ProofCostModel>,
 }
 
 impl Default for PerformanceSummary {
@@ -244,6 +633,14 @@ impl Default for PerformanceSummary {
             max_tps: 0.0,
             average_proof_size_bytes: 0,
             total_errors: 0,
+            errors_by_category: BTreeMap::new(),
+            block_production_percentiles: Percentiles::default(),
+            proof_generation_percentiles: Percentiles::default(),
+            validation_percentiles: Percentiles::default(),
+            prover: ProverUtilization::default(),
+            tx_latency: TxLatencyReport::default(),
+            proof_cache: ProofCacheStats::default(),
+            proof_cost_model: None,
         }
     }
 }
@@ -260,58 +657,120 @@ impl PerformanceTest {
         }
     }
 
+    /// Drive the default synthetic workload through a real engine and mempool
     pub async fn run_stress_test(
         &mut self,
         blocks_to_produce: u64,
         transactions_per_block: u64,
     ) -> PerformanceSummary {
+        match self.run_workload_test(WorkloadConfig::default(), blocks_to_produce, transactions_per_block as usize).await {
+            Ok(summary) => summary,
+            Err(e) => {
+                error!("❌ Stress test aborted: {}", e);
+                self.monitor.get_performance_summary()
+            }
+        }
+    }
+
+    /// Generate signed transactions from `config` and push them through a fresh
+    /// engine: mempool admission, block production, validation, and application
+    pub async fn run_workload_test(
+        &mut self,
+        config: WorkloadConfig,
+        blocks_to_produce: u64,
+        transactions_per_block: usize,
+    ) -> anyhow::Result<PerformanceSummary> {
         info!("🚀 Starting stress test: {} blocks, {} tx/block", blocks_to_produce, transactions_per_block);
-        
+
+        use crate::consensus::engine::ConsensusEngine;
+
+        let mut generator = WorkloadGenerator::new(config)?;
+        let mut engine = crate::consensus::engine::ZkSacConsensusEngine::new(
+            generator.genesis_state(1_000_000_000),
+            generator.validators(4),
+            crate::types::ProtocolConfig::default(),
+        )?;
+        self.monitor.proof_cache = engine.proof_cache.clone();
+        // This node signs for every validator and accepts every sender's transactions
+        for sender in generator.senders() {
+            let key = generator.key(sender).expect("every sender has a key");
+            engine.register_account_key(key.sig_type(), key.public_key());
+        }
+        for validator in generator.validators(4) {
+            engine.add_validator_key(generator.key(&validator.address).expect("validators are senders").clone())?;
+        }
+
         for block_num in 1..=blocks_to_produce {
-            self.monitor.start_timer("block_production");
-            
-            // Simulate block production
-            tokio::time::sleep(Duration::from_millis(10 + rand::random::<u64>() % 20)).await;
-            let block_time = self.monitor.end_timer("block_production");
-            
-            self.monitor.start_timer("proof_generation");
-            
-            // Simulate proof generation (longer for more transactions)
-            let proof_delay = 50 + (transactions_per_block * 2);
-            tokio::time::sleep(Duration::from_millis(proof_delay)).await;
-            let proof_time = self.monitor.end_timer("proof_generation");
-            
-            self.monitor.start_timer("validation");
-            
-            // Simulate validation
-            tokio::time::sleep(Duration::from_millis(5 + rand::random::<u64>() % 10)).await;
-            let validation_time = self.monitor.end_timer("validation");
-            
-            // Simulate occasional errors
-            if rand::random::<f64>() < 0.05 { // 5% error rate
-                self.monitor.record_error("network_timeout");
+            for tx in generator.batch(transactions_per_block)? {
+                if let Err(e) = engine.add_local_transaction(tx) {
+                    self.monitor.record_error(
+                        ErrorEvent::new(ErrorCategory::Validation, "mempool_rejected")
+                            .with_message(e.to_string())
+                            .at_block(block_num)
+                    );
+                }
+            }
+
+            let producer = engine.select_block_producer(block_num)?;
+
+            // Proving happens inside block production, so it is not timed separately
+            let timer = self.monitor.time(Operation::BlockProduction);
+            let block = engine.produce_block(producer).await?;
+            let block_time = timer.stop();
+
+            let timer = self.monitor.time(Operation::Validation);
+            let valid = engine.validate_block(&block).await?;
+            let validation_time = timer.stop();
+            if !valid {
+                self.monitor.record_error(
+                    ErrorEvent::new(ErrorCategory::Validation, "invalid_block").at_block(block_num)
+                );
+                continue;
             }
-            
-            // Create benchmark
-            let proof_size = 1024 + (transactions_per_block * 32) as usize;
+
+            let transaction_count = block.transactions.len() as u64;
+            let proof_size = block.recursive_proof.proof_data.len();
+            self.monitor.record_block_proof(BlockProofSample::from_transactions(
+                block_num,
+                &block.transactions,
+                0,
+                proof_size,
+                block_time,
+            ));
+            let timer = self.monitor.time(Operation::BlockApplication);
+            engine.apply_block(block).await?;
+            timer.stop();
+
             self.monitor.create_benchmark(
                 block_num,
-                transactions_per_block,
+                transaction_count,
                 block_time,
-                proof_time,
+                Duration::ZERO,
                 validation_time,
                 proof_size,
             );
-            
+
             if block_num % 10 == 0 {
                 info!("📊 Completed {} blocks", block_num);
             }
         }
-        
+
         let summary = self.monitor.get_performance_summary();
         self.monitor.print_performance_report();
-        
-        summary
+
+        Ok(summary)
+    }
+
+    /// Drive a real engine for `config.duration`, printing periodic dashboards
+    /// and flagging memory or block time growth trends
+    pub async fn run_soak_test(
+        &mut self,
+        engine: &mut crate::consensus::engine::ZkSacConsensusEngine,
+        config: SoakConfig,
+    ) -> anyhow::Result<SoakReport> {
+        let report = soak::run_soak(&mut self.monitor, engine, &config).await?;
+        self.monitor.print_performance_report();
+        Ok(report)
     }
 
     pub fn get_monitor(&self) -> &PerformanceMonitor {
@@ -0,0 +1,119 @@
+//! On-demand CPU profiling
+//!
+//! Samples the running process for a fixed duration and writes both a
+//! flamegraph SVG and a gzipped pprof protobuf (readable with `go tool pprof`)
+//! into an output directory. Only one capture runs at a time. On Unix,
+//! [`spawn_signal_trigger`] lets an operator start a capture on a live node
+//! with `kill -USR1 <pid>`.
+//!
+//! Requires the `profiling` feature.
+
+use anyhow::{Result, anyhow};
+use pprof::protos::Message;
+use serde::{Serialize, Deserialize};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tracing::{error, info};
+
+/// Set while a capture is running; the sampler is process-global
+static CAPTURING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileConfig {
+    pub output_dir: PathBuf,
+    pub duration: Duration,
+    /// Samples per second
+    pub frequency_hz: i32,
+}
+
+impl Default for ProfileConfig {
+    fn default() -> Self {
+        Self {
+            output_dir: PathBuf::from("profiles"),
+            duration: Duration::from_secs(30),
+            frequency_hz: 99,
+        }
+    }
+}
+
+impl ProfileConfig {
+    pub fn with_output_dir(mut self, output_dir: impl Into<PathBuf>) -> Self {
+        self.output_dir = output_dir.into();
+        self
+    }
+
+    pub fn with_duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+}
+
+/// Files written by a capture
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileOutput {
+    pub flamegraph: PathBuf,
+    pub pprof: PathBuf,
+    pub samples: usize,
+}
+
+/// Profile the whole process for `config.duration`
+pub async fn capture(config: ProfileConfig) -> Result<ProfileOutput> {
+    if CAPTURING.swap(true, Ordering::SeqCst) {
+        return Err(anyhow!("A CPU profile is already being captured"));
+    }
+    let result = tokio::task::spawn_blocking(move || capture_blocking(&config)).await;
+    CAPTURING.store(false, Ordering::SeqCst);
+    result?
+}
+
+pub fn is_capturing() -> bool {
+    CAPTURING.load(Ordering::SeqCst)
+}
+
+fn capture_blocking(config: &ProfileConfig) -> Result<ProfileOutput> {
+    info!("🔥 Capturing CPU profile for {:?} at {} Hz", config.duration, config.frequency_hz);
+
+    let guard = pprof::ProfilerGuardBuilder::default()
+        .frequency(config.frequency_hz)
+        .blocklist(&["libc", "libgcc", "pthread", "vdso"])
+        .build()?;
+    std::thread::sleep(config.duration);
+    let report = guard.report().build()?;
+
+    std::fs::create_dir_all(&config.output_dir)?;
+    let stamp = chrono::Utc::now().format("%Y%m%dT%H%M%SZ");
+    let flamegraph = config.output_dir.join(format!("cpu-{}.svg", stamp));
+    let pprof = config.output_dir.join(format!("cpu-{}.pb.gz", stamp));
+
+    report.flamegraph(std::fs::File::create(&flamegraph)?)?;
+
+    let mut encoded = Vec::new();
+    report.pprof()?.encode(&mut encoded)?;
+    let mut encoder = flate2::write::GzEncoder::new(std::fs::File::create(&pprof)?, flate2::Compression::default());
+    encoder.write_all(&encoded)?;
+    encoder.finish()?;
+
+    let samples = report.data.values().sum::<isize>().max(0) as usize;
+    info!("🔥 CPU profile written: {} ({} samples)", flamegraph.display(), samples);
+    Ok(ProfileOutput { flamegraph, pprof, samples })
+}
+
+/// Capture a profile with `config` every time the process receives SIGUSR1
+#[cfg(unix)]
+pub fn spawn_signal_trigger(config: ProfileConfig) -> Result<tokio::task::JoinHandle<()>> {
+    use tokio::signal::unix::{signal, SignalKind};
+
+    let mut signals = signal(SignalKind::user_defined1())?;
+    info!("🔥 CPU profiling armed: send SIGUSR1 to capture {:?} into {}",
+          config.duration, config.output_dir.display());
+
+    Ok(tokio::spawn(async move {
+        while signals.recv().await.is_some() {
+            if let Err(e) = capture(config.clone()).await {
+                error!("❌ CPU profile capture failed: {}", e);
+            }
+        }
+    }))
+}
@@ -0,0 +1,183 @@
+//! Prover utilization and job queue metrics
+//!
+//! [`ProverMetrics`] is a cheap, cloneable handle so that whatever drives the
+//! prover (a job queue, the consensus engine, a benchmark) can report job
+//! lifecycle events while the monitor reads a consistent snapshot.
+
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Point-in-time view of prover activity
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ProverUtilization {
+    pub jobs_queued: u64,
+    pub jobs_in_flight: u64,
+    pub jobs_completed: u64,
+    pub jobs_failed: u64,
+    pub total_cycles: u64,
+    /// Cycles proven per second of proving time
+    pub cycles_per_second: f64,
+    pub average_proof_bytes_per_block: f64,
+    /// Fraction of wall-clock time with at least one job in flight
+    pub busy_fraction: f64,
+}
+
+#[derive(Debug)]
+struct State {
+    queued: u64,
+    in_flight: u64,
+    completed: u64,
+    failed: u64,
+    total_cycles: u64,
+    proving_time: Duration,
+    proof_bytes: u64,
+    blocks_proven: u64,
+    busy_time: Duration,
+    busy_since: Option<Instant>,
+    started: Instant,
+}
+
+impl State {
+    fn mark_busy(&mut self) {
+        if self.in_flight == 0 {
+            self.busy_since = Some(Instant::now());
+        }
+        self.in_flight += 1;
+    }
+
+    fn mark_idle(&mut self) {
+        self.in_flight = self.in_flight.saturating_sub(1);
+        if self.in_flight == 0 {
+            if let Some(since) = self.busy_since.take() {
+                self.busy_time += since.elapsed();
+            }
+        }
+    }
+}
+
+/// Shared counters describing the prover's job queue and throughput
+#[derive(Debug, Clone)]
+pub struct ProverMetrics {
+    inner: Arc<Mutex<State>>,
+}
+
+impl ProverMetrics {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(State {
+                queued: 0,
+                in_flight: 0,
+                completed: 0,
+                failed: 0,
+                total_cycles: 0,
+                proving_time: Duration::ZERO,
+                proof_bytes: 0,
+                blocks_proven: 0,
+                busy_time: Duration::ZERO,
+                busy_since: None,
+                started: Instant::now(),
+            })),
+        }
+    }
+
+    /// A proving job was submitted and is waiting for a prover
+    pub fn job_queued(&self) {
+        self.inner.lock().queued += 1;
+    }
+
+    /// A queued job was picked up by a prover
+    pub fn job_started(&self) {
+        let mut state = self.inner.lock();
+        state.queued = state.queued.saturating_sub(1);
+        state.mark_busy();
+    }
+
+    /// A job finished successfully after proving `cycles` cycles into a `proof_bytes` proof
+    pub fn job_completed(&self, cycles: u64, proof_bytes: usize, proving_time: Duration) {
+        let mut state = self.inner.lock();
+        state.mark_idle();
+        state.completed += 1;
+        state.total_cycles += cycles;
+        state.proving_time += proving_time;
+        state.proof_bytes += proof_bytes as u64;
+        state.blocks_proven += 1;
+    }
+
+    pub fn job_failed(&self) {
+        let mut state = self.inner.lock();
+        state.mark_idle();
+        state.failed += 1;
+    }
+
+    pub fn snapshot(&self) -> ProverUtilization {
+        let state = self.inner.lock();
+
+        let proving_secs = state.proving_time.as_secs_f64();
+        let cycles_per_second = if proving_secs > 0.0 {
+            state.total_cycles as f64 / proving_secs
+        } else {
+            0.0
+        };
+        let average_proof_bytes_per_block = if state.blocks_proven > 0 {
+            state.proof_bytes as f64 / state.blocks_proven as f64
+        } else {
+            0.0
+        };
+
+        let busy = state.busy_time + state.busy_since.map_or(Duration::ZERO, |since| since.elapsed());
+        let wall = state.started.elapsed().as_secs_f64();
+        let busy_fraction = if wall > 0.0 {
+            (busy.as_secs_f64() / wall).min(1.0)
+        } else {
+            0.0
+        };
+
+        ProverUtilization {
+            jobs_queued: state.queued,
+            jobs_in_flight: state.in_flight,
+            jobs_completed: state.completed,
+            jobs_failed: state.failed,
+            total_cycles: state.total_cycles,
+            cycles_per_second,
+            average_proof_bytes_per_block,
+            busy_fraction,
+        }
+    }
+}
+
+impl Default for ProverMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_job_lifecycle_counts() {
+        let metrics = ProverMetrics::new();
+        metrics.job_queued();
+        metrics.job_queued();
+        metrics.job_queued();
+        metrics.job_started();
+        metrics.job_started();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.jobs_queued, 1);
+        assert_eq!(snapshot.jobs_in_flight, 2);
+
+        metrics.job_completed(1_000_000, 2048, Duration::from_millis(500));
+        metrics.job_failed();
+
+        let snapshot = metrics.snapshot();
+        assert_eq!(snapshot.jobs_in_flight, 0);
+        assert_eq!(snapshot.jobs_completed, 1);
+        assert_eq!(snapshot.jobs_failed, 1);
+        assert_eq!(snapshot.cycles_per_second, 2_000_000.0);
+        assert_eq!(snapshot.average_proof_bytes_per_block, 2048.0);
+    }
+}
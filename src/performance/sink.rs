@@ -0,0 +1,194 @@
+//! Metrics export
+//!
+//! The monitor measures; sinks decide where measurements go. Every event the
+//! monitor produces is handed to each registered [`MetricsSink`], so
+//! embedders can route telemetry into their own systems by implementing the
+//! trait.
+
+use super::errors::ErrorCategory;
+use super::event_log::{EventLog, TelemetryEvent};
+use super::system::SystemMetrics;
+use anyhow::Result;
+use parking_lot::Mutex;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::sync::Arc;
+
+/// Destination for monitor telemetry
+pub trait MetricsSink: Send + fmt::Debug {
+    fn record(&mut self, event: &TelemetryEvent) -> Result<()>;
+
+    fn flush(&mut self) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl MetricsSink for EventLog {
+    fn record(&mut self, event: &TelemetryEvent) -> Result<()> {
+        self.append(event)
+    }
+}
+
+/// Captures every event in memory, for tests
+#[derive(Debug, Clone, Default)]
+pub struct MemorySink {
+    events: Arc<Mutex<Vec<TelemetryEvent>>>,
+}
+
+impl MemorySink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn events(&self) -> Vec<TelemetryEvent> {
+        self.events.lock().clone()
+    }
+
+    pub fn len(&self) -> usize {
+        self.events.lock().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.events.lock().is_empty()
+    }
+
+    pub fn clear(&self) {
+        self.events.lock().clear();
+    }
+}
+
+impl MetricsSink for MemorySink {
+    fn record(&mut self, event: &TelemetryEvent) -> Result<()> {
+        self.events.lock().push(event.clone());
+        Ok(())
+    }
+}
+
+#[derive(Debug, Default)]
+struct PrometheusState {
+    blocks_total: u64,
+    transactions_total: u64,
+    errors_total: BTreeMap<(ErrorCategory, String), u32>,
+    consensus_events_total: BTreeMap<String, u64>,
+    last_block_number: u64,
+    block_production_ms: u64,
+    proof_generation_ms: u64,
+    validation_ms: u64,
+    proof_size_bytes: usize,
+    transactions_per_second: f64,
+    system: Option<SystemMetrics>,
+}
+
+/// Aggregates events into counters and gauges rendered in Prometheus text format.
+/// Clones share state, so one clone can be registered with the monitor while
+/// another serves scrapes.
+#[derive(Debug, Clone, Default)]
+pub struct PrometheusSink {
+    state: Arc<Mutex<PrometheusState>>,
+}
+
+impl PrometheusSink {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn render(&self) -> String {
+        let state = self.state.lock();
+        let mut out = String::new();
+
+        let mut metric = |name: &str, kind: &str, value: String| {
+            out.push_str(&format!("# TYPE {} {}\n{} {}\n", name, kind, name, value));
+        };
+        metric("zksac_blocks_total", "counter", state.blocks_total.to_string());
+        metric("zksac_transactions_total", "counter", state.transactions_total.to_string());
+        metric("zksac_last_block_number", "gauge", state.last_block_number.to_string());
+        metric("zksac_block_production_ms", "gauge", state.block_production_ms.to_string());
+        metric("zksac_proof_generation_ms", "gauge", state.proof_generation_ms.to_string());
+        metric("zksac_validation_ms", "gauge", state.validation_ms.to_string());
+        metric("zksac_proof_size_bytes", "gauge", state.proof_size_bytes.to_string());
+        metric("zksac_transactions_per_second", "gauge", format!("{:.2}", state.transactions_per_second));
+
+        if !state.errors_total.is_empty() {
+            out.push_str("# TYPE zksac_errors_total counter\n");
+            for ((category, kind), total) in &state.errors_total {
+                out.push_str(&format!("zksac_errors_total{{category=\"{}\",kind=\"{}\"}} {}\n", category, kind, total));
+            }
+        }
+        if !state.consensus_events_total.is_empty() {
+            out.push_str("# TYPE zksac_consensus_events_total counter\n");
+            for (kind, total) in &state.consensus_events_total {
+                out.push_str(&format!("zksac_consensus_events_total{{kind=\"{}\"}} {}\n", kind, total));
+            }
+        }
+        if let Some(system) = &state.system {
+            out.push_str(&system.to_prometheus());
+        }
+        out
+    }
+}
+
+impl MetricsSink for PrometheusSink {
+    fn record(&mut self, event: &TelemetryEvent) -> Result<()> {
+        let mut state = self.state.lock();
+        match event {
+            TelemetryEvent::Benchmark(benchmark) => {
+                state.blocks_total += 1;
+                state.transactions_total += benchmark.transaction_count;
+                state.last_block_number = benchmark.block_number;
+                state.block_production_ms = benchmark.metrics.block_production_time_ms;
+                state.proof_generation_ms = benchmark.metrics.proof_generation_time_ms;
+                state.validation_ms = benchmark.metrics.validation_time_ms;
+                state.proof_size_bytes = benchmark.metrics.proof_size_bytes;
+                state.transactions_per_second = benchmark.metrics.transactions_per_second;
+            }
+            TelemetryEvent::Error { error, total } => {
+                state.errors_total.insert((error.category, error.kind.clone()), *total);
+            }
+            TelemetryEvent::Consensus { kind, .. } => {
+                *state.consensus_events_total.entry(kind.clone()).or_insert(0) += 1;
+            }
+            TelemetryEvent::System(metrics) => {
+                state.system = Some(metrics.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::performance::errors::ErrorEvent;
+    use crate::performance::PerformanceMonitor;
+
+    #[test]
+    fn test_prometheus_sink_renders_counters() {
+        let sink = PrometheusSink::new();
+        let mut writer = sink.clone();
+        writer.record(&TelemetryEvent::Error {
+            error: ErrorEvent::new(ErrorCategory::Network, "timeout"),
+            total: 3,
+        }).unwrap();
+        writer.record(&TelemetryEvent::Consensus {
+            kind: "block_finalized".to_string(),
+            block_number: 1,
+            details: serde_json::Value::Null,
+        }).unwrap();
+
+        let text = sink.render();
+        assert!(text.contains("zksac_errors_total{category=\"network\",kind=\"timeout\"} 3"));
+        assert!(text.contains("zksac_consensus_events_total{kind=\"block_finalized\"} 1"));
+        assert!(text.contains("zksac_blocks_total 0"));
+    }
+
+    #[test]
+    fn test_monitor_writes_to_memory_sink() {
+        let sink = MemorySink::new();
+        let mut monitor = PerformanceMonitor::new().with_sink(sink.clone());
+        monitor.record_error(ErrorEvent::new(ErrorCategory::Network, "timeout"));
+
+        let events = sink.events();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(&events[0], TelemetryEvent::Error { total: 1, .. }));
+    }
+}
@@ -0,0 +1,281 @@
+//! Long-running soak tests against a real consensus engine
+//!
+//! A soak run produces, validates, and applies blocks back to back for a
+//! fixed duration, printing a dashboard line on every report interval and
+//! fitting a trend line to memory usage and block time so slow leaks and
+//! creeping latencies are flagged without anyone watching the graphs.
+
+use super::{BlockProofSample, ErrorCategory, ErrorEvent, Operation, PerformanceMonitor};
+use crate::consensus::engine::{ConsensusEngine, ZkSacConsensusEngine};
+use crate::crypto::signatures::PostQuantumSigner;
+use crate::types::{Address, Transaction};
+use anyhow::{Result, anyhow};
+use serde::{Serialize, Deserialize};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+use tracing::{info, warn};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakConfig {
+    pub duration: Duration,
+    pub report_interval: Duration,
+    pub transactions_per_block: usize,
+    /// Number of most recent report samples used for trend detection
+    pub trend_samples: usize,
+    /// Flag memory growth above this percentage across the trend window
+    pub max_memory_growth_percent: f64,
+    /// Flag block time growth above this percentage across the trend window
+    pub max_block_time_growth_percent: f64,
+}
+
+impl Default for SoakConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(4 * 60 * 60), // 4 hours
+            report_interval: Duration::from_secs(60),
+            transactions_per_block: 100,
+            trend_samples: 30,
+            max_memory_growth_percent: 10.0,
+            max_block_time_growth_percent: 20.0,
+        }
+    }
+}
+
+/// One dashboard interval
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakSample {
+    pub elapsed_secs: f64,
+    pub blocks: u64,
+    pub rss_bytes: u64,
+    pub average_block_time_ms: f64,
+    pub tps: f64,
+    pub mempool_size: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Degradation {
+    MemoryGrowth { growth_percent: f64 },
+    BlockTimeGrowth { growth_percent: f64 },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SoakReport {
+    pub blocks: u64,
+    pub transactions: u64,
+    pub elapsed_secs: f64,
+    pub samples: Vec<SoakSample>,
+    pub degradations: Vec<Degradation>,
+}
+
+impl SoakReport {
+    pub fn is_healthy(&self) -> bool {
+        self.degradations.is_empty()
+    }
+}
+
+/// Relative growth of a least-squares fit across the sampled span, in percent
+pub fn trend_growth_percent(points: &[(f64, f64)]) -> Option<f64> {
+    if points.len() < 3 {
+        return None;
+    }
+
+    let n = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / n;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / n;
+    let covariance: f64 = points.iter().map(|(x, y)| (x - mean_x) * (y - mean_y)).sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    if variance == 0.0 {
+        return None;
+    }
+
+    let slope = covariance / variance;
+    let first_x = points.first()?.0;
+    let last_x = points.last()?.0;
+    let start = mean_y + slope * (first_x - mean_x);
+    if start <= 0.0 {
+        return None;
+    }
+    Some(slope * (last_x - first_x) / start * 100.0)
+}
+
+/// Generates nonce-ordered transfers between the engine's genesis accounts,
+/// signed with the post-quantum scheme since it needs no registered public key
+struct TransferSource {
+    accounts: Vec<Address>,
+    nonces: HashMap<Address, u64>,
+    cursor: usize,
+    signer: PostQuantumSigner,
+}
+
+impl TransferSource {
+    fn new(engine: &ZkSacConsensusEngine) -> Result<Self> {
+        let mut accounts: Vec<Address> = engine.current_state.accounts.keys().copied().collect();
+        if accounts.len() < 2 {
+            return Err(anyhow!("Soak test needs at least two funded accounts in the engine state"));
+        }
+        accounts.sort();
+        let nonces = engine.current_state.accounts.iter()
+            .map(|(address, account)| (*address, account.nonce))
+            .collect();
+        Ok(Self { accounts, nonces, cursor: 0, signer: PostQuantumSigner::new()? })
+    }
+
+    fn next(&mut self) -> Result<Transaction> {
+        let from = self.accounts[self.cursor % self.accounts.len()];
+        let to = self.accounts[(self.cursor + 1) % self.accounts.len()];
+        self.cursor += 1;
+
+        let nonce = self.nonces.entry(from).or_insert(0);
+        let mut tx = Transaction::with_post_quantum(from, to, 1, *nonce);
+        tx.signature = self.signer.sign_lms(&from, &tx.signing_hash().0)?;
+        *nonce += 1;
+        Ok(tx)
+    }
+}
+
+/// Drive `engine` for `config.duration`, recording every block into `monitor`
+pub async fn run_soak(
+    monitor: &mut PerformanceMonitor,
+    engine: &mut ZkSacConsensusEngine,
+    config: &SoakConfig,
+) -> Result<SoakReport> {
+    info!("🧪 Starting soak test for {:?} ({} tx/block)", config.duration, config.transactions_per_block);
+
+    let mut source = TransferSource::new(engine)?;
+    let started = Instant::now();
+    let mut next_report = started + config.report_interval;
+    let mut samples = Vec::new();
+    let mut trend: VecDeque<SoakSample> = VecDeque::new();
+    let mut degradations = Vec::new();
+
+    let mut blocks = 0u64;
+    let mut transactions = 0u64;
+    let mut interval_blocks = 0u64;
+    let mut interval_transactions = 0u64;
+    let mut interval_block_time = Duration::ZERO;
+    let mut interval_started = started;
+
+    while started.elapsed() < config.duration {
+        for _ in 0..config.transactions_per_block {
+            engine.add_local_transaction(source.next()?)?;
+        }
+
+        let block_number = engine.blocks.len() as u64 + 1;
+        let producer = engine.select_block_producer(block_number)?;
+
+        let timer = monitor.time(Operation::BlockProduction);
+        let block = engine.produce_block(producer).await?;
+        let production_time = timer.stop();
+
+        let timer = monitor.time(Operation::Validation);
+        let valid = engine.validate_block(&block).await?;
+        let validation_time = timer.stop();
+        if !valid {
+            monitor.record_error(
+                ErrorEvent::new(ErrorCategory::Validation, "invalid_block").at_block(block_number)
+            );
+            continue;
+        }
+
+        let tx_count = block.transactions.len() as u64;
+        let proof_size = block.recursive_proof.proof_data.len();
+        monitor.record_block_proof(BlockProofSample::from_transactions(
+            block_number, &block.transactions, 0, proof_size, production_time,
+        ));
+        let timer = monitor.time(Operation::BlockApplication);
+        engine.apply_block(block).await?;
+        timer.stop();
+
+        monitor.create_benchmark(block_number, tx_count, production_time, Duration::ZERO, validation_time, proof_size);
+
+        blocks += 1;
+        transactions += tx_count;
+        interval_blocks += 1;
+        interval_transactions += tx_count;
+        interval_block_time += production_time;
+
+        if Instant::now() >= next_report {
+            let interval = interval_started.elapsed().as_secs_f64();
+            let sample = SoakSample {
+                elapsed_secs: started.elapsed().as_secs_f64(),
+                blocks,
+                rss_bytes: monitor.latest_system_metrics().map_or(0, |m| m.rss_bytes),
+                average_block_time_ms: interval_block_time.as_secs_f64() * 1000.0 / interval_blocks.max(1) as f64,
+                tps: if interval > 0.0 { interval_transactions as f64 / interval } else { 0.0 },
+                mempool_size: engine.mempool.len(),
+            };
+            info!("📟 [{:>7.0}s] blocks {} | {:.1} TPS | {:.2} ms/block | {:.1} MB RSS | mempool {}",
+                  sample.elapsed_secs, sample.blocks, sample.tps, sample.average_block_time_ms,
+                  sample.rss_bytes as f64 / (1024.0 * 1024.0), sample.mempool_size);
+
+            trend.push_back(sample.clone());
+            if trend.len() > config.trend_samples.max(3) {
+                trend.pop_front();
+            }
+            for degradation in detect_degradations(&trend, config) {
+                if !degradations.contains(&degradation) {
+                    warn!("📈 Soak degradation detected: {:?}", degradation);
+                }
+                degradations.retain(|d| std::mem::discriminant(d) != std::mem::discriminant(&degradation));
+                degradations.push(degradation);
+            }
+            samples.push(sample);
+
+            interval_blocks = 0;
+            interval_transactions = 0;
+            interval_block_time = Duration::ZERO;
+            interval_started = Instant::now();
+            next_report += config.report_interval;
+        }
+
+        // Let background tasks (samplers, notifiers) make progress between blocks
+        tokio::task::yield_now().await;
+    }
+
+    let report = SoakReport {
+        blocks,
+        transactions,
+        elapsed_secs: started.elapsed().as_secs_f64(),
+        samples,
+        degradations,
+    };
+    info!("🧪 Soak test finished: {} blocks, {} transactions, {} degradations",
+          report.blocks, report.transactions, report.degradations.len());
+    Ok(report)
+}
+
+fn detect_degradations(samples: &VecDeque<SoakSample>, config: &SoakConfig) -> Vec<Degradation> {
+    let mut found = Vec::new();
+
+    let memory: Vec<(f64, f64)> = samples.iter().map(|s| (s.elapsed_secs, s.rss_bytes as f64)).collect();
+    if let Some(growth_percent) = trend_growth_percent(&memory) {
+        if growth_percent > config.max_memory_growth_percent {
+            found.push(Degradation::MemoryGrowth { growth_percent });
+        }
+    }
+
+    let block_time: Vec<(f64, f64)> = samples.iter().map(|s| (s.elapsed_secs, s.average_block_time_ms)).collect();
+    if let Some(growth_percent) = trend_growth_percent(&block_time) {
+        if growth_percent > config.max_block_time_growth_percent {
+            found.push(Degradation::BlockTimeGrowth { growth_percent });
+        }
+    }
+
+    found
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_trend_growth_detects_linear_increase() {
+        let rising: Vec<(f64, f64)> = (0..10).map(|i| (i as f64, 100.0 + i as f64 * 5.0)).collect();
+        let growth = trend_growth_percent(&rising).unwrap();
+        assert!((growth - 45.0).abs() < 1e-9);
+
+        let flat: Vec<(f64, f64)> = (0..10).map(|i| (i as f64, 100.0)).collect();
+        assert_eq!(trend_growth_percent(&flat), Some(0.0));
+        assert_eq!(trend_growth_percent(&rising[..2]), None);
+    }
+}
@@ -0,0 +1,222 @@
+//! Scoped operation timers
+//!
+//! A [`TimerGuard`] measures from creation until it is stopped or dropped and
+//! records the elapsed time for its [`Operation`]. Guards share a recorder, so
+//! any number of timers for the same operation can run concurrently.
+//!
+//! Built with `alloc-tracking`, a guard also records what its thread
+//! allocated while it ran. A guard finished on another thread than it was
+//! started on (an async task that moved) records its time only.
+
+use super::alloc::{self, AllocationCount};
+use super::histogram::{LatencyHistogram, Percentiles};
+use parking_lot::Mutex;
+use serde::{Serialize, Deserialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
+use tracing::debug;
+
+/// Operations the monitor knows how to time
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Operation {
+    ConsensusCycle,
+    ProducerSelection,
+    BlockProduction,
+    ProofGeneration,
+    ProofVerification,
+    Validation,
+    BlockApplication,
+    SignatureVerification,
+}
+
+impl fmt::Display for Operation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Operation::ConsensusCycle => "consensus_cycle",
+            Operation::ProducerSelection => "producer_selection",
+            Operation::BlockProduction => "block_production",
+            Operation::ProofGeneration => "proof_generation",
+            Operation::ProofVerification => "proof_verification",
+            Operation::Validation => "validation",
+            Operation::BlockApplication => "block_application",
+            Operation::SignatureVerification => "signature_verification",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Heap allocated by all timed runs of one operation
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OperationAllocations {
+    pub bytes: u64,
+    pub allocations: u64,
+    /// Timed runs the allocations were measured over
+    pub samples: u64,
+}
+
+impl OperationAllocations {
+    pub fn average_bytes(&self) -> f64 {
+        if self.samples == 0 { 0.0 } else { self.bytes as f64 / self.samples as f64 }
+    }
+}
+
+/// Shared per-operation latency store written to by timer guards
+#[derive(Debug, Clone, Default)]
+pub struct TimingRecorder {
+    inner: Arc<Mutex<HashMap<Operation, LatencyHistogram>>>,
+    allocations: Arc<Mutex<HashMap<Operation, OperationAllocations>>>,
+}
+
+impl TimingRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start timing `operation`; the elapsed time is recorded when the guard is stopped or dropped
+    pub fn start(&self, operation: Operation) -> TimerGuard {
+        debug!("⏱️  Started timer for: {}", operation);
+        TimerGuard {
+            recorder: self.clone(),
+            operation,
+            started: Instant::now(),
+            thread: thread::current().id(),
+            allocated_before: alloc::thread_allocated(),
+            finished: false,
+        }
+    }
+
+    pub fn record(&self, operation: Operation, duration: Duration) {
+        self.inner.lock().entry(operation).or_default().record(duration);
+    }
+
+    /// Add what one run of `operation` allocated
+    pub fn record_allocations(&self, operation: Operation, count: AllocationCount) {
+        let mut allocations = self.allocations.lock();
+        let entry = allocations.entry(operation).or_default();
+        entry.bytes += count.bytes;
+        entry.allocations += count.allocations;
+        entry.samples += 1;
+    }
+
+    /// Allocations per operation; empty unless built with `alloc-tracking`
+    pub fn allocations(&self) -> HashMap<Operation, OperationAllocations> {
+        self.allocations.lock().clone()
+    }
+
+    pub fn count(&self, operation: Operation) -> u64 {
+        self.inner.lock().get(&operation).map_or(0, |h| h.count())
+    }
+
+    pub fn percentiles(&self, operation: Operation) -> Percentiles {
+        self.inner.lock().get(&operation).map(|h| h.percentiles()).unwrap_or_default()
+    }
+
+    /// Snapshot of all recorded operations
+    pub fn snapshot(&self) -> HashMap<Operation, LatencyHistogram> {
+        self.inner.lock().clone()
+    }
+}
+
+/// RAII timer that records its elapsed time exactly once
+#[must_use = "dropping a TimerGuard immediately records a near-zero duration"]
+pub struct TimerGuard {
+    recorder: TimingRecorder,
+    operation: Operation,
+    started: Instant,
+    thread: ThreadId,
+    allocated_before: AllocationCount,
+    finished: bool,
+}
+
+impl TimerGuard {
+    pub fn operation(&self) -> Operation {
+        self.operation
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    /// Stop the timer, record the measurement, and return it
+    pub fn stop(mut self) -> Duration {
+        self.finish()
+    }
+
+    /// Stop the timer without recording anything
+    pub fn cancel(mut self) {
+        self.finished = true;
+    }
+
+    fn finish(&mut self) -> Duration {
+        let elapsed = self.started.elapsed();
+        if !self.finished {
+            self.finished = true;
+            self.recorder.record(self.operation, elapsed);
+            if cfg!(feature = "alloc-tracking") && thread::current().id() == self.thread {
+                let allocated = alloc::thread_allocated().since(&self.allocated_before);
+                self.recorder.record_allocations(self.operation, allocated);
+            }
+            debug!("⏱️  {} completed in {:?}", self.operation, elapsed);
+        }
+        elapsed
+    }
+}
+
+impl Drop for TimerGuard {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_guard_records_on_drop_and_stop() {
+        let recorder = TimingRecorder::new();
+        {
+            let _guard = recorder.start(Operation::Validation);
+        }
+        let elapsed = recorder.start(Operation::Validation).stop();
+        assert!(elapsed < Duration::from_secs(1));
+        assert_eq!(recorder.count(Operation::Validation), 2);
+    }
+
+    #[test]
+    fn test_concurrent_timers_same_operation() {
+        let recorder = TimingRecorder::new();
+        let first = recorder.start(Operation::ProofGeneration);
+        let second = recorder.start(Operation::ProofGeneration);
+        std::thread::sleep(Duration::from_millis(5));
+        second.stop();
+        first.stop();
+
+        assert_eq!(recorder.count(Operation::ProofGeneration), 2);
+        assert_eq!(recorder.count(Operation::BlockProduction), 0);
+    }
+
+    #[test]
+    fn test_allocations_accumulate_per_operation() {
+        let recorder = TimingRecorder::new();
+        let before = AllocationCount { bytes: 100, allocations: 2 };
+        let after = AllocationCount { bytes: 4196, allocations: 5 };
+        recorder.record_allocations(Operation::BlockProduction, after.since(&before));
+        recorder.record_allocations(Operation::BlockProduction, AllocationCount { bytes: 0, allocations: 0 });
+
+        let production = recorder.allocations()[&Operation::BlockProduction];
+        assert_eq!(production, OperationAllocations { bytes: 4096, allocations: 3, samples: 2 });
+        assert_eq!(production.average_bytes(), 2048.0);
+        assert!(!recorder.allocations().contains_key(&Operation::Validation));
+    }
+
+    #[test]
+    fn test_cancel_records_nothing() {
+        let recorder = TimingRecorder::new();
+        recorder.start(Operation::BlockApplication).cancel();
+        assert_eq!(recorder.count(Operation::BlockApplication), 0);
+    }
+}
@@ -0,0 +1,275 @@
+//! Rolling-window and per-epoch aggregation of block metrics
+
+use super::errors::ErrorCategory;
+use serde::{Serialize, Deserialize};
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
+
+/// Time windows the monitor can be queried over
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Window {
+    OneMinute,
+    FiveMinutes,
+    OneHour,
+}
+
+impl Window {
+    pub const ALL: [Window; 3] = [Window::OneMinute, Window::FiveMinutes, Window::OneHour];
+
+    pub fn duration(&self) -> Duration {
+        match self {
+            Window::OneMinute => Duration::from_secs(60),
+            Window::FiveMinutes => Duration::from_secs(5 * 60),
+            Window::OneHour => Duration::from_secs(60 * 60),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Window::OneMinute => "1m",
+            Window::FiveMinutes => "5m",
+            Window::OneHour => "1h",
+        }
+    }
+}
+
+/// Aggregates over a window or epoch
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WindowStats {
+    pub blocks: u64,
+    pub transactions: u64,
+    pub errors: u64,
+    pub tps: f64,
+    pub average_proof_time_ms: f64,
+    /// Errors per block
+    pub error_rate: f64,
+    #[serde(default)]
+    pub errors_by_category: BTreeMap<ErrorCategory, u64>,
+}
+
+impl WindowStats {
+    /// Errors of `category` per block
+    pub fn category_error_rate(&self, category: ErrorCategory) -> f64 {
+        let errors = self.errors_by_category.get(&category).copied().unwrap_or(0);
+        if self.blocks > 0 {
+            errors as f64 / self.blocks as f64
+        } else {
+            0.0
+        }
+    }
+
+    fn count_error(&mut self, category: ErrorCategory) {
+        self.errors += 1;
+        *self.errors_by_category.entry(category).or_insert(0) += 1;
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Event {
+    Block { transactions: u64, proof_time_ms: u64 },
+    Error(ErrorCategory),
+}
+
+/// Time-ordered event buffer retaining one hour of history
+#[derive(Debug)]
+pub struct RollingAggregator {
+    events: VecDeque<(Instant, Event)>,
+    started: Instant,
+}
+
+impl RollingAggregator {
+    pub fn new() -> Self {
+        Self {
+            events: VecDeque::new(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn record_block(&mut self, transactions: u64, proof_time: Duration) {
+        self.push(Event::Block {
+            transactions,
+            proof_time_ms: proof_time.as_millis() as u64,
+        });
+    }
+
+    pub fn record_error(&mut self, category: ErrorCategory) {
+        self.push(Event::Error(category));
+    }
+
+    pub fn stats(&self, window: Window) -> WindowStats {
+        self.stats_at(window, Instant::now())
+    }
+
+    fn stats_at(&self, window: Window, now: Instant) -> WindowStats {
+        let span = window.duration();
+        let mut stats = WindowStats::default();
+        let mut total_proof_ms = 0u64;
+
+        for (at, event) in self.events.iter().rev() {
+            if now.duration_since(*at) > span {
+                break;
+            }
+            match event {
+                Event::Block { transactions, proof_time_ms } => {
+                    stats.blocks += 1;
+                    stats.transactions += transactions;
+                    total_proof_ms += proof_time_ms;
+                }
+                Event::Error(category) => stats.count_error(*category),
+            }
+        }
+
+        // A window longer than our uptime is measured over the uptime instead
+        let elapsed = span.min(now.duration_since(self.started)).as_secs_f64();
+        if elapsed > 0.0 {
+            stats.tps = stats.transactions as f64 / elapsed;
+        }
+        if stats.blocks > 0 {
+            stats.average_proof_time_ms = total_proof_ms as f64 / stats.blocks as f64;
+            stats.error_rate = stats.errors as f64 / stats.blocks as f64;
+        }
+        stats
+    }
+
+    fn push(&mut self, event: Event) {
+        let now = Instant::now();
+        self.events.push_back((now, event));
+
+        let retention = Window::OneHour.duration();
+        while let Some((at, _)) = self.events.front() {
+            if now.duration_since(*at) <= retention {
+                break;
+            }
+            self.events.pop_front();
+        }
+    }
+}
+
+impl Default for RollingAggregator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Running totals for one epoch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EpochStats {
+    pub epoch: u64,
+    pub stats: WindowStats,
+    pub block_time_total_ms: u64,
+}
+
+/// Per-epoch aggregates keyed by epoch number (`block_number / epoch_length`)
+#[derive(Debug)]
+pub struct EpochAggregator {
+    epoch_length: u64,
+    epochs: BTreeMap<u64, EpochStats>,
+    current_epoch: u64,
+}
+
+impl EpochAggregator {
+    pub fn new(epoch_length: u64) -> Self {
+        Self {
+            epoch_length: epoch_length.max(1),
+            epochs: BTreeMap::new(),
+            current_epoch: 0,
+        }
+    }
+
+    pub fn epoch_of(&self, block_number: u64) -> u64 {
+        block_number / self.epoch_length
+    }
+
+    pub fn record_block(&mut self, block_number: u64, transactions: u64, block_time: Duration, proof_time: Duration) {
+        let epoch = self.epoch_of(block_number);
+        self.current_epoch = epoch;
+
+        let entry = self.entry(epoch);
+        entry.stats.blocks += 1;
+        entry.stats.transactions += transactions;
+        entry.block_time_total_ms += block_time.as_millis() as u64;
+        let blocks = entry.stats.blocks as f64;
+        entry.stats.average_proof_time_ms +=
+            (proof_time.as_millis() as f64 - entry.stats.average_proof_time_ms) / blocks;
+        Self::refresh_rates(entry);
+    }
+
+    /// Attribute an error to the epoch of the most recent block
+    pub fn record_error(&mut self, category: ErrorCategory) {
+        let entry = self.entry(self.current_epoch);
+        entry.stats.count_error(category);
+        Self::refresh_rates(entry);
+    }
+
+    pub fn get(&self, epoch: u64) -> Option<&EpochStats> {
+        self.epochs.get(&epoch)
+    }
+
+    pub fn current(&self) -> Option<&EpochStats> {
+        self.epochs.get(&self.current_epoch)
+    }
+
+    pub fn all(&self) -> impl Iterator<Item = &EpochStats> {
+        self.epochs.values()
+    }
+
+    fn entry(&mut self, epoch: u64) -> &mut EpochStats {
+        self.epochs.entry(epoch).or_insert_with(|| EpochStats {
+            epoch,
+            stats: WindowStats::default(),
+            block_time_total_ms: 0,
+        })
+    }
+
+    fn refresh_rates(entry: &mut EpochStats) {
+        if entry.block_time_total_ms > 0 {
+            entry.stats.tps = entry.stats.transactions as f64 * 1000.0 / entry.block_time_total_ms as f64;
+        }
+        if entry.stats.blocks > 0 {
+            entry.stats.error_rate = entry.stats.errors as f64 / entry.stats.blocks as f64;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_window_excludes_old_events() {
+        let mut rolling = RollingAggregator::new();
+        rolling.record_block(100, Duration::from_millis(40));
+        rolling.record_error(ErrorCategory::Network);
+
+        let later = Instant::now() + Duration::from_secs(120);
+        assert_eq!(rolling.stats_at(Window::OneMinute, later).blocks, 0);
+
+        let stats = rolling.stats_at(Window::FiveMinutes, later);
+        assert_eq!(stats.blocks, 1);
+        assert_eq!(stats.transactions, 100);
+        assert_eq!(stats.average_proof_time_ms, 40.0);
+        assert_eq!(stats.error_rate, 1.0);
+        assert_eq!(stats.category_error_rate(ErrorCategory::Network), 1.0);
+        assert_eq!(stats.category_error_rate(ErrorCategory::Proving), 0.0);
+    }
+
+    #[test]
+    fn test_epoch_aggregation() {
+        let mut epochs = EpochAggregator::new(10);
+        epochs.record_block(9, 50, Duration::from_millis(500), Duration::from_millis(100));
+        epochs.record_block(10, 20, Duration::from_millis(500), Duration::from_millis(100));
+        epochs.record_block(11, 30, Duration::from_millis(500), Duration::from_millis(300));
+        epochs.record_error(ErrorCategory::Proving);
+
+        let first = epochs.get(0).unwrap();
+        assert_eq!(first.stats.transactions, 50);
+        assert_eq!(first.stats.errors, 0);
+
+        let second = epochs.current().unwrap();
+        assert_eq!(second.epoch, 1);
+        assert_eq!(second.stats.blocks, 2);
+        assert_eq!(second.stats.tps, 50.0);
+        assert_eq!(second.stats.average_proof_time_ms, 200.0);
+        assert_eq!(second.stats.error_rate, 0.5);
+    }
+}
@@ -0,0 +1,443 @@
+//! Protobuf types for external integrations
+//!
+//! [`pb`] is generated from `proto/zksac.proto` at build time. Engine types
+//! convert into their protobuf form with `From`, and back with `TryFrom`,
+//! which checks fixed-size fields and enum values coming from other
+//! languages.
+
+use crate::types::*;
+
+pub mod pb {
+    include!(concat!(env!("OUT_DIR"), "/zksac.v1.rs"));
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum ProtoError {
+    #[error("field '{field}' must be {expected} bytes, got {found}")]
+    InvalidLength { field: &'static str, expected: usize, found: usize },
+    #[error("field '{field}' has unknown enum value {value}")]
+    UnknownEnum { field: &'static str, value: i32 },
+    #[error("missing field '{0}'")]
+    MissingField(&'static str),
+}
+
+fn fixed<const N: usize>(field: &'static str, bytes: &[u8]) -> Result<[u8; N], ProtoError> {
+    bytes.try_into().map_err(|_| ProtoError::InvalidLength { field, expected: N, found: bytes.len() })
+}
+
+fn address(field: &'static str, bytes: &[u8]) -> Result<Address, ProtoError> {
+    fixed(field, bytes).map(Address)
+}
+
+fn hash(field: &'static str, bytes: &[u8]) -> Result<BlockHash, ProtoError> {
+    fixed(field, bytes).map(BlockHash)
+}
+
+impl From<&SignatureType> for pb::SignatureType {
+    fn from(sig_type: &SignatureType) -> Self {
+        match sig_type {
+            SignatureType::Ed25519 => pb::SignatureType::Ed25519,
+            SignatureType::Secp256k1 => pb::SignatureType::Secp256k1,
+            SignatureType::PostQuantum => pb::SignatureType::PostQuantum,
+        }
+    }
+}
+
+fn signature_type(field: &'static str, value: i32) -> Result<SignatureType, ProtoError> {
+    match pb::SignatureType::try_from(value) {
+        Ok(pb::SignatureType::Ed25519) => Ok(SignatureType::Ed25519),
+        Ok(pb::SignatureType::Secp256k1) => Ok(SignatureType::Secp256k1),
+        Ok(pb::SignatureType::PostQuantum) => Ok(SignatureType::PostQuantum),
+        Err(_) => Err(ProtoError::UnknownEnum { field, value }),
+    }
+}
+
+impl From<&ProofType> for pb::ProofType {
+    fn from(proof_type: &ProofType) -> Self {
+        match proof_type {
+            ProofType::SP1 => pb::ProofType::Sp1,
+            ProofType::Risc0 => pb::ProofType::Risc0,
+            ProofType::Plonky3 => pb::ProofType::Plonky3,
+            ProofType::Deferred => pb::ProofType::Deferred,
+        }
+    }
+}
+
+fn proof_type(value: i32) -> Result<ProofType, ProtoError> {
+    match pb::ProofType::try_from(value) {
+        Ok(pb::ProofType::Sp1) => Ok(ProofType::SP1),
+        Ok(pb::ProofType::Risc0) => Ok(ProofType::Risc0),
+        Ok(pb::ProofType::Plonky3) => Ok(ProofType::Plonky3),
+        Ok(pb::ProofType::Deferred) => Ok(ProofType::Deferred),
+        Err(_) => Err(ProtoError::UnknownEnum { field: "proof_type", value }),
+    }
+}
+
+impl From<&AccessListItem> for pb::AccessListItem {
+    fn from(item: &AccessListItem) -> Self {
+        pb::AccessListItem {
+            address: item.address.0.to_vec(),
+            storage_keys: item.storage_keys.iter().map(|key| key.to_vec()).collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::AccessListItem> for AccessListItem {
+    type Error = ProtoError;
+
+    fn try_from(item: pb::AccessListItem) -> Result<Self, Self::Error> {
+        Ok(AccessListItem {
+            address: address("access_list.address", &item.address)?,
+            storage_keys: item.storage_keys.iter()
+                .map(|key| fixed("access_list.storage_keys", key))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+impl From<&Transaction> for pb::Transaction {
+    fn from(tx: &Transaction) -> Self {
+        pb::Transaction {
+            from: tx.from.0.to_vec(),
+            to: tx.to.0.to_vec(),
+            value: tx.value,
+            data: tx.data.clone(),
+            gas_limit: tx.gas_limit,
+            gas_price: tx.gas_price,
+            nonce: tx.nonce,
+            chain_id: tx.chain_id,
+            access_list: tx.access_list.as_ref().map(|items| pb::AccessList {
+                items: items.iter().map(pb::AccessListItem::from).collect(),
+            }),
+            signature: tx.signature.clone(),
+            sig_type: pb::SignatureType::from(&tx.sig_type) as i32,
+        }
+    }
+}
+
+impl TryFrom<pb::Transaction> for Transaction {
+    type Error = ProtoError;
+
+    fn try_from(tx: pb::Transaction) -> Result<Self, Self::Error> {
+        Ok(Transaction {
+            from: address("from", &tx.from)?,
+            to: address("to", &tx.to)?,
+            value: tx.value,
+            data: tx.data,
+            gas_limit: tx.gas_limit,
+            gas_price: tx.gas_price,
+            nonce: tx.nonce,
+            chain_id: tx.chain_id,
+            access_list: tx.access_list
+                .map(|list| list.items.into_iter().map(AccessListItem::try_from).collect())
+                .transpose()?,
+            signature: tx.signature,
+            sig_type: signature_type("sig_type", tx.sig_type)?,
+        })
+    }
+}
+
+impl From<&BlockHeader> for pb::BlockHeader {
+    fn from(header: &BlockHeader) -> Self {
+        pb::BlockHeader {
+            previous_hash: header.previous_hash.0.to_vec(),
+            merkle_root: header.merkle_root.0.to_vec(),
+            state_root: header.state_root.0.to_vec(),
+            timestamp: header.timestamp,
+            block_number: header.block_number,
+            gas_limit: header.gas_limit,
+            gas_used: header.gas_used,
+            producer: header.producer.0.to_vec(),
+            extra_data: header.extra_data.clone(),
+            randomness_output: header.randomness.output.clone(),
+            randomness_proof: header.randomness.proof.clone(),
+            prev_state_root: header.prev_state_root.0.to_vec(),
+        }
+    }
+}
+
+impl TryFrom<pb::BlockHeader> for BlockHeader {
+    type Error = ProtoError;
+
+    fn try_from(header: pb::BlockHeader) -> Result<Self, Self::Error> {
+        Ok(BlockHeader {
+            previous_hash: hash("previous_hash", &header.previous_hash)?,
+            merkle_root: hash("merkle_root", &header.merkle_root)?,
+            prev_state_root: hash("prev_state_root", &header.prev_state_root)?,
+            state_root: hash("state_root", &header.state_root)?,
+            timestamp: header.timestamp,
+            block_number: header.block_number,
+            gas_limit: header.gas_limit,
+            gas_used: header.gas_used,
+            producer: address("producer", &header.producer)?,
+            extra_data: header.extra_data,
+            randomness: RandomnessProof { output: header.randomness_output, proof: header.randomness_proof },
+        })
+    }
+}
+
+impl From<&ValidatorSignature> for pb::ValidatorSignature {
+    fn from(signature: &ValidatorSignature) -> Self {
+        pb::ValidatorSignature {
+            validator_address: signature.validator_address.0.to_vec(),
+            stake_weight: signature.stake_weight,
+            signature: signature.signature.clone(),
+            sig_type: pb::SignatureType::from(&signature.sig_type) as i32,
+        }
+    }
+}
+
+impl TryFrom<pb::ValidatorSignature> for ValidatorSignature {
+    type Error = ProtoError;
+
+    fn try_from(signature: pb::ValidatorSignature) -> Result<Self, Self::Error> {
+        Ok(ValidatorSignature {
+            validator_address: address("validator_address", &signature.validator_address)?,
+            stake_weight: signature.stake_weight,
+            signature: signature.signature,
+            sig_type: signature_type("sig_type", signature.sig_type)?,
+        })
+    }
+}
+
+impl From<&AggregateSignature> for pb::AggregateSignature {
+    fn from(aggregate: &AggregateSignature) -> Self {
+        pb::AggregateSignature { signers: aggregate.signers.clone(), signature: aggregate.signature.clone() }
+    }
+}
+
+impl From<pb::AggregateSignature> for AggregateSignature {
+    fn from(aggregate: pb::AggregateSignature) -> Self {
+        AggregateSignature { signers: aggregate.signers, signature: aggregate.signature }
+    }
+}
+
+impl From<&ZkProof> for pb::ZkProof {
+    fn from(proof: &ZkProof) -> Self {
+        pb::ZkProof {
+            proof_data: proof.proof_data.clone(),
+            public_inputs: proof.public_inputs.clone(),
+            verification_key: proof.verification_key.clone(),
+            proof_type: pb::ProofType::from(&proof.proof_type) as i32,
+        }
+    }
+}
+
+impl TryFrom<pb::ZkProof> for ZkProof {
+    type Error = ProtoError;
+
+    fn try_from(proof: pb::ZkProof) -> Result<Self, Self::Error> {
+        Ok(ZkProof {
+            proof_data: proof.proof_data,
+            public_inputs: proof.public_inputs,
+            verification_key: proof.verification_key,
+            proof_type: proof_type(proof.proof_type)?,
+        })
+    }
+}
+
+impl From<&ProtocolRule> for pb::ProtocolRule {
+    fn from(rule: &ProtocolRule) -> Self {
+        pb::ProtocolRule {
+            rule_id: rule.rule_id,
+            rule_data: rule.rule_data.clone(),
+            validity_proof: Some((&rule.validity_proof).into()),
+            activation_epoch: rule.activation_epoch,
+        }
+    }
+}
+
+impl TryFrom<pb::ProtocolRule> for ProtocolRule {
+    type Error = ProtoError;
+
+    fn try_from(rule: pb::ProtocolRule) -> Result<Self, Self::Error> {
+        Ok(ProtocolRule {
+            rule_id: rule.rule_id,
+            rule_data: rule.rule_data,
+            validity_proof: rule.validity_proof.ok_or(ProtoError::MissingField("validity_proof"))?.try_into()?,
+            activation_epoch: rule.activation_epoch,
+        })
+    }
+}
+
+impl From<&Block> for pb::Block {
+    fn from(block: &Block) -> Self {
+        pb::Block {
+            header: Some((&block.header).into()),
+            transactions: block.transactions.iter().map(Into::into).collect(),
+            validator_signatures: block.validator_signatures.iter().map(Into::into).collect(),
+            recursive_proof: Some((&block.recursive_proof).into()),
+            protocol_updates: block.protocol_updates.iter().map(Into::into).collect(),
+            proof_signature: block.proof_signature.clone(),
+            aggregate_signature: block.aggregate_signature.as_ref().map(Into::into),
+        }
+    }
+}
+
+impl TryFrom<pb::Block> for Block {
+    type Error = ProtoError;
+
+    fn try_from(block: pb::Block) -> Result<Self, Self::Error> {
+        Ok(Block {
+            header: block.header.ok_or(ProtoError::MissingField("header"))?.try_into()?,
+            transactions: block.transactions.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+            validator_signatures: block.validator_signatures.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+            recursive_proof: block.recursive_proof.ok_or(ProtoError::MissingField("recursive_proof"))?.try_into()?,
+            protocol_updates: block.protocol_updates.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+            proof_signature: block.proof_signature,
+            aggregate_signature: block.aggregate_signature.map(Into::into),
+        })
+    }
+}
+
+impl From<&Validator> for pb::Validator {
+    fn from(validator: &Validator) -> Self {
+        pb::Validator {
+            address: validator.address.0.to_vec(),
+            stake: validator.stake,
+            public_key: validator.public_key.clone(),
+            performance_score: validator.performance_score,
+            bls_public_key: validator.bls_public_key.clone(),
+        }
+    }
+}
+
+impl TryFrom<pb::Validator> for Validator {
+    type Error = ProtoError;
+
+    fn try_from(validator: pb::Validator) -> Result<Self, Self::Error> {
+        Ok(Validator {
+            address: address("address", &validator.address)?,
+            stake: validator.stake,
+            public_key: validator.public_key,
+            performance_score: validator.performance_score,
+            bls_public_key: validator.bls_public_key,
+        })
+    }
+}
+
+impl From<&QueuedActivation> for pb::QueuedActivation {
+    fn from(queued: &QueuedActivation) -> Self {
+        pb::QueuedActivation { validator: Some((&queued.validator).into()), epoch: queued.epoch }
+    }
+}
+
+impl TryFrom<pb::QueuedActivation> for QueuedActivation {
+    type Error = ProtoError;
+
+    fn try_from(queued: pb::QueuedActivation) -> Result<Self, Self::Error> {
+        Ok(QueuedActivation {
+            validator: queued.validator.ok_or(ProtoError::MissingField("validator"))?.try_into()?,
+            epoch: queued.epoch,
+        })
+    }
+}
+
+impl From<&QueuedExit> for pb::QueuedExit {
+    fn from(queued: &QueuedExit) -> Self {
+        pb::QueuedExit { address: queued.address.0.to_vec(), epoch: queued.epoch }
+    }
+}
+
+impl TryFrom<pb::QueuedExit> for QueuedExit {
+    type Error = ProtoError;
+
+    fn try_from(queued: pb::QueuedExit) -> Result<Self, Self::Error> {
+        Ok(QueuedExit { address: address("address", &queued.address)?, epoch: queued.epoch })
+    }
+}
+
+impl From<&ValidatorSet> for pb::ValidatorSet {
+    fn from(set: &ValidatorSet) -> Self {
+        pb::ValidatorSet {
+            validators: set.validators.iter().map(Into::into).collect(),
+            total_stake: set.total_stake,
+            activation_queue: set.activation_queue.iter().map(Into::into).collect(),
+            exit_queue: set.exit_queue.iter().map(Into::into).collect(),
+        }
+    }
+}
+
+impl TryFrom<pb::ValidatorSet> for ValidatorSet {
+    type Error = ProtoError;
+
+    fn try_from(set: pb::ValidatorSet) -> Result<Self, Self::Error> {
+        Ok(ValidatorSet {
+            validators: set.validators.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+            total_stake: set.total_stake,
+            activation_queue: set.activation_queue.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+            exit_queue: set.exit_queue.into_iter().map(TryInto::try_into).collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Receipts for every transaction in `block`
+pub fn receipts(block: &Block) -> Vec<pb::Receipt> {
+    let block_hash = block.header.hash();
+    block.transactions.iter().enumerate()
+        .map(|(index, tx)| pb::Receipt {
+            transaction_hash: tx.hash().0.to_vec(),
+            block_hash: block_hash.0.to_vec(),
+            block_number: block.header.block_number,
+            index: index as u32,
+            gas_used: tx.gas_limit,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn test_block_roundtrips_through_protobuf_bytes() {
+        let block = Block {
+            header: BlockHeader {
+                previous_hash: BlockHash([1; 32]),
+                merkle_root: BlockHash::zero(),
+                prev_state_root: BlockHash::zero(),
+                state_root: BlockHash([2; 32]),
+                timestamp: 10,
+                block_number: 3,
+                gas_limit: 30_000_000,
+                gas_used: 21_000,
+                producer: Address::new(9),
+                extra_data: vec![],
+                randomness: RandomnessProof::default(),
+            },
+            transactions: vec![Transaction::with_post_quantum(Address::new(1), Address::new(2), 5, 0)],
+            validator_signatures: vec![],
+            recursive_proof: ZkProof {
+                proof_data: vec![7; 16],
+                public_inputs: vec![],
+                verification_key: vec![],
+                proof_type: ProofType::Deferred,
+            },
+            protocol_updates: vec![],
+            proof_signature: vec![4; 64],
+            aggregate_signature: None,
+        };
+
+        let bytes = pb::Block::from(&block).encode_to_vec();
+        let decoded = Block::try_from(pb::Block::decode(bytes.as_slice()).unwrap()).unwrap();
+        assert_eq!(decoded.header.hash(), block.header.hash());
+        assert_eq!(decoded.transactions[0].hash(), block.transactions[0].hash());
+
+        let receipts = receipts(&block);
+        assert_eq!(receipts[0].transaction_hash, block.transactions[0].hash().0.to_vec());
+    }
+
+    #[test]
+    fn test_rejects_bad_lengths_and_enums() {
+        let mut tx = pb::Transaction::from(&Transaction::new(Address::new(1), Address::new(2), 1, 0));
+        tx.to = vec![0; 19];
+        assert_eq!(Transaction::try_from(tx.clone()).unwrap_err(),
+                   ProtoError::InvalidLength { field: "to", expected: 20, found: 19 });
+
+        tx.to = vec![0; 20];
+        tx.sig_type = 42;
+        assert_eq!(Transaction::try_from(tx).unwrap_err(),
+                   ProtoError::UnknownEnum { field: "sig_type", value: 42 });
+    }
+}
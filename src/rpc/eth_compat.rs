@@ -0,0 +1,503 @@
+//! Ethereum JSON-RPC methods over HTTP, for this chain's own transactions
+//!
+//! Serves the `eth_*` methods tooling needs to read balances, submit
+//! transactions and follow them into blocks, in Ethereum's JSON encoding:
+//!
+//! - `eth_chainId`, `eth_blockNumber`
+//! - `eth_getBalance`
+//! - `eth_sendRawTransaction`
+//! - `eth_getTransactionReceipt`
+//! - `eth_call`
+//! - `zksac_checkpointStatus`, how far the chain is settled on L1, when the
+//!   API was given a bridge ([`EthApi::with_bridge`])
+//!
+//! This is not a drop-in Ethereum endpoint, and wallets that sign Ethereum
+//! transactions cannot submit through it:
+//!
+//! - Transactions are signed over this chain's own encoding, so the raw
+//!   transaction is a [`Transaction`] sealed in its versioned envelope
+//!   ([`seal`](crate::serialization::seal)). RLP legacy, EIP-155 and typed
+//!   (EIP-1559 and later) transactions are rejected as such.
+//! - Receipts are indexed from the event bus and cover the blocks applied
+//!   since the API was created; they carry no logs.
+//! - Only the latest state is kept, so state queries for older blocks are
+//!   rejected.
+
+use super::{Request, Response, RpcError, data, parse_quantity, quantity};
+use crate::bridge::BridgeStatus;
+use crate::consensus::engine::ZkSacConsensusEngine;
+use crate::consensus::events::{ConsensusEvent, TransactionReceipt};
+use crate::consensus::execution::{self, BlockContext};
+use crate::serialization::{DecodeLimits, open};
+use crate::types::*;
+use anyhow::Result;
+use axum::body::Bytes;
+use axum::extract::State;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response as HttpResponse};
+use axum::routing::post;
+use axum::{Json, Router};
+use parking_lot::Mutex as IndexLock;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::net::TcpListener;
+use tokio::sync::{Mutex, watch};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_util::sync::CancellationToken;
+use tracing::{info, warn};
+
+/// Bloom filter of a receipt without logs
+const EMPTY_BLOOM: [u8; 256] = [0; 256];
+
+/// A receipt with the transaction fields an Ethereum receipt repeats
+#[derive(Debug, Clone)]
+struct IndexedReceipt {
+    receipt: TransactionReceipt,
+    from: Address,
+    to: Address,
+    nonce: u64,
+    gas_price: u64,
+    /// Gas used by this and every earlier transaction in the block
+    cumulative_gas_used: u64,
+}
+
+/// Receipts of applied blocks by transaction hash, kept up to date from the event bus
+#[derive(Debug, Clone, Default)]
+pub struct ReceiptIndex {
+    receipts: Arc<IndexLock<HashMap<BlockHash, IndexedReceipt>>>,
+}
+
+impl ReceiptIndex {
+    /// Index the blocks `events` reports from now on, until the bus closes
+    pub fn follow(&self, mut events: broadcast::Receiver<ConsensusEvent>) {
+        let index = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match events.recv().await {
+                    Ok(event) => index.apply(&event),
+                    Err(RecvError::Lagged(missed)) => warn!("⚠️  Receipt index fell behind, {} events have no receipts", missed),
+                    Err(RecvError::Closed) => return,
+                }
+            }
+        });
+    }
+
+    pub fn apply(&self, event: &ConsensusEvent) {
+        match event {
+            ConsensusEvent::BlockApplied { block, receipts, .. } => {
+                let mut index = self.receipts.lock();
+                let mut cumulative_gas_used = 0;
+                for (receipt, transaction) in receipts.iter().zip(&block.transactions) {
+                    cumulative_gas_used += receipt.gas_used;
+                    index.insert(receipt.transaction_hash, IndexedReceipt {
+                        receipt: receipt.clone(),
+                        from: transaction.from,
+                        to: transaction.to,
+                        nonce: transaction.nonce,
+                        gas_price: transaction.gas_price,
+                        cumulative_gas_used,
+                    });
+                }
+            }
+            // Blocks of the new branch follow as BlockApplied
+            ConsensusEvent::Reorged { fork_height, .. } => {
+                self.receipts.lock().retain(|_, indexed| indexed.receipt.block_number <= *fork_height);
+            }
+            _ => {}
+        }
+    }
+
+    pub fn get(&self, transaction_hash: &BlockHash) -> Option<RpcReceipt> {
+        self.receipts.lock().get(transaction_hash).map(RpcReceipt::from)
+    }
+}
+
+/// Transaction receipt in Ethereum's JSON shape; there are no logs
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcReceipt {
+    pub transaction_hash: String,
+    pub transaction_index: String,
+    pub block_hash: String,
+    pub block_number: String,
+    pub from: String,
+    /// Null for contract deployments
+    pub to: Option<String>,
+    pub contract_address: Option<String>,
+    pub gas_used: String,
+    pub cumulative_gas_used: String,
+    pub effective_gas_price: String,
+    /// `0x1` on success, `0x0` if the contract reverted
+    pub status: String,
+    pub logs: Vec<Value>,
+    pub logs_bloom: String,
+    #[serde(rename = "type")]
+    pub kind: String,
+}
+
+impl From<&IndexedReceipt> for RpcReceipt {
+    fn from(indexed: &IndexedReceipt) -> Self {
+        let receipt = &indexed.receipt;
+        let deploys = indexed.to == CREATE_ADDRESS;
+        Self {
+            transaction_hash: data(&receipt.transaction_hash.0),
+            transaction_index: quantity(receipt.index.into()),
+            block_hash: data(&receipt.block_hash.0),
+            block_number: quantity(receipt.block_number),
+            from: data(&indexed.from.0),
+            to: (!deploys).then(|| data(&indexed.to.0)),
+            contract_address: deploys.then(|| created_contract(&indexed.from, indexed.nonce)).flatten().map(|address| data(&address.0)),
+            gas_used: quantity(receipt.gas_used),
+            cumulative_gas_used: quantity(indexed.cumulative_gas_used),
+            effective_gas_price: quantity(indexed.gas_price),
+            status: quantity(receipt.success.into()),
+            logs: Vec::new(),
+            logs_bloom: data(&EMPTY_BLOOM),
+            kind: quantity(0),
+        }
+    }
+}
+
+#[cfg(feature = "evm")]
+fn created_contract(sender: &Address, nonce: u64) -> Option<Address> {
+    Some(execution::evm::contract_address(sender, nonce))
+}
+
+#[cfg(not(feature = "evm"))]
+fn created_contract(_sender: &Address, _nonce: u64) -> Option<Address> {
+    None
+}
+
+/// Settlement progress in Ethereum's JSON encoding; block numbers are null
+/// until there is a checkpoint
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcCheckpointStatus {
+    pub latest_checkpoint: Option<String>,
+    pub latest_confirmed: Option<String>,
+    pub confirmed_state_root: Option<String>,
+    pub unconfirmed: String,
+    pub pending_withdrawals: String,
+    pub queued_deposits: String,
+}
+
+impl From<&BridgeStatus> for RpcCheckpointStatus {
+    fn from(status: &BridgeStatus) -> Self {
+        Self {
+            latest_checkpoint: status.latest_checkpoint.map(quantity),
+            latest_confirmed: status.latest_confirmed.map(quantity),
+            confirmed_state_root: status.confirmed_state_root.map(|root| data(&root.0)),
+            unconfirmed: quantity(status.unconfirmed as u64),
+            pending_withdrawals: quantity(status.pending_withdrawals as u64),
+            queued_deposits: quantity(status.queued_deposits as u64),
+        }
+    }
+}
+
+/// `eth_call` transaction object; every field is optional
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CallRequest {
+    pub from: Option<String>,
+    /// Absent to simulate a deployment
+    pub to: Option<String>,
+    pub gas: Option<String>,
+    pub gas_price: Option<String>,
+    pub value: Option<String>,
+    pub data: Option<String>,
+    /// Newer name for `data`
+    pub input: Option<String>,
+}
+
+impl CallRequest {
+    /// The unsigned transaction the call runs as on `state`, with the sender's next nonce
+    fn transaction(&self, state: &WorldState, config: &ProtocolConfig) -> Result<Transaction, RpcError> {
+        let from = self.from.as_deref().map(address_param).transpose()?.unwrap_or_else(Address::zero);
+        let quantity_or = |field: &Option<String>, default: u64| {
+            field.as_deref().map_or(Ok(default), |text| parse_quantity(text)
+                .ok_or_else(|| RpcError::invalid_params(format!("Invalid quantity {}", text))))
+        };
+        Ok(Transaction {
+            from,
+            to: self.to.as_deref().map(address_param).transpose()?.unwrap_or(CREATE_ADDRESS),
+            value: quantity_or(&self.value, 0)?,
+            data: self.input.as_deref().or(self.data.as_deref()).map(bytes_param).transpose()?.unwrap_or_default(),
+            gas_limit: quantity_or(&self.gas, config.gas_schedule.block_gas_limit)?,
+            gas_price: quantity_or(&self.gas_price, 0)?,
+            nonce: state.accounts.get(&from).map_or(0, |account| account.nonce),
+            chain_id: config.chain_id,
+            access_list: None,
+            signature: Vec::new(),
+            sig_type: SignatureType::Secp256k1,
+        })
+    }
+}
+
+fn address_param(text: &str) -> Result<Address, RpcError> {
+    Address::from_hex(text).map_err(|e| RpcError::invalid_params(format!("Invalid address {}: {}", text, e)))
+}
+
+fn bytes_param(text: &str) -> Result<Vec<u8>, RpcError> {
+    let digits = text.strip_prefix("0x").unwrap_or(text);
+    hex::decode(digits).map_err(|e| RpcError::invalid_params(format!("Invalid hex data: {}", e)))
+}
+
+/// Whether `raw` is an Ethereum transaction rather than a sealed one: an
+/// RLP list (legacy and EIP-155) or an EIP-2718 typed transaction
+fn is_ethereum_transaction(raw: &[u8]) -> bool {
+    matches!(raw.first(), Some(0x01..=0x04 | 0xc0..=0xff))
+}
+
+fn string_param(value: &Value) -> Result<&str, RpcError> {
+    value.as_str().ok_or_else(|| RpcError::invalid_params(format!("Expected a string, got {}", value)))
+}
+
+fn hash_param(value: &Value) -> Result<BlockHash, RpcError> {
+    let bytes = bytes_param(string_param(value)?)?;
+    Ok(BlockHash(bytes.try_into().map_err(|_| RpcError::invalid_params("Hashes are 32 bytes"))?))
+}
+
+/// Only the state at the head is kept: accept tags and numbers that name it
+fn check_state_block(block: &Value, height: u64) -> Result<(), RpcError> {
+    match block {
+        Value::Null => Ok(()),
+        Value::String(tag) if matches!(tag.as_str(), "latest" | "pending") => Ok(()),
+        Value::String(number) if parse_quantity(number) == Some(height) => Ok(()),
+        other => Err(RpcError::invalid_params(format!("State at block {} is not available, only the latest", other))),
+    }
+}
+
+#[derive(Clone)]
+pub struct EthApi {
+    engine: Arc<Mutex<ZkSacConsensusEngine>>,
+    receipts: ReceiptIndex,
+    limits: DecodeLimits,
+    bridge: Option<watch::Receiver<BridgeStatus>>,
+}
+
+impl EthApi {
+    /// API over `engine`, indexing receipts of the blocks it applies from now on
+    pub async fn new(engine: Arc<Mutex<ZkSacConsensusEngine>>) -> Self {
+        let (events, limits) = {
+            let engine = engine.lock().await;
+            (engine.events.subscribe(), DecodeLimits::from_protocol(&engine.protocol_config))
+        };
+        let receipts = ReceiptIndex::default();
+        receipts.follow(events);
+        Self { engine, receipts, limits, bridge: None }
+    }
+
+    /// Serve `zksac_checkpointStatus` from a [`SettlementBridge`](crate::bridge::SettlementBridge)'s status
+    pub fn with_bridge(mut self, status: watch::Receiver<BridgeStatus>) -> Self {
+        self.bridge = Some(status);
+        self
+    }
+
+    pub fn receipts(&self) -> &ReceiptIndex {
+        &self.receipts
+    }
+
+    pub async fn handle(&self, request: &Request) -> Result<Value, RpcError> {
+        match request.method.as_str() {
+            "eth_chainId" => Ok(json!(quantity(self.engine.lock().await.protocol_config.chain_id))),
+            "eth_blockNumber" => Ok(json!(quantity(self.engine.lock().await.height()))),
+            "eth_getBalance" => {
+                let address = address_param(string_param(request.param(0))?)?;
+                let engine = self.engine.lock().await;
+                check_state_block(request.param(1), engine.height())?;
+                let balance = engine.current_state.accounts.get(&address).map_or(0, |account| account.balance);
+                Ok(json!(quantity(balance)))
+            }
+            "eth_sendRawTransaction" => {
+                let raw = bytes_param(string_param(request.param(0))?)?;
+                if is_ethereum_transaction(&raw) {
+                    return Err(RpcError::invalid_params(
+                        "Ethereum-encoded transactions are not supported, submit a Transaction sealed in its envelope",
+                    ));
+                }
+                let transaction: Transaction = open(&raw, &self.limits)
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid raw transaction: {:#}", e)))?;
+                let hash = transaction.hash();
+                self.engine.lock().await.add_local_transaction(transaction)
+                    .map_err(|e| RpcError::new(RpcError::SERVER_ERROR, e.to_string()))?;
+                Ok(json!(data(&hash.0)))
+            }
+            "eth_getTransactionReceipt" => {
+                let hash = hash_param(request.param(0))?;
+                Ok(self.receipts.get(&hash).map_or(Value::Null, |receipt| json!(receipt)))
+            }
+            "eth_call" => {
+                let call: CallRequest = serde_json::from_value(request.param(0).clone())
+                    .map_err(|e| RpcError::invalid_params(format!("Invalid call object: {}", e)))?;
+                let engine = self.engine.lock().await;
+                check_state_block(request.param(1), engine.height())?;
+                let transaction = call.transaction(&engine.current_state, &engine.protocol_config)?;
+                let context = BlockContext {
+                    number: engine.height() + 1,
+                    timestamp: std::time::SystemTime::now()
+                        .duration_since(std::time::UNIX_EPOCH)
+                        .unwrap()
+                        .as_secs(),
+                    producer: Address::zero(),
+                };
+                let outcome = engine.execution_backend().call(&engine.current_state, &engine.protocol_config.gas_schedule, engine.transaction_hooks(), &context, &transaction)
+                    .map_err(|e| RpcError::new(RpcError::SERVER_ERROR, e.to_string()))?;
+                if !outcome.success {
+                    return Err(RpcError::new(RpcError::EXECUTION_REVERTED, "execution reverted")
+                        .with_data(json!(data(&outcome.output))));
+                }
+                Ok(json!(data(&outcome.output)))
+            }
+            "zksac_checkpointStatus" => match &self.bridge {
+                Some(status) => Ok(json!(RpcCheckpointStatus::from(&*status.borrow()))),
+                None => Err(RpcError::method_not_found(&request.method)),
+            },
+            method => Err(RpcError::method_not_found(method)),
+        }
+    }
+
+    /// Response to one request object; `None` for notifications
+    pub async fn respond(&self, request: Value) -> Option<Response> {
+        let request: Request = match serde_json::from_value(request) {
+            Ok(request) => request,
+            Err(e) => return Some(Response::error(Value::Null, RpcError::new(RpcError::INVALID_REQUEST, e.to_string()))),
+        };
+        let outcome = self.handle(&request).await;
+        if request.id.is_null() {
+            return None;
+        }
+        Some(match outcome {
+            Ok(result) => Response::result(request.id, result),
+            Err(error) => Response::error(request.id, error),
+        })
+    }
+}
+
+pub struct EthRpcServer {
+    listener: TcpListener,
+    api: EthApi,
+}
+
+impl EthRpcServer {
+    pub async fn bind(addr: SocketAddr, api: EthApi) -> Result<Self> {
+        Ok(Self { listener: TcpListener::bind(addr).await?, api })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Serve JSON-RPC over HTTP POST until `stop` is cancelled
+    pub async fn run(self, stop: CancellationToken) -> Result<()> {
+        info!("🔌 JSON-RPC on http://{}", self.local_addr()?);
+        let app = Router::new().route("/", post(serve_http)).with_state(self.api);
+        axum::serve(self.listener, app)
+            .with_graceful_shutdown(stop.cancelled_owned())
+            .await?;
+        Ok(())
+    }
+}
+
+/// A single request or a batch
+async fn serve_http(State(api): State<EthApi>, body: Bytes) -> HttpResponse {
+    let body: Value = match serde_json::from_slice(&body) {
+        Ok(body) => body,
+        Err(e) => return Json(Response::error(Value::Null, RpcError::parse_error(e))).into_response(),
+    };
+    match body {
+        Value::Array(requests) => {
+            let mut responses = Vec::with_capacity(requests.len());
+            for request in requests {
+                responses.extend(api.respond(request).await);
+            }
+            if responses.is_empty() {
+                StatusCode::NO_CONTENT.into_response()
+            } else {
+                Json(responses).into_response()
+            }
+        }
+        request => match api.respond(request).await {
+            Some(response) => Json(response).into_response(),
+            None => StatusCode::NO_CONTENT.into_response(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::engine::ConsensusEngine;
+    use crate::consensus::genesis::GenesisConfig;
+    use crate::crypto::keystore::KeyPair;
+    use crate::serialization::{Codec, seal};
+    use std::time::Duration;
+
+    fn request(method: &str, params: Value) -> Request {
+        Request { jsonrpc: "2.0".to_string(), id: json!(1), method: method.to_string(), params }
+    }
+
+    #[tokio::test]
+    async fn test_send_raw_transaction_until_receipt() {
+        let key = KeyPair::generate(SignatureType::Ed25519);
+        let mut engine = ZkSacConsensusEngine::from_genesis(&GenesisConfig::dev(&key)).unwrap();
+        engine.add_validator_key(key.clone()).unwrap();
+        let engine = Arc::new(Mutex::new(engine));
+        let api = EthApi::new(engine.clone()).await;
+        let sender = data(&key.address().0);
+
+        assert_eq!(api.handle(&request("eth_blockNumber", json!([]))).await.unwrap(), "0x0");
+        assert_eq!(api.handle(&request("eth_getBalance", json!([sender, "latest"]))).await.unwrap(), quantity(1_000_000));
+        assert!(api.handle(&request("eth_getBalance", json!([sender, "0x5"]))).await.is_err());
+        // A plain transfer runs but has no output
+        let call = json!([{ "from": sender, "to": data(&Address::new(2).0), "value": "0x10" }, "latest"]);
+        assert_eq!(api.handle(&request("eth_call", call)).await.unwrap(), "0x");
+
+        let transaction = Transaction::new(key.address(), Address::new(2), 100, 0).signed(&key).unwrap();
+        let raw = data(&seal(&transaction, Codec::Bincode).unwrap());
+        let hash = api.handle(&request("eth_sendRawTransaction", json!([raw]))).await.unwrap();
+        assert_eq!(hash, data(&transaction.hash().0));
+        assert_eq!(api.handle(&request("eth_getTransactionReceipt", json!([hash]))).await.unwrap(), Value::Null);
+        // Legacy RLP and EIP-1559 transactions are named as unsupported, not misparsed
+        for ethereum in ["0xf86c0985", "0x02f8700181"] {
+            let error = api.handle(&request("eth_sendRawTransaction", json!([ethereum]))).await.unwrap_err();
+            assert_eq!(error.code, RpcError::INVALID_PARAMS);
+            assert!(error.message.contains("Ethereum-encoded"), "{}", error.message);
+        }
+
+        {
+            let mut engine = engine.lock().await;
+            let block = engine.produce_block(key.address()).await.unwrap();
+            engine.apply_block(block).await.unwrap();
+        }
+        let receipt = tokio::time::timeout(Duration::from_secs(10), async {
+            loop {
+                let receipt = api.handle(&request("eth_getTransactionReceipt", json!([hash]))).await.unwrap();
+                if !receipt.is_null() {
+                    return serde_json::from_value::<RpcReceipt>(receipt).unwrap();
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }).await.unwrap();
+        assert_eq!(receipt.block_number, "0x1");
+        assert_eq!(receipt.status, "0x1");
+        assert_eq!(receipt.from, sender);
+        assert_eq!(receipt.gas_used, quantity(21_000));
+        assert_eq!(api.handle(&request("eth_blockNumber", json!([]))).await.unwrap(), "0x1");
+
+        let unknown = api.handle(&request("eth_mining", json!([]))).await.unwrap_err();
+        assert_eq!(unknown.code, RpcError::METHOD_NOT_FOUND);
+        let no_bridge = api.handle(&request("zksac_checkpointStatus", json!([]))).await.unwrap_err();
+        assert_eq!(no_bridge.code, RpcError::METHOD_NOT_FOUND);
+
+        let (bridge, status) = watch::channel(BridgeStatus::default());
+        let api = api.with_bridge(status);
+        bridge.send_replace(BridgeStatus { latest_checkpoint: Some(32), unconfirmed: 1, ..BridgeStatus::default() });
+        let status: RpcCheckpointStatus = serde_json::from_value(api.handle(&request("zksac_checkpointStatus", json!([]))).await.unwrap()).unwrap();
+        assert_eq!(status.latest_checkpoint.as_deref(), Some("0x20"));
+        assert_eq!(status.latest_confirmed, None);
+        assert_eq!(status.unconfirmed, "0x1");
+    }
+}
@@ -0,0 +1,164 @@
+//! JSON-RPC 2.0 interface of the node
+//!
+//! Values follow Ethereum's JSON-RPC encoding so existing tooling can read
+//! them: quantities are `0x`-prefixed hex without leading zeros, byte strings
+//! and hashes are `0x`-prefixed hex.
+
+pub mod eth_compat;
+pub mod subscriptions;
+
+pub use eth_compat::{EthApi, EthRpcServer};
+pub use subscriptions::{SubscriptionKind, SubscriptionServer};
+
+use crate::types::BlockHeader;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use thiserror::Error;
+
+pub const JSONRPC_VERSION: &str = "2.0";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Request {
+    pub jsonrpc: String,
+    /// Absent for notifications, which get no response
+    #[serde(default)]
+    pub id: Value,
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+}
+
+impl Request {
+    /// Positional parameter `index`, or null when there are fewer
+    pub fn param(&self, index: usize) -> &Value {
+        self.params.get(index).unwrap_or(&Value::Null)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Response {
+    pub jsonrpc: String,
+    pub id: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcError>,
+}
+
+impl Response {
+    pub fn result(id: Value, result: Value) -> Self {
+        Self { jsonrpc: JSONRPC_VERSION.to_string(), id, result: Some(result), error: None }
+    }
+
+    pub fn error(id: Value, error: RpcError) -> Self {
+        Self { jsonrpc: JSONRPC_VERSION.to_string(), id, result: None, error: Some(error) }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Error, Serialize, Deserialize)]
+#[error("{message} (code {code})")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<Value>,
+}
+
+impl RpcError {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+    /// Ethereum's code for rejected transactions and failed state access
+    pub const SERVER_ERROR: i64 = -32000;
+    /// Ethereum's code for a reverted `eth_call`, with the revert data as `data`
+    pub const EXECUTION_REVERTED: i64 = 3;
+
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self { code, message: message.into(), data: None }
+    }
+
+    pub fn with_data(mut self, data: Value) -> Self {
+        self.data = Some(data);
+        self
+    }
+
+    pub fn parse_error(e: impl std::fmt::Display) -> Self {
+        Self::new(Self::PARSE_ERROR, format!("Parse error: {}", e))
+    }
+
+    pub fn method_not_found(method: &str) -> Self {
+        Self::new(Self::METHOD_NOT_FOUND, format!("Method {} not found", method))
+    }
+
+    pub fn invalid_params(message: impl Into<String>) -> Self {
+        Self::new(Self::INVALID_PARAMS, message)
+    }
+}
+
+/// Server-initiated message carrying `result` for `subscription`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Notification {
+    pub jsonrpc: String,
+    pub method: String,
+    pub params: NotificationParams,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NotificationParams {
+    pub subscription: String,
+    pub result: Value,
+}
+
+/// `0x`-prefixed hex quantity, `0x0` for zero
+pub fn quantity(value: u64) -> String {
+    format!("0x{:x}", value)
+}
+
+/// Parse a hex quantity written by [`quantity`]
+pub fn parse_quantity(text: &str) -> Option<u64> {
+    let digits = text.strip_prefix("0x")?;
+    if digits.is_empty() {
+        return None;
+    }
+    u64::from_str_radix(digits, 16).ok()
+}
+
+/// `0x`-prefixed hex bytes
+pub fn data(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Block header in the shape of an Ethereum `newHeads` result
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RpcHeader {
+    pub hash: String,
+    pub parent_hash: String,
+    pub number: String,
+    pub state_root: String,
+    pub transactions_root: String,
+    pub timestamp: String,
+    pub gas_limit: String,
+    pub gas_used: String,
+    pub miner: String,
+    pub extra_data: String,
+}
+
+impl From<&BlockHeader> for RpcHeader {
+    fn from(header: &BlockHeader) -> Self {
+        Self {
+            hash: data(&header.hash().0),
+            parent_hash: data(&header.previous_hash.0),
+            number: quantity(header.block_number),
+            state_root: data(&header.state_root.0),
+            transactions_root: data(&header.merkle_root.0),
+            timestamp: quantity(header.timestamp),
+            gas_limit: quantity(header.gas_limit),
+            gas_used: quantity(header.gas_used),
+            miner: data(&header.producer.0),
+            extra_data: data(&header.extra_data),
+        }
+    }
+}
@@ -0,0 +1,231 @@
+//! WebSocket pub/sub fed by the consensus event bus
+//!
+//! Clients call `eth_subscribe` with `newHeads`, `pendingTransactions` or
+//! `finalizedBlocks` and receive an `eth_subscription` notification for every
+//! matching [`ConsensusEvent`] until they call `eth_unsubscribe` or
+//! disconnect. Every connection subscribes to the [`EventBus`] on its own, so
+//! a slow client only lags itself; the events it falls behind on are dropped.
+
+use super::{Notification, NotificationParams, Request, Response, RpcError, RpcHeader, JSONRPC_VERSION, data, parse_quantity, quantity};
+use crate::consensus::events::{ConsensusEvent, EventBus};
+use anyhow::Result;
+use futures::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::net::SocketAddr;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, warn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum SubscriptionKind {
+    /// Header of every applied block, including those of a branch reorged to
+    NewHeads,
+    /// Hash of every transaction entering the pool
+    PendingTransactions,
+    /// Number and hash of every newly finalized block
+    FinalizedBlocks,
+}
+
+impl SubscriptionKind {
+    /// Notification result for `event`, if it is one this kind reports
+    pub fn result(&self, event: &ConsensusEvent) -> Option<Value> {
+        match (self, event) {
+            (SubscriptionKind::NewHeads, ConsensusEvent::BlockApplied { block, .. }) => {
+                serde_json::to_value(RpcHeader::from(&block.header)).ok()
+            }
+            (SubscriptionKind::PendingTransactions, ConsensusEvent::TransactionPooled { hash }) => {
+                Some(Value::String(data(&hash.0)))
+            }
+            (SubscriptionKind::FinalizedBlocks, ConsensusEvent::Finalized { block_number, block_hash }) => {
+                Some(json!({ "number": quantity(*block_number), "hash": data(&block_hash.0) }))
+            }
+            _ => None,
+        }
+    }
+}
+
+/// Subscriptions of one connection, by id
+#[derive(Debug, Default)]
+struct Subscriptions {
+    active: BTreeMap<u64, SubscriptionKind>,
+    next_id: u64,
+}
+
+impl Subscriptions {
+    fn handle(&mut self, request: &Request) -> Result<Value, RpcError> {
+        match request.method.as_str() {
+            "eth_subscribe" => {
+                let kind: SubscriptionKind = serde_json::from_value(request.param(0).clone())
+                    .map_err(|_| RpcError::invalid_params("Expected newHeads, pendingTransactions or finalizedBlocks"))?;
+                self.next_id += 1;
+                self.active.insert(self.next_id, kind);
+                Ok(Value::String(quantity(self.next_id)))
+            }
+            "eth_unsubscribe" => {
+                let id = request.param(0).as_str()
+                    .and_then(parse_quantity)
+                    .ok_or_else(|| RpcError::invalid_params("Expected a subscription id"))?;
+                Ok(Value::Bool(self.active.remove(&id).is_some()))
+            }
+            method => Err(RpcError::method_not_found(method)),
+        }
+    }
+
+    /// Notifications `event` causes
+    fn notifications(&self, event: &ConsensusEvent) -> Vec<Notification> {
+        self.active.iter()
+            .filter_map(|(id, kind)| {
+                let result = kind.result(event)?;
+                Some(Notification {
+                    jsonrpc: JSONRPC_VERSION.to_string(),
+                    method: "eth_subscription".to_string(),
+                    params: NotificationParams { subscription: quantity(*id), result },
+                })
+            })
+            .collect()
+    }
+}
+
+/// Response to one text frame; `None` for notifications (requests without an id)
+fn respond(text: &str, subscriptions: &mut Subscriptions) -> Option<Response> {
+    let request: Request = match serde_json::from_str(text) {
+        Ok(request) => request,
+        Err(e) => return Some(Response::error(Value::Null, RpcError::parse_error(e))),
+    };
+    let outcome = subscriptions.handle(&request);
+    if request.id.is_null() {
+        return None;
+    }
+    Some(match outcome {
+        Ok(result) => Response::result(request.id, result),
+        Err(error) => Response::error(request.id, error),
+    })
+}
+
+pub struct SubscriptionServer {
+    listener: TcpListener,
+    events: EventBus,
+}
+
+impl SubscriptionServer {
+    pub async fn bind(addr: SocketAddr, events: EventBus) -> Result<Self> {
+        Ok(Self { listener: TcpListener::bind(addr).await?, events })
+    }
+
+    pub fn local_addr(&self) -> Result<SocketAddr> {
+        Ok(self.listener.local_addr()?)
+    }
+
+    /// Accept connections until `stop` is cancelled, which also closes the open ones
+    pub async fn run(self, stop: CancellationToken) -> Result<()> {
+        info!("📡 WebSocket subscriptions on {}", self.local_addr()?);
+        loop {
+            let (stream, peer) = tokio::select! {
+                _ = stop.cancelled() => return Ok(()),
+                accepted = self.listener.accept() => accepted?,
+            };
+            let events = self.events.subscribe();
+            let stop = stop.clone();
+            tokio::spawn(async move {
+                if let Err(e) = serve_connection(stream, events, stop).await {
+                    debug!("🔌 Subscription connection from {} ended: {:#}", peer, e);
+                }
+            });
+        }
+    }
+}
+
+async fn serve_connection(stream: TcpStream, mut events: broadcast::Receiver<ConsensusEvent>, stop: CancellationToken) -> Result<()> {
+    let mut socket = tokio_tungstenite::accept_async(stream).await?;
+    let mut subscriptions = Subscriptions::default();
+    loop {
+        tokio::select! {
+            _ = stop.cancelled() => {
+                socket.close(None).await?;
+                return Ok(());
+            }
+            message = socket.next() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    if let Some(response) = respond(&text, &mut subscriptions) {
+                        socket.send(Message::Text(serde_json::to_string(&response)?)).await?;
+                    }
+                }
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+            },
+            event = events.recv() => match event {
+                Ok(event) => {
+                    for notification in subscriptions.notifications(&event) {
+                        socket.send(Message::Text(serde_json::to_string(&notification)?)).await?;
+                    }
+                }
+                Err(RecvError::Lagged(missed)) => warn!("⚠️  Subscription client fell behind, dropped {} events", missed),
+                Err(RecvError::Closed) => return Ok(()),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::engine::{ConsensusEngine, ZkSacConsensusEngine};
+    use crate::consensus::genesis::GenesisConfig;
+    use crate::crypto::keystore::KeyPair;
+    use crate::types::SignatureType;
+    use tokio_tungstenite::connect_async;
+
+    async fn next_text<S>(socket: &mut S) -> Value
+    where
+        S: StreamExt<Item = Result<Message, tokio_tungstenite::tungstenite::Error>> + Unpin,
+    {
+        loop {
+            let message = tokio::time::timeout(std::time::Duration::from_secs(10), socket.next())
+                .await
+                .expect("no message in time")
+                .expect("connection closed")
+                .unwrap();
+            if let Message::Text(text) = message {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_new_heads_are_pushed_after_apply_block() {
+        let key = KeyPair::generate(SignatureType::Ed25519);
+        let mut engine = ZkSacConsensusEngine::from_genesis(&GenesisConfig::dev(&key)).unwrap();
+        engine.add_validator_key(key.clone()).unwrap();
+        let server = SubscriptionServer::bind("127.0.0.1:0".parse().unwrap(), engine.events.clone()).await.unwrap();
+        let addr = server.local_addr().unwrap();
+        let stop = CancellationToken::new();
+        let task = tokio::spawn(server.run(stop.clone()));
+
+        let (mut socket, _) = connect_async(format!("ws://{}", addr)).await.unwrap();
+        let subscribe = json!({ "jsonrpc": "2.0", "id": 1, "method": "eth_subscribe", "params": ["newHeads"] });
+        socket.send(Message::Text(subscribe.to_string())).await.unwrap();
+        let response = next_text(&mut socket).await;
+        assert_eq!(response["result"], "0x1");
+
+        let unknown = json!({ "jsonrpc": "2.0", "id": 2, "method": "eth_subscribe", "params": ["logs"] });
+        socket.send(Message::Text(unknown.to_string())).await.unwrap();
+        assert_eq!(next_text(&mut socket).await["error"]["code"], RpcError::INVALID_PARAMS);
+
+        let block = engine.produce_block(key.address()).await.unwrap();
+        engine.apply_block(block.clone()).await.unwrap();
+        let notification = next_text(&mut socket).await;
+        assert_eq!(notification["method"], "eth_subscription");
+        assert_eq!(notification["params"]["subscription"], "0x1");
+        let head: RpcHeader = serde_json::from_value(notification["params"]["result"].clone()).unwrap();
+        assert_eq!(head, RpcHeader::from(&block.header));
+
+        stop.cancel();
+        task.await.unwrap().unwrap();
+    }
+}
@@ -0,0 +1,320 @@
+//! Canonical encoding for data that is hashed or signed
+//!
+//! `bincode::serialize` writes `HashMap` entries in iteration order, which
+//! differs between processes, so two nodes with the same state could compute
+//! different state roots. Everything that feeds a hash or a signature goes
+//! through [`CanonicalEncode`] instead:
+//!
+//! * integers are fixed-width little-endian
+//! * sequences are prefixed with their length as a `u64`
+//! * maps are prefixed with their length and written in ascending order of
+//!   their encoded keys
+//! * enums are a one-byte tag
+//!
+//! Covered today: transaction hashes and signing hashes, block header hashes
+//! and the world state root. The same layout is the wire format read by
+//! [`super::zero_copy`].
+
+use crate::crypto::hash::keccak256_hash;
+use crate::types::*;
+use std::collections::{BTreeMap, HashMap};
+
+pub trait CanonicalEncode {
+    fn encode_canonical(&self, out: &mut Vec<u8>);
+}
+
+/// Canonical bytes of `value`
+pub fn canonical_bytes<T: CanonicalEncode + ?Sized>(value: &T) -> Vec<u8> {
+    let mut out = Vec::new();
+    value.encode_canonical(&mut out);
+    out
+}
+
+/// Keccak256 of the canonical bytes of `value`
+pub fn canonical_hash<T: CanonicalEncode + ?Sized>(value: &T) -> BlockHash {
+    BlockHash(keccak256_hash(&canonical_bytes(value)))
+}
+
+impl CanonicalEncode for u8 {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        out.push(*self);
+    }
+}
+
+impl CanonicalEncode for bool {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        out.push(u8::from(*self));
+    }
+}
+
+impl CanonicalEncode for u32 {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl CanonicalEncode for u64 {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.to_le_bytes());
+    }
+}
+
+impl<T: CanonicalEncode, const N: usize> CanonicalEncode for [T; N] {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        for item in self {
+            item.encode_canonical(out);
+        }
+    }
+}
+
+impl<T: CanonicalEncode> CanonicalEncode for [T] {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        (self.len() as u64).encode_canonical(out);
+        for item in self {
+            item.encode_canonical(out);
+        }
+    }
+}
+
+impl<T: CanonicalEncode> CanonicalEncode for Vec<T> {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.as_slice().encode_canonical(out);
+    }
+}
+
+impl<T: CanonicalEncode> CanonicalEncode for Option<T> {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        match self {
+            None => out.push(0),
+            Some(value) => {
+                out.push(1);
+                value.encode_canonical(out);
+            }
+        }
+    }
+}
+
+/// Write map entries sorted by their encoded key
+fn encode_entries<'a, K, V>(len: usize, entries: impl Iterator<Item = (&'a K, &'a V)>, out: &mut Vec<u8>)
+where
+    K: CanonicalEncode + 'a,
+    V: CanonicalEncode + 'a,
+{
+    let mut encoded: Vec<(Vec<u8>, &V)> = entries
+        .map(|(key, value)| (canonical_bytes(key), value))
+        .collect();
+    encoded.sort_by(|a, b| a.0.cmp(&b.0));
+
+    (len as u64).encode_canonical(out);
+    for (key, value) in encoded {
+        out.extend_from_slice(&key);
+        value.encode_canonical(out);
+    }
+}
+
+impl<K: CanonicalEncode, V: CanonicalEncode, S> CanonicalEncode for HashMap<K, V, S> {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        encode_entries(self.len(), self.iter(), out);
+    }
+}
+
+impl<K: CanonicalEncode, V: CanonicalEncode> CanonicalEncode for BTreeMap<K, V> {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        // `Ord` on the key need not match the order of its encoding
+        encode_entries(self.len(), self.iter(), out);
+    }
+}
+
+impl CanonicalEncode for Address {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.0);
+    }
+}
+
+impl CanonicalEncode for BlockHash {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        out.extend_from_slice(&self.0);
+    }
+}
+
+impl CanonicalEncode for Account {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.balance.encode_canonical(out);
+        self.nonce.encode_canonical(out);
+        self.code.encode_canonical(out);
+        self.storage.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for WorldState {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.accounts.encode_canonical(out);
+        self.global_nonce.encode_canonical(out);
+        self.state_root.encode_canonical(out);
+        self.block_number.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for SignatureType {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        let tag: u8 = match self {
+            SignatureType::Ed25519 => 0,
+            SignatureType::Secp256k1 => 1,
+            SignatureType::PostQuantum => 2,
+        };
+        tag.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for ProofType {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        let tag: u8 = match self {
+            ProofType::SP1 => 0,
+            ProofType::Risc0 => 1,
+            ProofType::Plonky3 => 2,
+            ProofType::Deferred => 3,
+        };
+        tag.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for AccessListItem {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.address.encode_canonical(out);
+        self.storage_keys.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for Transaction {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.from.encode_canonical(out);
+        self.to.encode_canonical(out);
+        self.value.encode_canonical(out);
+        self.data.encode_canonical(out);
+        self.gas_limit.encode_canonical(out);
+        self.gas_price.encode_canonical(out);
+        self.nonce.encode_canonical(out);
+        self.chain_id.encode_canonical(out);
+        self.access_list.encode_canonical(out);
+        self.signature.encode_canonical(out);
+        self.sig_type.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for BlockHeader {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.previous_hash.encode_canonical(out);
+        self.merkle_root.encode_canonical(out);
+        self.prev_state_root.encode_canonical(out);
+        self.state_root.encode_canonical(out);
+        self.timestamp.encode_canonical(out);
+        self.block_number.encode_canonical(out);
+        self.gas_limit.encode_canonical(out);
+        self.gas_used.encode_canonical(out);
+        self.producer.encode_canonical(out);
+        self.extra_data.encode_canonical(out);
+        self.randomness.output.encode_canonical(out);
+        self.randomness.proof.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for ZkProof {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.proof_data.encode_canonical(out);
+        self.public_inputs.encode_canonical(out);
+        self.verification_key.encode_canonical(out);
+        self.proof_type.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for ValidatorSignature {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.validator_address.encode_canonical(out);
+        self.stake_weight.encode_canonical(out);
+        self.signature.encode_canonical(out);
+        self.sig_type.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for AggregateSignature {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.signers.encode_canonical(out);
+        self.signature.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for ProtocolRule {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.rule_id.encode_canonical(out);
+        self.rule_data.encode_canonical(out);
+        self.validity_proof.encode_canonical(out);
+        self.activation_epoch.encode_canonical(out);
+    }
+}
+
+impl CanonicalEncode for Block {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.header.encode_canonical(out);
+        self.transactions.encode_canonical(out);
+        self.validator_signatures.encode_canonical(out);
+        self.recursive_proof.encode_canonical(out);
+        self.protocol_updates.encode_canonical(out);
+        self.proof_signature.encode_canonical(out);
+        self.aggregate_signature.encode_canonical(out);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn state_with(order: &[u8]) -> WorldState {
+        let mut state = WorldState::default();
+        for id in order {
+            let mut account = Account::new(u64::from(*id) * 100);
+            for slot in 0..4u8 {
+                account.storage.insert([slot.wrapping_mul(*id); 32], [*id; 32]);
+            }
+            state.accounts.insert(Address::new(*id), account);
+        }
+        state
+    }
+
+    #[test]
+    fn test_state_encoding_ignores_insertion_order() {
+        let ids: Vec<u8> = (1..=50).collect();
+        let reversed: Vec<u8> = ids.iter().rev().copied().collect();
+
+        let a = state_with(&ids);
+        let b = state_with(&reversed);
+        assert_eq!(canonical_bytes(&a), canonical_bytes(&b));
+        assert_eq!(a.compute_state_root(), b.compute_state_root());
+
+        // Fresh maps get fresh hasher seeds; the encoding must not depend on them
+        for _ in 0..10 {
+            assert_eq!(canonical_bytes(&state_with(&ids)), canonical_bytes(&a));
+        }
+    }
+
+    #[test]
+    fn test_transaction_layout_is_fixed() {
+        let tx = Transaction::new(Address::new(1), Address::new(2), 1000, 7);
+
+        let mut expected = Vec::new();
+        expected.extend_from_slice(&Address::new(1).0);
+        expected.extend_from_slice(&Address::new(2).0);
+        expected.extend_from_slice(&1000u64.to_le_bytes());
+        expected.extend_from_slice(&0u64.to_le_bytes());
+        expected.extend_from_slice(&21000u64.to_le_bytes());
+        expected.extend_from_slice(&1u64.to_le_bytes());
+        expected.extend_from_slice(&7u64.to_le_bytes());
+        expected.extend_from_slice(&DEFAULT_CHAIN_ID.to_le_bytes());
+        expected.push(0);
+        expected.extend_from_slice(&64u64.to_le_bytes());
+        expected.extend_from_slice(&[0u8; 64]);
+        expected.push(0);
+
+        assert_eq!(canonical_bytes(&tx), expected);
+        assert_eq!(tx.hash(), BlockHash(keccak256_hash(&expected)));
+    }
+}
@@ -0,0 +1,238 @@
+//! zstd compression with per-payload-kind policies
+//!
+//! Every compressed payload starts with a one-byte codec id (raw, zstd, or
+//! zstd with the policy's dictionary), so a reader never has to guess and
+//! payloads that are too small to benefit, or that do not shrink, are stored
+//! raw. Decompression is capped at the [`DecodeLimits`] size for the kind,
+//! which stops decompression bombs from peers.
+
+use super::framing::{DecodeError, DecodeLimits, PayloadKind, decode_bounded};
+use anyhow::Result;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+const CODEC_RAW: u8 = 0;
+const CODEC_ZSTD: u8 = 1;
+const CODEC_ZSTD_DICT: u8 = 2;
+
+#[derive(Debug, Clone)]
+pub struct CompressionPolicy {
+    /// zstd level; `None` stores payloads raw
+    pub level: Option<i32>,
+    /// Payloads smaller than this are stored raw
+    pub min_size: usize,
+    /// Shared dictionary for small, similar payloads such as transactions
+    pub dictionary: Option<Arc<[u8]>>,
+}
+
+impl CompressionPolicy {
+    pub fn zstd(level: i32) -> Self {
+        Self { level: Some(level), min_size: 0, dictionary: None }
+    }
+
+    pub fn none() -> Self {
+        Self { level: None, min_size: 0, dictionary: None }
+    }
+
+    pub fn with_min_size(mut self, min_size: usize) -> Self {
+        self.min_size = min_size;
+        self
+    }
+
+    pub fn with_dictionary(mut self, dictionary: Vec<u8>) -> Self {
+        self.dictionary = Some(Arc::from(dictionary));
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CompressionPolicies {
+    pub block: CompressionPolicy,
+    pub transaction: CompressionPolicy,
+    pub proof: CompressionPolicy,
+    pub message: CompressionPolicy,
+}
+
+impl Default for CompressionPolicies {
+    fn default() -> Self {
+        Self {
+            block: CompressionPolicy::zstd(3).with_min_size(256),
+            // Single transactions rarely compress well without a dictionary
+            transaction: CompressionPolicy::zstd(1).with_min_size(1024),
+            // Proofs are mostly high-entropy; only large ones are worth the CPU
+            proof: CompressionPolicy::zstd(3).with_min_size(4096),
+            message: CompressionPolicy::zstd(3).with_min_size(256),
+        }
+    }
+}
+
+impl CompressionPolicies {
+    /// Store everything raw, still with a codec byte
+    pub fn disabled() -> Self {
+        Self {
+            block: CompressionPolicy::none(),
+            transaction: CompressionPolicy::none(),
+            proof: CompressionPolicy::none(),
+            message: CompressionPolicy::none(),
+        }
+    }
+
+    pub fn for_kind(&self, kind: PayloadKind) -> &CompressionPolicy {
+        match kind {
+            PayloadKind::Block => &self.block,
+            PayloadKind::Transaction => &self.transaction,
+            PayloadKind::Proof => &self.proof,
+            PayloadKind::Message => &self.message,
+        }
+    }
+
+    pub fn with_policy(mut self, kind: PayloadKind, policy: CompressionPolicy) -> Self {
+        match kind {
+            PayloadKind::Block => self.block = policy,
+            PayloadKind::Transaction => self.transaction = policy,
+            PayloadKind::Proof => self.proof = policy,
+            PayloadKind::Message => self.message = policy,
+        }
+        self
+    }
+}
+
+/// Compress `payload` according to the policy for `kind`
+pub fn compress(kind: PayloadKind, payload: &[u8], policies: &CompressionPolicies) -> Result<Vec<u8>> {
+    let policy = policies.for_kind(kind);
+    let compressed = match policy.level {
+        Some(level) if payload.len() >= policy.min_size => {
+            let (codec, bytes) = match &policy.dictionary {
+                Some(dictionary) => (CODEC_ZSTD_DICT, zstd::bulk::Compressor::with_dictionary(level, dictionary)?.compress(payload)?),
+                None => (CODEC_ZSTD, zstd::bulk::compress(payload, level)?),
+            };
+            // Keep the raw form when compression does not pay off
+            (bytes.len() < payload.len()).then_some((codec, bytes))
+        }
+        _ => None,
+    };
+
+    let (codec, body) = match &compressed {
+        Some((codec, bytes)) => (*codec, bytes.as_slice()),
+        None => (CODEC_RAW, payload),
+    };
+    let mut out = Vec::with_capacity(body.len() + 1);
+    out.push(codec);
+    out.extend_from_slice(body);
+    Ok(out)
+}
+
+/// Output buffer size for a zstd frame: its declared content size when the
+/// header has one, so a tiny message does not reserve the whole kind limit
+fn output_capacity(kind: PayloadKind, body: &[u8], limits: &DecodeLimits) -> Result<usize, DecodeError> {
+    let limit = limits.limit_for(kind);
+    match zstd::zstd_safe::get_frame_content_size(body) {
+        Ok(Some(size)) if size > limit as u64 => Err(DecodeError::Oversize {
+            kind,
+            size: usize::try_from(size).unwrap_or(usize::MAX),
+            limit,
+        }),
+        Ok(Some(size)) => Ok(size as usize),
+        _ => Ok(limit),
+    }
+}
+
+/// Undo [`compress`], refusing output larger than the limit for `kind`
+pub fn decompress(kind: PayloadKind, data: &[u8], policies: &CompressionPolicies, limits: &DecodeLimits) -> Result<Vec<u8>> {
+    let (&codec, body) = data.split_first()
+        .ok_or(DecodeError::Truncated { needed: 1, available: 0 })?;
+    let malformed = |e: std::io::Error| DecodeError::Malformed { kind, reason: e.to_string() };
+
+    let payload = match codec {
+        CODEC_RAW => body.to_vec(),
+        CODEC_ZSTD => zstd::bulk::decompress(body, output_capacity(kind, body, limits)?).map_err(malformed)?,
+        CODEC_ZSTD_DICT => {
+            let dictionary = policies.for_kind(kind).dictionary.as_ref().ok_or_else(|| DecodeError::Malformed {
+                kind,
+                reason: "payload needs a compression dictionary".to_string(),
+            })?;
+            let capacity = output_capacity(kind, body, limits)?;
+            zstd::bulk::Decompressor::with_dictionary(dictionary)
+                .and_then(|mut decompressor| decompressor.decompress(body, capacity))
+                .map_err(malformed)?
+        }
+        other => return Err(DecodeError::Malformed { kind, reason: format!("unknown codec {}", other) }.into()),
+    };
+    limits.check(kind, payload.len())?;
+    Ok(payload)
+}
+
+/// Bincode-encode and compress `value`
+pub fn encode_compressed<T: Serialize>(kind: PayloadKind, value: &T, policies: &CompressionPolicies) -> Result<Vec<u8>> {
+    compress(kind, &bincode::serialize(value)?, policies)
+}
+
+/// Decompress and bincode-decode a value written by [`encode_compressed`]
+pub fn decode_compressed<T: DeserializeOwned>(
+    kind: PayloadKind,
+    bytes: &[u8],
+    policies: &CompressionPolicies,
+    limits: &DecodeLimits,
+) -> Result<T> {
+    let payload = decompress(kind, bytes, policies, limits)?;
+    decode_bounded(kind, &payload, limits)
+}
+
+/// Uncompressed size over compressed size
+pub fn compression_ratio(raw_len: usize, compressed_len: usize) -> f64 {
+    if compressed_len == 0 {
+        return 1.0;
+    }
+    raw_len as f64 / compressed_len as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_skips_small_and_compresses_large() {
+        let policies = CompressionPolicies::default();
+        let limits = DecodeLimits::default();
+
+        let small = vec![7u8; 100];
+        let packed = compress(PayloadKind::Message, &small, &policies).unwrap();
+        assert_eq!(packed[0], CODEC_RAW);
+
+        let large = vec![7u8; 64 * 1024];
+        let packed = compress(PayloadKind::Message, &large, &policies).unwrap();
+        assert_eq!(packed[0], CODEC_ZSTD);
+        assert!(packed.len() < large.len() / 10);
+        assert_eq!(decompress(PayloadKind::Message, &packed, &policies, &limits).unwrap(), large);
+    }
+
+    #[test]
+    fn test_decompression_is_capped_by_limits() {
+        let policies = CompressionPolicies::default();
+        let bomb = compress(PayloadKind::Message, &vec![0u8; 1024 * 1024], &policies).unwrap();
+
+        let tight = DecodeLimits { max_message_bytes: 4096, ..DecodeLimits::default() };
+        let err = decompress(PayloadKind::Message, &bomb, &policies, &tight).unwrap_err();
+        assert!(matches!(err.downcast_ref::<DecodeError>(),
+                         Some(DecodeError::Oversize { size: 1_048_576, limit: 4096, .. })));
+    }
+
+    #[test]
+    fn test_dictionary_roundtrip() {
+        let dictionary = b"transfer from to value nonce gas".repeat(8);
+        let policies = CompressionPolicies::default().with_policy(
+            PayloadKind::Transaction,
+            CompressionPolicy::zstd(3).with_dictionary(dictionary),
+        );
+        let payload = b"transfer from to value nonce gas transfer from to".to_vec();
+        let packed = compress(PayloadKind::Transaction, &payload, &policies).unwrap();
+        let unpacked = decompress(PayloadKind::Transaction, &packed, &policies, &DecodeLimits::default()).unwrap();
+        assert_eq!(unpacked, payload);
+
+        if packed[0] == CODEC_ZSTD_DICT {
+            let without = CompressionPolicies::default();
+            assert!(decompress(PayloadKind::Transaction, &packed, &without, &DecodeLimits::default()).is_err());
+        }
+    }
+}
@@ -0,0 +1,658 @@
+//! Schema-versioned envelopes for stored and networked payloads
+//!
+//! A sealed payload starts with a 7-byte header: a magic byte, the envelope
+//! format version, a [`TypeTag`], the schema version of the type, a
+//! [`Codec`] id and a reserved flags byte. Decoders read the header first,
+//! so a node can keep reading data written by an older release (or sent by a
+//! peer that has not upgraded)
+//! through [`Versioned::decode_legacy`], and can reject unknown types or
+//! future versions with a clear error instead of misparsing bytes.
+
+use super::compression::{CompressionPolicies, decode_compressed, encode_compressed};
+use super::framing::{DecodeLimits, PayloadKind, check_json_depth, decode_bounded};
+use crate::types::*;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde::de::DeserializeOwned;
+
+const MAGIC: u8 = 0x5a;
+const ENVELOPE_FORMAT: u8 = 1;
+pub const ENVELOPE_HEADER_LEN: usize = 7;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TypeTag {
+    Block,
+    Transaction,
+    ZkProof,
+    WorldState,
+    ValidatorSet,
+}
+
+impl TypeTag {
+    fn id(self) -> u8 {
+        match self {
+            TypeTag::Block => 1,
+            TypeTag::Transaction => 2,
+            TypeTag::ZkProof => 3,
+            TypeTag::WorldState => 4,
+            TypeTag::ValidatorSet => 5,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            1 => Some(TypeTag::Block),
+            2 => Some(TypeTag::Transaction),
+            3 => Some(TypeTag::ZkProof),
+            4 => Some(TypeTag::WorldState),
+            5 => Some(TypeTag::ValidatorSet),
+            _ => None,
+        }
+    }
+
+    /// Which size limit applies to payloads of this type
+    pub fn payload_kind(self) -> PayloadKind {
+        match self {
+            TypeTag::Block => PayloadKind::Block,
+            TypeTag::Transaction => PayloadKind::Transaction,
+            TypeTag::ZkProof => PayloadKind::Proof,
+            TypeTag::WorldState | TypeTag::ValidatorSet => PayloadKind::Message,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Codec {
+    Bincode,
+    /// Bincode compressed under the default [`CompressionPolicies`]
+    BincodeZstd,
+    Json,
+}
+
+impl Codec {
+    fn id(self) -> u8 {
+        match self {
+            Codec::Bincode => 0,
+            Codec::BincodeZstd => 1,
+            Codec::Json => 2,
+        }
+    }
+
+    fn from_id(id: u8) -> Option<Self> {
+        match id {
+            0 => Some(Codec::Bincode),
+            1 => Some(Codec::BincodeZstd),
+            2 => Some(Codec::Json),
+            _ => None,
+        }
+    }
+
+    pub fn encode<T: Serialize>(self, kind: PayloadKind, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Codec::Bincode => Ok(bincode::serialize(value)?),
+            Codec::BincodeZstd => encode_compressed(kind, value, &CompressionPolicies::default()),
+            Codec::Json => Ok(serde_json::to_vec(value)?),
+        }
+    }
+
+    /// Decode a payload; also used by [`Versioned::decode_legacy`] for old layouts
+    pub fn decode<T: DeserializeOwned>(self, kind: PayloadKind, payload: &[u8], limits: &DecodeLimits) -> Result<T> {
+        match self {
+            Codec::Bincode => decode_bounded(kind, payload, limits),
+            Codec::BincodeZstd => decode_compressed(kind, payload, &CompressionPolicies::default(), limits),
+            Codec::Json => {
+                limits.check(kind, payload.len())?;
+                check_json_depth(payload, limits)?;
+                Ok(serde_json::from_slice(payload)?)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum EnvelopeError {
+    #[error("not an envelope (missing magic byte)")]
+    BadMagic,
+    #[error("unsupported envelope format {0}")]
+    UnsupportedFormat(u8),
+    #[error("unknown type tag {0}")]
+    UnknownType(u8),
+    #[error("unknown codec {0}")]
+    UnknownCodec(u8),
+    #[error("expected a {expected:?} envelope, found {found:?}")]
+    WrongType { expected: TypeTag, found: TypeTag },
+    #[error("{tag:?} schema version {version} is not supported (current is {current})")]
+    UnsupportedVersion { tag: TypeTag, version: u16, current: u16 },
+}
+
+/// A type that can be sealed in an envelope
+pub trait Versioned: Serialize + DeserializeOwned {
+    const TYPE_TAG: TypeTag;
+    /// Bump whenever the serialized layout changes
+    const SCHEMA_VERSION: u16;
+
+    /// Decode a payload written under an older schema. Types override this
+    /// when they bump `SCHEMA_VERSION`, typically by decoding the old struct
+    /// with `codec.decode` and converting it.
+    fn decode_legacy(version: u16, codec: Codec, payload: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        let _ = (codec, payload, limits);
+        Err(EnvelopeError::UnsupportedVersion {
+            tag: Self::TYPE_TAG,
+            version,
+            current: Self::SCHEMA_VERSION,
+        }.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EnvelopeHeader {
+    pub type_tag: TypeTag,
+    pub schema_version: u16,
+    pub codec: Codec,
+}
+
+/// Read just the header, e.g. to route a message by type
+pub fn peek(bytes: &[u8]) -> Result<EnvelopeHeader, EnvelopeError> {
+    if bytes.len() < ENVELOPE_HEADER_LEN || bytes[0] != MAGIC {
+        return Err(EnvelopeError::BadMagic);
+    }
+    if bytes[1] != ENVELOPE_FORMAT {
+        return Err(EnvelopeError::UnsupportedFormat(bytes[1]));
+    }
+    Ok(EnvelopeHeader {
+        type_tag: TypeTag::from_id(bytes[2]).ok_or(EnvelopeError::UnknownType(bytes[2]))?,
+        schema_version: u16::from_le_bytes([bytes[3], bytes[4]]),
+        codec: Codec::from_id(bytes[5]).ok_or(EnvelopeError::UnknownCodec(bytes[5]))?,
+    })
+}
+
+/// Serialize `value` with `codec` and wrap it in an envelope
+pub fn seal<T: Versioned>(value: &T, codec: Codec) -> Result<Vec<u8>> {
+    let payload = codec.encode(T::TYPE_TAG.payload_kind(), value)?;
+    let mut out = Vec::with_capacity(ENVELOPE_HEADER_LEN + payload.len());
+    out.push(MAGIC);
+    out.push(ENVELOPE_FORMAT);
+    out.push(T::TYPE_TAG.id());
+    out.extend_from_slice(&T::SCHEMA_VERSION.to_le_bytes());
+    out.push(codec.id());
+    // Reserved for flags
+    out.push(0);
+    out.extend_from_slice(&payload);
+    Ok(out)
+}
+
+/// Decode an envelope holding a `T`, dispatching on its schema version and codec
+pub fn open<T: Versioned>(bytes: &[u8], limits: &DecodeLimits) -> Result<T> {
+    let header = peek(bytes)?;
+    if header.type_tag != T::TYPE_TAG {
+        return Err(EnvelopeError::WrongType { expected: T::TYPE_TAG, found: header.type_tag }.into());
+    }
+    let payload = &bytes[ENVELOPE_HEADER_LEN..];
+    let kind = T::TYPE_TAG.payload_kind();
+
+    if header.schema_version == T::SCHEMA_VERSION {
+        header.codec.decode(kind, payload, limits)
+    } else if header.schema_version < T::SCHEMA_VERSION {
+        T::decode_legacy(header.schema_version, header.codec, payload, limits)
+    } else {
+        Err(EnvelopeError::UnsupportedVersion {
+            tag: T::TYPE_TAG,
+            version: header.schema_version,
+            current: T::SCHEMA_VERSION,
+        }.into())
+    }
+}
+
+/// Transaction layout before `chain_id`. Its signature does not cover a
+/// chain id, so an upgraded transaction decodes but no longer verifies.
+#[derive(Deserialize)]
+struct TransactionV1 {
+    from: Address,
+    to: Address,
+    value: u64,
+    data: Vec<u8>,
+    gas_limit: u64,
+    gas_price: u64,
+    nonce: u64,
+    signature: Vec<u8>,
+    sig_type: SignatureType,
+}
+
+impl From<TransactionV1> for TransactionV2 {
+    fn from(old: TransactionV1) -> Self {
+        TransactionV2 {
+            from: old.from,
+            to: old.to,
+            value: old.value,
+            data: old.data,
+            gas_limit: old.gas_limit,
+            gas_price: old.gas_price,
+            nonce: old.nonce,
+            chain_id: DEFAULT_CHAIN_ID,
+            signature: old.signature,
+            sig_type: old.sig_type,
+        }
+    }
+}
+
+/// Transaction layout before `access_list`; upgraded transactions declare
+/// nothing, which their signatures still cover
+#[derive(Deserialize)]
+struct TransactionV2 {
+    from: Address,
+    to: Address,
+    value: u64,
+    data: Vec<u8>,
+    gas_limit: u64,
+    gas_price: u64,
+    nonce: u64,
+    chain_id: u64,
+    signature: Vec<u8>,
+    sig_type: SignatureType,
+}
+
+impl From<TransactionV2> for Transaction {
+    fn from(old: TransactionV2) -> Self {
+        Transaction {
+            from: old.from,
+            to: old.to,
+            value: old.value,
+            data: old.data,
+            gas_limit: old.gas_limit,
+            gas_price: old.gas_price,
+            nonce: old.nonce,
+            chain_id: old.chain_id,
+            access_list: None,
+            signature: old.signature,
+            sig_type: old.sig_type,
+        }
+    }
+}
+
+/// Header layout before the randomness beacon
+#[derive(Deserialize)]
+struct BlockHeaderV1 {
+    previous_hash: BlockHash,
+    merkle_root: BlockHash,
+    state_root: BlockHash,
+    timestamp: u64,
+    block_number: u64,
+    gas_limit: u64,
+    gas_used: u64,
+    producer: Address,
+    extra_data: Vec<u8>,
+}
+
+/// Header layout before `prev_state_root`
+#[derive(Deserialize)]
+struct BlockHeaderV2 {
+    previous_hash: BlockHash,
+    merkle_root: BlockHash,
+    state_root: BlockHash,
+    timestamp: u64,
+    block_number: u64,
+    gas_limit: u64,
+    gas_used: u64,
+    producer: Address,
+    extra_data: Vec<u8>,
+    randomness: RandomnessProof,
+}
+
+impl From<BlockHeaderV1> for BlockHeaderV2 {
+    fn from(old: BlockHeaderV1) -> Self {
+        BlockHeaderV2 {
+            previous_hash: old.previous_hash,
+            merkle_root: old.merkle_root,
+            state_root: old.state_root,
+            timestamp: old.timestamp,
+            block_number: old.block_number,
+            gas_limit: old.gas_limit,
+            gas_used: old.gas_used,
+            producer: old.producer,
+            extra_data: old.extra_data,
+            randomness: RandomnessProof::default(),
+        }
+    }
+}
+
+impl From<BlockHeaderV2> for BlockHeader {
+    fn from(old: BlockHeaderV2) -> Self {
+        BlockHeader {
+            previous_hash: old.previous_hash,
+            merkle_root: old.merkle_root,
+            prev_state_root: BlockHash::zero(),
+            state_root: old.state_root,
+            timestamp: old.timestamp,
+            block_number: old.block_number,
+            gas_limit: old.gas_limit,
+            gas_used: old.gas_used,
+            producer: old.producer,
+            extra_data: old.extra_data,
+            randomness: old.randomness,
+        }
+    }
+}
+
+/// Block layout before `proof_signature`
+#[derive(Deserialize)]
+struct BlockV1 {
+    header: BlockHeaderV1,
+    transactions: Vec<TransactionV1>,
+    validator_signatures: Vec<ValidatorSignature>,
+    recursive_proof: ZkProof,
+    protocol_updates: Vec<ProtocolRule>,
+}
+
+/// Block layout before `aggregate_signature`
+#[derive(Deserialize)]
+struct BlockV2 {
+    header: BlockHeaderV1,
+    transactions: Vec<TransactionV1>,
+    validator_signatures: Vec<ValidatorSignature>,
+    recursive_proof: ZkProof,
+    protocol_updates: Vec<ProtocolRule>,
+    proof_signature: Vec<u8>,
+}
+
+/// Block layout before transactions carried a chain id
+#[derive(Deserialize)]
+struct BlockV3 {
+    header: BlockHeaderV1,
+    transactions: Vec<TransactionV1>,
+    validator_signatures: Vec<ValidatorSignature>,
+    recursive_proof: ZkProof,
+    protocol_updates: Vec<ProtocolRule>,
+    proof_signature: Vec<u8>,
+    aggregate_signature: Option<AggregateSignature>,
+}
+
+/// Block layout before headers carried beacon randomness. Upgraded blocks
+/// decode, but their beacon no longer verifies.
+#[derive(Deserialize)]
+struct BlockV4 {
+    header: BlockHeaderV1,
+    transactions: Vec<TransactionV2>,
+    validator_signatures: Vec<ValidatorSignature>,
+    recursive_proof: ZkProof,
+    protocol_updates: Vec<ProtocolRule>,
+    proof_signature: Vec<u8>,
+    aggregate_signature: Option<AggregateSignature>,
+}
+
+impl From<BlockV3> for BlockV4 {
+    fn from(old: BlockV3) -> Self {
+        BlockV4 {
+            header: old.header,
+            transactions: old.transactions.into_iter().map(TransactionV2::from).collect(),
+            validator_signatures: old.validator_signatures,
+            recursive_proof: old.recursive_proof,
+            protocol_updates: old.protocol_updates,
+            proof_signature: old.proof_signature,
+            aggregate_signature: old.aggregate_signature,
+        }
+    }
+}
+
+/// Block layout before headers committed to the proof's public inputs.
+/// Upgraded blocks decode with a zero `prev_state_root`, so their proofs no
+/// longer validate statelessly.
+#[derive(Deserialize)]
+struct BlockV5 {
+    header: BlockHeaderV2,
+    transactions: Vec<TransactionV2>,
+    validator_signatures: Vec<ValidatorSignature>,
+    recursive_proof: ZkProof,
+    protocol_updates: Vec<ProtocolRule>,
+    proof_signature: Vec<u8>,
+    aggregate_signature: Option<AggregateSignature>,
+}
+
+impl From<BlockV4> for BlockV5 {
+    fn from(old: BlockV4) -> Self {
+        BlockV5 {
+            header: old.header.into(),
+            transactions: old.transactions,
+            validator_signatures: old.validator_signatures,
+            recursive_proof: old.recursive_proof,
+            protocol_updates: old.protocol_updates,
+            proof_signature: old.proof_signature,
+            aggregate_signature: old.aggregate_signature,
+        }
+    }
+}
+
+/// Block layout before transactions carried access lists
+#[derive(Deserialize)]
+struct BlockV6 {
+    header: BlockHeader,
+    transactions: Vec<TransactionV2>,
+    validator_signatures: Vec<ValidatorSignature>,
+    recursive_proof: ZkProof,
+    protocol_updates: Vec<ProtocolRule>,
+    proof_signature: Vec<u8>,
+    aggregate_signature: Option<AggregateSignature>,
+}
+
+impl From<BlockV5> for BlockV6 {
+    fn from(old: BlockV5) -> Self {
+        BlockV6 {
+            header: old.header.into(),
+            transactions: old.transactions,
+            validator_signatures: old.validator_signatures,
+            recursive_proof: old.recursive_proof,
+            protocol_updates: old.protocol_updates,
+            proof_signature: old.proof_signature,
+            aggregate_signature: old.aggregate_signature,
+        }
+    }
+}
+
+impl Versioned for Block {
+    const TYPE_TAG: TypeTag = TypeTag::Block;
+    const SCHEMA_VERSION: u16 = 7;
+
+    fn decode_legacy(version: u16, codec: Codec, payload: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        let old: BlockV4 = match version {
+            1 => {
+                let old: BlockV1 = codec.decode(PayloadKind::Block, payload, limits)?;
+                BlockV4::from(BlockV3 {
+                    header: old.header,
+                    transactions: old.transactions,
+                    validator_signatures: old.validator_signatures,
+                    recursive_proof: old.recursive_proof,
+                    protocol_updates: old.protocol_updates,
+                    proof_signature: Vec::new(),
+                    aggregate_signature: None,
+                })
+            }
+            2 => {
+                let old: BlockV2 = codec.decode(PayloadKind::Block, payload, limits)?;
+                BlockV4::from(BlockV3 {
+                    header: old.header,
+                    transactions: old.transactions,
+                    validator_signatures: old.validator_signatures,
+                    recursive_proof: old.recursive_proof,
+                    protocol_updates: old.protocol_updates,
+                    proof_signature: old.proof_signature,
+                    aggregate_signature: None,
+                })
+            }
+            3 => codec.decode::<BlockV3>(PayloadKind::Block, payload, limits)?.into(),
+            4 => codec.decode(PayloadKind::Block, payload, limits)?,
+            5 => return Ok(BlockV6::from(codec.decode::<BlockV5>(PayloadKind::Block, payload, limits)?).into()),
+            6 => return Ok(codec.decode::<BlockV6>(PayloadKind::Block, payload, limits)?.into()),
+            _ => return Err(EnvelopeError::UnsupportedVersion { tag: Self::TYPE_TAG, version, current: Self::SCHEMA_VERSION }.into()),
+        };
+        Ok(BlockV6::from(BlockV5::from(old)).into())
+    }
+}
+
+impl From<BlockV6> for Block {
+    fn from(old: BlockV6) -> Self {
+        Block {
+            header: old.header,
+            transactions: old.transactions.into_iter().map(Transaction::from).collect(),
+            validator_signatures: old.validator_signatures,
+            recursive_proof: old.recursive_proof,
+            protocol_updates: old.protocol_updates,
+            proof_signature: old.proof_signature,
+            aggregate_signature: old.aggregate_signature,
+        }
+    }
+}
+
+impl Versioned for Transaction {
+    const TYPE_TAG: TypeTag = TypeTag::Transaction;
+    const SCHEMA_VERSION: u16 = 3;
+
+    fn decode_legacy(version: u16, codec: Codec, payload: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        match version {
+            1 => Ok(TransactionV2::from(codec.decode::<TransactionV1>(PayloadKind::Transaction, payload, limits)?).into()),
+            2 => Ok(codec.decode::<TransactionV2>(PayloadKind::Transaction, payload, limits)?.into()),
+            _ => Err(EnvelopeError::UnsupportedVersion { tag: Self::TYPE_TAG, version, current: Self::SCHEMA_VERSION }.into()),
+        }
+    }
+}
+
+impl Versioned for ZkProof {
+    const TYPE_TAG: TypeTag = TypeTag::ZkProof;
+    const SCHEMA_VERSION: u16 = 1;
+}
+
+impl Versioned for WorldState {
+    const TYPE_TAG: TypeTag = TypeTag::WorldState;
+    const SCHEMA_VERSION: u16 = 1;
+}
+
+/// Validator layout before `bls_public_key`
+#[derive(Deserialize)]
+struct ValidatorV1 {
+    address: Address,
+    stake: u64,
+    public_key: Vec<u8>,
+    performance_score: f64,
+}
+
+impl From<ValidatorV1> for Validator {
+    fn from(old: ValidatorV1) -> Self {
+        Validator {
+            address: old.address,
+            stake: old.stake,
+            public_key: old.public_key,
+            performance_score: old.performance_score,
+            bls_public_key: Vec::new(),
+        }
+    }
+}
+
+/// Validator set layout before the activation and exit queues
+#[derive(Deserialize)]
+struct ValidatorSetV1 {
+    validators: Vec<ValidatorV1>,
+    total_stake: u64,
+}
+
+#[derive(Deserialize)]
+struct QueuedActivationV1 {
+    validator: ValidatorV1,
+    epoch: u64,
+}
+
+/// Validator set layout before validators had BLS keys
+#[derive(Deserialize)]
+struct ValidatorSetV2 {
+    validators: Vec<ValidatorV1>,
+    total_stake: u64,
+    activation_queue: Vec<QueuedActivationV1>,
+    exit_queue: Vec<QueuedExit>,
+}
+
+impl Versioned for ValidatorSet {
+    const TYPE_TAG: TypeTag = TypeTag::ValidatorSet;
+    const SCHEMA_VERSION: u16 = 3;
+
+    fn decode_legacy(version: u16, codec: Codec, payload: &[u8], limits: &DecodeLimits) -> Result<Self> {
+        let old: ValidatorSetV2 = match version {
+            1 => {
+                let old: ValidatorSetV1 = codec.decode(PayloadKind::Message, payload, limits)?;
+                ValidatorSetV2 { validators: old.validators, total_stake: old.total_stake, activation_queue: Vec::new(), exit_queue: Vec::new() }
+            }
+            2 => codec.decode(PayloadKind::Message, payload, limits)?,
+            _ => return Err(EnvelopeError::UnsupportedVersion { tag: Self::TYPE_TAG, version, current: Self::SCHEMA_VERSION }.into()),
+        };
+        Ok(ValidatorSet {
+            validators: old.validators.into_iter().map(Validator::from).collect(),
+            total_stake: old.total_stake,
+            activation_queue: old.activation_queue.into_iter()
+                .map(|queued| QueuedActivation { validator: queued.validator.into(), epoch: queued.epoch })
+                .collect(),
+            exit_queue: old.exit_queue,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[test]
+    fn test_seal_and_open_with_each_codec() {
+        let tx = Transaction::new(Address::new(1), Address::new(2), 10, 3);
+        for codec in [Codec::Bincode, Codec::BincodeZstd, Codec::Json] {
+            let sealed = seal(&tx, codec).unwrap();
+            assert_eq!(peek(&sealed).unwrap().codec, codec);
+            let opened: Transaction = open(&sealed, &DecodeLimits::default()).unwrap();
+            assert_eq!(opened.hash(), tx.hash());
+        }
+
+        let sealed = seal(&tx, Codec::Bincode).unwrap();
+        let err = open::<ZkProof>(&sealed, &DecodeLimits::default()).unwrap_err();
+        assert!(matches!(err.downcast_ref::<EnvelopeError>(), Some(EnvelopeError::WrongType { .. })));
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct PeerInfoV1 {
+        address: Address,
+    }
+
+    #[derive(Debug, Serialize, Deserialize)]
+    struct PeerInfo {
+        address: Address,
+        stake: u64,
+    }
+
+    impl Versioned for PeerInfoV1 {
+        const TYPE_TAG: TypeTag = TypeTag::ValidatorSet;
+        const SCHEMA_VERSION: u16 = 1;
+    }
+
+    impl Versioned for PeerInfo {
+        const TYPE_TAG: TypeTag = TypeTag::ValidatorSet;
+        const SCHEMA_VERSION: u16 = 2;
+
+        fn decode_legacy(version: u16, codec: Codec, payload: &[u8], limits: &DecodeLimits) -> Result<Self> {
+            match version {
+                1 => {
+                    let old: PeerInfoV1 = codec.decode(PayloadKind::Message, payload, limits)?;
+                    Ok(PeerInfo { address: old.address, stake: 0 })
+                }
+                _ => Err(anyhow::anyhow!("no decoder for version {}", version)),
+            }
+        }
+    }
+
+    #[test]
+    fn test_old_versions_are_upgraded_and_future_ones_rejected() {
+        let limits = DecodeLimits::default();
+        let old = seal(&PeerInfoV1 { address: Address::new(4) }, Codec::Json).unwrap();
+        let upgraded: PeerInfo = open(&old, &limits).unwrap();
+        assert_eq!(upgraded.address, Address::new(4));
+        assert_eq!(upgraded.stake, 0);
+
+        let new = seal(&PeerInfo { address: Address::new(4), stake: 9 }, Codec::Bincode).unwrap();
+        let err = open::<PeerInfoV1>(&new, &limits).unwrap_err();
+        assert!(matches!(err.downcast_ref::<EnvelopeError>(),
+                         Some(EnvelopeError::UnsupportedVersion { version: 2, current: 1, .. })));
+    }
+}
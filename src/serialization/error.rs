@@ -0,0 +1,51 @@
+//! Errors from the encoding helpers in this module
+
+use super::envelope::EnvelopeError;
+use super::framing::{DecodeError, error_offset};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum SerializationError {
+    #[error("bincode: {0}")]
+    Bincode(#[from] bincode::Error),
+    #[error("json: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("invalid UTF-8: {0}")]
+    Utf8(#[from] std::str::Utf8Error),
+    #[error("{source}{}", .offset.map(|offset| format!(" at byte {}", offset)).unwrap_or_default())]
+    Decode { source: DecodeError, offset: Option<usize> },
+    #[error(transparent)]
+    Envelope(#[from] EnvelopeError),
+    #[error("codec failed: {0}")]
+    Codec(String),
+}
+
+impl SerializationError {
+    /// The decode error behind this one, if decoding failed
+    pub fn decode_error(&self) -> Option<&DecodeError> {
+        match self {
+            Self::Decode { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl From<DecodeError> for SerializationError {
+    fn from(source: DecodeError) -> Self {
+        Self::Decode { source, offset: None }
+    }
+}
+
+/// Errors from the framing, envelope and compression layers, which attach
+/// context with `anyhow`, recovered as their typed cause
+impl From<anyhow::Error> for SerializationError {
+    fn from(error: anyhow::Error) -> Self {
+        if let Some(source) = error.downcast_ref::<DecodeError>() {
+            return Self::Decode { source: source.clone(), offset: error_offset(&error) };
+        }
+        match error.downcast::<EnvelopeError>() {
+            Ok(envelope) => Self::Envelope(envelope),
+            Err(error) => Self::Codec(format!("{:#}", error)),
+        }
+    }
+}
@@ -0,0 +1,308 @@
+//! Length-prefixed frames and size limits for untrusted input
+//!
+//! Anything read from a peer or from disk is decoded through this module.
+//! A frame is a one-byte [`PayloadKind`] tag, a `u32` little-endian payload
+//! length and the payload. The length is checked against the limit for its
+//! kind as soon as the header arrives, before the payload is buffered or
+//! decoded, and bincode decoding is capped at the same limit so a forged
+//! inner length cannot trigger a huge allocation. Failures are reported as
+//! [`DecodeError`] so callers can tell oversize input (ban the peer) from a
+//! frame that has simply not fully arrived yet.
+
+use crate::types::ProtocolConfig;
+use anyhow::Result;
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::{Serialize, Deserialize};
+use std::fmt;
+
+pub const FRAME_HEADER_LEN: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum PayloadKind {
+    Block,
+    Transaction,
+    Proof,
+    /// Any other network message or stored record
+    Message,
+}
+
+impl PayloadKind {
+    fn tag(self) -> u8 {
+        match self {
+            PayloadKind::Block => 0,
+            PayloadKind::Transaction => 1,
+            PayloadKind::Proof => 2,
+            PayloadKind::Message => 3,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Option<Self> {
+        match tag {
+            0 => Some(PayloadKind::Block),
+            1 => Some(PayloadKind::Transaction),
+            2 => Some(PayloadKind::Proof),
+            3 => Some(PayloadKind::Message),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PayloadKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            PayloadKind::Block => "block",
+            PayloadKind::Transaction => "transaction",
+            PayloadKind::Proof => "proof",
+            PayloadKind::Message => "message",
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DecodeError {
+    #[error("{kind} of {size} bytes exceeds the {limit} byte limit")]
+    Oversize { kind: PayloadKind, size: usize, limit: usize },
+    #[error("unexpected end of input: need {needed} bytes, {available} available")]
+    Truncated { needed: usize, available: usize },
+    #[error("nesting depth {depth} exceeds the limit of {limit}")]
+    TooDeep { depth: usize, limit: usize },
+    #[error("unknown frame kind tag {0}")]
+    UnknownKind(u8),
+    #[error("malformed {kind}: {reason}")]
+    Malformed { kind: PayloadKind, reason: String },
+    #[error("{0} trailing bytes after value")]
+    TrailingBytes(usize),
+}
+
+/// Byte offset in the input where decoding failed, attached as context to
+/// decode errors so malformed peer data can be located in logs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+#[error("decoding failed at byte {0}")]
+pub struct DecodeOffset(pub usize);
+
+/// The [`DecodeOffset`] attached to `error`, if any
+pub fn error_offset(error: &anyhow::Error) -> Option<usize> {
+    error.downcast_ref::<DecodeOffset>().map(|offset| offset.0)
+}
+
+/// Maximum sizes accepted when decoding, per payload kind
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DecodeLimits {
+    pub max_block_bytes: usize,
+    pub max_transaction_bytes: usize,
+    pub max_proof_bytes: usize,
+    pub max_message_bytes: usize,
+    /// Deepest nesting of sequences and objects
+    pub max_depth: usize,
+}
+
+impl Default for DecodeLimits {
+    fn default() -> Self {
+        Self {
+            max_block_bytes: 4 * 1024 * 1024,
+            max_transaction_bytes: 128 * 1024,
+            max_proof_bytes: 2 * 1024 * 1024,
+            max_message_bytes: 8 * 1024 * 1024,
+            max_depth: 16,
+        }
+    }
+}
+
+impl DecodeLimits {
+    /// Blocks may carry `max_block_size` of transactions plus their proof
+    pub fn from_protocol(config: &ProtocolConfig) -> Self {
+        let defaults = Self::default();
+        Self {
+            max_block_bytes: config.max_block_size + defaults.max_proof_bytes,
+            ..defaults
+        }
+    }
+
+    pub fn limit_for(&self, kind: PayloadKind) -> usize {
+        match kind {
+            PayloadKind::Block => self.max_block_bytes,
+            PayloadKind::Transaction => self.max_transaction_bytes,
+            PayloadKind::Proof => self.max_proof_bytes,
+            PayloadKind::Message => self.max_message_bytes,
+        }
+    }
+
+    /// Fail with [`DecodeError::Oversize`] if `size` is over the limit for `kind`
+    pub fn check(&self, kind: PayloadKind, size: usize) -> Result<(), DecodeError> {
+        let limit = self.limit_for(kind);
+        if size > limit {
+            return Err(DecodeError::Oversize { kind, size, limit });
+        }
+        Ok(())
+    }
+}
+
+/// A decoded frame borrowing its payload from the input buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Frame<'a> {
+    pub kind: PayloadKind,
+    pub payload: &'a [u8],
+}
+
+/// Header for a `kind` frame carrying `len` payload bytes
+pub fn frame_header(kind: PayloadKind, len: usize) -> Result<[u8; FRAME_HEADER_LEN], DecodeError> {
+    let len = u32::try_from(len)
+        .map_err(|_| DecodeError::Oversize { kind, size: len, limit: u32::MAX as usize })?;
+    let mut header = [0u8; FRAME_HEADER_LEN];
+    header[0] = kind.tag();
+    header[1..].copy_from_slice(&len.to_le_bytes());
+    Ok(header)
+}
+
+/// Prefix `payload` with its kind and length
+pub fn encode_frame(kind: PayloadKind, payload: &[u8]) -> Result<Vec<u8>> {
+    let header = frame_header(kind, payload.len())?;
+    let mut frame = Vec::with_capacity(FRAME_HEADER_LEN + payload.len());
+    frame.extend_from_slice(&header);
+    frame.extend_from_slice(payload);
+    Ok(frame)
+}
+
+/// Kind and payload length from a frame header, checked against `limits`
+pub fn parse_header(header: &[u8; FRAME_HEADER_LEN], limits: &DecodeLimits) -> Result<(PayloadKind, usize), DecodeError> {
+    let kind = PayloadKind::from_tag(header[0]).ok_or(DecodeError::UnknownKind(header[0]))?;
+    let len = u32::from_le_bytes([header[1], header[2], header[3], header[4]]) as usize;
+    limits.check(kind, len)?;
+    Ok((kind, len))
+}
+
+/// Read the next frame from the front of `buf`.
+///
+/// Returns `Ok(None)` while the frame is incomplete, and the frame together
+/// with the number of bytes it used otherwise. Oversize frames are rejected
+/// from the header alone.
+pub fn decode_frame<'a>(buf: &'a [u8], limits: &DecodeLimits) -> Result<Option<(Frame<'a>, usize)>, DecodeError> {
+    if buf.len() < FRAME_HEADER_LEN {
+        return Ok(None);
+    }
+    let mut header = [0u8; FRAME_HEADER_LEN];
+    header.copy_from_slice(&buf[..FRAME_HEADER_LEN]);
+    let (kind, len) = parse_header(&header, limits)?;
+
+    let end = FRAME_HEADER_LEN + len;
+    if buf.len() < end {
+        return Ok(None);
+    }
+    Ok(Some((Frame { kind, payload: &buf[FRAME_HEADER_LEN..end] }, end)))
+}
+
+/// Bincode-decode `bytes` as a `kind`, enforcing its size limit on the input
+/// and on everything the decoder allocates
+pub fn decode_bounded<T: DeserializeOwned>(kind: PayloadKind, bytes: &[u8], limits: &DecodeLimits) -> Result<T> {
+    let limit = limits.limit_for(kind);
+    limits.check(kind, bytes.len())?;
+    // Read through a shrinking slice so a failure can report its offset
+    let mut input = bytes;
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .allow_trailing_bytes()
+        .with_limit(limit as u64)
+        .deserialize_from(&mut input)
+        .map_err(|e| {
+            let error = match *e {
+                bincode::ErrorKind::SizeLimit => DecodeError::Oversize { kind, size: bytes.len(), limit },
+                bincode::ErrorKind::Io(_) => DecodeError::Truncated { needed: 1, available: 0 },
+                other => DecodeError::Malformed { kind, reason: other.to_string() },
+            };
+            anyhow::Error::new(error).context(DecodeOffset(bytes.len() - input.len()))
+        })
+}
+
+/// Deepest nesting of arrays and objects in a JSON document, ignoring brackets
+/// inside strings
+pub fn json_depth(bytes: &[u8]) -> usize {
+    let (mut depth, mut deepest) = (0usize, 0usize);
+    let (mut in_string, mut escaped) = (false, false);
+    for &byte in bytes {
+        if in_string {
+            match byte {
+                _ if escaped => escaped = false,
+                b'\\' => escaped = true,
+                b'"' => in_string = false,
+                _ => {}
+            }
+            continue;
+        }
+        match byte {
+            b'"' => in_string = true,
+            b'[' | b'{' => {
+                depth += 1;
+                deepest = deepest.max(depth);
+            }
+            b']' | b'}' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+    deepest
+}
+
+/// Fail with [`DecodeError::TooDeep`] if a JSON document nests too deeply
+pub fn check_json_depth(bytes: &[u8], limits: &DecodeLimits) -> Result<(), DecodeError> {
+    let depth = json_depth(bytes);
+    if depth > limits.max_depth {
+        return Err(DecodeError::TooDeep { depth, limit: limits.max_depth });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Transaction;
+    use crate::types::Address;
+
+    #[test]
+    fn test_frames_roundtrip_and_wait_for_payload() {
+        let limits = DecodeLimits::default();
+        let mut stream = encode_frame(PayloadKind::Transaction, b"first").unwrap();
+        stream.extend(encode_frame(PayloadKind::Proof, b"second").unwrap());
+
+        let (frame, used) = decode_frame(&stream, &limits).unwrap().unwrap();
+        assert_eq!(frame, Frame { kind: PayloadKind::Transaction, payload: b"first" });
+
+        let rest = &stream[used..];
+        assert_eq!(decode_frame(&rest[..rest.len() - 1], &limits).unwrap(), None);
+        assert_eq!(decode_frame(rest, &limits).unwrap().unwrap().0.payload, b"second");
+    }
+
+    #[test]
+    fn test_oversize_rejected_from_header() {
+        let limits = DecodeLimits { max_transaction_bytes: 16, ..DecodeLimits::default() };
+        let mut header = vec![PayloadKind::Transaction.tag()];
+        header.extend_from_slice(&1_000_000u32.to_le_bytes());
+
+        assert_eq!(decode_frame(&header, &limits).unwrap_err(),
+                   DecodeError::Oversize { kind: PayloadKind::Transaction, size: 1_000_000, limit: 16 });
+    }
+
+    #[test]
+    fn test_bounded_decode_limits_inner_lengths() {
+        let limits = DecodeLimits { max_transaction_bytes: 256, ..DecodeLimits::default() };
+        let tx = Transaction::new(Address::new(1), Address::new(2), 5, 0);
+        let bytes = bincode::serialize(&tx).unwrap();
+        let decoded: Transaction = decode_bounded(PayloadKind::Transaction, &bytes, &limits).unwrap();
+        assert_eq!(decoded.value, 5);
+
+        // A forged calldata length far beyond the input
+        let mut forged = bytes[..48].to_vec();
+        forged.extend_from_slice(&u64::MAX.to_le_bytes());
+        let err = decode_bounded::<Transaction>(PayloadKind::Transaction, &forged, &limits).unwrap_err();
+        assert!(err.downcast_ref::<DecodeError>().is_some());
+        assert_eq!(error_offset(&err), Some(56));
+    }
+
+    #[test]
+    fn test_json_depth_ignores_strings() {
+        assert_eq!(json_depth(br#"{"a": [1, {"b": "[[[["}]}"#), 3);
+        let deep = "[".repeat(100) + &"]".repeat(100);
+        let limits = DecodeLimits::default();
+        assert!(matches!(check_json_depth(deep.as_bytes(), &limits), Err(DecodeError::TooDeep { depth: 100, .. })));
+    }
+}
@@ -6,64 +6,75 @@
 
 use serde::{Serialize, Deserialize};
 use bincode;
-use anyhow::Result;
 use tracing::debug;
 use crate::types::*;
 
+pub mod canonical;
+pub mod compression;
+pub mod envelope;
+pub mod error;
+pub mod framing;
+pub mod stream;
+pub mod zero_copy;
+
+pub use canonical::{CanonicalEncode, canonical_bytes, canonical_hash};
+pub use compression::{CompressionPolicies, CompressionPolicy, compress, compression_ratio, decode_compressed, decompress, encode_compressed};
+pub use envelope::{Codec, EnvelopeError, EnvelopeHeader, TypeTag, Versioned, open, peek, seal};
+pub use error::SerializationError;
+pub use framing::{DecodeError, DecodeLimits, DecodeOffset, Frame, PayloadKind, decode_bounded, decode_frame, encode_frame, error_offset};
+pub use stream::{FrameReader, FrameWriter, ProofArchiveReader, ProofArchiveWriter, SnapshotHeader, SnapshotReader, read_block, read_next_block, write_block, write_snapshot};
+pub use zero_copy::{BlockRef, DecodeRef, Reader, TransactionRef, decode_block_ref, decode_transaction_ref};
+
 // Standard Bincode serialization using 1.x API
-pub fn encode_blockchain_data<T: Serialize>(data: &T) -> Result<Vec<u8>> {
+pub fn encode_blockchain_data<T: Serialize>(data: &T) -> Result<Vec<u8>, SerializationError> {
     let encoded = bincode::serialize(data)?;
     debug!("📦 Encoded blockchain data: {} bytes", encoded.len());
     Ok(encoded)
 }
 
 // Enhanced state data encoding with compression markers
-pub fn encode_state_data<T: Serialize>(data: &T) -> Result<Vec<u8>> {
+pub fn encode_state_data<T: Serialize>(data: &T) -> Result<Vec<u8>, SerializationError> {
     let encoded = bincode::serialize(data)?;
     debug!("📦 Encoded state data: {} bytes", encoded.len());
     Ok(encoded)
 }
 
 // Blockchain data decoding with error handling
-pub fn decode_blockchain_data<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T> {
-    let result = bincode::deserialize(data)?;
-    Ok(result)
+pub fn decode_blockchain_data<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T, SerializationError> {
+    Ok(decode_bounded(PayloadKind::Message, data, &DecodeLimits::default())?)
 }
 
 // zkVM optimized output encoding
-pub fn encode_zkvm_output<T: Serialize>(data: &T) -> Result<Vec<u8>> {
+pub fn encode_zkvm_output<T: Serialize>(data: &T) -> Result<Vec<u8>, SerializationError> {
     let encoded = bincode::serialize(data)?;
     debug!("🔧 Encoded zkVM output: {} bytes", encoded.len());
     Ok(encoded)
 }
 
 // zkVM output decoding
-pub fn decode_zkvm_output<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T> {
-    let result = bincode::deserialize(data)?;
-    Ok(result)
+pub fn decode_zkvm_output<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T, SerializationError> {
+    Ok(decode_bounded(PayloadKind::Proof, data, &DecodeLimits::default())?)
 }
 
 // Network-optimized state encoding
-pub fn decode_state_data<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T> {
-    let result = bincode::deserialize(data)?;
-    Ok(result)
+pub fn decode_state_data<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T, SerializationError> {
+    Ok(decode_bounded(PayloadKind::Message, data, &DecodeLimits::default())?)
 }
 
-// Network message encoding
-pub fn encode_network_message<T: Serialize>(data: &T) -> Result<Vec<u8>> {
-    let encoded = bincode::serialize(data)?;
+// Network message encoding, compressed under the default message policy
+pub fn encode_network_message<T: Serialize>(data: &T) -> Result<Vec<u8>, SerializationError> {
+    let encoded = encode_compressed(PayloadKind::Message, data, &CompressionPolicies::default())?;
     debug!("📡 Encoded network message: {} bytes", encoded.len());
     Ok(encoded)
 }
 
 // Network message decoding
-pub fn decode_network_message<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T> {
-    let result = bincode::deserialize(data)?;
-    Ok(result)
+pub fn decode_network_message<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T, SerializationError> {
+    Ok(decode_compressed(PayloadKind::Message, data, &CompressionPolicies::default(), &DecodeLimits::default())?)
 }
 
 // Enhanced batch operations for high-throughput scenarios
-pub fn encode_batch<T: Serialize>(items: &[T]) -> Result<Vec<Vec<u8>>> {
+pub fn encode_batch<T: Serialize>(items: &[T]) -> Result<Vec<Vec<u8>>, SerializationError> {
     let mut encoded_items = Vec::new();
     for item in items {
         let encoded = bincode::serialize(item)?;
@@ -73,10 +84,11 @@ pub fn encode_batch<T: Serialize>(items: &[T]) -> Result<Vec<Vec<u8>>> {
     Ok(encoded_items)
 }
 
-pub fn decode_batch<T: for<'de> Deserialize<'de>>(data: &[Vec<u8>]) -> Result<Vec<T>> {
+pub fn decode_batch<T: for<'de> Deserialize<'de>>(data: &[Vec<u8>]) -> Result<Vec<T>, SerializationError> {
+    let limits = DecodeLimits::default();
     let mut decoded_items = Vec::new();
     for item_data in data {
-        let decoded = bincode::deserialize(item_data)?;
+        let decoded = decode_bounded(PayloadKind::Message, item_data, &limits)?;
         decoded_items.push(decoded);
     }
     debug!("📦 Decoded batch: {} items", decoded_items.len());
@@ -84,37 +96,40 @@ pub fn decode_batch<T: for<'de> Deserialize<'de>>(data: &[Vec<u8>]) -> Result<Ve
 }
 
 // Size estimation for resource planning
-pub fn estimate_size<T: Serialize>(data: &T) -> Result<usize> {
+pub fn estimate_size<T: Serialize>(data: &T) -> Result<usize, SerializationError> {
     let encoded = bincode::serialize(data)?;
     Ok(encoded.len())
 }
 
 // JSON utilities for debugging and API compatibility
-pub fn to_json_pretty<T: Serialize>(data: &T) -> Result<String> {
+pub fn to_json_pretty<T: Serialize>(data: &T) -> Result<String, SerializationError> {
     let json = serde_json::to_string_pretty(data)?;
     Ok(json)
 }
 
-pub fn to_json_value<T: Serialize>(data: &T) -> Result<serde_json::Value> {
+pub fn to_json_value<T: Serialize>(data: &T) -> Result<serde_json::Value, SerializationError> {
     let value = serde_json::to_value(data)?;
     Ok(value)
 }
 
 // Hybrid serialization: Bincode for performance, JSON for debugging
-pub fn encode_hybrid<T: Serialize>(data: &T, debug_mode: bool) -> Result<Vec<u8>> {
+pub fn encode_hybrid<T: Serialize>(data: &T, debug_mode: bool) -> Result<Vec<u8>, SerializationError> {
     if debug_mode {
         let json = serde_json::to_string(data)?;
         Ok(json.into_bytes())
     } else {
-        bincode::serialize(data).map_err(Into::into)
+        Ok(bincode::serialize(data)?)
     }
 }
 
-pub fn decode_hybrid<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T> {
+pub fn decode_hybrid<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T, SerializationError> {
     // Try Bincode first, fallback to JSON
-    match bincode::deserialize(data) {
+    let limits = DecodeLimits::default();
+    match decode_bounded(PayloadKind::Message, data, &limits) {
         Ok(result) => Ok(result),
+        Err(e) if matches!(e.downcast_ref::<DecodeError>(), Some(DecodeError::Oversize { .. })) => Err(e.into()),
         Err(_) => {
+            framing::check_json_depth(data, &limits)?;
             let json_str = std::str::from_utf8(data)?;
             let result = serde_json::from_str(json_str)?;
             Ok(result)
@@ -123,7 +138,7 @@ pub fn decode_hybrid<T: for<'de> Deserialize<'de>>(data: &[u8]) -> Result<T> {
 }
 
 // Format comparison for optimization analysis
-pub fn compare_formats<T: Serialize>(data: &T) -> Result<(usize, usize)> {
+pub fn compare_formats<T: Serialize>(data: &T) -> Result<(usize, usize), SerializationError> {
     let bincode_size = bincode::serialize(data)?.len();
     let json_size = serde_json::to_string(data)?.len();
     debug!("📊 Format comparison - Bincode: {} bytes, JSON: {} bytes", bincode_size, json_size);
@@ -141,9 +156,11 @@ pub fn zkvm_input_config() -> bincode::config::DefaultOptions {
 }
 
 // Create block metadata for analytics
-pub fn create_block_metadata(transactions: &[Transaction], producer: Address) -> Result<BlockMetadata> {
+pub fn create_block_metadata(transactions: &[Transaction], producer: Address) -> Result<BlockMetadata, SerializationError> {
     let total_gas = transactions.iter().map(|tx| tx.gas_limit).sum();
     let total_value = transactions.iter().map(|tx| tx.value).sum();
+    let raw_size = estimate_size(&transactions)?;
+    let compressed_size = encode_compressed(PayloadKind::Block, &transactions, &CompressionPolicies::default())?.len();
     
     let metadata = BlockMetadata {
         transaction_count: transactions.len() as u64,
@@ -151,8 +168,8 @@ pub fn create_block_metadata(transactions: &[Transaction], producer: Address) ->
         total_value_transferred: total_value,
         producer,
         encoding_stats: EncodingStats {
-            compressed_size: estimate_size(&transactions)?,
-            compression_ratio: 1.0, // Will be calculated during actual compression
+            compressed_size,
+            compression_ratio: compression_ratio(raw_size, compressed_size),
         },
     };
     
@@ -251,7 +268,10 @@ mod tests {
             value: 1000,
             data: vec![1, 2, 3, 4, 5],
             gas_limit: 21000,
+            gas_price: 1,
             nonce: 1,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             signature: vec![0; 64],
             sig_type: SignatureType::Ed25519,
         };
@@ -309,7 +329,10 @@ mod tests {
             value: 1000,
             data: vec![1, 2, 3, 4, 5],
             gas_limit: 21000,
+            gas_price: 1,
             nonce: 1,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             signature: vec![0; 64],
             sig_type: SignatureType::Ed25519,
         };
@@ -348,7 +371,10 @@ mod tests {
                 value: 1000,
                 data: vec![1, 2, 3],
                 gas_limit: 21000,
+                gas_price: 1,
                 nonce: 1,
+                chain_id: DEFAULT_CHAIN_ID,
+                access_list: None,
                 signature: vec![0; 64],
                 sig_type: SignatureType::Ed25519,
             },
@@ -358,7 +384,10 @@ mod tests {
                 value: 500,
                 data: vec![4, 5, 6],
                 gas_limit: 10000,
+                gas_price: 1,
                 nonce: 2,
+                chain_id: DEFAULT_CHAIN_ID,
+                access_list: None,
                 signature: vec![1; 64],
                 sig_type: SignatureType::Ed25519,
             },
@@ -371,8 +400,10 @@ mod tests {
         assert_eq!(metadata.total_gas_used, 31000);
         assert_eq!(metadata.total_value_transferred, 1500);
         assert_eq!(metadata.producer, producer);
-        assert_eq!(metadata.encoding_stats.compressed_size, estimate_size(&transactions).unwrap());
-        assert_eq!(metadata.encoding_stats.compression_ratio, 1.0);
+        let compressed = encode_compressed(PayloadKind::Block, &transactions, &CompressionPolicies::default()).unwrap();
+        assert_eq!(metadata.encoding_stats.compressed_size, compressed.len());
+        assert_eq!(metadata.encoding_stats.compression_ratio,
+                   compression_ratio(estimate_size(&transactions).unwrap(), compressed.len()));
         
         println!("Block metadata: {}", to_string_pretty(&metadata).unwrap());
     }
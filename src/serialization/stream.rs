@@ -0,0 +1,490 @@
+//! Streaming encoding of blocks, state snapshots and proof archives
+//!
+//! Large objects are written as a sequence of frames (see [`super::framing`])
+//! to any `AsyncWrite` and read back one frame at a time from any
+//! `AsyncRead`, so neither side holds more than one account, transaction or
+//! proof in memory besides its own copy of the data. Frame payloads use the
+//! [`super::canonical`] layout, compressed per [`CompressionPolicies`], and
+//! are decoded under [`DecodeLimits`].
+//!
+//! * snapshot: a header frame with the account count, then one frame per
+//!   account in address order
+//! * block: a header frame with the transaction count, one frame per
+//!   transaction, then a frame with signatures, proof and protocol updates
+//! * proof archive: one frame per proof, closed by an empty end frame
+
+use super::canonical::CanonicalEncode;
+use super::compression::{CompressionPolicies, compress, decompress};
+use super::framing::{DecodeError, DecodeLimits, PayloadKind, FRAME_HEADER_LEN, frame_header, parse_header};
+use super::zero_copy::{DecodeRef, Reader, decode_ref};
+use crate::types::*;
+use anyhow::{Result, anyhow};
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+
+/// Writes frames to an `AsyncWrite`
+pub struct FrameWriter<W> {
+    writer: W,
+    policies: CompressionPolicies,
+    scratch: Vec<u8>,
+    bytes_written: u64,
+}
+
+impl<W: AsyncWrite + Unpin> FrameWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, policies: CompressionPolicies::default(), scratch: Vec::new(), bytes_written: 0 }
+    }
+
+    pub fn with_compression(mut self, policies: CompressionPolicies) -> Self {
+        self.policies = policies;
+        self
+    }
+
+    pub async fn write_frame(&mut self, kind: PayloadKind, payload: &[u8]) -> Result<()> {
+        let header = frame_header(kind, payload.len())?;
+        self.writer.write_all(&header).await?;
+        self.writer.write_all(payload).await?;
+        self.bytes_written += (FRAME_HEADER_LEN + payload.len()) as u64;
+        Ok(())
+    }
+
+    /// Write the compressed canonical encoding of `value` as one frame
+    pub async fn write_value<T: CanonicalEncode + ?Sized>(&mut self, kind: PayloadKind, value: &T) -> Result<()> {
+        self.scratch.clear();
+        value.encode_canonical(&mut self.scratch);
+        let payload = compress(kind, &self.scratch, &self.policies)?;
+        self.write_frame(kind, &payload).await
+    }
+
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written
+    }
+
+    pub async fn flush(&mut self) -> Result<()> {
+        self.writer.flush().await?;
+        Ok(())
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+/// Reads frames from an `AsyncRead`, enforcing limits from each header
+pub struct FrameReader<R> {
+    reader: R,
+    limits: DecodeLimits,
+    policies: CompressionPolicies,
+    payload: Vec<u8>,
+}
+
+impl<R: AsyncRead + Unpin> FrameReader<R> {
+    pub fn new(reader: R, limits: DecodeLimits) -> Self {
+        Self { reader, limits, policies: CompressionPolicies::default(), payload: Vec::new() }
+    }
+
+    /// Policies whose dictionaries the writer used
+    pub fn with_compression(mut self, policies: CompressionPolicies) -> Self {
+        self.policies = policies;
+        self
+    }
+
+    /// Next frame, or `None` at a clean end of input between frames
+    pub async fn read_frame(&mut self) -> Result<Option<(PayloadKind, &[u8])>> {
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        let first = self.reader.read(&mut header[..1]).await?;
+        if first == 0 {
+            return Ok(None);
+        }
+        self.reader.read_exact(&mut header[1..]).await?;
+        let (kind, len) = parse_header(&header, &self.limits)?;
+
+        self.payload.resize(len, 0);
+        self.reader.read_exact(&mut self.payload).await?;
+        Ok(Some((kind, &self.payload)))
+    }
+
+    /// Read the next frame, which must be a `kind` frame, and decode it
+    pub async fn read_value<T>(&mut self, kind: PayloadKind) -> Result<T>
+    where
+        T: for<'a> DecodeRef<'a>,
+    {
+        self.read_value_or_end(kind).await?
+            .ok_or_else(|| DecodeError::Truncated { needed: FRAME_HEADER_LEN, available: 0 }.into())
+    }
+
+    /// Like [`FrameReader::read_value`], but `None` at a clean end of input
+    pub async fn read_value_or_end<T>(&mut self, kind: PayloadKind) -> Result<Option<T>>
+    where
+        T: for<'a> DecodeRef<'a>,
+    {
+        let Some(found) = self.read_frame().await?.map(|(found, _)| found) else {
+            return Ok(None);
+        };
+        if found != kind {
+            return Err(anyhow!("Expected a {} frame, found a {} frame", kind, found));
+        }
+        self.decode_payload(kind).map(Some)
+    }
+
+    /// Decompress and decode the payload of the frame just read
+    fn decode_payload<T>(&self, kind: PayloadKind) -> Result<T>
+    where
+        T: for<'a> DecodeRef<'a>,
+    {
+        let payload = decompress(kind, &self.payload, &self.policies, &self.limits)?;
+        decode_ref(kind, &payload, &self.limits)
+    }
+}
+
+/// First frame of a snapshot
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SnapshotHeader {
+    pub block_number: u64,
+    pub global_nonce: u64,
+    pub state_root: BlockHash,
+    pub accounts: u64,
+}
+
+impl CanonicalEncode for SnapshotHeader {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.block_number.encode_canonical(out);
+        self.global_nonce.encode_canonical(out);
+        self.state_root.encode_canonical(out);
+        self.accounts.encode_canonical(out);
+    }
+}
+
+impl<'a> DecodeRef<'a> for SnapshotHeader {
+    const MIN_SIZE: usize = 8 * 3 + 32;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(SnapshotHeader {
+            block_number: reader.u64()?,
+            global_nonce: reader.u64()?,
+            state_root: BlockHash(reader.array()?),
+            accounts: reader.u64()?,
+        })
+    }
+}
+
+struct AccountEntry<'s>(&'s Address, &'s Account);
+
+impl CanonicalEncode for AccountEntry<'_> {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.0.encode_canonical(out);
+        self.1.encode_canonical(out);
+    }
+}
+
+struct OwnedAccountEntry(Address, Account);
+
+impl<'a> DecodeRef<'a> for OwnedAccountEntry {
+    const MIN_SIZE: usize = 20 + <Account as DecodeRef<'a>>::MIN_SIZE;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(OwnedAccountEntry(Address(reader.array()?), Account::decode_ref(reader)?))
+    }
+}
+
+/// Write `state` as a snapshot, one frame per account in address order
+pub async fn write_snapshot<W: AsyncWrite + Unpin>(writer: &mut FrameWriter<W>, state: &WorldState) -> Result<()> {
+    let header = SnapshotHeader {
+        block_number: state.block_number,
+        global_nonce: state.global_nonce,
+        state_root: state.state_root,
+        accounts: state.accounts.len() as u64,
+    };
+    writer.write_value(PayloadKind::Message, &header).await?;
+
+    let mut addresses: Vec<&Address> = state.accounts.keys().collect();
+    addresses.sort();
+    for address in addresses {
+        writer.write_value(PayloadKind::Message, &AccountEntry(address, &state.accounts[address])).await?;
+    }
+    writer.flush().await
+}
+
+/// Reads a snapshot back one account at a time
+pub struct SnapshotReader<R> {
+    frames: FrameReader<R>,
+    header: SnapshotHeader,
+    read: u64,
+}
+
+impl<R: AsyncRead + Unpin> SnapshotReader<R> {
+    pub async fn open(reader: R, limits: DecodeLimits) -> Result<Self> {
+        let mut frames = FrameReader::new(reader, limits);
+        let header = frames.read_value(PayloadKind::Message).await?;
+        Ok(Self { frames, header, read: 0 })
+    }
+
+    pub fn header(&self) -> &SnapshotHeader {
+        &self.header
+    }
+
+    /// Next account, or `None` once all accounts in the header were read
+    pub async fn next_account(&mut self) -> Result<Option<(Address, Account)>> {
+        if self.read == self.header.accounts {
+            return Ok(None);
+        }
+        let OwnedAccountEntry(address, account) = self.frames.read_value(PayloadKind::Message).await?;
+        self.read += 1;
+        Ok(Some((address, account)))
+    }
+
+    /// Read the remaining accounts into a full state
+    pub async fn into_state(mut self) -> Result<WorldState> {
+        let mut state = WorldState {
+            global_nonce: self.header.global_nonce,
+            state_root: self.header.state_root,
+            block_number: self.header.block_number,
+            ..WorldState::default()
+        };
+        while let Some((address, account)) = self.next_account().await? {
+            state.accounts.insert(address, account);
+        }
+        Ok(state)
+    }
+}
+
+struct BlockOpening<'b>(&'b BlockHeader, u64);
+
+impl CanonicalEncode for BlockOpening<'_> {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.0.encode_canonical(out);
+        self.1.encode_canonical(out);
+    }
+}
+
+struct OwnedBlockOpening(BlockHeader, u64);
+
+impl<'a> DecodeRef<'a> for OwnedBlockOpening {
+    const MIN_SIZE: usize = <BlockHeader as DecodeRef<'a>>::MIN_SIZE + 8;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(OwnedBlockOpening(BlockHeader::decode_ref(reader)?, reader.u64()?))
+    }
+}
+
+struct BlockClosing<'b>(&'b Block);
+
+impl CanonicalEncode for BlockClosing<'_> {
+    fn encode_canonical(&self, out: &mut Vec<u8>) {
+        self.0.validator_signatures.encode_canonical(out);
+        self.0.recursive_proof.encode_canonical(out);
+        self.0.protocol_updates.encode_canonical(out);
+        self.0.proof_signature.encode_canonical(out);
+        self.0.aggregate_signature.encode_canonical(out);
+    }
+}
+
+struct OwnedBlockClosing(Vec<ValidatorSignature>, ZkProof, Vec<ProtocolRule>, Vec<u8>, Option<AggregateSignature>);
+
+impl<'a> DecodeRef<'a> for OwnedBlockClosing {
+    const MIN_SIZE: usize = 8 * 3 + <ZkProof as DecodeRef<'a>>::MIN_SIZE + 1;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(OwnedBlockClosing(
+            reader.seq()?,
+            ZkProof::decode_ref(reader)?,
+            reader.seq()?,
+            reader.var_bytes()?.to_vec(),
+            Option::decode_ref(reader)?,
+        ))
+    }
+}
+
+/// Write `block` with each transaction in its own frame
+pub async fn write_block<W: AsyncWrite + Unpin>(writer: &mut FrameWriter<W>, block: &Block) -> Result<()> {
+    let opening = BlockOpening(&block.header, block.transactions.len() as u64);
+    writer.write_value(PayloadKind::Block, &opening).await?;
+    for tx in &block.transactions {
+        writer.write_value(PayloadKind::Transaction, tx).await?;
+    }
+    writer.write_value(PayloadKind::Block, &BlockClosing(block)).await?;
+    writer.flush().await
+}
+
+/// Read a block written by [`write_block`]
+pub async fn read_block<R: AsyncRead + Unpin>(frames: &mut FrameReader<R>) -> Result<Block> {
+    read_next_block(frames).await?
+        .ok_or_else(|| DecodeError::Truncated { needed: FRAME_HEADER_LEN, available: 0 }.into())
+}
+
+/// Read the next of a sequence of blocks, or `None` at a clean end of input
+pub async fn read_next_block<R: AsyncRead + Unpin>(frames: &mut FrameReader<R>) -> Result<Option<Block>> {
+    let Some(OwnedBlockOpening(header, count)) = frames.read_value_or_end(PayloadKind::Block).await? else {
+        return Ok(None);
+    };
+
+    // The count is untrusted; every transaction still has to arrive as a frame
+    let mut transactions = Vec::new();
+    for _ in 0..count {
+        transactions.push(frames.read_value(PayloadKind::Transaction).await?);
+    }
+    let OwnedBlockClosing(validator_signatures, recursive_proof, protocol_updates, proof_signature, aggregate_signature) =
+        frames.read_value(PayloadKind::Block).await?;
+
+    Ok(Some(Block { header, transactions, validator_signatures, recursive_proof, protocol_updates, proof_signature, aggregate_signature }))
+}
+
+/// Writes proofs one frame at a time; call [`ProofArchiveWriter::finish`] to
+/// mark the archive complete
+pub struct ProofArchiveWriter<W> {
+    frames: FrameWriter<W>,
+    proofs: u64,
+}
+
+impl<W: AsyncWrite + Unpin> ProofArchiveWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { frames: FrameWriter::new(writer), proofs: 0 }
+    }
+
+    pub async fn append(&mut self, proof: &ZkProof) -> Result<()> {
+        self.frames.write_value(PayloadKind::Proof, proof).await?;
+        self.proofs += 1;
+        Ok(())
+    }
+
+    pub fn proofs(&self) -> u64 {
+        self.proofs
+    }
+
+    /// Write the end frame and hand back the writer
+    pub async fn finish(mut self) -> Result<W> {
+        self.frames.write_frame(PayloadKind::Message, &[]).await?;
+        self.frames.flush().await?;
+        Ok(self.frames.into_inner())
+    }
+}
+
+/// Reads proofs back one at a time
+pub struct ProofArchiveReader<R> {
+    frames: FrameReader<R>,
+    finished: bool,
+}
+
+impl<R: AsyncRead + Unpin> ProofArchiveReader<R> {
+    pub fn new(reader: R, limits: DecodeLimits) -> Self {
+        Self { frames: FrameReader::new(reader, limits), finished: false }
+    }
+
+    /// Next proof, or `None` at the end frame. An archive that stops without
+    /// its end frame is an error.
+    pub async fn next_proof(&mut self) -> Result<Option<ZkProof>> {
+        if self.finished {
+            return Ok(None);
+        }
+        let frame = self.frames.read_frame().await?.map(|(kind, payload)| (kind, payload.is_empty()));
+        match frame {
+            Some((PayloadKind::Proof, _)) => Ok(Some(self.frames.decode_payload(PayloadKind::Proof)?)),
+            Some((PayloadKind::Message, true)) => {
+                self.finished = true;
+                Ok(None)
+            }
+            Some((kind, _)) => Err(anyhow!("Unexpected {} frame in proof archive", kind)),
+            None => Err(anyhow!("Proof archive ended without its end frame")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn large_state(accounts: u8) -> WorldState {
+        let mut state = WorldState { block_number: 12, global_nonce: 99, ..WorldState::default() };
+        for id in 0..accounts {
+            let mut account = Account::new(u64::from(id));
+            account.code = vec![id; 256];
+            account.storage.insert([id; 32], [1; 32]);
+            state.accounts.insert(Address::new(id), account);
+        }
+        state.state_root = state.compute_state_root();
+        state
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_roundtrip_streams_accounts() {
+        let state = large_state(200);
+        let mut writer = FrameWriter::new(Vec::new());
+        write_snapshot(&mut writer, &state).await.unwrap();
+        let bytes = writer.into_inner();
+
+        // Each account fits in a frame far smaller than the whole snapshot
+        let limits = DecodeLimits { max_message_bytes: 1024, ..DecodeLimits::default() };
+        assert!(bytes.len() > limits.max_message_bytes);
+
+        let mut snapshot = SnapshotReader::open(bytes.as_slice(), limits).await.unwrap();
+        assert_eq!(snapshot.header().accounts, 200);
+        let (first, _) = snapshot.next_account().await.unwrap().unwrap();
+        assert_eq!(first, Address::new(0));
+
+        let restored = SnapshotReader::open(bytes.as_slice(), limits).await.unwrap().into_state().await.unwrap();
+        assert_eq!(restored.compute_state_root(), state.state_root);
+        assert_eq!(restored.block_number, 12);
+
+        let truncated = &bytes[..bytes.len() - 10];
+        assert!(SnapshotReader::open(truncated, limits).await.unwrap().into_state().await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_block_and_proof_archive_roundtrip() {
+        let block = Block {
+            header: BlockHeader {
+                previous_hash: BlockHash::zero(),
+                merkle_root: BlockHash::zero(),
+                prev_state_root: BlockHash::zero(),
+                state_root: BlockHash([3; 32]),
+                timestamp: 1,
+                block_number: 1,
+                gas_limit: 1_000_000,
+                gas_used: 42_000,
+                producer: Address::new(1),
+                extra_data: vec![],
+                randomness: RandomnessProof::default(),
+            },
+            transactions: (0..5).map(|n| Transaction::new(Address::new(1), Address::new(2), 1, n)).collect(),
+            validator_signatures: vec![],
+            recursive_proof: ZkProof {
+                proof_data: vec![9; 64],
+                public_inputs: vec![],
+                verification_key: vec![],
+                proof_type: ProofType::Risc0,
+            },
+            protocol_updates: vec![],
+            proof_signature: vec![],
+            aggregate_signature: None,
+        };
+
+        let mut writer = FrameWriter::new(Vec::new());
+        write_block(&mut writer, &block).await.unwrap();
+        let bytes = writer.into_inner();
+        let mut frames = FrameReader::new(bytes.as_slice(), DecodeLimits::default());
+        let decoded = read_block(&mut frames).await.unwrap();
+        assert_eq!(decoded.transactions.len(), 5);
+        assert_eq!(decoded.transactions[4].hash(), block.transactions[4].hash());
+
+        let mut archive = ProofArchiveWriter::new(Vec::new());
+        for _ in 0..3 {
+            archive.append(&block.recursive_proof).await.unwrap();
+        }
+        assert_eq!(archive.proofs(), 3);
+        let bytes = archive.finish().await.unwrap();
+
+        let mut reader = ProofArchiveReader::new(bytes.as_slice(), DecodeLimits::default());
+        let mut read = 0;
+        while let Some(proof) = reader.next_proof().await.unwrap() {
+            assert_eq!(proof.proof_data, block.recursive_proof.proof_data);
+            read += 1;
+        }
+        assert_eq!(read, 3);
+
+        let unfinished = &bytes[..bytes.len() - FRAME_HEADER_LEN];
+        let mut reader = ProofArchiveReader::new(unfinished, DecodeLimits::default());
+        for _ in 0..3 {
+            reader.next_proof().await.unwrap();
+        }
+        assert!(reader.next_proof().await.is_err());
+    }
+}
@@ -0,0 +1,557 @@
+//! Borrowed decoding of canonically encoded blocks and transactions
+//!
+//! Block import decodes every transaction, and most of a transaction is its
+//! calldata and signature. [`TransactionRef`] and [`BlockRef`] borrow those
+//! byte fields straight from the received buffer instead of copying them into
+//! fresh `Vec`s; callers convert to owned types only for what they keep.
+//! Input is the [`super::canonical`] layout, so a borrowed transaction hashes
+//! its raw bytes without re-encoding. Decoding enforces [`DecodeLimits`] on
+//! the whole input, on every transaction and proof inside it, and on nesting.
+
+use super::framing::{DecodeError, DecodeLimits, DecodeOffset, PayloadKind};
+use crate::crypto::hash::keccak256_hash;
+use crate::types::*;
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+
+/// Cursor over a canonically encoded buffer
+#[derive(Debug, Clone)]
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    depth: usize,
+    limits: DecodeLimits,
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self::with_limits(buf, DecodeLimits::default())
+    }
+
+    pub fn with_limits(buf: &'a [u8], limits: DecodeLimits) -> Self {
+        Self { buf, pos: 0, depth: 0, limits }
+    }
+
+    pub fn limits(&self) -> &DecodeLimits {
+        &self.limits
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.pos
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Result<&'a [u8]> {
+        if len > self.remaining() {
+            return Err(DecodeError::Truncated { needed: len, available: self.remaining() }.into());
+        }
+        let bytes = &self.buf[self.pos..self.pos + len];
+        self.pos += len;
+        Ok(bytes)
+    }
+
+    pub fn array<const N: usize>(&mut self) -> Result<[u8; N]> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.bytes(N)?);
+        Ok(array)
+    }
+
+    pub fn u8(&mut self) -> Result<u8> {
+        Ok(self.bytes(1)?[0])
+    }
+
+    pub fn u32(&mut self) -> Result<u32> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    pub fn u64(&mut self) -> Result<u64> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    /// A `u64` length or count, rejected if it cannot fit in what is left
+    pub fn count(&mut self, min_item_size: usize) -> Result<usize> {
+        let len = self.u64()?;
+        let needed = len.saturating_mul(min_item_size.max(1) as u64);
+        if needed > self.remaining() as u64 {
+            return Err(DecodeError::Truncated {
+                needed: usize::try_from(needed).unwrap_or(usize::MAX),
+                available: self.remaining(),
+            }.into());
+        }
+        Ok(len as usize)
+    }
+
+    /// Length-prefixed bytes, borrowed from the buffer
+    pub fn var_bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.count(1)?;
+        self.bytes(len)
+    }
+
+    /// Run `decode` one nesting level deeper, failing past `max_depth`
+    pub fn nested<T>(&mut self, decode: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        if self.depth >= self.limits.max_depth {
+            return Err(DecodeError::TooDeep { depth: self.depth + 1, limit: self.limits.max_depth }.into());
+        }
+        self.depth += 1;
+        let result = decode(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// A count-prefixed sequence, one nesting level deeper
+    pub fn seq<T: DecodeRef<'a>>(&mut self) -> Result<Vec<T>> {
+        self.nested(|reader| {
+            let count = reader.count(T::MIN_SIZE)?;
+            (0..count).map(|_| T::decode_ref(reader)).collect()
+        })
+    }
+
+    /// Fail with [`DecodeError::Oversize`] if the bytes since `start` are over the limit for `kind`
+    fn check_size(&self, kind: PayloadKind, start: usize) -> Result<()> {
+        self.limits.check(kind, self.pos - start)?;
+        Ok(())
+    }
+
+    /// Fail if anything is left after the value
+    pub fn finish(&self) -> Result<()> {
+        if self.remaining() > 0 {
+            return Err(DecodeError::TrailingBytes(self.remaining()).into());
+        }
+        Ok(())
+    }
+}
+
+/// Decoding from a [`Reader`], possibly borrowing from its buffer
+pub trait DecodeRef<'a>: Sized {
+    /// Smallest encoded size, used to reject impossible counts early
+    const MIN_SIZE: usize;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self>;
+}
+
+/// Decode one `kind` value that must span all of `bytes`. Errors carry the
+/// [`DecodeOffset`] the reader had reached.
+pub fn decode_ref<'a, T: DecodeRef<'a>>(kind: PayloadKind, bytes: &'a [u8], limits: &DecodeLimits) -> Result<T> {
+    limits.check(kind, bytes.len())?;
+    let mut reader = Reader::with_limits(bytes, *limits);
+    let value = T::decode_ref(&mut reader)
+        .and_then(|value| reader.finish().map(|()| value))
+        .with_context(|| DecodeOffset(reader.position()))?;
+    Ok(value)
+}
+
+impl<'a, T: DecodeRef<'a>> DecodeRef<'a> for Option<T> {
+    const MIN_SIZE: usize = 1;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        match reader.u8()? {
+            0 => Ok(None),
+            1 => Ok(Some(T::decode_ref(reader)?)),
+            tag => Err(DecodeError::Malformed {
+                kind: PayloadKind::Message,
+                reason: format!("unknown option tag {}", tag),
+            }.into()),
+        }
+    }
+}
+
+impl<'a, T: DecodeRef<'a>> DecodeRef<'a> for Vec<T> {
+    const MIN_SIZE: usize = 8;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        reader.seq()
+    }
+}
+
+impl<'a> DecodeRef<'a> for SignatureType {
+    const MIN_SIZE: usize = 1;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        match reader.u8()? {
+            0 => Ok(SignatureType::Ed25519),
+            1 => Ok(SignatureType::Secp256k1),
+            2 => Ok(SignatureType::PostQuantum),
+            tag => Err(DecodeError::Malformed {
+                kind: PayloadKind::Transaction,
+                reason: format!("unknown signature type tag {}", tag),
+            }.into()),
+        }
+    }
+}
+
+impl<'a> DecodeRef<'a> for ProofType {
+    const MIN_SIZE: usize = 1;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        match reader.u8()? {
+            0 => Ok(ProofType::SP1),
+            1 => Ok(ProofType::Risc0),
+            2 => Ok(ProofType::Plonky3),
+            3 => Ok(ProofType::Deferred),
+            tag => Err(DecodeError::Malformed {
+                kind: PayloadKind::Proof,
+                reason: format!("unknown proof type tag {}", tag),
+            }.into()),
+        }
+    }
+}
+
+impl<'a> DecodeRef<'a> for BlockHeader {
+    const MIN_SIZE: usize = 32 * 4 + 8 * 4 + 20 + 8 * 3;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(BlockHeader {
+            previous_hash: BlockHash(reader.array()?),
+            merkle_root: BlockHash(reader.array()?),
+            prev_state_root: BlockHash(reader.array()?),
+            state_root: BlockHash(reader.array()?),
+            timestamp: reader.u64()?,
+            block_number: reader.u64()?,
+            gas_limit: reader.u64()?,
+            gas_used: reader.u64()?,
+            producer: Address(reader.array()?),
+            extra_data: reader.var_bytes()?.to_vec(),
+            randomness: RandomnessProof {
+                output: reader.var_bytes()?.to_vec(),
+                proof: reader.var_bytes()?.to_vec(),
+            },
+        })
+    }
+}
+
+impl<'a> DecodeRef<'a> for ZkProof {
+    const MIN_SIZE: usize = 8 * 3 + 1;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        let start = reader.pos;
+        let proof = ZkProof {
+            proof_data: reader.var_bytes()?.to_vec(),
+            public_inputs: reader.var_bytes()?.to_vec(),
+            verification_key: reader.var_bytes()?.to_vec(),
+            proof_type: ProofType::decode_ref(reader)?,
+        };
+        reader.check_size(PayloadKind::Proof, start)?;
+        Ok(proof)
+    }
+}
+
+impl<'a> DecodeRef<'a> for ValidatorSignature {
+    const MIN_SIZE: usize = 20 + 8 + 8 + 1;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(ValidatorSignature {
+            validator_address: Address(reader.array()?),
+            stake_weight: reader.u64()?,
+            signature: reader.var_bytes()?.to_vec(),
+            sig_type: SignatureType::decode_ref(reader)?,
+        })
+    }
+}
+
+impl<'a> DecodeRef<'a> for AggregateSignature {
+    const MIN_SIZE: usize = 8 * 2;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(AggregateSignature {
+            signers: reader.var_bytes()?.to_vec(),
+            signature: reader.var_bytes()?.to_vec(),
+        })
+    }
+}
+
+impl<'a> DecodeRef<'a> for ProtocolRule {
+    const MIN_SIZE: usize = 4 + 8 + ZkProof::MIN_SIZE + 8;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(ProtocolRule {
+            rule_id: reader.u32()?,
+            rule_data: reader.var_bytes()?.to_vec(),
+            validity_proof: reader.nested(ZkProof::decode_ref)?,
+            activation_epoch: reader.u64()?,
+        })
+    }
+}
+
+impl<'a> DecodeRef<'a> for Account {
+    const MIN_SIZE: usize = 8 * 4;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        let balance = reader.u64()?;
+        let nonce = reader.u64()?;
+        let code = reader.var_bytes()?.to_vec();
+        let storage = reader.nested(|reader| {
+            let slots = reader.count(64)?;
+            let mut storage = HashMap::with_capacity(slots);
+            let mut previous: Option<[u8; 32]> = None;
+            for _ in 0..slots {
+                let key: [u8; 32] = reader.array()?;
+                // Canonical maps are sorted, which also rules out duplicate keys
+                if previous.is_some_and(|previous| previous >= key) {
+                    return Err(DecodeError::Malformed {
+                        kind: PayloadKind::Message,
+                        reason: "storage slots out of order".to_string(),
+                    }.into());
+                }
+                previous = Some(key);
+                storage.insert(key, reader.array()?);
+            }
+            Ok(storage)
+        })?;
+        Ok(Account { balance, nonce, code, storage })
+    }
+}
+
+impl<'a> DecodeRef<'a> for AccessListItem {
+    const MIN_SIZE: usize = 20 + 8;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(AccessListItem {
+            address: Address(reader.array()?),
+            storage_keys: reader.nested(|reader| {
+                let count = reader.count(32)?;
+                (0..count).map(|_| reader.array()).collect()
+            })?,
+        })
+    }
+}
+
+/// A transaction whose calldata and signature borrow from the input buffer
+#[derive(Debug, Clone)]
+pub struct TransactionRef<'a> {
+    pub from: Address,
+    pub to: Address,
+    pub value: u64,
+    pub data: &'a [u8],
+    pub gas_limit: u64,
+    pub gas_price: u64,
+    pub nonce: u64,
+    pub chain_id: u64,
+    pub access_list: Option<Vec<AccessListItem>>,
+    pub signature: &'a [u8],
+    pub sig_type: SignatureType,
+    /// The whole encoded transaction
+    raw: &'a [u8],
+}
+
+impl<'a> TransactionRef<'a> {
+    /// Encoded bytes this transaction was decoded from
+    pub fn raw(&self) -> &'a [u8] {
+        self.raw
+    }
+
+    /// Same as [`Transaction::hash`], computed over the borrowed bytes
+    pub fn hash(&self) -> BlockHash {
+        BlockHash(keccak256_hash(self.raw))
+    }
+
+    pub fn to_transaction(&self) -> Transaction {
+        Transaction {
+            from: self.from,
+            to: self.to,
+            value: self.value,
+            data: self.data.to_vec(),
+            gas_limit: self.gas_limit,
+            gas_price: self.gas_price,
+            nonce: self.nonce,
+            chain_id: self.chain_id,
+            access_list: self.access_list.clone(),
+            signature: self.signature.to_vec(),
+            sig_type: self.sig_type.clone(),
+        }
+    }
+}
+
+impl<'a> DecodeRef<'a> for TransactionRef<'a> {
+    const MIN_SIZE: usize = 20 * 2 + 8 * 7 + 1 + 1;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        let start = reader.pos;
+        let mut tx = TransactionRef {
+            from: Address(reader.array()?),
+            to: Address(reader.array()?),
+            value: reader.u64()?,
+            data: reader.var_bytes()?,
+            gas_limit: reader.u64()?,
+            gas_price: reader.u64()?,
+            nonce: reader.u64()?,
+            chain_id: reader.u64()?,
+            access_list: Option::decode_ref(reader)?,
+            signature: reader.var_bytes()?,
+            sig_type: SignatureType::decode_ref(reader)?,
+            raw: &[],
+        };
+        reader.check_size(PayloadKind::Transaction, start)?;
+        tx.raw = &reader.buf[start..reader.pos];
+        Ok(tx)
+    }
+}
+
+impl<'a> DecodeRef<'a> for Transaction {
+    const MIN_SIZE: usize = <TransactionRef<'a> as DecodeRef<'a>>::MIN_SIZE;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(TransactionRef::decode_ref(reader)?.to_transaction())
+    }
+}
+
+/// A block whose transactions borrow from the input buffer
+#[derive(Debug, Clone)]
+pub struct BlockRef<'a> {
+    pub header: BlockHeader,
+    pub transactions: Vec<TransactionRef<'a>>,
+    pub validator_signatures: Vec<ValidatorSignature>,
+    pub recursive_proof: ZkProof,
+    pub protocol_updates: Vec<ProtocolRule>,
+    pub proof_signature: &'a [u8],
+    pub aggregate_signature: Option<AggregateSignature>,
+}
+
+impl<'a> BlockRef<'a> {
+    pub fn to_block(&self) -> Block {
+        Block {
+            header: self.header.clone(),
+            transactions: self.transactions.iter().map(TransactionRef::to_transaction).collect(),
+            validator_signatures: self.validator_signatures.clone(),
+            recursive_proof: self.recursive_proof.clone(),
+            protocol_updates: self.protocol_updates.clone(),
+            proof_signature: self.proof_signature.to_vec(),
+            aggregate_signature: self.aggregate_signature.clone(),
+        }
+    }
+}
+
+impl<'a> DecodeRef<'a> for BlockRef<'a> {
+    const MIN_SIZE: usize = BlockHeader::MIN_SIZE + 8 * 4 + ZkProof::MIN_SIZE + 1;
+
+    fn decode_ref(reader: &mut Reader<'a>) -> Result<Self> {
+        Ok(BlockRef {
+            header: BlockHeader::decode_ref(reader)?,
+            transactions: reader.seq()?,
+            validator_signatures: reader.seq()?,
+            recursive_proof: ZkProof::decode_ref(reader)?,
+            protocol_updates: reader.seq()?,
+            proof_signature: reader.var_bytes()?,
+            aggregate_signature: Option::decode_ref(reader)?,
+        })
+    }
+}
+
+pub fn decode_transaction_ref<'a>(bytes: &'a [u8], limits: &DecodeLimits) -> Result<TransactionRef<'a>> {
+    decode_ref(PayloadKind::Transaction, bytes, limits)
+}
+
+pub fn decode_block_ref<'a>(bytes: &'a [u8], limits: &DecodeLimits) -> Result<BlockRef<'a>> {
+    decode_ref(PayloadKind::Block, bytes, limits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::serialization::canonical::canonical_bytes;
+    use crate::serialization::framing::error_offset;
+
+    fn sample_block() -> Block {
+        let transactions = (0..3u8)
+            .map(|i| {
+                let mut tx = Transaction::new(Address::new(i), Address::new(i + 1), 10 * u64::from(i), u64::from(i));
+                tx.data = vec![i; 100];
+                if i == 1 {
+                    tx.access_list = Some(vec![AccessListItem { address: Address::new(9), storage_keys: vec![[i; 32]] }]);
+                }
+                tx
+            })
+            .collect();
+        Block {
+            header: BlockHeader {
+                previous_hash: BlockHash([1; 32]),
+                merkle_root: BlockHash::zero(),
+                prev_state_root: BlockHash::zero(),
+                state_root: BlockHash([2; 32]),
+                timestamp: 1_700_000_000,
+                block_number: 9,
+                gas_limit: 30_000_000,
+                gas_used: 63_000,
+                producer: Address::new(7),
+                extra_data: b"zk-sac".to_vec(),
+                randomness: RandomnessProof::default(),
+            },
+            transactions,
+            validator_signatures: vec![ValidatorSignature {
+                validator_address: Address::new(7),
+                stake_weight: 32,
+                signature: vec![5; 64],
+                sig_type: SignatureType::Ed25519,
+            }],
+            recursive_proof: ZkProof {
+                proof_data: vec![0; 32],
+                public_inputs: vec![],
+                verification_key: vec![],
+                proof_type: ProofType::Risc0,
+            },
+            protocol_updates: vec![],
+            proof_signature: vec![6; 64],
+            aggregate_signature: Some(AggregateSignature { signers: vec![0b101], signature: vec![7; 96] }),
+        }
+    }
+
+    #[test]
+    fn test_block_roundtrip_borrows_transaction_bodies() {
+        let block = sample_block();
+        let bytes = canonical_bytes(&block);
+        let decoded = decode_block_ref(&bytes, &DecodeLimits::default()).unwrap();
+
+        let range = bytes.as_ptr_range();
+        for (borrowed, original) in decoded.transactions.iter().zip(&block.transactions) {
+            assert!(range.contains(&borrowed.data.as_ptr()));
+            assert_eq!(borrowed.data, original.data.as_slice());
+            assert_eq!(borrowed.hash(), original.hash());
+        }
+        assert_eq!(canonical_bytes(&decoded.to_block()), bytes);
+    }
+
+    #[test]
+    fn test_rejects_truncated_and_trailing_input() {
+        let bytes = canonical_bytes(&sample_block());
+        assert!(decode_block_ref(&bytes[..bytes.len() - 1], &DecodeLimits::default()).is_err());
+
+        let mut padded = bytes.clone();
+        padded.push(0);
+        assert!(decode_block_ref(&padded, &DecodeLimits::default()).is_err());
+
+        // A huge transaction count must fail before anything is allocated for it
+        let header_len = canonical_bytes(&sample_block().header).len();
+        let mut forged = canonical_bytes(&sample_block().header);
+        forged.extend_from_slice(&u64::MAX.to_le_bytes());
+        let err = decode_block_ref(&forged, &DecodeLimits::default()).unwrap_err();
+        assert_eq!(error_offset(&err), Some(header_len + 8));
+    }
+
+    #[test]
+    fn test_every_prefix_fails_cleanly() {
+        let bytes = canonical_bytes(&sample_block());
+        for end in 0..bytes.len() {
+            let err = decode_block_ref(&bytes[..end], &DecodeLimits::default()).unwrap_err();
+            assert!(error_offset(&err).is_some_and(|offset| offset <= end));
+        }
+    }
+
+    #[test]
+    fn test_limits_apply_inside_the_block() {
+        let bytes = canonical_bytes(&sample_block());
+
+        let small_txs = DecodeLimits { max_transaction_bytes: 64, ..DecodeLimits::default() };
+        let err = decode_block_ref(&bytes, &small_txs).unwrap_err();
+        assert!(matches!(err.downcast_ref::<DecodeError>(),
+                         Some(DecodeError::Oversize { kind: PayloadKind::Transaction, .. })));
+
+        let flat = DecodeLimits { max_depth: 0, ..DecodeLimits::default() };
+        let err = decode_block_ref(&bytes, &flat).unwrap_err();
+        assert!(matches!(err.downcast_ref::<DecodeError>(), Some(DecodeError::TooDeep { .. })));
+
+        let tiny_blocks = DecodeLimits { max_block_bytes: 100, ..DecodeLimits::default() };
+        assert!(decode_block_ref(&bytes, &tiny_blocks).is_err());
+    }
+}
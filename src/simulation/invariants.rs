@@ -0,0 +1,176 @@
+//! Safety and liveness checks run against every simulation step
+
+use crate::types::{Address, Block, BlockHash};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::Duration;
+
+use super::NodeId;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Violation {
+    /// Two nodes finalized different blocks at the same height
+    ConflictingFinalized { height: u64, nodes: (NodeId, NodeId), hashes: (BlockHash, BlockHash) },
+    /// The finality gadgets of two nodes finalized different blocks at the same height
+    ConflictingGadgetFinality { height: u64, nodes: (NodeId, NodeId), hashes: (BlockHash, BlockHash) },
+    /// A producer signed two different blocks for the same height
+    Equivocation { height: u64, producer: Address },
+    /// No live node's chain grew for `stalled_for`
+    Stalled { since: Duration, stalled_for: Duration, height: u64 },
+}
+
+/// A height two nodes finalized differently: the nodes and their hashes
+type Conflict = (u64, (NodeId, NodeId), (BlockHash, BlockHash));
+
+/// First final hash seen at each height, checked against what other nodes finalize
+#[derive(Debug, Default)]
+struct FinalPrefixes {
+    /// First final hash seen at each height, and the node that finalized it
+    finalized: BTreeMap<u64, (NodeId, BlockHash)>,
+    /// Final prefix length already checked per node; final prefixes only grow
+    checked: HashMap<NodeId, usize>,
+}
+
+impl FinalPrefixes {
+    /// Record the first `final_len` blocks of `chain` as final on `node`,
+    /// returning the heights where another node finalized a different block
+    fn observe(&mut self, node: NodeId, chain: &[Block], final_len: usize) -> Vec<Conflict> {
+        let final_len = final_len.min(chain.len());
+        let checked = self.checked.entry(node).or_default();
+        let start = (*checked).min(final_len);
+        *checked = final_len;
+        let mut conflicts = Vec::new();
+        for block in &chain[start..final_len] {
+            let height = block.header.block_number;
+            let hash = block.header.hash();
+            match self.finalized.get(&height) {
+                Some(&(other, other_hash)) if other_hash != hash => {
+                    conflicts.push((height, (other, node), (other_hash, hash)));
+                }
+                Some(_) => {}
+                None => {
+                    self.finalized.insert(height, (node, hash));
+                }
+            }
+        }
+        conflicts
+    }
+}
+
+/// No two nodes finalize conflicting blocks. A block is final once
+/// `finality_depth` blocks have been built on it; separately, the blocks
+/// each node's finality gadget finalized by attestations must agree.
+#[derive(Debug)]
+pub struct SafetyChecker {
+    finality_depth: u64,
+    by_depth: FinalPrefixes,
+    by_gadget: FinalPrefixes,
+    produced: HashMap<(u64, Address), BlockHash>,
+    violations: Vec<Violation>,
+}
+
+impl SafetyChecker {
+    pub fn new(finality_depth: u64) -> Self {
+        Self {
+            finality_depth,
+            by_depth: FinalPrefixes::default(),
+            by_gadget: FinalPrefixes::default(),
+            produced: HashMap::new(),
+            violations: Vec::new(),
+        }
+    }
+
+    /// Record a block as it leaves its producer
+    pub fn observe_produced(&mut self, block: &Block) {
+        let key = (block.header.block_number, block.header.producer);
+        let hash = block.header.hash();
+        match self.produced.get(&key) {
+            Some(existing) if *existing != hash => {
+                let violation = Violation::Equivocation { height: key.0, producer: key.1 };
+                if !self.violations.contains(&violation) {
+                    self.violations.push(violation);
+                }
+            }
+            Some(_) => {}
+            None => {
+                self.produced.insert(key, hash);
+            }
+        }
+    }
+
+    /// Check the finalized prefix of `node`'s chain against every other
+    /// node's, both by depth and up to `gadget_finalized`, the height its
+    /// finality gadget reached
+    pub fn observe_chain(&mut self, node: NodeId, chain: &[Block], gadget_finalized: u64) {
+        let final_len = (chain.len() as u64).saturating_sub(self.finality_depth) as usize;
+        for (height, nodes, hashes) in self.by_depth.observe(node, chain, final_len) {
+            self.push_once(Violation::ConflictingFinalized { height, nodes, hashes });
+        }
+        let gadget_len = chain.iter().take_while(|block| block.header.block_number <= gadget_finalized).count();
+        for (height, nodes, hashes) in self.by_gadget.observe(node, chain, gadget_len) {
+            self.push_once(Violation::ConflictingGadgetFinality { height, nodes, hashes });
+        }
+    }
+
+    /// Record `violation` unless one of its kind was already recorded at its height
+    fn push_once(&mut self, violation: Violation) {
+        let already = self.violations.iter().any(|recorded| match (recorded, &violation) {
+            (Violation::ConflictingFinalized { height: a, .. }, Violation::ConflictingFinalized { height: b, .. })
+            | (Violation::ConflictingGadgetFinality { height: a, .. }, Violation::ConflictingGadgetFinality { height: b, .. }) => a == b,
+            _ => false,
+        });
+        if !already {
+            self.violations.push(violation);
+        }
+    }
+
+    pub fn finalized_height(&self) -> u64 {
+        self.by_depth.finalized.keys().next_back().copied().unwrap_or(0)
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+}
+
+/// The best chain among live nodes grows at least once every `window`
+#[derive(Debug)]
+pub struct LivenessChecker {
+    window: Duration,
+    height: u64,
+    last_growth: Duration,
+    violations: Vec<Violation>,
+}
+
+impl LivenessChecker {
+    pub fn new(window: Duration) -> Self {
+        Self { window, height: 0, last_growth: Duration::ZERO, violations: Vec::new() }
+    }
+
+    pub fn sample(&mut self, now: Duration, height: u64) {
+        if height > self.height {
+            self.height = height;
+            self.last_growth = now;
+            return;
+        }
+        let stalled_for = now.saturating_sub(self.last_growth);
+        if stalled_for < self.window {
+            return;
+        }
+        // One violation per stall, extended while it lasts
+        match self.violations.last_mut() {
+            Some(Violation::Stalled { since, stalled_for: recorded, .. }) if *since == self.last_growth => {
+                *recorded = stalled_for;
+            }
+            _ => self.violations.push(Violation::Stalled { since: self.last_growth, stalled_for, height }),
+        }
+    }
+
+    pub fn height(&self) -> u64 {
+        self.height
+    }
+
+    pub fn violations(&self) -> &[Violation] {
+        &self.violations
+    }
+}
@@ -0,0 +1,650 @@
+//! Deterministic multi-node consensus simulation
+//!
+//! A [`Simulation`] runs one consensus engine per validator on a virtual
+//! clock. Slot ticks and message deliveries are events in a single queue, and
+//! network jitter and message loss come from a seeded RNG, so a run is fully determined by
+//! its [`SimulationConfig`] and fault schedule. Faults (crashes, partitions,
+//! slow proofs, equivocation and other [`Byzantine`] behaviour) are scripted
+//! at virtual times, and the [`invariants`] checkers are updated after every
+//! event. Nodes import blocks through the engine's fork choice, so a split
+//! over an equivocating producer's blocks heals, and attest every block they
+//! apply, so their finality gadgets run. Honest nodes report the offenses
+//! they see as slashing evidence.
+
+pub mod invariants;
+
+pub use invariants::{LivenessChecker, SafetyChecker, Violation};
+
+use crate::consensus::engine::{ConsensusEngine, ZkSacConsensusEngine};
+use crate::consensus::error::ConsensusError;
+use crate::consensus::finality::Attestation;
+use crate::crypto::keystore::KeyPair;
+use crate::node::config::{GenesisAccount, GenesisConfig, GenesisValidator};
+use crate::types::*;
+use anyhow::{Result, anyhow, bail};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use serde::Serialize;
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use std::time::Duration;
+use tracing::debug;
+
+pub type NodeId = usize;
+
+/// Most blocks sent in reply to one catch-up request
+const MAX_BLOCKS_PER_RESPONSE: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct SimulationConfig {
+    pub validators: usize,
+    pub block_time: Duration,
+    /// Base one-way message latency
+    pub latency: Duration,
+    /// Uniform random extra latency, up to this much
+    pub jitter: Duration,
+    /// Chance that any one message is lost, from 0 to 1
+    pub drop_rate: f64,
+    pub seed: u64,
+    /// Blocks built on top of a block before it counts as final
+    pub finality_depth: u64,
+    /// Longest the chain may go without growing before liveness is violated
+    pub liveness_window: Duration,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            validators: 4,
+            block_time: Duration::from_secs(4),
+            latency: Duration::from_millis(100),
+            jitter: Duration::from_millis(50),
+            drop_rate: 0.0,
+            seed: 0,
+            finality_depth: 2,
+            liveness_window: Duration::from_secs(20),
+        }
+    }
+}
+
+impl SimulationConfig {
+    pub fn with_validators(mut self, validators: usize) -> Self {
+        self.validators = validators;
+        self
+    }
+
+    pub fn with_seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    pub fn with_latency(mut self, latency: Duration, jitter: Duration) -> Self {
+        self.latency = latency;
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn with_drop_rate(mut self, drop_rate: f64) -> Self {
+        self.drop_rate = drop_rate;
+        self
+    }
+
+    pub fn with_finality_depth(mut self, depth: u64) -> Self {
+        self.finality_depth = depth;
+        self
+    }
+
+    pub fn with_liveness_window(mut self, window: Duration) -> Self {
+        self.liveness_window = window;
+        self
+    }
+}
+
+/// A scripted fault, applied at a virtual time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub enum Fault {
+    /// Stop the node; it keeps its chain but neither sends nor receives
+    Crash(NodeId),
+    Restart(NodeId),
+    /// Only nodes in the same group can reach each other; unlisted nodes are isolated
+    Partition(Vec<Vec<NodeId>>),
+    Heal,
+    /// Blocks from `node` are published `delay` after their slot, as if proving were slow
+    DelayProofs { node: NodeId, delay: Duration },
+    /// `node` sends conflicting blocks to two halves of its peers whenever
+    /// it produces, each half receiving the other's block half a slot later
+    Equivocate(NodeId),
+    Byzantine { node: NodeId, behavior: Byzantine },
+}
+
+/// Misbehaviour a validator keeps up once set, besides equivocating
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Byzantine {
+    /// Publishes its blocks with the proof stripped, serving the full block
+    /// only to nodes that ask for it later
+    WithholdProofs,
+    /// Publishes its blocks with a made-up state root, serving the real
+    /// block only to nodes that ask for it later
+    InvalidStateRoot,
+    /// Sends its attestations this much later than honest nodes
+    DelayVotes(Duration),
+}
+
+#[derive(Debug, Clone)]
+enum Message {
+    /// Head height, announced every slot so lagging nodes notice and catch up
+    Status { height: u64 },
+    Block(Box<Block>),
+    RequestBlocks { from_height: u64 },
+    Blocks(Vec<Block>),
+    Votes(Vec<Attestation>),
+}
+
+#[derive(Debug)]
+enum Event {
+    Tick,
+    Deliver { from: NodeId, to: NodeId, message: Message },
+    /// A delayed block's proof is ready
+    Publish { node: NodeId, block: Box<Block> },
+    Fault(Fault),
+}
+
+#[derive(Debug)]
+struct Scheduled {
+    at: Duration,
+    seq: u64,
+    event: Event,
+}
+
+// Min-heap on (time, insertion order) so equal-time events run in the order scheduled
+impl Ord for Scheduled {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (other.at, other.seq).cmp(&(self.at, self.seq))
+    }
+}
+
+impl PartialOrd for Scheduled {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Scheduled {
+    fn eq(&self, other: &Self) -> bool {
+        (self.at, self.seq) == (other.at, other.seq)
+    }
+}
+
+impl Eq for Scheduled {}
+
+struct SimNode {
+    address: Address,
+    engine: ZkSacConsensusEngine,
+    crashed: bool,
+    equivocating: bool,
+    byzantine: Option<Byzantine>,
+    proof_delay: Duration,
+    /// A produced block is waiting on its delayed proof
+    proving: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SimulationStats {
+    pub blocks_produced: u64,
+    pub messages_sent: u64,
+    pub messages_dropped: u64,
+    pub blocks_rejected: u64,
+    /// Slashing evidence honest nodes submitted
+    pub offenses_reported: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SimulationReport {
+    pub elapsed: Duration,
+    /// Chain height of every node, by id
+    pub heights: Vec<u64>,
+    pub finalized_height: u64,
+    /// Height each node's finality gadget finalized, by id
+    pub gadget_finalized: Vec<u64>,
+    pub safety_violations: Vec<Violation>,
+    pub liveness_violations: Vec<Violation>,
+    pub stats: SimulationStats,
+}
+
+impl SimulationReport {
+    pub fn is_safe(&self) -> bool {
+        self.safety_violations.is_empty()
+    }
+
+    pub fn is_live(&self) -> bool {
+        self.liveness_violations.is_empty()
+    }
+}
+
+pub struct Simulation {
+    config: SimulationConfig,
+    now: Duration,
+    seq: u64,
+    queue: BinaryHeap<Scheduled>,
+    nodes: Vec<SimNode>,
+    /// Partition group of each node; `None` when the network is whole
+    groups: Option<Vec<usize>>,
+    rng: StdRng,
+    safety: SafetyChecker,
+    liveness: LivenessChecker,
+    stats: SimulationStats,
+}
+
+impl Simulation {
+    pub fn new(config: SimulationConfig) -> Result<Self> {
+        if config.validators == 0 {
+            bail!("A simulation needs at least one validator");
+        }
+        if !(0.0..=1.0).contains(&config.drop_rate) {
+            bail!("Drop rate {} is not a probability", config.drop_rate);
+        }
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let keys = (0..config.validators)
+            .map(|_| {
+                let mut secret = [0u8; 32];
+                rng.fill_bytes(&mut secret);
+                KeyPair::from_secret(SignatureType::Ed25519, &secret)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let genesis = GenesisConfig {
+            chain_id: None,
+            protocol: Some(ProtocolConfig { block_time: config.block_time, ..ProtocolConfig::default() }),
+            accounts: keys.iter()
+                .map(|key| GenesisAccount { address: key.address(), balance: 1_000_000 })
+                .collect(),
+            validators: keys.iter()
+                .map(|key| GenesisValidator { address: key.address(), stake: 32_000_000_000, public_key: key.public_key(), bls_public_key: Vec::new() })
+                .collect(),
+        };
+
+        let nodes = keys.into_iter()
+            .map(|key| {
+                let address = key.address();
+                let mut engine = ZkSacConsensusEngine::from_genesis(&genesis)?;
+                engine.add_validator_key(key)?;
+                Ok(SimNode {
+                    address,
+                    engine,
+                    crashed: false,
+                    equivocating: false,
+                    byzantine: None,
+                    proof_delay: Duration::ZERO,
+                    proving: false,
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut simulation = Self {
+            safety: SafetyChecker::new(config.finality_depth),
+            liveness: LivenessChecker::new(config.liveness_window),
+            config,
+            now: Duration::ZERO,
+            seq: 0,
+            queue: BinaryHeap::new(),
+            nodes,
+            groups: None,
+            rng,
+            stats: SimulationStats::default(),
+        };
+        let first_slot = simulation.config.block_time;
+        simulation.push(first_slot, Event::Tick);
+        Ok(simulation)
+    }
+
+    /// Apply `fault` at virtual time `at`
+    pub fn with_fault(mut self, at: Duration, fault: Fault) -> Self {
+        self.schedule(at, fault);
+        self
+    }
+
+    pub fn schedule(&mut self, at: Duration, fault: Fault) {
+        self.push(at, Event::Fault(fault));
+    }
+
+    pub fn now(&self) -> Duration {
+        self.now
+    }
+
+    pub fn chain(&self, node: NodeId) -> &[Block] {
+        &self.nodes[node].engine.blocks
+    }
+
+    /// Node selected to produce `block_number` on top of `node`'s chain
+    pub fn producer(&self, node: NodeId, block_number: u64) -> Result<NodeId> {
+        let address = self.nodes[node].engine.select_block_producer(block_number)?;
+        self.nodes.iter()
+            .position(|sim_node| sim_node.address == address)
+            .ok_or_else(|| anyhow!("Selected producer {:?} is not a simulated node", address))
+    }
+
+    pub fn height(&self, node: NodeId) -> u64 {
+        self.nodes[node].engine.blocks.len() as u64
+    }
+
+    /// Stake of `validator` in the validator set `observer` holds, 0 once removed
+    pub fn stake(&self, observer: NodeId, validator: NodeId) -> u64 {
+        let address = self.nodes[validator].address;
+        self.nodes[observer].engine.validator_set.validators.iter()
+            .find(|member| member.address == address)
+            .map_or(0, |member| member.stake)
+    }
+
+    /// Process every event up to `duration` past the current time
+    pub async fn run_for(&mut self, duration: Duration) -> Result<SimulationReport> {
+        let end = self.now + duration;
+        while let Some(next) = self.queue.peek() {
+            if next.at > end {
+                break;
+            }
+            let Scheduled { at, event, .. } = self.queue.pop().expect("peeked");
+            self.now = at;
+            self.handle(event).await?;
+            self.check_invariants();
+        }
+        self.now = end;
+        self.check_invariants();
+        Ok(self.report())
+    }
+
+    pub fn report(&self) -> SimulationReport {
+        SimulationReport {
+            elapsed: self.now,
+            heights: (0..self.nodes.len()).map(|node| self.height(node)).collect(),
+            finalized_height: self.safety.finalized_height(),
+            gadget_finalized: self.nodes.iter().map(|sim_node| sim_node.engine.finalized_height()).collect(),
+            safety_violations: self.safety.violations().to_vec(),
+            liveness_violations: self.liveness.violations().to_vec(),
+            stats: self.stats.clone(),
+        }
+    }
+
+    fn push(&mut self, at: Duration, event: Event) {
+        self.seq += 1;
+        self.queue.push(Scheduled { at, seq: self.seq, event });
+    }
+
+    async fn handle(&mut self, event: Event) -> Result<()> {
+        match event {
+            Event::Tick => {
+                let next_slot = self.now + self.config.block_time;
+                self.push(next_slot, Event::Tick);
+                for node in 0..self.nodes.len() {
+                    if !self.nodes[node].crashed {
+                        self.on_slot(node).await?;
+                        self.announce(node);
+                    }
+                }
+            }
+            Event::Deliver { from, to, message } => {
+                if self.nodes[to].crashed || !self.connected(from, to) {
+                    self.stats.messages_dropped += 1;
+                } else {
+                    self.on_message(from, to, message).await?;
+                }
+            }
+            Event::Publish { node, block } => self.publish(node, *block).await?,
+            Event::Fault(fault) => self.apply_fault(fault),
+        }
+        Ok(())
+    }
+
+    fn apply_fault(&mut self, fault: Fault) {
+        debug!("💥 {:?} at {:?}", fault, self.now);
+        match fault {
+            Fault::Crash(node) => self.nodes[node].crashed = true,
+            Fault::Restart(node) => self.nodes[node].crashed = false,
+            Fault::Partition(partition) => {
+                // Unlisted nodes each get a group of their own
+                let mut groups: Vec<usize> = (partition.len()..partition.len() + self.nodes.len()).collect();
+                for (group, members) in partition.iter().enumerate() {
+                    for &node in members {
+                        groups[node] = group;
+                    }
+                }
+                self.groups = Some(groups);
+            }
+            Fault::Heal => self.groups = None,
+            Fault::DelayProofs { node, delay } => self.nodes[node].proof_delay = delay,
+            Fault::Equivocate(node) => self.nodes[node].equivocating = true,
+            Fault::Byzantine { node, behavior } => self.nodes[node].byzantine = Some(behavior),
+        }
+    }
+
+    fn connected(&self, a: NodeId, b: NodeId) -> bool {
+        self.groups.as_ref().is_none_or(|groups| groups[a] == groups[b])
+    }
+
+    async fn on_slot(&mut self, node: NodeId) -> Result<()> {
+        let slot_time = self.now;
+        let sim_node = &mut self.nodes[node];
+        let next = sim_node.engine.blocks.len() as u64 + 1;
+        if sim_node.proving || sim_node.engine.select_block_producer(next)? != sim_node.address {
+            return Ok(());
+        }
+
+        let mut block = sim_node.engine.produce_block(sim_node.address).await?;
+        // Wall-clock timestamps would make runs irreproducible
+        block.header.timestamp = slot_time.as_secs();
+        block.validator_signatures.clear();
+        block.aggregate_signature = None;
+        sim_node.engine.collect_validator_signatures(&mut block)?;
+        self.stats.blocks_produced += 1;
+
+        let delay = sim_node.proof_delay;
+        if delay.is_zero() {
+            self.publish(node, block).await
+        } else {
+            // The block is neither applied nor announced until its proof is ready
+            sim_node.proving = true;
+            self.push(self.now + delay, Event::Publish { node, block: Box::new(block) });
+            Ok(())
+        }
+    }
+
+    /// Apply a produced block locally and send it to every peer
+    async fn publish(&mut self, node: NodeId, block: Block) -> Result<()> {
+        self.nodes[node].proving = false;
+        if self.nodes[node].crashed || !self.nodes[node].engine.validate_block(&block).await? {
+            return Ok(());
+        }
+        let peers: Vec<NodeId> = (0..self.nodes.len()).filter(|&peer| peer != node).collect();
+
+        if self.nodes[node].equivocating {
+            let mut conflicting = block.clone();
+            conflicting.header.extra_data = b"equivocation".to_vec();
+            conflicting.validator_signatures.clear();
+            conflicting.aggregate_signature = None;
+            self.nodes[node].engine.collect_validator_signatures(&mut conflicting)?;
+            self.safety.observe_produced(&block);
+            self.safety.observe_produced(&conflicting);
+            let late = self.config.block_time / 2;
+            for (index, &peer) in peers.iter().enumerate() {
+                let (first, second) = if index % 2 == 0 { (&block, &conflicting) } else { (&conflicting, &block) };
+                self.send(node, peer, Message::Block(Box::new(first.clone())));
+                self.send_after(node, peer, Message::Block(Box::new(second.clone())), late);
+            }
+        } else if let Some(behavior @ (Byzantine::WithholdProofs | Byzantine::InvalidStateRoot)) = self.nodes[node].byzantine {
+            let mut tampered = block.clone();
+            match behavior {
+                Byzantine::WithholdProofs => tampered.recursive_proof.proof_data.clear(),
+                _ => tampered.header.state_root = BlockHash::new([0xba; 32]),
+            }
+            tampered.validator_signatures.clear();
+            tampered.aggregate_signature = None;
+            self.nodes[node].engine.collect_validator_signatures(&mut tampered)?;
+            self.safety.observe_produced(&block);
+            for &peer in &peers {
+                self.send(node, peer, Message::Block(Box::new(tampered.clone())));
+            }
+        } else {
+            self.safety.observe_produced(&block);
+            for &peer in &peers {
+                self.send(node, peer, Message::Block(Box::new(block.clone())));
+            }
+        }
+
+        let block_number = block.header.block_number;
+        self.nodes[node].engine.apply_block(block).await?;
+        self.vote(node, block_number)
+    }
+
+    async fn on_message(&mut self, from: NodeId, to: NodeId, message: Message) -> Result<()> {
+        match message {
+            Message::Status { height } => {
+                let own = self.height(to);
+                if height > own {
+                    self.send(to, from, Message::RequestBlocks { from_height: own + 1 });
+                }
+            }
+            Message::Block(block) => {
+                let height = self.height(to);
+                let number = block.header.block_number;
+                if number > height + 1 {
+                    // Missed blocks; ask the sender for everything after our head
+                    self.send(to, from, Message::RequestBlocks { from_height: height + 1 });
+                } else {
+                    // A second block for a height is kept as a side branch,
+                    // which fork choice may switch to, and reported either way
+                    let accepted = self.try_apply(to, (*block).clone()).await?;
+                    if number <= height || !accepted {
+                        self.report_offense(to, &block);
+                    }
+                }
+            }
+            Message::RequestBlocks { from_height } => {
+                let blocks: Vec<Block> = self.nodes[to].engine.blocks.iter()
+                    .filter(|block| block.header.block_number >= from_height)
+                    .take(MAX_BLOCKS_PER_RESPONSE)
+                    .cloned()
+                    .collect();
+                if !blocks.is_empty() {
+                    self.send(to, from, Message::Blocks(blocks));
+                }
+            }
+            Message::Blocks(blocks) => {
+                for block in blocks {
+                    if block.header.block_number == self.height(to) + 1 && !self.try_apply(to, block).await? {
+                        break;
+                    }
+                }
+            }
+            Message::Votes(attestations) => {
+                for attestation in &attestations {
+                    if let Err(e) = self.nodes[to].engine.add_attestation(attestation) {
+                        debug!("🗳️ Node {} ignored a vote from node {}: {}", to, from, e);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Attest block `block_number` on `node` and send the votes to every peer
+    fn vote(&mut self, node: NodeId, block_number: u64) -> Result<()> {
+        let attestations = self.nodes[node].engine.attest(block_number)?;
+        let delay = match self.nodes[node].byzantine {
+            Some(Byzantine::DelayVotes(delay)) => delay,
+            _ => Duration::ZERO,
+        };
+        for peer in (0..self.nodes.len()).filter(|&peer| peer != node) {
+            self.send_after(node, peer, Message::Votes(attestations.clone()), delay);
+        }
+        Ok(())
+    }
+
+    /// Submit evidence of the offense `block` proves, if `node` is honest and sees one
+    fn report_offense(&mut self, node: NodeId, block: &Block) {
+        let sim_node = &mut self.nodes[node];
+        if sim_node.equivocating || sim_node.byzantine.is_some() {
+            return;
+        }
+        let Some(evidence) = sim_node.engine.detect_offense(block) else { return };
+        match sim_node.engine.report_offense(&evidence) {
+            Ok(_) => self.stats.offenses_reported += 1,
+            Err(e) => debug!("⚔️  Node {} did not report {:?}: {}", node, evidence.offense(), e),
+        }
+    }
+
+    fn announce(&mut self, node: NodeId) {
+        let height = self.height(node);
+        for peer in (0..self.nodes.len()).filter(|&peer| peer != node) {
+            self.send(node, peer, Message::Status { height });
+        }
+    }
+
+    /// Import `block` through the engine's fork choice and attest the blocks
+    /// it adds above the previous head; returns false if it was rejected
+    async fn try_apply(&mut self, node: NodeId, block: Block) -> Result<bool> {
+        let height = self.height(node);
+        match self.nodes[node].engine.on_block_received(block).await {
+            Ok(_) => {}
+            Err(e @ (ConsensusError::InvalidBlock(_) | ConsensusError::UnknownParent { .. } | ConsensusError::ConflictsWithFinalized(_))) => {
+                debug!("🚫 Node {} rejected a block: {}", node, e);
+                self.stats.blocks_rejected += 1;
+                return Ok(false);
+            }
+            Err(e) => return Err(e.into()),
+        }
+        // Heights attested before a reorg are not attested again
+        for block_number in height + 1..=self.height(node) {
+            self.vote(node, block_number)?;
+        }
+        Ok(true)
+    }
+
+    fn send(&mut self, from: NodeId, to: NodeId, message: Message) {
+        self.send_after(from, to, message, Duration::ZERO);
+    }
+
+    /// Send `message`, holding it back `delay` before the usual latency
+    fn send_after(&mut self, from: NodeId, to: NodeId, message: Message, delay: Duration) {
+        self.stats.messages_sent += 1;
+        // Only drawn when lossy, so lossless runs keep their seeds' schedules
+        if self.config.drop_rate > 0.0 && self.rng.gen_bool(self.config.drop_rate) {
+            self.stats.messages_dropped += 1;
+            return;
+        }
+        let jitter = if self.config.jitter.is_zero() {
+            Duration::ZERO
+        } else {
+            Duration::from_nanos(self.rng.gen_range(0..=self.config.jitter.as_nanos() as u64))
+        };
+        let at = self.now + delay + self.config.latency + jitter;
+        self.push(at, Event::Deliver { from, to, message });
+    }
+
+    fn check_invariants(&mut self) {
+        let mut best = 0;
+        for (node, sim_node) in self.nodes.iter().enumerate() {
+            self.safety.observe_chain(node, &sim_node.engine.blocks, sim_node.engine.finalized_height());
+            if !sim_node.crashed {
+                best = best.max(sim_node.engine.blocks.len() as u64);
+            }
+        }
+        self.liveness.sample(self.now, best);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn run(seed: u64) -> Vec<BlockHash> {
+        let mut simulation = Simulation::new(SimulationConfig::default().with_seed(seed)).unwrap();
+        simulation.run_for(Duration::from_secs(42)).await.unwrap();
+        simulation.chain(0).iter().map(|block| block.header.hash()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_same_seed_same_run() {
+        let first = run(7).await;
+        assert_eq!(first.len(), 10);
+        assert_eq!(first, run(7).await);
+        assert_ne!(first, run(8).await);
+    }
+}
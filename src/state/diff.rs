@@ -0,0 +1,292 @@
+//! State diffs and copy-on-write overlays
+//!
+//! Executing transactions does not copy the [`WorldState`]: they run on a
+//! [`StateOverlay`], which reads through to the state and keeps what they
+//! write in a [`StateDiff`] of the accounts and storage slots they touched.
+//! Applying the diff with [`WorldState::apply_diff`] commits them, and the
+//! diff [`StateDiff::undo`] returns reverts them again, which is what the
+//! engine keeps per unfinalized block to roll back a reorganization.
+//! Transactions that touch disjoint accounts run on forks of the block's
+//! overlay, see [`StateOverlay::fork`], whose diffs are absorbed back.
+
+use super::state_root_of;
+use crate::types::{Account, Address, BlockHash, WorldState};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+const ZERO: [u8; 32] = [0; 32];
+
+/// New contents of a touched account
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountDiff {
+    pub balance: u64,
+    pub nonce: u64,
+    /// New code, if it changed
+    pub code: Option<Vec<u8>>,
+    /// Whether the whole storage is cleared before `storage` applies
+    pub storage_cleared: bool,
+    /// Changed slots; a zero value clears the slot
+    pub storage: BTreeMap<[u8; 32], [u8; 32]>,
+}
+
+impl AccountDiff {
+    fn apply(&self, account: &mut Account) {
+        account.balance = self.balance;
+        account.nonce = self.nonce;
+        if let Some(code) = &self.code {
+            account.code = code.clone();
+        }
+        if self.storage_cleared {
+            account.storage.clear();
+        }
+        for (slot, value) in &self.storage {
+            if *value == ZERO {
+                account.storage.remove(slot);
+            } else {
+                account.storage.insert(*slot, *value);
+            }
+        }
+    }
+}
+
+/// Accounts and storage slots that executing transactions changed
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateDiff {
+    /// Touched accounts, `None` for removed ones
+    pub accounts: BTreeMap<Address, Option<AccountDiff>>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        self.accounts.is_empty()
+    }
+
+    /// Balance `address` has with this diff applied to `base`
+    pub fn balance(&self, base: &WorldState, address: &Address) -> u64 {
+        match self.accounts.get(address) {
+            Some(diff) => diff.as_ref().map_or(0, |diff| diff.balance),
+            None => base.accounts.get(address).map_or(0, |account| account.balance),
+        }
+    }
+
+    /// Root of `base` with this diff applied, copying only the touched accounts
+    pub fn state_root(&self, base: &WorldState) -> BlockHash {
+        let touched: Vec<(Address, Account)> = self.accounts.iter()
+            .filter_map(|(address, diff)| {
+                let mut account = base.accounts.get(address).cloned().unwrap_or_else(|| Account::new(0));
+                diff.as_ref()?.apply(&mut account);
+                Some((*address, account))
+            })
+            .collect();
+        let untouched = base.accounts.iter().filter(|(address, _)| !self.accounts.contains_key(address));
+        state_root_of(untouched.chain(touched.iter().map(|(address, account)| (address, account))), base.global_nonce)
+    }
+
+    /// The diff that reverts this one once applied to `base`
+    pub fn undo(&self, base: &WorldState) -> StateDiff {
+        let accounts = self.accounts.iter()
+            .map(|(address, diff)| {
+                let before = base.accounts.get(address).map(|account| {
+                    let (code_changed, storage_cleared) = match diff {
+                        Some(diff) => (diff.code.is_some(), diff.storage_cleared),
+                        None => (true, true),
+                    };
+                    let storage = match diff {
+                        Some(diff) if !storage_cleared => diff.storage.keys()
+                            .map(|slot| (*slot, account.storage.get(slot).copied().unwrap_or(ZERO)))
+                            .collect(),
+                        _ => account.storage.iter().map(|(slot, value)| (*slot, *value)).collect(),
+                    };
+                    AccountDiff {
+                        balance: account.balance,
+                        nonce: account.nonce,
+                        code: code_changed.then(|| account.code.clone()),
+                        storage_cleared,
+                        storage,
+                    }
+                });
+                (*address, before)
+            })
+            .collect();
+        StateDiff { accounts }
+    }
+}
+
+impl WorldState {
+    /// Write the accounts `diff` touched; the state root is left to the caller
+    pub fn apply_diff(&mut self, diff: &StateDiff) {
+        for (address, account_diff) in &diff.accounts {
+            match account_diff {
+                Some(account_diff) => account_diff.apply(self.accounts.entry(*address).or_insert_with(|| Account::new(0))),
+                None => {
+                    self.accounts.remove(address);
+                }
+            }
+        }
+    }
+}
+
+/// An account as a [`StateOverlay`] sees it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountView<'a> {
+    pub balance: u64,
+    pub nonce: u64,
+    pub code: &'a [u8],
+}
+
+/// Copy-on-write view of a state: reads fall through to the state until a
+/// write copies the account's balance and nonce into the diff, along with
+/// only the slots written
+#[derive(Debug, Clone)]
+pub struct StateOverlay<'a> {
+    base: &'a WorldState,
+    diff: StateDiff,
+}
+
+impl<'a> StateOverlay<'a> {
+    pub fn new(base: &'a WorldState) -> Self {
+        Self { base, diff: StateDiff::default() }
+    }
+
+    /// The state under the overlay
+    pub fn base(&self) -> &'a WorldState {
+        self.base
+    }
+
+    /// The account at `address`, or `None` if it has none
+    pub fn get(&self, address: &Address) -> Option<AccountView<'_>> {
+        let base = self.base.accounts.get(address);
+        match self.diff.accounts.get(address) {
+            Some(diff) => diff.as_ref().map(|diff| AccountView {
+                balance: diff.balance,
+                nonce: diff.nonce,
+                code: diff.code.as_deref().or(base.map(|account| account.code.as_slice())).unwrap_or(&[]),
+            }),
+            None => base.map(|account| AccountView { balance: account.balance, nonce: account.nonce, code: &account.code }),
+        }
+    }
+
+    /// Value of storage `slot` of `address`, zero if unset
+    pub fn storage(&self, address: &Address, slot: &[u8; 32]) -> [u8; 32] {
+        let base = || self.base.accounts.get(address).and_then(|account| account.storage.get(slot)).copied().unwrap_or(ZERO);
+        match self.diff.accounts.get(address) {
+            Some(Some(diff)) => match diff.storage.get(slot) {
+                Some(value) => *value,
+                None if diff.storage_cleared => ZERO,
+                None => base(),
+            },
+            Some(None) => ZERO,
+            None => base(),
+        }
+    }
+
+    /// The diff of `address` to write to, starting from its current balance
+    /// and nonce; an account is created if it has none
+    pub fn account_mut(&mut self, address: Address) -> &mut AccountDiff {
+        let base = self.base;
+        let entry = self.diff.accounts.entry(address).or_insert_with(|| {
+            Some(base.accounts.get(&address).map_or_else(AccountDiff::default, |account| AccountDiff {
+                balance: account.balance,
+                nonce: account.nonce,
+                ..AccountDiff::default()
+            }))
+        });
+        // Recreated after removal in this overlay, so nothing of the old account shows
+        entry.get_or_insert_with(|| AccountDiff { code: Some(Vec::new()), storage_cleared: true, ..AccountDiff::default() })
+    }
+
+    pub fn remove(&mut self, address: Address) {
+        self.diff.accounts.insert(address, None);
+    }
+
+    /// Whether `address` has no balance, nonce, code or storage
+    pub fn is_empty(&self, address: &Address) -> bool {
+        let Some(account) = self.get(address) else { return true };
+        if account.balance != 0 || account.nonce != 0 || !account.code.is_empty() {
+            return false;
+        }
+        let diff = self.diff.accounts.get(address).and_then(Option::as_ref);
+        let written = diff.is_some_and(|diff| diff.storage.values().any(|value| *value != ZERO));
+        let kept = match (self.base.accounts.get(address), diff) {
+            (Some(_), Some(diff)) if diff.storage_cleared => false,
+            (Some(account), diff) => account.storage.iter()
+                .any(|(slot, value)| *value != ZERO && !diff.is_some_and(|diff| diff.storage.contains_key(slot))),
+            (None, _) => false,
+        };
+        !written && !kept
+    }
+
+    /// An overlay on the same state that sees this one's changes to
+    /// `addresses` only, for a transaction that touches nothing else
+    pub fn fork(&self, addresses: impl IntoIterator<Item = Address>) -> StateOverlay<'a> {
+        let accounts = addresses.into_iter()
+            .filter_map(|address| Some((address, self.diff.accounts.get(&address)?.clone())))
+            .collect();
+        StateOverlay { base: self.base, diff: StateDiff { accounts } }
+    }
+
+    /// Take the accounts of `diff`, which a fork wrote, in place of ours
+    pub fn absorb(&mut self, diff: StateDiff) {
+        self.diff.accounts.extend(diff.accounts);
+    }
+
+    pub fn diff(&self) -> &StateDiff {
+        &self.diff
+    }
+
+    pub fn into_diff(self) -> StateDiff {
+        self.diff
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn slot(n: u8) -> [u8; 32] {
+        let mut slot = [0; 32];
+        slot[31] = n;
+        slot
+    }
+
+    #[test]
+    fn test_overlay_diff_applies_and_undoes() {
+        let mut state = WorldState::default();
+        let mut contract = Account::new(50);
+        contract.code = vec![1, 2, 3];
+        contract.storage.insert(slot(1), slot(10));
+        contract.storage.insert(slot(2), slot(20));
+        state.accounts.insert(Address::new(1), Account::new(100));
+        state.accounts.insert(Address::new(2), contract);
+        state.accounts.insert(Address::new(3), Account::new(7));
+        state.state_root = state.compute_state_root();
+        let original = state.clone();
+
+        let mut overlay = StateOverlay::new(&state);
+        overlay.account_mut(Address::new(1)).balance -= 40;
+        overlay.account_mut(Address::new(4)).balance += 40;
+        overlay.account_mut(Address::new(2)).storage.insert(slot(1), ZERO);
+        overlay.account_mut(Address::new(2)).storage.insert(slot(3), slot(30));
+        overlay.remove(Address::new(3));
+        assert_eq!(overlay.storage(&Address::new(2), &slot(1)), ZERO);
+        assert_eq!(overlay.storage(&Address::new(2), &slot(2)), slot(20));
+        assert_eq!(overlay.get(&Address::new(2)).unwrap().code, &[1, 2, 3]);
+        assert!(overlay.get(&Address::new(3)).is_none());
+        assert!(!overlay.is_empty(&Address::new(2)));
+        let diff = overlay.into_diff();
+        // Untouched accounts stay out of the diff
+        assert_eq!(diff.accounts.len(), 4);
+
+        let root = diff.state_root(&state);
+        let undo = diff.undo(&state);
+        state.apply_diff(&diff);
+        assert_eq!(state.compute_state_root(), root);
+        assert_eq!(state.accounts[&Address::new(4)].balance, 40);
+        assert_eq!(state.accounts[&Address::new(2)].storage.len(), 2);
+        assert!(!state.accounts.contains_key(&Address::new(3)));
+
+        state.apply_diff(&undo);
+        assert_eq!(state.compute_state_root(), original.state_root);
+        assert_eq!(state.accounts[&Address::new(2)].storage, original.accounts[&Address::new(2)].storage);
+    }
+}
@@ -0,0 +1,236 @@
+//! Account commitments and inclusion proofs
+//!
+//! The state root is a [`SparseMerkleTrie`] keyed by the Keccak-256 hash of
+//! each account's address, hashed together with the global nonce. A leaf
+//! commits to an account's balance, nonce, code hash and storage root, so
+//! proving one account does not require its code or storage. Each account's
+//! storage is itself a sparse Merkle trie keyed by the hash of the slot, so a
+//! [`StorageProof`] chains a slot proof onto an [`AccountProof`].
+//!
+//! A [`StateWitness`] is the multi-account counterpart the state transition
+//! guest takes: the paths to every account a block touches, from which it
+//! checks the state root before the block and computes the one after it.
+//!
+//! Blocks change the state through a [`StateDiff`], see [`diff`].
+
+pub mod diff;
+pub mod snapshot;
+pub mod trie;
+
+pub use diff::{AccountDiff, AccountView, StateDiff, StateOverlay};
+pub use snapshot::{SnapshotChunk, SnapshotError, SnapshotManifest, StateSnapshot};
+pub use trie::{SparseMerkleTrie, TrieProof};
+
+use crate::crypto::hash::keccak256_hash;
+use crate::types::{Account, Address, BlockHash, WorldState};
+use crate::zkvm::programs::guest_program::{StateWitness, WitnessAccount, WitnessNode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use thiserror::Error;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum StateProofError {
+    #[error("proof for {0:?} does not match the state root")]
+    RootMismatch(Address),
+    #[error("slot proof does not match the storage root of {0:?}")]
+    StorageMismatch(Address),
+}
+
+/// What a state leaf commits to for one account
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountLeaf {
+    pub balance: u64,
+    pub nonce: u64,
+    pub code_hash: BlockHash,
+    pub storage_root: BlockHash,
+}
+
+impl AccountLeaf {
+    pub fn from_account(account: &Account) -> Self {
+        Self {
+            balance: account.balance,
+            nonce: account.nonce,
+            code_hash: BlockHash(keccak256_hash(&account.code)),
+            storage_root: storage_root(&account.storage),
+        }
+    }
+
+    /// Value stored in the state trie for this account
+    pub fn hash(&self) -> [u8; 32] {
+        let mut bytes = Vec::with_capacity(16 + 64);
+        bytes.extend_from_slice(&self.balance.to_le_bytes());
+        bytes.extend_from_slice(&self.nonce.to_le_bytes());
+        bytes.extend_from_slice(&self.code_hash.0);
+        bytes.extend_from_slice(&self.storage_root.0);
+        keccak256_hash(&bytes)
+    }
+}
+
+/// Proof that an account is part of a state root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccountProof {
+    pub address: Address,
+    pub leaf: AccountLeaf,
+    pub proof: TrieProof,
+    pub global_nonce: u64,
+}
+
+impl AccountProof {
+    /// State root implied by this proof
+    pub fn compute_root(&self) -> BlockHash {
+        let accounts_root = self.proof.compute_root(&account_key(&self.address), &self.leaf.hash());
+        combine(&accounts_root, self.global_nonce)
+    }
+
+    pub fn verify(&self, state_root: &BlockHash) -> Result<(), StateProofError> {
+        if self.compute_root() == *state_root {
+            Ok(())
+        } else {
+            Err(StateProofError::RootMismatch(self.address))
+        }
+    }
+}
+
+/// Proof that a storage slot of an account holds `value` in a state root
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StorageProof {
+    pub account: AccountProof,
+    pub slot: [u8; 32],
+    pub value: [u8; 32],
+    pub proof: TrieProof,
+}
+
+impl StorageProof {
+    pub fn verify(&self, state_root: &BlockHash) -> Result<(), StateProofError> {
+        self.account.verify(state_root)?;
+        if self.proof.compute_root(&keccak256_hash(&self.slot), &self.value) == self.account.leaf.storage_root.0 {
+            Ok(())
+        } else {
+            Err(StateProofError::StorageMismatch(self.account.address))
+        }
+    }
+}
+
+/// Trie key of an account
+pub fn account_key(address: &Address) -> [u8; 32] {
+    keccak256_hash(&address.0)
+}
+
+/// Root of an account's storage; zero slots count as absent
+pub fn storage_root(storage: &HashMap<[u8; 32], [u8; 32]>) -> BlockHash {
+    BlockHash(storage_trie(storage).root())
+}
+
+fn storage_trie(storage: &HashMap<[u8; 32], [u8; 32]>) -> SparseMerkleTrie {
+    storage.iter()
+        .filter(|(_, value)| **value != [0; 32])
+        .map(|(slot, value)| (keccak256_hash(slot), *value))
+        .collect()
+}
+
+fn combine(accounts_root: &[u8; 32], global_nonce: u64) -> BlockHash {
+    let mut bytes = accounts_root.to_vec();
+    bytes.extend_from_slice(&global_nonce.to_le_bytes());
+    BlockHash(keccak256_hash(&bytes))
+}
+
+fn account_trie<'a>(accounts: impl Iterator<Item = (&'a Address, &'a Account)>) -> SparseMerkleTrie {
+    accounts
+        .map(|(address, account)| (account_key(address), AccountLeaf::from_account(account).hash()))
+        .collect()
+}
+
+pub fn state_root(state: &WorldState) -> BlockHash {
+    state_root_of(state.accounts.iter(), state.global_nonce)
+}
+
+/// Root of a state holding `accounts`
+fn state_root_of<'a>(accounts: impl Iterator<Item = (&'a Address, &'a Account)>, global_nonce: u64) -> BlockHash {
+    combine(&account_trie(accounts).root(), global_nonce)
+}
+
+/// Inclusion proof for `address`, or `None` if it has no account
+pub fn account_proof(state: &WorldState, address: &Address) -> Option<AccountProof> {
+    let account = state.accounts.get(address)?;
+    Some(AccountProof {
+        address: *address,
+        leaf: AccountLeaf::from_account(account),
+        proof: account_trie(state.accounts.iter()).proof(&account_key(address))?,
+        global_nonce: state.global_nonce,
+    })
+}
+
+/// Inclusion proof for `slot` of `address`, or `None` if the slot is zero
+pub fn storage_proof(state: &WorldState, address: &Address, slot: &[u8; 32]) -> Option<StorageProof> {
+    let account = state.accounts.get(address)?;
+    let value = *account.storage.get(slot).filter(|value| **value != [0; 32])?;
+    Some(StorageProof {
+        account: account_proof(state, address)?,
+        slot: *slot,
+        value,
+        proof: storage_trie(&account.storage).proof(&keccak256_hash(slot))?,
+    })
+}
+
+/// Witness of the accounts at `addresses`, present or not, for the state
+/// transition guest
+pub fn state_witness<'a>(state: &WorldState, addresses: impl IntoIterator<Item = &'a Address>) -> StateWitness {
+    let addresses: BTreeMap<[u8; 32], Address> = addresses.into_iter()
+        .map(|address| (account_key(address), *address))
+        .collect();
+    let keys = addresses.keys().copied().collect();
+    let accounts = account_trie(state.accounts.iter()).witness(&keys, |key| {
+        let address = addresses[key];
+        let leaf = AccountLeaf::from_account(&state.accounts[&address]);
+        WitnessNode::Account(WitnessAccount {
+            address: address.0,
+            balance: leaf.balance,
+            nonce: leaf.nonce,
+            code_hash: leaf.code_hash.0,
+            storage_root: leaf.storage_root.0,
+        })
+    });
+    StateWitness { global_nonce: state.global_nonce, accounts }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_every_account_proves_against_the_root() {
+        let mut state = WorldState { global_nonce: 3, ..WorldState::default() };
+        for id in 1..=7u8 {
+            state.accounts.insert(Address::new(id), Account::new(u64::from(id) * 10));
+        }
+        let root = state.compute_state_root();
+
+        for id in 1..=7u8 {
+            let proof = account_proof(&state, &Address::new(id)).unwrap();
+            assert_eq!(proof.leaf.balance, u64::from(id) * 10);
+            proof.verify(&root).unwrap();
+
+            let mut forged = proof.clone();
+            forged.leaf.balance += 1;
+            assert_eq!(forged.verify(&root), Err(StateProofError::RootMismatch(Address::new(id))));
+        }
+        assert!(account_proof(&state, &Address::new(9)).is_none());
+    }
+
+    #[test]
+    fn test_storage_is_committed_to_by_the_root() {
+        let mut state = WorldState::default();
+        state.accounts.insert(Address::new(1), Account::new(10));
+        let empty = state.compute_state_root();
+
+        let account = state.accounts.get_mut(&Address::new(1)).unwrap();
+        account.storage.insert([1; 32], [0; 32]);
+        assert_eq!(state.compute_state_root(), empty, "zero slots are absent");
+
+        let account = state.accounts.get_mut(&Address::new(1)).unwrap();
+        account.storage.insert([1; 32], [9; 32]);
+        let written = state.compute_state_root();
+        assert_ne!(written, empty);
+        account_proof(&state, &Address::new(1)).unwrap().verify(&written).unwrap();
+    }
+}
@@ -0,0 +1,192 @@
+//! State snapshots for bootstrapping
+//!
+//! A new node can start from the state after a block instead of replaying
+//! the chain. [`export`] splits the accounts, sorted by address, into
+//! encoded chunks that peers can serve one at a time. The
+//! [`SnapshotManifest`] lists each chunk's hash together with the block,
+//! state root and validator set of the height. A node that trusts the
+//! block's header checks the manifest against it, checks every chunk against
+//! the manifest, and checks the rebuilt state against the state root.
+
+use crate::crypto::hash::keccak256_hash;
+use crate::types::{Account, Address, BlockHash, BlockHeader, ValidatorSet, WorldState};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+/// Accounts per chunk unless the exporter chooses otherwise
+pub const DEFAULT_CHUNK_ACCOUNTS: usize = 1024;
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum SnapshotError {
+    #[error("snapshot is of block {found:?}, not the trusted block {expected:?}")]
+    BlockMismatch { expected: BlockHash, found: BlockHash },
+    #[error("snapshot has {found} chunks, its manifest lists {expected}")]
+    ChunkCount { expected: usize, found: usize },
+    #[error("chunk {0} does not match its hash in the manifest")]
+    ChunkMismatch(usize),
+    #[error("account {0:?} appears in more than one chunk")]
+    DuplicateAccount(Address),
+    #[error("restored state root {found:?} does not match {expected:?}")]
+    StateRootMismatch { expected: BlockHash, found: BlockHash },
+    #[error("malformed chunk {index}: {reason}")]
+    Malformed { index: usize, reason: String },
+}
+
+/// What a snapshot is of, and the hash of each of its chunks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub height: u64,
+    pub block_hash: BlockHash,
+    pub state_root: BlockHash,
+    pub global_nonce: u64,
+    pub validator_set: ValidatorSet,
+    pub chunk_hashes: Vec<BlockHash>,
+}
+
+impl SnapshotManifest {
+    /// Check that the manifest describes the state after `header`
+    pub fn verify_header(&self, header: &BlockHeader) -> Result<(), SnapshotError> {
+        let expected = header.hash();
+        if self.block_hash != expected || self.height != header.block_number {
+            return Err(SnapshotError::BlockMismatch { expected, found: self.block_hash });
+        }
+        if self.state_root != header.state_root {
+            return Err(SnapshotError::StateRootMismatch { expected: header.state_root, found: self.state_root });
+        }
+        Ok(())
+    }
+
+    /// Check a chunk fetched from a peer before keeping it
+    pub fn verify_chunk(&self, index: usize, chunk: &SnapshotChunk) -> Result<(), SnapshotError> {
+        match self.chunk_hashes.get(index) {
+            Some(hash) if *hash == chunk.hash() => Ok(()),
+            _ => Err(SnapshotError::ChunkMismatch(index)),
+        }
+    }
+}
+
+/// A slice of the accounts, bincode-encoded
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SnapshotChunk {
+    pub data: Vec<u8>,
+}
+
+impl SnapshotChunk {
+    pub fn hash(&self) -> BlockHash {
+        BlockHash(keccak256_hash(&self.data))
+    }
+
+    fn accounts(&self, index: usize) -> Result<Vec<(Address, Account)>, SnapshotError> {
+        bincode::deserialize(&self.data).map_err(|e| SnapshotError::Malformed { index, reason: e.to_string() })
+    }
+}
+
+/// A manifest and all of its chunks
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub manifest: SnapshotManifest,
+    pub chunks: Vec<SnapshotChunk>,
+}
+
+impl StateSnapshot {
+    /// Rebuild the world state, verifying every chunk and the state root
+    pub fn restore(&self) -> Result<WorldState, SnapshotError> {
+        let manifest = &self.manifest;
+        if self.chunks.len() != manifest.chunk_hashes.len() {
+            return Err(SnapshotError::ChunkCount { expected: manifest.chunk_hashes.len(), found: self.chunks.len() });
+        }
+        let mut state = WorldState {
+            global_nonce: manifest.global_nonce,
+            block_number: manifest.height,
+            ..WorldState::default()
+        };
+        for (index, chunk) in self.chunks.iter().enumerate() {
+            manifest.verify_chunk(index, chunk)?;
+            for (address, account) in chunk.accounts(index)? {
+                if state.accounts.insert(address, account).is_some() {
+                    return Err(SnapshotError::DuplicateAccount(address));
+                }
+            }
+        }
+
+        let root = state.compute_state_root();
+        if root != manifest.state_root {
+            return Err(SnapshotError::StateRootMismatch { expected: manifest.state_root, found: root });
+        }
+        state.state_root = root;
+        Ok(state)
+    }
+}
+
+/// Snapshot of `state` and `validator_set` after `header`, with at most
+/// `chunk_accounts` accounts per chunk
+pub fn export(state: &WorldState, validator_set: &ValidatorSet, header: &BlockHeader, chunk_accounts: usize) -> StateSnapshot {
+    let mut accounts: Vec<(&Address, &Account)> = state.accounts.iter().collect();
+    accounts.sort_by_key(|(address, _)| **address);
+    let chunks: Vec<SnapshotChunk> = accounts.chunks(chunk_accounts.max(1))
+        .map(|accounts| SnapshotChunk {
+            data: bincode::serialize(accounts).expect("accounts always encode"),
+        })
+        .collect();
+
+    StateSnapshot {
+        manifest: SnapshotManifest {
+            height: header.block_number,
+            block_hash: header.hash(),
+            state_root: header.state_root,
+            global_nonce: state.global_nonce,
+            validator_set: validator_set.clone(),
+            chunk_hashes: chunks.iter().map(SnapshotChunk::hash).collect(),
+        },
+        chunks,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::RandomnessProof;
+
+    fn header(state: &WorldState) -> BlockHeader {
+        BlockHeader {
+            previous_hash: BlockHash::zero(),
+            merkle_root: BlockHash::zero(),
+            prev_state_root: BlockHash::zero(),
+            state_root: state.compute_state_root(),
+            timestamp: 0,
+            block_number: 5,
+            gas_used: 0,
+            gas_limit: 0,
+            producer: Address::new(1),
+            extra_data: Vec::new(),
+            randomness: RandomnessProof::default(),
+        }
+    }
+
+    #[test]
+    fn test_export_restores_the_state_root() {
+        let mut state = WorldState { global_nonce: 2, ..WorldState::default() };
+        for id in 1..=5u8 {
+            state.accounts.insert(Address::new(id), Account::new(u64::from(id) * 100));
+        }
+        state.accounts.get_mut(&Address::new(3)).unwrap().storage.insert([1; 32], [2; 32]);
+        let header = header(&state);
+        let snapshot = export(&state, &ValidatorSet::new(Vec::new()), &header, 2);
+        assert_eq!(snapshot.chunks.len(), 3);
+        snapshot.manifest.verify_header(&header).unwrap();
+
+        let restored = snapshot.restore().unwrap();
+        assert_eq!(restored.state_root, header.state_root);
+        assert_eq!(restored.accounts[&Address::new(3)].storage[&[1; 32]], [2; 32]);
+
+        let mut tampered = snapshot.clone();
+        tampered.chunks[1].data[0] ^= 1;
+        assert_eq!(tampered.restore().unwrap_err(), SnapshotError::ChunkMismatch(1));
+
+        // A consistent manifest over different accounts fails the root check
+        let mut forged = snapshot.clone();
+        forged.chunks.pop();
+        forged.manifest.chunk_hashes.pop();
+        assert!(matches!(forged.restore(), Err(SnapshotError::StateRootMismatch { .. })));
+    }
+}
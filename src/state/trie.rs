@@ -0,0 +1,208 @@
+//! Sparse Merkle trie over 256-bit keys
+//!
+//! Conceptually a binary tree of depth 256 with one leaf slot per key, where
+//! the bits of the key pick the path from the root. Empty subtrees hash to
+//! zero and a subtree holding a single entry is represented by that entry's
+//! leaf, so roots and proofs only cost about `log2(n)` hashes per entry. The
+//! leaf hash includes the full key, which keeps a shortened path unambiguous.
+
+use crate::crypto::hash::keccak256_hash;
+use crate::types::BlockHash;
+use crate::zkvm::programs::guest_program::WitnessNode;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+
+/// Hash of an empty subtree
+pub const EMPTY_ROOT: [u8; 32] = [0; 32];
+
+pub fn leaf_hash(key: &[u8; 32], value: &[u8; 32]) -> [u8; 32] {
+    let mut bytes = [0u8; 65];
+    bytes[0] = LEAF_TAG;
+    bytes[1..33].copy_from_slice(key);
+    bytes[33..].copy_from_slice(value);
+    keccak256_hash(&bytes)
+}
+
+fn node_hash(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    let mut bytes = [0u8; 65];
+    bytes[0] = NODE_TAG;
+    bytes[1..33].copy_from_slice(left);
+    bytes[33..].copy_from_slice(right);
+    keccak256_hash(&bytes)
+}
+
+/// Bit `depth` of `key`, most significant first; set means the right child
+fn bit(key: &[u8; 32], depth: usize) -> bool {
+    key[depth / 8] >> (7 - depth % 8) & 1 == 1
+}
+
+/// Root of `leaves`, which are sorted by key and share their first `depth` bits
+fn subtree_root(leaves: &[([u8; 32], [u8; 32])], depth: usize) -> [u8; 32] {
+    match leaves {
+        [] => EMPTY_ROOT,
+        [(_, hash)] => *hash,
+        _ => {
+            let (left, right) = leaves.split_at(leaves.partition_point(|(key, _)| !bit(key, depth)));
+            node_hash(&subtree_root(left, depth + 1), &subtree_root(right, depth + 1))
+        }
+    }
+}
+
+/// Key/value trie; values are 32-byte hashes of whatever is stored
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SparseMerkleTrie {
+    entries: BTreeMap<[u8; 32], [u8; 32]>,
+}
+
+impl SparseMerkleTrie {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: [u8; 32], value: [u8; 32]) -> Option<[u8; 32]> {
+        self.entries.insert(key, value)
+    }
+
+    pub fn remove(&mut self, key: &[u8; 32]) -> Option<[u8; 32]> {
+        self.entries.remove(key)
+    }
+
+    pub fn get(&self, key: &[u8; 32]) -> Option<&[u8; 32]> {
+        self.entries.get(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    fn leaves(&self) -> Vec<([u8; 32], [u8; 32])> {
+        self.entries.iter().map(|(key, value)| (*key, leaf_hash(key, value))).collect()
+    }
+
+    pub fn root(&self) -> [u8; 32] {
+        subtree_root(&self.leaves(), 0)
+    }
+
+    /// Inclusion proof for `key`, or `None` if it is not in the trie
+    pub fn proof(&self, key: &[u8; 32]) -> Option<TrieProof> {
+        self.entries.get(key)?;
+        let leaves = self.leaves();
+        let mut remaining = &leaves[..];
+        let mut siblings = Vec::new();
+        let mut depth = 0;
+        // Descend until the key's leaf is alone in its subtree
+        while remaining.len() > 1 {
+            let (left, right) = remaining.split_at(remaining.partition_point(|(k, _)| !bit(k, depth)));
+            let (own, other) = if bit(key, depth) { (right, left) } else { (left, right) };
+            siblings.push(BlockHash(subtree_root(other, depth + 1)));
+            remaining = own;
+            depth += 1;
+        }
+        Some(TrieProof { siblings })
+    }
+
+    /// The part of the trie on the paths to `keys`, whether they are in it
+    /// or not. Leaves of the keys in it are opened by `open`, the leaves
+    /// other keys end at are kept so those keys can be inserted, and every
+    /// other subtree is reduced to its root.
+    pub fn witness(&self, keys: &BTreeSet<[u8; 32]>, open: impl Fn(&[u8; 32]) -> WitnessNode) -> WitnessNode {
+        let keys: Vec<[u8; 32]> = keys.iter().copied().collect();
+        self.witness_node(&self.leaves(), &keys, 0, &open)
+    }
+
+    fn witness_node(
+        &self,
+        leaves: &[([u8; 32], [u8; 32])],
+        keys: &[[u8; 32]],
+        depth: usize,
+        open: &impl Fn(&[u8; 32]) -> WitnessNode,
+    ) -> WitnessNode {
+        match leaves {
+            [] => WitnessNode::Empty,
+            _ if keys.is_empty() => WitnessNode::Hash(subtree_root(leaves, depth)),
+            [(key, _)] if keys.contains(key) => open(key),
+            [(key, _)] => WitnessNode::Leaf { key: *key, value: self.entries[key] },
+            _ => {
+                let (left, right) = leaves.split_at(leaves.partition_point(|(key, _)| !bit(key, depth)));
+                let (left_keys, right_keys) = keys.split_at(keys.partition_point(|key| !bit(key, depth)));
+                WitnessNode::Branch(
+                    Box::new(self.witness_node(left, left_keys, depth + 1, open)),
+                    Box::new(self.witness_node(right, right_keys, depth + 1, open)),
+                )
+            }
+        }
+    }
+}
+
+impl FromIterator<([u8; 32], [u8; 32])> for SparseMerkleTrie {
+    fn from_iter<I: IntoIterator<Item = ([u8; 32], [u8; 32])>>(iter: I) -> Self {
+        Self { entries: iter.into_iter().collect() }
+    }
+}
+
+/// Sibling hashes from the root down to a leaf
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrieProof {
+    pub siblings: Vec<BlockHash>,
+}
+
+impl TrieProof {
+    /// Root of the trie this proof places `key => value` in
+    pub fn compute_root(&self, key: &[u8; 32], value: &[u8; 32]) -> [u8; 32] {
+        self.siblings.iter().enumerate().rev().fold(leaf_hash(key, value), |node, (depth, sibling)| {
+            if bit(key, depth) {
+                node_hash(&sibling.0, &node)
+            } else {
+                node_hash(&node, &sibling.0)
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(id: u32) -> [u8; 32] {
+        keccak256_hash(&id.to_le_bytes())
+    }
+
+    #[test]
+    fn test_root_ignores_insertion_order_and_tracks_values() {
+        let forward: SparseMerkleTrie = (0..50).map(|id| (key(id), [id as u8; 32])).collect();
+        let mut backward = SparseMerkleTrie::new();
+        for id in (0..50).rev() {
+            backward.insert(key(id), [id as u8; 32]);
+        }
+        assert_eq!(forward.root(), backward.root());
+
+        backward.insert(key(7), [0xff; 32]);
+        assert_ne!(forward.root(), backward.root());
+        backward.insert(key(7), [7; 32]);
+        assert_eq!(forward.root(), backward.root());
+
+        assert_eq!(SparseMerkleTrie::new().root(), EMPTY_ROOT);
+        let single: SparseMerkleTrie = [(key(1), [1; 32])].into_iter().collect();
+        assert_eq!(single.root(), leaf_hash(&key(1), &[1; 32]));
+    }
+
+    #[test]
+    fn test_proofs_verify_against_the_root() {
+        let trie: SparseMerkleTrie = (0..33).map(|id| (key(id), [id as u8; 32])).collect();
+        let root = trie.root();
+        for id in 0..33 {
+            let proof = trie.proof(&key(id)).unwrap();
+            assert_eq!(proof.compute_root(&key(id), &[id as u8; 32]), root);
+            assert_ne!(proof.compute_root(&key(id), &[0xee; 32]), root);
+            assert_ne!(proof.compute_root(&key(id + 1), &[id as u8; 32]), root);
+        }
+        assert!(trie.proof(&key(99)).is_none());
+    }
+}
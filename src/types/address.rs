@@ -0,0 +1,227 @@
+//! Deriving, printing and parsing addresses
+//!
+//! An address is the last 20 bytes of the Keccak-256 hash of a public key;
+//! secp256k1 keys are hashed uncompressed without the `0x04` prefix, as in
+//! Ethereum. Addresses print as EIP-55 checksummed hex and parse from hex,
+//! where mixed case must match the checksum, or from bech32 with the
+//! [`ADDRESS_HRP`] prefix.
+
+use super::Address;
+use crate::crypto::hash::keccak256_hash;
+use std::fmt;
+use std::str::FromStr;
+use thiserror::Error;
+
+/// Human-readable part of bech32 addresses
+pub const ADDRESS_HRP: &str = "zksac";
+
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum AddressError {
+    #[error("invalid hex address: {0}")]
+    InvalidHex(String),
+    #[error("address must be 20 bytes, got {0}")]
+    InvalidLength(usize),
+    #[error("address checksum does not match")]
+    BadChecksum,
+    #[error("invalid bech32 address: {0}")]
+    InvalidBech32(String),
+    #[error("expected a {expected} address, found {found}")]
+    WrongPrefix { expected: String, found: String },
+    #[error("invalid secp256k1 public key")]
+    InvalidPublicKey,
+}
+
+impl Address {
+    /// Address of an Ed25519 public key
+    pub fn from_ed25519_pubkey(public_key: &[u8; 32]) -> Self {
+        Self::from_key_hash(&keccak256_hash(public_key))
+    }
+
+    /// Address of a compressed or uncompressed SEC1 secp256k1 public key
+    pub fn from_secp256k1_pubkey(public_key: &[u8]) -> Result<Self, AddressError> {
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+        let key = k256::PublicKey::from_sec1_bytes(public_key).map_err(|_| AddressError::InvalidPublicKey)?;
+        Ok(Self::from_key_hash(&keccak256_hash(&key.to_encoded_point(false).as_bytes()[1..])))
+    }
+
+    /// Address from the Keccak-256 hash of a public key
+    pub(crate) fn from_key_hash(hash: &[u8; 32]) -> Self {
+        let mut address = [0u8; 20];
+        address.copy_from_slice(&hash[12..]);
+        Address(address)
+    }
+
+    /// `0x`-prefixed hex with the EIP-55 mixed-case checksum
+    pub fn to_checksum_hex(&self) -> String {
+        let lower = hex::encode(self.0);
+        let hash = keccak256_hash(lower.as_bytes());
+        let checksummed: String = lower.chars().enumerate()
+            .map(|(i, c)| {
+                let nibble = (hash[i / 2] >> if i % 2 == 0 { 4 } else { 0 }) & 0x0f;
+                if nibble >= 8 { c.to_ascii_uppercase() } else { c }
+            })
+            .collect();
+        format!("0x{}", checksummed)
+    }
+
+    /// Parse hex with or without `0x`; a mixed-case address must carry a
+    /// valid EIP-55 checksum
+    pub fn from_hex(text: &str) -> Result<Self, AddressError> {
+        let digits = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")).unwrap_or(text);
+        let bytes = hex::decode(digits).map_err(|e| AddressError::InvalidHex(e.to_string()))?;
+        let address = Address(bytes.as_slice().try_into().map_err(|_| AddressError::InvalidLength(bytes.len()))?);
+        let mixed_case = digits.chars().any(|c| c.is_ascii_lowercase()) && digits.chars().any(|c| c.is_ascii_uppercase());
+        if mixed_case && address.to_checksum_hex()[2..] != *digits {
+            return Err(AddressError::BadChecksum);
+        }
+        Ok(address)
+    }
+
+    /// Bech32 (BIP-173) with the human-readable part `hrp`
+    pub fn to_bech32(&self, hrp: &str) -> String {
+        let data = convert_bits(&self.0, 8, 5, true).expect("bytes always regroup");
+        let checksum = bech32_checksum(hrp, &data);
+        let mut encoded = format!("{}1", hrp);
+        encoded.extend(data.iter().chain(&checksum).map(|&group| BECH32_CHARSET[usize::from(group)] as char));
+        encoded
+    }
+
+    /// Parse a bech32 address whose human-readable part is `hrp`
+    pub fn from_bech32(text: &str, hrp: &str) -> Result<Self, AddressError> {
+        let invalid = |reason: &str| AddressError::InvalidBech32(reason.to_string());
+        if text.chars().any(|c| c.is_ascii_lowercase()) && text.chars().any(|c| c.is_ascii_uppercase()) {
+            return Err(invalid("mixed case"));
+        }
+        let text = text.to_ascii_lowercase();
+        let (found, data) = text.rsplit_once('1').ok_or_else(|| invalid("no separator"))?;
+        if found != hrp {
+            return Err(AddressError::WrongPrefix { expected: hrp.to_string(), found: found.to_string() });
+        }
+        let groups: Vec<u8> = data.bytes()
+            .map(|c| BECH32_CHARSET.iter().position(|&d| d == c).map(|group| group as u8))
+            .collect::<Option<_>>()
+            .ok_or_else(|| invalid("character outside the bech32 set"))?;
+        if groups.len() < 6 {
+            return Err(invalid("too short"));
+        }
+        if bech32_polymod(&[hrp_expand(hrp), groups.clone()].concat()) != 1 {
+            return Err(AddressError::BadChecksum);
+        }
+        let bytes = convert_bits(&groups[..groups.len() - 6], 5, 8, false).ok_or_else(|| invalid("bad padding"))?;
+        Ok(Address(bytes.as_slice().try_into().map_err(|_| AddressError::InvalidLength(bytes.len()))?))
+    }
+}
+
+/// Checksummed hex
+impl fmt::Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_checksum_hex())
+    }
+}
+
+/// Hex, or bech32 with the [`ADDRESS_HRP`] prefix
+impl FromStr for Address {
+    type Err = AddressError;
+
+    fn from_str(text: &str) -> Result<Self, Self::Err> {
+        let text = text.trim();
+        let prefix = format!("{}1", ADDRESS_HRP);
+        if text.len() > prefix.len() && text[..prefix.len()].eq_ignore_ascii_case(&prefix) {
+            Self::from_bech32(text, ADDRESS_HRP)
+        } else {
+            Self::from_hex(text)
+        }
+    }
+}
+
+const BECH32_CHARSET: &[u8; 32] = b"qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+fn bech32_polymod(values: &[u8]) -> u32 {
+    const GENERATOR: [u32; 5] = [0x3b6a57b2, 0x26508e6d, 0x1ea119fa, 0x3d4233dd, 0x2a1462b3];
+    values.iter().fold(1u32, |checksum, &value| {
+        let top = checksum >> 25;
+        let checksum = ((checksum & 0x1ffffff) << 5) ^ u32::from(value);
+        GENERATOR.iter().enumerate()
+            .filter(|(i, _)| (top >> i) & 1 == 1)
+            .fold(checksum, |checksum, (_, generator)| checksum ^ generator)
+    })
+}
+
+fn hrp_expand(hrp: &str) -> Vec<u8> {
+    hrp.bytes().map(|c| c >> 5)
+        .chain(std::iter::once(0))
+        .chain(hrp.bytes().map(|c| c & 31))
+        .collect()
+}
+
+fn bech32_checksum(hrp: &str, data: &[u8]) -> [u8; 6] {
+    let polymod = bech32_polymod(&[hrp_expand(hrp), data.to_vec(), vec![0; 6]].concat()) ^ 1;
+    std::array::from_fn(|i| ((polymod >> (5 * (5 - i))) & 31) as u8)
+}
+
+/// Regroup `data` from `from`-bit to `to`-bit groups; without `pad`,
+/// leftover bits must be zero and fewer than `from`
+fn convert_bits(data: &[u8], from: u32, to: u32, pad: bool) -> Option<Vec<u8>> {
+    let (mut acc, mut bits) = (0u32, 0u32);
+    let mut out = Vec::new();
+    for &value in data {
+        acc = (acc << from) | u32::from(value);
+        bits += from;
+        while bits >= to {
+            bits -= to;
+            out.push(((acc >> bits) & ((1 << to) - 1)) as u8);
+        }
+    }
+    if pad {
+        if bits > 0 {
+            out.push(((acc << (to - bits)) & ((1 << to) - 1)) as u8);
+        }
+    } else if bits >= from || (acc << (to - bits)) & ((1 << to) - 1) != 0 {
+        return None;
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derives_ethereum_addresses_from_secp256k1_keys() {
+        // The public key of secret key 1 is the generator point
+        let mut secret = [0u8; 32];
+        secret[31] = 1;
+        let key = k256::SecretKey::from_slice(&secret).unwrap().public_key();
+        let expected: Address = "0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf".parse().unwrap();
+        assert_eq!(Address::from_secp256k1_pubkey(&key.to_sec1_bytes()).unwrap(), expected);
+        use k256::elliptic_curve::sec1::ToEncodedPoint;
+        assert_eq!(Address::from_secp256k1_pubkey(key.to_encoded_point(false).as_bytes()).unwrap(), expected);
+        assert_eq!(Address::from_secp256k1_pubkey(&[4; 10]), Err(AddressError::InvalidPublicKey));
+    }
+
+    #[test]
+    fn test_hex_round_trips_with_checksum() {
+        let text = "0x5aAeb6053F3E94C9b9A09f33669435E7Ef1BeAed";
+        let address: Address = text.parse().unwrap();
+        assert_eq!(address.to_string(), text);
+        assert_eq!(text.to_lowercase().parse::<Address>().unwrap(), address);
+        assert_eq!(text[2..].to_uppercase().parse::<Address>().unwrap(), address);
+        assert_eq!("0x5AAeb6053F3E94C9b9A09f33669435E7Ef1BeAed".parse::<Address>(), Err(AddressError::BadChecksum));
+        assert_eq!("0x1234".parse::<Address>(), Err(AddressError::InvalidLength(2)));
+    }
+
+    #[test]
+    fn test_bech32_round_trips_and_detects_errors() {
+        let address = Address::from_ed25519_pubkey(&[7; 32]);
+        let encoded = address.to_bech32(ADDRESS_HRP);
+        assert!(encoded.starts_with("zksac1"));
+        assert_eq!(encoded.parse::<Address>().unwrap(), address);
+        assert_eq!(encoded.to_uppercase().parse::<Address>().unwrap(), address);
+
+        let mut typo = encoded.clone().into_bytes();
+        let last = typo.len() - 1;
+        typo[last] = if typo[last] == b'q' { b'p' } else { b'q' };
+        assert_eq!(Address::from_bech32(std::str::from_utf8(&typo).unwrap(), ADDRESS_HRP), Err(AddressError::BadChecksum));
+        assert!(matches!(Address::from_bech32(&address.to_bech32("other"), ADDRESS_HRP), Err(AddressError::WrongPrefix { .. })));
+    }
+}
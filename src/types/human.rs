@@ -0,0 +1,240 @@
+//! Human-readable durations and byte sizes for config files
+//!
+//! Use with `#[serde(with = "...")]`. Human-readable formats (JSON, TOML)
+//! write strings such as `"4s"`, `"250ms"` or `"1MiB"` and accept either a
+//! string or a plain number (seconds or bytes). Binary formats such as
+//! bincode keep the compact numeric encoding.
+
+use serde::de::{self, Visitor};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::time::Duration;
+
+const BYTE_UNITS: &[(&str, u64)] = &[
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+    ("TB", 1_000_000_000_000),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("KB", 1_000),
+    ("B", 1),
+];
+
+/// Parse `"1MB"`, `"512 KiB"` or `"4096"`. Decimal units (KB, MB, ...) are
+/// powers of 1000, binary units (KiB, MiB, ...) powers of 1024.
+pub fn parse_byte_size(text: &str) -> Result<u64, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    if number.is_empty() {
+        return Err(format!("invalid byte size {:?}: expected a number followed by a unit, e.g. \"64MiB\"", text));
+    }
+    let number: u64 = number.parse()
+        .map_err(|_| format!("invalid byte size {:?}: number is too large", text))?;
+
+    let unit = unit.trim();
+    let multiplier = if unit.is_empty() {
+        1
+    } else {
+        BYTE_UNITS.iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(unit))
+            .map(|&(_, multiplier)| multiplier)
+            .ok_or_else(|| format!("invalid byte size {:?}: unknown unit {:?}, expected one of B, KB, MB, GB, TB, KiB, MiB, GiB, TiB", text, unit))?
+    };
+    number.checked_mul(multiplier)
+        .ok_or_else(|| format!("invalid byte size {:?}: value overflows 64 bits", text))
+}
+
+/// Format with the largest unit that represents `bytes` exactly, preferring
+/// binary units
+pub fn format_byte_size(bytes: u64) -> String {
+    BYTE_UNITS.iter()
+        .find(|&&(_, multiplier)| bytes >= multiplier && bytes.is_multiple_of(multiplier))
+        .map(|(name, multiplier)| format!("{}{}", bytes / multiplier, name))
+        .unwrap_or_else(|| format!("{}B", bytes))
+}
+
+/// Parse a humantime duration such as `"4s"`, `"250ms"` or `"1m 30s"`
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    humantime::parse_duration(text.trim())
+        .map_err(|e| format!("invalid duration {:?}: {}; expected e.g. \"4s\", \"250ms\" or \"1m 30s\"", text, e))
+}
+
+pub fn format_duration(duration: Duration) -> String {
+    humantime::format_duration(duration).to_string()
+}
+
+/// `#[serde(with = "crate::types::human::duration")]`
+pub mod duration {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(duration: &Duration, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format_duration(*duration))
+        } else {
+            duration.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Duration, D::Error> {
+        if !deserializer.is_human_readable() {
+            return Duration::deserialize(deserializer);
+        }
+
+        struct DurationVisitor;
+
+        impl<'de> Visitor<'de> for DurationVisitor {
+            type Value = Duration;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a duration such as \"4s\" or \"250ms\", or a number of seconds")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<Duration, E> {
+                parse_duration(value).map_err(E::custom)
+            }
+
+            fn visit_u64<E: de::Error>(self, secs: u64) -> Result<Duration, E> {
+                Ok(Duration::from_secs(secs))
+            }
+
+            fn visit_i64<E: de::Error>(self, secs: i64) -> Result<Duration, E> {
+                u64::try_from(secs)
+                    .map(Duration::from_secs)
+                    .map_err(|_| E::custom(format!("invalid duration {}: must not be negative", secs)))
+            }
+
+            fn visit_f64<E: de::Error>(self, secs: f64) -> Result<Duration, E> {
+                Duration::try_from_secs_f64(secs)
+                    .map_err(|_| E::custom(format!("invalid duration {}: must be a finite, non-negative number of seconds", secs)))
+            }
+
+            // The `{ secs, nanos }` map serde writes for a plain `Duration`
+            fn visit_map<A: de::MapAccess<'de>>(self, map: A) -> Result<Duration, A::Error> {
+                Duration::deserialize(de::value::MapAccessDeserializer::new(map))
+            }
+        }
+
+        deserializer.deserialize_any(DurationVisitor)
+    }
+}
+
+/// `#[serde(with = "crate::types::human::byte_size")]` for `usize` fields
+pub mod byte_size {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(bytes: &usize, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.serialize_str(&format_byte_size(*bytes as u64))
+        } else {
+            bytes.serialize(serializer)
+        }
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<usize, D::Error> {
+        if !deserializer.is_human_readable() {
+            return usize::deserialize(deserializer);
+        }
+
+        struct ByteSizeVisitor;
+
+        impl<'de> Visitor<'de> for ByteSizeVisitor {
+            type Value = usize;
+
+            fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+                formatter.write_str("a byte size such as \"1MB\" or \"64MiB\", or a number of bytes")
+            }
+
+            fn visit_str<E: de::Error>(self, value: &str) -> Result<usize, E> {
+                let bytes = parse_byte_size(value).map_err(E::custom)?;
+                usize::try_from(bytes).map_err(|_| E::custom(format!("byte size {:?} does not fit in memory", value)))
+            }
+
+            fn visit_u64<E: de::Error>(self, bytes: u64) -> Result<usize, E> {
+                usize::try_from(bytes).map_err(|_| E::custom(format!("byte size {} does not fit in memory", bytes)))
+            }
+
+            fn visit_i64<E: de::Error>(self, bytes: i64) -> Result<usize, E> {
+                usize::try_from(bytes).map_err(|_| E::custom(format!("invalid byte size {}: must not be negative", bytes)))
+            }
+        }
+
+        deserializer.deserialize_any(ByteSizeVisitor)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_byte_sizes() {
+        assert_eq!(parse_byte_size("1MB"), Ok(1_000_000));
+        assert_eq!(parse_byte_size("64 MiB"), Ok(64 << 20));
+        assert_eq!(parse_byte_size("4096"), Ok(4096));
+        assert_eq!(format_byte_size(1 << 30), "1GiB");
+        assert_eq!(format_byte_size(1_000_000), "1MB");
+        assert_eq!(format_byte_size(1001), "1001B");
+        assert_eq!(parse_byte_size(&format_byte_size(3 << 20)), Ok(3 << 20));
+
+        let err = parse_byte_size("12 parsecs").unwrap_err();
+        assert!(err.contains("unknown unit \"parsecs\""), "{}", err);
+        assert!(parse_byte_size("MB").is_err());
+        assert!(parse_byte_size("99999999999TiB").is_err());
+    }
+
+    #[test]
+    fn test_durations() {
+        assert_eq!(parse_duration("4s"), Ok(Duration::from_secs(4)));
+        assert_eq!(parse_duration("250ms"), Ok(Duration::from_millis(250)));
+        assert_eq!(format_duration(Duration::from_millis(1500)), "1s 500ms");
+        assert_eq!(parse_duration("1s 500ms"), Ok(Duration::from_millis(1500)));
+        assert!(parse_duration("4 fortnights").unwrap_err().contains("expected e.g."));
+    }
+
+    #[test]
+    fn test_protocol_config_roundtrips_through_json_and_toml() {
+        use crate::types::ProtocolConfig;
+
+        let mut config = ProtocolConfig::default();
+        config.block_time = Duration::from_millis(250);
+        config.max_block_size = 1 << 20;
+
+        let json = serde_json::to_value(&config).unwrap();
+        assert_eq!(json["block_time"], "250ms");
+        assert_eq!(json["max_block_size"], "1MiB");
+        assert_eq!(json["zkvm_config"]["memory_limit"], "1GiB");
+        assert_eq!(json["zkvm_config"]["execution_timeout"], "30s");
+        let decoded: ProtocolConfig = serde_json::from_value(json).unwrap();
+        assert_eq!(decoded.block_time, config.block_time);
+        assert_eq!(decoded.zkvm_config.memory_limit, config.zkvm_config.memory_limit);
+
+        let toml_text = toml::to_string(&config).unwrap();
+        let decoded: ProtocolConfig = toml::from_str(&toml_text).unwrap();
+        assert_eq!(decoded.max_block_size, 1 << 20);
+        assert_eq!(decoded.zkvm_config.execution_timeout, Duration::from_secs(30));
+    }
+
+    #[test]
+    fn test_protocol_config_accepts_numbers_and_reports_bad_values() {
+        use crate::types::ProtocolConfig;
+
+        let legacy = r#"{
+            "block_time_secs": 4, "max_block_size": 1000000, "max_transactions_per_block": 10,
+            "min_stake_threshold": 1, "slashing_rate": 0.05, "reward_rate": 0.04,
+            "zkvm_config": { "memory_limit": "512MB", "execution_timeout": 30,
+                             "proof_compression": true, "parallel_execution": true, "max_circuits": 4 }
+        }"#;
+        let config: ProtocolConfig = serde_json::from_str(legacy).unwrap();
+        assert_eq!(config.block_time, Duration::from_secs(4));
+        assert_eq!(config.zkvm_config.memory_limit, 512_000_000);
+        assert_eq!(config.epoch_schedule, crate::types::EpochSchedule::default());
+        assert_eq!(config.execution_backend, crate::types::ExecutionBackendKind::Evm);
+
+        let bad = legacy.replace(r#""block_time_secs": 4"#, r#""block_time": "4 lightyears""#);
+        let err = serde_json::from_str::<ProtocolConfig>(&bad).unwrap_err().to_string();
+        assert!(err.contains("invalid duration \"4 lightyears\""), "{}", err);
+    }
+}
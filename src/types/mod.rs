@@ -2,10 +2,19 @@ use serde::{Deserialize, Serialize, Deserializer, Serializer};
 // Removed bincode derive - using regular serde
 use std::collections::HashMap;
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub mod address;
+pub mod human;
+
+pub use address::{ADDRESS_HRP, AddressError};
+pub use crate::zkvm::programs::gas::GasSchedule;
+
+/// Chain id of networks that do not configure one
+pub const DEFAULT_CHAIN_ID: u64 = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct Address(pub [u8; 20]);
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct BlockHash(pub [u8; 32]);
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -28,6 +37,9 @@ pub struct WorldState {
 pub struct BlockHeader {
     pub previous_hash: BlockHash,
     pub merkle_root: BlockHash,
+    /// State root the block's transactions start from, so the header alone
+    /// fixes the [`ProofPublicInputs`] of its proof
+    pub prev_state_root: BlockHash,
     pub state_root: BlockHash,
     pub timestamp: u64,
     pub block_number: u64,
@@ -35,6 +47,17 @@ pub struct BlockHeader {
     pub gas_used: u64,
     pub producer: Address,
     pub extra_data: Vec<u8>,
+    /// The producer's randomness beacon output, which seeds the selection of
+    /// the next producer
+    pub randomness: RandomnessProof,
+}
+
+/// VDF output over a block's beacon seed, with its Wesolowski proof; both
+/// are big-endian RSA-2048 group elements
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RandomnessProof {
+    pub output: Vec<u8>,
+    pub proof: Vec<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -43,12 +66,34 @@ pub struct Validator {
     pub stake: u64,
     pub public_key: Vec<u8>,
     pub performance_score: f64,
+    /// BLS12-381 key the validator's aggregated votes verify against; empty
+    /// if it only signs votes with `public_key`
+    #[serde(default)]
+    pub bls_public_key: Vec<u8>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ValidatorSet {
     pub validators: Vec<Validator>,
     pub total_stake: u64,
+    /// Staked validators waiting to join, in the order they take effect
+    pub activation_queue: Vec<QueuedActivation>,
+    /// Validators waiting to leave, in the order they take effect
+    pub exit_queue: Vec<QueuedExit>,
+}
+
+/// A validator that joins the set at the start of `epoch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedActivation {
+    pub validator: Validator,
+    pub epoch: u64,
+}
+
+/// A validator that leaves the set at the start of `epoch`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedExit {
+    pub address: Address,
+    pub epoch: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -59,11 +104,35 @@ pub struct ZkProof {
     pub proof_type: ProofType,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// What a block's proof proves: the transactions under `transactions_root`
+/// take the state at `prev_state_root` to `state_root` using `gas_used` gas
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofPublicInputs {
+    pub prev_state_root: BlockHash,
+    pub state_root: BlockHash,
+    pub transactions_root: BlockHash,
+    pub gas_used: u64,
+}
+
+impl ProofPublicInputs {
+    /// The proof's `public_inputs`: the three roots, then the gas used big-endian
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(3 * 32 + 8);
+        bytes.extend_from_slice(&self.prev_state_root.0);
+        bytes.extend_from_slice(&self.state_root.0);
+        bytes.extend_from_slice(&self.transactions_root.0);
+        bytes.extend_from_slice(&self.gas_used.to_be_bytes());
+        bytes
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum ProofType {
     SP1,
     Risc0,
     Plonky3,
+    /// Proof postponed because the slot ran out of time; generated after the block
+    Deferred,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -73,12 +142,27 @@ pub struct Transaction {
     pub value: u64,
     pub data: Vec<u8>,
     pub gas_limit: u64,
+    pub gas_price: u64,
     pub nonce: u64,
+    /// Network the transaction is meant for; it is signed along with the
+    /// rest, so a signature cannot be replayed on another chain
+    pub chain_id: u64,
+    /// Accounts and storage slots the transaction declares it accesses
+    /// besides its sender and recipient, or `None` if it declares nothing.
+    /// Execution rejects a declared transaction that reaches past it.
+    pub access_list: Option<Vec<AccessListItem>>,
     pub signature: Vec<u8>,
     pub sig_type: SignatureType,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// An account a transaction accesses, with the storage slots it reads or writes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AccessListItem {
+    pub address: Address,
+    pub storage_keys: Vec<[u8; 32]>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub enum SignatureType {
     Ed25519,
     Secp256k1,
@@ -101,6 +185,14 @@ pub struct ValidatorSignature {
     pub sig_type: SignatureType,
 }
 
+/// BLS votes of several validators as one signature; bit `i` of `signers`,
+/// least significant first, marks validator `i` of the set
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateSignature {
+    pub signers: Vec<u8>,
+    pub signature: Vec<u8>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Block {
     pub header: BlockHeader,
@@ -108,26 +200,156 @@ pub struct Block {
     pub validator_signatures: Vec<ValidatorSignature>,
     pub recursive_proof: ZkProof,
     pub protocol_updates: Vec<ProtocolRule>,
+    /// Producer's signature over the header hash and `recursive_proof`, so
+    /// an invalid proof can be attributed to the producer
+    pub proof_signature: Vec<u8>,
+    /// Votes of the validators that sign with BLS, in addition to
+    /// `validator_signatures`
+    #[serde(default)]
+    pub aggregate_signature: Option<AggregateSignature>,
 }
 
 #[derive(Debug, Clone)]
 pub struct ProtocolConfig {
+    /// Transactions signed for any other chain are rejected
+    pub chain_id: u64,
     pub block_time: tokio::time::Duration,
     pub max_block_size: usize,
     pub max_transactions_per_block: usize,
     pub min_stake_threshold: u64,
     pub slashing_rate: f64,
+    /// Blocks within which a block produced with a deferred proof must have
+    /// its proof supplied; until then it is not final, and after that its
+    /// producer is slashed
+    pub proof_deadline: u64,
     pub reward_rate: f64,
     pub zkvm_config: ZkVMConfig,
+    pub epoch_schedule: EpochSchedule,
+    pub gas_schedule: GasSchedule,
+    /// Squarings in the VDF each producer evaluates for the randomness beacon
+    pub vdf_iterations: u64,
+    /// WASM module checking transactions and setting their fees, installed
+    /// by governance; empty for none. Not read from config files.
+    pub transaction_hooks: Vec<u8>,
+    /// Environment transactions execute in; every node must agree
+    pub execution_backend: ExecutionBackendKind,
+}
+
+/// How blocks group into epochs, at whose boundaries the validator set changes
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EpochSchedule {
+    /// Blocks per epoch
+    pub epoch_length: u64,
+    /// Epochs between a stake or unstake and the change taking effect
+    pub activation_delay: u64,
+    /// Most activations, and separately most exits, taking effect per epoch
+    pub churn_limit: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ZkVMConfig {
+    #[serde(with = "human::byte_size")]
     pub memory_limit: usize,
+    #[serde(with = "human::duration")]
     pub execution_timeout: tokio::time::Duration,
     pub proof_compression: bool,
     pub parallel_execution: bool,
     pub max_circuits: usize,
+    /// Proving system for state transitions
+    pub backend: ProverBackendKind,
+    /// Where Risc0 proves; unavailable modes fall back to the CPU
+    pub prover_mode: ProverMode,
+    /// Proving service that produced blocks are sent to, if any
+    pub remote: Option<RemoteProverConfig>,
+    /// Bounds of the cache of verified proofs
+    pub proof_cache: ProofCacheConfig,
+}
+
+/// Bounds of the verified proof cache, see [`crate::zkvm::cache`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ProofCacheConfig {
+    /// Proofs kept; 0 disables the cache
+    pub max_entries: usize,
+    /// Total proof bytes kept
+    #[serde(with = "human::byte_size")]
+    pub max_bytes: usize,
+}
+
+impl Default for ProofCacheConfig {
+    fn default() -> Self {
+        Self {
+            max_entries: 1024,
+            max_bytes: 64 * 1024 * 1024, // 64MiB
+        }
+    }
+}
+
+/// Remote proving service, see [`crate::zkvm::remote`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RemoteProverConfig {
+    /// Base URL of the service's HTTP API
+    pub endpoint: String,
+    pub api_key: Option<String>,
+    /// Time between status checks of a job
+    #[serde(with = "human::duration")]
+    pub poll_interval: tokio::time::Duration,
+    /// Time after which a job is given up and proven locally instead
+    #[serde(with = "human::duration")]
+    pub job_timeout: tokio::time::Duration,
+    /// Jobs submitted but not yet finished, beyond which new jobs wait
+    pub max_in_flight: usize,
+}
+
+impl Default for RemoteProverConfig {
+    fn default() -> Self {
+        Self {
+            endpoint: "http://127.0.0.1:8080".to_string(),
+            api_key: None,
+            poll_interval: tokio::time::Duration::from_secs(2),
+            job_timeout: tokio::time::Duration::from_secs(600),
+            max_in_flight: 8,
+        }
+    }
+}
+
+/// Proving system behind [`crate::zkvm::ProverBackend`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProverBackendKind {
+    #[default]
+    Risc0,
+    Sp1,
+    Plonky3,
+}
+
+/// Environment behind [`crate::consensus::execution::ExecutionBackend`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionBackendKind {
+    /// Transfers and bridge transactions only
+    Native,
+    /// Native, with contract transactions on revm
+    #[default]
+    Evm,
+    /// What the state transition guest proves
+    Guest,
+}
+
+/// Hardware or service a zkVM proves on
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ProverMode {
+    #[default]
+    Cpu,
+    Cuda,
+    Metal,
+    /// Remote proving on Bonsai
+    #[serde(alias = "remote")]
+    Bonsai,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -158,6 +380,9 @@ impl BlockHash {
     }
 }
 
+/// Recipient of contract creation transactions, whose data is the init code
+pub const CREATE_ADDRESS: Address = Address([0; 20]);
+
 impl Address {
     pub fn new(id: u8) -> Self {
         let mut addr = [0u8; 20];
@@ -201,12 +426,63 @@ impl Transaction {
             value,
             data: Vec::new(),
             gas_limit: 21000,
+            gas_price: 1,
             nonce,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             signature: vec![0; 64],
             sig_type: SignatureType::Ed25519,
         }
     }
 
+    /// Keccak256 of the canonically encoded transaction
+    pub fn hash(&self) -> BlockHash {
+        crate::serialization::canonical::canonical_hash(self)
+    }
+
+    /// Hash covered by the sender's signature: the transaction, chain id
+    /// included, with an empty signature
+    pub fn signing_hash(&self) -> BlockHash {
+        let unsigned = Transaction {
+            signature: Vec::new(),
+            ..self.clone()
+        };
+        unsigned.hash()
+    }
+
+    /// Sign with `key`, which also sets the signature type
+    pub fn signed(mut self, key: &crate::crypto::keystore::KeyPair) -> anyhow::Result<Self> {
+        self.sig_type = key.sig_type().clone();
+        self.signature = key.sign(&self.signing_hash().0)?;
+        Ok(self)
+    }
+
+    /// Whether the transaction may touch `address`: its sender and
+    /// recipient always, others only if declared or nothing is
+    pub fn declares(&self, address: &Address) -> bool {
+        match &self.access_list {
+            None => true,
+            Some(items) => *address == self.from || *address == self.to
+                || items.iter().any(|item| item.address == *address),
+        }
+    }
+
+    /// Whether the transaction may touch `slot` of `address`; all storage
+    /// of the sender and recipient is accessible
+    pub fn declares_slot(&self, address: &Address, slot: &[u8; 32]) -> bool {
+        match &self.access_list {
+            None => true,
+            Some(items) => *address == self.from || *address == self.to
+                || items.iter().any(|item| item.address == *address && item.storage_keys.contains(slot)),
+        }
+    }
+
+    /// Accounts the transaction may touch, or `None` if it declares nothing
+    pub fn accessed_accounts(&self) -> Option<impl Iterator<Item = Address> + '_> {
+        let items = self.access_list.as_ref()?;
+        Some([self.from, self.to].into_iter().chain(items.iter().map(|item| item.address)))
+    }
+
     pub fn with_post_quantum(from: Address, to: Address, value: u64, nonce: u64) -> Self {
         Transaction {
             from,
@@ -214,13 +490,89 @@ impl Transaction {
             value,
             data: Vec::new(),
             gas_limit: 21000,
+            gas_price: 1,
             nonce,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             signature: Vec::new(), // LMS signatures vary in size
             sig_type: SignatureType::PostQuantum,
         }
     }
 }
 
+impl BlockHeader {
+    /// Blake3 of the canonically encoded header, the hash blocks are chained by
+    pub fn hash(&self) -> BlockHash {
+        let bytes = crate::serialization::canonical::canonical_bytes(self);
+        BlockHash(crate::crypto::hash::blake3_hash(&bytes))
+    }
+
+    /// Public inputs the block's proof must carry
+    pub fn public_inputs(&self) -> ProofPublicInputs {
+        ProofPublicInputs {
+            prev_state_root: self.prev_state_root,
+            state_root: self.state_root,
+            transactions_root: self.merkle_root,
+            gas_used: self.gas_used,
+        }
+    }
+}
+
+impl Block {
+    /// Merkle root over the transaction hashes, the header's `merkle_root`
+    pub fn transactions_root(transactions: &[Transaction]) -> BlockHash {
+        BlockHash(crate::crypto::hash::merkle_root(&transaction_leaves(transactions)))
+    }
+
+    /// Proof that `transactions[index]` is included; verify it against the
+    /// header's `merkle_root` with the transaction hash as the leaf
+    pub fn transaction_proof(&self, index: usize) -> Option<crate::crypto::hash::MerkleProof> {
+        crate::crypto::hash::merkle_proof(&transaction_leaves(&self.transactions), index)
+    }
+}
+
+fn transaction_leaves(transactions: &[Transaction]) -> Vec<Vec<u8>> {
+    transactions.iter().map(|tx| tx.hash().0.to_vec()).collect()
+}
+
+impl AggregateSignature {
+    pub fn has_signer(&self, index: usize) -> bool {
+        self.signers.get(index / 8).is_some_and(|byte| byte & (1 << (index % 8)) != 0)
+    }
+
+    pub fn add_signer(&mut self, index: usize) {
+        if self.signers.len() <= index / 8 {
+            self.signers.resize(index / 8 + 1, 0);
+        }
+        self.signers[index / 8] |= 1 << (index % 8);
+    }
+
+    /// Set indices of the signers, in ascending order
+    pub fn signer_indices(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..self.signers.len() * 8).filter(|index| self.has_signer(*index))
+    }
+}
+
+impl ValidatorSet {
+    /// An active set of `validators` with nothing queued
+    pub fn new(validators: Vec<Validator>) -> Self {
+        Self {
+            total_stake: validators.iter().map(|validator| validator.stake).sum(),
+            validators,
+            activation_queue: Vec::new(),
+            exit_queue: Vec::new(),
+        }
+    }
+}
+
+impl WorldState {
+    /// Merkle root over the accounts sorted by address, combined with the
+    /// global nonce; see [`crate::state`] for inclusion proofs
+    pub fn compute_state_root(&self) -> BlockHash {
+        crate::state::state_root(self)
+    }
+}
+
 impl Default for WorldState {
     fn default() -> Self {
         Self {
@@ -235,17 +587,54 @@ impl Default for WorldState {
 impl Default for ProtocolConfig {
     fn default() -> Self {
         Self {
+            chain_id: DEFAULT_CHAIN_ID,
             block_time: tokio::time::Duration::from_secs(4),
             max_block_size: 1_000_000, // 1MB
             max_transactions_per_block: 10_000,
             min_stake_threshold: 32_000_000_000, // 32 ETH equivalent
             slashing_rate: 0.05, // 5%
+            proof_deadline: crate::consensus::slashing::DEFAULT_PROOF_DEADLINE,
             reward_rate: 0.04, // 4% annual
             zkvm_config: ZkVMConfig::default(),
+            epoch_schedule: EpochSchedule::default(),
+            gas_schedule: GasSchedule::default(),
+            vdf_iterations: crate::crypto::randomness::DEFAULT_VDF_ITERATIONS,
+            transaction_hooks: Vec::new(),
+            execution_backend: ExecutionBackendKind::default(),
+        }
+    }
+}
+
+impl Default for EpochSchedule {
+    fn default() -> Self {
+        Self {
+            epoch_length: 32,
+            activation_delay: 1,
+            churn_limit: 4,
         }
     }
 }
 
+impl EpochSchedule {
+    /// Epoch of `block_number`; epoch 0 holds genesis and the blocks before `epoch_length`
+    pub fn epoch_of(&self, block_number: u64) -> u64 {
+        block_number / self.epoch_length.max(1)
+    }
+
+    /// Whether `block_number` is the last block of its epoch
+    pub fn ends_epoch(&self, block_number: u64) -> bool {
+        self.epoch_of(block_number + 1) != self.epoch_of(block_number)
+    }
+}
+
+impl GasSchedule {
+    /// Gas `transaction` pays before any contract code runs; all that a
+    /// plain transfer pays
+    pub fn intrinsic_gas(&self, transaction: &Transaction) -> u64 {
+        self.intrinsic(transaction.data.len(), transaction.to == CREATE_ADDRESS)
+    }
+}
+
 impl Default for ZkVMConfig {
     fn default() -> Self {
         Self {
@@ -254,27 +643,44 @@ impl Default for ZkVMConfig {
             proof_compression: true,
             parallel_execution: true,
             max_circuits: 16,
+            backend: ProverBackendKind::default(),
+            prover_mode: ProverMode::default(),
+            remote: None,
+            proof_cache: ProofCacheConfig::default(),
         }
     }
 }
 
 
 
-// Custom serialization for Duration to handle UNIX timestamps
+#[derive(Serialize, Deserialize)]
+struct HumanDuration(#[serde(with = "human::duration")] tokio::time::Duration);
+
+#[derive(Serialize, Deserialize)]
+struct HumanByteSize(#[serde(with = "human::byte_size")] usize);
+
+// Custom serialization so config files can write "4s" and "1MB"; the older
+// integer `block_time_secs` field is still accepted
 impl Serialize for ProtocolConfig {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: Serializer,
     {
         use serde::ser::SerializeStruct;
-        let mut state = serializer.serialize_struct("ProtocolConfig", 8)?;
-        state.serialize_field("block_time_secs", &self.block_time.as_secs())?;
-        state.serialize_field("max_block_size", &self.max_block_size)?;
+        let mut state = serializer.serialize_struct("ProtocolConfig", 13)?;
+        state.serialize_field("chain_id", &self.chain_id)?;
+        state.serialize_field("block_time", &HumanDuration(self.block_time))?;
+        state.serialize_field("max_block_size", &HumanByteSize(self.max_block_size))?;
         state.serialize_field("max_transactions_per_block", &self.max_transactions_per_block)?;
         state.serialize_field("min_stake_threshold", &self.min_stake_threshold)?;
         state.serialize_field("slashing_rate", &self.slashing_rate)?;
+        state.serialize_field("proof_deadline", &self.proof_deadline)?;
         state.serialize_field("reward_rate", &self.reward_rate)?;
         state.serialize_field("zkvm_config", &self.zkvm_config)?;
+        state.serialize_field("epoch_schedule", &self.epoch_schedule)?;
+        state.serialize_field("gas_schedule", &self.gas_schedule)?;
+        state.serialize_field("vdf_iterations", &self.vdf_iterations)?;
+        state.serialize_field("execution_backend", &self.execution_backend)?;
         state.end()
     }
 }
@@ -290,13 +696,20 @@ impl<'de> Deserialize<'de> for ProtocolConfig {
         #[derive(Deserialize)]
         #[serde(field_identifier, rename_all = "snake_case")]
         enum Field {
+            ChainId,
+            BlockTime,
             BlockTimeSecs,
             MaxBlockSize,
             MaxTransactionsPerBlock,
             MinStakeThreshold,
             SlashingRate,
+            ProofDeadline,
             RewardRate,
             ZkvmConfig,
+            EpochSchedule,
+            GasSchedule,
+            VdfIterations,
+            ExecutionBackend,
         }
 
         struct ProtocolConfigVisitor;
@@ -312,27 +725,45 @@ impl<'de> Deserialize<'de> for ProtocolConfig {
             where
                 V: MapAccess<'de>,
             {
-                let mut block_time_secs = None;
+                let mut chain_id = None;
+                let mut block_time = None;
                 let mut max_block_size = None;
                 let mut max_transactions_per_block = None;
                 let mut min_stake_threshold = None;
                 let mut slashing_rate = None;
+                let mut proof_deadline = None;
                 let mut reward_rate = None;
                 let mut zkvm_config = None;
+                let mut epoch_schedule = None;
+                let mut gas_schedule = None;
+                let mut vdf_iterations = None;
+                let mut execution_backend = None;
 
                 while let Some(key) = map.next_key()? {
                     match key {
+                        Field::ChainId => {
+                            if chain_id.is_some() {
+                                return Err(de::Error::duplicate_field("chain_id"));
+                            }
+                            chain_id = Some(map.next_value()?);
+                        }
+                        Field::BlockTime => {
+                            if block_time.is_some() {
+                                return Err(de::Error::duplicate_field("block_time"));
+                            }
+                            block_time = Some(map.next_value::<HumanDuration>()?.0);
+                        }
                         Field::BlockTimeSecs => {
-                            if block_time_secs.is_some() {
-                                return Err(de::Error::duplicate_field("block_time_secs"));
+                            if block_time.is_some() {
+                                return Err(de::Error::duplicate_field("block_time"));
                             }
-                            block_time_secs = Some(map.next_value()?);
+                            block_time = Some(tokio::time::Duration::from_secs(map.next_value()?));
                         }
                         Field::MaxBlockSize => {
                             if max_block_size.is_some() {
                                 return Err(de::Error::duplicate_field("max_block_size"));
                             }
-                            max_block_size = Some(map.next_value()?);
+                            max_block_size = Some(map.next_value::<HumanByteSize>()?.0);
                         }
                         Field::MaxTransactionsPerBlock => {
                             if max_transactions_per_block.is_some() {
@@ -352,6 +783,12 @@ impl<'de> Deserialize<'de> for ProtocolConfig {
                             }
                             slashing_rate = Some(map.next_value()?);
                         }
+                        Field::ProofDeadline => {
+                            if proof_deadline.is_some() {
+                                return Err(de::Error::duplicate_field("proof_deadline"));
+                            }
+                            proof_deadline = Some(map.next_value()?);
+                        }
                         Field::RewardRate => {
                             if reward_rate.is_some() {
                                 return Err(de::Error::duplicate_field("reward_rate"));
@@ -364,30 +801,68 @@ impl<'de> Deserialize<'de> for ProtocolConfig {
                             }
                             zkvm_config = Some(map.next_value()?);
                         }
+                        Field::EpochSchedule => {
+                            if epoch_schedule.is_some() {
+                                return Err(de::Error::duplicate_field("epoch_schedule"));
+                            }
+                            epoch_schedule = Some(map.next_value()?);
+                        }
+                        Field::GasSchedule => {
+                            if gas_schedule.is_some() {
+                                return Err(de::Error::duplicate_field("gas_schedule"));
+                            }
+                            gas_schedule = Some(map.next_value()?);
+                        }
+                        Field::VdfIterations => {
+                            if vdf_iterations.is_some() {
+                                return Err(de::Error::duplicate_field("vdf_iterations"));
+                            }
+                            vdf_iterations = Some(map.next_value()?);
+                        }
+                        Field::ExecutionBackend => {
+                            if execution_backend.is_some() {
+                                return Err(de::Error::duplicate_field("execution_backend"));
+                            }
+                            execution_backend = Some(map.next_value()?);
+                        }
                     }
                 }
 
-                let block_time_secs = block_time_secs.ok_or_else(|| de::Error::missing_field("block_time_secs"))?;
+                let block_time = block_time.ok_or_else(|| de::Error::missing_field("block_time"))?;
                 let max_block_size = max_block_size.ok_or_else(|| de::Error::missing_field("max_block_size"))?;
                 let max_transactions_per_block = max_transactions_per_block.ok_or_else(|| de::Error::missing_field("max_transactions_per_block"))?;
                 let min_stake_threshold = min_stake_threshold.ok_or_else(|| de::Error::missing_field("min_stake_threshold"))?;
                 let slashing_rate = slashing_rate.ok_or_else(|| de::Error::missing_field("slashing_rate"))?;
                 let reward_rate = reward_rate.ok_or_else(|| de::Error::missing_field("reward_rate"))?;
                 let zkvm_config = zkvm_config.ok_or_else(|| de::Error::missing_field("zkvm_config"))?;
+                // Configs from before epochs existed run on the default schedule
+                let epoch_schedule = epoch_schedule.unwrap_or_default();
+                let gas_schedule = gas_schedule.unwrap_or_default();
+                let chain_id = chain_id.unwrap_or(DEFAULT_CHAIN_ID);
+                let vdf_iterations = vdf_iterations.unwrap_or(crate::crypto::randomness::DEFAULT_VDF_ITERATIONS);
+                let execution_backend = execution_backend.unwrap_or_default();
+                let proof_deadline = proof_deadline.unwrap_or(crate::consensus::slashing::DEFAULT_PROOF_DEADLINE);
 
                 Ok(ProtocolConfig {
-                    block_time: tokio::time::Duration::from_secs(block_time_secs),
+                    chain_id,
+                    block_time,
                     max_block_size,
                     max_transactions_per_block,
                     min_stake_threshold,
                     slashing_rate,
+                    proof_deadline,
                     reward_rate,
                     zkvm_config,
+                    epoch_schedule,
+                    gas_schedule,
+                    vdf_iterations,
+                    transaction_hooks: Vec::new(),
+                    execution_backend,
                 })
             }
         }
 
-        const FIELDS: &'static [&'static str] = &["block_time_secs", "max_block_size", "max_transactions_per_block", "min_stake_threshold", "slashing_rate", "reward_rate", "zkvm_config"];
+        const FIELDS: &'static [&'static str] = &["chain_id", "block_time", "block_time_secs", "max_block_size", "max_transactions_per_block", "min_stake_threshold", "slashing_rate", "proof_deadline", "reward_rate", "zkvm_config", "epoch_schedule", "gas_schedule", "vdf_iterations", "execution_backend"];
         deserializer.deserialize_struct("ProtocolConfig", FIELDS, ProtocolConfigVisitor)
     }
 } 
\ No newline at end of file
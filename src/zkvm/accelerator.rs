@@ -0,0 +1,65 @@
+//! Prover mode detection
+//!
+//! CUDA and Metal need the host built with the `cuda` or `metal` feature,
+//! which compiles risc0's GPU kernels into the local prover, and a matching
+//! device. Bonsai needs `BONSAI_API_URL` and `BONSAI_API_KEY`. A mode that is
+//! unavailable falls back to the CPU rather than failing to start.
+
+use crate::types::ProverMode;
+use std::path::Path;
+use tracing::warn;
+
+/// Whether an NVIDIA driver is loaded
+fn cuda_device_present() -> bool {
+    ["/dev/nvidiactl", "/proc/driver/nvidia/version"].iter().any(|path| Path::new(path).exists())
+}
+
+impl ProverMode {
+    /// Why this host cannot prove in this mode, or `None` if it can
+    pub fn unavailable(self) -> Option<&'static str> {
+        match self {
+            ProverMode::Cpu => None,
+            ProverMode::Cuda if !cfg!(feature = "cuda") => Some("built without the cuda feature"),
+            ProverMode::Cuda if !cuda_device_present() => Some("no NVIDIA driver found"),
+            ProverMode::Metal if !cfg!(feature = "metal") => Some("built without the metal feature"),
+            ProverMode::Metal if !cfg!(target_os = "macos") => Some("Metal needs macOS"),
+            ProverMode::Bonsai if !cfg!(feature = "risc0") => Some("built without the risc0 feature"),
+            ProverMode::Bonsai if std::env::var_os("BONSAI_API_URL").is_none() || std::env::var_os("BONSAI_API_KEY").is_none() => {
+                Some("BONSAI_API_URL and BONSAI_API_KEY are not both set")
+            }
+            _ => None,
+        }
+    }
+
+    /// This mode if the host supports it, otherwise the CPU
+    pub fn resolve(self) -> ProverMode {
+        match self.unavailable() {
+            Some(reason) => {
+                warn!("⚠️ Prover mode {:?} unavailable ({}), falling back to CPU", self, reason);
+                ProverMode::Cpu
+            }
+            None => self,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unavailable_modes_fall_back_to_cpu() {
+        assert_eq!(ProverMode::Cpu.resolve(), ProverMode::Cpu);
+        temp_env::with_vars_unset(["BONSAI_API_URL", "BONSAI_API_KEY"], || {
+            assert!(ProverMode::Bonsai.unavailable().is_some());
+            assert_eq!(ProverMode::Bonsai.resolve(), ProverMode::Cpu);
+        });
+        if !cfg!(feature = "cuda") {
+            assert_eq!(ProverMode::Cuda.unavailable(), Some("built without the cuda feature"));
+            assert_eq!(ProverMode::Cuda.resolve(), ProverMode::Cpu);
+        }
+
+        let mode: ProverMode = serde_json::from_str(r#""remote""#).unwrap();
+        assert_eq!(mode, ProverMode::Bonsai);
+    }
+}
@@ -0,0 +1,130 @@
+//! Aggregating Risc0 receipts into one succinct receipt
+//!
+//! [`Risc0Executor::aggregate_proofs`] runs the aggregator guest over a batch
+//! of receipts from this crate's guests — state transition proofs or earlier
+//! aggregates — adding each as an assumption that the guest discharges with
+//! `env::verify`. The result is a single succinct receipt whose journal is an
+//! [`AggregateOutput`], so verifying it costs the same for 2 receipts as for
+//! 2000. Without the `risc0` feature the aggregate is the mock output itself.
+
+use super::Risc0Executor;
+use super::error::ProofError;
+use super::programs::aggregation_program::AggregateOutput;
+use crate::fault::{self, FaultPoint};
+use tracing::info;
+
+#[cfg(feature = "risc0")]
+use super::methods::{ZK_SAC_AGGREGATOR_ELF, ZK_SAC_AGGREGATOR_ID, ZK_SAC_GUEST_ID};
+#[cfg(feature = "risc0")]
+use super::programs::aggregation_program::{AggregatedClaim, AggregationInput};
+#[cfg(feature = "risc0")]
+use crate::serialization::framing::{decode_bounded, DecodeLimits, PayloadKind};
+#[cfg(feature = "risc0")]
+use risc0_zkvm::{ExecutorEnv, Prover, ProverOpts, Receipt};
+#[cfg(feature = "risc0")]
+use tracing::warn;
+
+#[cfg(not(feature = "risc0"))]
+use super::programs::aggregation_program::{AggregatedClaim, AggregationInput, aggregate};
+
+/// Image ID of the guest among this crate's that produced `receipt`
+#[cfg(feature = "risc0")]
+fn image_id_of(receipt: &Receipt) -> Option<[u32; 8]> {
+    [ZK_SAC_GUEST_ID, ZK_SAC_AGGREGATOR_ID].into_iter().find(|image_id| receipt.verify(*image_id).is_ok())
+}
+
+#[cfg(feature = "risc0")]
+impl Risc0Executor {
+    /// Aggregate serialized receipts into one succinct receipt
+    pub async fn aggregate_proofs(&self, proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError> {
+        info!("🧩 Aggregating {} Risc0 receipts", proofs.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+        if proofs.is_empty() {
+            return Err(ProofError::NoProofs);
+        }
+
+        let mut env = ExecutorEnv::builder();
+        let mut claims = Vec::with_capacity(proofs.len());
+        for (index, proof_bytes) in proofs.iter().enumerate() {
+            let receipt: Receipt = decode_bounded(PayloadKind::Proof, proof_bytes, &DecodeLimits::default())
+                .map_err(|e| ProofError::Deserialization(format!("proof {}: {}", index, e)))?;
+            let image_id = image_id_of(&receipt)
+                .ok_or(ProofError::NotGuestReceipt(index))?;
+            claims.push(AggregatedClaim { image_id, journal: receipt.journal.bytes.clone() });
+            env.add_assumption(receipt);
+        }
+        let env = env.write(&AggregationInput { claims })
+            .and_then(|env| env.build())
+            .map_err(|e| ProofError::Prover(format!("{:#}", e)))?;
+
+        // A succinct receipt verifies in constant time whatever it covers
+        let prove_info = self.prover.prove_with_opts(env, ZK_SAC_AGGREGATOR_ELF, &ProverOpts::succinct())
+            .map_err(|e| ProofError::Prover(format!("{:#}", e)))?;
+        let proof_bytes = bincode::serialize(&prove_info.receipt)
+            .map_err(|e| ProofError::Serialization(e.to_string()))?;
+        info!("✅ Aggregate proof generated: {} bytes", proof_bytes.len());
+        Ok(proof_bytes)
+    }
+
+    /// The aggregate `proof` commits to, or `None` if it does not verify
+    pub async fn verify_aggregate(&self, proof_bytes: &[u8]) -> Result<Option<AggregateOutput>, ProofError> {
+        info!("🔍 Verifying aggregate proof ({} bytes)", proof_bytes.len());
+        let receipt: Receipt = decode_bounded(PayloadKind::Proof, proof_bytes, &DecodeLimits::default())
+            .map_err(|e| ProofError::Deserialization(e.to_string()))?;
+        match receipt.verify(ZK_SAC_AGGREGATOR_ID) {
+            Ok(()) => Ok(Some(receipt.journal.decode().map_err(|e| ProofError::Malformed(e.to_string()))?)),
+            Err(e) => {
+                warn!("❌ Aggregate proof verification failed: {}", e);
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "risc0"))]
+impl Risc0Executor {
+    /// Mock aggregate over `proofs`, each taken as the journal of a claim
+    pub async fn aggregate_proofs(&self, proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError> {
+        info!("🧩 Mock aggregation of {} proofs", proofs.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+        if proofs.is_empty() {
+            return Err(ProofError::NoProofs);
+        }
+
+        let claims = proofs.into_iter().map(|journal| AggregatedClaim { image_id: [0; 8], journal }).collect();
+        bincode::serialize(&aggregate(&AggregationInput { claims }))
+            .map_err(|e| ProofError::Serialization(e.to_string()))
+    }
+
+    /// The mock aggregate `proof` holds, or `None` if it holds none
+    pub async fn verify_aggregate(&self, proof_bytes: &[u8]) -> Result<Option<AggregateOutput>, ProofError> {
+        info!("🔍 Mock aggregate proof verification ({} bytes)", proof_bytes.len());
+        Ok(bincode::deserialize(proof_bytes).ok())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::zkvm::ProverBackend;
+    use crate::zkvm::programs::aggregation_program::{AggregatedClaim, claims_root};
+    use crate::zkvm::programs::guest_program::StateTransitionInput;
+
+    #[tokio::test]
+    async fn test_aggregate_commits_to_every_proof() {
+        let executor = Risc0Executor::new().unwrap();
+        let mut proofs = Vec::new();
+        for block_number in 0..3 {
+            let input = StateTransitionInput { prev_state_root: [1; 32], block_number, ..StateTransitionInput::default() };
+            proofs.push(executor.generate_state_transition_proof(&input).await.unwrap());
+        }
+
+        let aggregate = executor.aggregate_proofs(proofs.clone()).await.unwrap();
+        let output = executor.verify_aggregate(&aggregate).await.unwrap().unwrap();
+        assert_eq!(output.claim_count, 3);
+        let leaves = proofs.into_iter().map(|journal| AggregatedClaim { image_id: [0; 8], journal }.leaf()).collect();
+        assert_eq!(output.claims_root, claims_root(leaves));
+
+        assert!(matches!(executor.aggregate_proofs(Vec::new()).await, Err(ProofError::NoProofs)));
+    }
+}
@@ -0,0 +1,238 @@
+//! Prover backends
+//!
+//! [`ProverBackend`] is the boundary between the engine and a zkVM: it proves
+//! a state transition, folds proofs into a recursive one and verifies them.
+//! [`Risc0Executor`], [`Sp1Executor`] and [`Plonky3Executor`] implement it,
+//! each proving for real with its feature (`risc0`, `sp1`, `plonky3`) and
+//! returning mock proofs without it.
+//! [`prover_backend`] picks one from [`ZkVMConfig::backend`].
+//! [`ProverBackend::committed_output`] reads back what a proof commits to,
+//! which [`check_committed_output`] holds to the block.
+//!
+//! [`state_transition_input`] builds what the backends prove: a block's
+//! transactions with a witness of the accounts they touch in the state
+//! before it.
+
+use super::programs::guest_program::{StateTransitionInput, StateTransitionOutput, TransactionData};
+use super::error::ProofError;
+use super::{Plonky3Executor, Risc0Executor, Sp1Executor, ZKVMConfig};
+use crate::serialization::canonical::CanonicalEncode;
+use crate::state::state_witness;
+use crate::types::{Address, BlockHash, BlockHeader, GasSchedule, ProofType, ProverBackendKind, Transaction, WorldState, ZkVMConfig};
+use async_trait::async_trait;
+use std::collections::BTreeSet;
+
+#[async_trait]
+pub trait ProverBackend: Send + Sync {
+    /// Proof type recorded for proofs from this backend
+    fn proof_type(&self) -> ProofType;
+    /// Prove the state transition guest on `input`, returning the encoded proof
+    async fn generate_state_transition_proof(&self, input: &StateTransitionInput) -> Result<Vec<u8>, ProofError>;
+    /// Fold encoded proofs into one
+    async fn generate_recursive_proof(&self, proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError>;
+    /// Whether `proof` is a valid state transition proof from this backend;
+    /// aggregates of several proofs do not qualify
+    async fn verify_proof(&self, proof: &[u8]) -> Result<bool, ProofError>;
+    /// Output the guest committed to in `proof`, read from the proof rather
+    /// than derived on the host; `None` for proofs committing to none, as
+    /// mock proofs do
+    fn committed_output(&self, _proof: &[u8]) -> Result<Option<StateTransitionOutput>, ProofError> {
+        Ok(None)
+    }
+}
+
+/// Guest input proving that `transactions`, charged under `schedule`, take
+/// `state`, the state at `header.prev_state_root`, to the block `header`
+/// describes, witnessing every sender, recipient and the producer
+pub fn state_transition_input(state: &WorldState, schedule: &GasSchedule, header: &BlockHeader, transactions: &[Transaction]) -> StateTransitionInput {
+    let touched: BTreeSet<Address> = transactions.iter()
+        .flat_map(|tx| [tx.from, tx.to])
+        .chain([header.producer])
+        .collect();
+    StateTransitionInput {
+        prev_state_root: header.prev_state_root.0,
+        transactions: transactions.iter().map(TransactionData::from).collect(),
+        block_number: header.block_number,
+        timestamp: header.timestamp,
+        producer: header.producer.0,
+        gas_schedule: schedule.clone(),
+        witness: state_witness(state, &touched),
+    }
+}
+
+/// Check that `output`, what a proof commits to, is the successful
+/// transition from `prev_state_root` the block `header` describes: its
+/// transactions root, post-state root and gas, and, when the caller holds
+/// the block's body, its `transaction_count`. The guest only runs plain
+/// transfers, so a block holding anything else cannot carry a proof that
+/// commits to anything.
+pub fn check_committed_output(
+    output: &StateTransitionOutput,
+    prev_state_root: &BlockHash,
+    header: &BlockHeader,
+    transaction_count: Option<usize>,
+) -> Result<(), ProofError> {
+    let mismatch = |field| ProofError::CommitmentMismatch { block_number: header.block_number, field };
+    if output.prev_state_root != prev_state_root.0 {
+        return Err(mismatch("parent state root"));
+    }
+    if output.transactions_root != header.merkle_root.0 {
+        return Err(mismatch("transactions root"));
+    }
+    if transaction_count.is_some_and(|count| output.transaction_count != count as u64) {
+        return Err(mismatch("transaction count"));
+    }
+    if !output.success {
+        return Err(ProofError::FailedTransition);
+    }
+    if output.new_state_root != header.state_root.0 {
+        return Err(mismatch("state root"));
+    }
+    if output.gas_used != header.gas_used {
+        return Err(mismatch("gas used"));
+    }
+    Ok(())
+}
+
+/// Canonical encoding of what follows the nonce in `tx`'s, so the guest
+/// hashes it as [`Transaction::hash`] does
+fn transaction_tail(tx: &Transaction) -> Vec<u8> {
+    let mut tail = Vec::new();
+    tx.chain_id.encode_canonical(&mut tail);
+    tx.access_list.encode_canonical(&mut tail);
+    tx.signature.encode_canonical(&mut tail);
+    tx.sig_type.encode_canonical(&mut tail);
+    tail
+}
+
+impl From<&Transaction> for TransactionData {
+    fn from(tx: &Transaction) -> Self {
+        TransactionData {
+            from: tx.from.0,
+            to: tx.to.0,
+            value: tx.value,
+            nonce: tx.nonce,
+            gas_limit: tx.gas_limit,
+            gas_price: tx.gas_price,
+            data: tx.data.clone(),
+            tail: transaction_tail(tx),
+        }
+    }
+}
+
+/// The backend `config` selects
+pub fn prover_backend(config: &ZkVMConfig) -> Result<Box<dyn ProverBackend>, ProofError> {
+    Ok(match config.backend {
+        ProverBackendKind::Risc0 => Box::new(Risc0Executor::with_config(ZKVMConfig {
+            prover_mode: config.prover_mode,
+            ..ZKVMConfig::default()
+        })?),
+        ProverBackendKind::Sp1 => Box::new(Sp1Executor::new()?),
+        ProverBackendKind::Plonky3 => Box::new(Plonky3Executor::new()?),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::consensus::execution::{BlockContext, execute};
+    use crate::types::*;
+    use crate::zkvm::programs::guest_program::{WitnessNode, verify_state_transition};
+
+    fn header(state: &WorldState, producer: Address) -> BlockHeader {
+        BlockHeader {
+            previous_hash: BlockHash::zero(),
+            merkle_root: BlockHash::zero(),
+            prev_state_root: state.state_root,
+            state_root: BlockHash::zero(),
+            timestamp: 0,
+            block_number: 1,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            producer,
+            extra_data: Vec::new(),
+            randomness: RandomnessProof::default(),
+        }
+    }
+
+    fn funded_state() -> WorldState {
+        let mut state = WorldState::default();
+        for id in 1..=20 {
+            state.accounts.insert(Address::new(id), Account::new(u64::from(id) * 100_000));
+        }
+        state.state_root = state.compute_state_root();
+        state
+    }
+
+    #[tokio::test]
+    async fn test_backend_follows_config() {
+        let state = funded_state();
+        let input = state_transition_input(&state, &GasSchedule::default(), &header(&state, Address::new(1)), &[]);
+        for (kind, proof_type) in [(ProverBackendKind::Risc0, ProofType::Risc0), (ProverBackendKind::Sp1, ProofType::SP1), (ProverBackendKind::Plonky3, ProofType::Plonky3)] {
+            let config = ZkVMConfig { backend: kind, ..ZkVMConfig::default() };
+            let backend = prover_backend(&config).unwrap();
+            assert_eq!(backend.proof_type(), proof_type);
+
+            let proof = backend.generate_state_transition_proof(&input).await.unwrap();
+            assert!(backend.verify_proof(&proof).await.unwrap());
+        }
+
+        let config: ZkVMConfig = serde_json::from_str(r#"{ "backend": "sp1" }"#).unwrap();
+        assert_eq!(config.backend, ProverBackendKind::Sp1);
+    }
+
+    #[test]
+    fn test_guest_reaches_the_executed_state_root() {
+        let state = funded_state();
+        let producer = Address::new(5);
+        let mut with_data = Transaction::new(Address::new(2), Address::new(3), 1_000, 0);
+        with_data.data = vec![7; 40];
+        with_data.gas_limit = 30_000;
+        let transactions = vec![
+            Transaction::new(Address::new(1), Address::new(2), 5_000, 0),
+            with_data,
+            // New recipients and a transfer to self
+            Transaction::new(Address::new(3), Address::new(40), 1, 0),
+            Transaction::new(Address::new(1), Address::new(41), 0, 1),
+            Transaction::new(Address::new(6), Address::new(6), 10, 0),
+        ];
+        let context = BlockContext { number: 1, timestamp: 0, producer };
+        let execution = execute(&state, &GasSchedule::default(), None, &context, &transactions);
+        assert!(execution.rejected().next().is_none());
+
+        let header = header(&state, producer);
+        let input = state_transition_input(&state, &GasSchedule::default(), &header, &transactions);
+        let output = verify_state_transition(input.clone());
+        assert!(output.success);
+        assert_eq!(output.new_state_root, execution.state_root.0);
+        assert_eq!(output.gas_used, execution.gas_used);
+        // The guest hashes the transactions into the header's root itself
+        assert_eq!(output.transactions_root, Block::transactions_root(&transactions).0);
+        let mut resigned = input.clone();
+        resigned.transactions[0].tail.push(0);
+        assert_ne!(verify_state_transition(resigned).transactions_root, output.transactions_root);
+
+        // Both charge a repriced schedule alike
+        let repriced = GasSchedule { transaction_base: 20_000, data_byte: 50, ..GasSchedule::default() };
+        let execution = execute(&state, &repriced, None, &context, &transactions);
+        let output = verify_state_transition(state_transition_input(&state, &repriced, &header, &transactions));
+        assert!(output.success);
+        assert_eq!((output.new_state_root, output.gas_used), (execution.state_root.0, execution.gas_used));
+        assert_ne!(output.gas_used, 5 * 21_000 + 40 * 16);
+
+        // A witness that misstates an account does not match the root
+        let mut forged = input.clone();
+        let WitnessNode::Branch(left, _) = &mut forged.witness.accounts else { panic!("expected a branch") };
+        **left = WitnessNode::Hash([1; 32]);
+        assert!(!verify_state_transition(forged).success);
+
+        // Nor does one missing an account the block touches
+        let mut partial = input;
+        partial.witness = state_witness(&state, &[Address::new(1), Address::new(2)]);
+        assert!(!verify_state_transition(partial).success);
+
+        // A transaction the state does not fund fails
+        let overdraft = [Transaction::new(Address::new(1), Address::new(2), 10_000_000, 0)];
+        assert!(!verify_state_transition(state_transition_input(&state, &GasSchedule::default(), &header, &overdraft)).success);
+    }
+}
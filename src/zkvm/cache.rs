@@ -0,0 +1,205 @@
+//! Cache of verified proofs
+//!
+//! Validating the same block twice — once when producing it, again when it
+//! is gossiped back or replayed — would verify its proof twice. A
+//! [`ProofCache`] remembers the proofs that verified, keyed by the state root
+//! they start from and the Merkle root of the transactions they prove, and
+//! evicts the least recently used once either of its bounds is reached.
+//! Clones share one cache, so the engine and a
+//! [`PerformanceMonitor`](crate::performance::PerformanceMonitor) see the
+//! same hit and miss counters.
+
+use crate::types::{BlockHash, ProofCacheConfig, ZkProof};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tracing::debug;
+
+/// What a state transition proof proves: its transactions applied on top of a state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ProofCacheKey {
+    pub prev_state_root: BlockHash,
+    pub tx_root: BlockHash,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProofCacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    pub entries: usize,
+    pub bytes: usize,
+}
+
+impl ProofCacheStats {
+    /// Fraction of lookups that hit, 0 before any lookup
+    pub fn hit_rate(&self) -> f64 {
+        let lookups = self.hits + self.misses;
+        if lookups == 0 { 0.0 } else { self.hits as f64 / lookups as f64 }
+    }
+
+    /// Counters in Prometheus text exposition format
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        for (name, kind, value) in [
+            ("zksac_proof_cache_hits_total", "counter", self.hits),
+            ("zksac_proof_cache_misses_total", "counter", self.misses),
+            ("zksac_proof_cache_evictions_total", "counter", self.evictions),
+            ("zksac_proof_cache_entries", "gauge", self.entries as u64),
+            ("zksac_proof_cache_bytes", "gauge", self.bytes as u64),
+        ] {
+            out.push_str(&format!("# TYPE {} {}\n{} {}\n", name, kind, name, value));
+        }
+        out
+    }
+}
+
+#[derive(Debug)]
+struct Entry {
+    proof: ZkProof,
+    last_used: u64,
+}
+
+#[derive(Debug)]
+struct State {
+    config: ProofCacheConfig,
+    entries: HashMap<ProofCacheKey, Entry>,
+    /// Keys by the tick they were last used at, least recent first
+    recency: BTreeMap<u64, ProofCacheKey>,
+    tick: u64,
+    stats: ProofCacheStats,
+}
+
+impl State {
+    fn touch(&mut self, key: &ProofCacheKey) {
+        self.tick += 1;
+        let tick = self.tick;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = tick;
+            self.recency.insert(tick, *key);
+        }
+    }
+
+    fn remove(&mut self, key: &ProofCacheKey) -> Option<Entry> {
+        let entry = self.entries.remove(key)?;
+        self.recency.remove(&entry.last_used);
+        self.stats.entries -= 1;
+        self.stats.bytes -= entry.proof.proof_data.len();
+        Some(entry)
+    }
+
+    /// Evict the least recently used proof; false if the cache is empty
+    fn evict_least_recent(&mut self) -> bool {
+        let Some(&key) = self.recency.values().next() else { return false };
+        self.remove(&key);
+        self.stats.evictions += 1;
+        true
+    }
+}
+
+/// Shared LRU cache of proofs that verified
+#[derive(Debug, Clone)]
+pub struct ProofCache {
+    inner: Arc<Mutex<State>>,
+}
+
+impl ProofCache {
+    pub fn new(config: &ProofCacheConfig) -> Self {
+        Self {
+            inner: Arc::new(Mutex::new(State {
+                config: config.clone(),
+                entries: HashMap::new(),
+                recency: BTreeMap::new(),
+                tick: 0,
+                stats: ProofCacheStats::default(),
+            })),
+        }
+    }
+
+    /// Whether `proof` is the proof that verified for `key`; counts a hit or a miss
+    pub fn is_verified(&self, key: &ProofCacheKey, proof: &ZkProof) -> bool {
+        let mut state = self.inner.lock();
+        let hit = state.entries.get(key).is_some_and(|entry| {
+            entry.proof.proof_type == proof.proof_type && entry.proof.proof_data == proof.proof_data
+        });
+        if hit {
+            state.stats.hits += 1;
+            state.touch(key);
+        } else {
+            state.stats.misses += 1;
+        }
+        hit
+    }
+
+    /// Remember that `proof` verified for `key`, evicting the least recently
+    /// used proofs to stay within bounds; proofs larger than the byte bound
+    /// are not kept
+    pub fn insert(&self, key: ProofCacheKey, proof: ZkProof) {
+        let mut state = self.inner.lock();
+        let size = proof.proof_data.len();
+        if state.config.max_entries == 0 || size > state.config.max_bytes {
+            return;
+        }
+        state.remove(&key);
+        while state.stats.entries >= state.config.max_entries || state.stats.bytes + size > state.config.max_bytes {
+            if !state.evict_least_recent() {
+                break;
+            }
+        }
+
+        state.entries.insert(key, Entry { proof, last_used: 0 });
+        state.stats.entries += 1;
+        state.stats.bytes += size;
+        state.touch(&key);
+        debug!("🗃️ Cached verified proof ({} bytes, {} cached)", size, state.stats.entries);
+    }
+
+    pub fn stats(&self) -> ProofCacheStats {
+        self.inner.lock().stats
+    }
+}
+
+impl Default for ProofCache {
+    fn default() -> Self {
+        Self::new(&ProofCacheConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::ProofType;
+
+    fn key(n: u8) -> ProofCacheKey {
+        ProofCacheKey { prev_state_root: BlockHash([n; 32]), tx_root: BlockHash([n + 100; 32]) }
+    }
+
+    fn proof(n: u8, size: usize) -> ZkProof {
+        ZkProof { proof_data: vec![n; size], public_inputs: vec![], verification_key: vec![], proof_type: ProofType::Risc0 }
+    }
+
+    #[test]
+    fn test_evicts_least_recently_used_within_bounds() {
+        let cache = ProofCache::new(&ProofCacheConfig { max_entries: 2, max_bytes: 100 });
+        cache.insert(key(1), proof(1, 10));
+        cache.insert(key(2), proof(2, 10));
+        assert!(cache.is_verified(&key(1), &proof(1, 10)));
+        // Key 2 is now the least recently used
+        cache.insert(key(3), proof(3, 10));
+        assert!(!cache.is_verified(&key(2), &proof(2, 10)));
+        assert!(cache.is_verified(&key(1), &proof(1, 10)));
+
+        // A different proof for a cached key is a miss
+        assert!(!cache.is_verified(&key(3), &proof(4, 10)));
+
+        // The byte bound evicts too, and oversized proofs are not kept
+        cache.insert(key(4), proof(4, 91));
+        cache.insert(key(5), proof(5, 101));
+        let stats = cache.stats();
+        assert_eq!((stats.entries, stats.bytes), (1, 91));
+        assert_eq!((stats.hits, stats.misses, stats.evictions), (2, 2, 3));
+        assert!((stats.hit_rate() - 0.5).abs() < f64::EPSILON);
+    }
+}
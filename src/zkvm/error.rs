@@ -0,0 +1,41 @@
+//! Errors from proving and verifying
+
+use crate::fault::InjectedFault;
+use crate::types::ProofType;
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum ProofError {
+    #[error(transparent)]
+    Injected(#[from] InjectedFault),
+    #[error("proof serialization failed: {0}")]
+    Serialization(String),
+    #[error("proof deserialization failed: {0}")]
+    Deserialization(String),
+    #[error("proving failed: {0}")]
+    Prover(String),
+    #[error("{0}")]
+    Unsupported(&'static str),
+    #[error("no proofs to aggregate")]
+    NoProofs,
+    #[error("proof {0} is not a valid receipt of a ZK-SAC guest")]
+    NotGuestReceipt(usize),
+    #[error("{count} transactions exceed the AIR's limit of {limit}")]
+    TooManyTransactions { count: usize, limit: usize },
+    #[error("transaction {0} fails the signature check, so the transition does not succeed")]
+    FailedTransaction(usize),
+    #[error("the guest rejects the state transition")]
+    FailedTransition,
+    #[error("block {block_number} carries a {found:?} proof, but this node verifies {expected:?} proofs")]
+    WrongProofType { block_number: u64, found: ProofType, expected: ProofType },
+    #[error("proof of block {0} does not verify")]
+    Invalid(u64),
+    #[error("proof of block {0} is for other public inputs than its header commits to")]
+    PublicInputsMismatch(u64),
+    #[error("proof of block {block_number} commits to another {field} than the block")]
+    CommitmentMismatch { block_number: u64, field: &'static str },
+    #[error("malformed proof: {0}")]
+    Malformed(String),
+    #[error("proving task failed: {0}")]
+    Task(String),
+}
@@ -1,25 +1,56 @@
-use crate::types::{Transaction};
-use anyhow::{Result, anyhow};
+use crate::fault::{self, FaultPoint};
+#[cfg(feature = "risc0")]
+use crate::serialization::framing::{decode_bounded, DecodeLimits, PayloadKind};
+use crate::types::{ProofType, ProverMode};
+use async_trait::async_trait;
 use tracing::{info, warn};
 use serde::{Serialize, Deserialize};
 
 #[cfg(feature = "risc0")]
 use risc0_zkvm::{
+    BonsaiProver,
     LocalProver, 
     ExecutorEnv, 
     Receipt, 
     ProverOpts,
     Prover,
-    ProveInfo,
 };
+#[cfg(feature = "risc0")]
+use methods::{ZK_SAC_GUEST_ELF, ZK_SAC_GUEST_ID};
 
+use programs::guest_program::StateTransitionInput;
+#[cfg(feature = "risc0")]
+use programs::guest_program::StateTransitionOutput;
+
+pub mod accelerator;
+pub mod aggregation;
+pub mod backend;
+pub mod cache;
+pub mod error;
+pub mod plonky3;
 pub mod programs;
 pub mod real_proofs;
+pub mod remote;
+pub mod sp1;
+
+pub use backend::{ProverBackend, check_committed_output, prover_backend, state_transition_input};
+pub use error::ProofError;
+pub use plonky3::Plonky3Executor;
+pub use sp1::Sp1Executor;
+
+/// ELFs and image IDs of the guests, built by `build.rs`: `ZK_SAC_GUEST_ELF`
+/// and `ZK_SAC_GUEST_ID` for state transitions, `ZK_SAC_AGGREGATOR_ELF` and
+/// `ZK_SAC_AGGREGATOR_ID` for aggregation
+#[cfg(feature = "risc0")]
+pub mod methods {
+    include!(concat!(env!("OUT_DIR"), "/methods.rs"));
+}
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZKVMConfig {
     pub memory_optimization: String,
-    pub prover_mode: String,
+    #[serde(default)]
+    pub prover_mode: ProverMode,
     pub parallel_execution: bool,
 }
 
@@ -27,7 +58,7 @@ impl Default for ZKVMConfig {
     fn default() -> Self {
         Self {
             memory_optimization: "standard".to_string(),
-            prover_mode: "cpu".to_string(),
+            prover_mode: ProverMode::Cpu,
             parallel_execution: true,
         }
     }
@@ -35,27 +66,29 @@ impl Default for ZKVMConfig {
 
 #[cfg(feature = "risc0")]
 pub struct Risc0Executor {
-    prover: LocalProver,
+    prover: Box<dyn Prover + Send + Sync>,
     config: ZKVMConfig,
 }
 
 #[cfg(feature = "risc0")]
 impl Risc0Executor {
-    pub fn new() -> Result<Self> {
+    pub fn new() -> Result<Self, ProofError> {
         info!("🔬 Initializing Risc0 zkVM executor v2.3.1");
-        
-        let prover = LocalProver::new("local");
-        
-        Ok(Self {
-            prover,
-            config: ZKVMConfig::default(),
-        })
+        Self::with_config(ZKVMConfig::default())
     }
 
-    pub fn with_config(config: ZKVMConfig) -> Result<Self> {
+    /// Executor proving in `config.prover_mode`, or on the CPU if this host
+    /// cannot; the GPU kernels of a `cuda` or `metal` build run locally in
+    /// any mode but Bonsai
+    pub fn with_config(mut config: ZKVMConfig) -> Result<Self, ProofError> {
         info!("🔬 Initializing Risc0 executor v2.3.1 with custom config: {:?}", config);
         
-        let prover = LocalProver::new("local");
+        config.prover_mode = config.prover_mode.resolve();
+        let prover: Box<dyn Prover + Send + Sync> = match config.prover_mode {
+            ProverMode::Bonsai => Box::new(BonsaiProver::new("bonsai")),
+            ProverMode::Cpu | ProverMode::Cuda | ProverMode::Metal => Box::new(LocalProver::new("local")),
+        };
+        info!("⚙️ Risc0 proving mode: {:?}", config.prover_mode);
         
         Ok(Self {
             prover,
@@ -63,72 +96,57 @@ impl Risc0Executor {
         })
     }
 
-    pub async fn generate_state_transition_proof(
-        &self,
-        prev_state: Vec<u8>,
-        transactions: Vec<Transaction>
-    ) -> Result<Vec<u8>> {
-        info!("🔧 Generating state transition proof with Risc0 v2.3.1 for {} transactions", transactions.len());
-        
-        // Create execution environment - serialize data first
-        let prev_state_bytes = bincode::serialize(&prev_state)?;
-        let transactions_bytes = bincode::serialize(&transactions)?;
+    /// Mode proofs are generated in, after fallback
+    pub fn prover_mode(&self) -> ProverMode {
+        self.config.prover_mode
+    }
+}
+
+#[cfg(feature = "risc0")]
+#[async_trait]
+impl ProverBackend for Risc0Executor {
+    fn proof_type(&self) -> ProofType {
+        ProofType::Risc0
+    }
+
+    async fn generate_state_transition_proof(&self, input: &StateTransitionInput) -> Result<Vec<u8>, ProofError> {
+        info!("🔧 Generating state transition proof with Risc0 v2.3.1 for {} transactions", input.transactions.len());
+        fault::check(FaultPoint::ProofGeneration)?;
         
+        // The guest reads its input as one compact frame
         let env = ExecutorEnv::builder()
-            .write(&prev_state_bytes)?
-            .write(&transactions_bytes)?
-            .build()?;
-        
-        // Mock ELF binary for state transition program (minimal valid ELF)
-        let mock_elf = &[0x7f, 0x45, 0x4c, 0x46];
+            .write_frame(&input.encode_compact())
+            .build()
+            .map_err(|e| ProofError::Prover(format!("{:#}", e)))?;
         
-        // Generate proof using Risc0 2.3.1
+        // Generate proof with the guest ELF embedded by build.rs
         let opts = ProverOpts::default();
-        let prove_info = self.prover.prove_with_opts(env, mock_elf, &opts)?;
+        let prove_info = self.prover.prove_with_opts(env, ZK_SAC_GUEST_ELF, &opts)
+            .map_err(|e| ProofError::Prover(format!("{:#}", e)))?;
         
         // Extract receipt and serialize (ProveInfo is not serializable, but Receipt is)
         let proof_bytes = bincode::serialize(&prove_info.receipt)
-            .map_err(|e| anyhow!("Proof serialization failed: {}", e))?;
+            .map_err(|e| ProofError::Serialization(e.to_string()))?;
         
         info!("✅ State transition proof generated: {} bytes", proof_bytes.len());
         Ok(proof_bytes)
     }
 
-    pub async fn generate_recursive_proof(&self, proof_inputs: Vec<Vec<u8>>) -> Result<Vec<u8>> {
-        info!("🔄 Generating recursive proof with Risc0 v2.3.1 for {} inputs", proof_inputs.len());
-        
-        // Create execution environment for recursive proof - serialize data first
-        let proof_inputs_bytes = bincode::serialize(&proof_inputs)?;
-        
-        let env = ExecutorEnv::builder()
-            .write(&proof_inputs_bytes)?
-            .build()?;
-        
-        // Mock ELF for recursive verification program (minimal valid ELF)
-        let mock_elf = &[0x7f, 0x45, 0x4c, 0x46];
-        
-        // Generate recursive proof
-        let opts = ProverOpts::default();
-        let prove_info = self.prover.prove_with_opts(env, mock_elf, &opts)?;
-        
-        // Extract receipt and serialize
-        let proof_bytes = bincode::serialize(&prove_info.receipt)
-            .map_err(|e| anyhow!("Recursive proof serialization failed: {}", e))?;
-        
-        info!("✅ Recursive proof generated: {} bytes", proof_bytes.len());
-        Ok(proof_bytes)
+    async fn generate_recursive_proof(&self, proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError> {
+        info!("🔄 Generating recursive proof with Risc0 v2.3.1 for {} inputs", proofs.len());
+        self.aggregate_proofs(proofs).await
     }
 
-    pub async fn verify_proof(&self, proof_bytes: &[u8]) -> Result<bool> {
+    async fn verify_proof(&self, proof_bytes: &[u8]) -> Result<bool, ProofError> {
         info!("🔍 Verifying Risc0 v2.3.1 proof ({} bytes)", proof_bytes.len());
         
         // Deserialize the receipt
-        let receipt: Receipt = bincode::deserialize(proof_bytes)
-            .map_err(|e| anyhow!("Proof deserialization failed: {}", e))?;
+        let receipt: Receipt = decode_bounded(PayloadKind::Proof, proof_bytes, &DecodeLimits::default())
+            .map_err(|e| ProofError::Deserialization(e.to_string()))?;
         
-        // Verify the receipt using Risc0 2.3.1 API with mock ELF
-        let mock_elf = &[0x7f, 0x45, 0x4c, 0x46];
-        match receipt.verify(risc0_zkvm::compute_image_id(mock_elf)?) {
+        // Only the state transition guest proves blocks; aggregates verify
+        // through `verify_aggregate`
+        match receipt.verify(ZK_SAC_GUEST_ID) {
             Ok(_) => {
                 info!("✅ Proof verification completed: valid");
                 Ok(true)
@@ -139,6 +157,15 @@ impl Risc0Executor {
             }
         }
     }
+
+    fn committed_output(&self, proof_bytes: &[u8]) -> Result<Option<StateTransitionOutput>, ProofError> {
+        let receipt: Receipt = decode_bounded(PayloadKind::Proof, proof_bytes, &DecodeLimits::default())
+            .map_err(|e| ProofError::Deserialization(e.to_string()))?;
+        // The guest commits its output as the whole journal
+        let output = receipt.journal.decode::<StateTransitionOutput>()
+            .map_err(|e| ProofError::Malformed(format!("journal is not a state transition output: {}", e)))?;
+        Ok(Some(output))
+    }
 }
 
 #[cfg(not(feature = "risc0"))]
@@ -148,30 +175,45 @@ pub struct Risc0Executor {
 
 #[cfg(not(feature = "risc0"))]
 impl Risc0Executor {
-    pub fn new() -> Result<Self> {
+    pub fn new() -> Result<Self, ProofError> {
         info!("🔬 Mock Risc0 executor (risc0 feature disabled)");
         Ok(Self {
             config: ZKVMConfig::default(),
         })
     }
 
-    pub fn with_config(config: ZKVMConfig) -> Result<Self> {
+    pub fn with_config(mut config: ZKVMConfig) -> Result<Self, ProofError> {
         info!("🔬 Mock Risc0 executor with config: {:?}", config);
+        config.prover_mode = config.prover_mode.resolve();
         Ok(Self { config })
     }
 
-    pub async fn generate_state_transition_proof(&self, _prev_state: Vec<u8>, transactions: Vec<Transaction>) -> Result<Vec<u8>> {
-        info!("🔧 Mock state transition proof for {} transactions", transactions.len());
-        Ok(vec![0; 32])
+    /// Mode proofs would be generated in, after fallback
+    pub fn prover_mode(&self) -> ProverMode {
+        self.config.prover_mode
+    }
+}
+
+#[cfg(not(feature = "risc0"))]
+#[async_trait]
+impl ProverBackend for Risc0Executor {
+    fn proof_type(&self) -> ProofType {
+        ProofType::Risc0
     }
 
-    pub async fn generate_recursive_proof(&self, proof_inputs: Vec<Vec<u8>>) -> Result<Vec<u8>> {
-        info!("🔄 Mock recursive proof for {} inputs", proof_inputs.len());
+    async fn generate_state_transition_proof(&self, input: &StateTransitionInput) -> Result<Vec<u8>, ProofError> {
+        info!("🔧 Mock state transition proof for {} transactions", input.transactions.len());
+        fault::check(FaultPoint::ProofGeneration)?;
         Ok(vec![0; 32])
     }
 
-    pub async fn verify_proof(&self, proof_bytes: &[u8]) -> Result<bool> {
+    async fn generate_recursive_proof(&self, proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError> {
+        info!("🔄 Mock recursive proof for {} inputs", proofs.len());
+        self.aggregate_proofs(proofs).await
+    }
+
+    async fn verify_proof(&self, proof_bytes: &[u8]) -> Result<bool, ProofError> {
         info!("🔍 Mock proof verification ({} bytes)", proof_bytes.len());
         Ok(true)
     }
-} 
\ No newline at end of file
+}
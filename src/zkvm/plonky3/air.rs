@@ -0,0 +1,358 @@
+//! State transition AIR
+//!
+//! Arithmetizes the transaction checks and gas of [`verify_state_transition`]
+//! for transitions that succeed, and a [`transaction_digest`] binding the
+//! proof to the transactions. Each row holds one transaction and the digest,
+//! as bits, before it; the transaction's bits are XORed into the digest of
+//! the next row. At least one padding row follows the transactions, and the
+//! last row carries the digest, transaction count and gas before
+//! finalization, which the public values pin down. Gas is charged at the
+//! base and per-byte prices of the input's gas schedule, which are public
+//! values too, so the AIR certifies the gas host execution charges under
+//! that schedule. The state witness is
+//! checked by re-running the guest logic, not by the AIR, which has no
+//! Keccak. Trace generation is plain Rust; the constraints need the
+//! `plonky3` feature.
+//!
+//! [`verify_state_transition`]: crate::zkvm::programs::guest_program::verify_state_transition
+
+use crate::zkvm::programs::guest_program::{StateTransitionInput, StateTransitionOutput};
+use crate::zkvm::error::ProofError;
+
+#[cfg(feature = "plonky3")]
+use p3_air::{Air, AirBuilder, AirBuilderWithPublicValues, BaseAir};
+#[cfg(feature = "plonky3")]
+use p3_field::AbstractField;
+#[cfg(feature = "plonky3")]
+use p3_matrix::Matrix;
+
+/// Modulus of the BabyBear field the trace lives in
+pub const BABY_BEAR_MODULUS: u32 = 0x7800_0001;
+
+const ROOT_BITS: usize = 256;
+const ADDRESS_BITS: usize = 160;
+const WORD_BITS: usize = 64;
+/// Low bits of the transaction index; higher bits would wrap the field
+const INDEX_BITS: usize = 30;
+
+// Trace columns
+const IS_TX: usize = 0;
+const ROOT: usize = IS_TX + 1;
+const FROM: usize = ROOT + ROOT_BITS;
+const TO: usize = FROM + ADDRESS_BITS;
+const VALUE: usize = TO + ADDRESS_BITS;
+const NONCE: usize = VALUE + WORD_BITS;
+const INDEX: usize = NONCE + WORD_BITS;
+const INDEX_BIT: usize = INDEX + 1;
+const FROM_INV: usize = INDEX_BIT + INDEX_BITS;
+const TO_INV: usize = FROM_INV + 1;
+const DATA_LEN: usize = TO_INV + 1;
+const GAS: usize = DATA_LEN + 1;
+/// `from ^ to`
+const FROM_TO: usize = GAS + 1;
+/// `from ^ to ^ value` over the bits the index is also mixed into
+const LOW: usize = FROM_TO + ADDRESS_BITS;
+/// Root bits the transaction flips
+const FLIP: usize = LOW + INDEX_BITS;
+pub const WIDTH: usize = FLIP + ADDRESS_BITS;
+
+// Public values
+const PUB_PREV_ROOT: usize = 0;
+const PUB_DIGEST: usize = PUB_PREV_ROOT + ROOT_BITS;
+const PUB_BLOCK_NUMBER: usize = PUB_DIGEST + ROOT_BITS;
+const PUB_TIMESTAMP: usize = PUB_BLOCK_NUMBER + WORD_BITS;
+const PUB_TRANSACTION_COUNT: usize = PUB_TIMESTAMP + WORD_BITS;
+const PUB_GAS_USED: usize = PUB_TRANSACTION_COUNT + 1;
+const PUB_TRANSACTION_BASE: usize = PUB_GAS_USED + 1;
+const PUB_DATA_BYTE: usize = PUB_TRANSACTION_BASE + 1;
+pub const NUM_PUBLIC_VALUES: usize = PUB_DATA_BYTE + 1;
+
+/// Little-endian bits of `bytes`, each byte least significant bit first
+fn bits(bytes: &[u8]) -> impl Iterator<Item = u32> + '_ {
+    bytes.iter().flat_map(|byte| (0..8).map(move |bit| u32::from((byte >> bit) & 1)))
+}
+
+fn reduce(value: u64) -> u32 {
+    (value % u64::from(BABY_BEAR_MODULUS)) as u32
+}
+
+fn inverse(value: u32) -> u32 {
+    let modulus = u64::from(BABY_BEAR_MODULUS);
+    let (mut base, mut exponent, mut result) = (u64::from(value), modulus - 2, 1u64);
+    while exponent > 0 {
+        if exponent & 1 == 1 {
+            result = result * base % modulus;
+        }
+        base = base * base % modulus;
+        exponent >>= 1;
+    }
+    result as u32
+}
+
+/// Trace proving `input`, row-major with [`WIDTH`] canonical field elements
+/// per row; fails if the transition does not succeed
+pub fn generate_trace(input: &StateTransitionInput) -> Result<Vec<u32>, ProofError> {
+    let count = input.transactions.len();
+    if count >= 1 << INDEX_BITS {
+        return Err(ProofError::TooManyTransactions { count, limit: 1 << INDEX_BITS });
+    }
+    // Padding after the last transaction leaves room for its root update
+    let height = (count + 1).next_power_of_two().max(4);
+
+    let mut trace = Vec::with_capacity(height * WIDTH);
+    let mut root: Vec<u32> = bits(&input.prev_state_root).collect();
+    let mut gas = 0u64;
+    for index in 0..height {
+        let mut row = vec![0u32; WIDTH];
+        row[ROOT..FROM].copy_from_slice(&root);
+        row[INDEX] = index.min(count) as u32;
+        row[GAS] = reduce(gas);
+
+        if let Some(tx) = input.transactions.get(index) {
+            let from: Vec<u32> = bits(&tx.from).collect();
+            let to: Vec<u32> = bits(&tx.to).collect();
+            let (from_sum, to_sum) = (from.iter().sum::<u32>(), to.iter().sum::<u32>());
+            if from_sum == 0 || to_sum == 0 {
+                return Err(ProofError::FailedTransaction(index));
+            }
+
+            row[IS_TX] = 1;
+            row[FROM..TO].copy_from_slice(&from);
+            row[TO..VALUE].copy_from_slice(&to);
+            for (column, bit) in (VALUE..NONCE).zip(bits(&tx.value.to_le_bytes())) {
+                row[column] = bit;
+            }
+            for (column, bit) in (NONCE..INDEX).zip(bits(&tx.nonce.to_le_bytes())) {
+                row[column] = bit;
+            }
+            for bit in 0..INDEX_BITS {
+                row[INDEX_BIT + bit] = ((index >> bit) & 1) as u32;
+            }
+            row[FROM_INV] = inverse(from_sum);
+            row[TO_INV] = inverse(to_sum);
+            row[DATA_LEN] = reduce(tx.data.len() as u64);
+            gas += input.gas_schedule.intrinsic(tx.data.len(), false);
+
+            for bit in 0..ADDRESS_BITS {
+                let from_to = from[bit] ^ to[bit];
+                row[FROM_TO + bit] = from_to;
+                row[FLIP + bit] = if bit < INDEX_BITS {
+                    row[LOW + bit] = from_to ^ row[VALUE + bit];
+                    row[LOW + bit] ^ row[INDEX_BIT + bit]
+                } else if bit < WORD_BITS {
+                    from_to ^ row[VALUE + bit]
+                } else if bit < 2 * WORD_BITS {
+                    from_to ^ row[NONCE + bit - WORD_BITS]
+                } else {
+                    from_to
+                };
+                root[bit] ^= row[FLIP + bit];
+            }
+        }
+        trace.extend(row);
+    }
+    Ok(trace)
+}
+
+/// XOR fold of `input`'s transactions, block number and timestamp over its
+/// previous state root, the digest the AIR computes
+pub fn transaction_digest(input: &StateTransitionInput) -> [u8; 32] {
+    let mut digest = input.prev_state_root;
+    for (index, tx) in input.transactions.iter().enumerate() {
+        let mut flip = [0u8; 32];
+        for (byte, (from, to)) in flip.iter_mut().zip(tx.from.iter().zip(&tx.to)) {
+            *byte = from ^ to;
+        }
+        for (i, (value, nonce)) in tx.value.to_le_bytes().into_iter().zip(tx.nonce.to_le_bytes()).enumerate() {
+            flip[i] ^= value;
+            flip[i + 8] ^= nonce;
+        }
+        for (i, byte) in (index as u64).to_le_bytes().into_iter().enumerate() {
+            flip[i] ^= byte;
+        }
+        for (byte, flip) in digest.iter_mut().zip(flip) {
+            *byte ^= flip;
+        }
+    }
+    for (i, (number, time)) in input.block_number.to_le_bytes().into_iter().zip(input.timestamp.to_le_bytes()).enumerate() {
+        digest[i] ^= number;
+        digest[i + 8] ^= time;
+    }
+    digest
+}
+
+/// Public values binding a proof of `input` to its `output`
+pub fn public_values(input: &StateTransitionInput, output: &StateTransitionOutput) -> Vec<u32> {
+    let mut values = Vec::with_capacity(NUM_PUBLIC_VALUES);
+    values.extend(bits(&input.prev_state_root));
+    values.extend(bits(&transaction_digest(input)));
+    values.extend(bits(&input.block_number.to_le_bytes()));
+    values.extend(bits(&input.timestamp.to_le_bytes()));
+    values.push(reduce(output.transaction_count));
+    values.push(reduce(output.gas_used));
+    values.push(reduce(input.gas_schedule.transaction_base));
+    values.push(reduce(input.gas_schedule.data_byte));
+    values
+}
+
+/// AIR of [`generate_trace`]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct StateTransitionAir;
+
+#[cfg(feature = "plonky3")]
+fn xor<E: AbstractField>(a: E, b: E) -> E {
+    a.clone() + b.clone() - (a * b).double()
+}
+
+#[cfg(feature = "plonky3")]
+impl<F> BaseAir<F> for StateTransitionAir {
+    fn width(&self) -> usize {
+        WIDTH
+    }
+}
+
+#[cfg(feature = "plonky3")]
+impl<AB: AirBuilderWithPublicValues> Air<AB> for StateTransitionAir {
+    fn eval(&self, builder: &mut AB) {
+        let main = builder.main();
+        let local: Vec<AB::Expr> = main.row_slice(0).iter().map(|v| (*v).into()).collect();
+        let next: Vec<AB::Expr> = main.row_slice(1).iter().map(|v| (*v).into()).collect();
+        let public: Vec<AB::Expr> = builder.public_values().iter().map(|v| (*v).into()).collect();
+        let is_tx = local[IS_TX].clone();
+
+        // Transactions come first, then at least one padding row
+        builder.assert_bool(is_tx.clone());
+        builder.when_transition().assert_zero(next[IS_TX].clone() * (AB::Expr::one() - is_tx.clone()));
+        builder.when_last_row().assert_zero(is_tx.clone());
+        for column in (ROOT..INDEX).chain(INDEX_BIT..FROM_INV) {
+            builder.assert_bool(local[column].clone());
+        }
+
+        // Index and gas count up over the transactions
+        let index_bits = (0..INDEX_BITS).fold(AB::Expr::zero(), |sum, bit| {
+            sum + local[INDEX_BIT + bit].clone() * AB::Expr::from_canonical_u32(1 << bit)
+        });
+        builder.when(is_tx.clone()).assert_eq(local[INDEX].clone(), index_bits);
+        let gas = public[PUB_TRANSACTION_BASE].clone() + local[DATA_LEN].clone() * public[PUB_DATA_BYTE].clone();
+        builder.when_first_row().assert_zero(local[INDEX].clone());
+        builder.when_first_row().assert_zero(local[GAS].clone());
+        builder.when_transition().assert_eq(next[INDEX].clone(), local[INDEX].clone() + is_tx.clone());
+        builder.when_transition().assert_eq(next[GAS].clone(), local[GAS].clone() + is_tx.clone() * gas);
+        builder.when_last_row().assert_eq(local[INDEX].clone(), public[PUB_TRANSACTION_COUNT].clone());
+        builder.when_last_row().assert_eq(local[GAS].clone(), public[PUB_GAS_USED].clone());
+
+        // Sender and recipient are nonzero
+        for (start, inverse) in [(FROM, FROM_INV), (TO, TO_INV)] {
+            let sum = (start..start + ADDRESS_BITS).fold(AB::Expr::zero(), |sum, column| sum + local[column].clone());
+            builder.when(is_tx.clone()).assert_one(sum * local[inverse].clone());
+        }
+
+        // Transaction hash and index flip root bits
+        for bit in 0..ADDRESS_BITS {
+            let from_to = local[FROM_TO + bit].clone();
+            builder.assert_eq(from_to.clone(), xor(local[FROM + bit].clone(), local[TO + bit].clone()));
+            let mixed = if bit < INDEX_BITS {
+                builder.assert_eq(local[LOW + bit].clone(), xor(from_to, local[VALUE + bit].clone()));
+                xor(local[LOW + bit].clone(), local[INDEX_BIT + bit].clone())
+            } else if bit < WORD_BITS {
+                xor(from_to, local[VALUE + bit].clone())
+            } else if bit < 2 * WORD_BITS {
+                xor(from_to, local[NONCE + bit - WORD_BITS].clone())
+            } else {
+                from_to
+            };
+            builder.assert_eq(local[FLIP + bit].clone(), is_tx.clone() * mixed);
+        }
+        for bit in 0..ROOT_BITS {
+            let root = local[ROOT + bit].clone();
+            builder.when_first_row().assert_eq(root.clone(), public[PUB_PREV_ROOT + bit].clone());
+            let updated = if bit < ADDRESS_BITS { xor(root.clone(), local[FLIP + bit].clone()) } else { root.clone() };
+            builder.when_transition().assert_eq(next[ROOT + bit].clone(), updated);
+
+            // Finalization mixes in the block number and timestamp
+            let finalized = if bit < WORD_BITS {
+                xor(root, public[PUB_BLOCK_NUMBER + bit].clone())
+            } else if bit < 2 * WORD_BITS {
+                xor(root, public[PUB_TIMESTAMP + bit - WORD_BITS].clone())
+            } else {
+                root
+            };
+            builder.when_last_row().assert_eq(public[PUB_DIGEST + bit].clone(), finalized);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::state_witness;
+    use crate::types::{Account, Address, GasSchedule, WorldState};
+    use crate::zkvm::programs::guest_program::{TransactionData, verify_state_transition};
+
+    const PRODUCER: [u8; 20] = [0xff; 20];
+
+    fn input(count: u8) -> StateTransitionInput {
+        let transactions: Vec<TransactionData> = (1..=count)
+            .map(|i| TransactionData { from: [i; 20], to: [i + 1; 20], value: u64::from(i) * 1000, nonce: u64::from(i), gas_limit: 30_000, gas_price: 1, data: vec![0; i as usize], tail: Vec::new() })
+            .collect();
+        let mut state = WorldState::default();
+        for tx in &transactions {
+            state.accounts.insert(Address(tx.from), Account { nonce: tx.nonce, ..Account::new(1_000_000) });
+        }
+        let touched: Vec<Address> = transactions.iter()
+            .flat_map(|tx| [Address(tx.from), Address(tx.to)])
+            .chain([Address(PRODUCER)])
+            .collect();
+        StateTransitionInput {
+            prev_state_root: state.compute_state_root().0,
+            block_number: 42,
+            timestamp: 1_700_000_000,
+            producer: PRODUCER,
+            gas_schedule: GasSchedule::default(),
+            witness: state_witness(&state, &touched),
+            transactions,
+        }
+    }
+
+    fn column(trace: &[u32], row: usize, range: std::ops::Range<usize>) -> Vec<u32> {
+        trace[row * WIDTH..][range].to_vec()
+    }
+
+    #[test]
+    fn test_trace_reaches_the_guest_output() {
+        let repriced = GasSchedule { transaction_base: 25_000, data_byte: 4, ..GasSchedule::default() };
+        for (count, schedule) in [(0, None), (1, None), (3, None), (4, None), (3, Some(repriced))] {
+            let mut input = input(count);
+            if let Some(schedule) = schedule {
+                input.gas_schedule = schedule;
+            }
+            let output = verify_state_transition(input.clone());
+            assert!(output.success);
+            let trace = generate_trace(&input).unwrap();
+            let height = trace.len() / WIDTH;
+            assert!(height.is_power_of_two() && height > usize::from(count));
+
+            let public = public_values(&input, &output);
+            assert_eq!(public.len(), NUM_PUBLIC_VALUES);
+            let last = height - 1;
+            let finalized: Vec<u32> = column(&trace, last, ROOT..FROM).iter().enumerate()
+                .map(|(bit, root)| match bit {
+                    _ if bit < WORD_BITS => root ^ public[PUB_BLOCK_NUMBER + bit],
+                    _ if bit < 2 * WORD_BITS => root ^ public[PUB_TIMESTAMP + bit - WORD_BITS],
+                    _ => *root,
+                })
+                .collect();
+            assert_eq!(finalized, public[PUB_DIGEST..PUB_BLOCK_NUMBER]);
+            assert_eq!(trace[last * WIDTH + INDEX], public[PUB_TRANSACTION_COUNT]);
+            assert_eq!(trace[last * WIDTH + GAS], public[PUB_GAS_USED]);
+        }
+    }
+
+    #[test]
+    fn test_failing_transitions_have_no_trace() {
+        let mut input = input(2);
+        input.transactions[1].to = [0; 20];
+        assert!(!verify_state_transition(input.clone()).success);
+        assert!(generate_trace(&input).is_err());
+    }
+}
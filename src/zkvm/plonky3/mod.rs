@@ -0,0 +1,242 @@
+//! Plonky3 prover backend
+//!
+//! Proves state transitions with a STARK over BabyBear for the
+//! [`StateTransitionAir`], which arithmetizes the guest program's
+//! transaction checks and gas directly rather than running it in a zkVM.
+//! Only transitions that succeed can be proven. With the `plonky3` feature
+//! [`Plonky3Executor`] proves with `p3-uni-stark`; without it, it returns
+//! mock proofs.
+//!
+//! The backend is incomplete. The AIR has no Keccak, so the proof carries
+//! its input, state witness included, and verifiers re-run the guest logic
+//! on it for the state roots: proofs grow with the block and verify in time
+//! linear in it, and the state root is only as sound as that re-execution.
+//! Recursion needs an AIR for the FRI verifier, which is not written, so
+//! [`ProverBackend::generate_recursive_proof`] is unsupported.
+
+pub mod air;
+
+pub use air::StateTransitionAir;
+
+use super::backend::ProverBackend;
+use super::error::ProofError;
+use super::programs::guest_program::StateTransitionInput;
+use crate::fault::{self, FaultPoint};
+use crate::types::ProofType;
+use async_trait::async_trait;
+use tracing::info;
+
+#[cfg(feature = "plonky3")]
+use super::programs::guest_program::{StateTransitionOutput, verify_state_transition};
+#[cfg(feature = "plonky3")]
+use crate::serialization::framing::{decode_bounded, DecodeLimits, PayloadKind};
+#[cfg(feature = "plonky3")]
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "plonky3")]
+use tracing::warn;
+
+#[cfg(feature = "plonky3")]
+use p3_baby_bear::{BabyBear, DiffusionMatrixBabyBear};
+#[cfg(feature = "plonky3")]
+use p3_challenger::DuplexChallenger;
+#[cfg(feature = "plonky3")]
+use p3_commit::ExtensionMmcs;
+#[cfg(feature = "plonky3")]
+use p3_dft::Radix2DitParallel;
+#[cfg(feature = "plonky3")]
+use p3_field::{AbstractField, Field, extension::BinomialExtensionField};
+#[cfg(feature = "plonky3")]
+use p3_fri::{FriConfig, TwoAdicFriPcs};
+#[cfg(feature = "plonky3")]
+use p3_matrix::dense::RowMajorMatrix;
+#[cfg(feature = "plonky3")]
+use p3_merkle_tree::FieldMerkleTreeMmcs;
+#[cfg(feature = "plonky3")]
+use p3_poseidon2::{Poseidon2, Poseidon2ExternalMatrixGeneral};
+#[cfg(feature = "plonky3")]
+use p3_symmetric::{PaddingFreeSponge, TruncatedPermutation};
+#[cfg(feature = "plonky3")]
+use p3_uni_stark::{Proof, StarkConfig, prove, verify};
+#[cfg(feature = "plonky3")]
+use rand::{SeedableRng, rngs::StdRng};
+
+#[cfg(feature = "plonky3")]
+type Val = BabyBear;
+#[cfg(feature = "plonky3")]
+type Challenge = BinomialExtensionField<Val, 4>;
+#[cfg(feature = "plonky3")]
+type Perm = Poseidon2<Val, Poseidon2ExternalMatrixGeneral, DiffusionMatrixBabyBear, 16, 7>;
+#[cfg(feature = "plonky3")]
+type Hash = PaddingFreeSponge<Perm, 16, 8, 8>;
+#[cfg(feature = "plonky3")]
+type Compress = TruncatedPermutation<Perm, 2, 8, 16>;
+#[cfg(feature = "plonky3")]
+type ValMmcs = FieldMerkleTreeMmcs<<Val as Field>::Packing, <Val as Field>::Packing, Hash, Compress, 8>;
+#[cfg(feature = "plonky3")]
+type ChallengeMmcs = ExtensionMmcs<Val, Challenge, ValMmcs>;
+#[cfg(feature = "plonky3")]
+type Challenger = DuplexChallenger<Val, Perm, 16, 8>;
+#[cfg(feature = "plonky3")]
+type Pcs = TwoAdicFriPcs<Val, Radix2DitParallel, ValMmcs, ChallengeMmcs>;
+#[cfg(feature = "plonky3")]
+type Plonky3Config = StarkConfig<Pcs, Challenge, Challenger>;
+
+/// Seed of the Poseidon2 round constants; provers and verifiers must agree on it
+#[cfg(feature = "plonky3")]
+const POSEIDON2_SEED: u64 = 0x7a6b_7361_635f_7033;
+
+/// Log2 of the tallest trace the commitment scheme is set up for
+#[cfg(feature = "plonky3")]
+const MAX_LOG_HEIGHT: usize = 22;
+
+/// A STARK proof with the statement it proves
+#[cfg(feature = "plonky3")]
+#[derive(Serialize, Deserialize)]
+struct Plonky3Proof {
+    input: StateTransitionInput,
+    output: StateTransitionOutput,
+    stark: Vec<u8>,
+}
+
+#[cfg(feature = "plonky3")]
+pub struct Plonky3Executor {
+    config: Plonky3Config,
+    perm: Perm,
+}
+
+#[cfg(feature = "plonky3")]
+impl Plonky3Executor {
+    pub fn new() -> Result<Self, ProofError> {
+        info!("🔬 Initializing Plonky3 STARK executor");
+        let perm = Perm::new_from_rng_128(
+            Poseidon2ExternalMatrixGeneral,
+            DiffusionMatrixBabyBear,
+            &mut StdRng::seed_from_u64(POSEIDON2_SEED),
+        );
+        let val_mmcs = ValMmcs::new(Hash::new(perm.clone()), Compress::new(perm.clone()));
+        let fri_config = FriConfig {
+            log_blowup: 2,
+            num_queries: 60,
+            proof_of_work_bits: 8,
+            mmcs: ChallengeMmcs::new(val_mmcs.clone()),
+        };
+        let pcs = Pcs::new(MAX_LOG_HEIGHT, Radix2DitParallel, val_mmcs, fri_config);
+        Ok(Self { config: Plonky3Config::new(pcs), perm })
+    }
+
+    fn field_elements(values: Vec<u32>) -> Vec<Val> {
+        values.into_iter().map(Val::from_canonical_u32).collect()
+    }
+}
+
+#[cfg(feature = "plonky3")]
+#[async_trait]
+impl ProverBackend for Plonky3Executor {
+    fn proof_type(&self) -> ProofType {
+        ProofType::Plonky3
+    }
+
+    async fn generate_state_transition_proof(&self, input: &StateTransitionInput) -> Result<Vec<u8>, ProofError> {
+        info!("🔧 Generating state transition proof with Plonky3 for {} transactions", input.transactions.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+
+        let limit = (1 << MAX_LOG_HEIGHT) - 1;
+        if input.transactions.len() > limit {
+            return Err(ProofError::TooManyTransactions { count: input.transactions.len(), limit });
+        }
+        let trace = RowMajorMatrix::new(Self::field_elements(air::generate_trace(input)?), air::WIDTH);
+        let output = verify_state_transition(input.clone());
+        if !output.success {
+            return Err(ProofError::FailedTransition);
+        }
+        let public_values = Self::field_elements(air::public_values(input, &output));
+        let mut challenger = Challenger::new(self.perm.clone());
+        let proof = prove(&self.config, &StateTransitionAir, &mut challenger, trace, &public_values);
+
+        let stark = bincode::serialize(&proof)
+            .map_err(|e| ProofError::Serialization(e.to_string()))?;
+        let proof_bytes = bincode::serialize(&Plonky3Proof { input: input.clone(), output, stark })
+            .map_err(|e| ProofError::Serialization(e.to_string()))?;
+        info!("✅ State transition proof generated: {} bytes", proof_bytes.len());
+        Ok(proof_bytes)
+    }
+
+    async fn generate_recursive_proof(&self, proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError> {
+        info!("🔄 Generating recursive proof with Plonky3 for {} inputs", proofs.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+        // Folding needs an AIR for the FRI verifier, which is not written yet
+        Err(ProofError::Unsupported("recursive Plonky3 proofs need a verifier AIR, which is not written yet"))
+    }
+
+    async fn verify_proof(&self, proof_bytes: &[u8]) -> Result<bool, ProofError> {
+        info!("🔍 Verifying Plonky3 proof ({} bytes)", proof_bytes.len());
+        let proof: Plonky3Proof = decode_bounded(PayloadKind::Proof, proof_bytes, &DecodeLimits::default())
+            .map_err(|e| ProofError::Deserialization(e.to_string()))?;
+        if !proof.output.success {
+            warn!("❌ Proof verification failed: claims a failed transition");
+            return Ok(false);
+        }
+        // The AIR does not hash, so the witness is checked natively
+        if verify_state_transition(proof.input.clone()) != proof.output {
+            warn!("❌ Proof verification failed: output does not follow from the witness");
+            return Ok(false);
+        }
+        let stark: Proof<Plonky3Config> = decode_bounded(PayloadKind::Proof, &proof.stark, &DecodeLimits::default())
+            .map_err(|e| ProofError::Deserialization(e.to_string()))?;
+
+        let public_values = Self::field_elements(air::public_values(&proof.input, &proof.output));
+        let mut challenger = Challenger::new(self.perm.clone());
+        match verify(&self.config, &StateTransitionAir, &mut challenger, &stark, &public_values) {
+            Ok(()) => {
+                info!("✅ Proof verification completed: valid");
+                Ok(true)
+            }
+            Err(e) => {
+                warn!("❌ Proof verification failed: {:?}", e);
+                Ok(false)
+            }
+        }
+    }
+
+    fn committed_output(&self, proof_bytes: &[u8]) -> Result<Option<StateTransitionOutput>, ProofError> {
+        let proof: Plonky3Proof = decode_bounded(PayloadKind::Proof, proof_bytes, &DecodeLimits::default())
+            .map_err(|e| ProofError::Deserialization(e.to_string()))?;
+        Ok(Some(proof.output))
+    }
+}
+
+#[cfg(not(feature = "plonky3"))]
+pub struct Plonky3Executor;
+
+#[cfg(not(feature = "plonky3"))]
+impl Plonky3Executor {
+    pub fn new() -> Result<Self, ProofError> {
+        info!("🔬 Mock Plonky3 executor (plonky3 feature disabled)");
+        Ok(Self)
+    }
+}
+
+#[cfg(not(feature = "plonky3"))]
+#[async_trait]
+impl ProverBackend for Plonky3Executor {
+    fn proof_type(&self) -> ProofType {
+        ProofType::Plonky3
+    }
+
+    async fn generate_state_transition_proof(&self, input: &StateTransitionInput) -> Result<Vec<u8>, ProofError> {
+        info!("🔧 Mock Plonky3 state transition proof for {} transactions", input.transactions.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+        Ok(vec![0; 32])
+    }
+
+    async fn generate_recursive_proof(&self, proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError> {
+        info!("🔄 Mock Plonky3 recursive proof for {} inputs", proofs.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+        Ok(vec![0; 32])
+    }
+
+    async fn verify_proof(&self, proof_bytes: &[u8]) -> Result<bool, ProofError> {
+        info!("🔍 Mock Plonky3 proof verification ({} bytes)", proof_bytes.len());
+        Ok(true)
+    }
+}
@@ -0,0 +1,109 @@
+//! Guest program aggregating Risc0 receipts
+//!
+//! The aggregator verifies each claim — an image ID and the journal that
+//! guest committed — with `env::verify`, which the host resolves from the
+//! receipts it adds as assumptions. It commits only the claim count and a
+//! Merkle root over the claims, so the aggregate receipt and its journal stay
+//! the same size however many receipts it covers.
+
+#[cfg(feature = "risc0")]
+use risc0_zkvm::guest::env;
+use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
+
+/// A guest's image ID with the journal it committed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregatedClaim {
+    pub image_id: [u32; 8],
+    pub journal: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AggregationInput {
+    pub claims: Vec<AggregatedClaim>,
+}
+
+/// Journal of the aggregator
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregateOutput {
+    pub claim_count: u64,
+    pub claims_root: [u8; 32],
+}
+
+impl AggregatedClaim {
+    /// Merkle leaf of the claim
+    pub fn leaf(&self) -> [u8; 32] {
+        let mut hasher = Keccak256::new();
+        for word in self.image_id {
+            hasher.update(word.to_le_bytes());
+        }
+        hasher.update(&self.journal);
+        hasher.finalize().into()
+    }
+}
+
+/// Keccak Merkle root of `leaves`, promoting the odd node of a level
+pub fn claims_root(mut leaves: Vec<[u8; 32]>) -> [u8; 32] {
+    if leaves.is_empty() {
+        return [0; 32];
+    }
+    while leaves.len() > 1 {
+        leaves = leaves
+            .chunks(2)
+            .map(|pair| match pair {
+                [left, right] => {
+                    let mut hasher = Keccak256::new();
+                    hasher.update(left);
+                    hasher.update(right);
+                    hasher.finalize().into()
+                }
+                [single] => *single,
+                _ => unreachable!("chunks of two"),
+            })
+            .collect();
+    }
+    leaves[0]
+}
+
+/// Aggregator output for `input`, once its claims are verified
+pub fn aggregate(input: &AggregationInput) -> AggregateOutput {
+    AggregateOutput {
+        claim_count: input.claims.len() as u64,
+        claims_root: claims_root(input.claims.iter().map(AggregatedClaim::leaf).collect()),
+    }
+}
+
+// Guest program entry point
+#[cfg(feature = "risc0")]
+pub fn main() {
+    let input: AggregationInput = env::read();
+    for claim in &input.claims {
+        env::verify(claim.image_id, &claim.journal).expect("host added no receipt for an aggregated claim");
+    }
+    env::commit(&aggregate(&input));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn claim(journal: u8) -> AggregatedClaim {
+        AggregatedClaim { image_id: [7; 8], journal: vec![journal; 4] }
+    }
+
+    #[test]
+    fn test_root_covers_every_claim_in_order() {
+        let claims: Vec<AggregatedClaim> = (0..5).map(claim).collect();
+        let output = aggregate(&AggregationInput { claims: claims.clone() });
+        assert_eq!(output.claim_count, 5);
+
+        let mut reordered = claims.clone();
+        reordered.swap(0, 4);
+        assert_ne!(aggregate(&AggregationInput { claims: reordered }).claims_root, output.claims_root);
+        let mut changed = claims;
+        changed[4].journal.push(0);
+        assert_ne!(aggregate(&AggregationInput { claims: changed }).claims_root, output.claims_root);
+
+        assert_eq!(claims_root(vec![claim(1).leaf()]), claim(1).leaf());
+    }
+}
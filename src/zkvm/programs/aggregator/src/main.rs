@@ -0,0 +1,17 @@
+//! Risc0 guest aggregating receipts of other guests
+//!
+//! `build.rs` compiles this crate alongside the state transition guest. The
+//! logic is the host's `aggregation_program` module, included from source so
+//! host and guest agree on the claims root.
+
+#![no_main]
+
+#[allow(dead_code)]
+#[path = "../../aggregation_program.rs"]
+mod aggregation_program;
+
+risc0_zkvm::guest::entry!(main);
+
+fn main() {
+    aggregation_program::main();
+}
@@ -0,0 +1,52 @@
+//! Gas schedule shared by host execution and the guest programs
+//!
+//! The host charges transactions with this module and the state transition
+//! guest includes it from source, so the gas a proof certifies is computed
+//! by the same code validators charge with. It depends on nothing but serde
+//! so it compiles inside the guests.
+
+use serde::{Deserialize, Serialize};
+
+/// Gas charged for transactions and the most a block may use
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GasSchedule {
+    /// Gas every transaction pays before its data
+    pub transaction_base: u64,
+    /// Gas per byte of transaction data
+    pub data_byte: u64,
+    /// Extra gas a contract creation pays
+    pub contract_creation: u64,
+    /// Most gas the transactions of one block may use
+    pub block_gas_limit: u64,
+    /// Largest transaction data accepted
+    pub max_data_bytes: usize,
+}
+
+impl Default for GasSchedule {
+    fn default() -> Self {
+        Self {
+            transaction_base: 21_000,
+            data_byte: 16,
+            contract_creation: 32_000,
+            block_gas_limit: 30_000_000,
+            max_data_bytes: 128 * 1024, // 128KB, the largest blob
+        }
+    }
+}
+
+impl GasSchedule {
+    /// Gas a transaction carrying `data_len` bytes pays before any contract
+    /// code runs, with the creation surcharge if it `creates` a contract
+    pub fn intrinsic(&self, data_len: usize, creates: bool) -> u64 {
+        let creation = if creates { self.contract_creation } else { 0 };
+        self.transaction_base
+            .saturating_add(self.data_byte.saturating_mul(data_len as u64))
+            .saturating_add(creation)
+    }
+
+    /// Fee for `gas` at `gas_price`
+    pub fn fee(gas: u64, gas_price: u64) -> u64 {
+        gas.saturating_mul(gas_price)
+    }
+}
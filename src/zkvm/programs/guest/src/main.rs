@@ -0,0 +1,23 @@
+//! Risc0 guest proving ZK-SAC state transitions
+//!
+//! `build.rs` compiles this crate to a RISC-V ELF with `risc0-build` when the
+//! host is built with the `risc0` feature. The logic is the host's
+//! `guest_program` module and its `gas` schedule, included from source, so the
+//! outputs the host derives and the ones the guest commits to its journal
+//! cannot drift apart.
+
+#![no_main]
+
+#[allow(dead_code)]
+#[path = "../../gas.rs"]
+mod gas;
+
+#[allow(dead_code)]
+#[path = "../../guest_program.rs"]
+mod guest_program;
+
+risc0_zkvm::guest::entry!(main);
+
+fn main() {
+    guest_program::main();
+}
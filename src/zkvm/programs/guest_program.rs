@@ -1,16 +1,37 @@
 // RISC-V guest program for ZK-SAC state transition verification
 // This will be compiled to RISC-V and executed in Risc0 zkVM
+//
+// The guest has no access to the state, so the host sends the part of the
+// state trie the block touches as a `StateWitness`. The guest checks it
+// against the previous state root, applies the transactions to the accounts
+// it opens and recomputes the root from the updated trie. The hashing mirrors
+// the host's `state::trie` and `state::AccountLeaf`, which the host's tests
+// hold it to. Gas is charged with the `gas` module host execution uses,
+// under the schedule the host sends with the block. The guest hashes the
+// transactions into the block's transactions root as the host's
+// `Block::transactions_root` does, and the journal commits it and the
+// previous state root next to the results, so a proof only verifies for the
+// block whose transactions it ran.
 
 #[cfg(feature = "risc0")]
 use risc0_zkvm::guest::env;
 use serde::{Deserialize, Serialize};
+use sha3::{Digest, Keccak256};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use super::gas::GasSchedule;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct StateTransitionInput {
     pub prev_state_root: [u8; 32],
     pub transactions: Vec<TransactionData>,
     pub block_number: u64,
     pub timestamp: u64,
+    /// Receives the fees
+    pub producer: [u8; 20],
+    /// Schedule the block is charged under
+    pub gas_schedule: GasSchedule,
+    /// Every account the transactions and the producer touch
+    pub witness: StateWitness,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -19,26 +40,277 @@ pub struct TransactionData {
     pub to: [u8; 20],
     pub value: u64,
     pub nonce: u64,
+    pub gas_limit: u64,
+    pub gas_price: u64,
     pub data: Vec<u8>,
+    /// Canonical encoding of the fields the guest does not read, chain id,
+    /// access list and signature, which the transaction hash covers
+    pub tail: Vec<u8>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct StateTransitionOutput {
+    pub prev_state_root: [u8; 32],
+    pub transactions_root: [u8; 32],
     pub new_state_root: [u8; 32],
     pub transaction_count: u64,
     pub gas_used: u64,
     pub success: bool,
 }
 
+/// An account as its state leaf commits to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WitnessAccount {
+    pub address: [u8; 20],
+    pub balance: u64,
+    pub nonce: u64,
+    pub code_hash: [u8; 32],
+    pub storage_root: [u8; 32],
+}
+
+/// Part of the account trie: the paths to the accounts a block touches,
+/// with everything off those paths reduced to its hash
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WitnessNode {
+    #[default]
+    Empty,
+    /// A subtree no touched account is in
+    Hash([u8; 32]),
+    /// Leaf of an untouched account, where a touched one that does not
+    /// exist yet would be inserted
+    Leaf { key: [u8; 32], value: [u8; 32] },
+    /// Leaf of a touched account
+    Account(WitnessAccount),
+    Branch(Box<WitnessNode>, Box<WitnessNode>),
+}
+
+/// What the guest knows of the state before the block
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StateWitness {
+    pub global_nonce: u64,
+    pub accounts: WitnessNode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, thiserror::Error)]
+pub enum CompactCodecError {
+    #[error("input ends {0} bytes early")]
+    Truncated(usize),
+    #[error("{0} trailing bytes after input")]
+    TrailingBytes(usize),
+    #[error("unknown witness node tag {0}")]
+    InvalidNode(u8),
+    #[error("witness nests deeper than a 256-bit key")]
+    WitnessTooDeep,
+}
+
+/// Cursor over the compact layout
+struct CompactReader<'a> {
+    buf: &'a [u8],
+}
+
+impl<'a> CompactReader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], CompactCodecError> {
+        if len > self.buf.len() {
+            return Err(CompactCodecError::Truncated(len - self.buf.len()));
+        }
+        let (head, rest) = self.buf.split_at(len);
+        self.buf = rest;
+        Ok(head)
+    }
+
+    fn array<const N: usize>(&mut self) -> Result<[u8; N], CompactCodecError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.take(N)?);
+        Ok(array)
+    }
+
+    fn u8(&mut self) -> Result<u8, CompactCodecError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, CompactCodecError> {
+        Ok(u32::from_le_bytes(self.array()?))
+    }
+
+    fn u64(&mut self) -> Result<u64, CompactCodecError> {
+        Ok(u64::from_le_bytes(self.array()?))
+    }
+
+    fn node(&mut self, depth: usize) -> Result<WitnessNode, CompactCodecError> {
+        Ok(match self.u8()? {
+            NODE_EMPTY => WitnessNode::Empty,
+            NODE_HASH => WitnessNode::Hash(self.array()?),
+            NODE_LEAF => WitnessNode::Leaf { key: self.array()?, value: self.array()? },
+            NODE_ACCOUNT => WitnessNode::Account(WitnessAccount {
+                address: self.array()?,
+                balance: self.u64()?,
+                nonce: self.u64()?,
+                code_hash: self.array()?,
+                storage_root: self.array()?,
+            }),
+            NODE_BRANCH if depth < KEY_BITS => {
+                let left = self.node(depth + 1)?;
+                WitnessNode::Branch(Box::new(left), Box::new(self.node(depth + 1)?))
+            }
+            NODE_BRANCH => return Err(CompactCodecError::WitnessTooDeep),
+            tag => return Err(CompactCodecError::InvalidNode(tag)),
+        })
+    }
+}
+
+/// Size of the gas schedule in the compact layout
+const COMPACT_SCHEDULE: usize = 8 * 5;
+
+/// Fixed-size part of a transaction in the compact layout
+const COMPACT_TX_HEADER: usize = 20 + 20 + 8 * 4 + 4 + 4;
+
+// Tags of witness nodes in the compact layout
+const NODE_EMPTY: u8 = 0;
+const NODE_HASH: u8 = 1;
+const NODE_LEAF: u8 = 2;
+const NODE_ACCOUNT: u8 = 3;
+const NODE_BRANCH: u8 = 4;
+
+impl WitnessNode {
+    fn encode_compact(&self, out: &mut Vec<u8>) {
+        match self {
+            WitnessNode::Empty => out.push(NODE_EMPTY),
+            WitnessNode::Hash(hash) => {
+                out.push(NODE_HASH);
+                out.extend_from_slice(hash);
+            }
+            WitnessNode::Leaf { key, value } => {
+                out.push(NODE_LEAF);
+                out.extend_from_slice(key);
+                out.extend_from_slice(value);
+            }
+            WitnessNode::Account(account) => {
+                out.push(NODE_ACCOUNT);
+                out.extend_from_slice(&account.address);
+                out.extend_from_slice(&account.balance.to_le_bytes());
+                out.extend_from_slice(&account.nonce.to_le_bytes());
+                out.extend_from_slice(&account.code_hash);
+                out.extend_from_slice(&account.storage_root);
+            }
+            WitnessNode::Branch(left, right) => {
+                out.push(NODE_BRANCH);
+                left.encode_compact(out);
+                right.encode_compact(out);
+            }
+        }
+    }
+}
+
+impl StateTransitionInput {
+    /// Encode in the compact layout read by the guest. Fields are written in
+    /// declaration order as raw little-endian values with `u32` counts:
+    ///
+    /// `prev_state_root | block_number | timestamp | producer |`
+    /// `transaction_base | data_byte | contract_creation | block_gas_limit |`
+    /// `max_data_bytes | tx count |`
+    /// `(from | to | value | nonce | gas_limit | gas_price | data len | data | tail len | tail)* |`
+    /// `global_nonce | witness nodes`
+    ///
+    /// Witness nodes follow in pre-order, each a tag byte and its fields.
+    /// Unlike serde, decoding is a straight walk over the buffer, which keeps
+    /// the guest's cycle count for reading its input proportional to its size.
+    pub fn encode_compact(&self) -> Vec<u8> {
+        let data_len: usize = self.transactions.iter().map(|tx| tx.data.len() + tx.tail.len()).sum();
+        let mut out = Vec::with_capacity(32 + 8 + 8 + 20 + COMPACT_SCHEDULE + 4 + self.transactions.len() * COMPACT_TX_HEADER + data_len + 8);
+        out.extend_from_slice(&self.prev_state_root);
+        out.extend_from_slice(&self.block_number.to_le_bytes());
+        out.extend_from_slice(&self.timestamp.to_le_bytes());
+        out.extend_from_slice(&self.producer);
+        let schedule = &self.gas_schedule;
+        for value in [
+            schedule.transaction_base,
+            schedule.data_byte,
+            schedule.contract_creation,
+            schedule.block_gas_limit,
+            schedule.max_data_bytes as u64,
+        ] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out.extend_from_slice(&(self.transactions.len() as u32).to_le_bytes());
+        for tx in &self.transactions {
+            out.extend_from_slice(&tx.from);
+            out.extend_from_slice(&tx.to);
+            out.extend_from_slice(&tx.value.to_le_bytes());
+            out.extend_from_slice(&tx.nonce.to_le_bytes());
+            out.extend_from_slice(&tx.gas_limit.to_le_bytes());
+            out.extend_from_slice(&tx.gas_price.to_le_bytes());
+            out.extend_from_slice(&(tx.data.len() as u32).to_le_bytes());
+            out.extend_from_slice(&tx.data);
+            out.extend_from_slice(&(tx.tail.len() as u32).to_le_bytes());
+            out.extend_from_slice(&tx.tail);
+        }
+        out.extend_from_slice(&self.witness.global_nonce.to_le_bytes());
+        self.witness.accounts.encode_compact(&mut out);
+        out
+    }
+
+    pub fn decode_compact(bytes: &[u8]) -> Result<Self, CompactCodecError> {
+        let mut reader = CompactReader { buf: bytes };
+        let prev_state_root = reader.array()?;
+        let block_number = reader.u64()?;
+        let timestamp = reader.u64()?;
+        let producer = reader.array()?;
+        let gas_schedule = GasSchedule {
+            transaction_base: reader.u64()?,
+            data_byte: reader.u64()?,
+            contract_creation: reader.u64()?,
+            block_gas_limit: reader.u64()?,
+            max_data_bytes: usize::try_from(reader.u64()?).unwrap_or(usize::MAX),
+        };
+        let count = reader.u32()? as usize;
+        // Every transaction takes at least its header, so a forged count cannot over-allocate
+        let max_count = reader.buf.len() / COMPACT_TX_HEADER;
+        if count > max_count {
+            return Err(CompactCodecError::Truncated((count - max_count).saturating_mul(COMPACT_TX_HEADER)));
+        }
+
+        let mut transactions = Vec::with_capacity(count);
+        for _ in 0..count {
+            let from = reader.array()?;
+            let to = reader.array()?;
+            let value = reader.u64()?;
+            let nonce = reader.u64()?;
+            let gas_limit = reader.u64()?;
+            let gas_price = reader.u64()?;
+            let data_len = reader.u32()? as usize;
+            let data = reader.take(data_len)?.to_vec();
+            let tail_len = reader.u32()? as usize;
+            let tail = reader.take(tail_len)?.to_vec();
+            transactions.push(TransactionData { from, to, value, nonce, gas_limit, gas_price, data, tail });
+        }
+        let global_nonce = reader.u64()?;
+        let accounts = reader.node(0)?;
+        if !reader.buf.is_empty() {
+            return Err(CompactCodecError::TrailingBytes(reader.buf.len()));
+        }
+
+        Ok(StateTransitionInput {
+            prev_state_root,
+            transactions,
+            block_number,
+            timestamp,
+            producer,
+            gas_schedule,
+            witness: StateWitness { global_nonce, accounts },
+        })
+    }
+}
+
 // Guest program entry point
 #[cfg(feature = "risc0")]
 pub fn main() {
-    // Read input from host
-    let input: StateTransitionInput = env::read();
-    
+    // Read the compact input frame written by the host
+    let input = StateTransitionInput::decode_compact(&env::read_frame())
+        .expect("host wrote a malformed state transition input");
+
     // Verify state transition
     let output = verify_state_transition(input);
-    
+
     // Commit the output as public
     env::commit(&output);
 }
@@ -50,99 +322,316 @@ pub fn main() {
     println!("Mock guest program entry point");
 }
 
-fn verify_state_transition(input: StateTransitionInput) -> StateTransitionOutput {
-    let mut new_state_root = input.prev_state_root;
+/// Guest logic, also run on the host to derive expected outputs. The
+/// transition fails if the witness does not match `prev_state_root`, does
+/// not cover an account a transaction touches, the transactions request more
+/// gas than a block may use, or a transaction does not apply; the new root
+/// is then the previous one.
+pub fn verify_state_transition(input: StateTransitionInput) -> StateTransitionOutput {
+    let transaction_count = input.transactions.len() as u64;
+    let StateWitness { global_nonce, accounts: mut trie } = input.witness;
+    let schedule = &input.gas_schedule;
     let mut total_gas_used = 0u64;
-    let mut success = true;
-    
-    // Process each transaction
-    for (i, tx) in input.transactions.iter().enumerate() {
+    let requested = input.transactions.iter().fold(0u64, |total, tx| total.saturating_add(tx.gas_limit));
+    let mut success = state_root(&trie, global_nonce) == input.prev_state_root
+        && requested <= schedule.block_gas_limit;
+
+    // Process each transaction, once the witness is known to be the state's
+    let transactions = if success { &input.transactions[..] } else { &[] };
+    for tx in transactions {
         // Verify transaction signature (simplified)
         if !verify_transaction_signature(tx) {
             success = false;
             break;
         }
-        
-        // Update state root with transaction hash
-        let tx_hash = compute_transaction_hash(tx);
-        new_state_root = update_state_root(new_state_root, tx_hash, i as u64);
-        
-        // Add gas cost (simplified)
-        total_gas_used += 21000 + tx.data.len() as u64 * 16;
-    }
-    
-    // Additional state verification
-    if success {
-        new_state_root = finalize_state_root(new_state_root, input.block_number, input.timestamp);
-    }
-    
+
+        match apply_transaction(&mut trie, schedule, &input.producer, tx) {
+            Some(gas) => total_gas_used += gas,
+            None => {
+                success = false;
+                break;
+            }
+        }
+    }
+
     StateTransitionOutput {
-        new_state_root,
-        transaction_count: input.transactions.len() as u64,
+        prev_state_root: input.prev_state_root,
+        transactions_root: transactions_root(&input.transactions),
+        new_state_root: if success { state_root(&trie, global_nonce) } else { input.prev_state_root },
+        transaction_count,
         gas_used: total_gas_used,
         success,
     }
 }
 
+/// Root of the Blake3 Merkle tree over the transaction hashes, the header's
+/// `merkle_root`; an unpaired node is carried up a level unchanged
+pub fn transactions_root(transactions: &[TransactionData]) -> [u8; 32] {
+    if transactions.is_empty() {
+        return [0; 32];
+    }
+    let mut level: Vec<[u8; 32]> = transactions.iter()
+        .map(|tx| *blake3::hash(&transaction_hash(tx)).as_bytes())
+        .collect();
+    while level.len() > 1 {
+        level = level.chunks(2)
+            .map(|pair| match pair {
+                [left, right] => *blake3::hash(&[left.as_slice(), right.as_slice()].concat()).as_bytes(),
+                [single] => *single,
+                _ => unreachable!("chunks(2)"),
+            })
+            .collect();
+    }
+    level[0]
+}
+
+/// Keccak256 of the transaction's canonical encoding, the host's `Transaction::hash`
+fn transaction_hash(tx: &TransactionData) -> [u8; 32] {
+    keccak256(&[
+        &tx.from,
+        &tx.to,
+        &tx.value.to_le_bytes(),
+        &(tx.data.len() as u64).to_le_bytes(),
+        &tx.data,
+        &tx.gas_limit.to_le_bytes(),
+        &tx.gas_price.to_le_bytes(),
+        &tx.nonce.to_le_bytes(),
+        &tx.tail,
+    ])
+}
+
+/// Whether the guest accepts `tx` at all; it must still apply to the witnessed state
+pub fn accepts_transaction(tx: &TransactionData) -> bool {
+    verify_transaction_signature(tx)
+}
+
 fn verify_transaction_signature(tx: &TransactionData) -> bool {
     // Simplified signature verification
     // In real implementation, this would verify Ed25519/ECDSA signatures
     !tx.from.iter().all(|&b| b == 0) && !tx.to.iter().all(|&b| b == 0)
 }
 
-fn compute_transaction_hash(tx: &TransactionData) -> [u8; 32] {
-    // Simplified hash computation using simple XOR for guest program
-    let mut hash = [0u8; 32];
-    
-    // XOR address data
-    for (i, &byte) in tx.from.iter().enumerate() {
-        if i < 32 { hash[i] ^= byte; }
-    }
-    for (i, &byte) in tx.to.iter().enumerate() {
-        if i < 32 { hash[i] ^= byte; }
-    }
-    
-    // Mix in value and nonce
-    let value_bytes = tx.value.to_le_bytes();
-    let nonce_bytes = tx.nonce.to_le_bytes();
-    for i in 0..8 {
-        if i < 32 { 
-            hash[i] ^= value_bytes[i % 8];
-            hash[i + 8] ^= nonce_bytes[i % 8];
+/// Apply a transfer to `trie` as the host does, returning its gas, or
+/// `None` if it does not apply or the witness does not cover it
+fn apply_transaction(trie: &mut WitnessNode, schedule: &GasSchedule, producer: &[u8; 20], tx: &TransactionData) -> Option<u64> {
+    if tx.data.len() > schedule.max_data_bytes {
+        return None;
+    }
+    // A creation is sent to the zero address, which the signature check refuses
+    let gas = schedule.intrinsic(tx.data.len(), false);
+    if tx.gas_limit < gas {
+        return None;
+    }
+    let fee = GasSchedule::fee(gas, tx.gas_price);
+    let required = tx.value.saturating_add(fee);
+
+    let mut sender = trie.account(&tx.from)?;
+    if sender.nonce != tx.nonce || sender.balance < required {
+        return None;
+    }
+    sender.balance -= required;
+    sender.nonce += 1;
+    trie.insert(sender)?;
+
+    let mut recipient = trie.account(&tx.to)?;
+    recipient.balance = recipient.balance.saturating_add(tx.value);
+    trie.insert(recipient)?;
+
+    if fee > 0 {
+        let mut credited = trie.account(producer)?;
+        credited.balance = credited.balance.saturating_add(fee);
+        trie.insert(credited)?;
+    }
+    Some(gas)
+}
+
+const KEY_BITS: usize = 256;
+const EMPTY_ROOT: [u8; 32] = [0; 32];
+const LEAF_TAG: u8 = 0;
+const NODE_TAG: u8 = 1;
+
+fn keccak256(parts: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    for part in parts {
+        hasher.update(part);
+    }
+    hasher.finalize().into()
+}
+
+fn account_key(address: &[u8; 20]) -> [u8; 32] {
+    keccak256(&[address])
+}
+
+/// Bit `depth` of `key`, most significant first; set means the right child
+fn bit(key: &[u8; 32], depth: usize) -> bool {
+    key[depth / 8] >> (7 - depth % 8) & 1 == 1
+}
+
+fn state_root(trie: &WitnessNode, global_nonce: u64) -> [u8; 32] {
+    keccak256(&[&trie.root(), &global_nonce.to_le_bytes()])
+}
+
+impl WitnessAccount {
+    /// A new account, as the host creates it on first touch
+    fn new(address: [u8; 20]) -> Self {
+        Self { address, balance: 0, nonce: 0, code_hash: keccak256(&[]), storage_root: EMPTY_ROOT }
+    }
+
+    /// Value stored in the state trie for this account
+    fn leaf_value(&self) -> [u8; 32] {
+        keccak256(&[&self.balance.to_le_bytes(), &self.nonce.to_le_bytes(), &self.code_hash, &self.storage_root])
+    }
+}
+
+impl WitnessNode {
+    pub fn root(&self) -> [u8; 32] {
+        match self {
+            WitnessNode::Empty => EMPTY_ROOT,
+            WitnessNode::Hash(hash) => *hash,
+            WitnessNode::Leaf { key, value } => keccak256(&[&[LEAF_TAG], key, value]),
+            WitnessNode::Account(account) => {
+                keccak256(&[&[LEAF_TAG], &account_key(&account.address), &account.leaf_value()])
+            }
+            WitnessNode::Branch(left, right) => keccak256(&[&[NODE_TAG], &left.root(), &right.root()]),
+        }
+    }
+
+    /// The account at `address`, a new one if the witness shows there is
+    /// none, or `None` if the witness does not cover it
+    fn account(&self, address: &[u8; 20]) -> Option<WitnessAccount> {
+        let key = account_key(address);
+        let mut node = self;
+        let mut depth = 0;
+        while let WitnessNode::Branch(left, right) = node {
+            node = if bit(&key, depth) { &**right } else { &**left };
+            depth += 1;
+        }
+        match node {
+            WitnessNode::Empty => Some(WitnessAccount::new(*address)),
+            WitnessNode::Hash(_) => None,
+            WitnessNode::Leaf { key: other, .. } => (*other != key).then(|| WitnessAccount::new(*address)),
+            WitnessNode::Account(account) if account.address == *address => Some(*account),
+            WitnessNode::Account(_) => Some(WitnessAccount::new(*address)),
+            WitnessNode::Branch(..) => unreachable!("descended past every branch"),
+        }
+    }
+
+    /// Write `account` where its key leads, splitting the leaf already
+    /// there if it belongs to another account; `None` if the witness does
+    /// not cover the path
+    fn insert(&mut self, account: WitnessAccount) -> Option<()> {
+        self.insert_at(&account_key(&account.address), account, 0)
+    }
+
+    fn insert_at(&mut self, key: &[u8; 32], account: WitnessAccount, depth: usize) -> Option<()> {
+        let other_key = match self {
+            WitnessNode::Branch(left, right) => {
+                let child = if bit(key, depth) { right } else { left };
+                return child.insert_at(key, account, depth + 1);
+            }
+            WitnessNode::Empty => None,
+            WitnessNode::Hash(_) => return None,
+            WitnessNode::Leaf { key: other, .. } if other == key => return None,
+            WitnessNode::Leaf { key: other, .. } => Some(*other),
+            WitnessNode::Account(other) if other.address == account.address => None,
+            WitnessNode::Account(other) => Some(account_key(&other.address)),
+        };
+        *self = match other_key {
+            None => WitnessNode::Account(account),
+            Some(other_key) => {
+                let other = std::mem::take(self);
+                split(other, &other_key, WitnessNode::Account(account), key, depth)
+            }
+        };
+        Some(())
+    }
+}
+
+/// Subtree at `depth` holding the leaves `a` and `b`, whose distinct keys
+/// agree on the bits above it
+fn split(a: WitnessNode, a_key: &[u8; 32], b: WitnessNode, b_key: &[u8; 32], depth: usize) -> WitnessNode {
+    match (bit(a_key, depth), bit(b_key, depth)) {
+        (false, true) => WitnessNode::Branch(Box::new(a), Box::new(b)),
+        (true, false) => WitnessNode::Branch(Box::new(b), Box::new(a)),
+        (right, _) => {
+            let child = Box::new(split(a, a_key, b, b_key, depth + 1));
+            if right {
+                WitnessNode::Branch(Box::new(WitnessNode::Empty), child)
+            } else {
+                WitnessNode::Branch(child, Box::new(WitnessNode::Empty))
+            }
         }
     }
-    
-    hash
-}
-
-fn update_state_root(current_root: [u8; 32], tx_hash: [u8; 32], tx_index: u64) -> [u8; 32] {
-    let mut new_root = current_root;
-    
-    // Simple state root update by XORing with transaction hash
-    for i in 0..32 {
-        new_root[i] ^= tx_hash[i];
-    }
-    
-    // Mix in transaction index
-    let index_bytes = tx_index.to_le_bytes();
-    for i in 0..8 {
-        new_root[i % 32] ^= index_bytes[i];
-    }
-    
-    new_root
-}
-
-fn finalize_state_root(state_root: [u8; 32], block_number: u64, timestamp: u64) -> [u8; 32] {
-    let mut final_root = state_root;
-    
-    // Mix in block number and timestamp
-    let block_bytes = block_number.to_le_bytes();
-    let time_bytes = timestamp.to_le_bytes();
-    
-    for i in 0..8 {
-        final_root[i] ^= block_bytes[i];
-        final_root[i + 8] ^= time_bytes[i];
-    }
-    
-    final_root
-} 
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_input() -> StateTransitionInput {
+        StateTransitionInput {
+            prev_state_root: [3; 32],
+            transactions: (1..=3u8)
+                .map(|i| TransactionData {
+                    from: [i; 20],
+                    to: [i + 1; 20],
+                    value: u64::from(i) * 100,
+                    nonce: u64::from(i),
+                    gas_limit: 30_000,
+                    gas_price: 1,
+                    data: vec![i; usize::from(i) * 10],
+                    tail: vec![i; 4],
+                })
+                .collect(),
+            block_number: 42,
+            timestamp: 1_700_000_000,
+            producer: [9; 20],
+            gas_schedule: GasSchedule::default(),
+            witness: StateWitness {
+                global_nonce: 7,
+                accounts: WitnessNode::Branch(
+                    Box::new(WitnessNode::Hash([5; 32])),
+                    Box::new(WitnessNode::Account(WitnessAccount::new([1; 20]))),
+                ),
+            },
+        }
+    }
+
+    #[test]
+    fn test_compact_roundtrip_is_smaller_than_bincode() {
+        let input = sample_input();
+        let compact = input.encode_compact();
+        let decoded = StateTransitionInput::decode_compact(&compact).unwrap();
+        assert_eq!(decoded.prev_state_root, input.prev_state_root);
+        assert_eq!(decoded.transactions[1].tail, input.transactions[1].tail);
+        assert_eq!(decoded.block_number, 42);
+        assert_eq!(decoded.transactions.len(), 3);
+        assert_eq!(decoded.transactions[2].data, input.transactions[2].data);
+        assert_eq!(decoded.gas_schedule, input.gas_schedule);
+        assert_eq!(decoded.witness, input.witness);
+        assert_eq!(decoded.encode_compact(), compact);
+
+        assert!(compact.len() < bincode::serialize(&input).unwrap().len());
+    }
+
+    #[test]
+    fn test_compact_rejects_truncated_and_forged_input() {
+        let compact = sample_input().encode_compact();
+        assert!(matches!(StateTransitionInput::decode_compact(&compact[..compact.len() - 1]),
+                         Err(CompactCodecError::Truncated(1))));
+
+        let mut padded = compact.clone();
+        padded.push(0);
+        assert_eq!(StateTransitionInput::decode_compact(&padded).unwrap_err(), CompactCodecError::TrailingBytes(1));
+
+        let mut forged = compact[..68 + COMPACT_SCHEDULE].to_vec();
+        forged.extend_from_slice(&u32::MAX.to_le_bytes());
+        assert!(StateTransitionInput::decode_compact(&forged).is_err());
+
+        // One branch more than a key has bits
+        let mut deep = StateTransitionInput { witness: StateWitness::default(), ..sample_input() }.encode_compact();
+        deep.pop();
+        deep.extend([NODE_BRANCH; KEY_BITS + 1]);
+        assert_eq!(StateTransitionInput::decode_compact(&deep).unwrap_err(), CompactCodecError::WitnessTooDeep);
+    }
+}
@@ -1,5 +1,7 @@
 pub mod state_transition;
+pub mod gas;
 pub mod guest_program;
+pub mod aggregation_program;
 
 // This module contains the RISC-V programs that run inside SP1 zkVM
 // Each program is compiled to RISC-V and then proven using SP1 
\ No newline at end of file
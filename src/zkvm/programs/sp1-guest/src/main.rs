@@ -0,0 +1,24 @@
+//! SP1 guest proving ZK-SAC state transitions
+//!
+//! `build.rs` compiles this crate with `sp1-build` when the host is built
+//! with the `sp1` feature. Like the Risc0 guest, it includes the host's
+//! `guest_program` module from source and commits its output.
+
+#![no_main]
+sp1_zkvm::entrypoint!(main);
+
+#[allow(dead_code)]
+#[path = "../../gas.rs"]
+mod gas;
+
+#[allow(dead_code)]
+#[path = "../../guest_program.rs"]
+mod guest_program;
+
+use guest_program::{StateTransitionInput, verify_state_transition};
+
+pub fn main() {
+    let input = StateTransitionInput::decode_compact(&sp1_zkvm::io::read_vec())
+        .expect("host wrote a malformed state transition input");
+    sp1_zkvm::io::commit(&verify_state_transition(input));
+}
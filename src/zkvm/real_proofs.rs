@@ -1,4 +1,6 @@
-use crate::types::{Transaction, BlockHash};
+use crate::performance::alloc::{self, Subsystem};
+#[cfg(feature = "risc0")]
+use crate::serialization::framing::{decode_bounded, DecodeLimits, PayloadKind};
 use anyhow::{Result, anyhow};
 use tracing::{info, debug, warn};
 use serde::{Serialize, Deserialize};
@@ -10,10 +12,13 @@ use risc0_zkvm::{
     Receipt, 
     ProverOpts,
     Prover,
-    ProveInfo,
 };
+#[cfg(feature = "risc0")]
+use super::methods::{ZK_SAC_GUEST_ELF, ZK_SAC_GUEST_ID};
 
-use super::programs::guest_program::{StateTransitionInput, TransactionData, StateTransitionOutput};
+use super::programs::guest_program::{StateTransitionInput, StateTransitionOutput};
+#[cfg(not(feature = "risc0"))]
+use super::programs::guest_program::verify_state_transition;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ZKProofResult {
@@ -21,6 +26,9 @@ pub struct ZKProofResult {
     pub public_outputs: StateTransitionOutput,
     pub proof_size: usize,
     pub generation_time_ms: u64,
+    /// zkVM cycles executed while proving, zero for mock proofs
+    #[serde(default)]
+    pub cycles: u64,
 }
 
 pub struct RealZKProver {
@@ -41,64 +49,31 @@ impl RealZKProver {
         })
     }
 
-    pub async fn generate_state_transition_proof(
-        &self,
-        prev_state_root: BlockHash,
-        transactions: &[Transaction],
-        block_number: u64,
-        timestamp: u64,
-    ) -> Result<ZKProofResult> {
+    /// Prove `input`, which `zkvm::state_transition_input` builds with the
+    /// witness of the accounts the block touches
+    pub async fn generate_state_transition_proof(&self, input: &StateTransitionInput) -> Result<ZKProofResult> {
+        let _alloc = alloc::enter(Subsystem::Zkvm);
         let start_time = std::time::Instant::now();
         
-        info!("🔧 Generating REAL ZK proof for {} transactions", transactions.len());
-        debug!("   📊 Block: {}, Timestamp: {}", block_number, timestamp);
-        
-        // Convert transactions to guest program format
-        let guest_transactions: Vec<TransactionData> = transactions.iter()
-            .map(|tx| TransactionData {
-                from: tx.from.0,
-                to: tx.to.0,
-                value: tx.value,
-                nonce: tx.nonce,
-                data: tx.data.clone(),
-            })
-            .collect();
-        
-        let input = StateTransitionInput {
-            prev_state_root: prev_state_root.0,
-            transactions: guest_transactions,
-            block_number,
-            timestamp,
-        };
+        info!("🔧 Generating REAL ZK proof for {} transactions", input.transactions.len());
+        debug!("   📊 Block: {}, Timestamp: {}", input.block_number, input.timestamp);
         
         #[cfg(feature = "risc0")]
         {
-            // Create execution environment with serialized input
-            let input_bytes = bincode::serialize(&input)?;
+            // The guest reads its input as one compact frame
+            let input_bytes = input.encode_compact();
+            debug!("   📦 Guest input: {} bytes", input_bytes.len());
             let env = ExecutorEnv::builder()
-                .write(&input_bytes)?
+                .write_frame(&input_bytes)
                 .build()?;
             
-            // Load compiled guest program (in real implementation, this would be a compiled ELF)
-            // For now, we'll use a placeholder - in production, this would be:
-            // const GUEST_ELF: &[u8] = include_bytes!("../../../target/riscv32im-risc0-zkvm-elf/release/guest-program");
-            let guest_elf = self.create_mock_guest_elf();
-            
-            // Generate proof
+            // Prove with the guest ELF embedded by build.rs
             let opts = ProverOpts::default();
-            let prove_info = self.prover.prove_with_opts(env, &guest_elf, &opts)?;
+            let prove_info = self.prover.prove_with_opts(env, ZK_SAC_GUEST_ELF, &opts)?;
             
-            // Extract receipt and public outputs
+            // The public outputs are what the guest committed to its journal
             let receipt_bytes = bincode::serialize(&prove_info.receipt)?;
-            
-            // For now, simulate extracting public outputs from receipt
-            // In real implementation, this would be: prove_info.receipt.journal.decode()?
-            let public_outputs = StateTransitionOutput {
-                new_state_root: self.compute_new_state_root(&input),
-                transaction_count: transactions.len() as u64,
-                gas_used: transactions.iter().map(|tx| tx.gas_limit).sum(),
-                success: true,
-            };
+            let public_outputs: StateTransitionOutput = prove_info.receipt.journal.decode()?;
             
             let generation_time = start_time.elapsed();
             let proof_size = receipt_bytes.len();
@@ -114,24 +89,22 @@ impl RealZKProver {
                 public_outputs,
                 proof_size,
                 generation_time_ms: generation_time.as_millis() as u64,
+                cycles: prove_info.stats.total_cycles,
             })
         }
         
         #[cfg(not(feature = "risc0"))]
         {
             warn!("🚧 Risc0 feature disabled, generating mock proof");
-            let public_outputs = StateTransitionOutput {
-                new_state_root: self.compute_new_state_root(&input),
-                transaction_count: transactions.len() as u64,
-                gas_used: transactions.iter().map(|tx| tx.gas_limit).sum(),
-                success: true,
-            };
+            // What the guest would commit, computed on the host
+            let public_outputs = verify_state_transition(input.clone());
             
             Ok(ZKProofResult {
                 receipt: vec![0; 1024], // Mock receipt
                 public_outputs,
                 proof_size: 1024,
                 generation_time_ms: 1, // Instant mock generation
+                cycles: 0,
             })
         }
     }
@@ -142,19 +115,19 @@ impl RealZKProver {
         #[cfg(feature = "risc0")]
         {
             // Deserialize receipt
-            let receipt: Receipt = bincode::deserialize(&proof_result.receipt)?;
+            let receipt: Receipt = decode_bounded(PayloadKind::Proof, &proof_result.receipt, &DecodeLimits::default())?;
             
-            // Load the same guest ELF for verification
-            let guest_elf = self.create_mock_guest_elf();
-            
-            // Verify receipt
-            let image_id = risc0_zkvm::compute_image_id(&guest_elf)?;
-            match receipt.verify(image_id) {
+            // Verify receipt against the image ID of the embedded guest
+            match receipt.verify(ZK_SAC_GUEST_ID) {
                 Ok(_) => {
                     info!("✅ ZK proof verification successful");
                     
-                    // Additional validation of public outputs
-                    if proof_result.public_outputs.success {
+                    // The claimed outputs must be the ones the guest committed
+                    let committed: StateTransitionOutput = receipt.journal.decode()?;
+                    if committed != proof_result.public_outputs {
+                        warn!("❌ Public outputs do not match the proof's journal");
+                        Ok(false)
+                    } else if committed.success {
                         debug!("   ✅ State transition marked as successful");
                         debug!("   📊 New state root: {:?}", &proof_result.public_outputs.new_state_root[..8]);
                         Ok(true)
@@ -181,65 +154,31 @@ impl RealZKProver {
         &self,
         proof_results: Vec<ZKProofResult>,
     ) -> Result<ZKProofResult> {
+        let _alloc = alloc::enter(Subsystem::Zkvm);
         info!("🔄 Generating recursive ZK proof for {} sub-proofs", proof_results.len());
         
-        let start_time = std::time::Instant::now();
-        
-        // Combine all receipts and public outputs
-        let combined_receipts: Vec<Vec<u8>> = proof_results.iter()
-            .map(|p| p.receipt.clone())
-            .collect();
-        
-        let total_transactions: u64 = proof_results.iter()
-            .map(|p| p.public_outputs.transaction_count)
-            .sum();
-        
-        let total_gas: u64 = proof_results.iter()
-            .map(|p| p.public_outputs.gas_used)
-            .sum();
-        
         #[cfg(feature = "risc0")]
         {
-            // In real recursive proof, we would use a different guest program
-            // that verifies multiple sub-proofs
-            let recursive_input = bincode::serialize(&combined_receipts)?;
-            
-            let env = ExecutorEnv::builder()
-                .write(&recursive_input)?
-                .build()?;
-            
-            let guest_elf = self.create_mock_recursive_elf();
-            let opts = ProverOpts::default();
-            let prove_info = self.prover.prove_with_opts(env, &guest_elf, &opts)?;
-            
-            let receipt_bytes = bincode::serialize(&prove_info.receipt)?;
-            
-            let public_outputs = StateTransitionOutput {
-                new_state_root: self.compute_recursive_state_root(&proof_results),
-                transaction_count: total_transactions,
-                gas_used: total_gas,
-                success: proof_results.iter().all(|p| p.public_outputs.success),
-            };
-            
-            let generation_time = start_time.elapsed();
-            let proof_size = receipt_bytes.len();
-            
-            info!("✅ Recursive ZK proof generated!");
-            info!("   📏 Proof size: {} bytes", proof_size);
-            info!("   ⏱️  Generation time: {:?}", generation_time);
-            info!("   🔢 Total transactions: {}", total_transactions);
-            
-            Ok(ZKProofResult {
-                receipt: receipt_bytes,
-                public_outputs,
-                proof_size,
-                generation_time_ms: generation_time.as_millis() as u64,
-            })
+            // The aggregate commits to a claims root, not a state transition
+            // output; Risc0Executor::aggregate_proofs produces it
+            Err(anyhow!("Recursive proofs over {} receipts have no state transition output; use Risc0Executor::aggregate_proofs", proof_results.len()))
         }
         
         #[cfg(not(feature = "risc0"))]
         {
+            let total_transactions: u64 = proof_results.iter()
+                .map(|p| p.public_outputs.transaction_count)
+                .sum();
+            
+            let total_gas: u64 = proof_results.iter()
+                .map(|p| p.public_outputs.gas_used)
+                .sum();
+            
+            // The fold starts from the first proof's parent and spans the
+            // transactions of several blocks, so it has no one transactions root
             let public_outputs = StateTransitionOutput {
+                prev_state_root: proof_results.first().map_or([0; 32], |p| p.public_outputs.prev_state_root),
+                transactions_root: [0; 32],
                 new_state_root: self.compute_recursive_state_root(&proof_results),
                 transaction_count: total_transactions,
                 gas_used: total_gas,
@@ -251,61 +190,12 @@ impl RealZKProver {
                 public_outputs,
                 proof_size: 2048,
                 generation_time_ms: 2,
+                cycles: 0,
             })
         }
     }
 
-    // Helper methods
-    fn create_mock_guest_elf(&self) -> Vec<u8> {
-        // Try to load the compiled guest program
-        if let Ok(guest_elf_path) = std::env::var("GUEST_ELF_PATH") {
-            if let Ok(elf_data) = std::fs::read(&guest_elf_path) {
-                tracing::info!("✅ Loaded compiled guest program from {}", guest_elf_path);
-                return elf_data;
-            }
-        }
-        
-        // Fallback to mock ELF if compilation failed
-        tracing::warn!("⚠️  Using mock ELF - guest program compilation may have failed");
-        let mut elf = vec![0x7f, 0x45, 0x4c, 0x46]; // ELF magic
-        elf.extend_from_slice(&[2, 1, 1, 0]); // 64-bit, little-endian, current version
-        elf.extend_from_slice(&[0; 8]); // padding
-        elf.extend_from_slice(&[2, 0]); // executable file
-        elf.extend_from_slice(&[0xf3, 0x00]); // RISC-V architecture
-        elf.extend_from_slice(&[1, 0, 0, 0]); // version
-        elf.resize(128, 0); // Minimal ELF header size
-        elf
-    }
-
-    fn create_mock_recursive_elf(&self) -> Vec<u8> {
-        let mut elf = self.create_mock_guest_elf();
-        // Modify to indicate recursive program
-        elf[16] = 0x02; // Different identifier
-        elf
-    }
-
-    fn compute_new_state_root(&self, input: &StateTransitionInput) -> [u8; 32] {
-        use sha3::{Digest, Keccak256};
-        
-        let mut hasher = Keccak256::new();
-        hasher.update(&input.prev_state_root);
-        hasher.update(&input.block_number.to_le_bytes());
-        hasher.update(&input.timestamp.to_le_bytes());
-        
-        // Include transaction hashes
-        for tx in &input.transactions {
-            let mut tx_hasher = Keccak256::new();
-            tx_hasher.update(&tx.from);
-            tx_hasher.update(&tx.to);
-            tx_hasher.update(&tx.value.to_le_bytes());
-            tx_hasher.update(&tx.nonce.to_le_bytes());
-            let tx_hash = tx_hasher.finalize();
-            hasher.update(tx_hash);
-        }
-        
-        hasher.finalize().into()
-    }
-
+    #[cfg(not(feature = "risc0"))]
     fn compute_recursive_state_root(&self, proof_results: &[ZKProofResult]) -> [u8; 32] {
         use sha3::{Digest, Keccak256};
         
@@ -0,0 +1,313 @@
+//! Remote proving service client
+//!
+//! Proving a block locally can take longer than a slot. With a
+//! [`RemoteProofQueue`] the engine produces blocks optimistically, with a
+//! deferred proof, and hands each state transition to a proving service. A
+//! task per job submits it and polls until it finishes; the engine collects
+//! the outcomes and backfills the blocks' proofs. [`HttpProvingService`]
+//! speaks a small Bonsai-style REST API:
+//!
+//! - `POST {endpoint}/v1/jobs` with `{"proof_type", "block_number", "input"}`,
+//!   `input` being the compact guest input in base64, returns `{"id"}`
+//! - `GET {endpoint}/v1/jobs/{id}` returns `{"status", "proof", "error"}`,
+//!   with `status` one of `pending`, `running`, `succeeded` or `failed` and
+//!   `proof` in base64
+
+use super::backend::state_transition_input;
+use super::programs::guest_program::StateTransitionInput;
+use crate::types::{Block, BlockHash, GasSchedule, ProofType, RemoteProverConfig, WorldState};
+use anyhow::{Context, Result, anyhow, bail};
+use async_trait::async_trait;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use serde_json::{Value, json};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use tokio::sync::{Semaphore, mpsc};
+use tracing::{debug, info, warn};
+
+/// A state transition to prove for a block
+#[derive(Debug, Clone)]
+pub struct ProofJob {
+    pub block_number: u64,
+    pub block_hash: BlockHash,
+    pub proof_type: ProofType,
+    pub input: StateTransitionInput,
+}
+
+impl ProofJob {
+    /// Job proving `block`, charged under `schedule`, applied on top of `state`
+    pub fn for_block(state: &WorldState, schedule: &GasSchedule, block: &Block, proof_type: ProofType) -> Self {
+        Self {
+            block_number: block.header.block_number,
+            block_hash: block.header.hash(),
+            proof_type,
+            input: state_transition_input(state, schedule, &block.header, &block.transactions),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum JobStatus {
+    /// Queued or running
+    Pending,
+    Succeeded(Vec<u8>),
+    Failed(String),
+}
+
+#[async_trait]
+pub trait ProvingService: Send + Sync {
+    /// Submit `job`, returning its ID
+    async fn submit(&self, job: &ProofJob) -> Result<String>;
+    async fn status(&self, job_id: &str) -> Result<JobStatus>;
+}
+
+pub struct HttpProvingService {
+    endpoint: String,
+    api_key: Option<String>,
+    client: reqwest::Client,
+}
+
+impl HttpProvingService {
+    pub fn new(endpoint: impl Into<String>) -> Self {
+        Self {
+            endpoint: endpoint.into().trim_end_matches('/').to_string(),
+            api_key: None,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(30))
+                .build()
+                .unwrap_or_default(),
+        }
+    }
+
+    /// Key sent in the `x-api-key` header
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    fn authorized(&self, builder: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.api_key {
+            Some(key) => builder.header("x-api-key", key),
+            None => builder,
+        }
+    }
+}
+
+fn parse_status(response: &Value) -> Result<JobStatus> {
+    match response["status"].as_str() {
+        Some("pending" | "running") => Ok(JobStatus::Pending),
+        Some("succeeded") => {
+            let proof = response["proof"].as_str().ok_or_else(|| anyhow!("Succeeded job without a proof"))?;
+            Ok(JobStatus::Succeeded(BASE64.decode(proof).context("Invalid base64 in proof")?))
+        }
+        Some("failed") => Ok(JobStatus::Failed(response["error"].as_str().unwrap_or("unknown error").to_string())),
+        other => bail!("Unknown job status {:?}", other),
+    }
+}
+
+#[async_trait]
+impl ProvingService for HttpProvingService {
+    async fn submit(&self, job: &ProofJob) -> Result<String> {
+        let request = json!({
+            "proof_type": job.proof_type,
+            "block_number": job.block_number,
+            "input": BASE64.encode(job.input.encode_compact()),
+        });
+        let url = format!("{}/v1/jobs", self.endpoint);
+        let response: Value = self.authorized(self.client.post(&url).json(&request))
+            .send()
+            .await
+            .with_context(|| format!("Job submission to {} failed", url))?
+            .error_for_status()?
+            .json()
+            .await?;
+        response["id"].as_str()
+            .map(str::to_string)
+            .ok_or_else(|| anyhow!("Job submission returned no id"))
+    }
+
+    async fn status(&self, job_id: &str) -> Result<JobStatus> {
+        let url = format!("{}/v1/jobs/{}", self.endpoint, job_id);
+        let response: Value = self.authorized(self.client.get(&url))
+            .send()
+            .await
+            .with_context(|| format!("Job status request to {} failed", url))?
+            .error_for_status()?
+            .json()
+            .await?;
+        parse_status(&response)
+    }
+}
+
+/// How a job ended: the proof, or why there is none
+#[derive(Debug, Clone)]
+pub struct ProofOutcome {
+    pub block_number: u64,
+    pub block_hash: BlockHash,
+    pub result: Result<Vec<u8>, String>,
+}
+
+/// Jobs handed to a [`ProvingService`], each polled by its own task
+pub struct RemoteProofQueue {
+    service: Arc<dyn ProvingService>,
+    poll_interval: Duration,
+    job_timeout: Duration,
+    slots: Arc<Semaphore>,
+    in_flight: Arc<AtomicUsize>,
+    sender: mpsc::UnboundedSender<ProofOutcome>,
+    receiver: mpsc::UnboundedReceiver<ProofOutcome>,
+}
+
+impl RemoteProofQueue {
+    pub fn new(service: Arc<dyn ProvingService>, config: &RemoteProverConfig) -> Self {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        Self {
+            service,
+            poll_interval: config.poll_interval,
+            job_timeout: config.job_timeout,
+            slots: Arc::new(Semaphore::new(config.max_in_flight.max(1))),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            sender,
+            receiver,
+        }
+    }
+
+    /// Queue talking to the HTTP service `config` points at
+    pub fn from_config(config: &RemoteProverConfig) -> Self {
+        info!("🛰️ Remote proving through {}", config.endpoint);
+        let mut service = HttpProvingService::new(&config.endpoint);
+        if let Some(key) = &config.api_key {
+            service = service.with_api_key(key);
+        }
+        Self::new(Arc::new(service), config)
+    }
+
+    /// Start proving `job` in the background; needs a tokio runtime
+    pub fn submit(&self, job: ProofJob) -> Result<()> {
+        let runtime = tokio::runtime::Handle::try_current()
+            .map_err(|_| anyhow!("Remote proving needs a tokio runtime"))?;
+        let (service, slots, in_flight, sender) =
+            (self.service.clone(), self.slots.clone(), self.in_flight.clone(), self.sender.clone());
+        let (poll_interval, job_timeout) = (self.poll_interval, self.job_timeout);
+
+        in_flight.fetch_add(1, Ordering::SeqCst);
+        runtime.spawn(async move {
+            let _slot = slots.acquire_owned().await;
+            let result = match tokio::time::timeout(job_timeout, run_job(service.as_ref(), &job, poll_interval)).await {
+                Ok(result) => result.map_err(|e| format!("{:#}", e)),
+                Err(_) => Err(format!("no proof within {:?}", job_timeout)),
+            };
+            in_flight.fetch_sub(1, Ordering::SeqCst);
+            // The queue may be gone by now; the outcome is of no use then
+            let _ = sender.send(ProofOutcome { block_number: job.block_number, block_hash: job.block_hash, result });
+        });
+        Ok(())
+    }
+
+    /// Jobs submitted and not yet finished
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    /// Outcomes of the jobs that finished since the last call
+    pub fn finished(&mut self) -> Vec<ProofOutcome> {
+        let mut outcomes = Vec::new();
+        while let Ok(outcome) = self.receiver.try_recv() {
+            outcomes.push(outcome);
+        }
+        outcomes
+    }
+}
+
+/// Submit `job` and poll until it finishes; status requests that fail are
+/// retried at the next poll
+async fn run_job(service: &dyn ProvingService, job: &ProofJob, poll_interval: Duration) -> Result<Vec<u8>> {
+    let job_id = service.submit(job).await?;
+    debug!("🛰️ Block {} submitted for remote proving as job {}", job.block_number, job_id);
+    loop {
+        match service.status(&job_id).await {
+            Ok(JobStatus::Pending) => {}
+            Ok(JobStatus::Succeeded(proof)) => {
+                info!("✅ Remote proof for block {} ready: {} bytes", job.block_number, proof.len());
+                return Ok(proof);
+            }
+            Ok(JobStatus::Failed(error)) => bail!("job {} failed: {}", job_id, error),
+            Err(e) => warn!("⚠️ Status of job {} unavailable: {:#}", job_id, e),
+        }
+        tokio::time::sleep(poll_interval).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Proves each job after `polls` status requests; fails blocks numbered `fail`
+    struct ScriptedService {
+        polls: usize,
+        fail: u64,
+        seen: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl ProvingService for ScriptedService {
+        async fn submit(&self, job: &ProofJob) -> Result<String> {
+            Ok(job.block_number.to_string())
+        }
+
+        async fn status(&self, job_id: &str) -> Result<JobStatus> {
+            if self.seen.fetch_add(1, Ordering::SeqCst) % (self.polls + 1) < self.polls {
+                return Ok(JobStatus::Pending);
+            }
+            let block_number: u64 = job_id.parse()?;
+            Ok(if block_number == self.fail {
+                JobStatus::Failed("out of cycles".to_string())
+            } else {
+                JobStatus::Succeeded(vec![block_number as u8; 4])
+            })
+        }
+    }
+
+    fn job(block_number: u64) -> ProofJob {
+        ProofJob {
+            block_number,
+            block_hash: BlockHash([block_number as u8; 32]),
+            proof_type: ProofType::Risc0,
+            input: StateTransitionInput { block_number, ..StateTransitionInput::default() },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queue_polls_jobs_to_completion() {
+        let config = RemoteProverConfig { poll_interval: Duration::from_millis(5), max_in_flight: 1, ..RemoteProverConfig::default() };
+        let service = ScriptedService { polls: 2, fail: 2, seen: AtomicUsize::new(0) };
+        let mut queue = RemoteProofQueue::new(Arc::new(service), &config);
+        queue.submit(job(1)).unwrap();
+        queue.submit(job(2)).unwrap();
+
+        let mut outcomes = Vec::new();
+        for _ in 0..200 {
+            outcomes.extend(queue.finished());
+            if outcomes.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+        outcomes.sort_by_key(|outcome| outcome.block_number);
+        assert_eq!(outcomes[0].result, Ok(vec![1; 4]));
+        assert_eq!(outcomes[0].block_hash, BlockHash([1; 32]));
+        assert!(outcomes[1].result.as_ref().unwrap_err().contains("out of cycles"));
+        assert_eq!(queue.in_flight(), 0);
+    }
+
+    #[test]
+    fn test_parses_job_status() {
+        assert_eq!(parse_status(&json!({ "status": "running" })).unwrap(), JobStatus::Pending);
+        assert_eq!(parse_status(&json!({ "status": "succeeded", "proof": BASE64.encode([9u8; 3]) })).unwrap(), JobStatus::Succeeded(vec![9; 3]));
+        assert_eq!(parse_status(&json!({ "status": "failed", "error": "oom" })).unwrap(), JobStatus::Failed("oom".to_string()));
+        assert!(parse_status(&json!({ "status": "succeeded" })).is_err());
+        assert!(parse_status(&json!({})).is_err());
+    }
+}
@@ -0,0 +1,138 @@
+//! SP1 prover backend
+//!
+//! Proves the same state transition as the Risc0 guest, with the guest in
+//! `src/zkvm/programs/sp1-guest` built by `sp1-build` when the `sp1` feature
+//! is on. Without the feature, [`Sp1Executor`] returns mock proofs.
+
+use super::backend::ProverBackend;
+use super::error::ProofError;
+use super::programs::guest_program::StateTransitionInput;
+#[cfg(feature = "sp1")]
+use super::programs::guest_program::StateTransitionOutput;
+use crate::fault::{self, FaultPoint};
+#[cfg(feature = "sp1")]
+use crate::serialization::framing::{decode_bounded, DecodeLimits, PayloadKind};
+use crate::types::ProofType;
+use async_trait::async_trait;
+use tracing::info;
+#[cfg(feature = "sp1")]
+use tracing::warn;
+
+#[cfg(feature = "sp1")]
+use sp1_sdk::{EnvProver, ProverClient, SP1ProofWithPublicValues, SP1ProvingKey, SP1Stdin, SP1VerifyingKey, include_elf};
+
+/// ELF of the SP1 state transition guest, built by `build.rs`
+#[cfg(feature = "sp1")]
+pub const SP1_GUEST_ELF: &[u8] = include_elf!("zk-sac-sp1-guest");
+
+#[cfg(feature = "sp1")]
+pub struct Sp1Executor {
+    client: EnvProver,
+    proving_key: SP1ProvingKey,
+    verifying_key: SP1VerifyingKey,
+}
+
+#[cfg(feature = "sp1")]
+impl Sp1Executor {
+    /// Set up proving and verifying keys for the guest; the prover (CPU,
+    /// CUDA or network) is chosen by the `SP1_PROVER` environment variable
+    pub fn new() -> Result<Self, ProofError> {
+        info!("🔬 Initializing SP1 zkVM executor");
+        let client = ProverClient::from_env();
+        let (proving_key, verifying_key) = client.setup(SP1_GUEST_ELF);
+        Ok(Self { client, proving_key, verifying_key })
+    }
+}
+
+#[cfg(feature = "sp1")]
+#[async_trait]
+impl ProverBackend for Sp1Executor {
+    fn proof_type(&self) -> ProofType {
+        ProofType::SP1
+    }
+
+    async fn generate_state_transition_proof(&self, input: &StateTransitionInput) -> Result<Vec<u8>, ProofError> {
+        info!("🔧 Generating state transition proof with SP1 for {} transactions", input.transactions.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+
+        // The guest reads its input as one compact frame
+        let mut stdin = SP1Stdin::new();
+        stdin.write_vec(input.encode_compact());
+        let proof = self.client.prove(&self.proving_key, &stdin).compressed().run()
+            .map_err(|e| ProofError::Prover(format!("{:#}", e)))?;
+
+        let proof_bytes = bincode::serialize(&proof)
+            .map_err(|e| ProofError::Serialization(e.to_string()))?;
+        info!("✅ State transition proof generated: {} bytes", proof_bytes.len());
+        Ok(proof_bytes)
+    }
+
+    async fn generate_recursive_proof(&self, proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError> {
+        info!("🔄 Generating recursive proof with SP1 for {} inputs", proofs.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+        // Aggregating compressed proofs needs a guest that verifies them,
+        // which is not built yet
+        Err(ProofError::Unsupported("recursive SP1 proofs need an aggregation guest, which is not built yet"))
+    }
+
+    async fn verify_proof(&self, proof_bytes: &[u8]) -> Result<bool, ProofError> {
+        info!("🔍 Verifying SP1 proof ({} bytes)", proof_bytes.len());
+        let proof: SP1ProofWithPublicValues = decode_bounded(PayloadKind::Proof, proof_bytes, &DecodeLimits::default())
+            .map_err(|e| ProofError::Deserialization(e.to_string()))?;
+        match self.client.verify(&proof, &self.verifying_key) {
+            Ok(()) => {
+                info!("✅ Proof verification completed: valid");
+                Ok(true)
+            }
+            Err(e) => {
+                warn!("❌ Proof verification failed: {}", e);
+                Ok(false)
+            }
+        }
+    }
+
+    fn committed_output(&self, proof_bytes: &[u8]) -> Result<Option<StateTransitionOutput>, ProofError> {
+        let proof: SP1ProofWithPublicValues = decode_bounded(PayloadKind::Proof, proof_bytes, &DecodeLimits::default())
+            .map_err(|e| ProofError::Deserialization(e.to_string()))?;
+        // The guest commits its output with `io::commit`, which writes it with bincode
+        let output = bincode::deserialize(proof.public_values.as_slice())
+            .map_err(|e| ProofError::Malformed(format!("public values are not a state transition output: {}", e)))?;
+        Ok(Some(output))
+    }
+}
+
+#[cfg(not(feature = "sp1"))]
+pub struct Sp1Executor;
+
+#[cfg(not(feature = "sp1"))]
+impl Sp1Executor {
+    pub fn new() -> Result<Self, ProofError> {
+        info!("🔬 Mock SP1 executor (sp1 feature disabled)");
+        Ok(Self)
+    }
+}
+
+#[cfg(not(feature = "sp1"))]
+#[async_trait]
+impl ProverBackend for Sp1Executor {
+    fn proof_type(&self) -> ProofType {
+        ProofType::SP1
+    }
+
+    async fn generate_state_transition_proof(&self, input: &StateTransitionInput) -> Result<Vec<u8>, ProofError> {
+        info!("🔧 Mock SP1 state transition proof for {} transactions", input.transactions.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+        Ok(vec![0; 32])
+    }
+
+    async fn generate_recursive_proof(&self, proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError> {
+        info!("🔄 Mock SP1 recursive proof for {} inputs", proofs.len());
+        fault::check(FaultPoint::ProofGeneration)?;
+        Ok(vec![0; 32])
+    }
+
+    async fn verify_proof(&self, proof_bytes: &[u8]) -> Result<bool, ProofError> {
+        info!("🔍 Mock SP1 proof verification ({} bytes)", proof_bytes.len());
+        Ok(true)
+    }
+}
@@ -1,5 +1,5 @@
 // Basic tests that should compile and run
-use zk_sac_engine::types::{Address, Transaction};
+use zk_sac_engine::types::{Address, Transaction, DEFAULT_CHAIN_ID};
 
 #[test]
 fn test_address_creation() {
@@ -22,6 +22,8 @@ fn test_transaction_creation() {
         value: 1000,
         data: vec![1, 2, 3],
         nonce: 5,
+        chain_id: DEFAULT_CHAIN_ID,
+        access_list: None,
         gas_limit: 21000,
         gas_price: 20,
         signature: vec![0; 64],
@@ -0,0 +1,92 @@
+//! Chaos tests: run with `cargo test --features fault-injection --test chaos_tests`
+
+#![cfg(feature = "fault-injection")]
+
+use serial_test::serial;
+use std::time::Duration;
+use zk_sac_engine::fault::{self, FaultConfig, FaultPoint};
+use zk_sac_engine::node::{BlockLog, Devnet, DevnetConfig};
+use zk_sac_engine::serialization::DecodeLimits;
+use zk_sac_engine::types::*;
+
+fn block(number: u64) -> Block {
+    Block {
+        header: BlockHeader {
+            previous_hash: BlockHash([number as u8; 32]),
+            merkle_root: BlockHash::zero(),
+            prev_state_root: BlockHash::zero(),
+            state_root: BlockHash::zero(),
+            timestamp: 1_700_000_000 + number,
+            block_number: number,
+            gas_limit: 30_000_000,
+            gas_used: 0,
+            producer: Address::new(1),
+            extra_data: Vec::new(),
+            randomness: RandomnessProof::default(),
+        },
+        transactions: Vec::new(),
+        validator_signatures: Vec::new(),
+        recursive_proof: ZkProof { proof_data: Vec::new(), public_inputs: vec![], verification_key: vec![], proof_type: ProofType::Deferred },
+        protocol_updates: Vec::new(),
+        proof_signature: Vec::new(),
+        aggregate_signature: None,
+    }
+}
+
+#[tokio::test]
+#[serial(faults)]
+async fn test_devnet_recovers_from_injected_faults() {
+    let _faults = fault::install(FaultConfig::default()
+        .with_seed(42)
+        .with_probability(FaultPoint::ProofGeneration, 0.3)
+        .with_probability(FaultPoint::GossipCorruption, 0.1)
+        .with_probability(FaultPoint::SignatureCollection, 0.1)
+        .with_signature_delay(Duration::from_millis(60)));
+
+    let config = DevnetConfig::default()
+        .with_validators(4)
+        .with_block_time(Duration::from_millis(40));
+    let devnet = Devnet::start(config).unwrap();
+    devnet.wait_for_height(20, Duration::from_secs(20)).await.unwrap();
+
+    // Safety: every node holds the same chain, linked block to block
+    let reference = &devnet.nodes()[0];
+    let mut previous = BlockHash::zero();
+    for number in 1..=20 {
+        let expected = reference.block(number).await.unwrap();
+        if number > 1 {
+            assert_eq!(expected.header.previous_hash, previous, "block {} does not extend its parent", number);
+        }
+        previous = expected.header.hash();
+        for node in devnet.nodes() {
+            let block = node.block(number).await.unwrap();
+            assert_eq!(block.header.hash(), expected.header.hash(), "node {} diverged at block {}", node.id, number);
+        }
+    }
+
+    assert!(fault::injected(FaultPoint::ProofGeneration) > 0);
+    assert!(fault::injected(FaultPoint::GossipCorruption) > 0);
+    devnet.shutdown().await.unwrap();
+}
+
+#[tokio::test]
+#[serial(faults)]
+async fn test_block_log_survives_failed_writes() {
+    let path = std::env::temp_dir().join(format!("zksac-chaos-{}.log", uuid::Uuid::new_v4()));
+    let log = BlockLog::new(&path, DecodeLimits::default());
+    log.append(&block(1)).await.unwrap();
+
+    {
+        let _faults = fault::install(FaultConfig::default().with_probability(FaultPoint::StorageWrite, 1.0));
+        let error = log.append(&block(2)).await.unwrap_err();
+        assert!(error.to_string().contains("storage write"), "{}", error);
+        assert_eq!(fault::injected(FaultPoint::StorageWrite), 1);
+    }
+
+    // The failed write left nothing behind, and the log accepts the retry
+    log.append(&block(2)).await.unwrap();
+    let numbers: Vec<u64> = log.read_all().await.unwrap().iter().map(|block| block.header.block_number).collect();
+    assert_eq!(numbers, vec![1, 2]);
+
+    std::fs::remove_file(&path).unwrap();
+}
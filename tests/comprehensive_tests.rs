@@ -1,7 +1,24 @@
-use zk_sac_engine::consensus::engine::{ZkSacConsensusEngine, ConsensusEngine};
+use zk_sac_engine::async_utils::Deadline;
+use zk_sac_engine::consensus::engine::{ZkSacConsensusEngine, ConsensusEngine, select_weighted, selection_randomness};
+use zk_sac_engine::consensus::finality::{Attestation, FinalityError};
+use zk_sac_engine::consensus::{BlockImport, ConsensusError, ConsensusEvent, ExecutionError, Offense, SlashingEvidence, StakingAction, ValidatorEvent};
+use zk_sac_engine::bridge::{self, BRIDGE_ADDRESS, BridgeAction, BridgeConfig, BridgeError, DepositMessage, MockSettlement, SettlementBridge, WithdrawalMessage};
+use zk_sac_engine::consensus::slashing::PROOF_ADDRESS;
+use zk_sac_engine::consensus::staking::STAKING_ADDRESS;
+use zk_sac_engine::crypto::bls::{self, BlsKeyPair};
+use zk_sac_engine::crypto::keystore::KeyPair;
+use zk_sac_engine::crypto::randomness::beacon_randomness;
+use zk_sac_engine::crypto::signatures::PostQuantumSigner;
+use zk_sac_engine::light_client::{HeaderUpdate, LightClient};
+use zk_sac_engine::mempool::TxValidationError;
+use zk_sac_engine::state::SnapshotError;
 use zk_sac_engine::types::*;
+use zk_sac_engine::EngineError;
+use zk_sac_engine::zkvm::{ProofError, ProverBackend, state_transition_input};
+use zk_sac_engine::zkvm::programs::guest_program::{StateTransitionInput, StateTransitionOutput, verify_state_transition};
 use zk_sac_engine::zkvm::real_proofs::{RealZKProver, ZKProofResult};
-use zk_sac_engine::performance::{PerformanceMonitor, PerformanceTest};
+use zk_sac_engine::zkvm::remote::{JobStatus, ProofJob, ProvingService, RemoteProofQueue};
+use zk_sac_engine::performance::{ErrorCategory, ErrorEvent, Operation, PerformanceMonitor, PerformanceTest};
 use std::collections::HashMap;
 use tokio::time::{timeout, Duration};
 use tracing_test::traced_test;
@@ -20,8 +37,11 @@ async fn test_real_zk_proof_generation() -> Result<(), Box<dyn std::error::Error
             to: Address::new(2),
             value: 1000,
             data: vec![0x01, 0x02, 0x03],
-            gas_limit: 21000,
+            gas_limit: 30_000,
+            gas_price: 1,
             nonce: 0,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             signature: vec![0; 64],
             sig_type: SignatureType::Ed25519,
         },
@@ -30,26 +50,23 @@ async fn test_real_zk_proof_generation() -> Result<(), Box<dyn std::error::Error
             to: Address::new(3),
             value: 500,
             data: vec![0x04, 0x05, 0x06],
-            gas_limit: 21000,
+            gas_limit: 30_000,
+            gas_price: 1,
             nonce: 1,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             signature: vec![0; 64],
             sig_type: SignatureType::Ed25519,
         },
     ];
     
-    let prev_state_root = BlockHash::zero();
     let block_number = 1;
     let timestamp = 1640995200; // 2022-01-01
     
     // Generate proof with timeout
     let proof_result = timeout(
         Duration::from_secs(30),
-        prover.generate_state_transition_proof(
-            prev_state_root,
-            &transactions,
-            block_number,
-            timestamp,
-        ),
+        prover.generate_state_transition_proof(&proving_input(&transactions, block_number, timestamp)),
     ).await??;
     
     // Verify proof
@@ -75,45 +92,41 @@ async fn test_consensus_engine_with_performance_monitoring() -> Result<(), Box<d
     let mut monitor = PerformanceMonitor::new();
     
     // Setup consensus engine
-    let genesis_state = create_test_genesis_state();
-    let validators = create_test_validators();
-    let config = ProtocolConfig::default();
-    
-    let mut engine = ZkSacConsensusEngine::new(genesis_state, validators, config)?;
+    let mut engine = create_test_engine(create_test_validators())?;
     
     // Create test transactions
-    let transactions = create_large_transaction_set(100);
+    let transactions = create_large_transaction_set(100)?;
     for tx in transactions {
-        engine.pending_transactions.push(tx);
+        engine.add_local_transaction(tx)?;
     }
     
     // Test multiple block production cycles
     for block_num in 1..=5 {
-        monitor.start_timer("full_cycle");
+        let cycle_timer = monitor.time(Operation::ConsensusCycle);
         
         // Select producer
-        monitor.start_timer("producer_selection");
+        let timer = monitor.time(Operation::ProducerSelection);
         let producer = engine.select_block_producer(block_num)?;
-        let selection_time = monitor.end_timer("producer_selection");
+        let selection_time = timer.stop();
         
         // Produce block
-        monitor.start_timer("block_production");
-        let block = engine.produce_block(producer)?;
-        let production_time = monitor.end_timer("block_production");
+        let timer = monitor.time(Operation::BlockProduction);
+        let block = engine.produce_block(producer).await?;
+        let production_time = timer.stop();
         
         // Validate block
-        monitor.start_timer("validation");
-        let is_valid = engine.validate_block(&block)?;
-        let validation_time = monitor.end_timer("validation");
+        let timer = monitor.time(Operation::Validation);
+        let is_valid = engine.validate_block(&block).await?;
+        let validation_time = timer.stop();
         
         assert!(is_valid, "Block should be valid");
         
         // Apply block
-        monitor.start_timer("application");
-        engine.apply_block(block.clone())?;
-        let application_time = monitor.end_timer("application");
+        let timer = monitor.time(Operation::BlockApplication);
+        engine.apply_block(block.clone()).await?;
+        let application_time = timer.stop();
         
-        let full_cycle_time = monitor.end_timer("full_cycle");
+        let full_cycle_time = cycle_timer.stop();
         
         // Create benchmark
         monitor.create_benchmark(
@@ -182,18 +195,18 @@ async fn test_recursive_proof_generation() -> Result<(), Box<dyn std::error::Err
                 to: Address::new(i + 2),
                 value: 1000 * (i + 1) as u64,
                 data: vec![i as u8; 10],
-                gas_limit: 21000,
+                gas_limit: 30_000,
+                gas_price: 1,
                 nonce: i as u64,
+                chain_id: DEFAULT_CHAIN_ID,
+                access_list: None,
                 signature: vec![0; 64],
                 sig_type: SignatureType::Ed25519,
             }
         ];
         
         let proof_result = prover.generate_state_transition_proof(
-            BlockHash::random(),
-            &transactions,
-            (i + 1) as u64,
-            1640995200 + (i as u64),
+            &proving_input(&transactions, (i + 1) as u64, 1640995200 + (i as u64)),
         ).await?;
         
         sub_proofs.push(proof_result);
@@ -225,11 +238,8 @@ async fn test_recursive_proof_generation() -> Result<(), Box<dyn std::error::Err
 async fn test_validator_selection_fairness() -> Result<(), Box<dyn std::error::Error>> {
     println!("🧪 Testing Validator Selection Fairness");
     
-    let genesis_state = create_test_genesis_state();
     let validators = create_diverse_validators(); // Different stake amounts
-    let config = ProtocolConfig::default();
-    
-    let engine = ZkSacConsensusEngine::new(genesis_state, validators.clone(), config)?;
+    let engine = create_test_engine(validators.clone())?;
     
     let mut selection_counts = HashMap::new();
     let total_selections = 1000;
@@ -251,11 +261,22 @@ async fn test_validator_selection_fairness() -> Result<(), Box<dyn std::error::E
     assert_eq!(selection_counts.len(), validators.len(), 
                "All validators should be selected at least once");
     
-    // Check basic fairness (no validator should have >60% of selections)
-    for count in selection_counts.values() {
-        let percentage = (*count as f64 / total_selections as f64) * 100.0;
-        assert!(percentage < 60.0, "No validator should dominate selection");
+    // Selection is proportional to stake
+    let total_stake: u64 = validators.iter().map(|v| v.stake).sum();
+    for validator in &validators {
+        let share = selection_counts[&validator.address] as f64 / total_selections as f64;
+        let expected = validator.stake as f64 / total_stake as f64;
+        assert!((share - expected).abs() < 0.05,
+                "Validator {:?} selected {:.3} of the time, stake share {:.3}", validator.address, share, expected);
     }
+
+    // The producer is verifiable: a block from anyone else is rejected
+    let selected = engine.select_block_producer(1)?;
+    let mut engine = engine;
+    let mut block = engine.produce_block(selected).await?;
+    assert!(engine.validate_block(&block).await?);
+    block.header.producer = validators.iter().map(|v| v.address).find(|a| *a != selected).unwrap();
+    assert!(!engine.validate_block(&block).await?);
     
     println!("✅ Validator selection fairness test passed");
     
@@ -270,34 +291,931 @@ async fn test_error_handling_and_recovery() -> Result<(), Box<dyn std::error::Er
     let mut monitor = PerformanceMonitor::new();
     
     // Simulate various error conditions
-    monitor.record_error("network_timeout");
-    monitor.record_error("proof_generation_failed");
-    monitor.record_error("network_timeout"); // Duplicate
-    monitor.record_error("invalid_signature");
+    monitor.record_error(ErrorEvent::new(ErrorCategory::Network, "timeout"));
+    monitor.record_error(ErrorEvent::new(ErrorCategory::Proving, "generation_failed"));
+    monitor.record_error(ErrorEvent::new(ErrorCategory::Network, "timeout")); // Duplicate
+    monitor.record_error(ErrorEvent::new(ErrorCategory::Signature, "invalid_signature"));
     
     let summary = monitor.get_performance_summary();
     assert_eq!(summary.total_errors, 4);
+    assert_eq!(summary.errors_by_category[&ErrorCategory::Network], 2);
     
     // Test consensus engine error recovery
-    let genesis_state = create_test_genesis_state();
-    let validators = create_test_validators();
-    let config = ProtocolConfig::default();
-    
-    let mut engine = ZkSacConsensusEngine::new(genesis_state, validators, config)?;
+    let mut engine = create_test_engine(create_test_validators())?;
     
     // Try to produce block with empty transaction pool
     let producer = engine.select_block_producer(1)?;
-    let block = engine.produce_block(producer)?;
+    let block = engine.produce_block(producer).await?;
     
     // Should succeed even with no transactions
     assert_eq!(block.transactions.len(), 0);
-    assert!(engine.validate_block(&block)?);
+    assert!(engine.validate_block(&block).await?);
     
     println!("✅ Error handling test passed");
     
     Ok(())
 }
 
+#[tokio::test]
+#[traced_test]
+async fn test_late_slot_shrinks_block_and_defers_proof() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    for nonce in 0..10 {
+        engine.add_local_transaction(transfer(1, 2, 1, nonce)?)?;
+    }
+
+    // Less time left than the signature reserve: nothing fits and proving is deferred
+    let producer = engine.select_block_producer(1)?;
+    let block = engine.produce_block_by(producer, Deadline::after(Duration::from_millis(100))).await?;
+    assert!(block.transactions.is_empty());
+    assert!(matches!(block.recursive_proof.proof_type, ProofType::Deferred));
+    assert_eq!(engine.deferred_proofs, vec![1]);
+
+    engine.apply_block(block).await?;
+    assert_eq!(engine.prove_deferred().await?.len(), 1);
+    assert!(engine.deferred_proofs.is_empty());
+    assert!(!matches!(engine.blocks[0].recursive_proof.proof_type, ProofType::Deferred));
+
+    Ok(())
+}
+
+/// Proving service that proves odd blocks and fails even ones
+struct OddBlockProver;
+
+#[async_trait::async_trait]
+impl ProvingService for OddBlockProver {
+    async fn submit(&self, job: &ProofJob) -> anyhow::Result<String> {
+        Ok(job.block_number.to_string())
+    }
+
+    async fn status(&self, job_id: &str) -> anyhow::Result<JobStatus> {
+        let block_number: u64 = job_id.parse()?;
+        Ok(if block_number % 2 == 1 {
+            JobStatus::Succeeded(vec![7; 64])
+        } else {
+            JobStatus::Failed("prover crashed".to_string())
+        })
+    }
+}
+
+#[tokio::test]
+async fn test_remote_proofs_backfill_optimistic_blocks() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    let config = RemoteProverConfig { poll_interval: Duration::from_millis(5), ..RemoteProverConfig::default() };
+    engine.remote_prover = Some(RemoteProofQueue::new(std::sync::Arc::new(OddBlockProver), &config));
+
+    for block_number in 1..=2 {
+        let producer = engine.select_block_producer(block_number)?;
+        let block = engine.produce_block(producer).await?;
+        assert!(matches!(block.recursive_proof.proof_type, ProofType::Deferred));
+        engine.apply_block(block).await?;
+    }
+    assert!(engine.deferred_proofs.is_empty(), "remote proving replaces local deferral");
+
+    let mut backfilled = 0;
+    for _ in 0..200 {
+        backfilled += engine.backfill_remote_proofs()?.len();
+        if backfilled == 1 && !engine.deferred_proofs.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    assert_eq!(backfilled, 1);
+    assert_eq!(engine.blocks[0].recursive_proof.proof_data, vec![7; 64]);
+    assert!(matches!(engine.blocks[0].recursive_proof.proof_type, ProofType::Risc0));
+
+    // The failed job falls back to local proving
+    assert_eq!(engine.deferred_proofs, vec![2]);
+    assert_eq!(engine.prove_deferred().await?.len(), 1);
+    assert!(!matches!(engine.blocks[1].recursive_proof.proof_type, ProofType::Deferred));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deferred_proofs_hold_back_finality_until_supplied() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ProtocolConfig { proof_deadline: 2, ..ProtocolConfig::default() };
+    let mut engine = create_test_engine_with(create_test_validators(), config)?;
+    let mut events = engine.events.subscribe();
+
+    // Votes for a block still owing its proof do not finalize it
+    let block = engine.produce_block_by(engine.select_block_producer(1)?, Deadline::after(Duration::from_millis(100))).await?;
+    engine.apply_block(block).await?;
+    let block = engine.produce_block(engine.select_block_producer(2)?).await?;
+    engine.apply_block(block).await?;
+    assert_eq!(engine.finalized_height(), 0);
+
+    // The proof goes on chain with the next block, which finalizes the chain
+    assert_eq!(engine.prove_deferred().await?.len(), 1);
+    let block = engine.produce_block(engine.select_block_producer(3)?).await?;
+    assert!(block.transactions.iter().any(|tx| tx.to == PROOF_ADDRESS), "the proof is supplied in the block");
+    assert!(engine.validate_block(&block).await?);
+    engine.apply_block(block).await?;
+    assert_eq!(engine.finalized_height(), 3);
+
+    // A proof still missing `proof_deadline` blocks later costs the producer its stake, once
+    let block = engine.produce_block_by(engine.select_block_producer(4)?, Deadline::after(Duration::from_millis(100))).await?;
+    let offender = block.header.producer;
+    engine.apply_block(block).await?;
+    for block_number in 5..=8 {
+        let block = engine.produce_block(engine.select_block_producer(block_number)?).await?;
+        engine.apply_block(block).await?;
+    }
+    assert_eq!(engine.finalized_height(), 3);
+    assert!(engine.validator_set.validators.iter().all(|v| v.address != offender));
+    let mut slashed = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let ConsensusEvent::Validator { event, .. } = event {
+            slashed.push(event);
+        }
+    }
+    assert_eq!(slashed, vec![
+        ValidatorEvent::Slashed { address: offender, offense: Offense::MissingProof, amount: 1_600_000_000 },
+        ValidatorEvent::Removed { address: offender },
+    ]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_block_commits_to_its_transactions() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    for nonce in 0..5 {
+        engine.add_local_transaction(transfer(1, 2, 1, nonce)?)?;
+    }
+
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    assert_eq!(block.transactions.len(), 5);
+    assert_eq!(block.header.merkle_root, Block::transactions_root(&block.transactions));
+    assert!(engine.validate_block(&block).await?);
+
+    for (index, transaction) in block.transactions.iter().enumerate() {
+        let proof = block.transaction_proof(index).unwrap();
+        assert!(proof.verify(&transaction.hash().0, &block.header.merkle_root.0));
+    }
+
+    // Dropping a transaction from the body breaks the commitment
+    let mut tampered = block.clone();
+    tampered.transactions.pop();
+    assert!(!engine.validate_block(&tampered).await?);
+
+    // The header commits to the state after its transactions, not before
+    assert_ne!(block.header.state_root, engine.current_state.state_root);
+    let mut wrong_state = block.clone();
+    wrong_state.header.state_root = engine.current_state.state_root;
+    wrong_state.validator_signatures.clear();
+    engine.collect_validator_signatures(&mut wrong_state)?;
+    assert!(!engine.validate_block(&wrong_state).await?);
+    assert!(engine.apply_block(wrong_state).await.is_err());
+
+    engine.apply_block(block.clone()).await?;
+    assert_eq!(engine.current_state.state_root, block.header.state_root);
+    assert_eq!(engine.current_state.state_root, engine.current_state.compute_state_root());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocks_carry_a_verifiable_randomness_beacon() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    assert!(engine.verify_beacon(&block.header).is_ok());
+
+    // A forged beacon output is refused, even when the block is re-signed
+    let mut forged = block.clone();
+    forged.header.randomness.output = block.header.randomness.proof.clone();
+    forged.validator_signatures.clear();
+    engine.collect_validator_signatures(&mut forged)?;
+    assert!(!engine.validate_block(&forged).await?);
+    assert!(matches!(
+        engine.apply_block(forged).await,
+        Err(ConsensusError::Rejected { source, .. }) if matches!(*source, ConsensusError::InvalidRandomness { block_number: 1, .. })
+    ));
+
+    // The next producer is drawn from the applied block's beacon
+    engine.apply_block(block.clone()).await?;
+    let randomness = selection_randomness(&beacon_randomness(&block.header.randomness), 2);
+    let expected = select_weighted(&engine.validator_set.validators, &randomness).unwrap().address;
+    assert_eq!(engine.select_block_producer(2)?, expected);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_headers_validate_statelessly_against_their_proofs() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    engine.add_local_transaction(transfer(1, 2, 10, 0)?)?;
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    assert_eq!(block.header.prev_state_root, engine.current_state.state_root);
+    assert_eq!(block.recursive_proof.public_inputs, block.header.public_inputs().encode());
+    engine.validate_header_stateless(&block.header, &block.recursive_proof).await?;
+
+    // A header claiming another post-state no longer matches its proof
+    let mut wrong_state = block.header.clone();
+    wrong_state.state_root = BlockHash([7; 32]);
+    assert!(matches!(
+        engine.validate_header_stateless(&wrong_state, &block.recursive_proof).await,
+        Err(ConsensusError::Proof(ProofError::PublicInputsMismatch(1)))
+    ));
+
+    // Nor does one starting from another state
+    let mut wrong_start = block.header.clone();
+    wrong_start.prev_state_root = BlockHash([7; 32]);
+    assert!(matches!(
+        engine.validate_header_stateless(&wrong_start, &block.recursive_proof).await,
+        Err(ConsensusError::PrevStateRootMismatch(1))
+    ));
+    let mut deferred = block.recursive_proof.clone();
+    deferred.proof_type = ProofType::Deferred;
+    assert!(matches!(
+        engine.validate_header_stateless(&block.header, &deferred).await,
+        Err(ConsensusError::MissingProof(1))
+    ));
+
+    // Once applied, the header no longer extends the chain
+    engine.apply_block(block.clone()).await?;
+    assert!(matches!(
+        engine.validate_header_stateless(&block.header, &block.recursive_proof).await,
+        Err(ConsensusError::UnknownParent { block_number: 1, .. })
+    ));
+
+    Ok(())
+}
+
+/// Backend whose proofs all verify and commit to `0`
+struct CommittingBackend(StateTransitionOutput);
+
+#[async_trait::async_trait]
+impl ProverBackend for CommittingBackend {
+    fn proof_type(&self) -> ProofType {
+        ProofType::Risc0
+    }
+
+    async fn generate_state_transition_proof(&self, _input: &StateTransitionInput) -> Result<Vec<u8>, ProofError> {
+        Ok(vec![0; 32])
+    }
+
+    async fn generate_recursive_proof(&self, _proofs: Vec<Vec<u8>>) -> Result<Vec<u8>, ProofError> {
+        Ok(vec![0; 32])
+    }
+
+    async fn verify_proof(&self, _proof: &[u8]) -> Result<bool, ProofError> {
+        Ok(true)
+    }
+
+    fn committed_output(&self, _proof: &[u8]) -> Result<Option<StateTransitionOutput>, ProofError> {
+        Ok(Some(self.0.clone()))
+    }
+}
+
+#[tokio::test]
+async fn test_blocks_are_held_to_the_transition_their_proof_commits_to() -> Result<(), Box<dyn std::error::Error>> {
+    let mut producer = create_test_engine(create_test_validators())?;
+    producer.add_local_transaction(transfer(1, 2, 10, 0)?)?;
+    let block = producer.produce_block(producer.select_block_producer(1)?).await?;
+    let honest = StateTransitionOutput {
+        prev_state_root: block.header.prev_state_root.0,
+        transactions_root: block.header.merkle_root.0,
+        new_state_root: block.header.state_root.0,
+        transaction_count: 1,
+        gas_used: block.header.gas_used,
+        success: true,
+    };
+    // Where blocks execute as the guest proves them, all of the outcome counts
+    let guest = ProtocolConfig { execution_backend: ExecutionBackendKind::Guest, ..ProtocolConfig::default() };
+    let mut validator = create_test_engine_with(create_test_validators(), guest)?;
+
+    // Proofs that verify but commit to another transition are refused
+    let forged = [
+        (StateTransitionOutput { new_state_root: [7; 32], ..honest.clone() }, "state root"),
+        // A proof of an empty block from the same parent
+        (StateTransitionOutput {
+            transactions_root: [0; 32],
+            new_state_root: honest.prev_state_root,
+            transaction_count: 0,
+            gas_used: 0,
+            ..honest.clone()
+        }, "transactions root"),
+        (StateTransitionOutput { prev_state_root: [7; 32], ..honest.clone() }, "parent state root"),
+        (StateTransitionOutput { transaction_count: 2, ..honest.clone() }, "transaction count"),
+        (StateTransitionOutput { gas_used: honest.gas_used + 1, ..honest.clone() }, "gas used"),
+    ];
+    for (committed, mismatched) in forged {
+        validator.zkvm_engine = std::sync::Arc::new(CommittingBackend(committed));
+        assert!(!validator.validate_block(&block).await?, "{}", mismatched);
+        // A header alone does not say how many transactions the block has
+        if mismatched != "transaction count" {
+            assert!(matches!(
+                validator.validate_header_stateless(&block.header, &block.recursive_proof).await,
+                Err(ConsensusError::Proof(ProofError::CommitmentMismatch { block_number: 1, field })) if field == mismatched
+            ));
+        }
+    }
+
+    validator.zkvm_engine = std::sync::Arc::new(CommittingBackend(honest));
+    assert!(validator.validate_block(&block).await?);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocks_the_guest_cannot_run_cannot_carry_its_proof() -> Result<(), Box<dyn std::error::Error>> {
+    let mut producer = create_test_engine(create_test_validators())?;
+    producer.add_local_transaction(transfer(1, 2, 10, 0)?)?;
+    producer.add_local_transaction(BridgeAction::Withdraw { l1_recipient: [2; 20] }.to_transaction(&key(1), 300, 1)?)?;
+    let block = producer.produce_block(producer.select_block_producer(1)?).await?;
+    assert_eq!(block.transactions.len(), 2);
+
+    // The guest runs the withdrawal as a plain transfer, which burns nothing
+    let mut validator = create_test_engine(create_test_validators())?;
+    let input = state_transition_input(&validator.current_state, &validator.protocol_config.gas_schedule, &block.header, &block.transactions);
+    let committed = verify_state_transition(input);
+    assert_ne!(committed.new_state_root, block.header.state_root.0);
+
+    // Its proof binds the block's inputs but not the state the block reaches
+    assert_eq!(committed.transactions_root, block.header.merkle_root.0);
+    validator.zkvm_engine = std::sync::Arc::new(CommittingBackend(committed.clone()));
+    assert!(!validator.validate_block(&block).await?);
+    assert!(matches!(
+        validator.verify_block_proof(&block.header.prev_state_root, &block.header, Some(2), &block.recursive_proof).await,
+        Err(ProofError::CommitmentMismatch { field: "state root", .. })
+    ));
+    // So a producer does not prove such a block at all
+    producer.zkvm_engine = std::sync::Arc::new(CommittingBackend(committed));
+    let state = validator.current_state.clone();
+    assert!(producer.prove_block(&state, &validator.protocol_config.gas_schedule, &block.header, &block.transactions).await.is_err());
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transactions_pay_gas_fees_to_the_producer() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    let sender = key(1).address();
+    let recipient = key(5).address();
+    let mut tx = Transaction::new(sender, recipient, 100, 0);
+    tx.gas_price = 2;
+    engine.add_local_transaction(tx.signed(&key(1))?)?;
+
+    // Data costs gas beyond the base 21000
+    let mut underpriced = Transaction::new(sender, recipient, 1, 1);
+    underpriced.data = vec![0; 10];
+    assert_eq!(
+        engine.add_local_transaction(underpriced.signed(&key(1))?),
+        Err(TxValidationError::IntrinsicGasTooLow { gas_limit: 21_000, intrinsic: 21_160 })
+    );
+
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    let producer = block.header.producer;
+    let balance = |engine: &ZkSacConsensusEngine, address: &Address| engine.current_state.accounts.get(address).map_or(0, |a| a.balance);
+    let sender_before = balance(&engine, &sender);
+    let producer_before = balance(&engine, &producer);
+    assert_eq!(block.header.gas_used, 21_000);
+    assert_eq!(block.header.gas_limit, engine.protocol_config.gas_schedule.block_gas_limit);
+
+    // A header misreporting its gas is refused
+    let mut wrong_gas = block.clone();
+    wrong_gas.header.gas_used = 42_000;
+    wrong_gas.validator_signatures.clear();
+    engine.collect_validator_signatures(&mut wrong_gas)?;
+    assert!(!engine.validate_block(&wrong_gas).await?);
+    assert!(engine.apply_block(wrong_gas).await.is_err());
+
+    assert!(engine.validate_block(&block).await?);
+    engine.apply_block(block).await?;
+    let fee = 21_000 * 2;
+    if producer == sender {
+        assert_eq!(balance(&engine, &sender), sender_before - 100);
+    } else {
+        assert_eq!(balance(&engine, &sender), sender_before - 100 - fee);
+        assert_eq!(balance(&engine, &producer), producer_before + fee);
+    }
+    assert_eq!(balance(&engine, &recipient), 100);
+    assert_eq!(engine.current_state.accounts[&sender].nonce, 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocks_exclude_transactions_that_do_not_execute() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    engine.add_local_transaction(transfer(1, 2, 100, 0)?)?;
+    // key(6) holds nothing to pay the fee with
+    let unfunded = transfer(6, 2, 0, 0)?;
+    engine.add_local_transaction(unfunded.clone())?;
+
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    assert_eq!(block.transactions.len(), 1);
+    assert!(engine.mempool.is_empty(), "rejected transactions are dropped from the pool");
+
+    // The rejected transaction leaves the state root alone, but the block is still refused
+    let mut padded = block.clone();
+    padded.transactions.push(unfunded);
+    padded.header.merkle_root = Block::transactions_root(&padded.transactions);
+    padded.validator_signatures.clear();
+    engine.collect_validator_signatures(&mut padded)?;
+    assert!(!engine.validate_block(&padded).await?);
+    match engine.apply_block(padded).await {
+        Err(ConsensusError::Rejected { source, .. }) => assert!(matches!(
+            *source,
+            ConsensusError::RejectedTransaction { index: 1, source: ExecutionError::InsufficientFunds { balance: 0, required: 21_000 }, .. }
+        )),
+        other => panic!("expected a rejected transaction, got {:?}", other),
+    }
+
+    engine.apply_block(block).await?;
+    assert_eq!(
+        engine.add_local_transaction(transfer(1, 2, 100, 0)?),
+        Err(TxValidationError::StaleNonce { nonce: 0, expected: 1 })
+    );
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_secp256k1_senders_are_recovered_from_signatures() -> Result<(), Box<dyn std::error::Error>> {
+    // An Ethereum-style wallet whose public key the engine never learns
+    let wallet = KeyPair::from_secret(SignatureType::Secp256k1, &[42; 32])?;
+    let mut genesis = create_test_genesis_state();
+    genesis.accounts.insert(wallet.address(), Account::new(1_000_000));
+    let mut engine = ZkSacConsensusEngine::new(genesis, create_test_validators(), ProtocolConfig::default())?;
+    for i in 1..=3 {
+        engine.add_validator_key(key(i))?;
+    }
+
+    let transaction = Transaction::new(wallet.address(), key(5).address(), 100, 0).signed(&wallet)?;
+    assert_eq!(transaction.signature.len(), 65);
+    engine.add_remote_transaction(transaction.clone())?;
+
+    let impostor = KeyPair::from_secret(SignatureType::Secp256k1, &[43; 32])?;
+    let forged = Transaction::new(wallet.address(), key(5).address(), 100, 1).signed(&impostor)?;
+    assert_eq!(
+        engine.add_remote_transaction(forged),
+        Err(TxValidationError::KeyMismatch { sender: wallet.address(), sig_type: SignatureType::Secp256k1 })
+    );
+
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    assert_eq!(block.transactions.iter().map(Transaction::hash).collect::<Vec<_>>(), vec![transaction.hash()]);
+    assert!(engine.validate_block(&block).await?);
+    engine.apply_block(block).await?;
+    assert_eq!(engine.current_state.accounts[&key(5).address()].balance, 100);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_transactions_signed_for_another_chain_are_rejected() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    engine.add_local_transaction(transfer(1, 2, 10, 0)?)?;
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+
+    let mut other_chain = create_test_engine(create_test_validators())?;
+    other_chain.protocol_config.chain_id = 7;
+    assert_eq!(
+        other_chain.add_local_transaction(transfer(1, 2, 10, 0)?),
+        Err(TxValidationError::WrongChain { chain_id: DEFAULT_CHAIN_ID, expected: 7 })
+    );
+    // Batch-verified signatures are still held to the chain id
+    assert!(matches!(
+        other_chain.verify_block_signatures(&block),
+        Err(ConsensusError::InvalidTransaction { index: 0, source: TxValidationError::WrongChain { .. } })
+    ));
+
+    // The chain id is signed, so it cannot be rewritten after signing
+    let mut replayed = transfer(1, 2, 10, 0)?;
+    assert_ne!(replayed.signing_hash(), Transaction { chain_id: 7, ..replayed.clone() }.signing_hash());
+    replayed.chain_id = 7;
+    assert!(matches!(other_chain.add_local_transaction(replayed), Err(TxValidationError::InvalidSignature { .. })));
+
+    let mut resigned = Transaction::new(key(1).address(), key(2).address(), 10, 0);
+    resigned.chain_id = 7;
+    other_chain.add_local_transaction(resigned.signed(&key(1))?)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocks_carry_proofs_from_the_configured_backend() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    engine.add_local_transaction(transfer(1, 2, 10, 0)?)?;
+
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    assert_eq!(block.recursive_proof.proof_type, engine.zkvm_engine.proof_type());
+    assert!(!block.recursive_proof.proof_data.is_empty());
+    assert!(engine.validate_block(&block).await?);
+
+    // A proof from another backend is not one this node can verify
+    let mut foreign = block.clone();
+    foreign.recursive_proof.proof_type = ProofType::SP1;
+    engine.collect_validator_signatures(&mut foreign)?;
+    assert!(!engine.validate_block(&foreign).await?);
+
+    engine.apply_block(block).await?;
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_revalidating_a_block_hits_the_proof_cache() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    let monitor = PerformanceMonitor::new().with_proof_cache(engine.proof_cache.clone());
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+
+    // The producer cached its own proof when generating it
+    assert!(engine.validate_block(&block).await?);
+    assert!(engine.validate_block(&block).await?);
+    assert_eq!(monitor.proof_cache().hits, 2);
+    assert_eq!(monitor.get_performance_summary().proof_cache.misses, 0);
+
+    // A peer verifies the proof once
+    let peer = create_test_engine(create_test_validators())?;
+    assert!(peer.validate_block(&block).await?);
+    assert!(peer.validate_block(&block).await?);
+    let stats = peer.proof_cache.stats();
+    assert_eq!((stats.hits, stats.misses, stats.entries), (1, 1, 1));
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocks_need_valid_signatures() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+
+    // Unsigned and forged transactions never reach the pool
+    let mut unsigned = Transaction::new(key(1).address(), key(2).address(), 1, 0);
+    unsigned.signature.clear();
+    let unsigned = engine.add_local_transaction(unsigned).unwrap_err();
+    assert_eq!(unsigned, TxValidationError::Unsigned(key(1).address()));
+    assert!(matches!(EngineError::from(unsigned), EngineError::Transaction(TxValidationError::Unsigned(_))));
+    let forged = Transaction::new(key(1).address(), key(2).address(), 1, 0).signed(&key(2))?;
+    assert!(matches!(engine.add_local_transaction(forged), Err(TxValidationError::InvalidSignature { .. })));
+    for nonce in 0..3 {
+        engine.add_local_transaction(transfer(1, 2, 1, nonce)?)?;
+    }
+
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    assert_eq!(block.validator_signatures.len(), 3, "the engine signs for every validator it holds a key for");
+    assert!(engine.validate_block(&block).await?);
+
+    // Without the producer's vote the block is neither valid nor applied
+    let mut unsigned = block.clone();
+    unsigned.validator_signatures.retain(|vote| vote.validator_address != block.header.producer);
+    assert!(!engine.validate_block(&unsigned).await?);
+    match engine.apply_block(unsigned).await {
+        Err(ConsensusError::Rejected { block_number: 1, source }) => {
+            assert!(matches!(*source, ConsensusError::UnsignedByProducer { .. }), "{}", source);
+        }
+        other => panic!("expected a rejected block, got {:?}", other),
+    }
+
+    // A vote that does not verify
+    let mut bad_vote = block.clone();
+    bad_vote.validator_signatures[0].signature[0] ^= 1;
+    assert!(!engine.validate_block(&bad_vote).await?);
+
+    // A transaction whose signature was altered, in an otherwise well-formed block
+    let mut bad_transaction = block.clone();
+    bad_transaction.transactions[0].signature[0] ^= 1;
+    bad_transaction.header.merkle_root = Block::transactions_root(&bad_transaction.transactions);
+    bad_transaction.validator_signatures.clear();
+    engine.collect_validator_signatures(&mut bad_transaction)?;
+    assert!(!engine.validate_block(&bad_transaction).await?);
+
+    engine.apply_block(block).await?;
+    assert_eq!(engine.blocks.len(), 1);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_blocks_finalize_with_two_thirds_of_stake() -> Result<(), Box<dyn std::error::Error>> {
+    // One engine per validator, each holding only its own key
+    let mut nodes = Vec::new();
+    for i in 1..=3 {
+        let mut engine = ZkSacConsensusEngine::new(create_test_genesis_state(), create_test_validators(), ProtocolConfig::default())?;
+        engine.add_validator_key(key(i))?;
+        nodes.push(engine);
+    }
+    let producer = nodes[0].select_block_producer(1)?;
+    let producing = nodes.iter().position(|node| node.holds_validator_key(&producer)).unwrap();
+    let block = nodes[producing].produce_block(producer).await?;
+    let hash = block.header.hash();
+    for node in &mut nodes {
+        node.apply_block(block.clone()).await?;
+    }
+
+    // The producer's vote is a third of the stake
+    assert_eq!(nodes[0].finalized_height(), 0);
+    assert!(!nodes[0].is_finalized(&hash));
+
+    let attestations: Vec<Attestation> = nodes.iter()
+        .map(|node| node.attest(1))
+        .collect::<Result<Vec<_>, _>>()?
+        .into_iter()
+        .flatten()
+        .collect();
+    assert_eq!(attestations.len(), 3);
+    let other = attestations.iter().find(|a| a.vote.validator_address != producer).unwrap();
+    for node in &mut nodes {
+        node.add_attestation(other)?;
+        assert_eq!(node.finalized_height(), 1);
+        assert!(node.is_finalized(&hash));
+    }
+
+    // A vote for a different block at a finalized height changes nothing
+    let signer = (1..=3).map(key).find(|key| key.address() == other.vote.validator_address).unwrap();
+    let conflicting = Attestation::sign(&signer, other.vote.stake_weight, 1, BlockHash([7; 32]))?;
+    assert!(!nodes[0].add_attestation(&conflicting)?);
+    assert!(!nodes[0].is_finalized(&BlockHash([7; 32])));
+
+    // Nor can a vote be relabeled for another block
+    let mut relabeled = other.clone();
+    relabeled.block_hash = BlockHash([7; 32]);
+    assert!(matches!(
+        nodes[0].add_attestation(&relabeled),
+        Err(ConsensusError::Finality(FinalityError::InvalidSignature(address))) if address == other.vote.validator_address
+    ));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_bls_votes_are_aggregated_into_one_signature() -> Result<(), Box<dyn std::error::Error>> {
+    let bls_key = |i: u8| BlsKeyPair::from_seed(&[i; 32]).expect("32 bytes of key material");
+    let validators: Vec<Validator> = create_test_validators().into_iter().zip(1..)
+        .map(|(validator, i)| Validator { bls_public_key: bls_key(i).public_key(), ..validator })
+        .collect();
+    let mut engine = create_test_engine(validators)?;
+    for i in 1..=3 {
+        engine.add_validator_bls_key(key(i).address(), bls_key(i))?;
+    }
+    assert!(matches!(engine.add_validator_bls_key(key(1).address(), bls_key(9)), Err(ConsensusError::KeyMismatch(_))));
+
+    // The producer votes on its own; the other two share one signature
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    assert_eq!(block.validator_signatures.len(), 1);
+    assert_eq!(block.validator_signatures[0].validator_address, block.header.producer);
+    let aggregate = block.aggregate_signature.clone().expect("aggregated votes");
+    assert_eq!(aggregate.signer_indices().count(), 2);
+    assert_eq!(aggregate.signature.len(), bls::SIGNATURE_LEN);
+    assert_eq!(engine.signed_stake(&block), 96_000_000_000);
+    assert!(engine.validate_block(&block).await?);
+
+    // Naming a signer that did not sign, or signing something else, fails the aggregate
+    let producer = engine.validator_set.validators.iter().position(|v| v.address == block.header.producer).unwrap();
+    let mut extra_signer = block.clone();
+    extra_signer.aggregate_signature.as_mut().unwrap().add_signer(producer);
+    assert!(!engine.validate_block(&extra_signer).await?);
+    let mut forged = block.clone();
+    forged.aggregate_signature.as_mut().unwrap().signature = bls_key(1).sign(b"another message");
+    assert!(!engine.validate_block(&forged).await?);
+
+    // Aggregated stake counts for light clients and for finality
+    let mut light_client = LightClient::new(engine.validator_set.clone());
+    light_client.apply_update(HeaderUpdate::from_block(&block))?;
+    engine.apply_block(block.clone()).await?;
+    assert!(engine.is_finalized(&block.header.hash()));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_heavier_branch_reorganizes_the_chain() -> Result<(), Box<dyn std::error::Error>> {
+    let mut a = create_test_engine(create_test_validators())?;
+    let mut b = create_test_engine(create_test_validators())?;
+    let first = a.produce_block(a.select_block_producer(1)?).await?;
+    for node in [&mut a, &mut b] {
+        node.apply_block(first.clone()).await?;
+    }
+
+    // A applies a block at height 2 that only its producer voted for
+    a.add_local_transaction(transfer(1, 5, 50, 0)?)?;
+    let mut light = a.produce_block(a.select_block_producer(2)?).await?;
+    let producer = light.header.producer;
+    light.validator_signatures.retain(|signature| signature.validator_address == producer);
+    a.apply_block(light.clone()).await?;
+
+    // B's competing block carries every vote
+    b.add_local_transaction(transfer(1, 2, 100, 0)?)?;
+    let heavy = b.produce_block(b.select_block_producer(2)?).await?;
+    b.apply_block(heavy.clone()).await?;
+
+    let import = a.on_block_received(heavy.clone()).await?;
+    assert_eq!(import, BlockImport::Reorged { fork_height: 1, depth: 1, head: heavy.header.hash() });
+    assert_eq!(a.blocks.last().unwrap().header.hash(), heavy.header.hash());
+    assert_eq!(a.current_state.state_root, b.current_state.state_root);
+    assert!(!a.current_state.accounts.contains_key(&key(5).address()));
+    assert_eq!(a.mempool.len(), 0, "the orphaned transfer reuses a spent nonce");
+    assert_eq!(a.finalized_height(), 2);
+
+    assert_eq!(a.on_block_received(heavy).await?, BlockImport::Known);
+    assert!(matches!(a.on_block_received(light.clone()).await, Err(ConsensusError::ConflictsWithFinalized(2))));
+    let mut orphan = light;
+    orphan.header.block_number = 3;
+    orphan.header.previous_hash = BlockHash([9; 32]);
+    assert!(matches!(a.on_block_received(orphan).await, Err(ConsensusError::UnknownParent { block_number: 3, .. })));
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_nodes_start_from_a_state_snapshot() -> Result<(), Box<dyn std::error::Error>> {
+    let mut source = create_test_engine(create_test_validators())?;
+    for nonce in 0..3 {
+        source.add_local_transaction(transfer(1, 5 + nonce as u8, 100, nonce)?)?;
+        let block = source.produce_block(source.select_block_producer(nonce + 1)?).await?;
+        source.apply_block(block).await?;
+    }
+    let snapshot = source.export_snapshot(2)?;
+    let head = source.blocks.last().unwrap().header.clone();
+    assert_eq!(snapshot.chunks.len(), snapshot.manifest.chunk_hashes.len());
+
+    let mut tampered = snapshot.clone();
+    tampered.chunks[0].data[0] ^= 1;
+    assert!(matches!(
+        ZkSacConsensusEngine::from_snapshot(&tampered, &head, ProtocolConfig::default()),
+        Err(ConsensusError::Snapshot(SnapshotError::ChunkMismatch(0)))
+    ));
+    let first = source.blocks[0].header.clone();
+    assert!(matches!(
+        ZkSacConsensusEngine::from_snapshot(&snapshot, &first, ProtocolConfig::default()),
+        Err(ConsensusError::Snapshot(SnapshotError::BlockMismatch { .. }))
+    ));
+
+    let mut node = ZkSacConsensusEngine::from_snapshot(&snapshot, &head, ProtocolConfig::default())?;
+    for i in 1..=11 {
+        node.register_account_key(key(i).sig_type(), key(i).public_key());
+    }
+    assert_eq!(node.height(), 3);
+    assert_eq!(node.finalized_height(), 3);
+    assert_eq!(node.current_state.state_root, source.current_state.state_root);
+
+    // The node follows the chain from the snapshot on
+    source.add_local_transaction(transfer(1, 9, 100, 3)?)?;
+    let next = source.produce_block(source.select_block_producer(4)?).await?;
+    source.apply_block(next.clone()).await?;
+    assert!(node.validate_block(&next).await?);
+    node.apply_block(next).await?;
+    assert_eq!(node.height(), 4);
+    assert_eq!(node.current_state.state_root, source.current_state.state_root);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_double_production_and_invalid_proofs_are_slashed() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    let mut events = engine.events.subscribe();
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    let offender = block.header.producer;
+    engine.apply_block(block.clone()).await?;
+    assert!(engine.detect_offense(&block).is_none());
+
+    // The producer signs a second block for the same height
+    let mut conflicting = block.clone();
+    conflicting.header.extra_data = b"fork".to_vec();
+    conflicting.validator_signatures.clear();
+    engine.collect_validator_signatures(&mut conflicting)?;
+    let evidence = engine.detect_offense(&conflicting).expect("double production is detected");
+    assert_eq!(evidence.offense(), Offense::DoubleProduction);
+    let report = engine.report_offense(&evidence)?;
+
+    let next = engine.produce_block(engine.select_block_producer(2)?).await?;
+    assert!(next.transactions.iter().any(|tx| tx.hash() == report.hash()), "evidence is recorded in the block");
+    assert!(engine.validate_block(&next).await?);
+    engine.apply_block(next).await?;
+
+    // 5% of 32 ETH leaves the offender below the minimum stake
+    assert!(engine.validator_set.validators.iter().all(|v| v.address != offender));
+    assert_eq!(engine.validator_set.total_stake, 64_000_000_000);
+    let mut slashed = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let ConsensusEvent::Validator { event, .. } = event {
+            slashed.push(event);
+        }
+    }
+    assert_eq!(slashed, vec![
+        ValidatorEvent::Slashed { address: offender, offense: Offense::DoubleProduction, amount: 1_600_000_000 },
+        ValidatorEvent::Removed { address: offender },
+    ]);
+    assert!(engine.report_offense(&evidence).is_err(), "an offense is punished once");
+
+    // A block whose producer signed a proof that does not verify
+    let mut bad_proof = engine.produce_block(engine.select_block_producer(3)?).await?;
+    bad_proof.recursive_proof.proof_data.clear();
+    engine.collect_validator_signatures(&mut bad_proof)?;
+    assert!(!engine.validate_block(&bad_proof).await?);
+    let evidence = engine.detect_offense(&bad_proof).expect("invalid proof is detected");
+    assert_eq!(evidence.offense(), Offense::InvalidProof);
+    assert_eq!(evidence.offender(), bad_proof.header.producer);
+
+    // Whether a proof is invalid is up to this node's prover backend
+    let mut good_proof = engine.produce_block(engine.select_block_producer(3)?).await?;
+    engine.collect_validator_signatures(&mut good_proof)?;
+    let evidence = SlashingEvidence::invalid_proof(&good_proof).expect("the block is signed");
+    assert!(engine.report_offense(&evidence).is_err(), "a proof the backend accepts is not an offense");
+    // A backend reading another post-state out of the proof convicts the producer
+    let committed = StateTransitionOutput {
+        prev_state_root: good_proof.header.prev_state_root.0,
+        transactions_root: good_proof.header.merkle_root.0,
+        new_state_root: [7; 32],
+        transaction_count: 0,
+        gas_used: 0,
+        success: true,
+    };
+    engine.zkvm_engine = std::sync::Arc::new(CommittingBackend(committed));
+    engine.report_offense(&evidence)?;
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_validator_set_rotates_at_epoch_boundaries() -> Result<(), Box<dyn std::error::Error>> {
+    let config = ProtocolConfig {
+        min_stake_threshold: 100_000,
+        epoch_schedule: EpochSchedule { epoch_length: 4, activation_delay: 1, churn_limit: 4 },
+        ..ProtocolConfig::default()
+    };
+    let mut genesis = create_test_genesis_state();
+    genesis.accounts.insert(key(4).address(), Account { balance: 1_000_000, nonce: 0, code: Vec::new(), storage: HashMap::new() });
+    // Covers the fee of key(3)'s unstake
+    genesis.accounts.insert(key(3).address(), Account { balance: 100_000, nonce: 0, code: Vec::new(), storage: HashMap::new() });
+    let mut engine = ZkSacConsensusEngine::new(genesis, create_test_validators(), config)?;
+    for i in 1..=4 {
+        engine.register_account_key(key(i).sig_type(), key(i).public_key());
+    }
+    for i in 1..=3 {
+        engine.add_validator_key(key(i))?;
+    }
+    let mut events = engine.events.subscribe();
+
+    let too_small = StakingAction::Stake { public_key: key(4).public_key() }.to_transaction(&key(4), 1_000, 0)?;
+    assert!(engine.add_local_transaction(too_small).is_err());
+    engine.add_local_transaction(StakingAction::Stake { public_key: key(4).public_key() }.to_transaction(&key(4), 500_000, 0)?)?;
+    engine.add_local_transaction(StakingAction::Unstake.to_transaction(&key(3), 0, 0)?)?;
+
+    // Both take effect once epoch 0 (blocks 1 to 3) is over
+    for number in 1..=3 {
+        let block = engine.produce_block(engine.select_block_producer(number)?).await?;
+        assert!(engine.validate_block(&block).await?);
+        engine.apply_block(block).await?;
+        // One validator joins as another leaves
+        assert_eq!(engine.validator_set.validators.len(), 3);
+        assert_eq!(engine.validator_set.validators.iter().any(|v| v.address == key(4).address()), number == 3);
+    }
+    assert!(engine.validator_set.validators.iter().all(|v| v.address != key(3).address()));
+    assert_eq!(engine.validator_set.total_stake, 64_000_000_000 + 500_000);
+    assert_eq!(engine.current_state.accounts[&STAKING_ADDRESS].balance, 500_000);
+    assert!(engine.validator_set.activation_queue.is_empty() && engine.validator_set.exit_queue.is_empty());
+
+    let mut changes = Vec::new();
+    while let Ok(event) = events.try_recv() {
+        if let ConsensusEvent::Validator { block_number, event } = event {
+            changes.push((block_number, event));
+        }
+    }
+    assert_eq!(changes, vec![
+        (1, ValidatorEvent::Staked { address: key(4).address(), stake: 500_000, activation_epoch: 1 }),
+        (1, ValidatorEvent::Unstaked { address: key(3).address(), exit_epoch: 1 }),
+        (3, ValidatorEvent::Activated { address: key(4).address(), stake: 500_000 }),
+        (3, ValidatorEvent::Exited { address: key(3).address() }),
+    ]);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn test_deposits_mint_and_withdrawals_record_exits() -> Result<(), Box<dyn std::error::Error>> {
+    let mut engine = create_test_engine(create_test_validators())?;
+    let deposits: Vec<DepositMessage> = (0..2)
+        .map(|nonce| DepositMessage { nonce, l1_sender: [1; 20], recipient: key(5).address(), amount: 500 })
+        .collect();
+    assert_eq!(engine.relay_deposits(&deposits)?, 2);
+    let unauthorized = BridgeAction::Deposit(DepositMessage { nonce: 2, ..deposits[0].clone() }).to_transaction(&key(5), 0, 0)?;
+    assert!(matches!(engine.add_local_transaction(unauthorized),
+                     Err(TxValidationError::Bridge(BridgeError::UnauthorizedRelayer(_)))));
+    engine.add_local_transaction(BridgeAction::Withdraw { l1_recipient: [2; 20] }.to_transaction(&key(1), 300, 2)?)?;
+
+    let block = engine.produce_block(engine.select_block_producer(1)?).await?;
+    assert_eq!(block.transactions.len(), 3);
+    assert!(engine.validate_block(&block).await?);
+    engine.apply_block(block.clone()).await?;
+
+    let state = &engine.current_state;
+    assert_eq!(state.accounts[&key(5).address()].balance, 1_000);
+    assert_eq!(bridge::messages::next_deposit_nonce(state), 2);
+    assert_eq!(state.accounts[&BRIDGE_ADDRESS].balance, 0, "withdrawn value is burned");
+    let withdrawal = WithdrawalMessage { nonce: 0, sender: key(1).address(), l1_recipient: [2; 20], amount: 300 };
+    let proof = bridge::messages::exit_proof(state, &withdrawal).expect("exit is recorded");
+    assert!(bridge::messages::verify_exit(&block.header.state_root, &withdrawal, &proof));
+
+    // The settlement bridge queues the same withdrawal for L1
+    let config = BridgeConfig::default().with_checkpoint_interval(1);
+    let mut settlement = SettlementBridge::new(std::sync::Arc::new(MockSettlement::new()), config);
+    settlement.on_finalized_block(&block);
+    assert_eq!(settlement.checkpoint(1).unwrap().withdrawals, vec![withdrawal]);
+
+    Ok(())
+}
+
 #[tokio::test]
 #[traced_test]
 async fn test_performance_benchmark_export() -> Result<(), Box<dyn std::error::Error>> {
@@ -333,10 +1251,36 @@ async fn test_performance_benchmark_export() -> Result<(), Box<dyn std::error::E
 }
 
 // Helper functions
+
+/// Deterministic key of test account `i`; accounts 1 to 3 are the validators
+fn key(i: u8) -> KeyPair {
+    KeyPair::from_secret(SignatureType::Ed25519, &[i; 32]).expect("32-byte secret")
+}
+
+/// Engine that signs for every validator and knows the keys of accounts 1 to 11
+fn create_test_engine(validators: Vec<Validator>) -> anyhow::Result<ZkSacConsensusEngine> {
+    create_test_engine_with(validators, ProtocolConfig::default())
+}
+
+fn create_test_engine_with(validators: Vec<Validator>, config: ProtocolConfig) -> anyhow::Result<ZkSacConsensusEngine> {
+    let mut engine = ZkSacConsensusEngine::new(create_test_genesis_state(), validators, config)?;
+    for i in 1..=3 {
+        engine.add_validator_key(key(i))?;
+    }
+    for i in 1..=11 {
+        engine.register_account_key(key(i).sig_type(), key(i).public_key());
+    }
+    Ok(engine)
+}
+
+fn transfer(from: u8, to: u8, value: u64, nonce: u64) -> anyhow::Result<Transaction> {
+    Transaction::new(key(from).address(), key(to).address(), value, nonce).signed(&key(from))
+}
+
 fn create_test_genesis_state() -> WorldState {
     let mut accounts = HashMap::new();
     accounts.insert(
-        Address::new(1),
+        key(1).address(),
         Account {
             balance: 1_000_000,
             nonce: 0,
@@ -353,67 +1297,79 @@ fn create_test_genesis_state() -> WorldState {
     }
 }
 
+/// Guest input for a block of `transactions` on a state funding each sender
+/// at the transaction's nonce
+fn proving_input(transactions: &[Transaction], block_number: u64, timestamp: u64) -> StateTransitionInput {
+    let mut state = WorldState::default();
+    for tx in transactions {
+        state.accounts.insert(tx.from, Account { nonce: tx.nonce, ..Account::new(1_000_000) });
+    }
+    state.state_root = state.compute_state_root();
+    let header = BlockHeader {
+        previous_hash: BlockHash::zero(),
+        merkle_root: Block::transactions_root(transactions),
+        prev_state_root: state.state_root,
+        state_root: BlockHash::zero(),
+        timestamp,
+        block_number,
+        gas_limit: 30_000_000,
+        gas_used: 0,
+        producer: Address::new(99),
+        extra_data: Vec::new(),
+        randomness: RandomnessProof::default(),
+    };
+    state_transition_input(&state, &GasSchedule::default(), &header, transactions)
+}
+
+fn validator(i: u8, stake: u64, performance_score: f64) -> Validator {
+    Validator {
+        address: key(i).address(),
+        stake,
+        public_key: key(i).public_key(),
+        performance_score,
+        bls_public_key: Vec::new(),
+    }
+}
+
 fn create_test_validators() -> Vec<Validator> {
     vec![
-        Validator {
-            address: Address::new(1),
-            stake: 32_000_000_000,
-            public_key: vec![1; 32],
-            performance_score: 1.0,
-        },
-        Validator {
-            address: Address::new(2),
-            stake: 32_000_000_000,
-            public_key: vec![2; 32],
-            performance_score: 0.95,
-        },
-        Validator {
-            address: Address::new(3),
-            stake: 32_000_000_000,
-            public_key: vec![3; 32],
-            performance_score: 0.90,
-        },
+        validator(1, 32_000_000_000, 1.0),
+        validator(2, 32_000_000_000, 0.95),
+        validator(3, 32_000_000_000, 0.90),
     ]
 }
 
 fn create_diverse_validators() -> Vec<Validator> {
     vec![
-        Validator {
-            address: Address::new(1),
-            stake: 64_000_000_000, // 2x stake
-            public_key: vec![1; 32],
-            performance_score: 1.0,
-        },
-        Validator {
-            address: Address::new(2),
-            stake: 32_000_000_000, // 1x stake
-            public_key: vec![2; 32],
-            performance_score: 0.95,
-        },
-        Validator {
-            address: Address::new(3),
-            stake: 16_000_000_000, // 0.5x stake
-            public_key: vec![3; 32],
-            performance_score: 0.90,
-        },
+        validator(1, 64_000_000_000, 1.0), // 2x stake
+        validator(2, 32_000_000_000, 0.95), // 1x stake
+        validator(3, 16_000_000_000, 0.90), // 0.5x stake
     ]
 }
 
-fn create_large_transaction_set(count: usize) -> Vec<Transaction> {
+fn create_large_transaction_set(count: usize) -> anyhow::Result<Vec<Transaction>> {
+    let post_quantum = PostQuantumSigner::new()?;
     (0..count).map(|i| {
-        Transaction {
-            from: Address::new((i % 10 + 1) as u8),
-            to: Address::new((i % 10 + 2) as u8),
+        let sender = key((i % 10 + 1) as u8);
+        let data_len = i % 20 + 1;
+        let tx = Transaction {
+            from: sender.address(),
+            to: key((i % 10 + 2) as u8).address(),
             value: 100 + (i as u64 * 10),
-            data: vec![i as u8; i % 20 + 1],
-            gas_limit: 21000 + (i as u64 * 100),
-            nonce: i as u64,
-            signature: vec![0; 64],
-            sig_type: if i % 3 == 0 { 
-                SignatureType::PostQuantum 
-            } else { 
-                SignatureType::Ed25519 
-            },
+            data: vec![i as u8; data_len],
+            gas_limit: 21000 + 16 * data_len as u64 + (i as u64 * 100),
+            gas_price: 1,
+            nonce: (i / 10) as u64,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
+            signature: Vec::new(),
+            sig_type: SignatureType::PostQuantum,
+        };
+        if i % 3 == 0 {
+            let signature = post_quantum.sign_lms(&tx.from, &tx.signing_hash().0)?;
+            Ok(Transaction { signature, ..tx })
+        } else {
+            tx.signed(&sender)
         }
     }).collect()
-} 
\ No newline at end of file
+}
@@ -0,0 +1,36 @@
+use zk_sac_engine::conformance::{ConformanceTarget, ReferenceTarget, VectorInput, VectorSuite, generate, validate};
+
+#[test]
+fn test_generation_is_deterministic_and_roundtrips() {
+    let first = serde_json::to_string(&generate().unwrap()).unwrap();
+    let second = serde_json::to_string(&generate().unwrap()).unwrap();
+    assert_eq!(first, second);
+
+    let suite: VectorSuite = serde_json::from_str(&first).unwrap();
+    let report = validate(&suite, &ReferenceTarget);
+    assert!(report.is_conformant(), "{:?}", report.failures);
+    assert_eq!(report.passed, suite.vectors.len());
+}
+
+/// Stands in for a client that computes state roots differently
+struct DivergentStateRoot;
+
+impl ConformanceTarget for DivergentStateRoot {
+    fn evaluate(&self, input: &VectorInput) -> anyhow::Result<Vec<u8>> {
+        let mut output = ReferenceTarget.evaluate(input)?;
+        if let VectorInput::StateRoot { .. } = input {
+            output[0] ^= 1;
+        }
+        Ok(output)
+    }
+}
+
+#[test]
+fn test_divergent_implementation_is_reported() {
+    let suite = generate().unwrap();
+    let report = validate(&suite, &DivergentStateRoot);
+
+    let failed: Vec<&str> = report.failures.iter().map(|failure| failure.name.as_str()).collect();
+    assert_eq!(failed, vec!["state_root/empty", "state_root/three_accounts"]);
+    assert_eq!(report.passed, suite.vectors.len() - 2);
+}
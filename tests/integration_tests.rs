@@ -2,7 +2,7 @@ use std::time::Duration;
 use tokio::time::timeout;
 use zk_sac_engine::{
     consensus::{ConsensusEngine, BeamChainConfig},
-    types::{Block, Transaction, Address, BlockHash, ValidatorSet},
+    types::{Block, Transaction, Address, BlockHash, ValidatorSet, DEFAULT_CHAIN_ID},
     crypto::{hash::MultiHasher, signatures::QuantumResistantSigner},
     zkvm::Risc0Executor,
     async_utils::{AsyncTaskPool, BatchProcessor, ConsensusCoordinator},
@@ -205,6 +205,8 @@ async fn create_test_transactions(count: usize) -> Vec<Transaction> {
             value: 1000 + i as u64,
             data: vec![],
             nonce: i as u64,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             gas_limit: 21000,
             gas_price: 20,
             signature: vec![0; 64], // Mock signature
@@ -244,6 +246,8 @@ fn create_test_evm_transaction() -> Transaction {
         value: 1000,
         data: vec![0x60, 0x60, 0x60, 0x40], // Mock EVM bytecode
         nonce: 0,
+        chain_id: DEFAULT_CHAIN_ID,
+        access_list: None,
         gas_limit: 21000,
         gas_price: 20,
         signature: vec![0; 65], // Mock signature with recovery byte
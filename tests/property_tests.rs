@@ -2,7 +2,7 @@ use proptest::prelude::*;
 use quickcheck::{quickcheck, TestResult};
 use quickcheck_macros::quickcheck;
 use zk_sac_engine::{
-    types::{Block, Transaction, Address, BlockHash},
+    types::{Block, Transaction, Address, BlockHash, DEFAULT_CHAIN_ID},
     crypto::hash::MultiHasher,
     consensus::BeamChainConfig,
 };
@@ -89,6 +89,8 @@ proptest! {
                 value: 100,
                 data: vec![],
                 nonce,
+                chain_id: DEFAULT_CHAIN_ID,
+                access_list: None,
                 gas_limit: 21000,
                 gas_price: 20,
                 signature: vec![0; 64],
@@ -246,6 +248,8 @@ prop_compose! {
             value,
             data,
             nonce,
+            chain_id: DEFAULT_CHAIN_ID,
+            access_list: None,
             gas_limit,
             gas_price,
             signature: vec![0; 64], // Mock signature
@@ -0,0 +1,163 @@
+use std::time::Duration;
+use zk_sac_engine::simulation::{Byzantine, Fault, Simulation, SimulationConfig, Violation};
+
+fn secs(secs: u64) -> Duration {
+    Duration::from_secs(secs)
+}
+
+fn assert_converged(heights: &[u64]) {
+    let min = heights.iter().min().unwrap();
+    let max = heights.iter().max().unwrap();
+    assert!(max - min <= 1, "nodes diverged: {:?}", heights);
+}
+
+#[tokio::test]
+async fn test_honest_network_is_safe_and_live() {
+    let mut simulation = Simulation::new(SimulationConfig::default()).unwrap();
+    let report = simulation.run_for(secs(120)).await.unwrap();
+
+    assert!(report.is_safe(), "{:?}", report.safety_violations);
+    assert!(report.is_live(), "{:?}", report.liveness_violations);
+    assert!(report.heights.iter().all(|&height| height >= 29), "{:?}", report.heights);
+    assert!(report.finalized_height >= 27);
+}
+
+#[tokio::test]
+async fn test_lossy_network_is_safe_live_and_reproducible() {
+    let config = SimulationConfig::default().with_drop_rate(0.2).with_seed(3);
+    let mut simulation = Simulation::new(config.clone()).unwrap();
+    let report = simulation.run_for(secs(120)).await.unwrap();
+
+    assert!(report.is_safe(), "{:?}", report.safety_violations);
+    assert!(report.is_live(), "{:?}", report.liveness_violations);
+    assert!(report.stats.messages_dropped > 0);
+    let rerun = Simulation::new(config).unwrap().run_for(secs(120)).await.unwrap();
+    assert_eq!(rerun.heights, report.heights);
+    assert_eq!(rerun.stats.messages_dropped, report.stats.messages_dropped);
+}
+
+#[tokio::test]
+async fn test_crashed_producer_stalls_chain_until_restart() {
+    // Runs are deterministic, so a fault-free run to block 4 shows who is selected for block 5
+    let mut probe = Simulation::new(SimulationConfig::default()).unwrap();
+    probe.run_for(secs(18)).await.unwrap();
+    let producer = probe.producer(0, 5).unwrap();
+
+    let mut simulation = Simulation::new(SimulationConfig::default()).unwrap()
+        .with_fault(secs(18), Fault::Crash(producer))
+        .with_fault(secs(60), Fault::Restart(producer));
+
+    let report = simulation.run_for(secs(50)).await.unwrap();
+    assert!(report.is_safe());
+    // Nobody else may produce block 5, the crashed node's slot, so the chain halts
+    assert_eq!(simulation.producer(0, 5).unwrap(), producer);
+    assert!(matches!(report.liveness_violations.as_slice(), [Violation::Stalled { height: 4, .. }]),
+            "{:?}", report.liveness_violations);
+
+    let report = simulation.run_for(secs(72)).await.unwrap();
+    assert!(report.is_safe(), "{:?}", report.safety_violations);
+    assert_eq!(report.liveness_violations.len(), 1);
+    assert!(report.heights[producer] > 15, "restarted node did not catch up: {:?}", report.heights);
+    assert_converged(&report.heights);
+}
+
+#[tokio::test]
+async fn test_short_crash_is_tolerated() {
+    let mut simulation = Simulation::new(SimulationConfig::default()).unwrap()
+        .with_fault(secs(10), Fault::Crash(3))
+        .with_fault(secs(18), Fault::Restart(3));
+    let report = simulation.run_for(secs(60)).await.unwrap();
+
+    assert!(report.is_safe());
+    assert!(report.is_live(), "{:?}", report.liveness_violations);
+}
+
+#[tokio::test]
+async fn test_partition_halts_without_forking_and_recovers_after_heal() {
+    let mut simulation = Simulation::new(SimulationConfig::default()).unwrap()
+        .with_fault(secs(10), Fault::Partition(vec![vec![0, 1], vec![2, 3]]))
+        .with_fault(secs(50), Fault::Heal);
+    let report = simulation.run_for(secs(102)).await.unwrap();
+
+    assert!(report.is_safe(), "{:?}", report.safety_violations);
+    assert_eq!(report.liveness_violations.len(), 1);
+    assert!(report.heights.iter().all(|&height| height > 12), "{:?}", report.heights);
+    assert_converged(&report.heights);
+}
+
+#[tokio::test]
+async fn test_delayed_proofs_slow_but_do_not_stop_the_chain() {
+    let mut simulation = Simulation::new(SimulationConfig::default()).unwrap()
+        .with_fault(Duration::ZERO, Fault::DelayProofs { node: 1, delay: secs(6) });
+    let report = simulation.run_for(secs(120)).await.unwrap();
+
+    assert!(report.is_safe());
+    assert!(report.is_live(), "{:?}", report.liveness_violations);
+    assert!(report.stats.blocks_produced < 30, "delays should cost slots");
+}
+
+#[tokio::test]
+async fn test_equivocating_producer_is_detected() {
+    let config = SimulationConfig::default().with_finality_depth(0);
+    let mut simulation = Simulation::new(config).unwrap();
+    let producer = simulation.producer(0, 1).unwrap();
+    simulation.schedule(Duration::ZERO, Fault::Equivocate(producer));
+    let report = simulation.run_for(secs(30)).await.unwrap();
+
+    assert!(report.safety_violations.iter().any(|v| matches!(v, Violation::Equivocation { height: 1, .. })));
+    assert!(report.safety_violations.iter().any(|v| matches!(v, Violation::ConflictingFinalized { height: 1, .. })));
+}
+
+fn no_gadget_conflict(violations: &[Violation]) -> bool {
+    !violations.iter().any(|v| matches!(v, Violation::ConflictingGadgetFinality { .. }))
+}
+
+#[tokio::test]
+async fn test_equivocator_is_slashed_without_gadget_conflict() {
+    let mut simulation = Simulation::new(SimulationConfig::default()).unwrap();
+    let producer = simulation.producer(0, 1).unwrap();
+    let observer = (producer + 1) % 4;
+    let stake = simulation.stake(observer, producer);
+    simulation.schedule(Duration::ZERO, Fault::Equivocate(producer));
+    let report = simulation.run_for(secs(60)).await.unwrap();
+
+    assert!(report.stats.offenses_reported > 0);
+    assert!(simulation.stake(observer, producer) < stake);
+    assert!(no_gadget_conflict(&report.safety_violations), "{:?}", report.safety_violations);
+}
+
+#[tokio::test]
+async fn test_withheld_proofs_are_reported_and_slashed() {
+    let mut simulation = Simulation::new(SimulationConfig::default()).unwrap();
+    let producer = simulation.producer(0, 1).unwrap();
+    let observer = (producer + 1) % 4;
+    let stake = simulation.stake(observer, producer);
+    simulation.schedule(Duration::ZERO, Fault::Byzantine { node: producer, behavior: Byzantine::WithholdProofs });
+    let report = simulation.run_for(secs(60)).await.unwrap();
+
+    assert!(report.is_safe(), "{:?}", report.safety_violations);
+    assert!(report.stats.offenses_reported > 0);
+    assert!(simulation.stake(observer, producer) < stake);
+}
+
+#[tokio::test]
+async fn test_invalid_state_roots_are_rejected() {
+    let mut simulation = Simulation::new(SimulationConfig::default()).unwrap();
+    let producer = simulation.producer(0, 1).unwrap();
+    simulation.schedule(Duration::ZERO, Fault::Byzantine { node: producer, behavior: Byzantine::InvalidStateRoot });
+    let report = simulation.run_for(secs(60)).await.unwrap();
+
+    assert!(report.is_safe(), "{:?}", report.safety_violations);
+    assert!(report.stats.blocks_rejected > 0);
+}
+
+#[tokio::test]
+async fn test_delayed_votes_slow_finality_without_breaking_it() {
+    let mut simulation = Simulation::new(SimulationConfig::default()).unwrap()
+        .with_fault(Duration::ZERO, Fault::Byzantine { node: 2, behavior: Byzantine::DelayVotes(secs(8)) });
+    let report = simulation.run_for(secs(120)).await.unwrap();
+
+    assert!(report.is_safe(), "{:?}", report.safety_violations);
+    assert!(report.is_live(), "{:?}", report.liveness_violations);
+    assert!(report.gadget_finalized.iter().all(|&height| height > 10), "{:?}", report.gadget_finalized);
+}